@@ -15,9 +15,11 @@ use crate::{
 };
 use anyhow::Context as AnyhowContext;
 use aptos_api_types::{
-    AccountData, Address, AptosErrorCode, AsConverter, LedgerInfo, MoveModuleBytecode,
-    MoveModuleId, MoveResource, MoveStructTag, StateKeyWrapper, U64,
+    AccountData, AccountResourcesCursor, AccountResourcesQuery, AccountResourcesQueryResult,
+    Address, AptosErrorCode, AsConverter, LedgerInfo, MoveConverter, MoveModuleBytecode,
+    MoveModuleId, MoveResource, MoveStructTag, StateKeyWrapper, VerifyInput, U64,
 };
+use aptos_storage_interface::state_view::DbStateView;
 use aptos_types::{
     account_config::{AccountResource, ObjectGroupResource},
     event::{EventHandle, EventKey},
@@ -28,9 +30,19 @@ use move_core_types::{
 };
 use poem_openapi::{
     param::{Path, Query},
+    payload::Json,
     OpenApi,
 };
-use std::{collections::BTreeMap, convert::TryInto, sync::Arc};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    convert::TryInto,
+    sync::Arc,
+};
+
+/// Max number of pages (of the max page size) of resources read by a single resources
+/// query, so a selective query doesn't read all of the resources of an account with many
+/// of them at once
+const MAX_QUERY_SCANNED_PAGES: u64 = 10;
 
 /// API for accounts, their associated resources, and modules
 pub struct AccountsApi {
@@ -125,6 +137,90 @@ impl AccountsApi {
         .await
     }
 
+    /// Query account resources
+    ///
+    /// Retrieves a page of an account's resources, optionally only the resources of the
+    /// selected types, and only the selected fields within them. This avoids downloading
+    /// (and decoding) resources the client doesn't need, e.g. for accounts that own many
+    /// objects.
+    ///
+    /// Pagination is done with the cursor returned in the response, which pins the ledger
+    /// version of the first page so all pages are consistent. If the ledger version is not
+    /// specified in the request (nor pinned by the cursor), the latest ledger version is used.
+    ///
+    /// The Aptos nodes prune account state history, via a configurable time window.
+    /// If the requested ledger version has been pruned, the server responds with a 410.
+    #[oai(
+        path = "/accounts/:address/resources/query",
+        method = "post",
+        operation_id = "query_account_resources",
+        tag = "ApiTags::Accounts"
+    )]
+    async fn query_account_resources(
+        &self,
+        accept_type: AcceptType,
+        /// Address of account with or without a `0x` prefix
+        address: Path<Address>,
+        /// Resources, and fields within them, to retrieve
+        request: Json<AccountResourcesQuery>,
+        /// Ledger version to get state of account
+        ///
+        /// If not provided, it will be the latest version, or the version of the cursor
+        ledger_version: Query<Option<U64>>,
+        /// Cursor specifying where to start for pagination
+        ///
+        /// This cursor cannot be derived manually client-side. Instead, you must
+        /// call this endpoint once without this query parameter specified, and
+        /// then use the cursor returned in the response.
+        cursor: Query<Option<AccountResourcesCursor>>,
+        /// Max number of account resources to return
+        ///
+        /// Fewer resources may be returned if many of the account's resources are not
+        /// selected, in which case the cursor continues from the last resource read.
+        /// If not provided, defaults to default page size.
+        limit: Query<Option<u16>>,
+    ) -> BasicResultWith404<AccountResourcesQueryResult> {
+        fail_point_poem("endpoint_query_account_resources")?;
+        self.context
+            .check_api_output_enabled("Query account resources", &accept_type)?;
+        if accept_type == AcceptType::Bcs {
+            return Err(BasicErrorWith404::bad_request_with_code_no_info(
+                "BCS is not supported for account resources queries",
+                AptosErrorCode::BcsNotSupported,
+            ));
+        }
+        request
+            .0
+            .verify()
+            .context("'request' is invalid")
+            .map_err(|err| {
+                BasicErrorWith404::bad_request_with_code_no_info(err, AptosErrorCode::InvalidInput)
+            })?;
+
+        let (ledger_version, start) = match cursor.0 {
+            Some(cursor) => {
+                if ledger_version
+                    .0
+                    .is_some_and(|version| version.0 != cursor.ledger_version)
+                {
+                    return Err(BasicErrorWith404::bad_request_with_code_no_info(
+                        "'ledger_version' doesn't match the ledger version of the cursor",
+                        AptosErrorCode::InvalidInput,
+                    ));
+                }
+                (Some(U64(cursor.ledger_version)), Some(cursor.start))
+            },
+            None => (ledger_version.0, None),
+        };
+
+        let context = self.context.clone();
+        api_spawn_blocking(move || {
+            let account = Account::new(context, address.0, ledger_version, start, limit.0, true)?;
+            account.query_resources(request.0)
+        })
+        .await
+    }
+
     /// Get account modules
     ///
     /// Retrieves all account modules' bytecode for a given account at a specific ledger version.
@@ -386,6 +482,181 @@ impl Account {
         }
     }
 
+    /// Retrieves the move resources associated with the account, that are selected by the
+    /// query, with only the selected fields
+    ///
+    /// If all the selected resource types are fully specified, they're looked up directly.
+    /// Otherwise the account's resources are read until `limit` of them are selected, or
+    /// [`MAX_QUERY_SCANNED_PAGES`] pages worth of them have been read.
+    pub fn query_resources(
+        self,
+        query: AccountResourcesQuery,
+    ) -> BasicResultWith404<AccountResourcesQueryResult> {
+        // check account exists
+        self.verify_account_or_object_resource()?;
+        let max_account_resources_page_size = self.context.max_account_resources_page_size();
+        let limit = determine_limit(
+            self.limit,
+            max_account_resources_page_size,
+            max_account_resources_page_size,
+            &self.latest_ledger_info,
+        )? as usize;
+        let state_view = self
+            .context
+            .state_view_at_version(self.ledger_version)
+            .map_err(|err| {
+                BasicErrorWith404::internal_with_code(
+                    err,
+                    AptosErrorCode::InternalError,
+                    &self.latest_ledger_info,
+                )
+            })?;
+        let converter =
+            state_view.as_converter(self.context.db.clone(), self.context.indexer_reader.clone());
+
+        // Resources of fully specified types can be looked up directly, unless there are
+        // more of them than fit in a page
+        let resource_types = match &self.start {
+            Some(_) => None,
+            None => self.fully_specified_resource_types(&converter, &query)?,
+        };
+        let (resources, next_state_key) = match resource_types {
+            Some(resource_types) if resource_types.len() <= limit => {
+                let mut resources = vec![];
+                for resource_type in resource_types {
+                    let bytes = converter
+                        .find_resource(&state_view, self.address, &resource_type)
+                        .context(format!(
+                            "Failed to query DB to check for {} at {}",
+                            resource_type, self.address
+                        ))
+                        .map_err(|err| {
+                            BasicErrorWith404::internal_with_code(
+                                err,
+                                AptosErrorCode::InternalError,
+                                &self.latest_ledger_info,
+                            )
+                        })?;
+                    if let Some(bytes) = bytes {
+                        resources.push((resource_type, bytes.to_vec()));
+                    }
+                }
+                (resources, None)
+            },
+            _ => self.scan_selected_resources(&query, limit)?,
+        };
+
+        // Only the selected resources are converted into `MoveResource`s
+        let converted_resources = converter
+            .try_into_resources(resources.iter().map(|(k, v)| (k.clone(), v.as_slice())))
+            .context("Failed to build move resource response from data in DB")
+            .map_err(|err| {
+                BasicErrorWith404::internal_with_code(
+                    err,
+                    AptosErrorCode::InternalError,
+                    &self.latest_ledger_info,
+                )
+            })?;
+        let selected_resources = converted_resources
+            .into_iter()
+            .filter_map(|resource| query.select(resource).transpose())
+            .collect::<anyhow::Result<Vec<_>>>()
+            .context("Failed to select resource fields")
+            .map_err(|err| {
+                BasicErrorWith404::internal_with_code(
+                    err,
+                    AptosErrorCode::InternalError,
+                    &self.latest_ledger_info,
+                )
+            })?;
+
+        BasicResponse::try_from_json((
+            AccountResourcesQueryResult {
+                resources: selected_resources,
+                cursor: next_state_key.map(|start| AccountResourcesCursor {
+                    ledger_version: self.ledger_version,
+                    start,
+                }),
+            },
+            &self.latest_ledger_info,
+            BasicResponseStatus::Ok,
+        ))
+    }
+
+    /// Returns the resource types selected by the query, or `None` if any of them selects
+    /// all instantiations of a generic type (or the query selects all resources)
+    fn fully_specified_resource_types(
+        &self,
+        converter: &MoveConverter<DbStateView>,
+        query: &AccountResourcesQuery,
+    ) -> Result<Option<BTreeSet<StructTag>>, BasicErrorWith404> {
+        if query.selections.is_empty() {
+            return Ok(None);
+        }
+        let mut resource_types = BTreeSet::new();
+        for selection in &query.selections {
+            let resource_type: StructTag = selection
+                .resource_type
+                .clone()
+                .try_into()
+                .context("Failed to parse given resource type")
+                .map_err(|err| {
+                    BasicErrorWith404::bad_request_with_code(
+                        err,
+                        AptosErrorCode::InvalidInput,
+                        &self.latest_ledger_info,
+                    )
+                })?;
+            // A type without generic type parameters selects all instantiations of a
+            // generic struct
+            if resource_type.type_args.is_empty()
+                && converter
+                    .is_generic_struct(&resource_type.module_id(), &resource_type.name)
+                    .unwrap_or(false)
+            {
+                return Ok(None);
+            }
+            resource_types.insert(resource_type);
+        }
+        Ok(Some(resource_types))
+    }
+
+    /// Reads the account's resources until `limit` of them are selected by the query, or
+    /// [`MAX_QUERY_SCANNED_PAGES`] pages worth of them have been read. Returns the selected
+    /// resources, and the state key to continue reading from, if any.
+    fn scan_selected_resources(
+        &self,
+        query: &AccountResourcesQuery,
+        limit: usize,
+    ) -> Result<(Vec<(StructTag, Vec<u8>)>, Option<StateKey>), BasicErrorWith404> {
+        let max_scanned =
+            self.context.max_account_resources_page_size() as u64 * MAX_QUERY_SCANNED_PAGES;
+        self.context
+            .get_selected_resources_by_pagination(
+                self.address.into(),
+                self.start.as_ref(),
+                self.ledger_version,
+                limit as u64,
+                max_scanned,
+                |struct_tag| {
+                    let resource_type = MoveStructTag::from(struct_tag);
+                    query.selections.is_empty()
+                        || query
+                            .selections
+                            .iter()
+                            .any(|selection| selection.matches(&resource_type))
+                },
+            )
+            .context("Failed to get resources from storage")
+            .map_err(|err| {
+                BasicErrorWith404::internal_with_code(
+                    err,
+                    AptosErrorCode::InternalError,
+                    &self.latest_ledger_info,
+                )
+            })
+    }
+
     /// Retrieves the move modules' bytecode associated with the account
     ///
     /// * JSON: Return a JSON encoded version of [`Vec<MoveModuleBytecode>`] with parsed ABIs
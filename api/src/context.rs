@@ -16,11 +16,14 @@ use aptos_api_types::{
     AptosErrorCode, AsConverter, BcsBlock, GasEstimation, LedgerInfo, ResourceGroup,
     TransactionOnChainData,
 };
-use aptos_config::config::{NodeConfig, RoleType};
+use aptos_config::config::{transaction_filter_type::ReloadableFilter, NodeConfig, RoleType};
 use aptos_crypto::HashValue;
 use aptos_gas_schedule::{AptosGasParameters, FromOnChainGasSchedule};
 use aptos_logger::{error, info, Schema};
-use aptos_mempool::{MempoolClientRequest, MempoolClientSender, SubmissionStatus};
+use aptos_mempool::{
+    AccountPendingTransactions, MempoolClientRequest, MempoolClientSender, MempoolStats,
+    SubmissionStatus,
+};
 use aptos_storage_interface::{
     state_view::{DbStateView, DbStateViewAtVersion, LatestDbStateCheckpointView},
     AptosDbError, DbReader, Order, MAX_REQUEST_LIMIT,
@@ -76,8 +79,10 @@ pub struct Context {
     gas_limit_cache: Arc<RwLock<GasLimitCache>>,
     view_function_stats: Arc<FunctionStats>,
     simulate_txn_stats: Arc<FunctionStats>,
+    simulation_filter: Arc<ReloadableFilter>,
     pub indexer_reader: Option<Arc<dyn IndexerReader>>,
     pub wait_for_hash_active_connections: Arc<AtomicUsize>,
+    pub transaction_stream_active_connections: Arc<AtomicUsize>,
 }
 
 impl std::fmt::Debug for Context {
@@ -107,6 +112,9 @@ impl Context {
                 )),
             )
         };
+        let simulation_filter = Arc::new(ReloadableFilter::new(
+            node_config.api.simulation_filter.clone(),
+        ));
         Self {
             chain_id,
             db,
@@ -129,11 +137,23 @@ impl Context {
             })),
             view_function_stats,
             simulate_txn_stats,
+            simulation_filter,
             indexer_reader,
             wait_for_hash_active_connections: Arc::new(AtomicUsize::new(0)),
+            transaction_stream_active_connections: Arc::new(AtomicUsize::new(0)),
         }
     }
 
+    /// Replaces the simulation filter with one that can be updated at runtime
+    pub fn with_simulation_filter(mut self, simulation_filter: Arc<ReloadableFilter>) -> Self {
+        self.simulation_filter = simulation_filter;
+        self
+    }
+
+    pub fn simulation_filter(&self) -> &ReloadableFilter {
+        &self.simulation_filter
+    }
+
     pub fn max_transactions_page_size(&self) -> u16 {
         self.node_config.api.max_transactions_page_size
     }
@@ -432,26 +452,7 @@ impl Context {
         version: u64,
         limit: u64,
     ) -> Result<(Vec<(StructTag, Vec<u8>)>, Option<StateKey>)> {
-        let account_iter = if !db_sharding_enabled(&self.node_config) {
-            Box::new(
-                self.db
-                    .get_prefixed_state_value_iterator(
-                        &StateKeyPrefix::from(address),
-                        prev_state_key,
-                        version,
-                    )?
-                    .map(|item| item.map_err(|err| anyhow!(err.to_string()))),
-            )
-        } else {
-            self.indexer_reader
-                .as_ref()
-                .ok_or_else(|| format_err!("Indexer reader doesn't exist"))?
-                .get_prefixed_state_value_iterator(
-                    &StateKeyPrefix::from(address),
-                    prev_state_key,
-                    version,
-                )?
-        };
+        let account_iter = self.account_state_value_iterator(address, prev_state_key, version)?;
         // TODO: Consider rewriting this to consider resource groups:
         // * If a resource group is found, expand
         // * Return Option<Result<(PathType, StructTag, Vec<u8>)>>
@@ -516,6 +517,91 @@ impl Context {
         Ok((kvs, next_key))
     }
 
+    /// Reads the account's resources, starting at `prev_state_key`, until `limit` of them
+    /// are selected or `max_scanned` state values have been read. Resources in resource
+    /// groups are selected individually, but a resource group is never split across pages.
+    ///
+    /// Returns the selected resources, and the state key to continue reading from, if any.
+    pub fn get_selected_resources_by_pagination(
+        &self,
+        address: AccountAddress,
+        prev_state_key: Option<&StateKey>,
+        version: u64,
+        limit: u64,
+        max_scanned: u64,
+        is_selected: impl Fn(&StructTag) -> bool,
+    ) -> Result<(Vec<(StructTag, Vec<u8>)>, Option<StateKey>)> {
+        let state_view = self.state_view_at_version(version)?;
+        let converter = state_view.as_converter(self.db.clone(), self.indexer_reader.clone());
+
+        let mut selected = vec![];
+        let mut num_scanned = 0;
+        for item in self.account_state_value_iterator(address, prev_state_key, version)? {
+            let (state_key, state_value) = item?;
+            let struct_tag = match state_key.inner() {
+                StateKeyInner::AccessPath(AccessPath { address: _, path }) => {
+                    match Path::try_from(path.as_slice())? {
+                        Path::Resource(struct_tag) | Path::ResourceGroup(struct_tag) => struct_tag,
+                        Path::Code(_) => continue,
+                    }
+                },
+                _ => bail!(
+                    "storage prefix scan return inconsistent key ({:?})",
+                    state_key
+                ),
+            };
+            if selected.len() as u64 >= limit || num_scanned >= max_scanned {
+                return Ok((selected, Some(state_key)));
+            }
+            num_scanned += 1;
+
+            let resources: Vec<(StructTag, Vec<u8>)> = if converter.is_resource_group(&struct_tag) {
+                // An error here means a storage invariant has been violated
+                bcs::from_bytes::<ResourceGroup>(state_value.bytes())?
+                    .into_iter()
+                    .collect()
+            } else {
+                vec![(struct_tag, state_value.bytes().to_vec())]
+            };
+            selected.extend(
+                resources
+                    .into_iter()
+                    .filter(|(struct_tag, _)| is_selected(struct_tag)),
+            );
+        }
+        Ok((selected, None))
+    }
+
+    fn account_state_value_iterator(
+        &self,
+        address: AccountAddress,
+        prev_state_key: Option<&StateKey>,
+        version: u64,
+    ) -> Result<Box<dyn Iterator<Item = Result<(StateKey, StateValue)>> + '_>> {
+        let account_iter: Box<dyn Iterator<Item = Result<(StateKey, StateValue)>> + '_> =
+            if !db_sharding_enabled(&self.node_config) {
+                Box::new(
+                    self.db
+                        .get_prefixed_state_value_iterator(
+                            &StateKeyPrefix::from(address),
+                            prev_state_key,
+                            version,
+                        )?
+                        .map(|item| item.map_err(|err| anyhow!(err.to_string()))),
+                )
+            } else {
+                self.indexer_reader
+                    .as_ref()
+                    .ok_or_else(|| format_err!("Indexer reader doesn't exist"))?
+                    .get_prefixed_state_value_iterator(
+                        &StateKeyPrefix::from(address),
+                        prev_state_key,
+                        version,
+                    )?
+            };
+        Ok(account_iter)
+    }
+
     pub fn get_modules_by_pagination(
         &self,
         address: AccountAddress,
@@ -878,6 +964,35 @@ impl Context {
         callback.await.map_err(anyhow::Error::from)
     }
 
+    pub async fn get_pending_account_transactions(
+        &self,
+        address: AccountAddress,
+    ) -> Result<AccountPendingTransactions> {
+        let (req_sender, callback) = oneshot::channel();
+
+        self.mp_sender
+            .clone()
+            .send(MempoolClientRequest::GetAccountTransactions(
+                address, req_sender,
+            ))
+            .await
+            .map_err(anyhow::Error::from)?;
+
+        callback.await.map_err(anyhow::Error::from)
+    }
+
+    pub async fn get_mempool_stats(&self) -> Result<MempoolStats> {
+        let (req_sender, callback) = oneshot::channel();
+
+        self.mp_sender
+            .clone()
+            .send(MempoolClientRequest::GetMempoolStats(req_sender))
+            .await
+            .map_err(anyhow::Error::from)?;
+
+        callback.await.map_err(anyhow::Error::from)
+    }
+
     pub fn get_transaction_by_version(
         &self,
         version: u64,
@@ -25,6 +25,7 @@ pub mod spec;
 mod state;
 #[cfg(test)]
 pub mod tests;
+mod transaction_stream;
 mod transactions;
 mod view_function;
 
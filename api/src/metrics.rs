@@ -105,3 +105,11 @@ pub static WAIT_TRANSACTION_POLL_TIME: Lazy<HistogramVec> = Lazy::new(|| {
     )
     .unwrap()
 });
+
+pub static TRANSACTION_STREAM_GAUGE: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "aptos_api_transaction_streams",
+        "Number of active transaction streams"
+    )
+    .unwrap()
+});
@@ -19,7 +19,7 @@ use crate::{
     view_function::ViewFunctionApi,
 };
 use anyhow::Context as AnyhowContext;
-use aptos_config::config::{ApiConfig, NodeConfig};
+use aptos_config::config::{transaction_filter_type::ReloadableFilter, ApiConfig, NodeConfig};
 use aptos_logger::info;
 use aptos_mempool::MempoolClientSender;
 use aptos_storage_interface::DbReader;
@@ -45,11 +45,13 @@ pub fn bootstrap(
     db: Arc<dyn DbReader>,
     mp_sender: MempoolClientSender,
     indexer_reader: Option<Arc<dyn IndexerReader>>,
+    simulation_filter: Arc<ReloadableFilter>,
 ) -> anyhow::Result<Runtime> {
     let max_runtime_workers = get_max_runtime_workers(&config.api);
     let runtime = aptos_runtimes::spawn_named_runtime("api".into(), Some(max_runtime_workers));
 
-    let context = Context::new(chain_id, db, mp_sender, config.clone(), indexer_reader);
+    let context = Context::new(chain_id, db, mp_sender, config.clone(), indexer_reader)
+        .with_simulation_filter(simulation_filter);
 
     attach_poem_to_runtime(runtime.handle(), context.clone(), config, false)
         .context("Failed to attach poem to runtime")?;
@@ -288,9 +290,9 @@ mod tests {
     use super::bootstrap;
     use crate::runtime::get_max_runtime_workers;
     use aptos_api_test_context::{new_test_context, TestContext};
-    use aptos_config::config::{ApiConfig, NodeConfig};
+    use aptos_config::config::{transaction_filter_type::ReloadableFilter, ApiConfig, NodeConfig};
     use aptos_types::chain_id::ChainId;
-    use std::time::Duration;
+    use std::{sync::Arc, time::Duration};
 
     // TODO: Unignore this when I figure out why this only works when being
     // run alone (it fails when run with other tests).
@@ -351,6 +353,7 @@ mod tests {
             context.db.clone(),
             context.mempool.ac_client.clone(),
             None,
+            Arc::new(ReloadableFilter::new(cfg.api.simulation_filter.clone())),
         );
         assert!(ret.is_ok());
 
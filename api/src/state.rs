@@ -14,12 +14,15 @@ use crate::{
 };
 use anyhow::Context as AnyhowContext;
 use aptos_api_types::{
-    verify_module_identifier, Address, AptosErrorCode, AsConverter, IdentifierWrapper,
+    verify_module_identifier, Address, AptosErrorCode, AsConverter, IdentifierWrapper, LedgerInfo,
     MoveModuleBytecode, MoveResource, MoveStructTag, MoveValue, RawStateValueRequest,
-    RawTableItemRequest, TableItemRequest, VerifyInput, VerifyInputWithRecursion, U64,
+    RawTableItemRequest, StateValueWithProof, TableItemRequest, VerifyInput,
+    VerifyInputWithRecursion, U64,
 };
+use aptos_storage_interface::state_view::DbStateView;
 use aptos_types::state_store::{state_key::StateKey, table::TableHandle, TStateView};
-use move_core_types::language_storage::StructTag;
+use bytes::Bytes;
+use move_core_types::language_storage::{StructTag, TypeTag};
 use poem_openapi::{
     param::{Path, Query},
     payload::Json,
@@ -264,6 +267,61 @@ impl StateApi {
         let api = self.clone();
         api_spawn_blocking(move || api.raw_value(&accept_type, request.0, ledger_version.0)).await
     }
+
+    /// Get state value with proof.
+    ///
+    /// Get a state value identified by the key provided in the request body, along with
+    /// the proofs required to verify it against a trusted ledger state, so the response
+    /// doesn't need to be trusted. Proofs are only available at state snapshot versions,
+    /// so the value is read at the latest state snapshot at or before the requested
+    /// ledger version; the version actually used is returned in the response.
+    ///
+    /// The response contains a sparse Merkle proof of the state value (or its absence),
+    /// an accumulator proof of the transaction info at the snapshot version, and a state
+    /// proof consisting of the latest signed ledger info and the epoch changes since the
+    /// client's known version.
+    ///
+    /// The Aptos nodes prune account state history, via a configurable time window.
+    /// If the requested ledger version has been pruned, the server responds with a 410.
+    #[oai(
+        path = "/experimental/state_values/proof",
+        method = "post",
+        operation_id = "get_state_value_with_proof",
+        tag = "ApiTags::Experimental",
+        hidden
+    )]
+    async fn get_state_value_with_proof(
+        &self,
+        accept_type: AcceptType,
+        /// Request that carries the state key.
+        request: Json<RawStateValueRequest>,
+        /// Ledger version at which the value is got.
+        ///
+        /// If not provided, it will be the latest version
+        ledger_version: Query<Option<U64>>,
+        /// Ledger version already known (and trusted) by the client.
+        ///
+        /// Epoch changes after this version are included in the state proof. If not
+        /// provided, no epoch changes are included.
+        known_version: Query<Option<U64>>,
+    ) -> BasicResultWith404<MoveValue> {
+        fail_point_poem("endpoint_get_state_value_with_proof")?;
+
+        if AcceptType::Json == accept_type {
+            return Err(api_forbidden(
+                "Get state value with proof",
+                "Only BCS is supported as an AcceptType.",
+            ));
+        }
+        self.context
+            .check_api_output_enabled("Get state value with proof", &accept_type)?;
+
+        let api = self.clone();
+        api_spawn_blocking(move || {
+            api.value_with_proof(&accept_type, request.0, ledger_version.0, known_version.0)
+        })
+        .await
+    }
 }
 
 impl StateApi {
@@ -278,17 +336,47 @@ impl StateApi {
         resource_type: MoveStructTag,
         ledger_version: Option<u64>,
     ) -> BasicResultWith404<MoveResource> {
-        let tag: StructTag = resource_type
+        let tag = Self::parse_resource_type(resource_type)?;
+        let (ledger_info, ledger_version, state_view) = self.context.state_view(ledger_version)?;
+        let bytes = self.read_resource(&state_view, address, &tag, ledger_version, &ledger_info)?;
+
+        match accept_type {
+            AcceptType::Json => {
+                let resource = self.resource_to_json(&state_view, &tag, &bytes, &ledger_info)?;
+                BasicResponse::try_from_json((resource, &ledger_info, BasicResponseStatus::Ok))
+            },
+            AcceptType::Bcs => BasicResponse::try_from_encoded((
+                bytes.to_vec(),
+                &ledger_info,
+                BasicResponseStatus::Ok,
+            )),
+        }
+    }
+
+    /// Parses the struct tag of a resource
+    pub(crate) fn parse_resource_type(
+        resource_type: MoveStructTag,
+    ) -> Result<StructTag, BasicErrorWith404> {
+        resource_type
             .try_into()
             .context("Failed to parse given resource type")
             .map_err(|err| {
                 BasicErrorWith404::bad_request_with_code_no_info(err, AptosErrorCode::InvalidInput)
-            })?;
+            })
+    }
 
-        let (ledger_info, ledger_version, state_view) = self.context.state_view(ledger_version)?;
-        let bytes = state_view
+    /// Reads the (BCS encoded) resource from the state view
+    pub(crate) fn read_resource(
+        &self,
+        state_view: &DbStateView,
+        address: Address,
+        tag: &StructTag,
+        ledger_version: u64,
+        ledger_info: &LedgerInfo,
+    ) -> Result<Bytes, BasicErrorWith404> {
+        state_view
             .as_converter(self.context.db.clone(), self.context.indexer_reader.clone())
-            .find_resource(&state_view, address, &tag)
+            .find_resource(state_view, address, tag)
             .context(format!(
                 "Failed to query DB to check for {} at {}",
                 tag, address
@@ -297,33 +385,31 @@ impl StateApi {
                 BasicErrorWith404::internal_with_code(
                     err,
                     AptosErrorCode::InternalError,
-                    &ledger_info,
+                    ledger_info,
                 )
             })?
-            .ok_or_else(|| resource_not_found(address, &tag, ledger_version, &ledger_info))?;
-
-        match accept_type {
-            AcceptType::Json => {
-                let resource = state_view
-                    .as_converter(self.context.db.clone(), self.context.indexer_reader.clone())
-                    .try_into_resource(&tag, &bytes)
-                    .context("Failed to deserialize resource data retrieved from DB")
-                    .map_err(|err| {
-                        BasicErrorWith404::internal_with_code(
-                            err,
-                            AptosErrorCode::InternalError,
-                            &ledger_info,
-                        )
-                    })?;
+            .ok_or_else(|| resource_not_found(address, tag, ledger_version, ledger_info))
+    }
 
-                BasicResponse::try_from_json((resource, &ledger_info, BasicResponseStatus::Ok))
-            },
-            AcceptType::Bcs => BasicResponse::try_from_encoded((
-                bytes.to_vec(),
-                &ledger_info,
-                BasicResponseStatus::Ok,
-            )),
-        }
+    /// Converts the (BCS encoded) resource into a MoveResource
+    pub(crate) fn resource_to_json(
+        &self,
+        state_view: &DbStateView,
+        tag: &StructTag,
+        bytes: &[u8],
+        ledger_info: &LedgerInfo,
+    ) -> Result<MoveResource, BasicErrorWith404> {
+        state_view
+            .as_converter(self.context.db.clone(), self.context.indexer_reader.clone())
+            .try_into_resource(tag, bytes)
+            .context("Failed to deserialize resource data retrieved from DB")
+            .map_err(|err| {
+                BasicErrorWith404::internal_with_code(
+                    err,
+                    AptosErrorCode::InternalError,
+                    ledger_info,
+                )
+            })
     }
 
     /// Retrieve the module
@@ -385,51 +471,102 @@ impl StateApi {
         ledger_version: Option<U64>,
     ) -> BasicResultWith404<MoveValue> {
         // Parse the key and value types for the table
+        let (key_type, value_type) = Self::parse_table_item_types(&table_item_request)?;
+        let key = table_item_request.key;
+
+        // Retrieve local state
+        let (ledger_info, ledger_version, state_view) = self
+            .context
+            .state_view(ledger_version.map(|inner| inner.0))?;
+
+        // Convert key to lookup version for DB, and retrieve the value
+        let raw_key = self.table_item_raw_key(&state_view, &key_type, &key, &ledger_info)?;
+        let bytes = self.read_table_item(
+            &state_view,
+            table_handle,
+            &raw_key,
+            &key,
+            ledger_version,
+            &ledger_info,
+        )?;
+
+        match accept_type {
+            AcceptType::Json => {
+                let move_value =
+                    self.table_item_to_json(&state_view, &value_type, &bytes, &ledger_info)?;
+                BasicResponse::try_from_json((move_value, &ledger_info, BasicResponseStatus::Ok))
+            },
+            AcceptType::Bcs => BasicResponse::try_from_encoded((
+                bytes.to_vec(),
+                &ledger_info,
+                BasicResponseStatus::Ok,
+            )),
+        }
+    }
+
+    /// Parses the key and value types of a table item request
+    pub(crate) fn parse_table_item_types(
+        table_item_request: &TableItemRequest,
+    ) -> Result<(TypeTag, TypeTag), BasicErrorWith404> {
         let key_type = table_item_request
             .key_type
+            .clone()
             .try_into()
             .context("Failed to parse key_type")
             .map_err(|err| {
                 BasicErrorWith404::bad_request_with_code_no_info(err, AptosErrorCode::InvalidInput)
             })?;
-        let key = table_item_request.key;
         let value_type = table_item_request
             .value_type
+            .clone()
             .try_into()
             .context("Failed to parse value_type")
             .map_err(|err| {
                 BasicErrorWith404::bad_request_with_code_no_info(err, AptosErrorCode::InvalidInput)
             })?;
+        Ok((key_type, value_type))
+    }
 
-        // Retrieve local state
-        let (ledger_info, ledger_version, state_view) = self
-            .context
-            .state_view(ledger_version.map(|inner| inner.0))?;
-
-        let converter =
-            state_view.as_converter(self.context.db.clone(), self.context.indexer_reader.clone());
-
-        // Convert key to lookup version for DB
-        let vm_key = converter
-            .try_into_vm_value(&key_type, key.clone())
+    /// Converts the table item key into the (BCS encoded) key used by the DB
+    pub(crate) fn table_item_raw_key(
+        &self,
+        state_view: &DbStateView,
+        key_type: &TypeTag,
+        key: &serde_json::Value,
+        ledger_info: &LedgerInfo,
+    ) -> Result<Vec<u8>, BasicErrorWith404> {
+        let vm_key = state_view
+            .as_converter(self.context.db.clone(), self.context.indexer_reader.clone())
+            .try_into_vm_value(key_type, key.clone())
             .map_err(|err| {
                 BasicErrorWith404::bad_request_with_code(
                     err,
                     AptosErrorCode::InvalidInput,
-                    &ledger_info,
+                    ledger_info,
                 )
             })?;
-        let raw_key = vm_key.undecorate().simple_serialize().ok_or_else(|| {
+        vm_key.undecorate().simple_serialize().ok_or_else(|| {
             BasicErrorWith404::bad_request_with_code(
                 "Failed to serialize table key",
                 AptosErrorCode::InvalidInput,
-                &ledger_info,
+                ledger_info,
             )
-        })?;
+        })
+    }
 
-        // Retrieve value from the state key
-        let state_key = StateKey::table_item(&TableHandle(table_handle.into()), &raw_key);
-        let bytes = state_view
+    /// Reads the (BCS encoded) table item from the state view. The key is only used for
+    /// error messages.
+    pub(crate) fn read_table_item(
+        &self,
+        state_view: &DbStateView,
+        table_handle: Address,
+        raw_key: &[u8],
+        key: &serde_json::Value,
+        ledger_version: u64,
+        ledger_info: &LedgerInfo,
+    ) -> Result<Bytes, BasicErrorWith404> {
+        let state_key = StateKey::table_item(&TableHandle(table_handle.into()), raw_key);
+        state_view
             .get_state_value_bytes(&state_key)
             .context(format!(
                 "Failed when trying to retrieve table item from the DB with key: {}",
@@ -439,34 +576,31 @@ impl StateApi {
                 BasicErrorWith404::internal_with_code(
                     err,
                     AptosErrorCode::InternalError,
-                    &ledger_info,
+                    ledger_info,
                 )
             })?
-            .ok_or_else(|| {
-                table_item_not_found(table_handle, &key, ledger_version, &ledger_info)
-            })?;
-
-        match accept_type {
-            AcceptType::Json => {
-                let move_value = converter
-                    .try_into_move_value(&value_type, &bytes)
-                    .context("Failed to deserialize table item retrieved from DB")
-                    .map_err(|err| {
-                        BasicErrorWith404::internal_with_code(
-                            err,
-                            AptosErrorCode::InternalError,
-                            &ledger_info,
-                        )
-                    })?;
+            .ok_or_else(|| table_item_not_found(table_handle, key, ledger_version, ledger_info))
+    }
 
-                BasicResponse::try_from_json((move_value, &ledger_info, BasicResponseStatus::Ok))
-            },
-            AcceptType::Bcs => BasicResponse::try_from_encoded((
-                bytes.to_vec(),
-                &ledger_info,
-                BasicResponseStatus::Ok,
-            )),
-        }
+    /// Converts the (BCS encoded) table item into a MoveValue
+    pub(crate) fn table_item_to_json(
+        &self,
+        state_view: &DbStateView,
+        value_type: &TypeTag,
+        bytes: &[u8],
+        ledger_info: &LedgerInfo,
+    ) -> Result<MoveValue, BasicErrorWith404> {
+        state_view
+            .as_converter(self.context.db.clone(), self.context.indexer_reader.clone())
+            .try_into_move_value(value_type, bytes)
+            .context("Failed to deserialize table item retrieved from DB")
+            .map_err(|err| {
+                BasicErrorWith404::internal_with_code(
+                    err,
+                    AptosErrorCode::InternalError,
+                    ledger_info,
+                )
+            })
     }
 
     /// Retrieve table item for a specific ledger version
@@ -590,4 +724,154 @@ impl StateApi {
             },
         }
     }
+
+    /// Retrieve a state value with proof at the latest state snapshot before the ledger version
+    pub fn value_with_proof(
+        &self,
+        accept_type: &AcceptType,
+        request: RawStateValueRequest,
+        ledger_version: Option<U64>,
+        known_version: Option<U64>,
+    ) -> BasicResultWith404<MoveValue> {
+        let (ledger_info, ledger_version) = self
+            .context
+            .get_latest_ledger_info_and_verify_lookup_version(
+                ledger_version.map(|inner| inner.0),
+            )?;
+
+        let state_key: StateKey = bcs::from_bytes(&request.key.0)
+            .context(format!(
+                "Failed deserializing state key. key: {}",
+                request.key
+            ))
+            .map_err(|err| {
+                BasicErrorWith404::bad_request_with_code(
+                    err,
+                    AptosErrorCode::InvalidInput,
+                    &ledger_info,
+                )
+            })?;
+
+        // The signed ledger info is read after verifying the requested version, so it is
+        // guaranteed to be at or after it
+        let ledger_info_with_sigs = self
+            .context
+            .get_latest_ledger_info_with_signatures()
+            .context("Failed to retrieve latest ledger info")
+            .map_err(|err| {
+                BasicErrorWith404::internal_with_code(
+                    err,
+                    AptosErrorCode::InternalError,
+                    &ledger_info,
+                )
+            })?;
+        let signed_version = ledger_info_with_sigs.ledger_info().version();
+        let known_version = known_version.map_or(signed_version, |inner| inner.0);
+        if known_version > signed_version {
+            return Err(BasicErrorWith404::bad_request_with_code(
+                format!(
+                    "Known version({}) is newer than the latest ledger version({})",
+                    known_version, signed_version
+                ),
+                AptosErrorCode::InvalidInput,
+                &ledger_info,
+            ));
+        }
+
+        let (version, _) = self
+            .context
+            .db
+            .get_state_snapshot_before(ledger_version + 1)
+            .context("Failed to retrieve state snapshot")
+            .map_err(|err| {
+                BasicErrorWith404::internal_with_code(
+                    err,
+                    AptosErrorCode::InternalError,
+                    &ledger_info,
+                )
+            })?
+            .ok_or_else(|| {
+                build_not_found(
+                    "State snapshot",
+                    format!("Ledger version({})", ledger_version),
+                    AptosErrorCode::VersionNotFound,
+                    &ledger_info,
+                )
+            })?;
+
+        let (state_value, sparse_merkle_proof) = self
+            .context
+            .db
+            .get_state_value_with_proof_by_version(&state_key, version)
+            .context(format!(
+                "Failed fetching state value with proof. key: {}",
+                request.key
+            ))
+            .map_err(|err| {
+                BasicErrorWith404::internal_with_code(
+                    err,
+                    AptosErrorCode::InternalError,
+                    &ledger_info,
+                )
+            })?;
+        let transaction_info_with_proof = self
+            .context
+            .db
+            .get_transaction_by_version(version, signed_version, false)
+            .context(format!(
+                "Failed fetching transaction info with proof. version: {}",
+                version
+            ))
+            .map_err(|err| {
+                BasicErrorWith404::internal_with_code(
+                    err,
+                    AptosErrorCode::InternalError,
+                    &ledger_info,
+                )
+            })?
+            .proof;
+        let state_proof = self
+            .context
+            .db
+            .get_state_proof_with_ledger_info(known_version, ledger_info_with_sigs)
+            .context("Failed fetching state proof")
+            .map_err(|err| {
+                BasicErrorWith404::internal_with_code(
+                    err,
+                    AptosErrorCode::InternalError,
+                    &ledger_info,
+                )
+            })?;
+
+        let state_value_with_proof = StateValueWithProof {
+            version,
+            state_key,
+            state_value,
+            sparse_merkle_proof,
+            transaction_info_with_proof,
+            state_proof,
+        };
+        let bytes = bcs::to_bytes(&state_value_with_proof)
+            .context(format!(
+                "Failed serializing state value with proof. key: {}",
+                request.key
+            ))
+            .map_err(|err| {
+                BasicErrorWith404::internal_with_code(
+                    err,
+                    AptosErrorCode::InternalError,
+                    &ledger_info,
+                )
+            })?;
+
+        match accept_type {
+            AcceptType::Json => Err(api_forbidden(
+                "Get state value with proof",
+                "This serves only bytes. Use other APIs for Json.",
+            )),
+            AcceptType::Bcs => {
+                BasicResponse::try_from_encoded((bytes, &ledger_info, BasicResponseStatus::Ok))
+            },
+        }
+    }
 }
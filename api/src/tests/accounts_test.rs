@@ -7,7 +7,7 @@ use crate::tests::new_test_context_with_db_sharding_and_internal_indexer;
 use aptos_api_test_context::{current_function_name, find_value, TestContext};
 use aptos_api_types::{MoveModuleBytecode, MoveResource, MoveStructTag, StateKeyWrapper};
 use aptos_cached_packages::aptos_stdlib;
-use serde_json::json;
+use serde_json::{json, Value};
 use std::str::FromStr;
 
 /* TODO: reactivate once cause of failure for `"8"` vs `8` in the JSON output is known.
@@ -406,10 +406,214 @@ async fn test_get_account_items_limit_params() {
     assert_eq!(resp.status(), 400);
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_query_account_resources_with_selections() {
+    let context = new_test_context(current_function_name!());
+    let address = "0xA550C18";
+
+    // Select a single field of a single resource
+    let resp = context
+        .post(
+            &account_resources_query(address),
+            json!({"selections": [{
+                "resource_type": "0x1::account::Account",
+                "fields": ["sequence_number"],
+            }]}),
+        )
+        .await;
+    let resources = resp["resources"].as_array().unwrap();
+    assert_eq!(resources.len(), 1);
+    assert_eq!(resources[0]["type"], "0x1::account::Account");
+    assert_eq!(
+        resources[0]["data"]
+            .as_object()
+            .unwrap()
+            .keys()
+            .collect::<Vec<_>>(),
+        vec!["sequence_number"]
+    );
+    assert!(resp["cursor"].is_null());
+
+    // Select nested fields of all instantiations of a generic resource
+    let resp = context
+        .post(
+            &account_resources_query(address),
+            json!({"selections": [{
+                "resource_type": "0x1::coin::CoinStore",
+                "fields": ["coin.value"],
+            }]}),
+        )
+        .await;
+    let resources = resp["resources"].as_array().unwrap();
+    assert!(!resources.is_empty());
+    for resource in resources {
+        assert!(resource["type"]
+            .as_str()
+            .unwrap()
+            .starts_with("0x1::coin::CoinStore<"));
+        assert_eq!(
+            resource["data"],
+            json!({"coin": {"value": resource["data"]["coin"]["value"]}})
+        );
+    }
+
+    // Invalid field paths are rejected
+    context
+        .expect_status_code(400)
+        .post(
+            &account_resources_query(address),
+            json!({"selections": [{
+                "resource_type": "0x1::account::Account",
+                "fields": ["sequence_number..value"],
+            }]}),
+        )
+        .await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_query_account_resources_with_pagination() {
+    let mut context = new_test_context(current_function_name!());
+    let address = "0xA550C18";
+    let ledger_version = context.get_latest_ledger_info().version();
+    let all_resources = context
+        .get(&account_resources_with_ledger_version(
+            address,
+            ledger_version as i128,
+        ))
+        .await;
+
+    // Commit a block, so the latest ledger version differs from the one pinned by the cursor
+    let mut root_account = context.root_account().await;
+    let account = context.gen_account();
+    let txn = context.create_user_account_by(&mut root_account, &account);
+    context.commit_block(&vec![txn]).await;
+
+    let mut resources = vec![];
+    let mut path = format!(
+        "{}?limit=3&ledger_version={}",
+        account_resources_query(address),
+        ledger_version
+    );
+    loop {
+        let resp = context.post(&path, json!({})).await;
+        resources.extend(resp["resources"].as_array().unwrap().clone());
+        match resp["cursor"].as_str() {
+            Some(cursor) => {
+                path = format!(
+                    "{}?limit=3&cursor={}",
+                    account_resources_query(address),
+                    cursor
+                )
+            },
+            None => break,
+        }
+    }
+    assert_eq!(Value::Array(resources), all_resources);
+
+    // The ledger version must match the cursor's
+    let resp = context
+        .post(
+            &format!("{}?limit=3", account_resources_query(address)),
+            json!({}),
+        )
+        .await;
+    let cursor = resp["cursor"].as_str().unwrap();
+    context
+        .expect_status_code(400)
+        .post(
+            &format!(
+                "{}?cursor={}&ledger_version=0",
+                account_resources_query(address),
+                cursor
+            ),
+            json!({}),
+        )
+        .await;
+
+    // Invalid cursors are rejected
+    context
+        .expect_status_code(400)
+        .post(
+            &format!("{}?cursor=notacursor", account_resources_query(address)),
+            json!({}),
+        )
+        .await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_query_account_resources_by_type() {
+    let context = new_test_context(current_function_name!());
+    let address = "0xA550C18";
+
+    // Fully specified types are looked up directly, in a single page
+    let resp = context
+        .post(
+            &format!("{}?limit=2", account_resources_query(address)),
+            json!({"selections": [
+                {"resource_type": "0x1::coin::CoinStore<0x1::aptos_coin::AptosCoin>"},
+                {"resource_type": "0x1::account::Account", "fields": ["sequence_number"]},
+                {"resource_type": "0x1::account::Missing"},
+            ]}),
+        )
+        .await;
+    let resources = resp["resources"].as_array().unwrap();
+    let mut types: Vec<_> = resources
+        .iter()
+        .map(|resource| resource["type"].as_str().unwrap())
+        .collect();
+    types.sort();
+    assert_eq!(types, vec![
+        "0x1::account::Account",
+        "0x1::coin::CoinStore<0x1::aptos_coin::AptosCoin>",
+    ]);
+    assert!(resp["cursor"].is_null());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_query_account_resources_fills_pages() {
+    let context = new_test_context(current_function_name!());
+    let address = "0xA550C18";
+    let query = json!({"selections": [{"resource_type": "0x1::coin::CoinStore"}]});
+    let all_coin_stores = context
+        .post(&account_resources_query(address), query.clone())
+        .await["resources"]
+        .clone();
+    assert!(!all_coin_stores.as_array().unwrap().is_empty());
+
+    // Every page but the last one is full, even though most resources aren't selected
+    let mut resources = vec![];
+    let mut path = format!("{}?limit=1", account_resources_query(address));
+    loop {
+        let resp = context.post(&path, query.clone()).await;
+        let page = resp["resources"].as_array().unwrap();
+        match resp["cursor"].as_str() {
+            Some(cursor) => {
+                assert_eq!(page.len(), 1);
+                path = format!(
+                    "{}?limit=1&cursor={}",
+                    account_resources_query(address),
+                    cursor
+                );
+            },
+            None => {
+                assert!(page.len() <= 1);
+                resources.extend(page.clone());
+                break;
+            },
+        }
+        resources.extend(page.clone());
+    }
+    assert_eq!(Value::Array(resources), all_coin_stores);
+}
+
 fn account_resources(address: &str) -> String {
     format!("/accounts/{}/resources", address)
 }
 
+fn account_resources_query(address: &str) -> String {
+    format!("/accounts/{}/resources/query", address)
+}
+
 fn account_resources_with_ledger_version(address: &str, ledger_version: i128) -> String {
     format!(
         "{}?ledger_version={}",
@@ -16,6 +16,7 @@ mod secp256k1_ecdsa;
 mod simulation_test;
 mod state_test;
 mod string_resource_test;
+mod transaction_stream_test;
 mod transaction_vector_test;
 mod transactions_test;
 mod view_function;
@@ -3,12 +3,18 @@
 
 use super::new_test_context;
 use aptos_api_test_context::{current_function_name, TestContext};
+use aptos_api_types::{mime_types::BCS, StateValueWithProof};
 use aptos_sdk::{transaction_builder::aptos_stdlib::aptos_token_stdlib, types::LocalAccount};
 use aptos_storage_interface::DbReader;
+use aptos_types::{
+    account_config::AccountResource, state_store::state_key::StateKey, trusted_state::TrustedState,
+    waypoint::Waypoint,
+};
 use move_core_types::account_address::AccountAddress;
 use serde::Serialize;
 use serde_json::{json, Value};
 use std::path::PathBuf;
+use warp::http::header::ACCEPT;
 
 #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
 async fn test_get_account_resource() {
@@ -297,6 +303,51 @@ async fn test_get_table_item() {
     assert_table_item(ctx, &nested_table, "u8", "u8", 2, 3).await;
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_get_state_value_with_proof() {
+    let context = new_test_context(current_function_name!());
+    let state_key = StateKey::resource_typed::<AccountResource>(&AccountAddress::ONE).unwrap();
+    let trusted_state = latest_trusted_state(&context);
+
+    let proof = api_get_state_value_with_proof(&context, &state_key).await;
+    assert!(proof.state_value.is_some());
+    proof.verify(&state_key, &trusted_state).unwrap();
+
+    // A proof for a different key is rejected
+    let other_key = StateKey::resource_typed::<AccountResource>(&AccountAddress::TWO).unwrap();
+    assert!(proof.verify(&other_key, &trusted_state).is_err());
+
+    // A tampered state value is rejected
+    let mut tampered = proof.clone();
+    tampered.state_value = None;
+    assert!(tampered.verify(&state_key, &trusted_state).is_err());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_get_state_value_with_proof_of_absence() {
+    let context = new_test_context(current_function_name!());
+    let state_key = StateKey::resource_typed::<AccountResource>(&AccountAddress::random()).unwrap();
+    let trusted_state = latest_trusted_state(&context);
+
+    let proof = api_get_state_value_with_proof(&context, &state_key).await;
+    assert!(proof.state_value.is_none());
+    proof.verify(&state_key, &trusted_state).unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_get_state_value_with_proof_rejects_json() {
+    let context = new_test_context(current_function_name!());
+    let state_key = StateKey::resource_typed::<AccountResource>(&AccountAddress::ONE).unwrap();
+    let resp = context
+        .expect_status_code(403)
+        .post(
+            "/experimental/state_values/proof",
+            json!({ "key": hex::encode(bcs::to_bytes(&state_key).unwrap()) }),
+        )
+        .await;
+    assert_eq!(resp["error_code"], "api_disabled");
+}
+
 fn get_account_resource(address: &str, struct_tag: &str) -> String {
     format!("/accounts/{}/resource/{}", address, struct_tag)
 }
@@ -367,3 +418,31 @@ async fn assert_table_item<T: Serialize, U: Serialize>(
     let response = api_get_table_item(ctx, table, key_type, value_type, key).await;
     assert_eq!(response, json!(value));
 }
+
+/// Returns a trusted state at the latest ledger info. The test context doesn't sign its
+/// ledger infos, so the trusted state must already be at the latest version.
+fn latest_trusted_state(ctx: &TestContext) -> TrustedState {
+    let ledger_info = ctx.context.db.get_latest_ledger_info().unwrap();
+    TrustedState::EpochState {
+        waypoint: Waypoint::new_any(ledger_info.ledger_info()),
+        epoch_state: ctx.context.db.get_latest_epoch_state().unwrap(),
+    }
+}
+
+async fn api_get_state_value_with_proof(
+    ctx: &TestContext,
+    state_key: &StateKey,
+) -> StateValueWithProof {
+    let known_version = ctx.context.db.get_latest_ledger_info_version().unwrap();
+    let req = warp::test::request()
+        .method("POST")
+        .path(&format!(
+            "/v1/experimental/state_values/proof?known_version={}",
+            known_version
+        ))
+        .header(ACCEPT, BCS)
+        .json(&json!({ "key": hex::encode(bcs::to_bytes(state_key).unwrap()) }));
+    let resp = ctx.reply(req).await;
+    assert_eq!(resp.status(), 200);
+    bcs::from_bytes(resp.body()).unwrap()
+}
@@ -0,0 +1,243 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{new_test_context, new_test_context_with_config};
+use crate::transaction_stream::{
+    is_transaction_allowed, parse_transaction_filter, transaction_stream, TransactionStreamItem,
+};
+use aptos_api_test_context::{current_function_name, TestContext};
+use aptos_api_types::{Address, Transaction};
+use aptos_config::config::NodeConfig;
+use futures::StreamExt;
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use serde_json::json;
+use std::{
+    sync::{atomic::Ordering, Arc},
+    time::Duration,
+};
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_stream_transactions_with_invalid_filter() {
+    let context = new_test_context(current_function_name!());
+
+    // A filter that can't be parsed
+    let resp = context
+        .expect_status_code(400)
+        .get(&stream_path("not a filter"))
+        .await;
+    assert_eq!(resp["error_code"], "invalid_input");
+
+    // A filter that parses, but is empty (and therefore invalid)
+    let filter = json!({"type": "UserTransactionFilter"}).to_string();
+    let resp = context
+        .expect_status_code(400)
+        .get(&stream_path(&filter))
+        .await;
+    assert_eq!(resp["error_code"], "invalid_input");
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_stream_transactions_with_too_many_streams() {
+    let mut node_config = NodeConfig::default();
+    node_config.api.transaction_stream_max_active_connections = 0;
+    let context = new_test_context_with_config(current_function_name!(), node_config);
+
+    context
+        .expect_status_code(503)
+        .get("/transactions/stream")
+        .await;
+    assert_eq!(
+        context
+            .context
+            .transaction_stream_active_connections
+            .load(Ordering::Relaxed),
+        0
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_stream_transactions_by_sender() {
+    let mut context = new_test_context(current_function_name!());
+    let mut root_account = context.root_account().await;
+    let account = context.gen_account();
+    let txn = context.create_user_account_by(&mut root_account, &account);
+    context.commit_block(&vec![txn]).await;
+
+    // Stream all transactions from genesis, filtering by the root account
+    let filter = parse_transaction_filter(
+        &json!({
+            "type": "UserTransactionFilter",
+            "sender": Address::from(root_account.address()).to_string(),
+        })
+        .to_string(),
+    )
+    .unwrap();
+    let mut stream = Box::pin(start_stream(&context, 0, Some(filter)));
+    let txn = next_transaction(&mut stream).await;
+    match txn {
+        Transaction::UserTransaction(txn) => {
+            assert_eq!(txn.request.sender, root_account.address().into());
+        },
+        txn => panic!("Expected a user transaction, but got: {:?}", txn),
+    }
+
+    // Verify the stream waits for (and returns) newly committed transactions
+    let account = context.gen_account();
+    let txn = context.create_user_account_by(&mut root_account, &account);
+    let hash = txn.committed_hash();
+    let (_, txn) = tokio::join!(
+        async {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            context.commit_block(&[txn]).await
+        },
+        next_transaction(&mut stream),
+    );
+    match txn {
+        Transaction::UserTransaction(txn) => assert_eq!(txn.info.hash, hash.into()),
+        txn => panic!("Expected a user transaction, but got: {:?}", txn),
+    }
+
+    // Dropping the stream releases the connection
+    drop(stream);
+    assert_eq!(
+        context
+            .context
+            .transaction_stream_active_connections
+            .load(Ordering::Relaxed),
+        0
+    );
+}
+
+#[cfg(feature = "failpoints")]
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_stream_transactions_with_storage_errors() {
+    let context = new_test_context(current_function_name!());
+    let scenario = fail::FailScenario::setup();
+
+    // Verify transient storage errors are retried
+    fail::cfg("api::transaction_stream_fetch", "2*return").unwrap();
+    let mut stream = Box::pin(start_stream(&context, 0, None));
+    let txn = next_transaction(&mut stream).await;
+    assert_eq!(txn.version(), Some(0));
+    drop(stream);
+
+    // Verify persistent storage errors are returned, and end the stream
+    fail::cfg("api::transaction_stream_fetch", "return").unwrap();
+    let mut stream = Box::pin(start_stream(&context, 0, None));
+    match next_item(&mut stream).await {
+        Some(TransactionStreamItem::Error(error)) => {
+            assert!(error.message.contains("transaction_stream_fetch"))
+        },
+        item => panic!("Expected an error, but got: {:?}", item),
+    }
+    assert!(next_item(&mut stream).await.is_none());
+    drop(stream);
+
+    fail::remove("api::transaction_stream_fetch");
+    scenario.teardown();
+    assert_eq!(
+        context
+            .context
+            .transaction_stream_active_connections
+            .load(Ordering::Relaxed),
+        0
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_transaction_filters() {
+    let mut context = new_test_context(current_function_name!());
+    let mut root_account = context.root_account().await;
+    let account = context.gen_account();
+    let txn = context.create_user_account_by(&mut root_account, &account);
+    context.commit_block(&vec![txn]).await;
+
+    // Find the committed user transaction
+    let mut stream = Box::pin(start_stream(&context, 0, None));
+    let txn = loop {
+        let txn = next_transaction(&mut stream).await;
+        if matches!(txn, Transaction::UserTransaction(_)) {
+            break txn;
+        }
+    };
+
+    let is_allowed = |filter: serde_json::Value| {
+        is_transaction_allowed(
+            &parse_transaction_filter(&filter.to_string()).unwrap(),
+            &txn,
+        )
+    };
+
+    // Filter by entry function
+    assert!(is_allowed(json!({
+        "type": "UserTransactionFilter",
+        "payload": {"function": {"address": "0x1", "module": "aptos_account", "function": "create_account"}},
+    })));
+    assert!(!is_allowed(json!({
+        "type": "UserTransactionFilter",
+        "payload": {"function": {"address": "0x1", "module": "coin", "function": "transfer"}},
+    })));
+
+    // Filter by event type (all events must match)
+    assert!(is_allowed(json!({
+        "type": "EventFilter",
+        "struct_type": {"address": "0x1"},
+    })));
+    assert!(!is_allowed(json!({
+        "type": "EventFilter",
+        "struct_type": {"address": "0x2"},
+    })));
+
+    // Filter by transaction type and success, combined with boolean operators
+    assert!(is_allowed(json!({
+        "and": [
+            {"type": "TransactionRootFilter", "success": true},
+            {"not": {"type": "TransactionRootFilter", "txn_type": "TRANSACTION_TYPE_BLOCK_METADATA"}},
+        ],
+    })));
+    assert!(!is_allowed(json!({
+        "or": [
+            {"type": "TransactionRootFilter", "success": false},
+            {"type": "TransactionRootFilter", "txn_type": "TRANSACTION_TYPE_GENESIS"},
+        ],
+    })));
+}
+
+fn stream_path(filter: &str) -> String {
+    format!(
+        "/transactions/stream?filter={}",
+        utf8_percent_encode(filter, NON_ALPHANUMERIC)
+    )
+}
+
+fn start_stream(
+    context: &TestContext,
+    start_version: u64,
+    filter: Option<aptos_transaction_filter::BooleanTransactionFilter>,
+) -> impl futures::Stream<Item = TransactionStreamItem> {
+    let context = Arc::new(context.context.clone());
+    context
+        .transaction_stream_active_connections
+        .fetch_add(1, Ordering::Relaxed);
+    transaction_stream(context, start_version, filter)
+}
+
+async fn next_item(
+    stream: &mut (impl futures::Stream<Item = TransactionStreamItem> + Unpin),
+) -> Option<TransactionStreamItem> {
+    tokio::time::timeout(Duration::from_secs(10), stream.next())
+        .await
+        .expect("Timed out waiting for a stream item")
+}
+
+async fn next_transaction(
+    stream: &mut (impl futures::Stream<Item = TransactionStreamItem> + Unpin),
+) -> Transaction {
+    match next_item(stream).await {
+        Some(TransactionStreamItem::Transaction(txn)) => txn,
+        Some(TransactionStreamItem::Error(error)) => {
+            panic!("The transaction stream failed: {}", error)
+        },
+        None => panic!("The transaction stream ended unexpectedly"),
+    }
+}
@@ -1694,6 +1694,95 @@ async fn test_simulation_filter_allow_sender() {
     context.check_golden_output(resp);
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_get_account_pending_transactions() {
+    let mut context = new_test_context(current_function_name!());
+    let account_1 = context.gen_account();
+    let account_2 = context.gen_account();
+    let mut root_account = context.root_account().await;
+    let sequence_number = root_account.sequence_number();
+
+    // Submit two transactions with a sequence number gap between them
+    let txn_1 = context.create_user_account_by(&mut root_account, &account_1);
+    root_account.increment_sequence_number();
+    let txn_2 = context.create_user_account_by(&mut root_account, &account_2);
+    for txn in [txn_1, txn_2] {
+        context
+            .expect_status_code(202)
+            .post_bcs_txn("/transactions", bcs::to_bytes(&txn).unwrap())
+            .await;
+    }
+
+    // Verify the pending transactions are returned (with the second one parked)
+    let resp = context
+        .get(&format!(
+            "/accounts/{}/pending_transactions",
+            root_account.address()
+        ))
+        .await;
+    assert_eq!(resp["sequence_number"], sequence_number.to_string());
+    let transactions = resp["transactions"].as_array().unwrap();
+    assert_eq!(transactions.len(), 2);
+    assert_eq!(transactions[0]["status"], "ready");
+    assert_eq!(
+        transactions[0]["transaction"]["sequence_number"],
+        sequence_number.to_string()
+    );
+    assert_eq!(transactions[1]["status"], "parked");
+    assert_eq!(
+        transactions[1]["transaction"]["sequence_number"],
+        (sequence_number + 2).to_string()
+    );
+
+    // Verify the sequence number gap is explained
+    let gaps = resp["sequence_number_gaps"].as_array().unwrap();
+    assert_eq!(gaps.len(), 1);
+    assert_eq!(
+        gaps[0]["start_sequence_number"],
+        (sequence_number + 1).to_string()
+    );
+    assert_eq!(
+        gaps[0]["end_sequence_number"],
+        (sequence_number + 1).to_string()
+    );
+    assert_eq!(gaps[0]["num_blocked_transactions"], "1");
+
+    // Verify the mempool stats account for both transactions
+    let resp = context.get("/mempool/stats").await;
+    assert_eq!(resp["num_transactions"], "2");
+    assert_eq!(resp["num_parked_transactions"], "1");
+    let num_bucket_transactions: u64 = resp["fee_buckets"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|bucket| {
+            bucket["num_transactions"]
+                .as_str()
+                .unwrap()
+                .parse::<u64>()
+                .unwrap()
+        })
+        .sum();
+    assert_eq!(num_bucket_transactions, 2);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_get_account_pending_transactions_empty() {
+    let mut context = new_test_context(current_function_name!());
+    let account = context.gen_account();
+
+    // Verify an account without pending transactions has no transactions or gaps
+    let resp = context
+        .get(&format!(
+            "/accounts/{}/pending_transactions",
+            account.address()
+        ))
+        .await;
+    assert_eq!(resp["sequence_number"], "0");
+    assert!(resp["transactions"].as_array().unwrap().is_empty());
+    assert!(resp["sequence_number_gaps"].as_array().unwrap().is_empty());
+}
+
 fn gen_string(len: u64) -> String {
     let mut rng = thread_rng();
     std::iter::repeat(())
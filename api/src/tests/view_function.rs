@@ -3,11 +3,20 @@
 
 use super::{new_test_context, new_test_context_with_config};
 use aptos_api_test_context::{current_function_name, TestContext};
+use aptos_api_types::{
+    mime_types::{BCS, BCS_VIEW_BATCH},
+    AptosErrorCode, BatchViewRequestItemBcs, BatchViewResultBcs, ViewFunction,
+};
 use aptos_cached_packages::aptos_stdlib;
 use aptos_config::config::{NodeConfig, ViewFilter, ViewFunctionId};
-use aptos_types::account_address::AccountAddress;
+use aptos_types::{account_address::AccountAddress, account_config::AccountResource};
+use move_core_types::{
+    ident_str,
+    language_storage::{ModuleId, StructTag, TypeTag},
+};
 use serde_json::{json, Value};
 use std::{path::PathBuf, str::FromStr};
+use warp::http::header::{ACCEPT, CONTENT_TYPE};
 
 fn build_coin_balance_request(address: &AccountAddress) -> Value {
     json!({
@@ -252,3 +261,201 @@ async fn test_view_aggregator() {
         .await;
     context.check_golden_output_no_prune(resp);
 }
+
+fn build_batch_view_item(item_type: &str, mut request: Value) -> Value {
+    request["type"] = json!(item_type);
+    request
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_view_batch() {
+    let mut context = new_test_context(current_function_name!());
+    let creator = &mut context.gen_account();
+    let owner = &mut context.gen_account();
+    let txn1 = context.mint_user_account(creator).await;
+    let txn2 = context.account_transfer(creator, owner, 100_000);
+
+    context.commit_block(&vec![txn1, txn2]).await;
+
+    let req = warp::test::request()
+        .method("POST")
+        .path("/v1/view/batch")
+        .json(&json!({
+            "requests": [
+                build_batch_view_item(
+                    "view_function",
+                    build_coin_balance_request(&owner.address()),
+                ),
+                build_batch_view_item("view_function", build_coin_decimals_request()),
+                build_batch_view_item(
+                    "resource",
+                    json!({
+                        "address": owner.address().to_string(),
+                        "resource_type": "0x1::account::Account",
+                    }),
+                ),
+                build_batch_view_item(
+                    "resource",
+                    json!({
+                        "address": context.gen_account().address().to_string(),
+                        "resource_type": "0x1::account::Account",
+                    }),
+                ),
+                build_batch_view_item(
+                    "view_function",
+                    json!({
+                        "function":"0x1::aptos_account::assert_account_exists",
+                        "arguments": vec![owner.address().to_string()],
+                        "type_arguments": [],
+                    }),
+                ),
+            ],
+        }));
+    let resp = context.reply(req).await;
+    assert_eq!(resp.status(), 200);
+
+    // Confirm the gas used header is present
+    let gas_used = resp
+        .headers()
+        .get("X-Aptos-Gas-Used")
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .parse::<u64>()
+        .unwrap();
+    assert!(gas_used > 0);
+
+    // Verify the results are returned in order, with per-item errors
+    let results: Value = serde_json::from_slice(resp.body()).unwrap();
+    assert_eq!(results.as_array().unwrap().len(), 5);
+    assert_eq!(results[0]["values"], json!(["100000"]));
+    assert_eq!(results[1]["values"], json!([8]));
+    assert_eq!(results[2]["resource"]["type"], "0x1::account::Account");
+    assert_eq!(results[3]["error"]["error_code"], "resource_not_found");
+    assert_eq!(results[4]["error"]["error_code"], "invalid_input");
+
+    // The total gas used is the sum of the gas used by the view functions
+    let view_gas_used: u64 = [&results[0], &results[1]]
+        .iter()
+        .map(|result| result["gas_used"].as_str().unwrap().parse::<u64>().unwrap())
+        .sum();
+    assert!(gas_used >= view_gas_used);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_view_batch_gas_budget() {
+    let mut node_config = NodeConfig::default();
+    node_config.api.max_gas_view_batch = 1;
+    let mut context = new_test_context_with_config(current_function_name!(), node_config);
+    let creator = &mut context.gen_account();
+    let txn = context.mint_user_account(creator).await;
+    context.commit_block(&vec![txn]).await;
+
+    let resp = context
+        .post(
+            "/view/batch",
+            json!({
+                "requests": [
+                    build_batch_view_item(
+                        "view_function",
+                        build_coin_balance_request(&creator.address()),
+                    ),
+                    build_batch_view_item("view_function", build_coin_decimals_request()),
+                    build_batch_view_item(
+                        "resource",
+                        json!({
+                            "address": creator.address().to_string(),
+                            "resource_type": "0x1::account::Account",
+                        }),
+                    ),
+                ],
+            }),
+        )
+        .await;
+
+    // The view functions exceed the gas budget, but the resource read is unaffected
+    assert!(resp[0]["error"].is_object());
+    assert!(resp[1]["error"].is_object());
+    assert_eq!(resp[2]["resource"]["type"], "0x1::account::Account");
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_view_batch_too_large() {
+    let mut node_config = NodeConfig::default();
+    node_config.api.max_view_batch_size = 1;
+    let context = new_test_context_with_config(current_function_name!(), node_config);
+
+    let resp = context
+        .expect_status_code(400)
+        .post(
+            "/view/batch",
+            json!({
+                "requests": [
+                    build_batch_view_item("view_function", build_coin_decimals_request()),
+                    build_batch_view_item("view_function", build_coin_decimals_request()),
+                ],
+            }),
+        )
+        .await;
+    assert_eq!(resp["error_code"], "invalid_input");
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_view_batch_bcs() {
+    let mut context = new_test_context(current_function_name!());
+    let creator = &mut context.gen_account();
+    let owner = &mut context.gen_account();
+    let txn1 = context.mint_user_account(creator).await;
+    let txn2 = context.account_transfer(creator, owner, 100_000);
+    context.commit_block(&vec![txn1, txn2]).await;
+
+    let account_type = StructTag::from_str("0x1::account::Account").unwrap();
+    let requests = vec![
+        BatchViewRequestItemBcs::ViewFunction(ViewFunction {
+            module: ModuleId::new(AccountAddress::ONE, ident_str!("coin").to_owned()),
+            function: ident_str!("balance").to_owned(),
+            ty_args: vec![TypeTag::from_str("0x1::aptos_coin::AptosCoin").unwrap()],
+            args: vec![bcs::to_bytes(&owner.address()).unwrap()],
+        }),
+        BatchViewRequestItemBcs::Resource {
+            address: owner.address(),
+            resource_type: account_type.clone(),
+        },
+        BatchViewRequestItemBcs::Resource {
+            address: context.gen_account().address(),
+            resource_type: account_type,
+        },
+    ];
+    let req = warp::test::request()
+        .method("POST")
+        .path("/v1/view/batch")
+        .header(CONTENT_TYPE, BCS_VIEW_BATCH)
+        .header(ACCEPT, BCS)
+        .body(bcs::to_bytes(&requests).unwrap());
+    let resp = context.reply(req).await;
+    assert_eq!(resp.status(), 200);
+
+    // Verify the results are returned in order, with per-item errors
+    let results: Vec<BatchViewResultBcs> = bcs::from_bytes(resp.body()).unwrap();
+    assert_eq!(results.len(), 3);
+    match &results[0] {
+        BatchViewResultBcs::ViewFunction { values, gas_used } => {
+            assert_eq!(values, &vec![bcs::to_bytes(&100_000u64).unwrap()]);
+            assert!(*gas_used > 0);
+        },
+        result => panic!("Expected view function values, but got: {:?}", result),
+    }
+    match &results[1] {
+        BatchViewResultBcs::Resource(bytes) => {
+            let account: AccountResource = bcs::from_bytes(bytes).unwrap();
+            assert_eq!(account.sequence_number(), 0);
+        },
+        result => panic!("Expected a resource, but got: {:?}", result),
+    }
+    match &results[2] {
+        BatchViewResultBcs::Error(error) => {
+            assert!(matches!(error.error_code, AptosErrorCode::ResourceNotFound))
+        },
+        result => panic!("Expected an error, but got: {:?}", result),
+    }
+}
@@ -0,0 +1,354 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    context::{api_spawn_blocking, Context},
+    failpoint::fail_point_poem,
+    metrics::TRANSACTION_STREAM_GAUGE,
+    response::{version_pruned, AptosErrorResponse, BasicErrorWith404, InternalError},
+};
+use anyhow::Context as AnyhowContext;
+use aptos_api_types::{
+    AptosError, AptosErrorCode, EntryFunctionId, Event, MoveType, MultisigTransactionPayload,
+    Transaction, TransactionPayload,
+};
+use aptos_logger::warn;
+use aptos_protos::transaction::v1::{
+    self as proto, move_type::Content, multisig_transaction_payload, transaction::TxnData,
+    transaction_payload,
+};
+use aptos_transaction_filter::{BooleanTransactionFilter, Filterable};
+use futures::{stream, Stream};
+use poem_openapi::Union;
+use std::{
+    collections::VecDeque,
+    sync::{atomic::Ordering, Arc},
+    time::Duration,
+};
+
+/// The number of times a transient storage error is retried before the stream is ended
+const MAX_TRANSIENT_ERROR_RETRIES: u32 = 3;
+
+/// An item in a transaction stream: either a committed transaction, or the error that
+/// ended the stream.
+#[derive(Debug, Union)]
+#[oai(one_of)]
+pub enum TransactionStreamItem {
+    Transaction(Transaction),
+    Error(AptosError),
+}
+
+/// Parses a JSON encoded `BooleanTransactionFilter` (as accepted by the indexer gRPC
+/// data service), and verifies that it is valid.
+pub fn parse_transaction_filter(filter: &str) -> anyhow::Result<BooleanTransactionFilter> {
+    let filter: BooleanTransactionFilter =
+        serde_json::from_str(filter).context("Failed to parse transaction filter")?;
+    filter.is_valid().context("Invalid transaction filter")?;
+    Ok(filter)
+}
+
+/// Returns true iff the given transaction is allowed by the filter. The filter is applied to
+/// the protobuf representation used by the indexer, so the semantics are identical to the
+/// indexer gRPC transaction stream.
+pub fn is_transaction_allowed(filter: &BooleanTransactionFilter, txn: &Transaction) -> bool {
+    filter.is_allowed(&to_filterable_transaction(txn))
+}
+
+/// Returns a stream of committed transactions, starting at the given version (inclusive) and
+/// continuing indefinitely as new transactions are committed. Transactions that don't match
+/// the filter are skipped. Transient storage errors are retried (with backoff). If the storage
+/// keeps failing (or the next version has been pruned), the error is returned as the final
+/// item of the stream, so clients should reconnect from the last received version.
+///
+/// The caller is expected to have incremented `transaction_stream_active_connections`; it
+/// is decremented when the stream is dropped.
+pub fn transaction_stream(
+    context: Arc<Context>,
+    start_version: u64,
+    filter: Option<BooleanTransactionFilter>,
+) -> impl Stream<Item = TransactionStreamItem> + Send + 'static {
+    let state = TransactionStreamState {
+        poll_interval: Duration::from_millis(
+            context.node_config.api.transaction_stream_poll_interval_ms,
+        ),
+        _guard: ActiveStreamGuard::new(context.clone()),
+        context,
+        next_version: start_version,
+        filter,
+        pending: VecDeque::new(),
+        num_retries: 0,
+        ended: false,
+    };
+
+    stream::unfold(state, |mut state| async move {
+        if state.ended {
+            return None;
+        }
+
+        loop {
+            if let Some(txn) = state.pending.pop_front() {
+                return Some((TransactionStreamItem::Transaction(txn), state));
+            }
+
+            match state.fetch_next_batch().await {
+                Ok(has_new_transactions) => {
+                    state.num_retries = 0;
+                    if !has_new_transactions {
+                        tokio::time::sleep(state.poll_interval).await;
+                    }
+                },
+                Err(error) => {
+                    if is_transient_error(&error) && state.num_retries < MAX_TRANSIENT_ERROR_RETRIES
+                    {
+                        state.num_retries += 1;
+                        warn!(
+                            "Transaction stream at version {} failed (retry {} of {}): {}",
+                            state.next_version,
+                            state.num_retries,
+                            MAX_TRANSIENT_ERROR_RETRIES,
+                            error
+                        );
+                        tokio::time::sleep(state.poll_interval * 2u32.pow(state.num_retries)).await;
+                        continue;
+                    }
+
+                    warn!(
+                        "Transaction stream at version {} ended: {}",
+                        state.next_version, error
+                    );
+                    state.ended = true;
+                    return Some((TransactionStreamItem::Error(error), state));
+                },
+            }
+        }
+    })
+}
+
+struct TransactionStreamState {
+    context: Arc<Context>,
+    next_version: u64,
+    filter: Option<BooleanTransactionFilter>,
+    poll_interval: Duration,
+    pending: VecDeque<Transaction>,
+    num_retries: u32,
+    ended: bool,
+    _guard: ActiveStreamGuard,
+}
+
+impl TransactionStreamState {
+    /// Reads the next page of committed transactions (if any), and queues the ones that
+    /// match the filter. Returns false iff there were no new transactions to read.
+    async fn fetch_next_batch(&mut self) -> Result<bool, AptosError> {
+        let context = self.context.clone();
+        let start_version = self.next_version;
+        let txns = api_spawn_blocking(move || {
+            fail_point_poem::<BasicErrorWith404>("transaction_stream_fetch")?;
+            let latest_ledger_info = context.get_latest_ledger_info::<BasicErrorWith404>()?;
+            let ledger_version = latest_ledger_info.version();
+            if start_version > ledger_version {
+                return Ok(vec![]);
+            }
+            if start_version < latest_ledger_info.oldest_ledger_version.0 {
+                return Err(version_pruned(start_version, &latest_ledger_info));
+            }
+
+            let data = context
+                .get_transactions(
+                    start_version,
+                    context.max_transactions_page_size(),
+                    ledger_version,
+                )
+                .context("Failed to read raw transactions from storage")
+                .map_err(|err| {
+                    BasicErrorWith404::internal_with_code(
+                        err,
+                        AptosErrorCode::InternalError,
+                        &latest_ledger_info,
+                    )
+                })?;
+            let timestamp = context.get_block_timestamp(&latest_ledger_info, start_version)?;
+            context.render_transactions_sequential(&latest_ledger_info, data, timestamp)
+        })
+        .await
+        .map_err(|mut error: BasicErrorWith404| error.inner_mut().clone())?;
+
+        if txns.is_empty() {
+            return Ok(false);
+        }
+        self.next_version += txns.len() as u64;
+        for txn in txns {
+            if self
+                .filter
+                .as_ref()
+                .map_or(true, |filter| is_transaction_allowed(filter, &txn))
+            {
+                self.pending.push_back(txn);
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// Returns true iff the error may succeed if retried (e.g., it isn't caused by pruning)
+fn is_transient_error(error: &AptosError) -> bool {
+    !matches!(error.error_code, AptosErrorCode::VersionPruned)
+}
+
+/// Keeps track of the number of active transaction streams
+struct ActiveStreamGuard {
+    context: Arc<Context>,
+}
+
+impl ActiveStreamGuard {
+    fn new(context: Arc<Context>) -> Self {
+        TRANSACTION_STREAM_GAUGE.inc();
+        Self { context }
+    }
+}
+
+impl Drop for ActiveStreamGuard {
+    fn drop(&mut self) {
+        TRANSACTION_STREAM_GAUGE.dec();
+        self.context
+            .transaction_stream_active_connections
+            .fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Converts the transaction into the protobuf representation used by the indexer. Only the
+/// fields inspected by the transaction filters are populated, and they are formatted exactly
+/// as the indexer gRPC fullnode formats them.
+fn to_filterable_transaction(txn: &Transaction) -> proto::Transaction {
+    let (txn_type, txn_data) = match txn {
+        Transaction::UserTransaction(txn) => (
+            proto::transaction::TransactionType::User,
+            TxnData::User(proto::UserTransaction {
+                request: Some(proto::UserTransactionRequest {
+                    sender: txn.request.sender.to_string(),
+                    payload: Some(to_filterable_payload(&txn.request.payload)),
+                    ..Default::default()
+                }),
+                events: to_filterable_events(&txn.events),
+            }),
+        ),
+        Transaction::GenesisTransaction(txn) => (
+            proto::transaction::TransactionType::Genesis,
+            TxnData::Genesis(proto::GenesisTransaction {
+                events: to_filterable_events(&txn.events),
+                ..Default::default()
+            }),
+        ),
+        Transaction::BlockMetadataTransaction(txn) => (
+            proto::transaction::TransactionType::BlockMetadata,
+            TxnData::BlockMetadata(proto::BlockMetadataTransaction {
+                events: to_filterable_events(&txn.events),
+                ..Default::default()
+            }),
+        ),
+        Transaction::StateCheckpointTransaction(_) => (
+            proto::transaction::TransactionType::StateCheckpoint,
+            TxnData::StateCheckpoint(Default::default()),
+        ),
+        Transaction::BlockEpilogueTransaction(_) => (
+            proto::transaction::TransactionType::BlockEpilogue,
+            TxnData::BlockEpilogue(Default::default()),
+        ),
+        Transaction::ValidatorTransaction(_) => (
+            proto::transaction::TransactionType::Validator,
+            TxnData::Validator(Default::default()),
+        ),
+        // Pending transactions are never streamed, and are rejected by every filter
+        Transaction::PendingTransaction(_) => return proto::Transaction::default(),
+    };
+
+    proto::Transaction {
+        version: txn.version().unwrap_or_default(),
+        info: Some(proto::TransactionInfo {
+            success: txn.success(),
+            ..Default::default()
+        }),
+        r#type: txn_type as i32,
+        txn_data: Some(txn_data),
+        ..Default::default()
+    }
+}
+
+fn to_filterable_payload(payload: &TransactionPayload) -> proto::TransactionPayload {
+    let (payload_type, payload) = match payload {
+        TransactionPayload::EntryFunctionPayload(payload) => (
+            transaction_payload::Type::EntryFunctionPayload,
+            Some(transaction_payload::Payload::EntryFunctionPayload(
+                to_filterable_entry_function_payload(&payload.function),
+            )),
+        ),
+        TransactionPayload::MultisigPayload(payload) => (
+            transaction_payload::Type::MultisigPayload,
+            Some(transaction_payload::Payload::MultisigPayload(
+                proto::MultisigPayload {
+                    multisig_address: payload.multisig_address.to_string(),
+                    transaction_payload: payload.transaction_payload.as_ref().map(|payload| {
+                        match payload {
+                            MultisigTransactionPayload::EntryFunctionPayload(payload) => {
+                                proto::MultisigTransactionPayload {
+                                    r#type: multisig_transaction_payload::Type::EntryFunctionPayload
+                                        as i32,
+                                    payload: Some(
+                                        multisig_transaction_payload::Payload::EntryFunctionPayload(
+                                            to_filterable_entry_function_payload(&payload.function),
+                                        ),
+                                    ),
+                                }
+                            },
+                        }
+                    }),
+                },
+            )),
+        ),
+        TransactionPayload::ScriptPayload(_) => (transaction_payload::Type::ScriptPayload, None),
+        TransactionPayload::ModuleBundlePayload(_) => {
+            (transaction_payload::Type::Unspecified, None)
+        },
+    };
+
+    proto::TransactionPayload {
+        r#type: payload_type as i32,
+        payload,
+    }
+}
+
+fn to_filterable_entry_function_payload(function: &EntryFunctionId) -> proto::EntryFunctionPayload {
+    proto::EntryFunctionPayload {
+        function: Some(proto::EntryFunctionId {
+            module: Some(proto::MoveModuleId {
+                address: function.module.address.to_string(),
+                name: function.module.name.to_string(),
+            }),
+            name: function.name.to_string(),
+        }),
+        entry_function_id_str: function.to_string(),
+        ..Default::default()
+    }
+}
+
+fn to_filterable_events(events: &[Event]) -> Vec<proto::Event> {
+    events
+        .iter()
+        .map(|event| proto::Event {
+            r#type: Some(proto::MoveType {
+                content: match &event.typ {
+                    MoveType::Struct(struct_tag) => Some(Content::Struct(proto::MoveStructTag {
+                        address: struct_tag.address.to_string(),
+                        module: struct_tag.module.to_string(),
+                        name: struct_tag.name.to_string(),
+                        generic_type_params: vec![],
+                    })),
+                    _ => None,
+                },
+                ..Default::default()
+            }),
+            type_str: event.typ.to_string(),
+            data: event.data.to_string(),
+            ..Default::default()
+        })
+        .collect()
+}
@@ -15,22 +15,26 @@ use crate::{
         api_disabled, api_forbidden, transaction_not_found_by_hash,
         transaction_not_found_by_version, version_pruned, BadRequestError, BasicError,
         BasicErrorWith404, BasicResponse, BasicResponseStatus, BasicResult, BasicResultWith404,
-        ForbiddenError, InsufficientStorageError, InternalError,
+        ForbiddenError, InsufficientStorageError, InternalError, ServiceUnavailableError,
     },
+    transaction_stream::{parse_transaction_filter, transaction_stream, TransactionStreamItem},
     ApiTags,
 };
 use anyhow::Context as AnyhowContext;
 use aptos_api_types::{
-    verify_function_identifier, verify_module_identifier, Address, AptosError, AptosErrorCode,
-    AsConverter, EncodeSubmissionRequest, GasEstimation, GasEstimationBcs, HashValue,
-    HexEncodedBytes, LedgerInfo, MoveType, PendingTransaction, SubmitTransactionRequest,
-    Transaction, TransactionData, TransactionOnChainData, TransactionsBatchSingleSubmissionFailure,
-    TransactionsBatchSubmissionResult, UserTransaction, VerifyInput, VerifyInputWithRecursion,
-    MAX_RECURSIVE_TYPES_ALLOWED, U64,
+    verify_function_identifier, verify_module_identifier, AccountMempoolTransactions, Address,
+    AptosError, AptosErrorCode, AsConverter, EncodeSubmissionRequest, GasEstimation,
+    GasEstimationBcs, HashValue, HexEncodedBytes, LedgerInfo, MempoolFeeBucket, MempoolStats,
+    MempoolTransaction, MempoolTransactionStatus, MoveType, PendingTransaction, SequenceNumberGap,
+    SubmitTransactionRequest, Transaction, TransactionData, TransactionOnChainData,
+    TransactionsBatchSingleSubmissionFailure, TransactionsBatchSubmissionResult, UserTransaction,
+    VerifyInput, VerifyInputWithRecursion, MAX_RECURSIVE_TYPES_ALLOWED, U64,
 };
 use aptos_crypto::{hash::CryptoHash, signing_message};
+use aptos_mempool::{AccountPendingTransactions, PendingTransactionStatus};
 use aptos_types::{
     account_address::AccountAddress,
+    account_config::AccountResource,
     mempool_status::MempoolStatusCode,
     transaction::{
         EntryFunction, ExecutionStatus, MultisigTransactionPayload, RawTransaction,
@@ -40,13 +44,18 @@ use aptos_types::{
     APTOS_COIN_TYPE,
 };
 use aptos_vm::{AptosSimulationVM, AptosVM};
+use futures::{stream::BoxStream, StreamExt};
 use move_core_types::{ident_str, language_storage::ModuleId, vm_status::VMStatus};
+use poem::web::sse::Event as SseEvent;
 use poem_openapi::{
-    param::{Path, Query},
-    payload::Json,
+    param::{Header, Path, Query},
+    payload::{EventStream, Json},
     ApiRequest, OpenApi,
 };
-use std::{sync::Arc, time::Duration};
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
 
 generate_success_response!(SubmitTransactionResponse, (202, Accepted));
 
@@ -73,6 +82,9 @@ generate_success_response!(
 type SubmitTransactionsBatchResult<T> =
     poem::Result<SubmitTransactionsBatchResponse<T>, SubmitTransactionError>;
 
+/// The interval at which keep-alive comments are sent on idle transaction streams
+const TRANSACTION_STREAM_KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(15);
+
 type SimulateTransactionResult<T> = poem::Result<BasicResponse<T>, SubmitTransactionError>;
 
 // TODO: Consider making both content types accept either
@@ -278,6 +290,111 @@ impl TransactionsApi {
         result
     }
 
+    /// Stream transactions
+    ///
+    /// Streams on-chain committed transactions as server-sent events, starting at the given
+    /// ledger version and continuing as new transactions are committed. Each event contains a
+    /// single transaction (in JSON, including its events), and the event ID is the version of
+    /// the transaction. If no start version is provided, the stream starts with the next
+    /// committed transaction. If the start version has been pruned, a 410 will be returned.
+    ///
+    /// To resume a stream, reconnect with the `Last-Event-ID` header set to the ID of the
+    /// last received event (this takes precedence over the start version).
+    ///
+    /// Transient storage errors are retried. If the stream can't continue (e.g., because the
+    /// next version has been pruned), an `error` event containing the error is sent and the
+    /// stream is closed.
+    ///
+    /// Transactions can be filtered by providing a JSON encoded transaction filter, which
+    /// has the same format and semantics as the indexer gRPC transaction filters (e.g., to
+    /// filter by sender, entry function or event type).
+    #[oai(
+        path = "/transactions/stream",
+        method = "get",
+        operation_id = "stream_transactions",
+        tag = "ApiTags::Transactions"
+    )]
+    async fn stream_transactions(
+        &self,
+        /// Ledger version to start streaming transactions from
+        ///
+        /// If not provided, defaults to the next committed transaction
+        start: Query<Option<U64>>,
+        /// JSON encoded transaction filter
+        ///
+        /// If not provided, all transactions are streamed
+        filter: Query<Option<String>>,
+        /// Version of the last received transaction, used to resume a stream
+        #[oai(name = "Last-Event-ID")]
+        last_event_id: Header<Option<U64>>,
+    ) -> poem::Result<EventStream<BoxStream<'static, TransactionStreamItem>>, BasicErrorWith404>
+    {
+        fail_point_poem("endpoint_stream_transactions")?;
+        self.context
+            .check_api_output_enabled("Stream transactions", &AcceptType::Json)?;
+
+        let latest_ledger_info = self.context.get_latest_ledger_info()?;
+        let filter = filter
+            .0
+            .map(|filter| parse_transaction_filter(&filter))
+            .transpose()
+            .map_err(|err| {
+                BasicErrorWith404::bad_request_with_code(
+                    err,
+                    AptosErrorCode::InvalidInput,
+                    &latest_ledger_info,
+                )
+            })?;
+        let start_version = match (last_event_id.0, start.0) {
+            (Some(last_version), _) => last_version.0.saturating_add(1),
+            (None, Some(start_version)) => start_version.0,
+            (None, None) => latest_ledger_info.version() + 1,
+        };
+        if start_version < latest_ledger_info.oldest_ledger_version.0 {
+            return Err(version_pruned(start_version, &latest_ledger_info));
+        }
+
+        // Reject the stream if there are too many active streams
+        if self
+            .context
+            .transaction_stream_active_connections
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+            >= self
+                .context
+                .node_config
+                .api
+                .transaction_stream_max_active_connections
+        {
+            self.context
+                .transaction_stream_active_connections
+                .fetch_sub(1, std::sync::atomic::Ordering::Relaxed);
+            return Err(BasicErrorWith404::service_unavailable_with_code(
+                "Too many active transaction streams, please try again later",
+                AptosErrorCode::InternalError,
+                &latest_ledger_info,
+            ));
+        }
+
+        let stream = transaction_stream(self.context.clone(), start_version, filter);
+        Ok(EventStream::new(stream.boxed())
+            .keep_alive(TRANSACTION_STREAM_KEEP_ALIVE_INTERVAL)
+            .to_event(|item| match item {
+                TransactionStreamItem::Transaction(txn) => {
+                    let version = txn.version().unwrap_or_default();
+                    match serde_json::to_string(&txn) {
+                        Ok(data) => SseEvent::message(data).id(version.to_string()),
+                        Err(err) => {
+                            transaction_stream_error_event(AptosError::new_with_error_code(
+                                format!("Failed to serialize transaction {}: {}", version, err),
+                                AptosErrorCode::InternalError,
+                            ))
+                        },
+                    }
+                },
+                TransactionStreamItem::Error(error) => transaction_stream_error_event(error),
+            }))
+    }
+
     /// Get transaction by version
     ///
     /// Retrieves a transaction by a given version. If the version has been
@@ -344,6 +461,85 @@ impl TransactionsApi {
         api_spawn_blocking(move || api.list_by_account(&accept_type, page, address.0)).await
     }
 
+    /// Get account pending transactions
+    ///
+    /// Retrieves the transactions from an account that are pending in the mempool
+    /// of this node (i.e., not yet committed). Each transaction is either ready to
+    /// be included in a block, or parked because of a gap in the account's sequence
+    /// numbers. Any such gaps are also returned, to help diagnose stuck transactions.
+    ///
+    /// Note: only the mempool of the node serving the request is inspected.
+    #[oai(
+        path = "/accounts/:address/pending_transactions",
+        method = "get",
+        operation_id = "get_account_pending_transactions",
+        tag = "ApiTags::Transactions"
+    )]
+    async fn get_account_pending_transactions(
+        &self,
+        accept_type: AcceptType,
+        /// Address of account with or without a `0x` prefix
+        address: Path<Address>,
+    ) -> BasicResult<AccountMempoolTransactions> {
+        fail_point_poem("endpoint_get_account_pending_transactions")?;
+        self.context
+            .check_api_output_enabled("Get account pending transactions", &accept_type)?;
+        self.get_account_pending_transactions_inner(&accept_type, address.0)
+            .await
+    }
+
+    /// Get mempool stats
+    ///
+    /// Retrieves the stats of all transactions pending in the mempool of this
+    /// node, grouped by fee bucket (i.e., gas unit price range).
+    #[oai(
+        path = "/mempool/stats",
+        method = "get",
+        operation_id = "get_mempool_stats",
+        tag = "ApiTags::Transactions"
+    )]
+    async fn get_mempool_stats(&self, accept_type: AcceptType) -> BasicResult<MempoolStats> {
+        fail_point_poem("endpoint_get_mempool_stats")?;
+        self.context
+            .check_api_output_enabled("Get mempool stats", &accept_type)?;
+
+        let context = self.context.clone();
+        let ledger_info = api_spawn_blocking(move || context.get_latest_ledger_info()).await?;
+        let mempool_stats = self
+            .context
+            .get_mempool_stats()
+            .await
+            .context("Failed to get the mempool stats")
+            .map_err(|err| {
+                BasicError::internal_with_code(err, AptosErrorCode::InternalError, &ledger_info)
+            })?;
+
+        let mempool_stats = MempoolStats {
+            num_transactions: mempool_stats.num_transactions.into(),
+            num_parked_transactions: mempool_stats.num_parked_transactions.into(),
+            size_bytes: mempool_stats.size_bytes.into(),
+            capacity: mempool_stats.capacity.into(),
+            capacity_bytes: mempool_stats.capacity_bytes.into(),
+            fee_buckets: mempool_stats
+                .fee_buckets
+                .into_iter()
+                .map(|fee_bucket| MempoolFeeBucket {
+                    min_gas_unit_price: fee_bucket.min_ranking_score.into(),
+                    num_transactions: fee_bucket.num_transactions.into(),
+                    num_parked_transactions: fee_bucket.num_parked_transactions.into(),
+                })
+                .collect(),
+        };
+        match accept_type {
+            AcceptType::Json => {
+                BasicResponse::try_from_json((mempool_stats, &ledger_info, BasicResponseStatus::Ok))
+            },
+            AcceptType::Bcs => {
+                BasicResponse::try_from_bcs((mempool_stats, &ledger_info, BasicResponseStatus::Ok))
+            },
+        }
+    }
+
     /// Submit transaction
     ///
     /// This endpoint accepts transaction submissions in two formats.
@@ -515,7 +711,7 @@ impl TransactionsApi {
             // Confirm the simulation filter allows the transaction. We use HashValue::zero()
             // here for the block ID because we don't allow filtering by block ID for the
             // simulation filters. See the ConfigSanitizer for ApiConfig.
-            if !context.node_config.api.simulation_filter.allows(
+            if !context.simulation_filter().allows(
                 aptos_crypto::HashValue::zero(),
                 ledger_info.timestamp(),
                 &signed_transaction,
@@ -978,6 +1174,122 @@ impl TransactionsApi {
         })
     }
 
+    async fn get_account_pending_transactions_inner(
+        &self,
+        accept_type: &AcceptType,
+        address: Address,
+    ) -> BasicResult<AccountMempoolTransactions> {
+        let context = self.context.clone();
+        let ledger_info = api_spawn_blocking(move || context.get_latest_ledger_info()).await?;
+
+        let pending_transactions = self
+            .context
+            .get_pending_account_transactions(address.into())
+            .await
+            .context(format!(
+                "Failed to get pending transactions for account {}",
+                address
+            ))
+            .map_err(|err| {
+                BasicError::internal_with_code(err, AptosErrorCode::InternalError, &ledger_info)
+            })?;
+
+        let api = self.clone();
+        let accept_type = accept_type.clone();
+        api_spawn_blocking(move || {
+            api.render_account_pending_transactions(
+                &accept_type,
+                address,
+                pending_transactions,
+                &ledger_info,
+            )
+        })
+        .await
+    }
+
+    /// Converts the pending transactions of an account into the outgoing type,
+    /// and explains any gaps in the account's sequence numbers
+    fn render_account_pending_transactions(
+        &self,
+        accept_type: &AcceptType,
+        address: Address,
+        pending_transactions: AccountPendingTransactions,
+        ledger_info: &LedgerInfo,
+    ) -> BasicResult<AccountMempoolTransactions> {
+        // Fetch the on-chain sequence number of the account. If the account
+        // doesn't exist (yet), fall back to the sequence number known to mempool.
+        let sequence_number = self
+            .context
+            .get_resource_poem::<AccountResource, BasicError>(
+                address.into(),
+                ledger_info.version(),
+                ledger_info,
+            )?
+            .map(|account_resource| account_resource.sequence_number())
+            .or(pending_transactions.account_sequence_number)
+            .unwrap_or(0);
+
+        let state_view = self.context.latest_state_view_poem(ledger_info)?;
+        let converter =
+            state_view.as_converter(self.context.db.clone(), self.context.indexer_reader.clone());
+        let sequence_numbers: Vec<_> = pending_transactions
+            .transactions
+            .iter()
+            .map(|info| info.transaction.sequence_number())
+            .collect();
+        let transactions = pending_transactions
+            .transactions
+            .into_iter()
+            .map(|info| {
+                let transaction = converter
+                    .try_into_pending_transaction_poem(info.transaction)
+                    .context("Failed to convert pending transaction to PendingTransaction")
+                    .map_err(|err| {
+                        BasicError::internal_with_code(
+                            err,
+                            AptosErrorCode::InternalError,
+                            ledger_info,
+                        )
+                    })?;
+                let status = match info.status {
+                    PendingTransactionStatus::Ready => MempoolTransactionStatus::Ready,
+                    PendingTransactionStatus::Parked => MempoolTransactionStatus::Parked,
+                };
+                let insertion_timestamp_usecs = info
+                    .insertion_time
+                    .duration_since(SystemTime::UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_micros() as u64;
+                Ok(MempoolTransaction {
+                    transaction,
+                    status,
+                    insertion_timestamp_usecs: insertion_timestamp_usecs.into(),
+                })
+            })
+            .collect::<Result<Vec<_>, BasicError>>()?;
+
+        let account_transactions = AccountMempoolTransactions {
+            sequence_number: sequence_number.into(),
+            transactions,
+            sequence_number_gaps: SequenceNumberGap::compute_gaps(
+                sequence_number,
+                &sequence_numbers,
+            ),
+        };
+        match accept_type {
+            AcceptType::Json => BasicResponse::try_from_json((
+                account_transactions,
+                ledger_info,
+                BasicResponseStatus::Ok,
+            )),
+            AcceptType::Bcs => BasicResponse::try_from_bcs((
+                account_transactions,
+                ledger_info,
+                BasicResponseStatus::Ok,
+            )),
+        }
+    }
+
     /// List all transactions for an account
     fn list_by_account(
         &self,
@@ -1532,6 +1844,12 @@ impl TransactionsApi {
     }
 }
 
+/// Creates the `error` event that is sent before a transaction stream is closed
+fn transaction_stream_error_event(error: AptosError) -> SseEvent {
+    let data = serde_json::to_string(&error).unwrap_or_else(|_| error.to_string());
+    SseEvent::message(data).event_type("error")
+}
+
 fn override_gas_parameters(
     signed_txn: &SignedTransaction,
     max_gas_amount: Option<u64>,
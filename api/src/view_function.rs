@@ -7,20 +7,25 @@ use crate::{
     context::{api_spawn_blocking, FunctionStats},
     failpoint::fail_point_poem,
     response::{
-        BadRequestError, BasicErrorWith404, BasicResponse, BasicResponseStatus, BasicResultWith404,
-        ForbiddenError, InternalError,
+        AptosErrorResponse, BadRequestError, BasicErrorWith404, BasicResponse, BasicResponseStatus,
+        BasicResultWith404, ForbiddenError, InternalError,
     },
+    state::StateApi,
     ApiTags, Context,
 };
 use anyhow::Context as anyhowContext;
 use aptos_api_types::{
-    AptosErrorCode, AsConverter, MoveValue, ViewFunction, ViewRequest, MAX_RECURSIVE_TYPES_ALLOWED,
-    U64,
+    Address, AptosError, AptosErrorCode, AsConverter, BatchViewRequest, BatchViewRequestItem,
+    BatchViewRequestItemBcs, BatchViewResult, BatchViewResultBcs, HexEncodedBytes, LedgerInfo,
+    MoveConverter, MoveValue, ResourceRequest, TableItemWithHandleRequest, VerifyInput,
+    VerifyInputWithRecursion, ViewFunction, ViewRequest, MAX_RECURSIVE_TYPES_ALLOWED, U64,
 };
 use aptos_bcs_utils::serialize_uleb128;
+use aptos_storage_interface::state_view::DbStateView;
 use aptos_vm::AptosVM;
+use bytes::Bytes;
 use itertools::Itertools;
-use move_core_types::language_storage::TypeTag;
+use move_core_types::language_storage::{StructTag, TypeTag};
 use poem_openapi::{param::Query, payload::Json, ApiRequest, OpenApi};
 use std::sync::Arc;
 
@@ -39,6 +44,15 @@ pub enum ViewFunctionRequest {
     Bcs(Bcs),
 }
 
+#[derive(ApiRequest, Debug)]
+pub enum BatchViewFunctionRequest {
+    #[oai(content_type = "application/json")]
+    Json(Json<BatchViewRequest>),
+
+    #[oai(content_type = "application/x.aptos.view_batch+bcs")]
+    Bcs(Bcs),
+}
+
 #[OpenApi]
 impl ViewFunctionApi {
     /// Execute view function of a module
@@ -71,6 +85,53 @@ impl ViewFunctionApi {
         api_spawn_blocking(move || view_request(context, accept_type, request, ledger_version))
             .await
     }
+
+    /// Execute a batch of view functions and reads
+    ///
+    /// Execute multiple view functions, and read multiple account resources and table items,
+    /// in a single request. All reads are executed against the same ledger version, so their
+    /// results are consistent with each other.
+    ///
+    /// The results are returned in the same order as the reads. If a read fails, its result
+    /// contains the error, and the other reads are unaffected. The view functions share a
+    /// combined gas budget, and once it has been used up, the remaining view functions fail.
+    ///
+    /// BCS encoded requests contain a list of `BatchViewRequestItemBcs`, and BCS encoded
+    /// responses contain a list of `BatchViewResultBcs` (see the Rust API types).
+    ///
+    /// The Aptos nodes prune account state history, via a configurable time window.
+    /// If the requested ledger version has been pruned, the server responds with a 410.
+    #[oai(
+        path = "/view/batch",
+        method = "post",
+        operation_id = "view_batch",
+        tag = "ApiTags::View"
+    )]
+    async fn view_batch(
+        &self,
+        accept_type: AcceptType,
+        /// The view functions and reads to execute
+        request: BatchViewFunctionRequest,
+        /// Ledger version to get state of accounts
+        ///
+        /// If not provided, it will be the latest version
+        ledger_version: Query<Option<U64>>,
+    ) -> BasicResultWith404<Vec<BatchViewResult>> {
+        fail_point_poem("endpoint_view_batch")?;
+        self.context
+            .check_api_output_enabled("View batch", &accept_type)?;
+
+        let context = self.context.clone();
+        api_spawn_blocking(move || {
+            view_batch_request(
+                context,
+                accept_type,
+                request,
+                ledger_version.0.map(|inner| inner.0),
+            )
+        })
+        .await
+    }
 }
 
 fn view_request(
@@ -208,3 +269,381 @@ fn view_request(
     );
     result.map(|r| r.with_gas_used(Some(output.gas_used)))
 }
+
+/// A single parsed read in a batch view request
+enum BatchRead {
+    ViewFunction(ViewFunction),
+    Resource {
+        address: Address,
+        tag: StructTag,
+    },
+    TableItem {
+        table_handle: Address,
+        raw_key: Vec<u8>,
+        /// The key, used for error messages
+        key: serde_json::Value,
+        value_type: TypeTag,
+    },
+}
+
+/// The (BCS encoded) output of a single read in a batch view request
+enum BatchReadOutput {
+    ViewFunction {
+        view_function: ViewFunction,
+        values: Vec<Vec<u8>>,
+        gas_used: u64,
+    },
+    Resource {
+        tag: StructTag,
+        bytes: Bytes,
+    },
+    TableItem {
+        value_type: TypeTag,
+        bytes: Bytes,
+    },
+}
+
+fn view_batch_request(
+    context: Arc<Context>,
+    accept_type: AcceptType,
+    request: BatchViewFunctionRequest,
+    ledger_version: Option<u64>,
+) -> BasicResultWith404<Vec<BatchViewResult>> {
+    // Retrieve the state of the chain, which is shared by all reads in the batch
+    let (ledger_info, ledger_version, state_view) = context.state_view(ledger_version)?;
+    let state_api = StateApi {
+        context: context.clone(),
+    };
+
+    // Parse the reads. A read that can't be parsed fails without failing the batch.
+    let reads: Vec<Result<BatchRead, AptosError>> = match request {
+        BatchViewFunctionRequest::Json(request) => {
+            verify_batch_size(&context, request.0.requests.len(), &ledger_info)?;
+            request
+                .0
+                .requests
+                .into_iter()
+                .map(|item| parse_batch_read(&state_api, &state_view, item, &ledger_info))
+                .collect()
+        },
+        BatchViewFunctionRequest::Bcs(data) => {
+            let items: Vec<BatchViewRequestItemBcs> =
+                bcs::from_bytes_with_limit(data.0.as_slice(), MAX_RECURSIVE_TYPES_ALLOWED as usize)
+                    .context("Failed to deserialize input into BatchViewRequestItemBcs")
+                    .map_err(|err| {
+                        BasicErrorWith404::bad_request_with_code(
+                            err,
+                            AptosErrorCode::InvalidInput,
+                            &ledger_info,
+                        )
+                    })?;
+            verify_batch_size(&context, items.len(), &ledger_info)?;
+            items.into_iter().map(parse_batch_read_bcs).collect()
+        },
+    };
+
+    // Execute the reads
+    let max_gas_view_function = context.node_config.api.max_gas_view_function;
+    let mut remaining_gas = context.node_config.api.max_gas_view_batch;
+    let mut total_gas_used = 0;
+    let outputs = reads
+        .into_iter()
+        .map(|read| match read? {
+            BatchRead::ViewFunction(view_function) => {
+                let max_gas = std::cmp::min(remaining_gas, max_gas_view_function);
+                if max_gas == 0 {
+                    return Err(AptosError::new_with_error_code(
+                        "The gas budget of the batch view request has been used up",
+                        AptosErrorCode::InvalidInput,
+                    ));
+                }
+                let (values, gas_used) =
+                    batch_view_function(&context, &state_view, &view_function, max_gas);
+                remaining_gas = remaining_gas.saturating_sub(gas_used);
+                total_gas_used += gas_used;
+                values.map(|values| BatchReadOutput::ViewFunction {
+                    view_function,
+                    values,
+                    gas_used,
+                })
+            },
+            BatchRead::Resource { address, tag } => state_api
+                .read_resource(&state_view, address, &tag, ledger_version, &ledger_info)
+                .map(|bytes| BatchReadOutput::Resource { tag, bytes })
+                .map_err(into_aptos_error),
+            BatchRead::TableItem {
+                table_handle,
+                raw_key,
+                key,
+                value_type,
+            } => state_api
+                .read_table_item(
+                    &state_view,
+                    table_handle,
+                    &raw_key,
+                    &key,
+                    ledger_version,
+                    &ledger_info,
+                )
+                .map(|bytes| BatchReadOutput::TableItem { value_type, bytes })
+                .map_err(into_aptos_error),
+        })
+        .collect::<Vec<_>>();
+
+    // Render the outputs
+    let response = match accept_type {
+        AcceptType::Json => {
+            let converter =
+                state_view.as_converter(context.db.clone(), context.indexer_reader.clone());
+            let results = outputs
+                .into_iter()
+                .map(|output| {
+                    output
+                        .and_then(|output| {
+                            batch_output_to_json(
+                                &state_api,
+                                &state_view,
+                                &converter,
+                                output,
+                                &ledger_info,
+                            )
+                        })
+                        .unwrap_or_else(BatchViewResult::error)
+                })
+                .collect::<Vec<_>>();
+            BasicResponse::try_from_json((results, &ledger_info, BasicResponseStatus::Ok))
+        },
+        AcceptType::Bcs => {
+            let results = outputs
+                .into_iter()
+                .map(|output| match output {
+                    Ok(BatchReadOutput::ViewFunction {
+                        values, gas_used, ..
+                    }) => BatchViewResultBcs::ViewFunction { values, gas_used },
+                    Ok(BatchReadOutput::Resource { bytes, .. }) => {
+                        BatchViewResultBcs::Resource(bytes.to_vec())
+                    },
+                    Ok(BatchReadOutput::TableItem { bytes, .. }) => {
+                        BatchViewResultBcs::TableItem(bytes.to_vec())
+                    },
+                    Err(error) => BatchViewResultBcs::Error(error),
+                })
+                .collect::<Vec<_>>();
+            BasicResponse::try_from_bcs((results, &ledger_info, BasicResponseStatus::Ok))
+        },
+    };
+    response.map(|r| r.with_gas_used(Some(total_gas_used)))
+}
+
+/// Verifies the number of reads in a batch view request is within the configured limit
+fn verify_batch_size(
+    context: &Context,
+    num_reads: usize,
+    ledger_info: &LedgerInfo,
+) -> Result<(), BasicErrorWith404> {
+    let max_view_batch_size = context.node_config.api.max_view_batch_size;
+    if num_reads > max_view_batch_size {
+        return Err(BasicErrorWith404::bad_request_with_code(
+            format!(
+                "Batch view request contains {} reads, but the maximum is {}",
+                num_reads, max_view_batch_size
+            ),
+            AptosErrorCode::InvalidInput,
+            ledger_info,
+        ));
+    }
+    Ok(())
+}
+
+/// Parses a single (JSON) read in a batch view request
+fn parse_batch_read(
+    state_api: &StateApi,
+    state_view: &DbStateView,
+    item: BatchViewRequestItem,
+    ledger_info: &LedgerInfo,
+) -> Result<BatchRead, AptosError> {
+    match item {
+        BatchViewRequestItem::ViewFunction(request) => state_view
+            .as_converter(
+                state_api.context.db.clone(),
+                state_api.context.indexer_reader.clone(),
+            )
+            .convert_view_function(request)
+            .map(BatchRead::ViewFunction)
+            .map_err(|err| AptosError::new_with_error_code(err, AptosErrorCode::InvalidInput)),
+        BatchViewRequestItem::Resource(ResourceRequest {
+            address,
+            resource_type,
+        }) => {
+            resource_type
+                .verify(0)
+                .context("'resource_type' invalid")
+                .map_err(|err| {
+                    AptosError::new_with_error_code(err, AptosErrorCode::InvalidInput)
+                })?;
+            let tag = StateApi::parse_resource_type(resource_type).map_err(into_aptos_error)?;
+            Ok(BatchRead::Resource { address, tag })
+        },
+        BatchViewRequestItem::TableItem(TableItemWithHandleRequest {
+            table_handle,
+            request,
+        }) => {
+            request
+                .verify()
+                .context("'table_item_request' invalid")
+                .map_err(|err| {
+                    AptosError::new_with_error_code(err, AptosErrorCode::InvalidInput)
+                })?;
+            let (key_type, value_type) =
+                StateApi::parse_table_item_types(&request).map_err(into_aptos_error)?;
+            let raw_key = state_api
+                .table_item_raw_key(state_view, &key_type, &request.key, ledger_info)
+                .map_err(into_aptos_error)?;
+            Ok(BatchRead::TableItem {
+                table_handle,
+                raw_key,
+                key: request.key,
+                value_type,
+            })
+        },
+    }
+}
+
+/// Parses a single (BCS) read in a batch view request
+fn parse_batch_read_bcs(item: BatchViewRequestItemBcs) -> Result<BatchRead, AptosError> {
+    Ok(match item {
+        BatchViewRequestItemBcs::ViewFunction(view_function) => {
+            BatchRead::ViewFunction(view_function)
+        },
+        BatchViewRequestItemBcs::Resource {
+            address,
+            resource_type,
+        } => BatchRead::Resource {
+            address: address.into(),
+            tag: resource_type,
+        },
+        BatchViewRequestItemBcs::TableItem {
+            table_handle,
+            key,
+            value_type,
+        } => BatchRead::TableItem {
+            table_handle: table_handle.into(),
+            key: serde_json::Value::String(HexEncodedBytes::from(key.clone()).to_string()),
+            raw_key: key,
+            value_type,
+        },
+    })
+}
+
+/// Converts the output of a single read in a batch view request into JSON
+fn batch_output_to_json(
+    state_api: &StateApi,
+    state_view: &DbStateView,
+    converter: &MoveConverter<DbStateView>,
+    output: BatchReadOutput,
+    ledger_info: &LedgerInfo,
+) -> Result<BatchViewResult, AptosError> {
+    Ok(match output {
+        BatchReadOutput::ViewFunction {
+            view_function,
+            values,
+            gas_used,
+        } => BatchViewResult {
+            values: Some(view_function_values_to_json(
+                converter,
+                &view_function,
+                values,
+            )?),
+            gas_used: Some(gas_used.into()),
+            ..Default::default()
+        },
+        BatchReadOutput::Resource { tag, bytes } => BatchViewResult {
+            resource: Some(
+                state_api
+                    .resource_to_json(state_view, &tag, &bytes, ledger_info)
+                    .map_err(into_aptos_error)?,
+            ),
+            ..Default::default()
+        },
+        BatchReadOutput::TableItem { value_type, bytes } => BatchViewResult {
+            table_item: Some(
+                state_api
+                    .table_item_to_json(state_view, &value_type, &bytes, ledger_info)
+                    .map_err(into_aptos_error)?,
+            ),
+            ..Default::default()
+        },
+    })
+}
+
+/// Executes a single view function in a batch. Returns the gas used, even if the
+/// execution failed.
+fn batch_view_function(
+    context: &Context,
+    state_view: &DbStateView,
+    view_function: &ViewFunction,
+    max_gas: u64,
+) -> (Result<Vec<Vec<u8>>, AptosError>, u64) {
+    // Reject the request if it's not allowed by the filter.
+    if !context.node_config.api.view_filter.allows(
+        view_function.module.address(),
+        view_function.module.name().as_str(),
+        view_function.function.as_str(),
+    ) {
+        return (
+            Err(AptosError::new_with_error_code(
+                format!(
+                    "Function {}::{} is not allowed",
+                    view_function.module, view_function.function
+                ),
+                AptosErrorCode::InvalidInput,
+            )),
+            0,
+        );
+    }
+
+    let output = AptosVM::execute_view_function(
+        state_view,
+        view_function.module.clone(),
+        view_function.function.clone(),
+        view_function.ty_args.clone(),
+        view_function.args.clone(),
+        max_gas,
+    );
+    context.view_function_stats().increment(
+        FunctionStats::function_to_key(&view_function.module, &view_function.function),
+        output.gas_used,
+    );
+
+    let values = output
+        .values
+        .map_err(|err| AptosError::new_with_error_code(err, AptosErrorCode::InvalidInput));
+    (values, output.gas_used)
+}
+
+/// Converts the (BCS encoded) return values of a view function into MoveValues
+fn view_function_values_to_json(
+    converter: &MoveConverter<DbStateView>,
+    view_function: &ViewFunction,
+    values: Vec<Vec<u8>>,
+) -> Result<Vec<MoveValue>, AptosError> {
+    let return_types = converter
+        .function_return_types(view_function)
+        .and_then(|tys| {
+            tys.into_iter()
+                .map(TypeTag::try_from)
+                .collect::<anyhow::Result<Vec<_>>>()
+        })
+        .map_err(|err| AptosError::new_with_error_code(err, AptosErrorCode::InternalError))?;
+    values
+        .into_iter()
+        .zip(return_types)
+        .map(|(v, ty)| converter.try_into_move_value(&ty, &v))
+        .collect::<anyhow::Result<Vec<_>>>()
+        .map_err(|err| AptosError::new_with_error_code(err, AptosErrorCode::InternalError))
+}
+
+/// Extracts the error from an error response, so it can be returned for a single read
+fn into_aptos_error(mut error: BasicErrorWith404) -> AptosError {
+    error.inner_mut().clone()
+}
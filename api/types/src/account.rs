@@ -2,10 +2,15 @@
 // Parts of the project are originally copyright © Meta Platforms, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{HexEncodedBytes, U64};
+use crate::{
+    move_types::verify_field_identifier, AccountResourcesCursor, HexEncodedBytes, MoveResource,
+    MoveStructTag, VerifyInput, VerifyInputWithRecursion, U64,
+};
+use anyhow::{bail, Context};
 use aptos_types::account_config::AccountResource;
 use poem_openapi::Object;
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 
 /// Account data
 ///
@@ -25,3 +30,271 @@ impl From<AccountResource> for AccountData {
         }
     }
 }
+
+/// A query for a subset of an account's resources, and of the fields within them
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Object)]
+pub struct AccountResourcesQuery {
+    /// Resources to return, with the fields to return from each of them
+    ///
+    /// If empty, all resources are returned in full. A resource matching several
+    /// selections is returned once, with the fields of all of them.
+    #[serde(default)]
+    #[oai(default)]
+    pub selections: Vec<ResourceSelection>,
+}
+
+impl AccountResourcesQuery {
+    /// Applies the query to a resource. Returns `None` if the resource isn't selected,
+    /// otherwise the resource with only the selected fields.
+    pub fn select(&self, resource: MoveResource) -> anyhow::Result<Option<MoveResource>> {
+        if self.selections.is_empty() {
+            return Ok(Some(resource));
+        }
+
+        let selections: Vec<_> = self
+            .selections
+            .iter()
+            .filter(|selection| selection.matches(&resource.typ))
+            .collect();
+        if selections.is_empty() {
+            return Ok(None);
+        }
+        if selections
+            .iter()
+            .any(|selection| selection.fields.is_none())
+        {
+            return Ok(Some(resource));
+        }
+
+        let data =
+            serde_json::to_value(&resource.data).context("Failed to serialize resource data")?;
+        let mut selected = Value::Object(Map::new());
+        for path in selections
+            .iter()
+            .flat_map(|selection| selection.fields.iter().flatten())
+        {
+            let path: Vec<_> = path.split('.').collect();
+            if let Some(value) = select_path(&data, &path) {
+                merge(&mut selected, value);
+            }
+        }
+        Ok(Some(MoveResource {
+            typ: resource.typ,
+            data: serde_json::from_value(selected)
+                .context("Failed to deserialize selected resource data")?,
+        }))
+    }
+}
+
+impl VerifyInput for AccountResourcesQuery {
+    fn verify(&self) -> anyhow::Result<()> {
+        for selection in &self.selections {
+            selection.resource_type.verify(0)?;
+            for path in selection.fields.iter().flatten() {
+                for field in path.split('.') {
+                    if field.is_empty() {
+                        bail!("Field path {} contains an empty field name", path);
+                    }
+                    verify_field_identifier(field)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Selects the resources of a type, and optionally only some of their fields
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Object)]
+pub struct ResourceSelection {
+    /// Type of the resources to select
+    ///
+    /// If the type has no generic type parameters, all instantiations of the type are
+    /// selected, e.g. `0x1::coin::CoinStore` selects every coin store.
+    pub resource_type: MoveStructTag,
+    /// Paths of the fields to return, as field names separated by `.`, e.g. `coin.value`
+    ///
+    /// Paths through a vector are applied to each of its elements. Fields that don't
+    /// exist are omitted. If not provided, all fields are returned.
+    pub fields: Option<Vec<String>>,
+}
+
+impl ResourceSelection {
+    /// Returns true iff the resource type is selected
+    pub fn matches(&self, resource_type: &MoveStructTag) -> bool {
+        self.resource_type.address == resource_type.address
+            && self.resource_type.module == resource_type.module
+            && self.resource_type.name == resource_type.name
+            && (self.resource_type.generic_type_params.is_empty()
+                || self.resource_type.generic_type_params == resource_type.generic_type_params)
+    }
+}
+
+/// A page of the resources selected by an [`AccountResourcesQuery`]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Object)]
+pub struct AccountResourcesQueryResult {
+    /// The selected resources, with only the selected fields
+    pub resources: Vec<MoveResource>,
+    /// Cursor for the next page, or `None` if this is the last page
+    ///
+    /// The cursor pins the ledger version of the first page, so all pages are read from
+    /// the same state. A page may contain fewer resources than the limit (or none) even
+    /// if there are more pages, if many of the resources read were not selected.
+    pub cursor: Option<AccountResourcesCursor>,
+}
+
+/// Returns the value at `path` within `value`, nested the same way as in `value`. Paths
+/// through an array are applied to each of its elements.
+fn select_path(value: &Value, path: &[&str]) -> Option<Value> {
+    let Some((field, rest)) = path.split_first() else {
+        return Some(value.clone());
+    };
+    match value {
+        Value::Object(fields) => {
+            let selected = select_path(fields.get(*field)?, rest)?;
+            Some(Value::Object(Map::from_iter([(
+                field.to_string(),
+                selected,
+            )])))
+        },
+        Value::Array(items) => Some(Value::Array(
+            items
+                .iter()
+                .map(|item| select_path(item, path).unwrap_or(Value::Null))
+                .collect(),
+        )),
+        _ => None,
+    }
+}
+
+/// Merges two selections from the same value
+fn merge(into: &mut Value, from: Value) {
+    match (into, from) {
+        (Value::Object(into), Value::Object(from)) => {
+            for (field, value) in from {
+                match into.get_mut(&field) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        into.insert(field, value);
+                    },
+                }
+            }
+        },
+        (Value::Array(into), Value::Array(from)) => {
+            for (existing, value) in into.iter_mut().zip(from) {
+                merge(existing, value);
+            }
+        },
+        (into, from) => {
+            if !from.is_null() {
+                *into = from;
+            }
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::str::FromStr;
+
+    fn resource(typ: &str, data: Value) -> MoveResource {
+        MoveResource {
+            typ: MoveStructTag::from_str(typ).unwrap(),
+            data: serde_json::from_value(data).unwrap(),
+        }
+    }
+
+    fn selection(typ: &str, fields: Option<Vec<&str>>) -> ResourceSelection {
+        ResourceSelection {
+            resource_type: MoveStructTag::from_str(typ).unwrap(),
+            fields: fields.map(|fields| fields.into_iter().map(String::from).collect()),
+        }
+    }
+
+    #[test]
+    fn test_select_resource_types() {
+        let coin_store = resource(
+            "0x1::coin::CoinStore<0x1::aptos_coin::AptosCoin>",
+            json!({"coin": {"value": "100"}, "frozen": false}),
+        );
+        let query = |selections| AccountResourcesQuery { selections };
+
+        // No selections select everything
+        assert_eq!(
+            query(vec![]).select(coin_store.clone()).unwrap(),
+            Some(coin_store.clone())
+        );
+        // A type without generic type parameters selects all instantiations
+        assert_eq!(
+            query(vec![selection("0x1::coin::CoinStore", None)])
+                .select(coin_store.clone())
+                .unwrap(),
+            Some(coin_store.clone())
+        );
+        assert_eq!(
+            query(vec![selection(
+                "0x1::coin::CoinStore<0x1::aptos_coin::AptosCoin>",
+                None
+            )])
+            .select(coin_store.clone())
+            .unwrap(),
+            Some(coin_store.clone())
+        );
+        assert_eq!(
+            query(vec![selection(
+                "0x1::coin::CoinStore<0x1::coin::Other>",
+                None
+            )])
+            .select(coin_store.clone())
+            .unwrap(),
+            None
+        );
+        assert_eq!(
+            query(vec![selection("0x1::account::Account", None)])
+                .select(coin_store)
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_select_fields() {
+        let resource = resource(
+            "0x1::test::Resource",
+            json!({
+                "coin": {"value": "100", "extra": "1"},
+                "frozen": false,
+                "items": [{"id": "1", "name": "a"}, {"id": "2"}],
+            }),
+        );
+        let query = AccountResourcesQuery {
+            selections: vec![
+                selection(
+                    "0x1::test::Resource",
+                    Some(vec!["coin.value", "items.name"]),
+                ),
+                selection("0x1::test::Resource", Some(vec!["frozen", "missing.field"])),
+            ],
+        };
+        let selected = query.select(resource).unwrap().unwrap();
+        assert_eq!(
+            serde_json::to_value(&selected.data).unwrap(),
+            json!({
+                "coin": {"value": "100"},
+                "frozen": false,
+                "items": [{"name": "a"}, null],
+            })
+        );
+    }
+
+    #[test]
+    fn test_verify_query() {
+        let query = |fields| AccountResourcesQuery {
+            selections: vec![selection("0x1::test::Resource", Some(fields))],
+        };
+        assert!(query(vec!["coin.value"]).verify().is_ok());
+        assert!(query(vec!["coin..value"]).verify().is_err());
+        assert!(query(vec![""]).verify().is_err());
+    }
+}
@@ -110,6 +110,16 @@ impl<'a, S: StateView> MoveConverter<'a, S> {
         false
     }
 
+    /// Returns whether the struct is declared with generic type parameters, or `None` if
+    /// the struct doesn't exist
+    pub fn is_generic_struct(&self, module_id: &ModuleId, name: &IdentStr) -> Option<bool> {
+        let module = self.inner.view_module(module_id).ok()??;
+        module.struct_defs.iter().find_map(|def| {
+            let handle = module.struct_handle_at(def.struct_handle);
+            (module.identifier_at(handle.name) == name).then(|| !handle.type_parameters.is_empty())
+        })
+    }
+
     pub fn find_resource(
         &self,
         state_view: &impl StateView,
@@ -20,8 +20,8 @@
 
 use crate::{
     move_types::{MoveAbility, MoveStructValue},
-    Address, EntryFunctionId, HashValue, HexEncodedBytes, IdentifierWrapper, MoveModuleId,
-    MoveStructTag, MoveType, StateKeyWrapper, U128, U256, U64,
+    AccountResourcesCursor, Address, EntryFunctionId, HashValue, HexEncodedBytes,
+    IdentifierWrapper, MoveModuleId, MoveStructTag, MoveType, StateKeyWrapper, U128, U256, U64,
 };
 use aptos_openapi::{impl_poem_parameter, impl_poem_type};
 use indoc::indoc;
@@ -242,6 +242,17 @@ impl_poem_type!(
     )
 );
 
+impl_poem_type!(
+    AccountResourcesCursor,
+    "string",
+    (
+        example = Some(serde_json::Value::String("00000000000000400000000000000000000000000000000000000000000000000000000000000001012f0000000000000000000000000000000000000000000000000000000000000000010d7374616b696e675f70726f7879".to_string())),
+        description = Some(indoc! {"
+          A cursor for paginating through an account's resources at a fixed ledger version, as a hex string.
+        "})
+    )
+);
+
 impl_poem_type!(
     U64,
     "string",
@@ -292,6 +303,7 @@ impl_poem_type!(
 );
 
 impl_poem_parameter!(
+    AccountResourcesCursor,
     Address,
     HashValue,
     IdentifierWrapper,
@@ -15,6 +15,7 @@ mod hash;
 mod headers;
 mod index;
 mod ledger_info;
+mod mempool;
 pub mod mime_types;
 mod move_types;
 mod state;
@@ -23,7 +24,9 @@ pub mod transaction;
 mod view;
 mod wrappers;
 
-pub use account::AccountData;
+pub use account::{
+    AccountData, AccountResourcesQuery, AccountResourcesQueryResult, ResourceSelection,
+};
 pub use address::Address;
 pub use block::{BcsBlock, Block};
 pub use bytecode::Bytecode;
@@ -33,6 +36,10 @@ pub use hash::HashValue;
 pub use headers::*;
 pub use index::{IndexResponse, IndexResponseBcs};
 pub use ledger_info::LedgerInfo;
+pub use mempool::{
+    AccountMempoolTransactions, MempoolFeeBucket, MempoolStats, MempoolTransaction,
+    MempoolTransactionStatus, SequenceNumberGap,
+};
 pub use move_types::{
     verify_field_identifier, verify_function_identifier, verify_module_identifier, EntryFunctionId,
     HexEncodedBytes, MoveAbility, MoveFunction, MoveFunctionGenericTypeParam,
@@ -41,7 +48,7 @@ pub use move_types::{
     ResourceGroup, MAX_RECURSIVE_TYPES_ALLOWED, U128, U256, U64,
 };
 use serde::{Deserialize, Deserializer};
-pub use state::RawStateValueRequest;
+pub use state::{RawStateValueRequest, StateValueWithProof};
 use std::str::FromStr;
 pub use table::{RawTableItemRequest, TableItemRequest};
 pub use transaction::{
@@ -57,8 +64,11 @@ pub use transaction::{
     UserTransactionRequest, VersionedEvent, WriteModule, WriteResource, WriteSet, WriteSetChange,
     WriteSetPayload, WriteTableItem,
 };
-pub use view::{ViewFunction, ViewRequest};
-pub use wrappers::{EventGuid, IdentifierWrapper, StateKeyWrapper};
+pub use view::{
+    BatchViewRequest, BatchViewRequestItem, BatchViewRequestItemBcs, BatchViewResult,
+    BatchViewResultBcs, ResourceRequest, TableItemWithHandleRequest, ViewFunction, ViewRequest,
+};
+pub use wrappers::{AccountResourcesCursor, EventGuid, IdentifierWrapper, StateKeyWrapper};
 
 pub fn deserialize_from_string<'de, D, T>(deserializer: D) -> Result<T, D::Error>
 where
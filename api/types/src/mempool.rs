@@ -0,0 +1,136 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{PendingTransaction, U64};
+use poem_openapi::{Enum, Object};
+use serde::{Deserialize, Serialize};
+
+/// The status of a transaction that is pending in mempool
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Enum)]
+#[serde(rename_all = "snake_case")]
+#[oai(rename_all = "snake_case")]
+pub enum MempoolTransactionStatus {
+    /// The transaction can be included in the next block
+    Ready,
+    /// The transaction is parked, and can't be included in a block until
+    /// the gaps before it in the account's sequence numbers are filled
+    Parked,
+}
+
+/// A transaction that is pending in mempool
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Object)]
+pub struct MempoolTransaction {
+    pub transaction: PendingTransaction,
+    pub status: MempoolTransactionStatus,
+    /// The time (in microseconds since the unix epoch) the transaction was inserted into mempool
+    pub insertion_timestamp_usecs: U64,
+}
+
+/// A gap in the sequence numbers of an account's pending transactions.
+/// Transactions after a gap can't be committed until it is filled.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Object)]
+pub struct SequenceNumberGap {
+    /// The first missing sequence number
+    pub start_sequence_number: U64,
+    /// The last missing sequence number (inclusive)
+    pub end_sequence_number: U64,
+    /// The number of pending transactions blocked by this gap
+    pub num_blocked_transactions: U64,
+    /// A human readable explanation of the gap
+    pub explanation: String,
+}
+
+impl SequenceNumberGap {
+    /// Computes the gaps in the given (sorted) sequence numbers of the pending
+    /// transactions, starting at the account's on-chain sequence number.
+    /// Sequence numbers below the on-chain sequence number are ignored.
+    pub fn compute_gaps(account_sequence_number: u64, sequence_numbers: &[u64]) -> Vec<Self> {
+        let mut gaps = vec![];
+        let mut expected_sequence_number = account_sequence_number;
+        for (index, sequence_number) in sequence_numbers.iter().enumerate() {
+            if *sequence_number > expected_sequence_number {
+                let start_sequence_number = expected_sequence_number;
+                let end_sequence_number = sequence_number - 1;
+                let num_blocked_transactions = (sequence_numbers.len() - index) as u64;
+                let explanation = format!(
+                    "Sequence numbers {} to {} are not in mempool. The {} pending transaction(s) \
+                    after this gap are parked until the missing transactions are submitted.",
+                    start_sequence_number, end_sequence_number, num_blocked_transactions
+                );
+                gaps.push(Self {
+                    start_sequence_number: start_sequence_number.into(),
+                    end_sequence_number: end_sequence_number.into(),
+                    num_blocked_transactions: num_blocked_transactions.into(),
+                    explanation,
+                });
+            }
+            expected_sequence_number =
+                expected_sequence_number.max(sequence_number.saturating_add(1));
+        }
+        gaps
+    }
+}
+
+/// The transactions pending in mempool for a single account
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Object)]
+pub struct AccountMempoolTransactions {
+    /// The on-chain sequence number of the account
+    pub sequence_number: U64,
+    /// The pending transactions, ordered by sequence number
+    pub transactions: Vec<MempoolTransaction>,
+    /// The gaps in the sequence numbers of the pending transactions
+    pub sequence_number_gaps: Vec<SequenceNumberGap>,
+}
+
+/// The stats of the transactions in a single mempool fee bucket
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Object)]
+pub struct MempoolFeeBucket {
+    /// The minimum gas unit price of the transactions in the bucket
+    pub min_gas_unit_price: U64,
+    pub num_transactions: U64,
+    pub num_parked_transactions: U64,
+}
+
+/// The stats of all transactions in mempool
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Object)]
+pub struct MempoolStats {
+    pub num_transactions: U64,
+    pub num_parked_transactions: U64,
+    /// The estimated size (in bytes) of all transactions in mempool
+    pub size_bytes: U64,
+    /// The maximum number of transactions allowed in mempool
+    pub capacity: U64,
+    /// The maximum number of bytes allowed in mempool
+    pub capacity_bytes: U64,
+    /// The transaction stats grouped by fee bucket (ordered by gas unit price)
+    pub fee_buckets: Vec<MempoolFeeBucket>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SequenceNumberGap;
+
+    #[test]
+    fn test_compute_sequence_number_gaps() {
+        // Verify there are no gaps for contiguous sequence numbers
+        assert!(SequenceNumberGap::compute_gaps(5, &[5, 6, 7]).is_empty());
+        assert!(SequenceNumberGap::compute_gaps(5, &[]).is_empty());
+
+        // Verify stale sequence numbers are ignored
+        assert!(SequenceNumberGap::compute_gaps(5, &[3, 4, 5]).is_empty());
+
+        // Verify the gaps are computed correctly
+        let gaps = SequenceNumberGap::compute_gaps(5, &[7, 8, 11]);
+        let gaps: Vec<_> = gaps
+            .iter()
+            .map(|gap| {
+                (
+                    gap.start_sequence_number.0,
+                    gap.end_sequence_number.0,
+                    gap.num_blocked_transactions.0,
+                )
+            })
+            .collect();
+        assert_eq!(gaps, vec![(5, 6, 3), (9, 10, 1)]);
+    }
+}
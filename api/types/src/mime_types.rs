@@ -5,6 +5,8 @@
 /// MIME type to submit BCS transactions
 pub const BCS_VIEW_FUNCTION: &str = "application/x.aptos.view_function+bcs";
 
+pub const BCS_VIEW_BATCH: &str = "application/x.aptos.view_batch+bcs";
+
 pub const BCS_SIGNED_TRANSACTION: &str = "application/x.aptos.signed_transaction+bcs";
 
 /// MIME type to submit JSON transactions and get JSON output
@@ -2,6 +2,15 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::HexEncodedBytes;
+use anyhow::{ensure, Context};
+use aptos_crypto::hash::CryptoHash;
+use aptos_types::{
+    proof::{SparseMerkleProof, TransactionInfoWithProof},
+    state_proof::StateProof,
+    state_store::{state_key::StateKey, state_value::StateValue},
+    transaction::Version,
+    trusted_state::{TrustedState, TrustedStateChange},
+};
 use poem_openapi::Object;
 use serde::{Deserialize, Serialize};
 
@@ -10,3 +19,76 @@ use serde::{Deserialize, Serialize};
 pub struct RawStateValueRequest {
     pub key: HexEncodedBytes,
 }
+
+/// A state value (or the proof of its absence) at a state snapshot version, along with
+/// everything required to verify it against a client's [`TrustedState`]
+///
+/// This is only served as BCS by the GetStateValueWithProof API.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct StateValueWithProof {
+    /// The version of the state snapshot the value was read at
+    pub version: Version,
+    pub state_key: StateKey,
+    /// The state value, or `None` if the key doesn't exist at `version`
+    pub state_value: Option<StateValue>,
+    /// Proves the state value against the state checkpoint hash of the transaction at `version`
+    pub sparse_merkle_proof: SparseMerkleProof,
+    /// Proves the transaction info at `version` against the latest ledger info
+    pub transaction_info_with_proof: TransactionInfoWithProof,
+    /// The signed latest ledger info, and the epoch changes since the client's known version
+    pub state_proof: StateProof,
+}
+
+impl StateValueWithProof {
+    /// Verifies the state value for `state_key` against the given trusted state:
+    ///
+    /// 1. The latest ledger info (and any epoch changes) are verified by ratcheting the
+    ///    trusted state.
+    /// 2. The transaction info at `version` is verified against the latest ledger info.
+    /// 3. The state value (or its absence) is verified against the state checkpoint hash
+    ///    of the transaction info.
+    ///
+    /// On success, the caller should persist the resulting trusted state change.
+    pub fn verify<'a>(
+        &'a self,
+        state_key: &StateKey,
+        trusted_state: &TrustedState,
+    ) -> anyhow::Result<TrustedStateChange<'a>> {
+        ensure!(
+            &self.state_key == state_key,
+            "State key mismatch. Expected: {:?}, got: {:?}",
+            state_key,
+            self.state_key
+        );
+
+        let latest_ledger_info = self.state_proof.latest_ledger_info();
+        let trusted_state_change = trusted_state
+            .verify_and_ratchet(&self.state_proof)
+            .context("Failed to verify the state proof")?;
+        match &trusted_state_change {
+            TrustedStateChange::Version { new_state }
+            | TrustedStateChange::Epoch { new_state, .. } => ensure!(
+                new_state.version() == latest_ledger_info.version(),
+                "The epoch change proof is incomplete, the latest ledger info can't be verified"
+            ),
+            TrustedStateChange::NoChange => {},
+        }
+
+        self.transaction_info_with_proof
+            .verify(latest_ledger_info, self.version)
+            .context("Failed to verify the transaction info")?;
+        let state_checkpoint_hash = self
+            .transaction_info_with_proof
+            .transaction_info()
+            .ensure_state_checkpoint_hash()?;
+        self.sparse_merkle_proof
+            .verify(
+                state_checkpoint_hash,
+                self.state_key.hash(),
+                self.state_value.as_ref(),
+            )
+            .context("Failed to verify the state value")?;
+
+        Ok(trusted_state_change)
+    }
+}
@@ -1,13 +1,17 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{EntryFunctionId, MoveType};
+use crate::{
+    Address, AptosError, EntryFunctionId, MoveResource, MoveStructTag, MoveType, MoveValue,
+    TableItemRequest, U64,
+};
 use aptos_types::serde_helper::vec_bytes;
 use move_core_types::{
+    account_address::AccountAddress,
     identifier::Identifier,
-    language_storage::{ModuleId, TypeTag},
+    language_storage::{ModuleId, StructTag, TypeTag},
 };
-use poem_openapi::Object;
+use poem_openapi::{Object, Union};
 use serde::{Deserialize, Serialize};
 
 /// View request for the Move View Function API
@@ -20,6 +24,107 @@ pub struct ViewRequest {
     pub arguments: Vec<serde_json::Value>,
 }
 
+/// Batch view request for the Move View Function API
+///
+/// All reads in the batch are executed against the same ledger version.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Object)]
+pub struct BatchViewRequest {
+    /// The reads to execute
+    pub requests: Vec<BatchViewRequestItem>,
+}
+
+/// A single read in a batch view request
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Union)]
+#[serde(tag = "type", rename_all = "snake_case")]
+#[oai(one_of, discriminator_name = "type", rename_all = "snake_case")]
+pub enum BatchViewRequestItem {
+    ViewFunction(ViewRequest),
+    Resource(ResourceRequest),
+    TableItem(TableItemWithHandleRequest),
+}
+
+/// Request for an account resource in a batch view request
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Object)]
+pub struct ResourceRequest {
+    pub address: Address,
+    /// Name of struct to retrieve e.g. `0x1::account::Account`
+    pub resource_type: MoveStructTag,
+}
+
+/// Request for a table item in a batch view request
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Object)]
+pub struct TableItemWithHandleRequest {
+    /// Table handle hex encoded 32-byte string
+    pub table_handle: Address,
+    #[serde(flatten)]
+    #[oai(flatten)]
+    pub request: TableItemRequest,
+}
+
+/// The result of a single read in a batch view request
+///
+/// Exactly one of `values`, `resource`, `table_item` and `error` is set, depending on the
+/// type of the read and whether it succeeded.
+#[derive(Clone, Debug, Default, Serialize, Object)]
+pub struct BatchViewResult {
+    /// The return values of the view function
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub values: Option<Vec<MoveValue>>,
+    /// The account resource
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resource: Option<MoveResource>,
+    /// The value of the table item
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub table_item: Option<MoveValue>,
+    /// The reason the read failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<AptosError>,
+    /// The gas used by the view function (if it was executed)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gas_used: Option<U64>,
+}
+
+impl BatchViewResult {
+    pub fn error(error: AptosError) -> Self {
+        Self {
+            error: Some(error),
+            ..Default::default()
+        }
+    }
+}
+
+/// A single read in a BCS encoded batch view request
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BatchViewRequestItemBcs {
+    ViewFunction(ViewFunction),
+    Resource {
+        address: AccountAddress,
+        resource_type: StructTag,
+    },
+    /// The key is BCS encoded. The value type is only used to render JSON responses.
+    TableItem {
+        table_handle: AccountAddress,
+        key: Vec<u8>,
+        value_type: TypeTag,
+    },
+}
+
+/// The BCS encoded result of a single read in a batch view request
+///
+/// The return values, resources and table items are BCS encoded, exactly as they are
+/// returned by the individual BCS endpoints.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum BatchViewResultBcs {
+    ViewFunction {
+        #[serde(with = "vec_bytes")]
+        values: Vec<Vec<u8>>,
+        gas_used: u64,
+    },
+    Resource(Vec<u8>),
+    TableItem(Vec<u8>),
+    Error(AptosError),
+}
+
 #[derive(Clone, Debug, Hash, Eq, PartialEq, Serialize, Deserialize)]
 pub struct ViewFunction {
     pub module: ModuleId,
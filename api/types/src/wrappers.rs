@@ -146,3 +146,55 @@ impl From<StateKeyWrapper> for StateKey {
         value.0
     }
 }
+
+/// A cursor for paginating through an account's resources. It pins the ledger version
+/// of the first page, so all pages are read from the same state.
+///
+/// It is serialized as a hex string, and can't be derived manually client-side.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AccountResourcesCursor {
+    /// Ledger version the resources are read at
+    pub ledger_version: u64,
+    /// State key of the first resource of the next page
+    pub start: StateKey,
+}
+
+impl fmt::Display for AccountResourcesCursor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:016x}{}",
+            self.ledger_version,
+            hex::encode(self.start.encoded())
+        )
+    }
+}
+
+impl FromStr for AccountResourcesCursor {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self, anyhow::Error> {
+        if s.len() < 16 || !s.is_char_boundary(16) {
+            bail!("Invalid account resources cursor: {}", s);
+        }
+        let (ledger_version, start) = s.split_at(16);
+        Ok(Self {
+            ledger_version: u64::from_str_radix(ledger_version, 16)
+                .context("Failed to decode ledger version of cursor")?,
+            start: StateKeyWrapper::from_str(start)?.0,
+        })
+    }
+}
+
+impl Serialize for AccountResourcesCursor {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for AccountResourcesCursor {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = <String>::deserialize(deserializer)?;
+        Self::from_str(&s).map_err(serde::de::Error::custom)
+    }
+}
@@ -3,7 +3,7 @@
 
 use crate::{network::ApplicationNetworkInterfaces, services};
 use aptos_admin_service::AdminService;
-use aptos_config::config::NodeConfig;
+use aptos_config::config::{transaction_filter_type::ReloadableFilter, NodeConfig};
 use aptos_consensus::{
     consensus_observer::{
         network_message::ConsensusObserverMessage, publisher::ConsensusPublisher,
@@ -40,6 +40,7 @@ pub fn create_consensus_observer_runtime(
     consensus_observer_reconfig_subscription: Option<
         ReconfigNotificationListener<DbBackedOnChainConfig>,
     >,
+    execution_filter: Arc<ReloadableFilter>,
 ) -> Option<Runtime> {
     if node_config
         .consensus_observer
@@ -59,6 +60,7 @@ pub fn create_consensus_observer_runtime(
             consensus_to_mempool_sender,
             db_rw,
             consensus_observer_reconfig_subscription,
+            execution_filter,
         );
         Some(consensus_observer_runtime)
     } else {
@@ -120,6 +122,7 @@ pub fn create_consensus_runtime(
             consensus_to_mempool_sender.clone(),
             vtxn_pool,
             consensus_publisher.clone(),
+            admin_service.execution_filter(),
         );
         admin_service.set_consensus_dbs(consensus_db, quorum_store_db);
 
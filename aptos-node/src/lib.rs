@@ -664,6 +664,9 @@ pub fn setup_environment_and_start_node(
         db_rw.reader.clone(),
     );
 
+    // Create the store for the tracked states (if the node only syncs the tracked states)
+    let partial_state_store = state_sync::create_partial_state_store(&node_config);
+
     // Start state sync and get the notification endpoints for mempool and consensus
     let (aptos_data_client, state_sync_runtimes, mempool_listener, consensus_notifier) =
         state_sync::start_state_sync_and_get_notification_handles(
@@ -672,6 +675,7 @@ pub fn setup_environment_and_start_node(
             genesis_waypoint,
             event_subscription_service,
             db_rw.clone(),
+            partial_state_store.clone(),
         )?;
 
     // Start the node inspection service
@@ -689,7 +693,14 @@ pub fn setup_environment_and_start_node(
         indexer_runtime,
         indexer_grpc_runtime,
         internal_indexer_db_runtime,
-    ) = services::bootstrap_api_and_indexer(&node_config, db_rw.clone(), chain_id, indexer_db_opt)?;
+    ) = services::bootstrap_api_and_indexer(
+        &node_config,
+        db_rw.clone(),
+        chain_id,
+        indexer_db_opt,
+        admin_service.simulation_filter(),
+        partial_state_store,
+    )?;
 
     // Create mempool and get the consensus to mempool sender
     let (mempool_runtime, consensus_to_mempool_sender) =
@@ -757,6 +768,7 @@ pub fn setup_environment_and_start_node(
         consensus_to_mempool_sender,
         db_rw,
         consensus_observer_reconfig_subscription,
+        admin_service.execution_filter(),
     );
 
     Ok(AptosHandle {
@@ -4,7 +4,7 @@
 use crate::{bootstrap_api, indexer, mpsc::Receiver, network::ApplicationNetworkInterfaces};
 use aptos_admin_service::AdminService;
 use aptos_build_info::build_information;
-use aptos_config::config::NodeConfig;
+use aptos_config::config::{transaction_filter_type::ReloadableFilter, NodeConfig};
 use aptos_consensus::{
     consensus_observer::publisher::ConsensusPublisher, network_interface::ConsensusMsg,
     persistent_liveness_storage::StorageWriteProxy, quorum_store::quorum_store_db::QuorumStoreDB,
@@ -27,6 +27,7 @@ use aptos_peer_monitoring_service_server::{
     PeerMonitoringServiceServer,
 };
 use aptos_peer_monitoring_service_types::PeerMonitoringServiceMessage;
+use aptos_state_sync_driver::partial_state_storage::{PartialStateDbReader, PartialStateStore};
 use aptos_storage_interface::{DbReader, DbReaderWriter};
 use aptos_time_service::TimeService;
 use aptos_types::{chain_id::ChainId, indexer::indexer_db_reader::IndexerReader};
@@ -45,6 +46,8 @@ pub fn bootstrap_api_and_indexer(
     db_rw: DbReaderWriter,
     chain_id: ChainId,
     internal_indexer_db: Option<InternalIndexerDB>,
+    simulation_filter: Arc<ReloadableFilter>,
+    partial_state_store: Option<PartialStateStore>,
 ) -> anyhow::Result<(
     Receiver<MempoolClientRequest>,
     Option<Runtime>,
@@ -81,13 +84,23 @@ pub fn bootstrap_api_and_indexer(
         trait_object
     });
 
+    // Serve the tracked states to the API (if the node only syncs the tracked states)
+    let api_db_reader: Arc<dyn DbReader> = match partial_state_store {
+        Some(partial_state_store) => Arc::new(PartialStateDbReader::new(
+            db_rw.reader.clone(),
+            partial_state_store,
+        )),
+        None => db_rw.reader.clone(),
+    };
+
     let api_runtime = if node_config.api.enabled {
         Some(bootstrap_api(
             node_config,
             chain_id,
-            db_rw.reader.clone(),
+            api_db_reader,
             mempool_client_sender.clone(),
             indexer_reader.clone(),
+            simulation_filter,
         )?)
     } else {
         None
@@ -130,6 +143,7 @@ pub fn start_consensus_runtime(
     consensus_to_mempool_sender: Sender<QuorumStoreRequest>,
     vtxn_pool: VTxnPoolState,
     consensus_publisher: Option<Arc<ConsensusPublisher>>,
+    execution_filter: Arc<ReloadableFilter>,
 ) -> (Runtime, Arc<StorageWriteProxy>, Arc<QuorumStoreDB>) {
     let instant = Instant::now();
 
@@ -146,6 +160,7 @@ pub fn start_consensus_runtime(
         reconfig_subscription,
         vtxn_pool,
         consensus_publisher,
+        execution_filter,
     );
     debug!("Consensus started in {} ms", instant.elapsed().as_millis());
 
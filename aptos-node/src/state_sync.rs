@@ -23,6 +23,7 @@ use aptos_network::application::{
 use aptos_state_sync_driver::{
     driver_factory::{DriverFactory, StateSyncRuntimes},
     metadata_storage::PersistentMetadataStorage,
+    partial_state_storage::PartialStateStore,
 };
 use aptos_storage_interface::{DbReader, DbReaderWriter};
 use aptos_storage_service_client::StorageServiceClient;
@@ -124,6 +125,17 @@ pub fn create_event_subscription_service(
     )
 }
 
+/// Creates the store for the tracked states, iff the node only syncs the
+/// tracked states (i.e., the bootstrapping mode is `DownloadFilteredStates`).
+pub fn create_partial_state_store(node_config: &NodeConfig) -> Option<PartialStateStore> {
+    let bootstrapping_mode = node_config.state_sync.state_sync_driver.bootstrapping_mode;
+    if bootstrapping_mode.is_partial_state_sync() {
+        Some(PartialStateStore::new(node_config.storage.dir()))
+    } else {
+        None
+    }
+}
+
 /// Sets up all state sync runtimes and return the notification endpoints
 pub fn start_state_sync_and_get_notification_handles(
     node_config: &NodeConfig,
@@ -131,6 +143,7 @@ pub fn start_state_sync_and_get_notification_handles(
     waypoint: Waypoint,
     event_subscription_service: EventSubscriptionService,
     db_rw: DbReaderWriter,
+    partial_state_store: Option<PartialStateStore>,
 ) -> anyhow::Result<(
     AptosDataClient,
     StateSyncRuntimes,
@@ -194,6 +207,7 @@ pub fn start_state_sync_and_get_notification_handles(
         event_subscription_service,
         aptos_data_client.clone(),
         streaming_service_client,
+        partial_state_store,
         TimeService::real(),
     );
 
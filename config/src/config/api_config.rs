@@ -2,7 +2,7 @@
 // Parts of the project are originally copyright © Meta Platforms, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use super::transaction_filter_type::{Filter, Matcher};
+use super::transaction_filter_type::Filter;
 use crate::{
     config::{
         config_sanitizer::ConfigSanitizer, gas_estimation_config::GasEstimationConfig,
@@ -65,6 +65,12 @@ pub struct ApiConfig {
     ///
     /// This limits the execution length of a view function to the given gas used.
     pub max_gas_view_function: u64,
+    /// Maximum number of reads (view functions, resources and table items) in a batch view request
+    pub max_view_batch_size: usize,
+    /// Maximum gas unit limit for all view functions in a batch view request
+    ///
+    /// Each view function is still limited by `max_gas_view_function`.
+    pub max_gas_view_batch: u64,
     /// Optional: Maximum number of worker threads for the API.
     ///
     /// If not set, `runtime_worker_multiplier` will multiply times the number of CPU cores on the machine
@@ -89,6 +95,10 @@ pub struct ApiConfig {
     pub wait_by_hash_poll_interval_ms: u64,
     /// The number of active wait_by_hash requests that can be active at any given time.
     pub wait_by_hash_max_active_connections: usize,
+    /// The interval at which transaction streams will poll the storage for new transactions.
+    pub transaction_stream_poll_interval_ms: u64,
+    /// The number of transaction streams that can be active at any given time.
+    pub transaction_stream_max_active_connections: usize,
 }
 
 const DEFAULT_ADDRESS: &str = "127.0.0.1";
@@ -99,6 +109,8 @@ pub const DEFAULT_MAX_PAGE_SIZE: u16 = 100;
 const DEFAULT_MAX_ACCOUNT_RESOURCES_PAGE_SIZE: u16 = 9999;
 const DEFAULT_MAX_ACCOUNT_MODULES_PAGE_SIZE: u16 = 9999;
 const DEFAULT_MAX_VIEW_GAS: u64 = 2_000_000; // We keep this value the same as the max number of gas allowed for one single transaction defined in aptos-gas.
+const DEFAULT_MAX_VIEW_BATCH_SIZE: usize = 100;
+const DEFAULT_MAX_VIEW_BATCH_GAS: u64 = 5 * DEFAULT_MAX_VIEW_GAS;
 
 fn default_enabled() -> bool {
     true
@@ -131,6 +143,8 @@ impl Default for ApiConfig {
             max_account_resources_page_size: DEFAULT_MAX_ACCOUNT_RESOURCES_PAGE_SIZE,
             max_account_modules_page_size: DEFAULT_MAX_ACCOUNT_MODULES_PAGE_SIZE,
             max_gas_view_function: DEFAULT_MAX_VIEW_GAS,
+            max_view_batch_size: DEFAULT_MAX_VIEW_BATCH_SIZE,
+            max_gas_view_batch: DEFAULT_MAX_VIEW_BATCH_GAS,
             max_runtime_workers: None,
             runtime_worker_multiplier: 2,
             gas_estimation: GasEstimationConfig::default(),
@@ -141,6 +155,8 @@ impl Default for ApiConfig {
             wait_by_hash_timeout_ms: 1_000,
             wait_by_hash_poll_interval_ms: 20,
             wait_by_hash_max_active_connections: 100,
+            transaction_stream_poll_interval_ms: 100,
+            transaction_stream_max_active_connections: 100,
         }
     }
 }
@@ -190,9 +206,17 @@ impl ConfigSanitizer for ApiConfig {
             ));
         }
 
+        // Verify that transaction streams don't poll the storage in a busy loop
+        if api_config.transaction_stream_poll_interval_ms == 0 {
+            return Err(Error::ConfigSanitizerFailed(
+                sanitizer_name,
+                "transaction_stream_poll_interval_ms must be greater than 0!".into(),
+            ));
+        }
+
         // We don't support Block ID based simulation filters.
         for rule in api_config.simulation_filter.rules() {
-            if let Matcher::BlockId(_) = rule.matcher() {
+            if rule.matcher().contains_block_id() {
                 return Err(Error::ConfigSanitizerFailed(
                     sanitizer_name,
                     "Block ID based simulation filters are not supported!".into(),
@@ -295,6 +319,26 @@ mod tests {
         ApiConfig::sanitize(&node_config, NodeType::Validator, None).unwrap();
     }
 
+    #[test]
+    fn test_sanitize_invalid_transaction_stream_poll_interval() {
+        // Create a node config with a zero transaction stream poll interval
+        let node_config = NodeConfig {
+            api: ApiConfig {
+                enabled: true,
+                transaction_stream_poll_interval_ms: 0,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        // Sanitize the config and verify that it fails because
+        // the poll interval is invalid.
+        let error =
+            ApiConfig::sanitize(&node_config, NodeType::Validator, Some(ChainId::mainnet()))
+                .unwrap_err();
+        assert!(matches!(error, Error::ConfigSanitizerFailed(_, _)));
+    }
+
     #[test]
     fn test_sanitize_invalid_workers() {
         // Create a node config with failpoints enabled
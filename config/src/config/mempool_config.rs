@@ -10,6 +10,7 @@ use aptos_global_constants::DEFAULT_BUCKETS;
 use aptos_types::chain_id::ChainId;
 use serde::{Deserialize, Serialize};
 use serde_yaml::Value;
+use std::path::PathBuf;
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
 #[serde(default, deny_unknown_fields)]
@@ -100,6 +101,16 @@ pub struct MempoolConfig {
     /// up to 10 minutes (shared_mempool_priority_update_interval_secs) to enable the load balancing. If this flag is enabled,
     /// then the PFNs will always do load balancing irrespective of the load.
     pub enable_max_load_balancing_at_any_load: bool,
+    /// Whether or not to persist accepted transactions to an on-disk journal. The journal
+    /// is replayed (and the transactions revalidated) when the node restarts.
+    pub enable_journal: bool,
+    /// Interval to compact the journal (i.e., drop committed and expired transactions).
+    pub journal_compaction_interval_secs: u64,
+    /// Maximum number of bytes allowed in the journal. Accepted transactions that don't fit
+    /// are not persisted until the next compaction frees up space.
+    pub journal_max_size_bytes: u64,
+    /// The path of the journal file. Relative paths are resolved against the storage directory.
+    pub journal_path: PathBuf,
 }
 
 impl Default for MempoolConfig {
@@ -164,17 +175,40 @@ impl Default for MempoolConfig {
                 },
             ],
             enable_max_load_balancing_at_any_load: false,
+            enable_journal: false,
+            journal_compaction_interval_secs: 60,
+            journal_max_size_bytes: 256 * 1024 * 1024, // 256 MiB
+            journal_path: PathBuf::from("mempool_journal.bin"),
         }
     }
 }
 
 impl ConfigSanitizer for MempoolConfig {
     fn sanitize(
-        _node_config: &NodeConfig,
+        node_config: &NodeConfig,
         _node_type: NodeType,
         _chain_id: Option<ChainId>,
     ) -> Result<(), Error> {
-        Ok(()) // TODO: add reasonable verifications
+        let sanitizer_name = Self::get_sanitizer_name();
+        let mempool_config = &node_config.mempool;
+
+        // Verify that the journal is bounded and compacted (if enabled)
+        if mempool_config.enable_journal {
+            if mempool_config.journal_max_size_bytes == 0 {
+                return Err(Error::ConfigSanitizerFailed(
+                    sanitizer_name,
+                    "The mempool journal size limit must be greater than 0!".to_string(),
+                ));
+            }
+            if mempool_config.journal_compaction_interval_secs == 0 {
+                return Err(Error::ConfigSanitizerFailed(
+                    sanitizer_name,
+                    "The mempool journal compaction interval must be greater than 0!".to_string(),
+                ));
+            }
+        }
+
+        Ok(()) // TODO: add more reasonable verifications
     }
 }
 
@@ -340,4 +374,47 @@ mod tests {
             local_max_broadcasts_per_peer
         );
     }
+
+    #[test]
+    fn test_sanitize_journal_config() {
+        // Create a node config with an unbounded journal
+        let node_config = NodeConfig {
+            mempool: MempoolConfig {
+                enable_journal: true,
+                journal_max_size_bytes: 0,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        // Verify that the config fails sanitization
+        let error =
+            MempoolConfig::sanitize(&node_config, NodeType::PublicFullnode, None).unwrap_err();
+        assert!(matches!(error, Error::ConfigSanitizerFailed(_, _)));
+
+        // Create a node config with a journal that is never compacted
+        let node_config = NodeConfig {
+            mempool: MempoolConfig {
+                enable_journal: true,
+                journal_compaction_interval_secs: 0,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        // Verify that the config fails sanitization
+        let error =
+            MempoolConfig::sanitize(&node_config, NodeType::PublicFullnode, None).unwrap_err();
+        assert!(matches!(error, Error::ConfigSanitizerFailed(_, _)));
+
+        // Verify that the default journal config passes sanitization
+        let node_config = NodeConfig {
+            mempool: MempoolConfig {
+                enable_journal: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        MempoolConfig::sanitize(&node_config, NodeType::PublicFullnode, None).unwrap();
+    }
 }
@@ -11,7 +11,8 @@ use crate::{
         persistable_config::PersistableConfig, utils::RootPath, AdminServiceConfig, ApiConfig,
         BaseConfig, ConsensusConfig, Error, ExecutionConfig, IndexerConfig, IndexerGrpcConfig,
         InspectionServiceConfig, LoggerConfig, MempoolConfig, NetworkConfig,
-        PeerMonitoringServiceConfig, SafetyRulesTestConfig, StateSyncConfig, StorageConfig,
+        PartialStateSyncConfig, PeerMonitoringServiceConfig, SafetyRulesTestConfig,
+        StateSyncBackupConfig, StateSyncConfig, StorageConfig,
     },
     network_id::NetworkId,
 };
@@ -81,6 +82,10 @@ pub struct NodeConfig {
     #[serde(default)]
     pub state_sync: StateSyncConfig,
     #[serde(default)]
+    pub state_sync_backup: StateSyncBackupConfig,
+    #[serde(default)]
+    pub state_sync_partial: PartialStateSyncConfig,
+    #[serde(default)]
     pub storage: StorageConfig,
     #[serde(default)]
     pub validator_network: Option<NetworkConfig>,
@@ -3,7 +3,10 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::config::Error;
-use aptos_secure_storage::{InMemoryStorage, Namespaced, OnDiskStorage, Storage, VaultStorage};
+use aptos_secure_storage::{
+    EncryptedOnDiskStorage, InMemoryStorage, Namespaced, OnDiskStorage, Pkcs11Storage, Storage,
+    VaultStorage,
+};
 use serde::{Deserialize, Serialize};
 use std::{
     fs::File,
@@ -20,15 +23,20 @@ pub enum SecureBackend {
     InMemoryStorage,
     Vault(VaultConfig),
     OnDiskStorage(OnDiskStorageConfig),
+    EncryptedOnDiskStorage(EncryptedOnDiskStorageConfig),
+    Pkcs11(Pkcs11Config),
 }
 
 impl SecureBackend {
     pub fn namespace(&self) -> Option<&str> {
         match self {
             SecureBackend::Vault(VaultConfig { namespace, .. })
-            | SecureBackend::OnDiskStorage(OnDiskStorageConfig { namespace, .. }) => {
-                namespace.as_deref()
-            },
+            | SecureBackend::OnDiskStorage(OnDiskStorageConfig { namespace, .. })
+            | SecureBackend::EncryptedOnDiskStorage(EncryptedOnDiskStorageConfig {
+                namespace,
+                ..
+            })
+            | SecureBackend::Pkcs11(Pkcs11Config { namespace, .. }) => namespace.as_deref(),
             SecureBackend::InMemoryStorage => None,
         }
     }
@@ -36,7 +44,12 @@ impl SecureBackend {
     pub fn clear_namespace(&mut self) {
         match self {
             SecureBackend::Vault(VaultConfig { namespace, .. })
-            | SecureBackend::OnDiskStorage(OnDiskStorageConfig { namespace, .. }) => {
+            | SecureBackend::OnDiskStorage(OnDiskStorageConfig { namespace, .. })
+            | SecureBackend::EncryptedOnDiskStorage(EncryptedOnDiskStorageConfig {
+                namespace,
+                ..
+            })
+            | SecureBackend::Pkcs11(Pkcs11Config { namespace, .. }) => {
                 *namespace = None;
             },
             SecureBackend::InMemoryStorage => {},
@@ -47,6 +60,16 @@ impl SecureBackend {
     pub fn is_in_memory(&self) -> bool {
         matches!(self, SecureBackend::InMemoryStorage)
     }
+
+    /// Sets the data directory for backends that are stored on disk
+    pub fn set_data_dir(&mut self, data_dir: PathBuf) {
+        match self {
+            SecureBackend::OnDiskStorage(backend) => backend.set_data_dir(data_dir),
+            SecureBackend::EncryptedOnDiskStorage(backend) => backend.set_data_dir(data_dir),
+            SecureBackend::InMemoryStorage | SecureBackend::Vault(_) | SecureBackend::Pkcs11(_) => {
+            },
+        }
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
@@ -97,6 +120,90 @@ pub struct OnDiskStorageConfig {
     data_dir: PathBuf,
 }
 
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct EncryptedOnDiskStorageConfig {
+    // Required path for the encrypted on disk storage
+    pub path: PathBuf,
+    /// A namespace is an optional portion of the path to a key stored within the storage. For
+    /// example, a key, S, without a namespace would be available in S, with a namespace, N, it
+    /// would be in N/S.
+    pub namespace: Option<String>,
+    /// The passphrase from which the encryption key is derived
+    pub passphrase: Passphrase,
+    #[serde(skip)]
+    data_dir: PathBuf,
+}
+
+impl EncryptedOnDiskStorageConfig {
+    pub fn new(path: PathBuf, passphrase: Passphrase) -> Self {
+        Self {
+            path,
+            namespace: None,
+            passphrase,
+            data_dir: PathBuf::from("/opt/aptos/data"),
+        }
+    }
+
+    pub fn path(&self) -> PathBuf {
+        if self.path.is_relative() {
+            self.data_dir.join(&self.path)
+        } else {
+            self.path.clone()
+        }
+    }
+
+    pub fn set_data_dir(&mut self, data_dir: PathBuf) {
+        self.data_dir = data_dir;
+    }
+}
+
+/// Passphrases can either be stored on disk or provided via an environment variable.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Passphrase {
+    /// This is an absolute path and not relative to data_dir
+    FromDisk(PathBuf),
+    /// The name of the environment variable holding the passphrase
+    FromEnv(String),
+}
+
+impl Passphrase {
+    /// Reads the passphrase. Trailing newlines are ignored, as passphrase files often end with one.
+    pub fn read_passphrase(&self) -> Result<String, Error> {
+        let passphrase = match self {
+            Passphrase::FromDisk(path) => read_file(path)?,
+            Passphrase::FromEnv(name) => std::env::var(name).map_err(|_| {
+                Error::InvariantViolation(format!(
+                    "The passphrase environment variable is not set: {}",
+                    name
+                ))
+            })?,
+        };
+        Ok(passphrase.trim_end_matches(['\r', '\n']).to_string())
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Pkcs11Config {
+    /// The full path to the PKCS#11 library of the HSM (e.g., libsofthsm2.so).
+    pub library_path: PathBuf,
+    /// The label of the token (within the HSM) that holds the keys.
+    pub token_label: String,
+    /// The user PIN for logging into the token.
+    pub pin: Token,
+    /// A namespace is an optional portion of the label of a key stored within the token. For
+    /// example, a key, S, without a namespace would be available in S, with a namespace, N, it
+    /// would be in N/S.
+    pub namespace: Option<String>,
+    /// By default, Ed25519 keys are generated inside the token and can never be exported. Set this
+    /// to generate keys that are wrapped by the token instead, which is required for keys that must
+    /// be exported (e.g., network identity keys).
+    #[serde(default)]
+    pub extractable_keys: bool,
+}
+
 /// Tokens can either be directly within this config or stored somewhere on disk.
 #[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
 #[serde(rename_all = "snake_case")]
@@ -172,6 +279,37 @@ impl From<&SecureBackend> for Storage {
                     storage
                 }
             },
+            SecureBackend::EncryptedOnDiskStorage(config) => {
+                let passphrase = config
+                    .passphrase
+                    .read_passphrase()
+                    .expect("Unable to read passphrase");
+                let storage = Storage::from(
+                    EncryptedOnDiskStorage::new(config.path(), passphrase.as_bytes())
+                        .expect("Unable to open encrypted on disk storage"),
+                );
+                if let Some(namespace) = &config.namespace {
+                    Storage::from(Namespaced::new(namespace, Box::new(storage)))
+                } else {
+                    storage
+                }
+            },
+            SecureBackend::Pkcs11(config) => {
+                let storage = Storage::from(
+                    Pkcs11Storage::new(
+                        &config.library_path,
+                        &config.token_label,
+                        config.pin.read_token().expect("Unable to read PIN"),
+                        config.extractable_keys,
+                    )
+                    .expect("Unable to open PKCS#11 storage"),
+                );
+                if let Some(namespace) = &config.namespace {
+                    Storage::from(Namespaced::new(namespace, Box::new(storage)))
+                } else {
+                    storage
+                }
+            },
             SecureBackend::Vault(config) => {
                 let storage = Storage::from(VaultStorage::new(
                     config.server.clone(),
@@ -290,6 +428,93 @@ vault:
         serde_yaml::to_string(&from_disk).unwrap();
     }
 
+    #[test]
+    fn test_pkcs11_config_parsing() {
+        #[derive(Debug, Deserialize, PartialEq, Eq, Serialize)]
+        struct Config {
+            backend: SecureBackend,
+        }
+
+        let from_config = Config {
+            backend: SecureBackend::Pkcs11(Pkcs11Config {
+                library_path: PathBuf::from("/usr/lib/softhsm/libsofthsm2.so"),
+                token_label: "validator".to_string(),
+                pin: Token::FromDisk(PathBuf::from("/pin")),
+                namespace: Some("safety_rules".to_string()),
+                extractable_keys: false,
+            }),
+        };
+
+        let text_from_config = r#"
+backend:
+    type: pkcs11
+    library_path: "/usr/lib/softhsm/libsofthsm2.so"
+    token_label: "validator"
+    pin:
+        from_disk: "/pin"
+    namespace: "safety_rules"
+        "#;
+
+        let de_from_config: Config = serde_yaml::from_str(text_from_config).unwrap();
+        assert_eq!(de_from_config, from_config);
+        assert_eq!(from_config.backend.namespace(), Some("safety_rules"));
+        // Just assert that it can be serialized, no need to do string comparison
+        serde_yaml::to_string(&from_config).unwrap();
+    }
+
+    #[test]
+    fn test_encrypted_on_disk_config_parsing() {
+        #[derive(Debug, Deserialize, PartialEq, Eq, Serialize)]
+        struct Config {
+            backend: SecureBackend,
+        }
+
+        let from_config = Config {
+            backend: SecureBackend::EncryptedOnDiskStorage(EncryptedOnDiskStorageConfig::new(
+                PathBuf::from("secure_storage.sealed.json"),
+                Passphrase::FromEnv("APTOS_SECURE_STORAGE_PASSPHRASE".to_string()),
+            )),
+        };
+
+        let text_from_config = r#"
+backend:
+    type: encrypted_on_disk_storage
+    path: "secure_storage.sealed.json"
+    passphrase:
+        from_env: "APTOS_SECURE_STORAGE_PASSPHRASE"
+        "#;
+
+        let de_from_config: Config = serde_yaml::from_str(text_from_config).unwrap();
+        assert_eq!(de_from_config, from_config);
+        // Just assert that it can be serialized, no need to do string comparison
+        serde_yaml::to_string(&from_config).unwrap();
+
+        // Verify relative paths are resolved against the data directory
+        let mut backend = from_config.backend;
+        backend.set_data_dir(PathBuf::from("/data"));
+        match backend {
+            SecureBackend::EncryptedOnDiskStorage(config) => assert_eq!(
+                config.path(),
+                PathBuf::from("/data/secure_storage.sealed.json")
+            ),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_passphrase_reading() {
+        let temppath = aptos_temppath::TempPath::new();
+        temppath.create_as_file().unwrap();
+        let mut file = File::create(temppath.path()).unwrap();
+        file.write_all(b"disk passphrase\n").unwrap();
+
+        let disk = Passphrase::FromDisk(temppath.path().to_path_buf());
+        assert_eq!("disk passphrase", disk.read_passphrase().unwrap());
+
+        let missing = Passphrase::FromEnv("APTOS_TEST_MISSING_PASSPHRASE".to_string());
+        missing.read_passphrase().unwrap_err();
+    }
+
     #[test]
     fn test_token_reading() {
         let temppath = aptos_temppath::TempPath::new();
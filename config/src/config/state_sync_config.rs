@@ -6,9 +6,10 @@ use crate::config::{
     config_optimizer::ConfigOptimizer, config_sanitizer::ConfigSanitizer,
     node_config_loader::NodeType, Error, NodeConfig,
 };
-use aptos_types::chain_id::ChainId;
+use aptos_types::{account_address::AccountAddress, chain_id::ChainId};
 use serde::{Deserialize, Serialize};
 use serde_yaml::Value;
+use std::path::PathBuf;
 
 // The maximum message size per state sync message
 const MAX_MESSAGE_SIZE: usize = 6 * 1024 * 1024; /* 6 MiB */
@@ -44,6 +45,13 @@ pub enum BootstrappingMode {
     ExecuteTransactionsFromGenesis,
     /// Executes transactions or applies outputs from genesis (whichever is faster)
     ExecuteOrApplyFromGenesis,
+    /// Restores the latest state snapshot and applies the trailing transaction
+    /// outputs from a backup storage (see `StateSyncBackupConfig`)
+    RestoreFromBackup,
+    /// Only downloads and verifies the states of the tracked accounts (see
+    /// `PartialStateSyncConfig`). The node does not execute or apply any
+    /// transactions, so it cannot serve the full ledger.
+    DownloadFilteredStates,
 }
 
 impl BootstrappingMode {
@@ -57,6 +65,8 @@ impl BootstrappingMode {
                 "execute_transactions_from_genesis"
             },
             BootstrappingMode::ExecuteOrApplyFromGenesis => "execute_or_apply_from_genesis",
+            BootstrappingMode::RestoreFromBackup => "restore_from_backup",
+            BootstrappingMode::DownloadFilteredStates => "download_filtered_states",
         }
     }
 
@@ -64,6 +74,102 @@ impl BootstrappingMode {
     pub fn is_fast_sync(&self) -> bool {
         *self == BootstrappingMode::DownloadLatestStates
     }
+
+    /// Returns true iff the bootstrapping mode restores from a backup
+    pub fn is_restore_from_backup(&self) -> bool {
+        *self == BootstrappingMode::RestoreFromBackup
+    }
+
+    /// Returns true iff the bootstrapping mode only syncs the tracked states
+    pub fn is_partial_state_sync(&self) -> bool {
+        *self == BootstrappingMode::DownloadFilteredStates
+    }
+}
+
+/// The backup storage to bootstrap from, as created by the backup-cli
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackupStorageConfig {
+    /// A local directory holding the backup
+    LocalFs(PathBuf),
+    /// The config file of a command adapter backup storage
+    CommandAdapter(PathBuf),
+    /// The config file of an S3 backup storage
+    S3(PathBuf),
+    /// The config file of an Azure Blob backup storage
+    AzureBlob(PathBuf),
+}
+
+/// The config for bootstrapping from a backup (i.e., when the bootstrapping
+/// mode is `RestoreFromBackup`).
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StateSyncBackupConfig {
+    /// The backup storage to restore from
+    pub backup_storage: Option<BackupStorageConfig>,
+    /// The maximum number of concurrent downloads from the backup storage
+    pub concurrent_downloads: usize,
+    /// The file holding the hex encoded key of an encrypted backup (if any)
+    pub encryption_key_file: Option<PathBuf>,
+    /// The directory to cache the backup metadata in (defaults to a temporary directory)
+    pub metadata_cache_dir: Option<PathBuf>,
+}
+
+impl Default for StateSyncBackupConfig {
+    fn default() -> Self {
+        Self {
+            backup_storage: None,
+            concurrent_downloads: 8,
+            encryption_key_file: None,
+            metadata_cache_dir: None,
+        }
+    }
+}
+
+/// The config for partial state syncing (i.e., when the bootstrapping mode
+/// is `DownloadFilteredStates`). The tracked states are persisted in a
+/// dedicated database in the storage dir, which the API reads from.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PartialStateSyncConfig {
+    /// Whether or not to also track the state of the framework accounts
+    pub include_framework_state: bool,
+    /// The interval (ms) at which to refresh the tracked states
+    pub refresh_interval_ms: u64,
+    /// The timeout (ms) for each data request sent to peers
+    pub request_timeout_ms: u64,
+    /// The accounts whose resources and modules are tracked
+    pub tracked_accounts: Vec<AccountAddress>,
+}
+
+impl Default for PartialStateSyncConfig {
+    fn default() -> Self {
+        Self {
+            include_framework_state: true,
+            refresh_interval_ms: 10_000, // 10 seconds
+            request_timeout_ms: 10_000,  // 10 seconds
+            tracked_accounts: vec![],
+        }
+    }
+}
+
+impl PartialStateSyncConfig {
+    /// Returns all accounts to track (including the framework accounts, if required)
+    pub fn get_tracked_accounts(&self) -> Vec<AccountAddress> {
+        let mut tracked_accounts = self.tracked_accounts.clone();
+        if self.include_framework_state {
+            for framework_account in [
+                AccountAddress::ONE,
+                AccountAddress::THREE,
+                AccountAddress::FOUR,
+            ] {
+                if !tracked_accounts.contains(&framework_account) {
+                    tracked_accounts.push(framework_account);
+                }
+            }
+        }
+        tracked_accounts
+    }
 }
 
 /// The continuous syncing mode determines how the node will stay up-to-date
@@ -466,7 +572,7 @@ impl ConfigSanitizer for StateSyncConfig {
 impl ConfigSanitizer for StateSyncDriverConfig {
     fn sanitize(
         node_config: &NodeConfig,
-        _node_type: NodeType,
+        node_type: NodeType,
         _chain_id: Option<ChainId>,
     ) -> Result<(), Error> {
         let sanitizer_name = Self::get_sanitizer_name();
@@ -483,6 +589,46 @@ impl ConfigSanitizer for StateSyncDriverConfig {
             ));
         }
 
+        // Verify that a backup storage is configured for nodes that
+        // are restoring from a backup.
+        if state_sync_driver_config
+            .bootstrapping_mode
+            .is_restore_from_backup()
+            && node_config.state_sync_backup.backup_storage.is_none()
+        {
+            return Err(Error::ConfigSanitizerFailed(
+                sanitizer_name,
+                "A backup storage must be configured for nodes that are restoring from a backup!"
+                    .to_string(),
+            ));
+        }
+
+        // Verify that partial state syncing is only used by fullnodes
+        // that track at least one account.
+        if state_sync_driver_config
+            .bootstrapping_mode
+            .is_partial_state_sync()
+        {
+            if node_type.is_validator() {
+                return Err(Error::ConfigSanitizerFailed(
+                    sanitizer_name,
+                    "Validators cannot sync filtered states! They require the full state."
+                        .to_string(),
+                ));
+            }
+            if node_config
+                .state_sync_partial
+                .get_tracked_accounts()
+                .is_empty()
+            {
+                return Err(Error::ConfigSanitizerFailed(
+                    sanitizer_name,
+                    "At least one account must be tracked for nodes that sync filtered states!"
+                        .to_string(),
+                ));
+            }
+        }
+
         Ok(())
     }
 }
@@ -788,6 +934,85 @@ mod tests {
         assert!(matches!(error, Error::ConfigSanitizerFailed(_, _)));
     }
 
+    #[test]
+    fn test_sanitize_restore_from_backup_without_storage() {
+        // Create a node config that restores from a backup,
+        // but without a backup storage.
+        let mut node_config = NodeConfig {
+            state_sync: StateSyncConfig {
+                state_sync_driver: StateSyncDriverConfig {
+                    bootstrapping_mode: BootstrappingMode::RestoreFromBackup,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        // Verify that sanitization fails
+        let error = StateSyncConfig::sanitize(
+            &node_config,
+            NodeType::PublicFullnode,
+            Some(ChainId::testnet()),
+        )
+        .unwrap_err();
+        assert!(matches!(error, Error::ConfigSanitizerFailed(_, _)));
+
+        // Add a backup storage and verify that sanitization succeeds
+        node_config.state_sync_backup.backup_storage = Some(BackupStorageConfig::LocalFs(
+            PathBuf::from("/opt/aptos/backup"),
+        ));
+        StateSyncConfig::sanitize(
+            &node_config,
+            NodeType::PublicFullnode,
+            Some(ChainId::testnet()),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_sanitize_partial_state_sync() {
+        // Create a node config that syncs filtered states, but without any tracked accounts
+        let mut node_config = NodeConfig {
+            state_sync: StateSyncConfig {
+                state_sync_driver: StateSyncDriverConfig {
+                    bootstrapping_mode: BootstrappingMode::DownloadFilteredStates,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            state_sync_partial: PartialStateSyncConfig {
+                include_framework_state: false,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        // Verify that sanitization fails
+        let error = StateSyncConfig::sanitize(
+            &node_config,
+            NodeType::PublicFullnode,
+            Some(ChainId::testnet()),
+        )
+        .unwrap_err();
+        assert!(matches!(error, Error::ConfigSanitizerFailed(_, _)));
+
+        // Track an account and verify that sanitization succeeds
+        node_config.state_sync_partial.tracked_accounts = vec![AccountAddress::random()];
+        StateSyncConfig::sanitize(
+            &node_config,
+            NodeType::PublicFullnode,
+            Some(ChainId::testnet()),
+        )
+        .unwrap();
+
+        // Verify that sanitization fails for validators
+        let error =
+            StateSyncConfig::sanitize(&node_config, NodeType::Validator, Some(ChainId::testnet()))
+                .unwrap_err();
+        assert!(matches!(error, Error::ConfigSanitizerFailed(_, _)));
+    }
+
     /// Creates and returns a node config with the syncing modes set to execution
     fn create_execution_mode_config() -> NodeConfig {
         NodeConfig {
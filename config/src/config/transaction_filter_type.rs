@@ -2,11 +2,142 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use aptos_crypto::HashValue;
+use aptos_infallible::RwLock;
 use aptos_types::{
     account_address::AccountAddress,
-    transaction::{SignedTransaction, TransactionPayload},
+    transaction::{
+        authenticator::{AccountAuthenticator, AnyPublicKey, TransactionAuthenticator},
+        EntryFunction, SignedTransaction, TransactionPayload,
+    },
 };
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::sync::Arc;
+
+/// The kind of payload carried by a transaction.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum PayloadKind {
+    Script,
+    ModuleBundle,
+    EntryFunction,
+    Multisig,
+}
+
+impl PayloadKind {
+    fn matches(&self, payload: &TransactionPayload) -> bool {
+        matches!(
+            (self, payload),
+            (PayloadKind::Script, TransactionPayload::Script(_))
+                | (
+                    PayloadKind::ModuleBundle,
+                    TransactionPayload::ModuleBundle(_)
+                )
+                | (
+                    PayloadKind::EntryFunction,
+                    TransactionPayload::EntryFunction(_)
+                )
+                | (PayloadKind::Multisig, TransactionPayload::Multisig(_))
+        )
+    }
+}
+
+/// The kind of authenticator used to sign a transaction. All variants except `Keyless`
+/// correspond to the top level transaction authenticator. `Keyless` matches any transaction
+/// where at least one of the signers (sender, secondary signers or fee payer) uses a keyless
+/// public key.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum AuthenticatorKind {
+    Ed25519,
+    MultiEd25519,
+    MultiAgent,
+    FeePayer,
+    SingleSender,
+    Keyless,
+}
+
+impl AuthenticatorKind {
+    fn matches(&self, authenticator: &TransactionAuthenticator) -> bool {
+        match self {
+            AuthenticatorKind::Ed25519 => {
+                matches!(authenticator, TransactionAuthenticator::Ed25519 { .. })
+            },
+            AuthenticatorKind::MultiEd25519 => {
+                matches!(authenticator, TransactionAuthenticator::MultiEd25519 { .. })
+            },
+            AuthenticatorKind::MultiAgent => {
+                matches!(authenticator, TransactionAuthenticator::MultiAgent { .. })
+            },
+            AuthenticatorKind::FeePayer => {
+                matches!(authenticator, TransactionAuthenticator::FeePayer { .. })
+            },
+            AuthenticatorKind::SingleSender => {
+                matches!(authenticator, TransactionAuthenticator::SingleSender { .. })
+            },
+            AuthenticatorKind::Keyless => {
+                authenticator
+                    .all_signers()
+                    .iter()
+                    .any(|signer| match signer {
+                        AccountAuthenticator::SingleKey { authenticator } => {
+                            matches!(authenticator.public_key(), AnyPublicKey::Keyless { .. })
+                        },
+                        AccountAuthenticator::MultiKey { authenticator } => authenticator
+                            .public_keys()
+                            .public_keys()
+                            .iter()
+                            .any(|public_key| matches!(public_key, AnyPublicKey::Keyless { .. })),
+                        AccountAuthenticator::Ed25519 { .. }
+                        | AccountAuthenticator::MultiEd25519 { .. } => false,
+                    })
+            },
+        }
+    }
+}
+
+/// An expected value for an entry function argument. Arguments are BCS decoded as the type of
+/// the expected value before being compared, so an argument that fails to decode never matches.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum ArgumentValue {
+    Address(AccountAddress),
+    Bool(bool),
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+    U128(u128),
+    String(String),
+    Bytes(Vec<u8>),
+}
+
+impl ArgumentValue {
+    fn matches(&self, argument: &[u8]) -> bool {
+        match self {
+            ArgumentValue::Address(expected) => bcs_decoded_equals(argument, expected),
+            ArgumentValue::Bool(expected) => bcs_decoded_equals(argument, expected),
+            ArgumentValue::U8(expected) => bcs_decoded_equals(argument, expected),
+            ArgumentValue::U16(expected) => bcs_decoded_equals(argument, expected),
+            ArgumentValue::U32(expected) => bcs_decoded_equals(argument, expected),
+            ArgumentValue::U64(expected) => bcs_decoded_equals(argument, expected),
+            ArgumentValue::U128(expected) => bcs_decoded_equals(argument, expected),
+            ArgumentValue::String(expected) => bcs_decoded_equals(argument, expected),
+            ArgumentValue::Bytes(expected) => bcs_decoded_equals(argument, expected),
+        }
+    }
+}
+
+fn bcs_decoded_equals<T: DeserializeOwned + PartialEq>(bytes: &[u8], expected: &T) -> bool {
+    bcs::from_bytes::<T>(bytes).map_or(false, |value| value == *expected)
+}
+
+fn entry_function_matches(
+    entry_function: &EntryFunction,
+    address: &AccountAddress,
+    module_name: &str,
+    function: &str,
+) -> bool {
+    *entry_function.module().address() == *address
+        && entry_function.module().name().as_str() == module_name
+        && entry_function.function().as_str() == function
+}
 
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub enum Matcher {
@@ -18,6 +149,22 @@ pub enum Matcher {
     Sender(AccountAddress),
     ModuleAddress(AccountAddress),
     EntryFunction(AccountAddress, String, String),
+    /// Matches the entry function (address, module, function) whose argument at the given
+    /// index BCS decodes to the given value.
+    EntryFunctionArgument(AccountAddress, String, String, usize, ArgumentValue),
+    PayloadKind(PayloadKind),
+    AuthenticatorKind(AuthenticatorKind),
+    GasUnitPriceGreaterThan(u64),
+    GasUnitPriceLessThan(u64),
+    MaxGasAmountGreaterThan(u64),
+    MaxGasAmountLessThan(u64),
+    ExpirationTimeStampGreaterThan(u64),
+    ExpirationTimeStampLessThan(u64),
+    /// Matches if all of the inner matchers match (an empty list always matches).
+    And(Vec<Matcher>),
+    /// Matches if any of the inner matchers match (an empty list never matches).
+    Or(Vec<Matcher>),
+    Not(Box<Matcher>),
 }
 
 impl Matcher {
@@ -37,12 +184,49 @@ impl Matcher {
             },
             Matcher::EntryFunction(address, module_name, function) => match txn.payload() {
                 TransactionPayload::EntryFunction(entry_function) => {
-                    *entry_function.module().address() == *address
-                        && entry_function.module().name().to_string() == *module_name
-                        && entry_function.function().to_string() == *function
+                    entry_function_matches(entry_function, address, module_name, function)
                 },
                 _ => false,
             },
+            Matcher::EntryFunctionArgument(address, module_name, function, index, value) => {
+                match txn.payload() {
+                    TransactionPayload::EntryFunction(entry_function) => {
+                        entry_function_matches(entry_function, address, module_name, function)
+                            && entry_function
+                                .args()
+                                .get(*index)
+                                .map_or(false, |argument| value.matches(argument))
+                    },
+                    _ => false,
+                }
+            },
+            Matcher::PayloadKind(kind) => kind.matches(txn.payload()),
+            Matcher::AuthenticatorKind(kind) => kind.matches(txn.authenticator_ref()),
+            Matcher::GasUnitPriceGreaterThan(price) => txn.gas_unit_price() > *price,
+            Matcher::GasUnitPriceLessThan(price) => txn.gas_unit_price() < *price,
+            Matcher::MaxGasAmountGreaterThan(amount) => txn.max_gas_amount() > *amount,
+            Matcher::MaxGasAmountLessThan(amount) => txn.max_gas_amount() < *amount,
+            Matcher::ExpirationTimeStampGreaterThan(ts) => txn.expiration_timestamp_secs() > *ts,
+            Matcher::ExpirationTimeStampLessThan(ts) => txn.expiration_timestamp_secs() < *ts,
+            Matcher::And(matchers) => matchers
+                .iter()
+                .all(|matcher| matcher.matches(block_id, timestamp, txn)),
+            Matcher::Or(matchers) => matchers
+                .iter()
+                .any(|matcher| matcher.matches(block_id, timestamp, txn)),
+            Matcher::Not(matcher) => !matcher.matches(block_id, timestamp, txn),
+        }
+    }
+
+    /// Returns true iff this matcher, or any matcher nested within it, matches on block ID.
+    pub fn contains_block_id(&self) -> bool {
+        match self {
+            Matcher::BlockId(_) => true,
+            Matcher::And(matchers) | Matcher::Or(matchers) => {
+                matchers.iter().any(|matcher| matcher.contains_block_id())
+            },
+            Matcher::Not(matcher) => matcher.contains_block_id(),
+            _ => false,
         }
     }
 }
@@ -113,6 +297,22 @@ impl Rule {
 /// This filter allows transactions from the sender with address f8871acf2c827d40e23b71f6ff2b9accef8dbb17709b88bd9eb95e6bb748c25a or
 /// from the module with address 0000000000000000000000000000000000000000000000000000000000000001 or entry functions
 /// test::check and test::new from the module 0000000000000000000000000000000000000000000000000000000000000001. All other transactions are denied.
+///
+/// Matchers can also be combined using `And`, `Or` and `Not`. For example, the following rules
+/// deny all scripts, and all calls to 0x1::coin::transfer that send to address 0xbad with a max
+/// gas amount above 100000:
+///             rules:
+///                 - Deny:
+///                     PayloadKind: Script
+///                 - Deny:
+///                     And:
+///                         - EntryFunctionArgument:
+///                             - "0000000000000000000000000000000000000000000000000000000000000001"
+///                             - coin
+///                             - transfer
+///                             - 0
+///                             - Address: "0000000000000000000000000000000000000000000000000000000000000bad"
+///                         - MaxGasAmountGreaterThan: 100000
 #[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
 pub struct Filter {
     rules: Vec<Rule>,
@@ -197,6 +397,51 @@ impl Filter {
         self
     }
 
+    pub fn add_deny_entry_function_argument(
+        mut self,
+        address: AccountAddress,
+        module_name: String,
+        function: String,
+        index: usize,
+        value: ArgumentValue,
+    ) -> Self {
+        self.rules.push(Rule::Deny(Matcher::EntryFunctionArgument(
+            address,
+            module_name,
+            function,
+            index,
+            value,
+        )));
+        self
+    }
+
+    pub fn add_deny_payload_kind(mut self, kind: PayloadKind) -> Self {
+        self.rules.push(Rule::Deny(Matcher::PayloadKind(kind)));
+        self
+    }
+
+    pub fn add_deny_authenticator_kind(mut self, kind: AuthenticatorKind) -> Self {
+        self.rules
+            .push(Rule::Deny(Matcher::AuthenticatorKind(kind)));
+        self
+    }
+
+    pub fn add_deny_max_gas_amount_greater_than(mut self, max_gas_amount: u64) -> Self {
+        self.rules
+            .push(Rule::Deny(Matcher::MaxGasAmountGreaterThan(max_gas_amount)));
+        self
+    }
+
+    pub fn add_allow(mut self, matcher: Matcher) -> Self {
+        self.rules.push(Rule::Allow(matcher));
+        self
+    }
+
+    pub fn add_deny(mut self, matcher: Matcher) -> Self {
+        self.rules.push(Rule::Deny(matcher));
+        self
+    }
+
     pub fn rules(&self) -> &[Rule] {
         &self.rules
     }
@@ -214,3 +459,45 @@ impl Filter {
         true
     }
 }
+
+/// A filter together with the version it was installed at. The version starts at 0 for the
+/// filter loaded from the node config and is incremented every time the filter is replaced.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct VersionedFilter {
+    pub version: u64,
+    pub filter: Filter,
+}
+
+/// A filter that can be replaced at runtime (e.g., via the admin service) without restarting
+/// the node. Readers always observe a consistent snapshot of the filter and its version.
+#[derive(Debug, Default)]
+pub struct ReloadableFilter {
+    current: RwLock<Arc<VersionedFilter>>,
+}
+
+impl ReloadableFilter {
+    pub fn new(filter: Filter) -> Self {
+        Self {
+            current: RwLock::new(Arc::new(VersionedFilter { version: 0, filter })),
+        }
+    }
+
+    /// Returns a snapshot of the currently active filter
+    pub fn load(&self) -> Arc<VersionedFilter> {
+        self.current.read().clone()
+    }
+
+    /// Replaces the active filter and returns the snapshot that was replaced
+    pub fn store(&self, filter: Filter) -> Arc<VersionedFilter> {
+        let mut current = self.current.write();
+        let new_filter = Arc::new(VersionedFilter {
+            version: current.version + 1,
+            filter,
+        });
+        std::mem::replace(&mut *current, new_filter)
+    }
+
+    pub fn allows(&self, block_id: HashValue, timestamp: u64, txn: &SignedTransaction) -> bool {
+        self.load().filter.allows(block_id, timestamp, txn)
+    }
+}
@@ -22,7 +22,7 @@ use crate::{
     util::time_service::ClockTimeService,
 };
 use aptos_bounded_executor::BoundedExecutor;
-use aptos_config::config::NodeConfig;
+use aptos_config::config::{transaction_filter_type::ReloadableFilter, NodeConfig};
 use aptos_consensus_notifications::ConsensusNotificationSender;
 use aptos_event_notifications::{DbBackedOnChainConfig, ReconfigNotificationListener};
 use aptos_executor::block_executor::BlockExecutor;
@@ -52,6 +52,7 @@ pub fn start_consensus(
     reconfig_events: ReconfigNotificationListener<DbBackedOnChainConfig>,
     vtxn_pool: VTxnPoolState,
     consensus_publisher: Option<Arc<ConsensusPublisher>>,
+    execution_filter: Arc<ReloadableFilter>,
 ) -> (Runtime, Arc<StorageWriteProxy>, Arc<QuorumStoreDB>) {
     let runtime = aptos_runtimes::spawn_named_runtime("consensus".into(), None);
     let storage = Arc::new(StorageWriteProxy::new(node_config, aptos_db.reader.clone()));
@@ -67,7 +68,7 @@ pub fn start_consensus(
         txn_notifier,
         state_sync_notifier,
         runtime.handle(),
-        TransactionFilter::new(node_config.execution.transaction_filter.clone()),
+        TransactionFilter::new_reloadable(execution_filter),
     );
 
     let time_service = Arc::new(ClockTimeService::new(runtime.handle().clone()));
@@ -132,6 +133,7 @@ pub fn start_consensus_observer(
     consensus_to_mempool_sender: mpsc::Sender<QuorumStoreRequest>,
     aptos_db: DbReaderWriter,
     reconfig_events: Option<ReconfigNotificationListener<DbBackedOnChainConfig>>,
+    execution_filter: Arc<ReloadableFilter>,
 ) -> Runtime {
     // Create a consensus observer runtime
     let runtime = aptos_runtimes::spawn_named_runtime("observer".into(), None);
@@ -174,7 +176,7 @@ pub fn start_consensus_observer(
             txn_notifier,
             state_sync_notifier,
             runtime.handle(),
-            TransactionFilter::new(node_config.execution.transaction_filter.clone()),
+            TransactionFilter::new_reloadable(execution_filter),
         );
 
         // Create the execution proxy client
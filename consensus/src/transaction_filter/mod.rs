@@ -1,16 +1,25 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
+#[cfg(test)]
 use aptos_config::config::transaction_filter_type::Filter;
+use aptos_config::config::transaction_filter_type::ReloadableFilter;
 use aptos_crypto::HashValue;
 use aptos_types::transaction::SignedTransaction;
+use std::sync::Arc;
 
 pub struct TransactionFilter {
-    filter: Filter,
+    filter: Arc<ReloadableFilter>,
 }
 
 impl TransactionFilter {
+    #[cfg(test)]
     pub(crate) fn new(filter: Filter) -> Self {
+        Self::new_reloadable(Arc::new(ReloadableFilter::new(filter)))
+    }
+
+    /// Creates a transaction filter whose rules can be replaced at runtime
+    pub(crate) fn new_reloadable(filter: Arc<ReloadableFilter>) -> Self {
         Self { filter }
     }
 
@@ -20,12 +29,15 @@ impl TransactionFilter {
         timestamp: u64,
         txns: Vec<SignedTransaction>,
     ) -> Vec<SignedTransaction> {
+        // Take a snapshot of the filter so the whole block is filtered by the same rules
+        let filter = self.filter.load();
+
         // Special case for no filter to avoid unnecessary iteration through all transactions in the default case
-        if self.filter.is_empty() {
+        if filter.filter.is_empty() {
             return txns;
         }
         txns.into_iter()
-            .filter(|txn| self.filter.allows(block_id, timestamp, txn))
+            .filter(|txn| filter.filter.allows(block_id, timestamp, txn))
             .collect()
     }
 }
@@ -33,33 +45,59 @@ impl TransactionFilter {
 #[cfg(test)]
 mod test {
     use crate::transaction_filter::TransactionFilter;
-    use aptos_config::config::transaction_filter_type::Filter;
+    use aptos_config::config::transaction_filter_type::{
+        ArgumentValue, AuthenticatorKind, Filter, Matcher, PayloadKind, ReloadableFilter,
+    };
     use aptos_crypto::{ed25519::Ed25519PrivateKey, HashValue, PrivateKey, SigningKey, Uniform};
     use aptos_types::{
         chain_id::ChainId,
         move_utils::MemberId,
-        transaction::{EntryFunction, RawTransaction, SignedTransaction, TransactionPayload},
+        transaction::{
+            EntryFunction, RawTransaction, Script, SignedTransaction, TransactionPayload,
+        },
     };
     use move_core_types::account_address::AccountAddress;
+    use std::sync::Arc;
 
     fn create_signed_transaction(function: MemberId) -> SignedTransaction {
-        let private_key = Ed25519PrivateKey::generate_for_testing();
-        let public_key = private_key.public_key();
-        let sender = AccountAddress::random();
-        let sequence_number = 0;
+        create_entry_function_transaction(function, vec![], 0)
+    }
+
+    fn create_entry_function_transaction(
+        function: MemberId,
+        args: Vec<Vec<u8>>,
+        max_gas_amount: u64,
+    ) -> SignedTransaction {
         let MemberId {
             module_id,
             member_id: function_id,
         } = function;
-
         let payload = TransactionPayload::EntryFunction(EntryFunction::new(
             module_id,
             function_id,
             vec![],
-            vec![],
+            args,
         ));
-        let raw_transaction =
-            RawTransaction::new(sender, sequence_number, payload, 0, 0, 0, ChainId::new(10));
+        create_signed_transaction_with_payload(payload, max_gas_amount)
+    }
+
+    fn create_signed_transaction_with_payload(
+        payload: TransactionPayload,
+        max_gas_amount: u64,
+    ) -> SignedTransaction {
+        let private_key = Ed25519PrivateKey::generate_for_testing();
+        let public_key = private_key.public_key();
+        let sender = AccountAddress::random();
+        let sequence_number = 0;
+        let raw_transaction = RawTransaction::new(
+            sender,
+            sequence_number,
+            payload,
+            max_gas_amount,
+            0,
+            0,
+            ChainId::new(10),
+        );
 
         SignedTransaction::new(
             raw_transaction.clone(),
@@ -287,4 +325,177 @@ mod test {
         let filtered_txns = allow_list_filter.filter(block_id, 0, txns.clone());
         assert_eq!(filtered_txns, txns[4..].to_vec());
     }
+
+    #[test]
+    fn test_payload_kind_filter() {
+        let mut txns = get_transactions();
+        txns.push(create_signed_transaction_with_payload(
+            TransactionPayload::Script(Script::new(vec![], vec![], vec![])),
+            0,
+        ));
+        let block_id = HashValue::random();
+        let deny_scripts_filter =
+            TransactionFilter::new(Filter::empty().add_deny_payload_kind(PayloadKind::Script));
+        let filtered_txns = deny_scripts_filter.filter(block_id, 0, txns.clone());
+        assert_eq!(filtered_txns, txns[0..7].to_vec());
+    }
+
+    #[test]
+    fn test_authenticator_kind_filter() {
+        let txns = get_transactions();
+        let block_id = HashValue::random();
+        let deny_ed25519_filter = TransactionFilter::new(
+            Filter::empty().add_deny_authenticator_kind(AuthenticatorKind::Ed25519),
+        );
+        let filtered_txns = deny_ed25519_filter.filter(block_id, 0, txns.clone());
+        assert_eq!(filtered_txns, vec![]);
+
+        let deny_keyless_filter = TransactionFilter::new(
+            Filter::empty().add_deny_authenticator_kind(AuthenticatorKind::Keyless),
+        );
+        let filtered_txns = deny_keyless_filter.filter(block_id, 0, txns.clone());
+        assert_eq!(filtered_txns, txns);
+    }
+
+    #[test]
+    fn test_max_gas_amount_filter() {
+        let txns = vec![
+            create_entry_function_transaction(str::parse("0x1::test::add").unwrap(), vec![], 10),
+            create_entry_function_transaction(str::parse("0x1::test::add").unwrap(), vec![], 100),
+            create_entry_function_transaction(str::parse("0x1::test::add").unwrap(), vec![], 1000),
+        ];
+        let block_id = HashValue::random();
+        let max_gas_filter =
+            TransactionFilter::new(Filter::empty().add_deny_max_gas_amount_greater_than(100));
+        let filtered_txns = max_gas_filter.filter(block_id, 0, txns.clone());
+        assert_eq!(filtered_txns, txns[0..2].to_vec());
+    }
+
+    #[test]
+    fn test_entry_function_argument_filter() {
+        let bad_address = AccountAddress::random();
+        let txns = vec![
+            create_entry_function_transaction(
+                str::parse("0x1::coin::transfer").unwrap(),
+                vec![
+                    bcs::to_bytes(&bad_address).unwrap(),
+                    bcs::to_bytes(&1u64).unwrap(),
+                ],
+                0,
+            ),
+            create_entry_function_transaction(
+                str::parse("0x1::coin::transfer").unwrap(),
+                vec![
+                    bcs::to_bytes(&AccountAddress::random()).unwrap(),
+                    bcs::to_bytes(&1u64).unwrap(),
+                ],
+                0,
+            ),
+            create_entry_function_transaction(
+                str::parse("0x1::coin::mint").unwrap(),
+                vec![
+                    bcs::to_bytes(&bad_address).unwrap(),
+                    bcs::to_bytes(&1u64).unwrap(),
+                ],
+                0,
+            ),
+        ];
+        let block_id = HashValue::random();
+        let argument_filter =
+            TransactionFilter::new(Filter::empty().add_deny_entry_function_argument(
+                AccountAddress::ONE,
+                "coin".to_string(),
+                "transfer".to_string(),
+                0,
+                ArgumentValue::Address(bad_address),
+            ));
+        let filtered_txns = argument_filter.filter(block_id, 0, txns.clone());
+        assert_eq!(filtered_txns, txns[1..].to_vec());
+
+        // An argument that doesn't decode as the expected type never matches
+        let argument_filter =
+            TransactionFilter::new(Filter::empty().add_deny_entry_function_argument(
+                AccountAddress::ONE,
+                "coin".to_string(),
+                "transfer".to_string(),
+                1,
+                ArgumentValue::Address(bad_address),
+            ));
+        let filtered_txns = argument_filter.filter(block_id, 0, txns.clone());
+        assert_eq!(filtered_txns, txns);
+    }
+
+    #[test]
+    fn test_boolean_combinator_filter() {
+        let txns = get_transactions();
+        let block_id = HashValue::random();
+
+        // Deny everything that is not from module address 0x1
+        let not_filter = TransactionFilter::new(Filter::empty().add_deny(Matcher::Not(Box::new(
+            Matcher::ModuleAddress(get_module_address(&txns[0])),
+        ))));
+        let filtered_txns = not_filter.filter(block_id, 0, txns.clone());
+        assert_eq!(filtered_txns, txns[0..4].to_vec());
+
+        // Deny the sender of the first transaction, but only after timestamp 1000
+        let and_filter = TransactionFilter::new(Filter::empty().add_deny(Matcher::And(vec![
+            Matcher::Sender(txns[0].sender()),
+            Matcher::BlockTimeStampGreaterThan(1000),
+        ])));
+        let filtered_txns = and_filter.filter(block_id, 0, txns.clone());
+        assert_eq!(filtered_txns, txns);
+        let filtered_txns = and_filter.filter(block_id, 1001, txns.clone());
+        assert_eq!(filtered_txns, txns[1..].to_vec());
+
+        // Deny either of the first two senders
+        let or_filter = TransactionFilter::new(Filter::empty().add_deny(Matcher::Or(vec![
+            Matcher::Sender(txns[0].sender()),
+            Matcher::Sender(txns[1].sender()),
+        ])));
+        let filtered_txns = or_filter.filter(block_id, 0, txns.clone());
+        assert_eq!(filtered_txns, txns[2..].to_vec());
+    }
+
+    #[test]
+    fn test_composite_combinator_filter() {
+        let txns = get_transactions();
+        let block_id = HashValue::random();
+        let filter = serde_yaml::from_str::<Filter>(r#"
+            rules:
+                - Deny:
+                    PayloadKind: Script
+                - Deny:
+                    And:
+                        - ModuleAddress: "0000000000000000000000000000000000000000000000000000000000000001"
+                        - Not:
+                            EntryFunction:
+                                - "0000000000000000000000000000000000000000000000000000000000000001"
+                                - test
+                                - check
+              "#).unwrap();
+
+        let combinator_filter = TransactionFilter::new(filter);
+        let filtered_txns = combinator_filter.filter(block_id, 0, txns.clone());
+        let mut expected_txns = vec![txns[1].clone()];
+        expected_txns.extend_from_slice(&txns[4..]);
+        assert_eq!(filtered_txns, expected_txns);
+    }
+
+    #[test]
+    fn test_reloadable_filter() {
+        let txns = get_transactions();
+        let block_id = HashValue::random();
+        let reloadable_filter = Arc::new(ReloadableFilter::new(Filter::empty()));
+        let transaction_filter = TransactionFilter::new_reloadable(reloadable_filter.clone());
+
+        let filtered_txns = transaction_filter.filter(block_id, 0, txns.clone());
+        assert_eq!(filtered_txns, txns);
+
+        // Replace the filter and verify the new rules are applied
+        let previous_filter = reloadable_filter.store(Filter::empty().add_deny_all());
+        assert_eq!(previous_filter.version, 0);
+        assert_eq!(reloadable_filter.load().version, 1);
+        let filtered_txns = transaction_filter.filter(block_id, 0, txns.clone());
+        assert_eq!(filtered_txns, vec![]);
+    }
 }
@@ -1,7 +1,9 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
-use aptos_config::config::{AuthenticationConfig, NodeConfig};
+use aptos_config::config::{
+    transaction_filter_type::ReloadableFilter, AuthenticationConfig, NodeConfig,
+};
 use aptos_consensus::{
     persistent_liveness_storage::StorageWriteProxy, quorum_store::quorum_store_db::QuorumStoreDB,
 };
@@ -26,11 +28,15 @@ use std::{
 use tokio::runtime::Runtime;
 
 mod consensus;
+mod transaction_filter;
 
 #[derive(Default)]
 pub struct Context {
     authentication_configs: Vec<AuthenticationConfig>,
 
+    execution_filter: Arc<ReloadableFilter>,
+    simulation_filter: Arc<ReloadableFilter>,
+
     aptos_db: RwLock<Option<Arc<DbReaderWriter>>>,
     consensus_db: RwLock<Option<Arc<StorageWriteProxy>>>,
     quorum_store_db: RwLock<Option<Arc<QuorumStoreDB>>>,
@@ -83,6 +89,12 @@ impl AdminService {
             runtime,
             context: Arc::new(Context {
                 authentication_configs: node_config.admin_service.authentication_configs.clone(),
+                execution_filter: Arc::new(ReloadableFilter::new(
+                    node_config.execution.transaction_filter.clone(),
+                )),
+                simulation_filter: Arc::new(ReloadableFilter::new(
+                    node_config.api.simulation_filter.clone(),
+                )),
                 ..Default::default()
             }),
         };
@@ -98,6 +110,16 @@ impl AdminService {
         self.context.set_aptos_db(aptos_db)
     }
 
+    /// Returns the consensus execution filter, which can be replaced through the admin service
+    pub fn execution_filter(&self) -> Arc<ReloadableFilter> {
+        self.context.execution_filter.clone()
+    }
+
+    /// Returns the API simulation filter, which can be replaced through the admin service
+    pub fn simulation_filter(&self) -> Arc<ReloadableFilter> {
+        self.context.simulation_filter.clone()
+    }
+
     pub fn set_consensus_dbs(
         &self,
         consensus_db: Arc<StorageWriteProxy>,
@@ -159,6 +181,21 @@ impl AdminService {
             }
         };
 
+        // Replacing a transaction filter changes which transactions the node executes, so it
+        // requires an authenticated request, even if the rest of the service is open.
+        if req.method() == hyper::Method::POST
+            && req.uri().path().starts_with("/debug/filters/")
+            && (context.authentication_configs.is_empty() || !authenticated)
+        {
+            return Ok(reply_with_status(
+                StatusCode::FORBIDDEN,
+                format!(
+                    "{} endpoint requires an authentication config and an authenticated request.",
+                    req.uri().path()
+                ),
+            ));
+        }
+
         if !authenticated {
             return Ok(reply_with_status(
                 StatusCode::NETWORK_AUTHENTICATION_REQUIRED,
@@ -210,7 +247,142 @@ impl AdminService {
                     ))
                 }
             },
+            (hyper::Method::GET, "/debug/filters/execution") => {
+                transaction_filter::handle_get_filter_request(req, context.execution_filter.clone())
+                    .await
+            },
+            (hyper::Method::POST, "/debug/filters/execution") => {
+                transaction_filter::handle_update_filter_request(
+                    req,
+                    "execution",
+                    context.execution_filter.clone(),
+                    true,
+                )
+                .await
+            },
+            (hyper::Method::POST, "/debug/filters/execution/dry_run") => {
+                Self::dry_run_filter(context.clone(), req, context.execution_filter.clone(), true)
+                    .await
+            },
+            (hyper::Method::GET, "/debug/filters/simulation") => {
+                transaction_filter::handle_get_filter_request(
+                    req,
+                    context.simulation_filter.clone(),
+                )
+                .await
+            },
+            (hyper::Method::POST, "/debug/filters/simulation") => {
+                // We don't support Block ID based simulation filters (see the ApiConfig sanitizer)
+                transaction_filter::handle_update_filter_request(
+                    req,
+                    "simulation",
+                    context.simulation_filter.clone(),
+                    false,
+                )
+                .await
+            },
+            (hyper::Method::POST, "/debug/filters/simulation/dry_run") => {
+                Self::dry_run_filter(
+                    context.clone(),
+                    req,
+                    context.simulation_filter.clone(),
+                    false,
+                )
+                .await
+            },
             _ => Ok(reply_with_status(StatusCode::NOT_FOUND, "Not found.")),
         }
     }
+
+    async fn dry_run_filter(
+        context: Arc<Context>,
+        req: Request<Body>,
+        filter: Arc<ReloadableFilter>,
+        allow_block_id_matchers: bool,
+    ) -> hyper::Result<Response<Body>> {
+        let aptos_db = context.aptos_db.read().clone();
+        if let Some(aptos_db) = aptos_db {
+            transaction_filter::handle_dry_run_filter_request(
+                req,
+                filter,
+                aptos_db.reader.clone(),
+                allow_block_id_matchers,
+            )
+            .await
+        } else {
+            Ok(reply_with_status(
+                StatusCode::NOT_FOUND,
+                "AptosDB is not available.",
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_config::config::transaction_filter_type::Filter;
+    use aptos_types::account_address::AccountAddress;
+
+    const PASSCODE: &str = "abc";
+
+    fn create_context(authentication_configs: Vec<AuthenticationConfig>) -> Arc<Context> {
+        Arc::new(Context {
+            authentication_configs,
+            ..Default::default()
+        })
+    }
+
+    fn update_filter_request(uri: &str) -> Request<Body> {
+        let filter = Filter::empty().add_deny_sender(AccountAddress::ONE);
+        Request::post(uri)
+            .body(Body::from(serde_yaml::to_string(&filter).unwrap()))
+            .unwrap()
+    }
+
+    async fn serve(context: &Arc<Context>, req: Request<Body>) -> StatusCode {
+        AdminService::serve_requests(context.clone(), req, true)
+            .await
+            .unwrap()
+            .status()
+    }
+
+    #[tokio::test]
+    async fn test_update_filter_without_authentication_config() {
+        let context = create_context(vec![]);
+        for uri in [
+            "/debug/filters/execution",
+            "/debug/filters/execution/dry_run",
+            "/debug/filters/simulation",
+            "/debug/filters/simulation/dry_run",
+        ] {
+            let status = serve(&context, update_filter_request(uri)).await;
+            assert_eq!(status, StatusCode::FORBIDDEN);
+        }
+        assert_eq!(context.execution_filter.load().version, 0);
+        assert_eq!(context.simulation_filter.load().version, 0);
+
+        // Reading the filters does not require authentication
+        let req = Request::get("/debug/filters/execution")
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(serve(&context, req).await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_update_filter_with_wrong_passcode() {
+        let context = create_context(vec![AuthenticationConfig::PasscodeSha256(sha256::digest(
+            PASSCODE,
+        ))]);
+
+        let req = update_filter_request("/debug/filters/execution?passcode=wrong");
+        assert_eq!(serve(&context, req).await, StatusCode::FORBIDDEN);
+        let req = update_filter_request("/debug/filters/execution");
+        assert_eq!(serve(&context, req).await, StatusCode::FORBIDDEN);
+        assert_eq!(context.execution_filter.load().version, 0);
+
+        let req = update_filter_request(&format!("/debug/filters/execution?passcode={PASSCODE}"));
+        assert_eq!(serve(&context, req).await, StatusCode::OK);
+        assert_eq!(context.execution_filter.load().version, 1);
+    }
 }
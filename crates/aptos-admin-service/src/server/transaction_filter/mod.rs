@@ -0,0 +1,467 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use anyhow::{bail, Error};
+use aptos_config::config::transaction_filter_type::{Filter, ReloadableFilter};
+use aptos_crypto::HashValue;
+use aptos_logger::{info, warn};
+use aptos_storage_interface::DbReader;
+use aptos_system_utils::utils::{reply_with, reply_with_status, spawn_blocking};
+use aptos_types::transaction::{Transaction, Version};
+use hyper::{Body, Request, Response, StatusCode};
+use serde::Serialize;
+use std::{collections::HashMap, sync::Arc};
+
+const DEFAULT_DRY_RUN_BLOCKS: u64 = 10;
+const MAX_DRY_RUN_BLOCKS: u64 = 1000;
+
+/// A transaction that would be treated differently by the candidate filter
+#[derive(Debug, Serialize)]
+struct ChangedTransaction {
+    version: Version,
+    hash: HashValue,
+    sender: String,
+}
+
+/// The result of evaluating a candidate filter against recently committed blocks
+#[derive(Debug, Default, Serialize)]
+struct DryRunReport {
+    active_filter_version: u64,
+    first_version: Version,
+    last_version: Version,
+    num_blocks: u64,
+    num_user_transactions: u64,
+    num_denied_by_active_filter: u64,
+    num_denied_by_candidate_filter: u64,
+    newly_denied_transactions: Vec<ChangedTransaction>,
+    newly_allowed_transactions: Vec<ChangedTransaction>,
+}
+
+pub async fn handle_get_filter_request(
+    _req: Request<Body>,
+    filter: Arc<ReloadableFilter>,
+) -> hyper::Result<Response<Body>> {
+    match serde_yaml::to_string(filter.load().as_ref()) {
+        Ok(result) => Ok(reply_with(vec![], result)),
+        Err(e) => Ok(reply_with_status(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            e.to_string(),
+        )),
+    }
+}
+
+/// Replaces the active filter with the (YAML encoded) filter in the request body. The change
+/// is logged so that every update to a filter can be audited.
+pub async fn handle_update_filter_request(
+    req: Request<Body>,
+    filter_name: &'static str,
+    filter: Arc<ReloadableFilter>,
+    allow_block_id_matchers: bool,
+) -> hyper::Result<Response<Body>> {
+    let new_filter = match parse_filter(req, allow_block_id_matchers).await {
+        Ok(new_filter) => new_filter,
+        Err(e) => return Ok(reply_with_status(StatusCode::BAD_REQUEST, e.to_string())),
+    };
+
+    let new_filter_yaml = serde_yaml::to_string(&new_filter).unwrap_or_default();
+    let old_filter = filter.store(new_filter);
+    let new_version = old_filter.version + 1;
+    warn!(
+        filter_name = filter_name,
+        old_version = old_filter.version,
+        new_version = new_version,
+        old_filter = ?old_filter.filter,
+        new_filter = new_filter_yaml,
+        "Transaction filter replaced through the admin service."
+    );
+
+    Ok(reply_with(
+        vec![],
+        format!("Replaced {filter_name} filter, new version: {new_version}.\n"),
+    ))
+}
+
+/// Evaluates the (YAML encoded) filter in the request body against the last N committed
+/// blocks, without changing the active filter. N is given by the `blocks` query parameter.
+pub async fn handle_dry_run_filter_request(
+    req: Request<Body>,
+    filter: Arc<ReloadableFilter>,
+    aptos_db: Arc<dyn DbReader>,
+    allow_block_id_matchers: bool,
+) -> hyper::Result<Response<Body>> {
+    let query = req.uri().query().unwrap_or("");
+    let query_pairs: HashMap<_, _> = url::form_urlencoded::parse(query.as_bytes()).collect();
+
+    let num_blocks: u64 = match query_pairs.get("blocks") {
+        Some(val) => match val.parse() {
+            Ok(val) => val,
+            Err(err) => return Ok(reply_with_status(StatusCode::BAD_REQUEST, err.to_string())),
+        },
+        None => DEFAULT_DRY_RUN_BLOCKS,
+    };
+    if num_blocks == 0 || num_blocks > MAX_DRY_RUN_BLOCKS {
+        return Ok(reply_with_status(
+            StatusCode::BAD_REQUEST,
+            format!("blocks must be between 1 and {MAX_DRY_RUN_BLOCKS}."),
+        ));
+    }
+
+    let candidate_filter = match parse_filter(req, allow_block_id_matchers).await {
+        Ok(candidate_filter) => candidate_filter,
+        Err(e) => return Ok(reply_with_status(StatusCode::BAD_REQUEST, e.to_string())),
+    };
+
+    info!("Dry running transaction filter against the last {num_blocks} block(s).");
+
+    match spawn_blocking(move || {
+        dry_run_filter(
+            aptos_db.as_ref(),
+            filter.as_ref(),
+            &candidate_filter,
+            num_blocks,
+        )
+        .and_then(|report| serde_yaml::to_string(&report).map_err(Error::msg))
+    })
+    .await
+    {
+        Ok(result) => {
+            info!("Finished dry running transaction filter.");
+            Ok(reply_with(vec![], result))
+        },
+        Err(e) => {
+            info!("Failed to dry run transaction filter: {e:?}");
+            Ok(reply_with_status(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                e.to_string(),
+            ))
+        },
+    }
+}
+
+async fn parse_filter(req: Request<Body>, allow_block_id_matchers: bool) -> anyhow::Result<Filter> {
+    let body = hyper::body::to_bytes(req.into_body()).await?;
+    let filter: Filter = serde_yaml::from_slice(&body)?;
+
+    if !allow_block_id_matchers
+        && filter
+            .rules()
+            .iter()
+            .any(|rule| rule.matcher().contains_block_id())
+    {
+        bail!("Block ID based matchers are not supported for this filter!");
+    }
+
+    Ok(filter)
+}
+
+fn dry_run_filter(
+    aptos_db: &dyn DbReader,
+    active_filter: &ReloadableFilter,
+    candidate_filter: &Filter,
+    num_blocks: u64,
+) -> anyhow::Result<DryRunReport> {
+    let active_filter = active_filter.load();
+    let mut report = DryRunReport {
+        active_filter_version: active_filter.version,
+        ..Default::default()
+    };
+
+    let mut version = aptos_db.get_latest_ledger_info_version()?;
+    report.last_version = version;
+    while report.num_blocks < num_blocks {
+        let (start_version, end_version, new_block_event) =
+            aptos_db.get_block_info_by_version(version)?;
+        let block_id = new_block_event.hash()?;
+        let timestamp = new_block_event.proposed_time();
+
+        let txns =
+            aptos_db.get_transaction_iterator(start_version, end_version - start_version + 1)?;
+        for (txn_version, txn) in (start_version..).zip(txns) {
+            let txn = match txn? {
+                Transaction::UserTransaction(txn) => txn,
+                _ => continue,
+            };
+            report.num_user_transactions += 1;
+
+            let allowed_by_active = active_filter.filter.allows(block_id, timestamp, &txn);
+            let allowed_by_candidate = candidate_filter.allows(block_id, timestamp, &txn);
+            if !allowed_by_active {
+                report.num_denied_by_active_filter += 1;
+            }
+            if !allowed_by_candidate {
+                report.num_denied_by_candidate_filter += 1;
+            }
+
+            let changed_txn = || ChangedTransaction {
+                version: txn_version,
+                hash: txn.committed_hash(),
+                sender: txn.sender().to_hex_literal(),
+            };
+            match (allowed_by_active, allowed_by_candidate) {
+                (true, false) => report.newly_denied_transactions.push(changed_txn()),
+                (false, true) => report.newly_allowed_transactions.push(changed_txn()),
+                _ => {},
+            }
+        }
+
+        report.first_version = start_version;
+        report.num_blocks += 1;
+        if start_version == 0 {
+            break;
+        }
+        version = start_version - 1;
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_crypto::{ed25519::Ed25519PrivateKey, PrivateKey, SigningKey, Uniform};
+    use aptos_storage_interface::{DbReader, Result};
+    use aptos_types::{
+        account_address::AccountAddress,
+        account_config::NewBlockEvent,
+        chain_id::ChainId,
+        transaction::{RawTransaction, Script, SignedTransaction, TransactionPayload},
+    };
+
+    /// A DB holding a single block with the given user transactions
+    struct MockDb {
+        txns: Vec<Transaction>,
+    }
+
+    impl MockDb {
+        fn new(senders: &[AccountAddress]) -> Self {
+            let txns = senders
+                .iter()
+                .map(|sender| Transaction::UserTransaction(create_signed_transaction(*sender)))
+                .collect();
+            Self { txns }
+        }
+    }
+
+    impl DbReader for MockDb {
+        fn get_latest_ledger_info_version(&self) -> Result<Version> {
+            Ok(self.txns.len() as Version - 1)
+        }
+
+        fn get_block_info_by_version(
+            &self,
+            _version: Version,
+        ) -> Result<(Version, Version, NewBlockEvent)> {
+            let new_block_event = NewBlockEvent::new(
+                AccountAddress::ONE,
+                0,
+                0,
+                0,
+                vec![],
+                AccountAddress::ONE,
+                vec![],
+                0,
+            );
+            Ok((0, self.txns.len() as Version - 1, new_block_event))
+        }
+
+        fn get_transaction_iterator(
+            &self,
+            start_version: Version,
+            limit: u64,
+        ) -> Result<Box<dyn Iterator<Item = Result<Transaction>> + '_>> {
+            Ok(Box::new(
+                self.txns
+                    .iter()
+                    .skip(start_version as usize)
+                    .take(limit as usize)
+                    .cloned()
+                    .map(Ok),
+            ))
+        }
+    }
+
+    fn create_signed_transaction(sender: AccountAddress) -> SignedTransaction {
+        let private_key = Ed25519PrivateKey::generate_for_testing();
+        let raw_transaction = RawTransaction::new(
+            sender,
+            0,
+            TransactionPayload::Script(Script::new(vec![], vec![], vec![])),
+            0,
+            0,
+            0,
+            ChainId::new(10),
+        );
+        SignedTransaction::new(
+            raw_transaction.clone(),
+            private_key.public_key(),
+            private_key.sign(&raw_transaction).unwrap(),
+        )
+    }
+
+    fn filter_request(uri: &str, filter: &Filter) -> Request<Body> {
+        Request::post(uri)
+            .body(Body::from(serde_yaml::to_string(filter).unwrap()))
+            .unwrap()
+    }
+
+    async fn response_body(response: Response<Body>) -> String {
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        String::from_utf8(body.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_update_filter() {
+        let filter = Arc::new(ReloadableFilter::new(Filter::empty()));
+        let new_filter = Filter::empty().add_deny_sender(AccountAddress::ONE);
+
+        let response = handle_update_filter_request(
+            filter_request("/debug/filters/execution", &new_filter),
+            "execution",
+            filter.clone(),
+            false,
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response_body(response).await.contains("new version: 1"));
+
+        let active_filter = filter.load();
+        assert_eq!(active_filter.version, 1);
+        assert_eq!(active_filter.filter, new_filter);
+
+        // The updated filter is returned by the get handler
+        let response = handle_get_filter_request(Request::default(), filter)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let returned_filter: Filter = serde_yaml::from_str(&response_body(response).await).unwrap();
+        assert_eq!(returned_filter, new_filter);
+    }
+
+    #[tokio::test]
+    async fn test_update_filter_invalid() {
+        let old_filter = Filter::empty().add_deny_sender(AccountAddress::ONE);
+        let filter = Arc::new(ReloadableFilter::new(old_filter.clone()));
+
+        let request = Request::post("/debug/filters/execution")
+            .body(Body::from("rules: [NotARule]"))
+            .unwrap();
+        let response = handle_update_filter_request(request, "execution", filter.clone(), false)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let active_filter = filter.load();
+        assert_eq!(active_filter.version, 0);
+        assert_eq!(active_filter.filter, old_filter);
+    }
+
+    #[tokio::test]
+    async fn test_update_filter_block_id() {
+        let filter = Arc::new(ReloadableFilter::new(Filter::empty()));
+        let new_filter = Filter::empty().add_deny_block_id(HashValue::random());
+
+        // Block ID matchers are rejected unless the filter supports them
+        let response = handle_update_filter_request(
+            filter_request("/debug/filters/simulation", &new_filter),
+            "simulation",
+            filter.clone(),
+            false,
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert!(response_body(response).await.contains("Block ID"));
+        assert_eq!(filter.load().version, 0);
+
+        // Block ID matchers nested in other matchers are rejected as well
+        let nested_filter: Filter = serde_yaml::from_str(&format!(
+            "rules:\n  - Deny:\n      Not:\n        BlockId: \"{}\"\n",
+            HashValue::random().to_hex()
+        ))
+        .unwrap();
+        assert!(nested_filter.rules()[0].matcher().contains_block_id());
+        let response = handle_update_filter_request(
+            filter_request("/debug/filters/simulation", &nested_filter),
+            "simulation",
+            filter.clone(),
+            false,
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(filter.load().version, 0);
+
+        let response = handle_update_filter_request(
+            filter_request("/debug/filters/execution", &new_filter),
+            "execution",
+            filter.clone(),
+            true,
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(filter.load().filter, new_filter);
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_filter() {
+        let denied_sender = AccountAddress::random();
+        let aptos_db = Arc::new(MockDb::new(&[
+            AccountAddress::random(),
+            denied_sender,
+            AccountAddress::random(),
+        ]));
+        let old_filter = Filter::empty().add_deny_sender(AccountAddress::ONE);
+        let filter = Arc::new(ReloadableFilter::new(old_filter.clone()));
+        let candidate_filter = Filter::empty().add_deny_sender(denied_sender);
+
+        let response = handle_dry_run_filter_request(
+            filter_request(
+                "/debug/filters/execution/dry_run?blocks=1",
+                &candidate_filter,
+            ),
+            filter.clone(),
+            aptos_db,
+            false,
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let report: serde_yaml::Value =
+            serde_yaml::from_str(&response_body(response).await).unwrap();
+        assert_eq!(report["num_blocks"], 1);
+        assert_eq!(report["num_user_transactions"], 3);
+        assert_eq!(report["num_denied_by_active_filter"], 0);
+        assert_eq!(report["num_denied_by_candidate_filter"], 1);
+        let newly_denied = report["newly_denied_transactions"].as_sequence().unwrap();
+        assert_eq!(newly_denied.len(), 1);
+        assert_eq!(newly_denied[0]["version"], 1);
+        assert_eq!(newly_denied[0]["sender"], denied_sender.to_hex_literal());
+
+        // The active filter is left unchanged
+        let active_filter = filter.load();
+        assert_eq!(active_filter.version, 0);
+        assert_eq!(active_filter.filter, old_filter);
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_filter_invalid_blocks() {
+        let filter = Arc::new(ReloadableFilter::new(Filter::empty()));
+        for query in ["blocks=0", "blocks=1001", "blocks=many"] {
+            let response = handle_dry_run_filter_request(
+                filter_request(
+                    &format!("/debug/filters/execution/dry_run?{query}"),
+                    &Filter::empty(),
+                ),
+                filter.clone(),
+                Arc::new(MockDb::new(&[AccountAddress::ONE])),
+                false,
+            )
+            .await
+            .unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        }
+    }
+}
@@ -6,20 +6,28 @@ mod google_captcha;
 mod ip_blocklist;
 mod magic_header;
 mod memory_ratelimit;
+mod quota;
 mod redis_ratelimit;
 mod referer_blocklist;
 mod tap_captcha;
+mod webhook;
 
-pub use self::tap_captcha::CaptchaManager;
 use self::{
     auth_token::AuthTokenChecker,
     google_captcha::{CaptchaChecker as GoogleCaptchaChecker, GoogleCaptchaCheckerConfig},
     ip_blocklist::IpBlocklistChecker,
     magic_header::{MagicHeaderChecker, MagicHeaderCheckerConfig},
     memory_ratelimit::{MemoryRatelimitChecker, MemoryRatelimitCheckerConfig},
+    quota::QuotaCheckerConfig,
     redis_ratelimit::{RedisRatelimitChecker, RedisRatelimitCheckerConfig},
     referer_blocklist::RefererBlocklistChecker,
     tap_captcha::{TapCaptchaChecker, TapCaptchaCheckerConfig},
+    webhook::{WebhookChecker, WebhookCheckerConfig},
+};
+pub use self::{
+    quota::{QuotaChecker, QuotaUsage},
+    tap_captcha::CaptchaManager,
+    webhook::{sign_webhook_request, WEBHOOK_SIGNATURE_HEADER, WEBHOOK_TIMESTAMP_HEADER},
 };
 use crate::{
     common::{IpRangeManagerConfig, ListManagerConfig},
@@ -96,6 +104,10 @@ pub enum CheckerConfig {
     /// Basic in memory ratelimiter that allows a single successful request per IP.
     MemoryRatelimit(MemoryRatelimitCheckerConfig),
 
+    /// Per account, subnet and ASN quotas on requests and amount funded, with
+    /// the usage ledger kept in Redis.
+    Quota(QuotaCheckerConfig),
+
     /// Ratelimiter that uses Redis.
     RedisRatelimit(RedisRatelimitCheckerConfig),
 
@@ -104,6 +116,9 @@ pub enum CheckerConfig {
 
     /// In-house captcha solution.
     TapCaptcha(TapCaptchaCheckerConfig),
+
+    /// Delegates the decision to an external service over HTTP.
+    Webhook(WebhookCheckerConfig),
 }
 
 impl CheckerConfig {
@@ -118,6 +133,7 @@ impl CheckerConfig {
             CheckerConfig::MemoryRatelimit(config) => {
                 Checker::from(MemoryRatelimitChecker::new(config))
             },
+            CheckerConfig::Quota(config) => Checker::from(QuotaChecker::new(config).await?),
             CheckerConfig::RedisRatelimit(config) => {
                 Checker::from(RedisRatelimitChecker::new(config).await?)
             },
@@ -127,6 +143,7 @@ impl CheckerConfig {
             CheckerConfig::TapCaptcha(config) => {
                 Checker::from(TapCaptchaChecker::new(config, captcha_manager)?)
             },
+            CheckerConfig::Webhook(config) => Checker::from(WebhookChecker::new(config)?),
         })
     }
 }
@@ -139,15 +156,19 @@ pub enum Checker {
     IpBlocklistChecker,
     MagicHeaderChecker,
     MemoryRatelimitChecker,
+    QuotaChecker,
     RedisRatelimitChecker,
     RefererBlocklistChecker,
     TapCaptchaChecker,
+    WebhookChecker,
 }
 
 #[derive(Clone, Debug)]
 pub struct CheckerData {
     pub time_request_received_secs: u64,
     pub receiver: AccountAddress,
+    /// The amount the Funder will fund, assuming the checkers aren't bypassed.
+    pub amount: u64,
     pub source_ip: IpAddr,
     pub headers: Arc<HeaderMap>,
 }
@@ -0,0 +1,474 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{redis_ratelimit::RedisDatabaseConfig, CheckerData, CheckerTrait, CompleteData};
+use crate::{
+    common::{ListManager, ListManagerConfig},
+    endpoints::{AptosTapError, AptosTapErrorCode, RejectionReason, RejectionReasonCode},
+    helpers::{days_since_tap_epoch, get_current_time_secs, seconds_until_next_day},
+};
+use anyhow::{bail, Context, Result};
+use aptos_logger::debug;
+use aptos_sdk::types::account_address::AccountAddress;
+use async_trait::async_trait;
+use deadpool_redis::{Connection, Pool};
+use ipnet::IpNet;
+use maxminddb::{geoip2, Reader};
+use poem::http::{header::AUTHORIZATION, HeaderMap};
+use poem_openapi::Object;
+use serde::{Deserialize, Serialize};
+use std::{net::IpAddr, path::PathBuf};
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct QuotaLimits {
+    /// Max number of requests per day. 500s are not counted.
+    pub max_requests_per_day: Option<u64>,
+
+    /// Max total amount funded per day. 500s are not counted.
+    pub max_amount_per_day: Option<u64>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SubnetQuotaConfig {
+    /// IPv4 addresses are grouped into subnets of this prefix length.
+    #[serde(default = "SubnetQuotaConfig::default_ipv4_prefix_len")]
+    pub ipv4_prefix_len: u8,
+
+    /// IPv6 addresses are grouped into subnets of this prefix length.
+    #[serde(default = "SubnetQuotaConfig::default_ipv6_prefix_len")]
+    pub ipv6_prefix_len: u8,
+
+    #[serde(flatten)]
+    pub limits: QuotaLimits,
+}
+
+impl SubnetQuotaConfig {
+    fn default_ipv4_prefix_len() -> u8 {
+        24
+    }
+
+    fn default_ipv6_prefix_len() -> u8 {
+        48
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct AsnQuotaConfig {
+    /// Path to a MaxMind ASN database file (e.g. GeoLite2-ASN.mmdb) used to
+    /// look up the autonomous system of the source IP. Requests from IPs not
+    /// in the database aren't subject to this quota.
+    pub asn_database_file: PathBuf,
+
+    #[serde(flatten)]
+    pub limits: QuotaLimits,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct QuotaCheckerConfig {
+    #[serde(flatten)]
+    pub database: RedisDatabaseConfig,
+
+    /// Quota for each receiver account.
+    pub per_account: Option<QuotaLimits>,
+
+    /// Quota for each subnet the source IP is in.
+    pub per_subnet: Option<SubnetQuotaConfig>,
+
+    /// Quota for each autonomous system the source IP is in.
+    pub per_asn: Option<AsnQuotaConfig>,
+
+    /// Auth tokens allowed to read the ledger through the quota usage
+    /// endpoint. The endpoint is disabled if this is not set.
+    pub usage_auth_tokens: Option<ListManagerConfig>,
+
+    /// How many days to keep the usage of a day for after it ends. If not
+    /// set, the ledger is kept forever.
+    pub retention_days: Option<u64>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum QuotaDimension {
+    Account,
+    Subnet,
+    Asn,
+}
+
+impl QuotaDimension {
+    fn name(&self) -> &'static str {
+        match self {
+            QuotaDimension::Account => "account",
+            QuotaDimension::Subnet => "subnet",
+            QuotaDimension::Asn => "asn",
+        }
+    }
+
+    fn rejection_reason_code(&self) -> RejectionReasonCode {
+        match self {
+            QuotaDimension::Account => RejectionReasonCode::AccountUsageLimitExhausted,
+            QuotaDimension::Subnet => RejectionReasonCode::SubnetUsageLimitExhausted,
+            QuotaDimension::Asn => RejectionReasonCode::AsnUsageLimitExhausted,
+        }
+    }
+}
+
+/// A single thing we track usage for, e.g. a particular account.
+struct QuotaBucket<'a> {
+    dimension: QuotaDimension,
+    id: String,
+    limits: &'a QuotaLimits,
+}
+
+impl<'a> QuotaBucket<'a> {
+    fn key(&self, day: u64) -> String {
+        format!("quota:{}:{}:{}", self.dimension.name(), self.id, day)
+    }
+
+    /// Returns a rejection reason if the given usage, including that of the
+    /// request being checked, is over the limits.
+    fn check_usage(
+        &self,
+        requests: u64,
+        amount: u64,
+        seconds_until_next_day: u64,
+    ) -> Option<RejectionReason> {
+        let reason = if self
+            .limits
+            .max_requests_per_day
+            .map_or(false, |max| requests > max)
+        {
+            format!(
+                "The {} {} has reached the maximum allowed number of requests per day: {}",
+                self.dimension.name(),
+                self.id,
+                self.limits.max_requests_per_day.unwrap_or_default(),
+            )
+        } else if self
+            .limits
+            .max_amount_per_day
+            .map_or(false, |max| amount > max)
+        {
+            format!(
+                "The {} {} has reached the maximum allowed amount funded per day: {}",
+                self.dimension.name(),
+                self.id,
+                self.limits.max_amount_per_day.unwrap_or_default(),
+            )
+        } else {
+            return None;
+        };
+        Some(
+            RejectionReason::new(reason, self.dimension.rejection_reason_code())
+                .retry_after(seconds_until_next_day),
+        )
+    }
+}
+
+/// Usage of a quota bucket on a given day, as reported by the quota usage
+/// endpoint.
+#[derive(Clone, Debug, Object)]
+pub struct QuotaUsage {
+    /// What the usage is tracked by: account, subnet or asn.
+    pub dimension: String,
+    /// The account address, subnet or AS number.
+    pub id: String,
+    /// Number of requests counted against the quota on the day.
+    pub requests: u64,
+    /// Total amount funded on the day.
+    pub amount: u64,
+    /// The configured limit on the number of requests per day, if any.
+    pub max_requests_per_day: Option<u64>,
+    /// The configured limit on the amount funded per day, if any.
+    pub max_amount_per_day: Option<u64>,
+}
+
+/// The QuotaChecker tracks usage per receiver account, per subnet and per
+/// autonomous system of the source IP, both in number of requests and in
+/// total amount funded, and rejects requests over the configured daily
+/// limits. Unlike the RedisRatelimitChecker, this can't be circumvented by
+/// rotating IPs within a provider while draining funds into the same accounts.
+///
+/// The ledger is kept in Redis, with one hash per bucket per day. Unlike the
+/// counters of the RedisRatelimitChecker, these don't expire at the end of the
+/// day, so the usage of past days stays around for reporting, for as long as
+/// `retention_days` says. For the ledger to survive restarts, Redis must be
+/// configured with persistence (e.g. AOF). It uses the same order of operations
+/// as the RedisRatelimitChecker to avoid exceeding the limits with concurrent
+/// requests.
+pub struct QuotaChecker {
+    config: QuotaCheckerConfig,
+    db_pool: Pool,
+    asn_reader: Option<Reader<Vec<u8>>>,
+    usage_auth_tokens: Option<ListManager>,
+}
+
+impl QuotaChecker {
+    pub async fn new(config: QuotaCheckerConfig) -> Result<Self> {
+        if let Some(per_subnet) = &config.per_subnet {
+            if per_subnet.ipv4_prefix_len > 32 || per_subnet.ipv6_prefix_len > 128 {
+                bail!("Invalid subnet prefix length in {:?}", per_subnet);
+            }
+        }
+        let asn_reader = match &config.per_asn {
+            Some(per_asn) => Some(
+                Reader::open_readfile(&per_asn.asn_database_file).with_context(|| {
+                    format!(
+                        "Failed to open ASN database at {}",
+                        per_asn.asn_database_file.to_string_lossy()
+                    )
+                })?,
+            ),
+            None => None,
+        };
+        let usage_auth_tokens = match &config.usage_auth_tokens {
+            Some(usage_auth_tokens) => Some(ListManager::new(usage_auth_tokens.clone())?),
+            None => None,
+        };
+
+        let db_pool = config.database.build_db_pool()?;
+
+        // Ensure we can connect.
+        db_pool
+            .get()
+            .await
+            .context("Failed to connect to redis on startup")?;
+
+        Ok(Self {
+            config,
+            db_pool,
+            asn_reader,
+            usage_auth_tokens,
+        })
+    }
+
+    /// Ensures the request carries an auth token allowed to read the ledger.
+    pub fn check_usage_auth(&self, headers: &HeaderMap) -> Result<(), AptosTapError> {
+        let usage_auth_tokens = self.usage_auth_tokens.as_ref().ok_or_else(|| {
+            AptosTapError::new(
+                "The quota usage endpoint is not enabled".to_string(),
+                AptosTapErrorCode::EndpointNotEnabled,
+            )
+        })?;
+        let authorized = headers
+            .get(AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.split_whitespace().nth(1))
+            .map_or(false, |auth_token| usage_auth_tokens.contains(auth_token));
+        if authorized {
+            Ok(())
+        } else {
+            Err(AptosTapError::new(
+                "Reading the quota usage requires an allowed auth token in the form of 'Bearer <token>'".to_string(),
+                AptosTapErrorCode::Rejected,
+            )
+            .rejection_reasons(vec![RejectionReason::new(
+                "The auth token is missing or not allowed to read the quota usage".to_string(),
+                RejectionReasonCode::AuthTokenInvalid,
+            )]))
+        }
+    }
+
+    async fn get_redis_connection(&self) -> Result<Connection, AptosTapError> {
+        self.db_pool.get().await.map_err(|e| {
+            AptosTapError::new_with_error_code(
+                format!("Failed to connect to redis storage: {}", e),
+                AptosTapErrorCode::StorageError,
+            )
+        })
+    }
+
+    /// Returns the buckets the given receiver and source IP count against.
+    fn buckets(
+        &self,
+        receiver: Option<AccountAddress>,
+        source_ip: Option<IpAddr>,
+    ) -> Vec<QuotaBucket> {
+        let mut buckets = Vec::new();
+        if let (Some(limits), Some(receiver)) = (&self.config.per_account, receiver) {
+            buckets.push(QuotaBucket {
+                dimension: QuotaDimension::Account,
+                id: receiver.to_hex_literal(),
+                limits,
+            });
+        }
+        if let (Some(per_subnet), Some(source_ip)) = (&self.config.per_subnet, source_ip) {
+            let prefix_len = match source_ip {
+                IpAddr::V4(_) => per_subnet.ipv4_prefix_len,
+                IpAddr::V6(_) => per_subnet.ipv6_prefix_len,
+            };
+            let subnet = IpNet::new(source_ip, prefix_len).expect("Checked in new()");
+            buckets.push(QuotaBucket {
+                dimension: QuotaDimension::Subnet,
+                id: subnet.trunc().to_string(),
+                limits: &per_subnet.limits,
+            });
+        }
+        if let (Some(per_asn), Some(reader), Some(source_ip)) =
+            (&self.config.per_asn, &self.asn_reader, source_ip)
+        {
+            match reader.lookup::<geoip2::Asn>(source_ip) {
+                Ok(geoip2::Asn {
+                    autonomous_system_number: Some(asn),
+                    ..
+                }) => buckets.push(QuotaBucket {
+                    dimension: QuotaDimension::Asn,
+                    id: asn.to_string(),
+                    limits: &per_asn.limits,
+                }),
+                _ => debug!(
+                    message = "No ASN found for IP, skipping ASN quota",
+                    source_ip = source_ip
+                ),
+            }
+        }
+        buckets
+    }
+
+    /// Returns the usage on the given day of the buckets the given receiver
+    /// and source IP count against.
+    pub async fn get_usage(
+        &self,
+        receiver: Option<AccountAddress>,
+        source_ip: Option<IpAddr>,
+        day: u64,
+    ) -> Result<Vec<QuotaUsage>, AptosTapError> {
+        let buckets = self.buckets(receiver, source_ip);
+        let mut conn = self.get_redis_connection().await?;
+        let mut usages = Vec::new();
+        for bucket in buckets {
+            let (requests, amount) = get_bucket_usage(&mut conn, &bucket.key(day)).await?;
+            usages.push(QuotaUsage {
+                dimension: bucket.dimension.name().to_string(),
+                id: bucket.id,
+                requests,
+                amount,
+                max_requests_per_day: bucket.limits.max_requests_per_day,
+                max_amount_per_day: bucket.limits.max_amount_per_day,
+            });
+        }
+        Ok(usages)
+    }
+}
+
+async fn get_bucket_usage(conn: &mut Connection, key: &str) -> Result<(u64, u64), AptosTapError> {
+    let (requests, amount): (Option<u64>, Option<u64>) = redis::cmd("HMGET")
+        .arg(key)
+        .arg("requests")
+        .arg("amount")
+        .query_async(&mut **conn)
+        .await
+        .map_err(|e| {
+            AptosTapError::new_with_error_code(
+                format!("Failed to get value for redis key {}: {}", key, e),
+                AptosTapErrorCode::StorageError,
+            )
+        })?;
+    Ok((requests.unwrap_or(0), amount.unwrap_or(0)))
+}
+
+#[async_trait]
+impl CheckerTrait for QuotaChecker {
+    async fn check(
+        &self,
+        data: CheckerData,
+        dry_run: bool,
+    ) -> Result<Vec<RejectionReason>, AptosTapError> {
+        let buckets = self.buckets(Some(data.receiver), Some(data.source_ip));
+        if buckets.is_empty() {
+            return Ok(vec![]);
+        }
+        let mut conn = self.get_redis_connection().await?;
+        let now_secs = get_current_time_secs();
+        let day = days_since_tap_epoch(now_secs);
+        let seconds_until_next_day = seconds_until_next_day(now_secs);
+
+        // First check the current usage, so that we don't count requests
+        // that would be rejected anyway.
+        let mut rejection_reasons = Vec::new();
+        for bucket in &buckets {
+            let (requests, amount) = get_bucket_usage(&mut conn, &bucket.key(day)).await?;
+            rejection_reasons.extend(bucket.check_usage(
+                requests + 1,
+                amount.saturating_add(data.amount),
+                seconds_until_next_day,
+            ));
+        }
+        if !rejection_reasons.is_empty() || dry_run {
+            return Ok(rejection_reasons);
+        }
+
+        // Atomically add the request to the usage of all the buckets.
+        let mut pipe = redis::pipe();
+        pipe.atomic();
+        for bucket in &buckets {
+            let key = bucket.key(day);
+            pipe.hincr(&key, "requests", 1)
+                .hincr(&key, "amount", data.amount);
+            if let Some(retention_days) = self.config.retention_days {
+                // Expire once the day is out of the retention period.
+                pipe.expire(
+                    &key,
+                    (seconds_until_next_day + retention_days * 86400) as usize,
+                )
+                .ignore();
+            }
+        }
+        let usages: Vec<(u64, u64)> = pipe
+            .query_async::<_, Vec<u64>>(&mut *conn)
+            .await
+            .map_err(|e| {
+                AptosTapError::new_with_error_code(
+                    format!("Failed to update quota usage: {}", e),
+                    AptosTapErrorCode::StorageError,
+                )
+            })?
+            .chunks(2)
+            .map(|usage| (usage[0], usage[1]))
+            .collect();
+
+        // Check the usage again, to ensure there wasn't a get / set race.
+        Ok(buckets
+            .iter()
+            .zip(usages)
+            .filter_map(|(bucket, (requests, amount))| {
+                bucket.check_usage(requests, amount, seconds_until_next_day)
+            })
+            .collect())
+    }
+
+    /// Take the request out of the usage if the request failed due to
+    /// something wrong on our end.
+    async fn complete(&self, data: CompleteData) -> Result<(), AptosTapError> {
+        if !data.response_is_500 {
+            return Ok(());
+        }
+
+        let checker_data = &data.checker_data;
+        let buckets = self.buckets(Some(checker_data.receiver), Some(checker_data.source_ip));
+        if buckets.is_empty() {
+            return Ok(());
+        }
+        let mut conn = self.get_redis_connection().await?;
+        let day = days_since_tap_epoch(get_current_time_secs());
+
+        let mut pipe = redis::pipe();
+        pipe.atomic();
+        for bucket in &buckets {
+            let key = bucket.key(day);
+            pipe.hincr(&key, "requests", -1)
+                .ignore()
+                .hincr(&key, "amount", -(checker_data.amount as i64))
+                .ignore();
+        }
+        pipe.query_async::<_, ()>(&mut *conn).await.map_err(|e| {
+            AptosTapError::new_with_error_code(
+                format!("Failed to update quota usage: {}", e),
+                AptosTapErrorCode::StorageError,
+            )
+        })?;
+        Ok(())
+    }
+
+    fn cost(&self) -> u8 {
+        55
+    }
+}
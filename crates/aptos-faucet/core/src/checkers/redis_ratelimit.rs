@@ -16,17 +16,17 @@ use serde::{Deserialize, Serialize};
 use std::net::IpAddr;
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
-pub struct RedisRatelimitCheckerConfig {
+pub struct RedisDatabaseConfig {
     /// The database address to connect to, not including port,
     /// e.g. db.example.com or 234.121.222.42.
     pub database_address: String,
 
     /// The port to connect to.
-    #[serde(default = "RedisRatelimitCheckerConfig::default_database_port")]
+    #[serde(default = "RedisDatabaseConfig::default_database_port")]
     pub database_port: u16,
 
     /// The number of the database to use. If it doesn't exist, it will be created (todo verify this)
-    #[serde(default = "RedisRatelimitCheckerConfig::default_database_number")]
+    #[serde(default = "RedisDatabaseConfig::default_database_number")]
     pub database_number: i64,
 
     /// The name of the user to use, if necessary.
@@ -34,13 +34,19 @@ pub struct RedisRatelimitCheckerConfig {
 
     /// The password of the given user, if necessary.
     pub database_password: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RedisRatelimitCheckerConfig {
+    #[serde(flatten)]
+    pub database: RedisDatabaseConfig,
 
     /// Max number of requests per IP per day. 500s are not counted, because
     /// they are not the user's fault, but everything else is.
     pub max_requests_per_ip_per_day: u32,
 }
 
-impl RedisRatelimitCheckerConfig {
+impl RedisDatabaseConfig {
     fn default_database_port() -> u16 {
         6379
     }
@@ -108,7 +114,7 @@ pub struct RedisRatelimitChecker {
 
 impl RedisRatelimitChecker {
     pub async fn new(args: RedisRatelimitCheckerConfig) -> Result<Self> {
-        let db_pool = args.build_db_pool()?;
+        let db_pool = args.database.build_db_pool()?;
 
         // Ensure we can connect.
         db_pool
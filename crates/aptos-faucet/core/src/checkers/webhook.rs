@@ -0,0 +1,214 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{google_captcha::KeyString, CheckerData, CheckerTrait};
+use crate::{
+    endpoints::{AptosTapError, AptosTapErrorCode, RejectionReason, RejectionReasonCode},
+    helpers::get_current_time_secs,
+};
+use anyhow::{ensure, Context, Result};
+use aptos_logger::warn;
+use async_trait::async_trait;
+use hmac::{Hmac, Mac};
+use reqwest::{header::CONTENT_TYPE, Url};
+use serde::{Deserialize, Serialize};
+use sha2_0_10_6::Sha256;
+use std::{collections::HashMap, time::Duration};
+
+/// Header containing the unixtime (in seconds) at which the request was signed.
+pub const WEBHOOK_TIMESTAMP_HEADER: &str = "X-Aptos-Faucet-Timestamp";
+
+/// Header containing the signature of the request, in the form `sha256=<hex>`.
+pub const WEBHOOK_SIGNATURE_HEADER: &str = "X-Aptos-Faucet-Signature";
+
+type HmacSha256 = Hmac<Sha256>;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct WebhookCheckerConfig {
+    /// The URL to POST the request data to.
+    pub url: Url,
+
+    /// The key used to sign requests. The signature is the hex encoded
+    /// HMAC-SHA256 of `<timestamp>.<body>`, where timestamp is the value of
+    /// the X-Aptos-Faucet-Timestamp header, so the webhook can verify that the
+    /// request came from the faucet and reject replayed requests.
+    pub signing_key: KeyString,
+
+    /// How long to wait for the webhook to respond.
+    #[serde(default = "WebhookCheckerConfig::default_timeout_ms")]
+    pub timeout_ms: u64,
+
+    /// Headers of the faucet request to include in the data sent to the
+    /// webhook, e.g. User-Agent. Headers not in this list are not sent.
+    #[serde(default)]
+    pub forward_headers: Vec<String>,
+
+    /// If set, requests are allowed when the webhook can't be reached or
+    /// responds with something unexpected. Otherwise the request fails with a
+    /// 500 in that case.
+    #[serde(default)]
+    pub fail_open: bool,
+}
+
+impl WebhookCheckerConfig {
+    fn default_timeout_ms() -> u64 {
+        2000
+    }
+}
+
+/// The data POSTed to the webhook as JSON.
+#[derive(Debug, Serialize)]
+pub struct WebhookRequest {
+    pub time_request_received_secs: u64,
+    pub receiver: String,
+    pub source_ip: String,
+    pub headers: HashMap<String, String>,
+    /// If set, the webhook should not count this request against any limits,
+    /// it comes from an eligibility check rather than a funding request.
+    pub dry_run: bool,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookDecision {
+    Allow,
+    Deny,
+    Ratelimit,
+}
+
+/// The JSON response expected from the webhook.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct WebhookResponse {
+    pub decision: WebhookDecision,
+
+    /// Explanation included in the rejection reason returned to the user.
+    #[serde(default)]
+    pub reason: Option<String>,
+
+    /// For ratelimit decisions, when the user should try again.
+    #[serde(default)]
+    pub retry_after_secs: Option<u64>,
+}
+
+/// Delegates the decision to an external service, so that abuse detection
+/// can be done outside of the faucet.
+pub struct WebhookChecker {
+    config: WebhookCheckerConfig,
+    client: reqwest::Client,
+}
+
+impl WebhookChecker {
+    pub fn new(config: WebhookCheckerConfig) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_millis(config.timeout_ms))
+            .build()
+            .context("Failed to build webhook client")?;
+        Ok(Self { config, client })
+    }
+
+    async fn call_webhook(&self, data: &CheckerData, dry_run: bool) -> Result<WebhookResponse> {
+        let request = WebhookRequest {
+            time_request_received_secs: data.time_request_received_secs,
+            receiver: data.receiver.to_hex_literal(),
+            source_ip: data.source_ip.to_string(),
+            headers: self
+                .config
+                .forward_headers
+                .iter()
+                .filter_map(|name| {
+                    let value = data.headers.get(name)?.to_str().ok()?;
+                    Some((name.to_lowercase(), value.to_string()))
+                })
+                .collect(),
+            dry_run,
+        };
+        let body = serde_json::to_string(&request)?;
+        let timestamp = get_current_time_secs();
+
+        let response = self
+            .client
+            .post(self.config.url.clone())
+            .header(CONTENT_TYPE, "application/json")
+            .header(WEBHOOK_TIMESTAMP_HEADER, timestamp)
+            .header(
+                WEBHOOK_SIGNATURE_HEADER,
+                sign_webhook_request(&self.config.signing_key.0, timestamp, &body),
+            )
+            .body(body)
+            .send()
+            .await
+            .context("Failed to call webhook")?;
+
+        let status = response.status();
+        let text = response
+            .text()
+            .await
+            .context("Failed to read webhook response")?;
+        ensure!(
+            status.is_success(),
+            "Webhook returned status {}: {}",
+            status,
+            text
+        );
+        serde_json::from_str(&text)
+            .with_context(|| format!("Failed to parse webhook response: {}", text))
+    }
+}
+
+/// Returns the value of the signature header for a request.
+pub fn sign_webhook_request(signing_key: &str, timestamp: u64, body: &str) -> String {
+    let mut mac = HmacSha256::new_from_slice(signing_key.as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(format!("{}.{}", timestamp, body).as_bytes());
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+#[async_trait]
+impl CheckerTrait for WebhookChecker {
+    async fn check(
+        &self,
+        data: CheckerData,
+        dry_run: bool,
+    ) -> Result<Vec<RejectionReason>, AptosTapError> {
+        let response = match self.call_webhook(&data, dry_run).await {
+            Ok(response) => response,
+            Err(e) if self.config.fail_open => {
+                warn!(
+                    message = "Webhook failed, allowing request",
+                    error = format!("{:#}", e),
+                    source_ip = data.source_ip
+                );
+                return Ok(vec![]);
+            },
+            Err(e) => {
+                return Err(AptosTapError::new_with_error_code(
+                    e,
+                    AptosTapErrorCode::CheckerError,
+                ))
+            },
+        };
+
+        let reason = response
+            .reason
+            .unwrap_or_else(|| "Rejected by webhook".to_string());
+        Ok(match response.decision {
+            WebhookDecision::Allow => vec![],
+            WebhookDecision::Deny => vec![RejectionReason::new(
+                reason,
+                RejectionReasonCode::WebhookRejected,
+            )],
+            WebhookDecision::Ratelimit => {
+                let rejection_reason =
+                    RejectionReason::new(reason, RejectionReasonCode::WebhookRatelimited);
+                vec![match response.retry_after_secs {
+                    Some(retry_after) => rejection_reason.retry_after(retry_after),
+                    None => rejection_reason,
+                }]
+            },
+        })
+    }
+
+    fn cost(&self) -> u8 {
+        60
+    }
+}
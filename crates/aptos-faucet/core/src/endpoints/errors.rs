@@ -64,7 +64,14 @@ impl AptosTapError {
     pub fn status_and_retry_after(&self) -> (StatusCode, Option<u64>) {
         let (mut status_code, mut retry_after) = (self.error_code.status(), None);
         for rejection_reason in &self.rejection_reasons {
-            if rejection_reason.code == RejectionReasonCode::IpUsageLimitExhausted {
+            if matches!(
+                rejection_reason.code,
+                RejectionReasonCode::IpUsageLimitExhausted
+                    | RejectionReasonCode::WebhookRatelimited
+                    | RejectionReasonCode::AccountUsageLimitExhausted
+                    | RejectionReasonCode::SubnetUsageLimitExhausted
+                    | RejectionReasonCode::AsnUsageLimitExhausted
+            ) {
                 status_code = StatusCode::TOO_MANY_REQUESTS;
                 retry_after = rejection_reason.retry_after;
                 break;
@@ -256,4 +263,25 @@ pub enum RejectionReasonCode {
 
     /// Referer was in the blocklist.
     RefererBlocklisted = 108,
+
+    /// The webhook rejected the request.
+    WebhookRejected = 109,
+
+    /// The webhook ratelimited the request.
+    WebhookRatelimited = 110,
+
+    /// Receiver account has exhausted its quota.
+    AccountUsageLimitExhausted = 111,
+
+    /// Subnet of the IP has exhausted its quota.
+    SubnetUsageLimitExhausted = 112,
+
+    /// Autonomous system of the IP has exhausted its quota.
+    AsnUsageLimitExhausted = 113,
+
+    /// Transaction to sponsor exceeds the gas budget.
+    GasBudgetExceeded = 114,
+
+    /// Transaction to sponsor calls a function that isn't sponsored.
+    FunctionNotAllowed = 115,
 }
@@ -8,8 +8,8 @@ use super::{
 use crate::{
     bypasser::{Bypasser, BypasserTrait},
     checkers::{Checker, CheckerData, CheckerTrait, CompleteData},
-    endpoints::AptosTapErrorCode,
-    funder::{Funder, FunderTrait},
+    endpoints::{AptosTapErrorCode, RejectionReason},
+    funder::{Funder, FunderTrait, SponsorTransaction},
     helpers::{get_current_time_secs, transaction_hashes},
 };
 use aptos_logger::info;
@@ -25,7 +25,8 @@ use poem_openapi::{
     payload::{Json, PlainText},
     ApiResponse, Object, OpenApi,
 };
-use std::sync::Arc;
+use serde::de::DeserializeOwned;
+use std::{net::IpAddr, sync::Arc};
 use tokio::sync::{Semaphore, SemaphorePermit};
 
 #[derive(Clone, Debug, Default, Object)]
@@ -80,6 +81,78 @@ impl FundRequest {
     }
 }
 
+#[derive(Clone, Debug, Default, Object)]
+pub struct FundBatchRequest {
+    /// The accounts to fund. Each of these is checked as if it were its own
+    /// request to `/fund`, so checkers that expect a single use token per
+    /// request, e.g. captchas, will reject batches of more than one account.
+    pub requests: Vec<FundRequest>,
+}
+
+#[derive(Clone, Debug, Object)]
+pub struct SponsorRequest {
+    /// Hex encoded BCS serialized RawTransaction.
+    pub raw_transaction: String,
+
+    /// Hex encoded BCS serialized AccountAuthenticator of the sender. The
+    /// sender must sign the transaction as a fee payer transaction, with the
+    /// fee payer address returned by `/sponsor_info`.
+    pub sender_authenticator: String,
+
+    /// Addresses of the secondary signers of the transaction, if any.
+    #[oai(default)]
+    pub secondary_signer_addresses: Vec<String>,
+
+    /// Hex encoded BCS serialized AccountAuthenticators of the secondary
+    /// signers, in the same order as `secondary_signer_addresses`.
+    #[oai(default)]
+    pub secondary_signer_authenticators: Vec<String>,
+}
+
+impl SponsorRequest {
+    pub fn sponsor_transaction(&self) -> Result<SponsorTransaction, AptosTapError> {
+        if self.secondary_signer_addresses.len() != self.secondary_signer_authenticators.len() {
+            return Err(AptosTapError::new(
+                "The number of secondary signer addresses and authenticators must match"
+                    .to_string(),
+                AptosTapErrorCode::InvalidRequest,
+            ));
+        }
+        Ok(SponsorTransaction {
+            raw_transaction: decode_bcs("raw_transaction", &self.raw_transaction)?,
+            sender: decode_bcs("sender_authenticator", &self.sender_authenticator)?,
+            secondary_signer_addresses: self
+                .secondary_signer_addresses
+                .iter()
+                .map(|address| {
+                    AccountAddress::from_str_strict(address).map_err(|e| {
+                        AptosTapError::new(
+                            format!("Invalid secondary signer address {}: {}", address, e),
+                            AptosTapErrorCode::InvalidRequest,
+                        )
+                    })
+                })
+                .collect::<Result<_, _>>()?,
+            secondary_signers: self
+                .secondary_signer_authenticators
+                .iter()
+                .map(|authenticator| decode_bcs("secondary_signer_authenticators", authenticator))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+#[derive(Clone, Debug, Object)]
+pub struct SponsorResponse {
+    pub txn_hash: String,
+}
+
+#[derive(Clone, Debug, Object)]
+pub struct SponsorInfoResponse {
+    /// The fee payer address to use when signing transactions to sponsor.
+    pub fee_payer_address: String,
+}
+
 impl std::fmt::Display for FundRequest {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         write!(f, "<Fund {:?} to {:?}>", self.amount, self.receiver())
@@ -162,6 +235,92 @@ impl FundApi {
 
         Ok(())
     }
+
+    /// Funds multiple accounts
+    ///
+    /// With this endpoint a user can create and fund multiple accounts in a
+    /// single request. Each account is checked as if it were its own request
+    /// to `/fund`, and if any of them is rejected, none of them are funded.
+    /// Depending on the configured funding backend, the accounts are funded
+    /// with one or a couple of transactions, rather than one per account.
+    #[oai(
+        path = "/fund_batch",
+        method = "post",
+        operation_id = "fund_batch",
+        tag = "ApiTags::Fund"
+    )]
+    async fn fund_batch(
+        &self,
+        fund_batch_request: Json<FundBatchRequest>,
+        // This automagically uses FromRequest to get this data from the request.
+        // It takes into things like X-Forwarded-IP and X-Real-IP.
+        source_ip: RealIp,
+        // Same thing, this uses FromRequest.
+        header_map: &HeaderMap,
+    ) -> poem::Result<Json<FundResponse>, AptosTapErrorResponse> {
+        let txns = self
+            .components
+            .fund_batch_inner(fund_batch_request.0, source_ip, header_map)
+            .await?;
+        Ok(Json(FundResponse {
+            txn_hashes: get_hashes(&txns),
+        }))
+    }
+
+    /// Sponsors a transaction
+    ///
+    /// With this endpoint a user can have the faucet pay the fees for their
+    /// transaction. The user signs the transaction as a fee payer transaction
+    /// with the address from `/sponsor_info` as the fee payer, and the faucet
+    /// signs it as the fee payer and submits it, as long as it is within the
+    /// configured gas budget. The sender of the transaction is checked like
+    /// the account to fund would be in `/fund`.
+    #[oai(
+        path = "/sponsor",
+        method = "post",
+        operation_id = "sponsor",
+        tag = "ApiTags::Fund"
+    )]
+    async fn sponsor(
+        &self,
+        sponsor_request: Json<SponsorRequest>,
+        // This automagically uses FromRequest to get this data from the request.
+        // It takes into things like X-Forwarded-IP and X-Real-IP.
+        source_ip: RealIp,
+        // Same thing, this uses FromRequest.
+        header_map: &HeaderMap,
+    ) -> poem::Result<Json<SponsorResponse>, AptosTapErrorResponse> {
+        let txn = self
+            .components
+            .sponsor_inner(sponsor_request.0, source_ip, header_map)
+            .await?;
+        Ok(Json(SponsorResponse {
+            txn_hash: txn.committed_hash().to_hex(),
+        }))
+    }
+
+    /// Get sponsorship info
+    ///
+    /// This returns the address the faucet uses to pay the fees for
+    /// transactions submitted to `/sponsor`.
+    #[oai(
+        path = "/sponsor_info",
+        method = "get",
+        operation_id = "sponsor_info",
+        tag = "ApiTags::Fund"
+    )]
+    async fn sponsor_info(&self) -> poem::Result<Json<SponsorInfoResponse>, AptosTapErrorResponse> {
+        match self.components.funder.get_sponsor_address().await {
+            Some(fee_payer_address) => Ok(Json(SponsorInfoResponse {
+                fee_payer_address: fee_payer_address.to_hex_literal(),
+            })),
+            None => Err(AptosTapError::new(
+                "Sponsoring transactions is not enabled".to_string(),
+                AptosTapErrorCode::EndpointNotEnabled,
+            )
+            .into()),
+        }
+    }
 }
 
 pub struct FundApiComponents {
@@ -178,6 +337,10 @@ pub struct FundApiComponents {
     /// See the comment in `RunConfig`.
     pub return_rejections_early: bool,
 
+    /// The maximum number of accounts that can be funded in one request to
+    /// `/fund_batch`.
+    pub max_batch_size: usize,
+
     /// This semaphore is used to ensure we only process a certain number of
     /// requests concurrently.
     pub concurrent_requests_semaphore: Option<Arc<Semaphore>>,
@@ -198,28 +361,8 @@ impl FundApiComponents {
         header_map: &HeaderMap,
         dry_run: bool,
     ) -> poem::Result<(CheckerData, bool, Option<SemaphorePermit>), AptosTapError> {
-        let permit = match &self.concurrent_requests_semaphore {
-            Some(semaphore) => match semaphore.try_acquire() {
-                Ok(permit) => Some(permit),
-                Err(_) => {
-                    return Err(AptosTapError::new(
-                        "Server overloaded, please try again later".to_string(),
-                        AptosTapErrorCode::ServerOverloaded,
-                    ))
-                },
-            },
-            None => None,
-        };
-
-        let source_ip = match source_ip.0 {
-            Some(ip) => ip,
-            None => {
-                return Err(AptosTapError::new(
-                    "No source IP found in the request".to_string(),
-                    AptosTapErrorCode::SourceIpMissing,
-                ))
-            },
-        };
+        let permit = self.acquire_permit()?;
+        let source_ip = get_source_ip(source_ip)?;
 
         let receiver = match fund_request.receiver() {
             Some(receiver) => receiver,
@@ -233,11 +376,45 @@ impl FundApiComponents {
 
         let checker_data = CheckerData {
             receiver,
+            amount: self.funder.get_amount(fund_request.amount, false),
             source_ip,
             headers: Arc::new(header_map.clone()),
             time_request_received_secs: get_current_time_secs(),
         };
 
+        let (bypass, rejection_reasons) = self.run_checks(&checker_data, dry_run).await?;
+        if !rejection_reasons.is_empty() {
+            return Err(AptosTapError::new(
+                format!("Request rejected by {} checkers", rejection_reasons.len()),
+                AptosTapErrorCode::Rejected,
+            )
+            .rejection_reasons(rejection_reasons));
+        }
+
+        Ok((checker_data, bypass, permit))
+    }
+
+    fn acquire_permit(&self) -> Result<Option<SemaphorePermit>, AptosTapError> {
+        match &self.concurrent_requests_semaphore {
+            Some(semaphore) => match semaphore.try_acquire() {
+                Ok(permit) => Ok(Some(permit)),
+                Err(_) => Err(AptosTapError::new(
+                    "Server overloaded, please try again later".to_string(),
+                    AptosTapErrorCode::ServerOverloaded,
+                )),
+            },
+            None => Ok(None),
+        }
+    }
+
+    /// Runs the Bypassers, and if none of them let the request bypass the
+    /// checks, the Checkers. Returns whether the request bypassed the checks
+    /// and the rejection reasons from the Checkers, if any.
+    async fn run_checks(
+        &self,
+        checker_data: &CheckerData,
+        dry_run: bool,
+    ) -> Result<(bool, Vec<RejectionReason>), AptosTapError> {
         // See if this request meets the criteria to bypass checkers / storage.
         for bypasser in &self.bypassers {
             if bypasser
@@ -249,9 +426,9 @@ impl FundApiComponents {
             {
                 info!(
                     "Allowing request from {} to bypass checks / storage",
-                    source_ip
+                    checker_data.source_ip
                 );
-                return Ok((checker_data, true, permit));
+                return Ok((true, vec![]));
             }
         }
 
@@ -266,15 +443,29 @@ impl FundApiComponents {
             }
         }
 
-        if !rejection_reasons.is_empty() {
-            return Err(AptosTapError::new(
-                format!("Request rejected by {} checkers", rejection_reasons.len()),
-                AptosTapErrorCode::Rejected,
-            )
-            .rejection_reasons(rejection_reasons));
-        }
+        Ok((false, rejection_reasons))
+    }
 
-        Ok((checker_data, false, permit))
+    /// Give all Checkers the chance to run the completion step. We should
+    /// monitor for failures in these steps because they could lead to an
+    /// unintended data state.
+    async fn complete_checks(
+        &self,
+        checker_data: CheckerData,
+        txn_hashes: Vec<String>,
+        response_is_500: bool,
+    ) -> Result<(), AptosTapError> {
+        let complete_data = CompleteData {
+            checker_data,
+            txn_hashes,
+            response_is_500,
+        };
+        for checker in &self.checkers {
+            checker.complete(complete_data.clone()).await.map_err(|e| {
+                AptosTapError::new_with_error_code(e, AptosTapErrorCode::CheckerError)
+            })?;
+        }
+        Ok(())
     }
 
     async fn fund_inner(
@@ -313,28 +504,229 @@ impl FundApiComponents {
             success = fund_result.is_ok(),
         );
 
-        // Give all Checkers the chance to run the completion step. We should
-        // monitor for failures in these steps because they could lead to an
-        // unintended data state.
         if !bypass {
             let response_is_500 = match &fund_result {
                 Ok(_) => false,
                 Err(e) => e.error_code.status().is_server_error(),
             };
-            let complete_data = CompleteData {
-                checker_data,
-                txn_hashes: txn_hashes.clone(),
-                response_is_500,
+            self.complete_checks(checker_data, txn_hashes, response_is_500)
+                .await?;
+        }
+
+        fund_result
+    }
+
+    async fn fund_batch_inner(
+        &self,
+        fund_batch_request: FundBatchRequest,
+        source_ip: RealIp,
+        header_map: &HeaderMap,
+    ) -> poem::Result<Vec<SignedTransaction>, AptosTapError> {
+        let _semaphore_permit = self.acquire_permit()?;
+        let source_ip = get_source_ip(source_ip)?;
+
+        let requests = fund_batch_request.requests;
+        if requests.is_empty() || requests.len() > self.max_batch_size {
+            return Err(AptosTapError::new(
+                format!(
+                    "Between 1 and {} accounts must be funded per batch, got {}",
+                    self.max_batch_size,
+                    requests.len()
+                ),
+                AptosTapErrorCode::InvalidRequest,
+            ));
+        }
+
+        let headers = Arc::new(header_map.clone());
+        let time_request_received_secs = get_current_time_secs();
+        let mut batch = Vec::with_capacity(requests.len());
+        for (i, fund_request) in requests.iter().enumerate() {
+            let receiver = fund_request.receiver().ok_or_else(|| {
+                AptosTapError::new(
+                    format!(
+                        "Account address, auth key, or pub key must be provided and valid for request {}",
+                        i
+                    ),
+                    AptosTapErrorCode::InvalidRequest,
+                )
+            })?;
+            if batch
+                .iter()
+                .any(|(checker_data, _): &(CheckerData, _)| checker_data.receiver == receiver)
+            {
+                return Err(AptosTapError::new(
+                    format!("Account {} appears more than once in the batch", receiver),
+                    AptosTapErrorCode::InvalidRequest,
+                ));
+            }
+            let checker_data = CheckerData {
+                receiver,
+                amount: self.funder.get_amount(fund_request.amount, false),
+                source_ip,
+                headers: headers.clone(),
+                time_request_received_secs,
             };
-            for checker in &self.checkers {
-                checker.complete(complete_data.clone()).await.map_err(|e| {
-                    AptosTapError::new_with_error_code(e, AptosTapErrorCode::CheckerError)
-                })?;
+            batch.push((checker_data, fund_request.amount));
+        }
+
+        // Each receiver is checked as if it were its own request, so that per
+        // account limits apply the same way they do for `/fund`.
+        let mut checked = Vec::with_capacity(batch.len());
+        let mut rejection_reasons = Vec::new();
+        let mut check_error = None;
+        for (checker_data, amount) in batch {
+            match self.run_checks(&checker_data, false).await {
+                Ok((bypass, receiver_rejection_reasons)) => {
+                    if receiver_rejection_reasons.is_empty() {
+                        checked.push((checker_data, amount, bypass));
+                    } else {
+                        rejection_reasons.extend(receiver_rejection_reasons);
+                    }
+                },
+                Err(e) => {
+                    check_error = Some(e);
+                    break;
+                },
+            }
+        }
+
+        if check_error.is_some() || !rejection_reasons.is_empty() {
+            // Nothing will be funded, so let the Checkers know that the
+            // receivers that passed shouldn't count against any limits.
+            for (checker_data, _, bypass) in checked {
+                if !bypass {
+                    self.complete_checks(checker_data, vec![], true).await?;
+                }
+            }
+            if let Some(e) = check_error {
+                return Err(e);
+            }
+            return Err(AptosTapError::new(
+                format!("Request rejected by {} checkers", rejection_reasons.len()),
+                AptosTapErrorCode::Rejected,
+            )
+            .rejection_reasons(rejection_reasons));
+        }
+
+        let receivers = checked
+            .iter()
+            .map(|(checker_data, amount, bypass)| {
+                (
+                    checker_data.receiver,
+                    self.funder.get_amount(*amount, *bypass),
+                )
+            })
+            .collect();
+        let fund_result = self.funder.fund_batch(receivers, false).await;
+
+        let txn_hashes = match &fund_result {
+            Ok(txns) => transaction_hashes(&txns.iter().collect::<Vec<&SignedTransaction>>()),
+            Err(e) => e.txn_hashes.to_vec(),
+        };
+
+        info!(
+            source_ip = source_ip,
+            num_receivers = checked.len(),
+            txn_hashes = txn_hashes,
+            success = fund_result.is_ok(),
+        );
+
+        let response_is_500 = match &fund_result {
+            Ok(_) => false,
+            Err(e) => e.error_code.status().is_server_error(),
+        };
+        for (checker_data, _, bypass) in checked {
+            if !bypass {
+                self.complete_checks(checker_data, txn_hashes.clone(), response_is_500)
+                    .await?;
             }
         }
 
         fund_result
     }
+
+    async fn sponsor_inner(
+        &self,
+        sponsor_request: SponsorRequest,
+        source_ip: RealIp,
+        header_map: &HeaderMap,
+    ) -> poem::Result<SignedTransaction, AptosTapError> {
+        let _semaphore_permit = self.acquire_permit()?;
+        let source_ip = get_source_ip(source_ip)?;
+        let transaction = sponsor_request.sponsor_transaction()?;
+
+        // The sender is treated as the receiver, and the most the transaction
+        // can cost us as the amount.
+        let raw_transaction = &transaction.raw_transaction;
+        let checker_data = CheckerData {
+            receiver: raw_transaction.sender(),
+            amount: raw_transaction
+                .max_gas_amount()
+                .saturating_mul(raw_transaction.gas_unit_price()),
+            source_ip,
+            headers: Arc::new(header_map.clone()),
+            time_request_received_secs: get_current_time_secs(),
+        };
+
+        let (bypass, rejection_reasons) = self.run_checks(&checker_data, false).await?;
+        if !rejection_reasons.is_empty() {
+            return Err(AptosTapError::new(
+                format!("Request rejected by {} checkers", rejection_reasons.len()),
+                AptosTapErrorCode::Rejected,
+            )
+            .rejection_reasons(rejection_reasons));
+        }
+
+        let sponsor_result = self.funder.sponsor(transaction).await;
+
+        let txn_hashes = match &sponsor_result {
+            Ok(txn) => transaction_hashes(&[txn]),
+            Err(e) => e.txn_hashes.to_vec(),
+        };
+
+        info!(
+            source_ip = checker_data.source_ip,
+            address = checker_data.receiver,
+            txn_hashes = txn_hashes,
+            success = sponsor_result.is_ok(),
+        );
+
+        if !bypass {
+            let response_is_500 = match &sponsor_result {
+                Ok(_) => false,
+                Err(e) => e.error_code.status().is_server_error(),
+            };
+            self.complete_checks(checker_data, txn_hashes, response_is_500)
+                .await?;
+        }
+
+        sponsor_result
+    }
+}
+
+fn get_source_ip(source_ip: RealIp) -> Result<IpAddr, AptosTapError> {
+    source_ip.0.ok_or_else(|| {
+        AptosTapError::new(
+            "No source IP found in the request".to_string(),
+            AptosTapErrorCode::SourceIpMissing,
+        )
+    })
+}
+
+/// Decodes a hex encoded BCS value from a request.
+fn decode_bcs<T: DeserializeOwned>(name: &str, value: &str) -> Result<T, AptosTapError> {
+    let bytes = hex::decode(value.trim_start_matches("0x")).map_err(|e| {
+        AptosTapError::new(
+            format!("{} is not valid hex: {}", name, e),
+            AptosTapErrorCode::InvalidRequest,
+        )
+    })?;
+    aptos_sdk::bcs::from_bytes(&bytes).map_err(|e| {
+        AptosTapError::new(
+            format!("{} is not valid BCS: {}", name, e),
+            AptosTapErrorCode::InvalidRequest,
+        )
+    })
 }
 
 /////////////////////////////////////////////////////////////////
@@ -7,6 +7,7 @@ mod captcha;
 mod error_converter;
 mod errors;
 mod fund;
+mod quota;
 
 pub use self::captcha::{CaptchaApi, CAPTCHA_KEY, CAPTCHA_VALUE};
 pub use api::build_openapi_service;
@@ -15,8 +16,12 @@ pub use error_converter::convert_error;
 pub use errors::{
     AptosTapError, AptosTapErrorCode, RejectionReason, RejectionReasonCode, USE_HELPFUL_ERRORS,
 };
-pub use fund::{mint, FundApi, FundApiComponents, FundRequest, FundResponse};
+pub use fund::{
+    mint, FundApi, FundApiComponents, FundBatchRequest, FundRequest, FundResponse,
+    SponsorInfoResponse, SponsorRequest, SponsorResponse,
+};
 use poem_openapi::Tags;
+pub use quota::{QuotaApi, QuotaUsageResponse};
 
 /// API categories for the OpenAPI spec
 #[derive(Tags)]
@@ -29,4 +34,7 @@ pub enum ApiTags {
 
     /// Captcha API
     Captcha,
+
+    /// Quota API
+    Quota,
 }
@@ -0,0 +1,99 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! This API is for the QuotaChecker, it lets operators see how much of their
+//! quotas an account or IP has used on a given day. Reading the ledger
+//! requires one of the auth tokens configured in `usage_auth_tokens`.
+
+use super::{
+    errors::AptosTapErrorResponse, ApiTags, AptosTapError, AptosTapErrorCode, FundApiComponents,
+};
+use crate::{
+    checkers::{Checker, QuotaUsage},
+    helpers::{days_since_tap_epoch, get_current_time_secs},
+};
+use aptos_sdk::types::account_address::AccountAddress;
+use poem::{http::HeaderMap, Result};
+use poem_openapi::{param::Query, payload::Json, Object, OpenApi};
+use std::{net::IpAddr, str::FromStr, sync::Arc};
+
+pub struct QuotaApi {
+    pub components: Arc<FundApiComponents>,
+}
+
+#[derive(Clone, Debug, Object)]
+pub struct QuotaUsageResponse {
+    /// Days since the tap epoch of the reported usage, the quotas reset at
+    /// the start of each day.
+    pub day: u64,
+    /// The usage of each quota the given account and IP count against.
+    pub usage: Vec<QuotaUsage>,
+}
+
+#[OpenApi]
+impl QuotaApi {
+    /// Get quota usage
+    ///
+    /// With this endpoint you can see how many requests and how much funding
+    /// the given account and / or IP have used on a given day, for each of the
+    /// quotas they count against. This endpoint is only relevant if the
+    /// QuotaChecker is enabled with `usage_auth_tokens`, one of which must be
+    /// given in the Authorization header as `Bearer <token>`.
+    #[oai(
+        path = "/quota_usage",
+        method = "get",
+        operation_id = "quota_usage",
+        tag = "ApiTags::Quota"
+    )]
+    async fn quota_usage(
+        &self,
+        /// Account address to get the per account usage of.
+        address: Query<Option<String>>,
+        /// IP to get the per subnet and per ASN usage of.
+        ip: Query<Option<String>>,
+        /// Days since the tap epoch to get the usage of, defaults to today.
+        day: Query<Option<u64>>,
+        header_map: &HeaderMap,
+    ) -> Result<Json<QuotaUsageResponse>, AptosTapErrorResponse> {
+        let quota_checker = self
+            .components
+            .checkers
+            .iter()
+            .find_map(|checker| match checker {
+                Checker::QuotaChecker(quota_checker) => Some(quota_checker),
+                _ => None,
+            })
+            .ok_or_else(|| {
+                AptosTapError::new(
+                    "The QuotaChecker is not enabled".to_string(),
+                    AptosTapErrorCode::EndpointNotEnabled,
+                )
+            })?;
+        quota_checker.check_usage_auth(header_map)?;
+
+        let receiver = match address.0 {
+            Some(address) => Some(AccountAddress::from_str(&address).map_err(|e| {
+                AptosTapError::new(
+                    format!("Invalid account address: {}", e),
+                    AptosTapErrorCode::InvalidRequest,
+                )
+            })?),
+            None => None,
+        };
+        let source_ip = match ip.0 {
+            Some(ip) => Some(IpAddr::from_str(&ip).map_err(|e| {
+                AptosTapError::new(
+                    format!("Invalid IP address: {}", e),
+                    AptosTapErrorCode::InvalidRequest,
+                )
+            })?),
+            None => None,
+        };
+
+        let day = day
+            .0
+            .unwrap_or_else(|| days_since_tap_epoch(get_current_time_secs()));
+        let usage = quota_checker.get_usage(receiver, source_ip, day).await?;
+        Ok(Json(QuotaUsageResponse { day, usage }))
+    }
+}
@@ -3,7 +3,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    endpoints::{AptosTapError, AptosTapErrorCode},
+    endpoints::{AptosTapError, AptosTapErrorCode, RejectionReason, RejectionReasonCode},
     middleware::NUM_OUTSTANDING_TRANSACTIONS,
 };
 use anyhow::{anyhow, Context, Result};
@@ -14,10 +14,15 @@ use aptos_logger::{
     warn,
 };
 use aptos_sdk::{
-    crypto::ed25519::Ed25519PrivateKey,
+    crypto::{ed25519::Ed25519PrivateKey, SigningKey},
     rest_client::Client,
     types::{
-        account_address::AccountAddress, chain_id::ChainId, transaction::SignedTransaction,
+        account_address::AccountAddress,
+        chain_id::ChainId,
+        transaction::{
+            authenticator::AccountAuthenticator, RawTransaction, RawTransactionWithData,
+            SignedTransaction, TransactionPayload,
+        },
         LocalAccount,
     },
 };
@@ -154,6 +159,11 @@ pub struct TransactionSubmissionConfig {
     /// Whether to wait for the transaction before returning.
     #[serde(default)]
     pub wait_for_transactions: bool,
+
+    /// If given, the funder account will act as fee payer for transactions
+    /// submitted to the `/sponsor` endpoint, within these limits.
+    #[serde(default)]
+    pub sponsor_config: Option<SponsorConfig>,
 }
 
 impl TransactionSubmissionConfig {
@@ -176,6 +186,7 @@ impl TransactionSubmissionConfig {
             transaction_expiration_secs,
             wait_for_outstanding_txns_secs,
             wait_for_transactions,
+            sponsor_config: None,
         }
     }
 
@@ -214,6 +225,76 @@ impl TransactionSubmissionConfig {
     }
 }
 
+/// Limits on the transactions the funder will pay the fees for.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SponsorConfig {
+    /// The maximum max_gas_amount a sponsored transaction may set.
+    pub max_gas_amount: u64,
+
+    /// The maximum gas_unit_price a sponsored transaction may set.
+    pub max_gas_unit_price: u64,
+
+    /// If given, only transactions calling one of these entry functions are
+    /// sponsored, e.g. `0x1::aptos_account::transfer`. Scripts are never
+    /// sponsored if this is set.
+    #[serde(default)]
+    pub allowed_functions: Option<Vec<String>>,
+}
+
+impl SponsorConfig {
+    /// Returns the reasons the transaction isn't within these limits, if any.
+    pub fn check(&self, transaction: &RawTransaction) -> Vec<RejectionReason> {
+        let mut rejection_reasons = Vec::new();
+        if transaction.max_gas_amount() > self.max_gas_amount {
+            rejection_reasons.push(RejectionReason::new(
+                format!(
+                    "max_gas_amount {} is greater than the maximum sponsored amount: {}",
+                    transaction.max_gas_amount(),
+                    self.max_gas_amount
+                ),
+                RejectionReasonCode::GasBudgetExceeded,
+            ));
+        }
+        if transaction.gas_unit_price() > self.max_gas_unit_price {
+            rejection_reasons.push(RejectionReason::new(
+                format!(
+                    "gas_unit_price {} is greater than the maximum sponsored price: {}",
+                    transaction.gas_unit_price(),
+                    self.max_gas_unit_price
+                ),
+                RejectionReasonCode::GasBudgetExceeded,
+            ));
+        }
+        if let Some(allowed_functions) = &self.allowed_functions {
+            let function = match transaction.payload() {
+                TransactionPayload::EntryFunction(entry_function) => Some(format!(
+                    "{}::{}",
+                    entry_function.module().short_str_lossless(),
+                    entry_function.function()
+                )),
+                _ => None,
+            };
+            if !function.map_or(false, |function| allowed_functions.contains(&function)) {
+                rejection_reasons.push(RejectionReason::new(
+                    "Only calls to certain entry functions are sponsored".to_string(),
+                    RejectionReasonCode::FunctionNotAllowed,
+                ));
+            }
+        }
+        rejection_reasons
+    }
+}
+
+/// A transaction signed by its sender (and secondary signers, if any) that
+/// is waiting for the funder to sign it as fee payer.
+#[derive(Clone, Debug)]
+pub struct SponsorTransaction {
+    pub raw_transaction: RawTransaction,
+    pub sender: AccountAuthenticator,
+    pub secondary_signer_addresses: Vec<AccountAddress>,
+    pub secondary_signers: Vec<AccountAuthenticator>,
+}
+
 struct NumOutstandingTransactionsResetter;
 
 impl Drop for NumOutstandingTransactionsResetter {
@@ -223,7 +304,8 @@ impl Drop for NumOutstandingTransactionsResetter {
 }
 
 /// This function is responsible for updating our local record of the sequence
-/// numbers of the funder and receiver accounts.
+/// numbers of the funder and receiver accounts. It waits until there is room
+/// for `num_transactions` more outstanding transactions from the funder.
 pub async fn update_sequence_numbers(
     client: &Client,
     funder_account: &RwLock<LocalAccount>,
@@ -231,6 +313,7 @@ pub async fn update_sequence_numbers(
     outstanding_requests: &RwLock<Vec<(AccountAddress, u64)>>,
     receiver_address: AccountAddress,
     amount: u64,
+    num_transactions: u64,
     wait_for_outstanding_txns_secs: u64,
 ) -> Result<(u64, Option<u64>), AptosTapError> {
     let (mut funder_seq, mut receiver_seq) =
@@ -251,7 +334,7 @@ pub async fn update_sequence_numbers(
     let mut set_outstanding = false;
     // We shouldn't have too many outstanding txns
     for _ in 0..(wait_for_outstanding_txns_secs * 2) {
-        if our_funder_seq < funder_seq + MAX_NUM_OUTSTANDING_TRANSACTIONS {
+        if our_funder_seq + num_transactions <= funder_seq + MAX_NUM_OUTSTANDING_TRANSACTIONS {
             // Enforce a stronger ordering of priorities based upon the MintParams that arrived
             // first. Then put the other folks to sleep to try again until the queue fills up.
             if !set_outstanding {
@@ -292,10 +375,12 @@ pub async fn update_sequence_numbers(
     }
 
     // If after 30 seconds we still have not caught up, we are likely unhealthy.
-    if our_funder_seq >= funder_seq + MAX_NUM_OUTSTANDING_TRANSACTIONS {
+    if our_funder_seq + num_transactions > funder_seq + MAX_NUM_OUTSTANDING_TRANSACTIONS {
         error!("We are unhealthy, transactions have likely expired.");
         let funder_account = funder_account.write().await;
-        if funder_account.sequence_number() >= funder_seq + MAX_NUM_OUTSTANDING_TRANSACTIONS {
+        if funder_account.sequence_number() + num_transactions
+            > funder_seq + MAX_NUM_OUTSTANDING_TRANSACTIONS
+        {
             info!("Resetting the sequence number counter.");
             funder_account.set_sequence_number(funder_seq);
         } else {
@@ -405,6 +490,109 @@ pub async fn submit_transaction(
     }
 }
 
+/// Signs the transaction with the funder account as fee payer and submits it
+/// if it is within the limits of the SponsorConfig. Unlike the transactions
+/// sent by the funder, this doesn't use the sequence number of the funder
+/// account, so there is no need to go through `update_sequence_numbers`.
+pub async fn sponsor_transaction(
+    client: &Client,
+    fee_payer_account: &RwLock<LocalAccount>,
+    sponsor_config: &SponsorConfig,
+    chain_id: ChainId,
+    transaction: SponsorTransaction,
+    wait_for_transactions: bool,
+) -> Result<SignedTransaction, AptosTapError> {
+    let SponsorTransaction {
+        raw_transaction,
+        sender,
+        secondary_signer_addresses,
+        secondary_signers,
+    } = transaction;
+
+    // Only sign transactions that are eligible for sponsorship.
+    if raw_transaction.chain_id() != chain_id {
+        return Err(AptosTapError::new(
+            format!(
+                "Transaction is for chain {}, but this faucet is for chain {}",
+                raw_transaction.chain_id(),
+                chain_id
+            ),
+            AptosTapErrorCode::InvalidRequest,
+        ));
+    }
+
+    let rejection_reasons = sponsor_config.check(&raw_transaction);
+    if !rejection_reasons.is_empty() {
+        return Err(AptosTapError::new(
+            "Transaction is not eligible for sponsorship".to_string(),
+            AptosTapErrorCode::Rejected,
+        )
+        .rejection_reasons(rejection_reasons));
+    }
+
+    let signed_transaction = {
+        let fee_payer_account = fee_payer_account.read().await;
+        let message = RawTransactionWithData::new_fee_payer(
+            raw_transaction.clone(),
+            secondary_signer_addresses.clone(),
+            fee_payer_account.address(),
+        );
+        let signature = fee_payer_account
+            .private_key()
+            .sign(&message)
+            .map_err(|e| {
+                AptosTapError::new_with_error_code(e, AptosTapErrorCode::FunderAccountProblem)
+            })?;
+        SignedTransaction::new_fee_payer(
+            raw_transaction,
+            sender,
+            secondary_signer_addresses,
+            secondary_signers,
+            fee_payer_account.address(),
+            AccountAuthenticator::ed25519(fee_payer_account.public_key().clone(), signature),
+        )
+    };
+
+    // Catch bad signatures here rather than paying to find out.
+    signed_transaction.verify_signature().map_err(|e| {
+        AptosTapError::new(
+            format!("Transaction signature is invalid: {:#}", e),
+            AptosTapErrorCode::InvalidRequest,
+        )
+    })?;
+
+    let result = if wait_for_transactions {
+        client
+            .submit_and_wait_bcs(&signed_transaction)
+            .await
+            .map(|_| ())
+    } else {
+        client.submit_bcs(&signed_transaction).await.map(|_| ())
+    };
+    match result {
+        Ok(_) => {
+            info!(
+                hash = signed_transaction.committed_hash(),
+                address = signed_transaction.sender(),
+                event = "sponsored_transaction_submitted",
+            );
+            Ok(signed_transaction)
+        },
+        Err(e) => {
+            warn!(
+                hash = signed_transaction.committed_hash(),
+                address = signed_transaction.sender(),
+                event = "sponsored_transaction_failure",
+                error_message = format!("{:#}", e)
+            );
+            Err(AptosTapError::new_with_error_code(
+                e,
+                AptosTapErrorCode::TransactionFailed,
+            ))
+        },
+    }
+}
+
 /// This struct manages gas unit price. When callers get the value through this
 /// struct, it will update the value if it is too old.
 pub struct GasUnitPriceManager {
@@ -453,3 +641,208 @@ impl GasUnitPriceManager {
             .gas_estimate)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use aptos_sdk::{
+        transaction_builder::{aptos_stdlib, TransactionFactory},
+        types::transaction::Script,
+    };
+
+    const MAX_GAS_AMOUNT: u64 = 1000;
+    const MAX_GAS_UNIT_PRICE: u64 = 100;
+
+    fn get_sponsor_config(allowed_functions: Option<Vec<&str>>) -> SponsorConfig {
+        SponsorConfig {
+            max_gas_amount: MAX_GAS_AMOUNT,
+            max_gas_unit_price: MAX_GAS_UNIT_PRICE,
+            allowed_functions: allowed_functions
+                .map(|functions| functions.into_iter().map(String::from).collect()),
+        }
+    }
+
+    /// Builds a transaction from a new account, signed by it for the given fee
+    /// payer.
+    fn get_sponsor_transaction(
+        fee_payer_address: AccountAddress,
+        payload: TransactionPayload,
+        max_gas_amount: u64,
+        gas_unit_price: u64,
+    ) -> SponsorTransaction {
+        let sender = LocalAccount::generate(&mut rand::rngs::OsRng);
+        let raw_transaction = TransactionFactory::new(ChainId::test())
+            .with_max_gas_amount(max_gas_amount)
+            .with_gas_unit_price(gas_unit_price)
+            .payload(payload)
+            .sender(sender.address())
+            .sequence_number(0)
+            .build();
+        let message = RawTransactionWithData::new_fee_payer(
+            raw_transaction.clone(),
+            vec![],
+            fee_payer_address,
+        );
+        let signature = sender.private_key().sign(&message).unwrap();
+        SponsorTransaction {
+            raw_transaction,
+            sender: AccountAuthenticator::ed25519(sender.public_key().clone(), signature),
+            secondary_signer_addresses: vec![],
+            secondary_signers: vec![],
+        }
+    }
+
+    /// Builds the raw transaction of a sponsor transaction, which is what
+    /// `SponsorConfig::check` looks at.
+    fn get_raw_transaction(
+        payload: TransactionPayload,
+        max_gas_amount: u64,
+        gas_unit_price: u64,
+    ) -> RawTransaction {
+        get_sponsor_transaction(AccountAddress::ONE, payload, max_gas_amount, gas_unit_price)
+            .raw_transaction
+    }
+
+    fn get_codes(rejection_reasons: &[RejectionReason]) -> Vec<RejectionReasonCode> {
+        rejection_reasons.iter().map(|r| r.get_code()).collect()
+    }
+
+    fn transfer_payload() -> TransactionPayload {
+        aptos_stdlib::aptos_account_transfer(AccountAddress::ONE, 10)
+    }
+
+    fn script_payload() -> TransactionPayload {
+        TransactionPayload::Script(Script::new(vec![], vec![], vec![]))
+    }
+
+    #[test]
+    fn test_sponsor_config_check_gas_budget() {
+        let sponsor_config = get_sponsor_config(None);
+
+        // Right at the limits is fine, scripts included.
+        for payload in [transfer_payload(), script_payload()] {
+            let transaction = get_raw_transaction(payload, MAX_GAS_AMOUNT, MAX_GAS_UNIT_PRICE);
+            assert!(sponsor_config.check(&transaction).is_empty());
+        }
+
+        // Going over either limit is rejected, with a reason for each.
+        let transaction =
+            get_raw_transaction(transfer_payload(), MAX_GAS_AMOUNT + 1, MAX_GAS_UNIT_PRICE);
+        assert_eq!(get_codes(&sponsor_config.check(&transaction)), vec![
+            RejectionReasonCode::GasBudgetExceeded
+        ]);
+        let transaction =
+            get_raw_transaction(transfer_payload(), MAX_GAS_AMOUNT, MAX_GAS_UNIT_PRICE + 1);
+        assert_eq!(get_codes(&sponsor_config.check(&transaction)), vec![
+            RejectionReasonCode::GasBudgetExceeded
+        ]);
+        let transaction = get_raw_transaction(
+            transfer_payload(),
+            MAX_GAS_AMOUNT + 1,
+            MAX_GAS_UNIT_PRICE + 1,
+        );
+        assert_eq!(get_codes(&sponsor_config.check(&transaction)), vec![
+            RejectionReasonCode::GasBudgetExceeded,
+            RejectionReasonCode::GasBudgetExceeded,
+        ]);
+    }
+
+    #[test]
+    fn test_sponsor_config_check_allowed_functions() {
+        let sponsor_config = get_sponsor_config(Some(vec!["0x1::aptos_account::transfer"]));
+
+        let transaction =
+            get_raw_transaction(transfer_payload(), MAX_GAS_AMOUNT, MAX_GAS_UNIT_PRICE);
+        assert!(sponsor_config.check(&transaction).is_empty());
+
+        // Other entry functions and scripts are rejected.
+        for payload in [
+            aptos_stdlib::aptos_account_create_account(AccountAddress::ONE),
+            script_payload(),
+        ] {
+            let transaction = get_raw_transaction(payload, MAX_GAS_AMOUNT, MAX_GAS_UNIT_PRICE);
+            assert_eq!(get_codes(&sponsor_config.check(&transaction)), vec![
+                RejectionReasonCode::FunctionNotAllowed
+            ]);
+        }
+
+        // Nothing is sponsored with an empty allowlist.
+        let sponsor_config = get_sponsor_config(Some(vec![]));
+        let transaction =
+            get_raw_transaction(transfer_payload(), MAX_GAS_AMOUNT, MAX_GAS_UNIT_PRICE);
+        assert_eq!(get_codes(&sponsor_config.check(&transaction)), vec![
+            RejectionReasonCode::FunctionNotAllowed
+        ]);
+    }
+
+    /// These are all rejected before anything is submitted, so the client
+    /// never gets used.
+    #[tokio::test]
+    async fn test_sponsor_transaction_rejections() {
+        let client = Client::new(Url::parse("http://127.0.0.1:1").unwrap());
+        let fee_payer = LocalAccount::generate(&mut rand::rngs::OsRng);
+        let fee_payer_address = fee_payer.address();
+        let fee_payer = RwLock::new(fee_payer);
+        let sponsor_config = get_sponsor_config(Some(vec!["0x1::aptos_account::transfer"]));
+
+        // The transaction is for another chain.
+        let transaction = get_sponsor_transaction(
+            fee_payer_address,
+            transfer_payload(),
+            MAX_GAS_AMOUNT,
+            MAX_GAS_UNIT_PRICE,
+        );
+        let error = sponsor_transaction(
+            &client,
+            &fee_payer,
+            &sponsor_config,
+            ChainId::new(ChainId::test().id() + 1),
+            transaction,
+            false,
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(error.error_code, AptosTapErrorCode::InvalidRequest);
+
+        // The transaction is over the gas budget.
+        let transaction = get_sponsor_transaction(
+            fee_payer_address,
+            transfer_payload(),
+            MAX_GAS_AMOUNT + 1,
+            MAX_GAS_UNIT_PRICE,
+        );
+        let error = sponsor_transaction(
+            &client,
+            &fee_payer,
+            &sponsor_config,
+            ChainId::test(),
+            transaction,
+            false,
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(error.error_code, AptosTapErrorCode::Rejected);
+        assert_eq!(get_codes(&error.rejection_reasons), vec![
+            RejectionReasonCode::GasBudgetExceeded
+        ]);
+
+        // The sender signed the transaction for a different fee payer.
+        let transaction = get_sponsor_transaction(
+            AccountAddress::ONE,
+            transfer_payload(),
+            MAX_GAS_AMOUNT,
+            MAX_GAS_UNIT_PRICE,
+        );
+        let error = sponsor_transaction(
+            &client,
+            &fee_payer,
+            &sponsor_config,
+            ChainId::test(),
+            transaction,
+            false,
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(error.error_code, AptosTapErrorCode::InvalidRequest);
+    }
+}
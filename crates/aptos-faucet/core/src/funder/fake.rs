@@ -24,6 +24,14 @@ impl FunderTrait for FakeFunder {
         Ok(vec![])
     }
 
+    async fn fund_batch(
+        &self,
+        _receivers: Vec<(AccountAddress, u64)>,
+        _check_only: bool,
+    ) -> Result<Vec<SignedTransaction>, AptosTapError> {
+        Ok(vec![])
+    }
+
     fn get_amount(&self, amount: Option<u64>, _did_bypass_checkers: bool) -> u64 {
         amount.unwrap_or(100)
     }
@@ -29,8 +29,8 @@ static MINTER_SCRIPT: &[u8] = include_bytes!(
 );
 
 use super::common::{
-    submit_transaction, update_sequence_numbers, ApiConnectionConfig, GasUnitPriceManager,
-    TransactionSubmissionConfig,
+    sponsor_transaction, submit_transaction, update_sequence_numbers, ApiConnectionConfig,
+    GasUnitPriceManager, SponsorTransaction, TransactionSubmissionConfig,
 };
 
 /// explain these contain additional args for the mint funder.
@@ -220,6 +220,7 @@ impl MintFunder {
             &self.outstanding_requests,
             receiver_address,
             amount,
+            1,
             self.txn_config.wait_for_outstanding_txns_secs,
         )
         .await?;
@@ -261,6 +262,87 @@ impl MintFunder {
             .await?,
         ])
     }
+
+    /// The minter script only funds a single account, so to fund many accounts
+    /// at once we mint the total to the faucet account and then transfer it to
+    /// all the receivers in a single batch transfer.
+    pub async fn process_batch(
+        &self,
+        client: &Client,
+        receivers: Vec<(AccountAddress, u64)>,
+        check_only: bool,
+        wait_for_transactions: bool,
+    ) -> Result<Vec<SignedTransaction>, AptosTapError> {
+        let (first_receiver, _) = *receivers.first().ok_or_else(|| {
+            AptosTapError::new(
+                "No accounts to fund were given".to_string(),
+                AptosTapErrorCode::InvalidRequest,
+            )
+        })?;
+        let total_amount = receivers
+            .iter()
+            .try_fold(0u64, |total, (_, amount)| total.checked_add(*amount))
+            .ok_or_else(|| {
+                AptosTapError::new(
+                    "Total amount to fund overflows".to_string(),
+                    AptosTapErrorCode::InvalidRequest,
+                )
+            })?;
+
+        update_sequence_numbers(
+            client,
+            &self.faucet_account,
+            &self.outstanding_requests,
+            first_receiver,
+            total_amount,
+            // The mint and the batch transfer.
+            2,
+            self.txn_config.wait_for_outstanding_txns_secs,
+        )
+        .await?;
+
+        if check_only {
+            return Ok(vec![]);
+        }
+
+        let transaction_factory = self.get_transaction_factory().await?;
+        let (recipients, amounts) = receivers.into_iter().unzip();
+
+        // We submit these one at a time so that if the first one fails, we
+        // don't leave a gap in the sequence numbers of the faucet account.
+        let mint_txn = {
+            let faucet_account = self.faucet_account.write().await;
+            faucet_account.sign_with_transaction_builder(transaction_factory.payload(
+                aptos_stdlib::aptos_coin_mint(faucet_account.address(), total_amount),
+            ))
+        };
+        let mint_txn = submit_transaction(
+            client,
+            &self.faucet_account,
+            mint_txn,
+            &first_receiver,
+            wait_for_transactions,
+        )
+        .await?;
+
+        let transfer_txn = self
+            .faucet_account
+            .write()
+            .await
+            .sign_with_transaction_builder(transaction_factory.payload(
+                aptos_stdlib::aptos_account_batch_transfer(recipients, amounts),
+            ));
+        let transfer_txn = submit_transaction(
+            client,
+            &self.faucet_account,
+            transfer_txn,
+            &first_receiver,
+            wait_for_transactions,
+        )
+        .await?;
+
+        Ok(vec![mint_txn, transfer_txn])
+    }
 }
 
 #[async_trait]
@@ -284,6 +366,49 @@ impl FunderTrait for MintFunder {
         .await
     }
 
+    async fn fund_batch(
+        &self,
+        receivers: Vec<(AccountAddress, u64)>,
+        check_only: bool,
+    ) -> Result<Vec<SignedTransaction>, AptosTapError> {
+        let client = self.get_api_client();
+        self.process_batch(
+            &client,
+            receivers,
+            check_only,
+            self.txn_config.wait_for_transactions,
+        )
+        .await
+    }
+
+    async fn sponsor(
+        &self,
+        transaction: SponsorTransaction,
+    ) -> Result<SignedTransaction, AptosTapError> {
+        let sponsor_config = self.txn_config.sponsor_config.as_ref().ok_or_else(|| {
+            AptosTapError::new(
+                "Sponsoring transactions is not enabled".to_string(),
+                AptosTapErrorCode::EndpointNotEnabled,
+            )
+        })?;
+        sponsor_transaction(
+            &self.get_api_client(),
+            &self.faucet_account,
+            sponsor_config,
+            self.transaction_factory.get_chain_id(),
+            transaction,
+            self.txn_config.wait_for_transactions,
+        )
+        .await
+    }
+
+    async fn get_sponsor_address(&self) -> Option<AccountAddress> {
+        match self.txn_config.sponsor_config {
+            Some(_) => Some(self.faucet_account.read().await.address()),
+            None => None,
+        }
+    }
+
     fn get_amount(&self, amount: Option<u64>, did_bypass_checkers: bool) -> u64 {
         match (
             amount,
@@ -7,11 +7,11 @@ mod mint;
 mod transfer;
 
 pub use self::{
-    common::{ApiConnectionConfig, TransactionSubmissionConfig},
+    common::{ApiConnectionConfig, SponsorConfig, SponsorTransaction, TransactionSubmissionConfig},
     mint::MintFunderConfig,
 };
 use self::{fake::FakeFunderConfig, transfer::TransferFunderConfig};
-use crate::endpoints::AptosTapError;
+use crate::endpoints::{AptosTapError, AptosTapErrorCode};
 use anyhow::{Context, Result};
 use aptos_sdk::types::{account_address::AccountAddress, transaction::SignedTransaction};
 use async_trait::async_trait;
@@ -42,6 +42,37 @@ pub trait FunderTrait: Sync + Send + 'static {
         did_bypass_checkers: bool,
     ) -> Result<Vec<SignedTransaction>, AptosTapError>;
 
+    /// Like `fund`, but funds all the given accounts at once. Unlike `fund`,
+    /// the amounts given here have already been determined using `get_amount`.
+    async fn fund_batch(
+        &self,
+        _receivers: Vec<(AccountAddress, u64)>,
+        _check_only: bool,
+    ) -> Result<Vec<SignedTransaction>, AptosTapError> {
+        Err(AptosTapError::new(
+            "This Funder does not support batch funding".to_string(),
+            AptosTapErrorCode::EndpointNotEnabled,
+        ))
+    }
+
+    /// Signs the given transaction as fee payer and submits it, assuming it is
+    /// within the configured limits.
+    async fn sponsor(
+        &self,
+        _transaction: SponsorTransaction,
+    ) -> Result<SignedTransaction, AptosTapError> {
+        Err(AptosTapError::new(
+            "This Funder does not support sponsoring transactions".to_string(),
+            AptosTapErrorCode::EndpointNotEnabled,
+        ))
+    }
+
+    /// The address sponsored transactions must use as fee payer, or None if
+    /// this Funder doesn't sponsor transactions.
+    async fn get_sponsor_address(&self) -> Option<AccountAddress> {
+        None
+    }
+
     /// Given a requested amount and any configuration internal to this funder,
     /// determine the amount that can be funded.
     fn get_amount(
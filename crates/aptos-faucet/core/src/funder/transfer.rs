@@ -3,7 +3,8 @@
 
 use super::{
     common::{
-        submit_transaction, ApiConnectionConfig, GasUnitPriceManager, TransactionSubmissionConfig,
+        sponsor_transaction, submit_transaction, ApiConnectionConfig, GasUnitPriceManager,
+        SponsorConfig, SponsorTransaction, TransactionSubmissionConfig,
     },
     FunderHealthMessage, FunderTrait,
 };
@@ -74,6 +75,7 @@ impl TransferFunderConfig {
             self.transaction_submission_config
                 .wait_for_outstanding_txns_secs,
             self.transaction_submission_config.wait_for_transactions,
+            self.transaction_submission_config.sponsor_config.clone(),
         );
 
         Ok(funder)
@@ -110,6 +112,9 @@ pub struct TransferFunder {
 
     /// If set, we won't return responses until the transaction is processed.
     wait_for_transactions: bool,
+
+    /// If set, we'll pay the fees for transactions within these limits.
+    sponsor_config: Option<SponsorConfig>,
 }
 
 impl TransferFunder {
@@ -125,6 +130,7 @@ impl TransferFunder {
         transaction_expiration_secs: u64,
         wait_for_outstanding_txns_secs: u64,
         wait_for_transactions: bool,
+        sponsor_config: Option<SponsorConfig>,
     ) -> Self {
         let gas_unit_price_manager =
             GasUnitPriceManager::new(node_url.clone(), gas_unit_price_ttl_secs);
@@ -142,6 +148,7 @@ impl TransferFunder {
             outstanding_requests: RwLock::new(vec![]),
             wait_for_outstanding_txns_secs,
             wait_for_transactions,
+            sponsor_config,
         }
     }
 
@@ -254,6 +261,7 @@ impl FunderTrait for TransferFunder {
             &self.outstanding_requests,
             receiver_address,
             amount,
+            1,
             self.wait_for_outstanding_txns_secs,
         )
         .await?;
@@ -296,6 +304,121 @@ impl FunderTrait for TransferFunder {
         Ok(transactions)
     }
 
+    /// This funds all the receivers with a single batch transfer. Like `fund`,
+    /// this only funds accounts that don't exist yet.
+    async fn fund_batch(
+        &self,
+        receivers: Vec<(AccountAddress, u64)>,
+        check_only: bool,
+    ) -> Result<Vec<SignedTransaction>, AptosTapError> {
+        self.is_healthy_as_result().await?;
+
+        let client = self.get_api_client();
+
+        let (first_receiver, _) = *receivers.first().ok_or_else(|| {
+            AptosTapError::new(
+                "No accounts to fund were given".to_string(),
+                AptosTapErrorCode::InvalidRequest,
+            )
+        })?;
+        let total_amount = receivers
+            .iter()
+            .try_fold(0u64, |total, (_, amount)| total.checked_add(*amount))
+            .ok_or_else(|| {
+                AptosTapError::new(
+                    "Total amount to fund overflows".to_string(),
+                    AptosTapErrorCode::InvalidRequest,
+                )
+            })?;
+
+        // The batch transfer doesn't fail if an account already exists, so
+        // we have to check for that ourselves.
+        let accounts = futures::future::join_all(
+            receivers
+                .iter()
+                .map(|(receiver_address, _)| client.get_account(*receiver_address)),
+        )
+        .await;
+        let rejection_reasons: Vec<RejectionReason> = receivers
+            .iter()
+            .zip(accounts)
+            .filter(|(_, account)| account.is_ok())
+            .map(|((receiver_address, _), _)| {
+                RejectionReason::new(
+                    format!("Account {} already exists", receiver_address),
+                    RejectionReasonCode::AccountAlreadyExists,
+                )
+            })
+            .collect();
+        if !rejection_reasons.is_empty() {
+            return Err(AptosTapError::new(
+                "Account ineligible".to_string(),
+                AptosTapErrorCode::Rejected,
+            )
+            .rejection_reasons(rejection_reasons));
+        }
+
+        update_sequence_numbers(
+            &client,
+            &self.faucet_account,
+            &self.outstanding_requests,
+            first_receiver,
+            total_amount,
+            1,
+            self.wait_for_outstanding_txns_secs,
+        )
+        .await?;
+
+        if check_only {
+            return Ok(vec![]);
+        }
+
+        let (recipients, amounts) = receivers.into_iter().unzip();
+        let txn = self
+            .execute_transaction(
+                &client,
+                aptos_stdlib::aptos_account_batch_transfer(recipients, amounts),
+                &first_receiver,
+            )
+            .await?;
+        info!(
+            hash = txn.committed_hash().to_hex_literal(),
+            address = first_receiver,
+            amount = total_amount,
+            event = "transaction_submitted"
+        );
+        Ok(vec![txn])
+    }
+
+    async fn sponsor(
+        &self,
+        transaction: SponsorTransaction,
+    ) -> Result<SignedTransaction, AptosTapError> {
+        let sponsor_config = self.sponsor_config.as_ref().ok_or_else(|| {
+            AptosTapError::new(
+                "Sponsoring transactions is not enabled".to_string(),
+                AptosTapErrorCode::EndpointNotEnabled,
+            )
+        })?;
+        self.is_healthy_as_result().await?;
+        sponsor_transaction(
+            &self.get_api_client(),
+            &self.faucet_account,
+            sponsor_config,
+            self.transaction_factory.get_chain_id(),
+            transaction,
+            self.wait_for_transactions,
+        )
+        .await
+    }
+
+    async fn get_sponsor_address(&self) -> Option<AccountAddress> {
+        match self.sponsor_config {
+            Some(_) => Some(self.faucet_account.read().await.address()),
+            None => None,
+        }
+    }
+
     fn get_amount(
         &self,
         amount: Option<u64>,
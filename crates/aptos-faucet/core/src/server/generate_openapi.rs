@@ -3,7 +3,9 @@
 
 use crate::{
     checkers::CaptchaManager,
-    endpoints::{build_openapi_service, BasicApi, CaptchaApi, FundApi, FundApiComponents},
+    endpoints::{
+        build_openapi_service, BasicApi, CaptchaApi, FundApi, FundApiComponents, QuotaApi,
+    },
     funder::{FakeFunder, Funder},
 };
 use anyhow::Result;
@@ -20,14 +22,16 @@ pub struct GenerateOpenapi {
 impl GenerateOpenapi {
     pub async fn generate_openapi(&self) -> Result<()> {
         let funder = Arc::new(Funder::from(FakeFunder));
+        let fund_api_components = Arc::new(FundApiComponents {
+            bypassers: Vec::new(),
+            checkers: Vec::new(),
+            funder: funder.clone(),
+            return_rejections_early: true,
+            max_batch_size: 10,
+            concurrent_requests_semaphore: None,
+        });
         let fund_api = FundApi {
-            components: Arc::new(FundApiComponents {
-                bypassers: Vec::new(),
-                checkers: Vec::new(),
-                funder: funder.clone(),
-                return_rejections_early: true,
-                concurrent_requests_semaphore: None,
-            }),
+            components: fund_api_components.clone(),
         };
 
         let api_service = build_openapi_service(
@@ -40,6 +44,9 @@ impl GenerateOpenapi {
                 captcha_manager: Arc::new(Mutex::new(CaptchaManager::new())),
             },
             fund_api,
+            QuotaApi {
+                components: fund_api_components,
+            },
         );
 
         let spec = match self.output_args.format {
@@ -7,7 +7,7 @@ use crate::{
     checkers::{CaptchaManager, Checker, CheckerConfig, CheckerTrait},
     endpoints::{
         build_openapi_service, convert_error, mint, BasicApi, CaptchaApi, FundApi,
-        FundApiComponents,
+        FundApiComponents, QuotaApi,
     },
     funder::{ApiConnectionConfig, FunderConfig, MintFunderConfig, TransactionSubmissionConfig},
     middleware::middleware_log,
@@ -43,6 +43,17 @@ pub struct HandlerConfig {
     /// This allows the tap to avoid overloading its Funder, as well as to
     /// signal to a healthchecker that it is overloaded (via `/`).
     pub max_concurrent_requests: Option<usize>,
+
+    /// The maximum number of accounts that can be funded in one request to
+    /// `/fund_batch`.
+    #[serde(default = "HandlerConfig::default_max_batch_size")]
+    pub max_batch_size: usize,
+}
+
+impl HandlerConfig {
+    fn default_max_batch_size() -> usize {
+        10
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -133,6 +144,7 @@ impl RunConfig {
             checkers,
             funder,
             return_rejections_early: self.handler_config.return_rejections_early,
+            max_batch_size: self.handler_config.max_batch_size,
             concurrent_requests_semaphore,
         });
 
@@ -153,7 +165,11 @@ impl RunConfig {
             captcha_manager,
         };
 
-        let api_service = build_openapi_service(basic_api, captcha_api, fund_api);
+        let quota_api = QuotaApi {
+            components: fund_api_components.clone(),
+        };
+
+        let api_service = build_openapi_service(basic_api, captcha_api, fund_api, quota_api);
         let spec_json = api_service.spec_endpoint();
         let spec_yaml = api_service.spec_endpoint_yaml();
 
@@ -277,6 +293,7 @@ impl RunConfig {
                 use_helpful_errors: true,
                 return_rejections_early: false,
                 max_concurrent_requests: None,
+                max_batch_size: HandlerConfig::default_max_batch_size(),
             },
         }
     }
@@ -362,18 +379,39 @@ impl RunSimple {
 mod test {
     use super::*;
     use crate::{
+        checkers::{sign_webhook_request, WEBHOOK_SIGNATURE_HEADER, WEBHOOK_TIMESTAMP_HEADER},
         endpoints::{
-            AptosTapError, AptosTapErrorCode, FundRequest, FundResponse, RejectionReasonCode,
+            AptosTapError, AptosTapErrorCode, FundBatchRequest, FundRequest, FundResponse,
+            QuotaUsageResponse, RejectionReasonCode, SponsorInfoResponse, SponsorRequest,
+            SponsorResponse,
         },
         helpers::get_current_time_secs,
     };
     use anyhow::{bail, Result};
     use aptos_sdk::{
-        crypto::{ed25519::Ed25519PrivateKey, HashValue, PrivateKey, Uniform},
-        types::{account_address::AccountAddress, transaction::authenticator::AuthenticationKey},
+        bcs,
+        crypto::{ed25519::Ed25519PrivateKey, HashValue, PrivateKey, SigningKey, Uniform},
+        transaction_builder::{aptos_stdlib, TransactionFactory},
+        types::{
+            account_address::AccountAddress,
+            chain_id::ChainId,
+            transaction::{
+                authenticator::{AccountAuthenticator, AuthenticationKey},
+                RawTransactionWithData, TransactionPayload,
+            },
+            LocalAccount,
+        },
     };
     use once_cell::sync::OnceCell;
-    use poem::http::header::{AUTHORIZATION, CONTENT_TYPE, REFERER};
+    use poem::{
+        http::{
+            header::{AUTHORIZATION, CONTENT_TYPE, REFERER, RETRY_AFTER},
+            HeaderMap, StatusCode,
+        },
+        listener::{Acceptor, Listener},
+        web::Json,
+        IntoResponse,
+    };
     use poem_openapi::types::{ParseFromJSON, ToJSON};
     use rand::{
         rngs::{OsRng, StdRng},
@@ -408,7 +446,7 @@ mod test {
         format!("{}/fund", get_root_endpoint(port))
     }
 
-    async fn start_server(config_content: &'static str) -> Result<(u16, JoinHandle<Result<()>>)> {
+    async fn start_server(config_content: &str) -> Result<(u16, JoinHandle<Result<()>>)> {
         // Load config.
         let run_config: RunConfig =
             serde_yaml::from_str(config_content).context("Failed to parse config content")?;
@@ -461,6 +499,10 @@ mod test {
         make_list_file("/tmp/auth_tokens.txt", auth_tokens)
     }
 
+    fn make_quota_usage_auth_tokens_file(auth_tokens: &[&str]) -> Result<()> {
+        make_list_file("/tmp/quota_usage_auth_tokens.txt", auth_tokens)
+    }
+
     fn make_ip_allowlist(ip_ranges: &[&str]) -> Result<()> {
         make_list_file("/tmp/ip_allowlist.txt", ip_ranges)
     }
@@ -604,6 +646,104 @@ mod test {
         Ok(())
     }
 
+    /// Stands in for an external abuse detection service. It rejects requests
+    /// with a bad signature, fails for the receiver in the forwarded broken
+    /// receiver header, and otherwise decides based on the forwarded wallet
+    /// header.
+    #[poem::handler]
+    async fn webhook(headers: &HeaderMap, body: String) -> poem::Response {
+        let header = |name| headers.get(name).unwrap().to_str().unwrap();
+        let timestamp = header(WEBHOOK_TIMESTAMP_HEADER).parse().unwrap();
+        if header(WEBHOOK_SIGNATURE_HEADER)
+            != sign_webhook_request("test_signing_key", timestamp, &body)
+        {
+            return StatusCode::UNAUTHORIZED.into_response();
+        }
+
+        let request: serde_json::Value = serde_json::from_str(&body).unwrap();
+        if request["headers"]["broken_receiver"] == request["receiver"] {
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+        let response = match request["headers"]["what_wallet_my_guy"].as_str() {
+            Some("bad_wallet") => serde_json::json!({"decision": "deny", "reason": "Bad wallet"}),
+            Some("busy_wallet") => {
+                serde_json::json!({"decision": "ratelimit", "retry_after_secs": 60})
+            },
+            _ => serde_json::json!({"decision": "allow"}),
+        };
+        Json(response).into_response()
+    }
+
+    /// Binds the webhook to an ephemeral port and returns the config with the
+    /// checker pointed at it.
+    async fn start_webhook(config_content: &str) -> Result<String> {
+        let acceptor = TcpListener::bind("127.0.0.1:0").into_acceptor().await?;
+        let webhook_addr = *acceptor.local_addr()[0]
+            .as_socket_addr()
+            .context("Webhook server is not listening on a socket address")?;
+        tokio::spawn(
+            Server::new_with_acceptor(acceptor).run(Route::new().at("/check", poem::post(webhook))),
+        );
+        Ok(config_content.replace("127.0.0.1:9106", &webhook_addr.to_string()))
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_webhook_checker() -> Result<()> {
+        init();
+        let config_content =
+            start_webhook(include_str!("../../../configs/testing_webhook.yaml")).await?;
+        let (port, _handle) = start_server(&config_content).await?;
+
+        // Assert that a request the webhook allows passes, which also means
+        // the webhook accepted the signature.
+        unwrap_reqwest_result(
+            reqwest::Client::new()
+                .post(get_fund_endpoint(port))
+                .body(get_fund_request(Some(10)).to_json_string())
+                .header(CONTENT_TYPE, "application/json")
+                .header("what_wallet_my_guy", "good_wallet")
+                .send()
+                .await,
+        )
+        .await?;
+
+        // Assert that a request the webhook denies is rejected.
+        let response = reqwest::Client::new()
+            .post(get_fund_endpoint(port))
+            .body(get_fund_request(Some(10)).to_json_string())
+            .header(CONTENT_TYPE, "application/json")
+            .header("what_wallet_my_guy", "bad_wallet")
+            .send()
+            .await?;
+        assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+        let aptos_error = AptosTapError::parse_from_json_string(&response.text().await?)
+            .expect("Failed to read response as AptosError");
+        assert_eq!(aptos_error.rejection_reasons.len(), 1);
+        assert_eq!(
+            aptos_error.rejection_reasons[0].get_code(),
+            RejectionReasonCode::WebhookRejected
+        );
+
+        // Assert that a request the webhook ratelimits gets a 429.
+        let response = reqwest::Client::new()
+            .post(get_fund_endpoint(port))
+            .body(get_fund_request(Some(10)).to_json_string())
+            .header(CONTENT_TYPE, "application/json")
+            .header("what_wallet_my_guy", "busy_wallet")
+            .send()
+            .await?;
+        assert_eq!(response.status(), reqwest::StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers().get(RETRY_AFTER).unwrap(), "60");
+        let aptos_error = AptosTapError::parse_from_json_string(&response.text().await?)
+            .expect("Failed to read response as AptosError");
+        assert_eq!(
+            aptos_error.rejection_reasons[0].get_code(),
+            RejectionReasonCode::WebhookRatelimited
+        );
+
+        Ok(())
+    }
+
     #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
     async fn test_redis_ratelimiter() -> Result<()> {
         // Assert that a localnet is alive.
@@ -675,6 +815,295 @@ mod test {
         Ok(())
     }
 
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_quota_checker() -> Result<()> {
+        init();
+        make_quota_usage_auth_tokens_file(&["quota_token"])?;
+        let config_content = include_str!("../../../configs/testing_quota.yaml");
+        let (port, _handle) = start_server(config_content).await?;
+
+        // Use the same receiver for all the requests, so they count against
+        // the same per account quota.
+        let fund_request = get_fund_request(Some(10));
+        let receiver = fund_request.receiver().unwrap();
+
+        // The first 2 requests fit within the quota.
+        for _ in 0..2 {
+            unwrap_reqwest_result(
+                reqwest::Client::new()
+                    .post(get_fund_endpoint(port))
+                    .body(fund_request.to_json_string())
+                    .header(CONTENT_TYPE, "application/json")
+                    .send()
+                    .await,
+            )
+            .await?;
+        }
+
+        // The third would put the account over the max amount per day.
+        let response = reqwest::Client::new()
+            .post(get_fund_endpoint(port))
+            .body(fund_request.to_json_string())
+            .header(CONTENT_TYPE, "application/json")
+            .send()
+            .await?;
+        assert_eq!(response.status(), reqwest::StatusCode::TOO_MANY_REQUESTS);
+        assert!(response.headers().contains_key(RETRY_AFTER));
+        let aptos_error = AptosTapError::parse_from_json_string(&response.text().await?)
+            .expect("Failed to read response as AptosError");
+        let rejection_reason_codes: HashSet<RejectionReasonCode> = aptos_error
+            .rejection_reasons
+            .into_iter()
+            .map(|r| r.get_code())
+            .collect();
+        assert_eq!(
+            rejection_reason_codes,
+            HashSet::from([RejectionReasonCode::AccountUsageLimitExhausted])
+        );
+
+        // Other accounts are unaffected.
+        unwrap_reqwest_result(
+            reqwest::Client::new()
+                .post(get_fund_endpoint(port))
+                .body(get_fund_request(Some(10)).to_json_string())
+                .header(CONTENT_TYPE, "application/json")
+                .send()
+                .await,
+        )
+        .await?;
+
+        // The rejected request is not counted in the reported usage.
+        let response = unwrap_reqwest_result(
+            reqwest::Client::new()
+                .get(format!(
+                    "{}/quota_usage?address={}&ip=127.0.0.1",
+                    get_root_endpoint(port),
+                    receiver
+                ))
+                .header(AUTHORIZATION, "Bearer quota_token")
+                .send()
+                .await,
+        )
+        .await?;
+        let usage_response = QuotaUsageResponse::parse_from_json_string(&response.text().await?)
+            .expect("Failed to read response as QuotaUsageResponse");
+        assert_eq!(usage_response.usage.len(), 2);
+        let account_usage = &usage_response.usage[0];
+        assert_eq!(account_usage.dimension, "account");
+        assert_eq!(account_usage.id, receiver.to_hex_literal());
+        assert_eq!(account_usage.requests, 2);
+        assert_eq!(account_usage.amount, 20);
+        assert_eq!(account_usage.max_amount_per_day, Some(25));
+        let subnet_usage = &usage_response.usage[1];
+        assert_eq!(subnet_usage.dimension, "subnet");
+        assert_eq!(subnet_usage.id, "127.0.0.0/24");
+        assert!(subnet_usage.requests >= 3);
+
+        // The usage of past days is reported separately.
+        let response = unwrap_reqwest_result(
+            reqwest::Client::new()
+                .get(format!(
+                    "{}/quota_usage?address={}&day={}",
+                    get_root_endpoint(port),
+                    receiver,
+                    usage_response.day - 1
+                ))
+                .header(AUTHORIZATION, "Bearer quota_token")
+                .send()
+                .await,
+        )
+        .await?;
+        let usage_response = QuotaUsageResponse::parse_from_json_string(&response.text().await?)
+            .expect("Failed to read response as QuotaUsageResponse");
+        assert_eq!(usage_response.usage[0].requests, 0);
+
+        // Reading the usage requires an allowed auth token.
+        for auth_token in [None, Some("Bearer wrong_token")] {
+            let mut request = reqwest::Client::new().get(format!(
+                "{}/quota_usage?address={}",
+                get_root_endpoint(port),
+                receiver
+            ));
+            if let Some(auth_token) = auth_token {
+                request = request.header(AUTHORIZATION, auth_token);
+            }
+            let response = request.send().await?;
+            assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+            let aptos_error = AptosTapError::parse_from_json_string(&response.text().await?)
+                .expect("Failed to read response as AptosError");
+            assert_eq!(
+                aptos_error.rejection_reasons[0].get_code(),
+                RejectionReasonCode::AuthTokenInvalid
+            );
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_fund_batch() -> Result<()> {
+        init();
+        make_quota_usage_auth_tokens_file(&["quota_token"])?;
+        let config_content = include_str!("../../../configs/testing_quota.yaml");
+        let (port, _handle) = start_server(config_content).await?;
+        let fund_batch_endpoint = format!("{}/fund_batch", get_root_endpoint(port));
+
+        let fund_request = get_fund_request(Some(10));
+        let other_fund_request = get_fund_request(Some(10));
+
+        // Fund two accounts at once, then one of them again on its own.
+        unwrap_reqwest_result(
+            reqwest::Client::new()
+                .post(&fund_batch_endpoint)
+                .body(
+                    FundBatchRequest {
+                        requests: vec![fund_request.clone(), get_fund_request(Some(10))],
+                    }
+                    .to_json_string(),
+                )
+                .header(CONTENT_TYPE, "application/json")
+                .send()
+                .await,
+        )
+        .await?;
+        unwrap_reqwest_result(
+            reqwest::Client::new()
+                .post(get_fund_endpoint(port))
+                .body(fund_request.to_json_string())
+                .header(CONTENT_TYPE, "application/json")
+                .send()
+                .await,
+        )
+        .await?;
+
+        // A batch including the account at its quota is rejected as a whole.
+        let response = reqwest::Client::new()
+            .post(&fund_batch_endpoint)
+            .body(
+                FundBatchRequest {
+                    requests: vec![fund_request.clone(), other_fund_request.clone()],
+                }
+                .to_json_string(),
+            )
+            .header(CONTENT_TYPE, "application/json")
+            .send()
+            .await?;
+        assert_eq!(response.status(), reqwest::StatusCode::TOO_MANY_REQUESTS);
+        let aptos_error = AptosTapError::parse_from_json_string(&response.text().await?)
+            .expect("Failed to read response as AptosError");
+        let rejection_reason_codes: HashSet<RejectionReasonCode> = aptos_error
+            .rejection_reasons
+            .into_iter()
+            .map(|r| r.get_code())
+            .collect();
+        assert_eq!(
+            rejection_reason_codes,
+            HashSet::from([RejectionReasonCode::AccountUsageLimitExhausted])
+        );
+
+        // The other account in the rejected batch wasn't counted.
+        let response = unwrap_reqwest_result(
+            reqwest::Client::new()
+                .get(format!(
+                    "{}/quota_usage?address={}",
+                    get_root_endpoint(port),
+                    other_fund_request.receiver().unwrap()
+                ))
+                .header(AUTHORIZATION, "Bearer quota_token")
+                .send()
+                .await,
+        )
+        .await?;
+        let usage_response = QuotaUsageResponse::parse_from_json_string(&response.text().await?)
+            .expect("Failed to read response as QuotaUsageResponse");
+        assert_eq!(usage_response.usage[0].requests, 0);
+
+        // Batches must be within the configured size.
+        let response = reqwest::Client::new()
+            .post(&fund_batch_endpoint)
+            .body(FundBatchRequest { requests: vec![] }.to_json_string())
+            .header(CONTENT_TYPE, "application/json")
+            .send()
+            .await?;
+        assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+        let response = reqwest::Client::new()
+            .post(&fund_batch_endpoint)
+            .body(
+                FundBatchRequest {
+                    requests: (0..11).map(|_| get_fund_request(Some(1))).collect(),
+                }
+                .to_json_string(),
+            )
+            .header(CONTENT_TYPE, "application/json")
+            .send()
+            .await?;
+        assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+
+        // The FakeFunder doesn't sponsor transactions.
+        let response = reqwest::Client::new()
+            .get(format!("{}/sponsor_info", get_root_endpoint(port)))
+            .send()
+            .await?;
+        assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_fund_batch_checker_error() -> Result<()> {
+        init();
+        make_quota_usage_auth_tokens_file(&["quota_token"])?;
+        let config_content =
+            start_webhook(include_str!("../../../configs/testing_quota_webhook.yaml")).await?;
+        let (port, _handle) = start_server(&config_content).await?;
+
+        // The webhook fails for the second receiver, after the first one
+        // passed the checks.
+        let fund_request = get_fund_request(Some(10));
+        let broken_fund_request = get_fund_request(Some(10));
+        let response = reqwest::Client::new()
+            .post(format!("{}/fund_batch", get_root_endpoint(port)))
+            .body(
+                FundBatchRequest {
+                    requests: vec![fund_request.clone(), broken_fund_request.clone()],
+                }
+                .to_json_string(),
+            )
+            .header(CONTENT_TYPE, "application/json")
+            .header(
+                "broken_receiver",
+                broken_fund_request.receiver().unwrap().to_hex_literal(),
+            )
+            .send()
+            .await?;
+        assert_eq!(
+            response.status(),
+            reqwest::StatusCode::INTERNAL_SERVER_ERROR
+        );
+        let aptos_error = AptosTapError::parse_from_json_string(&response.text().await?)
+            .expect("Failed to read response as AptosError");
+        assert_eq!(aptos_error.error_code, AptosTapErrorCode::CheckerError);
+
+        // The first receiver wasn't counted.
+        let response = unwrap_reqwest_result(
+            reqwest::Client::new()
+                .get(format!(
+                    "{}/quota_usage?address={}",
+                    get_root_endpoint(port),
+                    fund_request.receiver().unwrap()
+                ))
+                .header(AUTHORIZATION, "Bearer quota_token")
+                .send()
+                .await,
+        )
+        .await?;
+        let usage_response = QuotaUsageResponse::parse_from_json_string(&response.text().await?)
+            .expect("Failed to read response as QuotaUsageResponse");
+        assert_eq!(usage_response.usage[0].requests, 0);
+
+        Ok(())
+    }
+
     // We skip this for now since we have no current need to use the TransferFunder.
     #[ignore]
     #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
@@ -887,6 +1316,189 @@ mod test {
         Ok(())
     }
 
+    /// Builds a request to sponsor a transaction from the sender, signed by the
+    /// sender for the given fee payer.
+    fn get_sponsor_request(
+        sender: &LocalAccount,
+        fee_payer_address: AccountAddress,
+        payload: TransactionPayload,
+        max_gas_amount: u64,
+    ) -> SponsorRequest {
+        let raw_transaction = TransactionFactory::new(ChainId::test())
+            .with_max_gas_amount(max_gas_amount)
+            .with_gas_unit_price(100)
+            .payload(payload)
+            .sender(sender.address())
+            .sequence_number(sender.sequence_number())
+            .build();
+        let message = RawTransactionWithData::new_fee_payer(
+            raw_transaction.clone(),
+            vec![],
+            fee_payer_address,
+        );
+        let signature = sender.private_key().sign(&message).unwrap();
+        let sender_authenticator =
+            AccountAuthenticator::ed25519(sender.public_key().clone(), signature);
+        SponsorRequest {
+            raw_transaction: hex::encode(bcs::to_bytes(&raw_transaction).unwrap()),
+            sender_authenticator: hex::encode(bcs::to_bytes(&sender_authenticator).unwrap()),
+            secondary_signer_addresses: vec![],
+            secondary_signer_authenticators: vec![],
+        }
+    }
+
+    async fn get_sponsor_rejection_codes(
+        port: u16,
+        sponsor_request: &SponsorRequest,
+    ) -> Result<HashSet<RejectionReasonCode>> {
+        let response = reqwest::Client::new()
+            .post(format!("{}/sponsor", get_root_endpoint(port)))
+            .body(sponsor_request.to_json_string())
+            .header(CONTENT_TYPE, "application/json")
+            .send()
+            .await?;
+        assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+        let aptos_error = AptosTapError::parse_from_json_string(&response.text().await?)
+            .expect("Failed to read response as AptosError");
+        Ok(aptos_error
+            .rejection_reasons
+            .into_iter()
+            .map(|r| r.get_code())
+            .collect())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_sponsor() -> Result<()> {
+        // Assert that a localnet is alive.
+        let aptos_node_api_client = aptos_sdk::rest_client::Client::new(
+            reqwest::Url::from_str("http://127.0.0.1:8080").unwrap(),
+        );
+        aptos_node_api_client
+            .get_index_bcs()
+            .await
+            .context("Localnet API couldn't be reached at port 8080, have you started one?")?;
+
+        init();
+        let (port, _handle) = {
+            // Ensure this server and those of the other mint funder tests
+            // don't start up simultaneously, since they're using the same mint key.
+            let _guard = MUTEX.get().unwrap().lock().await;
+            let config_content =
+                include_str!("../../../configs/testing_mint_funder_local_sponsor.yaml");
+            start_server(config_content).await?
+        };
+
+        let response = unwrap_reqwest_result(
+            reqwest::Client::new()
+                .get(format!("{}/sponsor_info", get_root_endpoint(port)))
+                .send()
+                .await,
+        )
+        .await?;
+        let fee_payer_address = AccountAddress::from_str(
+            &SponsorInfoResponse::parse_from_json_string(&response.text().await?)
+                .expect("Failed to read response as SponsorInfoResponse")
+                .fee_payer_address,
+        )?;
+
+        // Create the sender.
+        let sender = LocalAccount::generate(&mut OsRng);
+        unwrap_reqwest_result(
+            reqwest::Client::new()
+                .post(get_fund_endpoint(port))
+                .body(
+                    FundRequest {
+                        amount: Some(100),
+                        address: Some(sender.address().to_string()),
+                        ..Default::default()
+                    }
+                    .to_json_string(),
+                )
+                .header(CONTENT_TYPE, "application/json")
+                .send()
+                .await,
+        )
+        .await?;
+
+        // Transactions over the gas budget or calling other functions are
+        // rejected.
+        let sponsor_request = get_sponsor_request(
+            &sender,
+            fee_payer_address,
+            aptos_stdlib::aptos_account_transfer(AccountAddress::random(), 10),
+            10001,
+        );
+        assert_eq!(
+            get_sponsor_rejection_codes(port, &sponsor_request).await?,
+            HashSet::from([RejectionReasonCode::GasBudgetExceeded])
+        );
+        let sponsor_request = get_sponsor_request(
+            &sender,
+            fee_payer_address,
+            aptos_stdlib::aptos_account_create_account(AccountAddress::random()),
+            10000,
+        );
+        assert_eq!(
+            get_sponsor_rejection_codes(port, &sponsor_request).await?,
+            HashSet::from([RejectionReasonCode::FunctionNotAllowed])
+        );
+
+        // A transaction signed for another fee payer is invalid.
+        let sponsor_request = get_sponsor_request(
+            &sender,
+            AccountAddress::random(),
+            aptos_stdlib::aptos_account_transfer(AccountAddress::random(), 10),
+            10000,
+        );
+        let response = reqwest::Client::new()
+            .post(format!("{}/sponsor", get_root_endpoint(port)))
+            .body(sponsor_request.to_json_string())
+            .header(CONTENT_TYPE, "application/json")
+            .send()
+            .await?;
+        assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+
+        // A transaction within the limits is sponsored, so the sender pays
+        // nothing but the amount transferred.
+        let receiver_address = AccountAddress::random();
+        let sponsor_request = get_sponsor_request(
+            &sender,
+            fee_payer_address,
+            aptos_stdlib::aptos_account_transfer(receiver_address, 10),
+            10000,
+        );
+        let response = unwrap_reqwest_result(
+            reqwest::Client::new()
+                .post(format!("{}/sponsor", get_root_endpoint(port)))
+                .body(sponsor_request.to_json_string())
+                .header(CONTENT_TYPE, "application/json")
+                .send()
+                .await,
+        )
+        .await?;
+        let sponsor_response = SponsorResponse::parse_from_json_string(&response.text().await?)
+            .expect("Failed to read response as SponsorResponse");
+        let response = aptos_node_api_client
+            .get_transaction_by_hash(HashValue::from_str(&sponsor_response.txn_hash)?)
+            .await
+            .context("Failed to get transaction, it should be on-chain now")?;
+        assert!(
+            response.inner().success(),
+            "Transaction failed: {:#?}",
+            response
+        );
+        let response = aptos_node_api_client
+            .get_account_balance(sender.address())
+            .await?;
+        assert_eq!(response.into_inner().get(), 90);
+        let response = aptos_node_api_client
+            .get_account_balance(receiver_address)
+            .await?;
+        assert_eq!(response.into_inner().get(), 10);
+
+        Ok(())
+    }
+
     #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
     async fn test_maximum_amount_with_bypass() -> Result<()> {
         make_auth_tokens_file(&["test_token"])?;
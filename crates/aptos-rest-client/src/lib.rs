@@ -27,8 +27,8 @@ use aptos_api_types::{
     deserialize_from_string,
     mime_types::{BCS, BCS_SIGNED_TRANSACTION, BCS_VIEW_FUNCTION, JSON},
     AptosError, BcsBlock, Block, GasEstimation, HexEncodedBytes, IndexResponse, MoveModuleId,
-    TransactionData, TransactionOnChainData, TransactionsBatchSubmissionResult, UserTransaction,
-    VersionedEvent, ViewFunction, ViewRequest,
+    StateValueWithProof, TransactionData, TransactionOnChainData,
+    TransactionsBatchSubmissionResult, UserTransaction, VersionedEvent, ViewFunction, ViewRequest,
 };
 use aptos_crypto::HashValue;
 use aptos_logger::{debug, info, sample, sample::SampleRate};
@@ -36,8 +36,9 @@ use aptos_types::{
     account_address::AccountAddress,
     account_config::{AccountResource, CoinStoreResource, NewBlockEvent, CORE_CODE_ADDRESS},
     contract_event::EventWithVersion,
-    state_store::state_key::StateKey,
+    state_store::{state_key::StateKey, state_value::StateValue},
     transaction::SignedTransaction,
+    trusted_state::TrustedState,
 };
 use move_core_types::language_storage::StructTag;
 use reqwest::{
@@ -1417,6 +1418,52 @@ impl Client {
         Ok(response.map(|inner| inner.to_vec()))
     }
 
+    /// Retrieves a state value along with the proofs required to verify it, at the latest
+    /// state snapshot at or before `version`. The state proof includes the epoch changes
+    /// since `known_version`.
+    pub async fn get_state_value_with_proof(
+        &self,
+        state_key: &StateKey,
+        version: u64,
+        known_version: u64,
+    ) -> AptosResult<Response<StateValueWithProof>> {
+        let url = self.build_path(&format!(
+            "experimental/state_values/proof?ledger_version={}&known_version={}",
+            version, known_version
+        ))?;
+        let data = json!({
+            "key": hex::encode(bcs::to_bytes(state_key)?),
+        });
+
+        let response = self.post_bcs(url, data).await?;
+        Ok(response.and_then(|inner| bcs::from_bytes(&inner))?)
+    }
+
+    /// Retrieves a state value at the latest state snapshot at or before `version`, and
+    /// verifies it against the given trusted state. Returns the verified state value along
+    /// with the ratcheted trusted state, which the caller should persist.
+    pub async fn get_verified_state_value(
+        &self,
+        state_key: &StateKey,
+        version: u64,
+        trusted_state: &TrustedState,
+    ) -> AptosResult<Response<(Option<StateValue>, TrustedState)>> {
+        let response = self
+            .get_state_value_with_proof(state_key, version, trusted_state.version())
+            .await?;
+        let (state_value_with_proof, state) = response.into_parts();
+        let new_trusted_state = state_value_with_proof
+            .verify(state_key, trusted_state)
+            .map_err(RestError::InvalidProof)?
+            .new_state()
+            .unwrap_or_else(|| trusted_state.clone());
+
+        Ok(Response::new(
+            (state_value_with_proof.state_value, new_trusted_state),
+            state,
+        ))
+    }
+
     pub async fn get_account(&self, address: AccountAddress) -> AptosResult<Response<Account>> {
         let url = self.build_path(&format!("accounts/{}", address.to_hex()))?;
         let response = self.inner.get(url).send().await?;
@@ -1577,7 +1624,7 @@ impl Client {
                     | RestError::Json(_)
                     | RestError::Timeout(_)
                     | RestError::Unknown(_) => true,
-                    RestError::UrlParse(_) => false,
+                    RestError::UrlParse(_) | RestError::InvalidProof(_) => false,
                 },
             };
 
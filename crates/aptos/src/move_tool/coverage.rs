@@ -7,11 +7,19 @@ use async_trait::async_trait;
 use clap::{Parser, Subcommand};
 use move_compiler::compiled_unit::{CompiledUnit, NamedCompiledModule};
 use move_coverage::{
-    coverage_map::CoverageMap, format_csv_summary, format_human_summary,
-    source_coverage::SourceCoverageBuilder, summary::summarize_inst_cov,
+    coverage_map::CoverageMap,
+    export::{CoverageReport, ExportFormat},
+    format_csv_summary, format_human_summary,
+    source_coverage::SourceCoverageBuilder,
+    summary::summarize_inst_cov,
 };
 use move_disassembler::disassembler::Disassembler;
 use move_package::{compilation::compiled_package::CompiledPackage, BuildConfig, CompilerConfig};
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::PathBuf,
+};
 
 /// Display a coverage summary for all modules in a package
 ///
@@ -142,6 +150,79 @@ impl CliCommand<()> for BytecodeCoverage {
     }
 }
 
+/// Export coverage information for all modules in a package
+///
+/// Writes line and branch coverage of the package's modules in LCOV or Cobertura XML format,
+/// for consumption by coverage tooling. Source files are reported relative to the package
+/// directory.
+#[derive(Debug, Parser)]
+pub struct ExportCoverage {
+    /// The format of the coverage report
+    #[clap(long, value_enum, default_value_t = ExportFormat::Lcov)]
+    pub format: ExportFormat,
+    /// File to write the coverage report to
+    ///
+    /// Defaults to `lcov.info` or `cobertura.xml` in the package directory, depending on the
+    /// format.
+    #[clap(long, value_parser)]
+    pub output_file: Option<PathBuf>,
+    /// A filter string to determine which modules to export coverage for
+    #[clap(long, short)]
+    pub filter: Option<String>,
+    #[clap(flatten)]
+    pub move_options: MovePackageDir,
+}
+
+#[async_trait]
+impl CliCommand<String> for ExportCoverage {
+    fn command_name(&self) -> &'static str {
+        "ExportCoverage"
+    }
+
+    async fn execute(self) -> CliTypedResult<String> {
+        let package_path = self.move_options.get_package_path()?;
+        let (coverage_map, package) = compile_coverage(self.move_options)?;
+        let coverage_map = coverage_map.to_unified_exec_map();
+        let mut report = CoverageReport::new(&package_path);
+        for unit in package.root_modules() {
+            if let Some(filter_str) = &self.filter {
+                if !unit.unit.name().as_str().contains(filter_str.as_str()) {
+                    continue;
+                }
+            }
+            if let CompiledUnit::Module(NamedCompiledModule {
+                module, source_map, ..
+            }) = &unit.unit
+            {
+                report
+                    .add_module(module, source_map, &unit.source_path, &coverage_map)
+                    .map_err(|err| {
+                        CliError::UnexpectedError(format!("Failed to export coverage {}", err))
+                    })?;
+            }
+        }
+
+        let output_file = self.output_file.unwrap_or_else(|| {
+            package_path.join(match self.format {
+                ExportFormat::Lcov => "lcov.info",
+                ExportFormat::Cobertura => "cobertura.xml",
+            })
+        });
+        File::create(&output_file)
+            .and_then(|file| {
+                let mut writer = BufWriter::new(file);
+                report.write(self.format, &mut writer)?;
+                writer.flush()
+            })
+            .map_err(|err| CliError::IO(output_file.display().to_string(), err))?;
+        Ok(format!(
+            "Wrote {} coverage report to {}",
+            self.format,
+            output_file.display()
+        ))
+    }
+}
+
 fn compile_coverage(
     move_options: MovePackageDir,
 ) -> CliTypedResult<(CoverageMap, CompiledPackage)> {
@@ -178,6 +259,7 @@ pub enum CoveragePackage {
     Summary(SummaryCoverage),
     Source(SourceCoverage),
     Bytecode(BytecodeCoverage),
+    Export(ExportCoverage),
 }
 
 impl CoveragePackage {
@@ -186,6 +268,7 @@ impl CoveragePackage {
             Self::Summary(tool) => tool.execute_serialized_success().await,
             Self::Source(tool) => tool.execute_serialized_success().await,
             Self::Bytecode(tool) => tool.execute_serialized_success().await,
+            Self::Export(tool) => tool.execute_serialized().await,
         }
     }
 }
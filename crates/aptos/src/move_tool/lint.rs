@@ -0,0 +1,66 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::common::types::{CliCommand, CliError, CliTypedResult, MovePackageDir};
+use aptos_framework::{BuildOptions, BuiltPackage};
+use async_trait::async_trait;
+use clap::Parser;
+use move_compiler_v2::{lint, Experiment};
+use move_model::metadata::CompilerVersion;
+
+/// Run lint checks on a Move package
+///
+/// Compiles the package with the Move 2 compiler and reports code which is legal, but likely
+/// a mistake or needlessly complicated. Lints are reported as warnings; individual lints can
+/// be suppressed with `#[lint::allow(<name>)]` or turned into errors with
+/// `#[lint::deny(<name>)]` on a module or function.
+#[derive(Parser)]
+pub struct LintPackage {
+    /// List the available lints instead of checking the package
+    #[clap(long)]
+    pub(crate) list: bool,
+
+    #[clap(flatten)]
+    pub(crate) move_options: MovePackageDir,
+}
+
+#[async_trait]
+impl CliCommand<Vec<String>> for LintPackage {
+    fn command_name(&self) -> &'static str {
+        "LintPackage"
+    }
+
+    async fn execute(self) -> CliTypedResult<Vec<String>> {
+        if self.list {
+            return Ok(lint::all_lints()
+                .into_iter()
+                .map(|(name, description)| format!("{}: {}", name, description))
+                .collect());
+        }
+        if self.move_options.compiler_version == Some(CompilerVersion::V1) {
+            return Err(CliError::CommandArgumentError(
+                "Lint checks are only supported by compiler v2".to_string(),
+            ));
+        }
+        let build_options = BuildOptions {
+            dev: self.move_options.dev,
+            named_addresses: self.move_options.named_addresses(),
+            override_std: self.move_options.override_std.clone(),
+            skip_fetch_latest_git_deps: self.move_options.skip_fetch_latest_git_deps,
+            bytecode_version: self.move_options.bytecode_version,
+            compiler_version: Some(CompilerVersion::V2_0),
+            language_version: self.move_options.language_version,
+            skip_attribute_checks: self.move_options.skip_attribute_checks,
+            check_test_code: self.move_options.check_test_code,
+            install_dir: self.move_options.output_dir.clone(),
+            ..BuildOptions::default()
+        }
+        .with_experiment(Experiment::LINT_CHECKS);
+        let pack = BuiltPackage::build(self.move_options.get_package_path()?, build_options)
+            .map_err(|e| CliError::MoveCompilationError(format!("{:#}", e)))?;
+        Ok(pack
+            .modules()
+            .map(|m| m.self_id().to_string())
+            .collect::<Vec<_>>())
+    }
+}
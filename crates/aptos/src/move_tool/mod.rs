@@ -22,6 +22,7 @@ use crate::{
         bytecode::{Decompile, Disassemble},
         coverage::SummaryCoverage,
         fmt::Fmt,
+        lint::LintPackage,
         manifest::{Dependency, ManifestNamedAddress, MovePackageManifest, PackageInfo},
     },
     CliCommand, CliResult,
@@ -41,21 +42,26 @@ use aptos_types::{
     account_address::{create_resource_address, AccountAddress},
     object_address::create_object_code_deployment_address,
     on_chain_config::aptos_test_feature_flags_genesis,
+    state_store::{state_key::StateKey, TStateView},
     transaction::{Transaction, TransactionArgument, TransactionPayload, TransactionStatus},
 };
 use aptos_vm::data_cache::AsMoveResolver;
 use async_trait::async_trait;
 use clap::{Parser, Subcommand, ValueEnum};
 use itertools::Itertools;
+use move_binary_format::CompiledModule;
 use move_cli::{self, base::test::UnitTestResult};
 use move_command_line_common::env::MOVE_HOME;
 use move_core_types::{identifier::Identifier, language_storage::ModuleId, u256::U256};
+use move_debug_adapter::{DebugSession, DebugSources};
 use move_model::metadata::{CompilerVersion, LanguageVersion};
 use move_package::{
     source_package::{layout::SourcePackageLayout, std_lib::StdVersion},
     BuildConfig, CompilerConfig,
 };
-use move_unit_test::UnitTestingConfig;
+use move_unit_test::{
+    test_reporter::TestReportFormat, UnitTestingConfig, DEFAULT_RANDOM_TEST_RUNS,
+};
 pub use package_hooks::*;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
@@ -64,6 +70,7 @@ use std::{
     fmt::{Display, Formatter},
     path::{Path, PathBuf},
     str::FromStr,
+    sync::Arc,
 };
 pub use stored_package::*;
 use tokio::task;
@@ -73,6 +80,7 @@ mod aptos_debug_natives;
 mod bytecode;
 pub mod coverage;
 mod fmt;
+mod lint;
 mod manifest;
 pub mod package_hooks;
 mod show;
@@ -108,6 +116,7 @@ pub enum MoveTool {
     Document(DocumentPackage),
     Download(DownloadPackage),
     Init(InitPackage),
+    Lint(LintPackage),
     List(ListPackage),
     Prove(ProvePackage),
     #[clap(alias = "deploy")]
@@ -145,6 +154,7 @@ impl MoveTool {
             MoveTool::Document(tool) => tool.execute_serialized().await,
             MoveTool::Download(tool) => tool.execute_serialized().await,
             MoveTool::Init(tool) => tool.execute_serialized_success().await,
+            MoveTool::Lint(tool) => tool.execute_serialized().await,
             MoveTool::List(tool) => tool.execute_serialized().await,
             MoveTool::Prove(tool) => tool.execute_serialized().await,
             MoveTool::Publish(tool) => tool.execute_serialized().await,
@@ -522,6 +532,54 @@ pub struct TestPackage {
     /// Dump storage state on failure.
     #[clap(long = "dump")]
     pub dump_state: bool,
+
+    /// Write a report of the test results to a file, for use in CI
+    ///
+    /// Takes the format, `junit` or `json`, and the path of the file, e.g.
+    /// `--report junit target/move-tests.xml`.  The report contains the status, duration and
+    /// gas used of each test, and for failed tests the failure location and abort code.
+    #[clap(long, num_args = 2, value_names = ["FORMAT", "PATH"])]
+    pub report: Vec<String>,
+
+    /// Number of runs of each test annotated with `#[fuzz]` or `#[test(random_args)]`
+    #[clap(long, default_value_t = DEFAULT_RANDOM_TEST_RUNS)]
+    pub random_test_runs: u64,
+
+    /// Seed for generating the arguments of tests annotated with `#[fuzz]` or `#[test(random_args)]`
+    ///
+    /// If not given, a random seed is used.  The seed is printed when such a test fails, so the
+    /// failure can be reproduced.
+    #[clap(long)]
+    pub seed: Option<u64>,
+
+    /// Run the tests under a debugger
+    ///
+    /// Waits for a Debug Adapter Protocol client, e.g. an editor, to connect on the given port,
+    /// then runs the tests one at a time.  The client can set breakpoints, step through the
+    /// code, and inspect the call stack, locals and the resources read from global storage.
+    #[clap(long, value_name = "PORT")]
+    pub debug: Option<u16>,
+}
+
+impl TestPackage {
+    /// Parses `--report <FORMAT> <PATH>`, if given.
+    fn report(&self) -> CliTypedResult<(Option<TestReportFormat>, Option<PathBuf>)> {
+        match self.report.as_slice() {
+            [] => Ok((None, None)),
+            [format, path] => {
+                let format = TestReportFormat::from_str(format, true).map_err(|_| {
+                    CliError::CommandArgumentError(format!(
+                        "Invalid report format '{}', expected 'junit' or 'json'",
+                        format
+                    ))
+                })?;
+                Ok((Some(format), Some(PathBuf::from(path))))
+            },
+            _ => Err(CliError::CommandArgumentError(
+                "--report expects a format and a path".to_string(),
+            )),
+        }
+    }
 }
 
 #[async_trait]
@@ -549,6 +607,7 @@ impl CliCommand<&'static str> for TestPackage {
             ..Default::default()
         };
 
+        let (report_format, report_path) = self.report()?;
         let path = self.move_options.get_package_path()?;
         let result = move_cli::base::test::run_move_unit_tests(
             path.as_path(),
@@ -558,6 +617,11 @@ impl CliCommand<&'static str> for TestPackage {
                 report_stacktrace_on_abort: true,
                 report_storage_on_error: self.dump_state,
                 ignore_compile_warnings: self.ignore_compile_warnings,
+                report_format,
+                report_path,
+                random_test_runs: self.random_test_runs,
+                seed: self.seed,
+                debug_port: self.debug,
                 ..UnitTestingConfig::default_with_bound(None)
             },
             // TODO(Gas): we may want to switch to non-zero costs in the future
@@ -1785,6 +1849,13 @@ pub struct Replay {
     /// If present, skip the comparison against the expected transaction output.
     #[clap(long)]
     pub(crate) skip_comparison: bool,
+
+    /// Replay the transaction under a debugger
+    ///
+    /// Waits for a Debug Adapter Protocol client to connect on the given port before executing
+    /// the transaction.  Code fetched from chain has no source maps, so it is shown as bytecode.
+    #[clap(long, value_name = "PORT")]
+    pub(crate) debug: Option<u16>,
 }
 
 impl FromStr for ReplayNetworkSelection {
@@ -1814,6 +1885,11 @@ impl CliCommand<TransactionSummary> for Replay {
                 "Cannot perform benchmarking and gas profiling at the same time.".to_string(),
             ));
         }
+        if self.debug.is_some() && (self.profile_gas || self.benchmark) {
+            return Err(CliError::UnexpectedError(
+                "Cannot debug the transaction while benchmarking or gas profiling.".to_string(),
+            ));
+        }
 
         let rest_endpoint = match &self.network {
             Mainnet => "https://fullnode.mainnet.aptoslabs.com",
@@ -1861,6 +1937,24 @@ impl CliCommand<TransactionSummary> for Replay {
                 txn.clone(),
                 hash,
             )?
+        } else if let Some(port) = self.debug {
+            let state_view = debugger.state_view_at_version(self.txn_id);
+            let sources = DebugSources::new().with_module_fetcher(Box::new(move |module_id| {
+                let bytes = state_view
+                    .get_state_value_bytes(&StateKey::module_id(module_id))
+                    .ok()??;
+                CompiledModule::deserialize(&bytes).ok().map(Arc::new)
+            }));
+            let session = DebugSession::start(port, sources)?;
+            println!("Replaying transaction under the debugger...");
+            let result = local_simulation::run_transaction_using_debugger(
+                &debugger,
+                self.txn_id,
+                txn.clone(),
+                hash,
+            );
+            session.finish();
+            result?
         } else {
             println!("Replaying transaction...");
             local_simulation::run_transaction_using_debugger(
@@ -25,6 +25,7 @@ use aptos_backup_cli::{
     utils::GlobalRestoreOpt,
 };
 use aptos_cached_packages::aptos_stdlib;
+use aptos_config::config::Passphrase;
 use aptos_crypto::{bls12381, bls12381::PublicKey, x25519, ValidCryptoMaterialStringExt};
 use aptos_genesis::config::{HostAndPort, OperatorConfiguration};
 use aptos_logger::Level;
@@ -32,6 +33,7 @@ use aptos_network_checker::args::{
     validate_address, CheckEndpointArgs, HandshakeArgs, NodeAddressArgs,
 };
 use aptos_rest_client::{aptos_api_types::VersionedEvent, Client, State};
+use aptos_secure_storage::EncryptedOnDiskStorage;
 use aptos_types::{
     account_address::AccountAddress,
     account_config::{BlockResource, CORE_CODE_ADDRESS},
@@ -72,6 +74,8 @@ pub enum NodeTool {
     InitializeValidator(InitializeValidator),
     JoinValidatorSet(JoinValidatorSet),
     LeaveValidatorSet(LeaveValidatorSet),
+    MigrateSecureStorage(MigrateSecureStorage),
+    RotateSecureStoragePassphrase(RotateSecureStoragePassphrase),
     ShowEpochInfo(ShowEpochInfo),
     ShowValidatorConfig(ShowValidatorConfig),
     ShowValidatorSet(ShowValidatorSet),
@@ -97,6 +101,8 @@ impl NodeTool {
             InitializeValidator(tool) => tool.execute_serialized().await,
             JoinValidatorSet(tool) => tool.execute_serialized().await,
             LeaveValidatorSet(tool) => tool.execute_serialized().await,
+            MigrateSecureStorage(tool) => tool.execute_serialized().await,
+            RotateSecureStoragePassphrase(tool) => tool.execute_serialized().await,
             ShowEpochInfo(tool) => tool.execute_serialized().await,
             ShowValidatorSet(tool) => tool.execute_serialized().await,
             ShowValidatorStake(tool) => tool.execute_serialized().await,
@@ -1356,6 +1362,161 @@ impl CliCommand<()> for BootstrapDb {
     }
 }
 
+/// Migrate a plaintext on-disk secure storage file to an encrypted one
+///
+/// All values in the plaintext file (e.g., consensus keys and safety data) are sealed with a
+/// key derived from the given passphrase. Once the node is configured to use the encrypted
+/// file (via an `encrypted_on_disk_storage` secure backend), the plaintext file should be
+/// securely deleted.
+#[derive(Parser)]
+pub struct MigrateSecureStorage {
+    /// Path to the plaintext secure storage file (e.g., secure_storage.json)
+    #[clap(long, value_parser)]
+    pub(crate) input_file: PathBuf,
+
+    /// Path to write the encrypted secure storage file to
+    #[clap(long, value_parser)]
+    pub(crate) output_file: PathBuf,
+
+    #[clap(flatten)]
+    pub(crate) passphrase_args: PassphraseArgs,
+
+    /// Overwrite the output file if it already exists
+    ///
+    /// Without this flag, an existing output file (which may hold the only copy of the keys) is
+    /// never deleted.
+    #[clap(long)]
+    pub(crate) force: bool,
+}
+
+#[async_trait]
+impl CliCommand<String> for MigrateSecureStorage {
+    fn command_name(&self) -> &'static str {
+        "MigrateSecureStorage"
+    }
+
+    async fn execute(self) -> CliTypedResult<String> {
+        if !self.input_file.exists() {
+            return Err(CliError::CommandArgumentError(format!(
+                "Input file {:?} does not exist",
+                self.input_file
+            )));
+        }
+
+        // Read the passphrase before touching an existing output file
+        let passphrase = self.passphrase_args.read_passphrase()?;
+        if self.output_file.exists() {
+            if !self.force {
+                return Err(CliError::CommandArgumentError(format!(
+                    "Output file {:?} already exists. Use --force to overwrite it",
+                    self.output_file
+                )));
+            }
+            std::fs::remove_file(&self.output_file)
+                .map_err(|err| CliError::IO(self.output_file.display().to_string(), err))?;
+        }
+
+        let mut storage =
+            EncryptedOnDiskStorage::new(self.output_file.clone(), passphrase.as_bytes())
+                .map_err(|err| secure_storage_error("open", err))?;
+        let num_values = storage
+            .import_from_plaintext(&self.input_file)
+            .map_err(|err| secure_storage_error("migrate", err))?;
+
+        Ok(format!(
+            "Migrated {} values to {:?}. Update the node config to use an \
+            `encrypted_on_disk_storage` backend, and securely delete {:?}.",
+            num_values, self.output_file, self.input_file
+        ))
+    }
+}
+
+/// Rotate the passphrase of an encrypted on-disk secure storage file
+///
+/// The file is re-sealed (atomically) with a key derived from the new passphrase and a fresh
+/// salt. Rotating to the same passphrase still rotates the underlying key. The node must be
+/// stopped while the passphrase is rotated.
+#[derive(Parser)]
+pub struct RotateSecureStoragePassphrase {
+    /// Path to the encrypted secure storage file
+    #[clap(long, value_parser)]
+    pub(crate) storage_file: PathBuf,
+
+    #[clap(flatten)]
+    pub(crate) passphrase_args: PassphraseArgs,
+
+    /// File holding the new passphrase
+    #[clap(long, value_parser, conflicts_with = "new_passphrase_env")]
+    pub(crate) new_passphrase_file: Option<PathBuf>,
+
+    /// Environment variable holding the new passphrase
+    #[clap(long)]
+    pub(crate) new_passphrase_env: Option<String>,
+}
+
+#[async_trait]
+impl CliCommand<String> for RotateSecureStoragePassphrase {
+    fn command_name(&self) -> &'static str {
+        "RotateSecureStoragePassphrase"
+    }
+
+    async fn execute(self) -> CliTypedResult<String> {
+        let new_passphrase = PassphraseArgs {
+            passphrase_file: self.new_passphrase_file,
+            passphrase_env: self.new_passphrase_env,
+        }
+        .read_passphrase()?;
+        let passphrase = self.passphrase_args.read_passphrase()?;
+
+        let mut storage =
+            EncryptedOnDiskStorage::new(self.storage_file.clone(), passphrase.as_bytes())
+                .map_err(|err| secure_storage_error("open", err))?;
+        storage
+            .rotate_passphrase(new_passphrase.as_bytes())
+            .map_err(|err| secure_storage_error("rotate the passphrase of", err))?;
+
+        Ok(format!(
+            "Rotated the passphrase of {:?}. Update the node config (or environment) to use the \
+            new passphrase.",
+            self.storage_file
+        ))
+    }
+}
+
+/// Arguments for providing the passphrase of an encrypted secure storage file
+#[derive(Debug, Default, Parser)]
+pub struct PassphraseArgs {
+    /// File holding the passphrase
+    #[clap(long, value_parser, conflicts_with = "passphrase_env")]
+    pub(crate) passphrase_file: Option<PathBuf>,
+
+    /// Environment variable holding the passphrase
+    #[clap(long)]
+    pub(crate) passphrase_env: Option<String>,
+}
+
+impl PassphraseArgs {
+    fn read_passphrase(&self) -> CliTypedResult<String> {
+        let passphrase = match (&self.passphrase_file, &self.passphrase_env) {
+            (Some(path), _) => Passphrase::FromDisk(path.clone()),
+            (None, Some(name)) => Passphrase::FromEnv(name.clone()),
+            (None, None) => {
+                return Err(CliError::CommandArgumentError(
+                    "A passphrase file or environment variable must be provided".to_string(),
+                ))
+            },
+        };
+        Ok(passphrase.read_passphrase()?)
+    }
+}
+
+fn secure_storage_error(action: &str, error: aptos_secure_storage::Error) -> CliError {
+    CliError::UnexpectedError(format!(
+        "Unable to {} the encrypted secure storage: {}",
+        action, error
+    ))
+}
+
 /// Checks the network connectivity of a node
 ///
 /// Checks network connectivity by dialing the node and attempting
@@ -1492,8 +1653,12 @@ impl Time {
 #[cfg(test)]
 mod tests {
     use crate::{CliResult, Tool};
+    use aptos_secure_storage::{EncryptedOnDiskStorage, KVStorage, OnDiskStorage};
+    use aptos_temppath::TempPath;
     use clap::Parser;
 
+    const VALUE_KEY: &str = "value_key";
+
     // TODO: there have to be cleaner ways to test things. Maybe a CLI test framework?
 
     // FIXME: Remove this test, it's very fragile and move to E2E CLI test framework
@@ -1524,6 +1689,128 @@ mod tests {
         assert_contains(error_message, "Timed out while checking endpoint");
     }
 
+    #[tokio::test]
+    async fn test_migrate_secure_storage() {
+        // Create a plaintext secure storage file and a passphrase file
+        let input_file = TempPath::new();
+        let mut plaintext_storage = OnDiskStorage::new(input_file.path().into());
+        plaintext_storage.set(VALUE_KEY, 10u64).unwrap();
+        let passphrase_file = create_passphrase_file("passphrase\n");
+
+        // Verify the migration fails without a passphrase
+        let output_file = TempPath::new();
+        let error_message = run_migrate_secure_storage(&input_file, &output_file, None, false)
+            .await
+            .unwrap_err();
+        assert_contains(error_message, "A passphrase file or environment variable");
+        assert!(!output_file.path().exists());
+
+        // Migrate the storage and verify the values can be read with the passphrase
+        run_migrate_secure_storage(&input_file, &output_file, Some(&passphrase_file), false)
+            .await
+            .unwrap();
+        let storage =
+            EncryptedOnDiskStorage::new(output_file.path().into(), b"passphrase").unwrap();
+        assert_eq!(storage.get::<u64>(VALUE_KEY).unwrap().value, 10);
+
+        // Verify the existing output file is not overwritten without --force
+        plaintext_storage.set(VALUE_KEY, 11u64).unwrap();
+        let error_message =
+            run_migrate_secure_storage(&input_file, &output_file, Some(&passphrase_file), false)
+                .await
+                .unwrap_err();
+        assert_contains(error_message, "already exists");
+        let storage =
+            EncryptedOnDiskStorage::new(output_file.path().into(), b"passphrase").unwrap();
+        assert_eq!(storage.get::<u64>(VALUE_KEY).unwrap().value, 10);
+
+        // Verify the existing output file is overwritten with --force
+        run_migrate_secure_storage(&input_file, &output_file, Some(&passphrase_file), true)
+            .await
+            .unwrap();
+        let storage =
+            EncryptedOnDiskStorage::new(output_file.path().into(), b"passphrase").unwrap();
+        assert_eq!(storage.get::<u64>(VALUE_KEY).unwrap().value, 11);
+    }
+
+    #[tokio::test]
+    async fn test_rotate_secure_storage_passphrase() {
+        // Create an encrypted secure storage file
+        let storage_file = TempPath::new();
+        let mut storage =
+            EncryptedOnDiskStorage::new(storage_file.path().into(), b"passphrase").unwrap();
+        storage.set(VALUE_KEY, 10u64).unwrap();
+        let passphrase_file = create_passphrase_file("passphrase");
+        let new_passphrase_file = create_passphrase_file("new passphrase\n");
+
+        // Verify the rotation fails with the wrong passphrase
+        let args = &[
+            "aptos",
+            "node",
+            "rotate-secure-storage-passphrase",
+            "--storage-file",
+            path_str(&storage_file),
+            "--passphrase-file",
+            path_str(&new_passphrase_file),
+            "--new-passphrase-file",
+            path_str(&passphrase_file),
+        ];
+        let error_message = run_tool_with_args(args).await.unwrap_err();
+        assert_contains(error_message, "Unable to open the encrypted secure storage");
+
+        // Rotate the passphrase and verify only the new passphrase can open the storage
+        let args = &[
+            "aptos",
+            "node",
+            "rotate-secure-storage-passphrase",
+            "--storage-file",
+            path_str(&storage_file),
+            "--passphrase-file",
+            path_str(&passphrase_file),
+            "--new-passphrase-file",
+            path_str(&new_passphrase_file),
+        ];
+        run_tool_with_args(args).await.unwrap();
+        assert!(EncryptedOnDiskStorage::new(storage_file.path().into(), b"passphrase").is_err());
+        let storage =
+            EncryptedOnDiskStorage::new(storage_file.path().into(), b"new passphrase").unwrap();
+        assert_eq!(storage.get::<u64>(VALUE_KEY).unwrap().value, 10);
+    }
+
+    async fn run_migrate_secure_storage(
+        input_file: &TempPath,
+        output_file: &TempPath,
+        passphrase_file: Option<&TempPath>,
+        force: bool,
+    ) -> CliResult {
+        let mut args = vec![
+            "aptos",
+            "node",
+            "migrate-secure-storage",
+            "--input-file",
+            path_str(input_file),
+            "--output-file",
+            path_str(output_file),
+        ];
+        if let Some(passphrase_file) = passphrase_file {
+            args.extend(["--passphrase-file", path_str(passphrase_file)]);
+        }
+        if force {
+            args.push("--force");
+        }
+        run_tool_with_args(&args).await
+    }
+
+    fn create_passphrase_file(passphrase: &str) -> TempPath {
+        let passphrase_file = TempPath::new();
+        std::fs::write(passphrase_file.path(), passphrase).unwrap();
+        passphrase_file
+    }
+
+    fn path_str(path: &TempPath) -> &str {
+        path.path().to_str().unwrap()
+    }
+
     async fn run_tool_with_args(args: &[&str]) -> CliResult {
         let tool: Tool = Tool::try_parse_from(args).map_err(|msg| msg.to_string())?;
         tool.execute().await
@@ -870,6 +870,10 @@ impl CliTestFramework {
             ignore_compile_warnings: false,
             compute_coverage: false,
             dump_state: false,
+            report: vec![],
+            random_test_runs: move_unit_test::DEFAULT_RANDOM_TEST_RUNS,
+            seed: None,
+            debug: None,
         }
         .execute()
         .await
@@ -0,0 +1,24 @@
+#!/bin/sh
+# Copyright © Aptos Foundation
+# SPDX-License-Identifier: Apache-2.0
+
+# This script is a utility script used by some rust tests such as aptos-core/secure/storage/src/tests/pkcs11.rs .
+# It initializes a SoftHSM token and prints the environment variables required to run the tests.
+# If this is not directly referenced anywhere in the codebase anymore you can delete it.
+
+set -e
+
+LABEL="aptos_test_token"
+PIN="1234"
+SO_PIN="5678"
+LIBRARY="${SOFTHSM2_LIBRARY:-/usr/lib/softhsm/libsofthsm2.so}"
+TOKEN_DIR="${SOFTHSM2_TOKEN_DIR:-$(mktemp -d)}"
+CONFIG="$TOKEN_DIR/softhsm2.conf"
+
+echo "directories.tokendir = $TOKEN_DIR" > "$CONFIG"
+SOFTHSM2_CONF="$CONFIG" softhsm2-util --init-token --free --label "$LABEL" --pin "$PIN" --so-pin "$SO_PIN" > /dev/null
+
+echo "export SOFTHSM2_CONF=$CONFIG"
+echo "export APTOS_PKCS11_LIBRARY=$LIBRARY"
+echo "export APTOS_PKCS11_TOKEN_LABEL=$LABEL"
+echo "export APTOS_PKCS11_PIN=$PIN"
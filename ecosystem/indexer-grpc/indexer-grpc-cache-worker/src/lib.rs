@@ -6,7 +6,9 @@ pub mod worker;
 
 use anyhow::{Context, Result};
 use aptos_indexer_grpc_server_framework::RunnableConfig;
-use aptos_indexer_grpc_utils::{config::IndexerGrpcFileStoreConfig, types::RedisUrl};
+use aptos_indexer_grpc_utils::{
+    compression_util::CompressionAlgorithm, config::IndexerGrpcFileStoreConfig, types::RedisUrl,
+};
 use serde::{Deserialize, Serialize};
 use url::Url;
 use worker::Worker;
@@ -19,6 +21,8 @@ pub struct IndexerGrpcCacheWorkerConfig {
     pub redis_main_instance_address: RedisUrl,
     #[serde(default = "default_enable_cache_compression")]
     pub enable_cache_compression: bool,
+    #[serde(default)]
+    pub cache_compression_algorithm: CompressionAlgorithm,
 }
 
 const fn default_enable_cache_compression() -> bool {
@@ -31,12 +35,14 @@ impl IndexerGrpcCacheWorkerConfig {
         file_store_config: IndexerGrpcFileStoreConfig,
         redis_main_instance_address: RedisUrl,
         enable_cache_compression: bool,
+        cache_compression_algorithm: CompressionAlgorithm,
     ) -> Self {
         Self {
             fullnode_grpc_address,
             file_store_config,
             redis_main_instance_address,
             enable_cache_compression,
+            cache_compression_algorithm,
         }
     }
 }
@@ -49,6 +55,7 @@ impl RunnableConfig for IndexerGrpcCacheWorkerConfig {
             self.redis_main_instance_address.clone(),
             self.file_store_config.clone(),
             self.enable_cache_compression,
+            self.cache_compression_algorithm,
         )
         .await
         .context("Failed to create cache worker")?;
@@ -5,7 +5,7 @@ use crate::metrics::{ERROR_COUNT, WAIT_FOR_FILE_STORE_COUNTER};
 use anyhow::{bail, Context, Result};
 use aptos_indexer_grpc_utils::{
     cache_operator::CacheOperator,
-    compression_util::{FileStoreMetadata, StorageFormat},
+    compression_util::{CompressionAlgorithm, FileStoreMetadata, StorageFormat},
     config::IndexerGrpcFileStoreConfig,
     counters::{log_grpc_step, IndexerGrpcStep},
     create_grpc_client,
@@ -46,6 +46,8 @@ pub struct Worker {
     file_store: IndexerGrpcFileStoreConfig,
     /// Cache storage format.
     cache_storage_format: StorageFormat,
+    /// The zstd dictionary of the file store, which is also used to compress cache entries.
+    cache_zstd_dictionary_id: Option<u32>,
 }
 
 /// GRPC data status enum is to identify the data frame.
@@ -75,12 +77,16 @@ impl Worker {
         redis_main_instance_address: RedisUrl,
         file_store: IndexerGrpcFileStoreConfig,
         enable_cache_compression: bool,
+        cache_compression_algorithm: CompressionAlgorithm,
     ) -> Result<Self> {
         let cache_storage_format = if enable_cache_compression {
-            StorageFormat::Lz4CompressedProto
+            cache_compression_algorithm.storage_format()
         } else {
             StorageFormat::Base64UncompressedProto
         };
+        let cache_zstd_dictionary_id = file_store
+            .load_zstd_dictionary()
+            .context("Failed to load zstd dictionary")?;
         let redis_client = redis::Client::open(redis_main_instance_address.0.clone())
             .with_context(|| {
                 format!(
@@ -93,6 +99,7 @@ impl Worker {
             file_store,
             fullnode_grpc_address,
             cache_storage_format,
+            cache_zstd_dictionary_id,
         })
     }
 
@@ -167,6 +174,7 @@ impl Worker {
             process_streaming_response(
                 conn,
                 self.cache_storage_format,
+                self.cache_zstd_dictionary_id,
                 file_store_metadata,
                 response.into_inner(),
             )
@@ -328,6 +336,7 @@ async fn verify_fullnode_init_signal(
 async fn process_streaming_response(
     conn: redis::aio::ConnectionManager,
     cache_storage_format: StorageFormat,
+    cache_zstd_dictionary_id: Option<u32>,
     file_store_metadata: FileStoreMetadata,
     mut resp_stream: impl futures_core::Stream<Item = Result<TransactionsFromNodeResponse, tonic::Status>>
         + std::marker::Unpin,
@@ -341,7 +350,8 @@ async fn process_streaming_response(
             bail!("[Indexer Cache] Streaming error: no response.");
         },
     };
-    let mut cache_operator = CacheOperator::new(conn, cache_storage_format);
+    let mut cache_operator = CacheOperator::new(conn, cache_storage_format)
+        .with_zstd_dictionary(cache_zstd_dictionary_id);
 
     let (fullnode_chain_id, starting_version) =
         verify_fullnode_init_signal(&mut cache_operator, init_signal, file_store_metadata)
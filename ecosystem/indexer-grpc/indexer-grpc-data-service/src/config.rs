@@ -5,8 +5,10 @@ use crate::service::RawDataServerWrapper;
 use anyhow::{bail, Result};
 use aptos_indexer_grpc_server_framework::RunnableConfig;
 use aptos_indexer_grpc_utils::{
-    compression_util::StorageFormat, config::IndexerGrpcFileStoreConfig,
-    in_memory_cache::InMemoryCacheConfig, types::RedisUrl,
+    compression_util::{CompressionAlgorithm, StorageFormat},
+    config::IndexerGrpcFileStoreConfig,
+    in_memory_cache::InMemoryCacheConfig,
+    types::RedisUrl,
 };
 use aptos_protos::{
     indexer::v1::FILE_DESCRIPTOR_SET as INDEXER_V1_FILE_DESCRIPTOR_SET,
@@ -68,6 +70,9 @@ pub struct IndexerGrpcDataServiceConfig {
     /// Support compressed cache data.
     #[serde(default = "IndexerGrpcDataServiceConfig::default_enable_cache_compression")]
     pub enable_cache_compression: bool,
+    /// Compression algorithm of the cache data, if compressed.
+    #[serde(default)]
+    pub cache_compression_algorithm: CompressionAlgorithm,
     #[serde(default)]
     pub in_memory_cache_config: InMemoryCacheConfig,
     /// Any transaction that matches this filter will be stripped. This means we remove
@@ -93,6 +98,7 @@ impl IndexerGrpcDataServiceConfig {
         file_store_config: IndexerGrpcFileStoreConfig,
         redis_read_replica_address: RedisUrl,
         enable_cache_compression: bool,
+        cache_compression_algorithm: CompressionAlgorithm,
         in_memory_cache_config: InMemoryCacheConfig,
         txns_to_strip_filter: BooleanTransactionFilter,
     ) -> Self {
@@ -106,6 +112,7 @@ impl IndexerGrpcDataServiceConfig {
             file_store_config,
             redis_read_replica_address,
             enable_cache_compression,
+            cache_compression_algorithm,
             in_memory_cache_config,
             txns_to_strip_filter,
         }
@@ -153,8 +160,9 @@ impl RunnableConfig for IndexerGrpcDataServiceConfig {
             .accept_compressed(CompressionEncoding::Zstd)
             .accept_compressed(CompressionEncoding::Gzip);
 
+        self.file_store_config.load_zstd_dictionary()?;
         let cache_storage_format: StorageFormat = if self.enable_cache_compression {
-            StorageFormat::Lz4CompressedProto
+            self.cache_compression_algorithm.storage_format()
         } else {
             StorageFormat::Base64UncompressedProto
         };
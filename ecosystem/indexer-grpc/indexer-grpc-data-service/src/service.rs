@@ -716,10 +716,10 @@ async fn deserialize_cached_transactions(
                 let cache_entry = CacheEntry::new(transaction, storage_format);
                 cache_entry.into_transaction()
             })
-            .collect::<Vec<Transaction>>()
+            .collect::<anyhow::Result<Vec<Transaction>>>()
     })
     .await;
-    task.context("Transaction bytes to CacheEntry deserialization task failed")
+    task.context("Transaction bytes to CacheEntry deserialization task failed")?
 }
 
 /// Fetches data from cache or the file store. It returns the data if it is ready in the cache or file store.
@@ -1,6 +1,7 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
+pub mod migrator;
 pub mod processor;
 
 use anyhow::Result;
@@ -49,6 +50,7 @@ const fn default_validating_task_count() -> usize {
 #[async_trait::async_trait]
 impl RunnableConfig for IndexerGrpcFileStoreBackfillerConfig {
     async fn run(&self) -> Result<()> {
+        self.file_store_config.load_zstd_dictionary()?;
         let mut processor = Processor::new(
             self.fullnode_grpc_address.clone(),
             self.file_store_config.clone(),
@@ -0,0 +1,482 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::processor::ProgressFile;
+use anyhow::{ensure, Context, Result};
+use aptos_indexer_grpc_server_framework::RunnableConfig;
+use aptos_indexer_grpc_utils::{
+    compression_util::{train_zstd_dictionary, StorageFormat, FILE_ENTRY_TRANSACTION_COUNT},
+    config::IndexerGrpcFileStoreConfig,
+    file_store_operator::FileStoreOperator,
+};
+use prost::Message;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeSet, sync::Arc, time::Duration};
+use tokio::sync::Mutex;
+
+const MIGRATION_READ_RETRIES: u8 = 5;
+
+/// Re-encodes the files of an existing file store into another storage format, e.g., from
+/// lz4 to zstd. The source and target may be the same bucket, since the file keys differ
+/// per storage format. The target metadata is only written once all files are migrated,
+/// which is when readers can be switched to the target config.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct IndexerGrpcFileStoreMigratorConfig {
+    pub source_file_store_config: IndexerGrpcFileStoreConfig,
+    pub target_file_store_config: IndexerGrpcFileStoreConfig,
+    pub progress_file_path: String,
+    pub chain_id: u64,
+    pub starting_version: Option<u64>,
+    // Exclusive; defaults to the version of the source file store.
+    pub ending_version: Option<u64>,
+    // If set, a zstd dictionary is trained from the source files before migrating and written
+    // to the `zstd_dictionary_path` of the target config.
+    pub zstd_dictionary_training: Option<ZstdDictionaryTrainingConfig>,
+    #[serde(default = "default_migration_task_count")]
+    pub migration_task_count: usize,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ZstdDictionaryTrainingConfig {
+    #[serde(default = "default_sample_file_count")]
+    pub sample_file_count: u64,
+    #[serde(default = "default_max_dictionary_size_bytes")]
+    pub max_dictionary_size_bytes: usize,
+}
+
+const fn default_migration_task_count() -> usize {
+    20
+}
+
+const fn default_sample_file_count() -> u64 {
+    100
+}
+
+const fn default_max_dictionary_size_bytes() -> usize {
+    // Same as the zstd command line default.
+    110 * 1024
+}
+
+#[async_trait::async_trait]
+impl RunnableConfig for IndexerGrpcFileStoreMigratorConfig {
+    async fn run(&self) -> Result<()> {
+        let mut migrator = Migrator::new(self.clone()).await?;
+        migrator.run().await
+    }
+
+    fn get_server_name(&self) -> String {
+        "idxfilemigr".to_string()
+    }
+}
+
+pub struct Migrator {
+    config: IndexerGrpcFileStoreMigratorConfig,
+    source_file_store_operator: Box<dyn FileStoreOperator>,
+    target_file_store_operator: Box<dyn FileStoreOperator>,
+    starting_version: u64,
+    ending_version: u64,
+}
+
+impl Migrator {
+    pub async fn new(config: IndexerGrpcFileStoreMigratorConfig) -> Result<Self> {
+        let source_file_store_operator = config.source_file_store_config.create();
+        source_file_store_operator
+            .verify_storage_bucket_existence()
+            .await;
+
+        let metadata = source_file_store_operator
+            .get_file_store_metadata()
+            .await
+            .context("Source file store metadata not found.")?;
+        ensure!(metadata.chain_id == config.chain_id, "Chain ID mismatch.");
+        ensure!(
+            metadata.storage_format == source_file_store_operator.storage_format(),
+            "Source file store is stored as {:?}, but configured as {:?}.",
+            metadata.storage_format,
+            source_file_store_operator.storage_format()
+        );
+
+        let ending_version = config
+            .ending_version
+            .unwrap_or(metadata.version)
+            .min(metadata.version);
+        // Only full files are migrated.
+        let ending_version =
+            ending_version / FILE_ENTRY_TRANSACTION_COUNT * FILE_ENTRY_TRANSACTION_COUNT;
+        let starting_version = config.starting_version.unwrap_or(0);
+        ensure!(
+            starting_version % FILE_ENTRY_TRANSACTION_COUNT == 0,
+            "Starting version has to be a multiple of {}.",
+            FILE_ENTRY_TRANSACTION_COUNT
+        );
+        ensure!(
+            starting_version <= ending_version,
+            "Starting version {} is after the ending version {}.",
+            starting_version,
+            ending_version
+        );
+
+        // The dictionary has to exist before the target operator is created, which loads it.
+        if let Some(training_config) = &config.zstd_dictionary_training {
+            prepare_zstd_dictionary(
+                source_file_store_operator.as_ref(),
+                &config.target_file_store_config,
+                training_config,
+                starting_version,
+                ending_version,
+            )
+            .await?;
+        }
+
+        let target_file_store_operator = config.target_file_store_config.create();
+        target_file_store_operator
+            .verify_storage_bucket_existence()
+            .await;
+        if let Some(target_metadata) = target_file_store_operator.get_file_store_metadata().await {
+            ensure!(
+                target_metadata.chain_id == config.chain_id,
+                "Chain ID mismatch."
+            );
+        }
+
+        Ok(Self {
+            config,
+            source_file_store_operator,
+            target_file_store_operator,
+            starting_version,
+            ending_version,
+        })
+    }
+
+    pub async fn run(&mut self) -> Result<()> {
+        // Resume from the progress file if the file exists.
+        let progress_file: ProgressFile = match std::fs::read(&self.config.progress_file_path) {
+            Ok(bytes) => serde_json::from_slice(&bytes).context("Failed to parse progress file")?,
+            Err(_) => ProgressFile {
+                version: self.starting_version,
+            },
+        };
+        let starting_version = std::cmp::max(self.starting_version, progress_file.version);
+        let ending_version = self.ending_version;
+        tracing::info!(
+            starting_version = starting_version,
+            ending_version = ending_version,
+            source_storage_format = ?self.source_file_store_operator.storage_format(),
+            target_storage_format = ?self.target_file_store_operator.storage_format(),
+            "Starting file store migration."
+        );
+
+        let version_allocator = Arc::new(Mutex::new(starting_version));
+        let finished_starting_versions = Arc::new(Mutex::new(BTreeSet::new()));
+        let mut tasks = Vec::new();
+        for _ in 0..self.config.migration_task_count {
+            let version_allocator = version_allocator.clone();
+            let finished_starting_versions = finished_starting_versions.clone();
+            let source_file_store_operator = self.source_file_store_operator.clone_box();
+            let mut target_file_store_operator = self.target_file_store_operator.clone_box();
+            let chain_id = self.config.chain_id;
+            let task = tokio::spawn(async move {
+                loop {
+                    let version = {
+                        let mut version_allocator = version_allocator.lock().await;
+                        let version = *version_allocator;
+                        if version >= ending_version {
+                            return Ok(());
+                        }
+                        *version_allocator += FILE_ENTRY_TRANSACTION_COUNT;
+                        version
+                    };
+                    let transactions = source_file_store_operator
+                        .get_transactions(version, MIGRATION_READ_RETRIES)
+                        .await?;
+                    ensure!(
+                        transactions.len() == FILE_ENTRY_TRANSACTION_COUNT as usize,
+                        "Unexpected transaction count"
+                    );
+                    for (idx, t) in transactions.iter().enumerate() {
+                        ensure!(t.version == version + idx as u64, "Unexpected version");
+                    }
+                    target_file_store_operator
+                        .upload_transaction_batch(chain_id, transactions)
+                        .await?;
+                    finished_starting_versions.lock().await.insert(version);
+                }
+            });
+            tasks.push(task);
+        }
+
+        let progress_file_path = self.config.progress_file_path.clone();
+        let mut next_version_to_process = starting_version;
+        let progress_task = tokio::spawn(async move {
+            while next_version_to_process < ending_version {
+                tokio::time::sleep(Duration::from_millis(5000)).await;
+                {
+                    let mut finished_starting_versions = finished_starting_versions.lock().await;
+                    while finished_starting_versions.remove(&next_version_to_process) {
+                        next_version_to_process += FILE_ENTRY_TRANSACTION_COUNT;
+                    }
+                }
+                let progress_file = ProgressFile {
+                    version: next_version_to_process,
+                };
+                let bytes = serde_json::to_vec(&progress_file)
+                    .context("Failed to serialize progress file")?;
+                std::fs::write(&progress_file_path, &bytes)
+                    .context("Failed to write progress file")?;
+                tracing::info!(
+                    "Progress file updated to version {}",
+                    next_version_to_process
+                );
+            }
+            Ok(())
+        });
+        tasks.push(progress_task);
+
+        for task in tasks {
+            task.await??;
+        }
+
+        // All files are migrated; readers can be switched to the target storage format.
+        self.target_file_store_operator
+            .update_file_store_metadata_internal(self.config.chain_id, ending_version)
+            .await?;
+        tracing::info!(
+            ending_version = ending_version,
+            "File store migration finished."
+        );
+        Ok(())
+    }
+}
+
+/// Trains a dictionary from transactions sampled evenly across the migrated range and writes it
+/// to the dictionary path of the target, unless a dictionary was trained there before.
+async fn prepare_zstd_dictionary(
+    source_file_store_operator: &dyn FileStoreOperator,
+    target_file_store_config: &IndexerGrpcFileStoreConfig,
+    training_config: &ZstdDictionaryTrainingConfig,
+    starting_version: u64,
+    ending_version: u64,
+) -> Result<()> {
+    ensure!(
+        target_file_store_config.storage_format() == StorageFormat::ZstdCompressedProto,
+        "A zstd dictionary can only be trained for a zstd target file store."
+    );
+    let output_path = target_file_store_config
+        .zstd_dictionary_path()
+        .context("Target file store has no zstd_dictionary_path to write the dictionary to.")?;
+    if output_path.exists() {
+        tracing::info!(
+            path = output_path.display().to_string(),
+            "Using previously trained zstd dictionary."
+        );
+        return Ok(());
+    }
+
+    let file_count = (ending_version - starting_version) / FILE_ENTRY_TRANSACTION_COUNT;
+    ensure!(file_count > 0, "No files to sample from.");
+    let step = std::cmp::max(1, file_count / training_config.sample_file_count.max(1))
+        * FILE_ENTRY_TRANSACTION_COUNT;
+
+    let mut samples = Vec::new();
+    for version in (starting_version..ending_version)
+        .step_by(step as usize)
+        .take(training_config.sample_file_count as usize)
+    {
+        let transactions = source_file_store_operator
+            .get_transactions(version, MIGRATION_READ_RETRIES)
+            .await?;
+        samples.extend(transactions.iter().map(|t| t.encode_to_vec()));
+    }
+    tracing::info!(num_samples = samples.len(), "Training zstd dictionary.");
+    let dictionary = train_zstd_dictionary(&samples, training_config.max_dictionary_size_bytes)?;
+    std::fs::write(output_path, &dictionary).context("Failed to write zstd dictionary")?;
+    tracing::info!(
+        path = output_path.display().to_string(),
+        "Trained zstd dictionary for migrated files."
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_indexer_grpc_utils::{
+        compression_util::{load_zstd_dictionary, CompressionAlgorithm},
+        config::LocalFileStore,
+        file_store_operator::LocalFileStoreOperator,
+    };
+    use aptos_protos::transaction::v1::Transaction;
+    use std::path::{Path, PathBuf};
+
+    const CHAIN_ID: u64 = 42;
+
+    fn transactions(starting_version: u64, ending_version: u64) -> Vec<Transaction> {
+        (starting_version..ending_version)
+            .map(|version| Transaction {
+                version,
+                epoch: version / 100,
+                block_height: version / 10,
+                ..Transaction::default()
+            })
+            .collect()
+    }
+
+    fn local_config(
+        path: &Path,
+        compression_algorithm: CompressionAlgorithm,
+        zstd_dictionary_path: Option<PathBuf>,
+    ) -> IndexerGrpcFileStoreConfig {
+        IndexerGrpcFileStoreConfig::LocalFileStore(LocalFileStore {
+            local_file_store_path: path.to_path_buf(),
+            enable_compression: true,
+            compression_algorithm,
+            zstd_dictionary_path,
+        })
+    }
+
+    async fn create_source(path: &Path, ending_version: u64) {
+        let mut operator =
+            LocalFileStoreOperator::new(path.to_path_buf(), true, CompressionAlgorithm::Lz4);
+        operator
+            .upload_transaction_batch(CHAIN_ID, transactions(0, ending_version))
+            .await
+            .unwrap();
+        operator
+            .update_file_store_metadata_internal(CHAIN_ID, ending_version)
+            .await
+            .unwrap();
+    }
+
+    fn migrator_config(
+        source: &Path,
+        target_file_store_config: IndexerGrpcFileStoreConfig,
+        progress_file_path: &Path,
+    ) -> IndexerGrpcFileStoreMigratorConfig {
+        IndexerGrpcFileStoreMigratorConfig {
+            source_file_store_config: local_config(source, CompressionAlgorithm::Lz4, None),
+            target_file_store_config,
+            progress_file_path: progress_file_path.to_str().unwrap().to_string(),
+            chain_id: CHAIN_ID,
+            starting_version: None,
+            ending_version: None,
+            zstd_dictionary_training: None,
+            migration_task_count: default_migration_task_count(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_migrate_lz4_to_zstd() {
+        let source = tempfile::tempdir().unwrap();
+        let target = tempfile::tempdir().unwrap();
+        let progress_file_path = source.path().join("progress.json");
+        // The trailing partial file isn't migrated.
+        create_source(source.path(), 2 * FILE_ENTRY_TRANSACTION_COUNT).await;
+        LocalFileStoreOperator::new(source.path().to_path_buf(), true, CompressionAlgorithm::Lz4)
+            .update_file_store_metadata_internal(CHAIN_ID, 2 * FILE_ENTRY_TRANSACTION_COUNT + 10)
+            .await
+            .unwrap();
+
+        let config = migrator_config(
+            source.path(),
+            local_config(target.path(), CompressionAlgorithm::Zstd, None),
+            &progress_file_path,
+        );
+        let mut migrator = Migrator::new(config).await.unwrap();
+        migrator.run().await.unwrap();
+
+        let target_operator = LocalFileStoreOperator::new(
+            target.path().to_path_buf(),
+            true,
+            CompressionAlgorithm::Zstd,
+        );
+        let metadata = target_operator.get_file_store_metadata().await.unwrap();
+        assert_eq!(metadata.version, 2 * FILE_ENTRY_TRANSACTION_COUNT);
+        assert_eq!(metadata.storage_format, StorageFormat::ZstdCompressedProto);
+        for version in [0, FILE_ENTRY_TRANSACTION_COUNT] {
+            assert_eq!(
+                target_operator.get_transactions(version, 1).await.unwrap(),
+                transactions(version, version + FILE_ENTRY_TRANSACTION_COUNT)
+            );
+        }
+        let progress_file: ProgressFile =
+            serde_json::from_slice(&std::fs::read(&progress_file_path).unwrap()).unwrap();
+        assert_eq!(progress_file.version, 2 * FILE_ENTRY_TRANSACTION_COUNT);
+    }
+
+    #[tokio::test]
+    async fn test_migrate_with_trained_zstd_dictionary() {
+        let source = tempfile::tempdir().unwrap();
+        let target = tempfile::tempdir().unwrap();
+        let dictionary_path = target.path().join("transactions.zstd-dict");
+        create_source(source.path(), 2 * FILE_ENTRY_TRANSACTION_COUNT).await;
+
+        let mut config = migrator_config(
+            source.path(),
+            local_config(
+                target.path(),
+                CompressionAlgorithm::Zstd,
+                Some(dictionary_path.clone()),
+            ),
+            &source.path().join("progress.json"),
+        );
+        config.zstd_dictionary_training = Some(ZstdDictionaryTrainingConfig {
+            sample_file_count: default_sample_file_count(),
+            max_dictionary_size_bytes: 1024,
+        });
+        let mut migrator = Migrator::new(config).await.unwrap();
+        migrator.run().await.unwrap();
+
+        // Migrated files are compressed with the trained dictionary, and only those.
+        let dictionary_id = load_zstd_dictionary(&dictionary_path).unwrap();
+        let target_operator = LocalFileStoreOperator::new(
+            target.path().to_path_buf(),
+            true,
+            CompressionAlgorithm::Zstd,
+        );
+        let raw_file = target_operator.get_raw_file(0).await.unwrap();
+        assert_eq!(
+            zstd::zstd_safe::get_dict_id_from_frame(&raw_file).map(|id| id.get()),
+            Some(dictionary_id)
+        );
+        assert_eq!(
+            target_operator.get_transactions(0, 1).await.unwrap(),
+            transactions(0, FILE_ENTRY_TRANSACTION_COUNT)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_dictionary_training_requires_target_dictionary_path() {
+        let source = tempfile::tempdir().unwrap();
+        let target = tempfile::tempdir().unwrap();
+        create_source(source.path(), FILE_ENTRY_TRANSACTION_COUNT).await;
+
+        let mut config = migrator_config(
+            source.path(),
+            local_config(target.path(), CompressionAlgorithm::Zstd, None),
+            &source.path().join("progress.json"),
+        );
+        config.zstd_dictionary_training = Some(ZstdDictionaryTrainingConfig {
+            sample_file_count: default_sample_file_count(),
+            max_dictionary_size_bytes: 1024,
+        });
+        assert!(Migrator::new(config).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_starting_version_after_ending_version() {
+        let source = tempfile::tempdir().unwrap();
+        let target = tempfile::tempdir().unwrap();
+        create_source(source.path(), FILE_ENTRY_TRANSACTION_COUNT).await;
+
+        let mut config = migrator_config(
+            source.path(),
+            local_config(target.path(), CompressionAlgorithm::Zstd, None),
+            &source.path().join("progress.json"),
+        );
+        config.starting_version = Some(2 * FILE_ENTRY_TRANSACTION_COUNT);
+        let err = Migrator::new(config).await.err().unwrap();
+        assert!(err.to_string().contains("is after the ending version"));
+    }
+}
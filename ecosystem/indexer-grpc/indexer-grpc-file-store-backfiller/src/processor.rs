@@ -38,7 +38,7 @@ pub struct Processor {
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ProgressFile {
-    version: u64,
+    pub version: u64,
 }
 
 impl Processor {
@@ -4,9 +4,11 @@
 pub mod metrics;
 pub mod processor;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use aptos_indexer_grpc_server_framework::RunnableConfig;
-use aptos_indexer_grpc_utils::{config::IndexerGrpcFileStoreConfig, types::RedisUrl};
+use aptos_indexer_grpc_utils::{
+    compression_util::CompressionAlgorithm, config::IndexerGrpcFileStoreConfig, types::RedisUrl,
+};
 use processor::Processor;
 use serde::{Deserialize, Serialize};
 
@@ -19,6 +21,8 @@ pub struct IndexerGrpcFileStoreWorkerConfig {
     pub chain_id: u64,
     #[serde(default = "default_enable_cache_compression")]
     pub enable_cache_compression: bool,
+    #[serde(default)]
+    pub cache_compression_algorithm: CompressionAlgorithm,
 }
 
 const fn default_enable_cache_compression() -> bool {
@@ -32,6 +36,7 @@ impl IndexerGrpcFileStoreWorkerConfig {
         enable_expensive_logging: Option<bool>,
         chain_id: u64,
         enable_cache_compression: bool,
+        cache_compression_algorithm: CompressionAlgorithm,
     ) -> Self {
         Self {
             file_store_config,
@@ -39,6 +44,7 @@ impl IndexerGrpcFileStoreWorkerConfig {
             enable_expensive_logging,
             chain_id,
             enable_cache_compression,
+            cache_compression_algorithm,
         }
     }
 }
@@ -46,11 +52,15 @@ impl IndexerGrpcFileStoreWorkerConfig {
 #[async_trait::async_trait]
 impl RunnableConfig for IndexerGrpcFileStoreWorkerConfig {
     async fn run(&self) -> Result<()> {
+        self.file_store_config
+            .load_zstd_dictionary()
+            .context("Failed to load zstd dictionary")?;
         let mut processor = Processor::new(
             self.redis_main_instance_address.clone(),
             self.file_store_config.clone(),
             self.chain_id,
             self.enable_cache_compression,
+            self.cache_compression_algorithm,
         )
         .await
         .expect("Failed to create file store processor");
@@ -5,7 +5,9 @@ use crate::metrics::{METADATA_UPLOAD_FAILURE_COUNT, PROCESSED_VERSIONS_COUNT};
 use anyhow::{ensure, Context, Result};
 use aptos_indexer_grpc_utils::{
     cache_operator::CacheOperator,
-    compression_util::{FileStoreMetadata, StorageFormat, FILE_ENTRY_TRANSACTION_COUNT},
+    compression_util::{
+        CompressionAlgorithm, FileStoreMetadata, StorageFormat, FILE_ENTRY_TRANSACTION_COUNT,
+    },
     config::IndexerGrpcFileStoreConfig,
     counters::{log_grpc_step, IndexerGrpcStep},
     file_store_operator::FileStoreOperator,
@@ -33,9 +35,10 @@ impl Processor {
         file_store_config: IndexerGrpcFileStoreConfig,
         chain_id: u64,
         enable_cache_compression: bool,
+        cache_compression_algorithm: CompressionAlgorithm,
     ) -> Result<Self> {
         let cache_storage_format = if enable_cache_compression {
-            StorageFormat::Lz4CompressedProto
+            cache_compression_algorithm.storage_format()
         } else {
             StorageFormat::Base64UncompressedProto
         };
@@ -101,6 +101,7 @@ pub fn get_ttl_in_seconds(timestamp_in_seconds: u64) -> u64 {
 pub struct CacheOperator<T: redis::aio::ConnectionLike + Send> {
     conn: T,
     storage_format: StorageFormat,
+    zstd_dictionary_id: Option<u32>,
 }
 
 impl<T: redis::aio::ConnectionLike + Send + Clone> CacheOperator<T> {
@@ -108,9 +109,16 @@ impl<T: redis::aio::ConnectionLike + Send + Clone> CacheOperator<T> {
         Self {
             conn,
             storage_format,
+            zstd_dictionary_id: None,
         }
     }
 
+    /// Compresses new zstd entries with the given registered dictionary instead of none.
+    pub fn with_zstd_dictionary(mut self, zstd_dictionary_id: Option<u32>) -> Self {
+        self.zstd_dictionary_id = zstd_dictionary_id;
+        self
+    }
+
     // Set up the cache if needed.
     pub async fn cache_setup_if_needed(&mut self) -> anyhow::Result<bool> {
         let version_inserted: bool = redis::cmd("SET")
@@ -236,7 +244,7 @@ impl<T: redis::aio::ConnectionLike + Send + Clone> CacheOperator<T> {
         let mut transactions = vec![];
         for encoded_transaction in encoded_transactions {
             let cache_entry: CacheEntry = CacheEntry::new(encoded_transaction, self.storage_format);
-            let transaction = cache_entry.into_transaction();
+            let transaction = cache_entry.into_transaction()?;
             transactions.push(transaction);
         }
         ensure!(
@@ -266,8 +274,11 @@ impl<T: redis::aio::ConnectionLike + Send + Clone> CacheOperator<T> {
                 .timestamp
                 .clone()
                 .map_or(0, |t| t.seconds as u64);
-            let cache_entry: CacheEntry =
-                CacheEntry::from_transaction(transaction, self.storage_format);
+            let cache_entry: CacheEntry = CacheEntry::from_transaction_with_zstd_dictionary(
+                transaction,
+                self.storage_format,
+                self.zstd_dictionary_id,
+            );
             let bytes = cache_entry.into_inner();
             size_in_bytes += bytes.len();
             redis_pipeline
@@ -384,7 +395,7 @@ impl<T: redis::aio::ConnectionLike + Send + Clone> CacheOperator<T> {
         let mut transactions = vec![];
         for encoded_transaction in encoded_transactions {
             let cache_entry: CacheEntry = CacheEntry::new(encoded_transaction, self.storage_format);
-            let transaction = cache_entry.into_transaction();
+            let transaction = cache_entry.into_transaction()?;
             transactions.push(transaction);
         }
         ensure!(
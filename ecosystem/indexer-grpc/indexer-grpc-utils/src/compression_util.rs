@@ -2,18 +2,30 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::default_file_storage_format;
+use anyhow::{anyhow, bail, Context, Result};
 use aptos_protos::{indexer::v1::TransactionsInStorage, transaction::v1::Transaction};
 use lz4::{Decoder, EncoderBuilder};
+use once_cell::sync::Lazy;
 use prost::Message;
 use ripemd::{Digest, Ripemd128};
 use serde::{Deserialize, Serialize};
-use std::io::{Read, Write};
+use std::{
+    collections::HashMap,
+    io::{Read, Write},
+    path::Path,
+    sync::{Arc, RwLock},
+};
 
 pub const FILE_ENTRY_TRANSACTION_COUNT: u64 = 1000;
 
+const ZSTD_COMPRESSION_LEVEL: i32 = 3;
+
 #[derive(Serialize, Deserialize, Debug, Copy, Clone, Eq, PartialEq)]
 pub enum StorageFormat {
     Lz4CompressedProto,
+    // Zstd compressed proto, optionally with a dictionary. The dictionary id is
+    // stored in the frame header, see `register_zstd_dictionary`.
+    ZstdCompressedProto,
     // Only used for legacy file format.
     // Use by cache only.
     Base64UncompressedProto,
@@ -65,8 +77,110 @@ impl FileStoreMetadata {
     }
 }
 
+/// Compression algorithm used when compression is enabled for the file store or cache.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Default, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CompressionAlgorithm {
+    #[default]
+    Lz4,
+    Zstd,
+}
+
+impl CompressionAlgorithm {
+    pub fn storage_format(self) -> StorageFormat {
+        match self {
+            CompressionAlgorithm::Lz4 => StorageFormat::Lz4CompressedProto,
+            CompressionAlgorithm::Zstd => StorageFormat::ZstdCompressedProto,
+        }
+    }
+}
+
+/// Zstd dictionaries known to this process, keyed by dictionary id.
+/// Entries are decompressed with the dictionary referenced in their frame header, while new
+/// entries are only compressed with a dictionary if the writer selects one explicitly, see
+/// `CacheEntry::from_transaction_with_zstd_dictionary`. This way, e.g., a process reading from
+/// one file store and writing to another doesn't compress with the dictionary of the source.
+static ZSTD_DICTIONARIES: Lazy<RwLock<HashMap<u32, Arc<Vec<u8>>>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// Registers a zstd dictionary so that entries compressed with it can be read, and so that
+/// writers can select it by the returned dictionary id.
+pub fn register_zstd_dictionary(dictionary: Vec<u8>) -> Result<u32> {
+    let dictionary_id = match zstd::zstd_safe::get_dict_id_from_dict(&dictionary) {
+        Some(dictionary_id) => dictionary_id.get(),
+        None => bail!(
+            "Zstd dictionary has no dictionary id; raw content dictionaries are not supported."
+        ),
+    };
+    ZSTD_DICTIONARIES
+        .write()
+        .unwrap()
+        .insert(dictionary_id, Arc::new(dictionary));
+    Ok(dictionary_id)
+}
+
+/// Reads a zstd dictionary from `path` and registers it, see `register_zstd_dictionary`.
+pub fn load_zstd_dictionary(path: &Path) -> Result<u32> {
+    let dictionary = std::fs::read(path)
+        .with_context(|| format!("Failed to read zstd dictionary {}", path.display()))?;
+    register_zstd_dictionary(dictionary)
+}
+
+/// Trains a zstd dictionary of at most `max_size` bytes from serialized samples, e.g.,
+/// proto encoded transactions.
+pub fn train_zstd_dictionary(samples: &[Vec<u8>], max_size: usize) -> Result<Vec<u8>> {
+    zstd::dict::from_samples(samples, max_size).context("Failed to train zstd dictionary.")
+}
+
+fn get_zstd_dictionary(dictionary_id: u32) -> Result<Arc<Vec<u8>>> {
+    ZSTD_DICTIONARIES
+        .read()
+        .unwrap()
+        .get(&dictionary_id)
+        .cloned()
+        .ok_or_else(|| anyhow!("Zstd dictionary {} is not loaded.", dictionary_id))
+}
+
+fn zstd_compress(bytes: &[u8], dictionary_id: Option<u32>) -> Vec<u8> {
+    match dictionary_id {
+        Some(dictionary_id) => {
+            let dictionary = get_zstd_dictionary(dictionary_id).expect("Zstd compression failed.");
+            let mut compressor =
+                zstd::bulk::Compressor::with_dictionary(ZSTD_COMPRESSION_LEVEL, &dictionary)
+                    .expect("Zstd compression failed.");
+            compressor
+                .compress(bytes)
+                .expect("Zstd compression failed.")
+        },
+        None => zstd::stream::encode_all(bytes, ZSTD_COMPRESSION_LEVEL)
+            .expect("Zstd compression failed."),
+    }
+}
+
+/// Decompresses a zstd frame, with the dictionary referenced in its header if any. Fails if
+/// that dictionary isn't registered in this process, e.g., if the data was written by a
+/// process configured with a different set of dictionaries.
+fn zstd_decompress(bytes: &[u8]) -> Result<Vec<u8>> {
+    let mut decompressed = Vec::new();
+    match zstd::zstd_safe::get_dict_id_from_frame(bytes) {
+        Some(dictionary_id) => {
+            let dictionary = get_zstd_dictionary(dictionary_id.get())?;
+            zstd::stream::read::Decoder::with_dictionary(bytes, &dictionary)
+                .and_then(|mut decoder| decoder.read_to_end(&mut decompressed))
+                .context("Zstd decompression failed.")?;
+        },
+        None => {
+            zstd::stream::read::Decoder::new(bytes)
+                .and_then(|mut decoder| decoder.read_to_end(&mut decompressed))
+                .context("Zstd decompression failed.")?;
+        },
+    }
+    Ok(decompressed)
+}
+
 pub enum CacheEntry {
     Lz4CompressionProto(Vec<u8>),
+    ZstdCompressionProto(Vec<u8>),
     // Only used for legacy cache entry.
     Base64UncompressedProto(Vec<u8>),
 }
@@ -75,6 +189,7 @@ impl CacheEntry {
     pub fn new(bytes: Vec<u8>, storage_format: StorageFormat) -> Self {
         match storage_format {
             StorageFormat::Lz4CompressedProto => Self::Lz4CompressionProto(bytes),
+            StorageFormat::ZstdCompressedProto => Self::ZstdCompressionProto(bytes),
             // Legacy format.
             StorageFormat::Base64UncompressedProto => Self::Base64UncompressedProto(bytes),
             StorageFormat::JsonBase64UncompressedProto => {
@@ -86,6 +201,7 @@ impl CacheEntry {
     pub fn into_inner(self) -> Vec<u8> {
         match self {
             CacheEntry::Lz4CompressionProto(bytes) => bytes,
+            CacheEntry::ZstdCompressionProto(bytes) => bytes,
             CacheEntry::Base64UncompressedProto(bytes) => bytes,
         }
     }
@@ -93,11 +209,22 @@ impl CacheEntry {
     pub fn size(&self) -> usize {
         match self {
             CacheEntry::Lz4CompressionProto(bytes) => bytes.len(),
+            CacheEntry::ZstdCompressionProto(bytes) => bytes.len(),
             CacheEntry::Base64UncompressedProto(bytes) => bytes.len(),
         }
     }
 
     pub fn from_transaction(transaction: Transaction, storage_format: StorageFormat) -> Self {
+        Self::from_transaction_with_zstd_dictionary(transaction, storage_format, None)
+    }
+
+    /// Like `from_transaction`, but zstd entries are compressed with the given registered
+    /// dictionary, if any. Other storage formats ignore the dictionary.
+    pub fn from_transaction_with_zstd_dictionary(
+        transaction: Transaction,
+        storage_format: StorageFormat,
+        zstd_dictionary_id: Option<u32>,
+    ) -> Self {
         let mut bytes = Vec::new();
         transaction
             .encode(&mut bytes)
@@ -113,6 +240,9 @@ impl CacheEntry {
                     .expect("Lz4 compression failed.");
                 CacheEntry::Lz4CompressionProto(compressed.finish().0)
             },
+            StorageFormat::ZstdCompressedProto => {
+                CacheEntry::ZstdCompressionProto(zstd_compress(&bytes, zstd_dictionary_id))
+            },
             StorageFormat::Base64UncompressedProto => {
                 let base64 = base64::encode(bytes).into_bytes();
                 CacheEntry::Base64UncompressedProto(base64)
@@ -129,6 +259,9 @@ impl CacheEntry {
             StorageFormat::Lz4CompressedProto => {
                 format!("l4:{}", version)
             },
+            StorageFormat::ZstdCompressedProto => {
+                format!("zs:{}", version)
+            },
             StorageFormat::Base64UncompressedProto => {
                 format!("{}", version)
             },
@@ -139,8 +272,8 @@ impl CacheEntry {
         }
     }
 
-    pub fn into_transaction(self) -> Transaction {
-        match self {
+    pub fn into_transaction(self) -> Result<Transaction> {
+        let transaction = match self {
             CacheEntry::Lz4CompressionProto(bytes) => {
                 let mut decompressor = Decoder::new(&bytes[..]).expect("Lz4 decompression failed.");
                 let mut decompressed = Vec::new();
@@ -149,16 +282,22 @@ impl CacheEntry {
                     .expect("Lz4 decompression failed.");
                 Transaction::decode(decompressed.as_slice()).expect("proto deserialization failed.")
             },
+            CacheEntry::ZstdCompressionProto(bytes) => {
+                let decompressed = zstd_decompress(&bytes)?;
+                Transaction::decode(decompressed.as_slice()).expect("proto deserialization failed.")
+            },
             CacheEntry::Base64UncompressedProto(bytes) => {
                 let bytes: Vec<u8> = base64::decode(bytes).expect("base64 decoding failed.");
                 Transaction::decode(bytes.as_slice()).expect("proto deserialization failed.")
             },
-        }
+        };
+        Ok(transaction)
     }
 }
 
 pub enum FileEntry {
     Lz4CompressionProto(Vec<u8>),
+    ZstdCompressionProto(Vec<u8>),
     // Only used for legacy file format.
     JsonBase64UncompressedProto(Vec<u8>),
 }
@@ -167,6 +306,7 @@ impl FileEntry {
     pub fn new(bytes: Vec<u8>, storage_format: StorageFormat) -> Self {
         match storage_format {
             StorageFormat::Lz4CompressedProto => Self::Lz4CompressionProto(bytes),
+            StorageFormat::ZstdCompressedProto => Self::ZstdCompressionProto(bytes),
             StorageFormat::Base64UncompressedProto => {
                 panic!("Base64UncompressedProto is not supported.")
             },
@@ -177,6 +317,7 @@ impl FileEntry {
     pub fn into_inner(self) -> Vec<u8> {
         match self {
             FileEntry::Lz4CompressionProto(bytes) => bytes,
+            FileEntry::ZstdCompressionProto(bytes) => bytes,
             FileEntry::JsonBase64UncompressedProto(bytes) => bytes,
         }
     }
@@ -184,6 +325,7 @@ impl FileEntry {
     pub fn size(&self) -> usize {
         match self {
             FileEntry::Lz4CompressionProto(bytes) => bytes.len(),
+            FileEntry::ZstdCompressionProto(bytes) => bytes.len(),
             FileEntry::JsonBase64UncompressedProto(bytes) => bytes.len(),
         }
     }
@@ -191,6 +333,16 @@ impl FileEntry {
     pub fn from_transactions(
         transactions: Vec<Transaction>,
         storage_format: StorageFormat,
+    ) -> Self {
+        Self::from_transactions_with_zstd_dictionary(transactions, storage_format, None)
+    }
+
+    /// Like `from_transactions`, but zstd entries are compressed with the given registered
+    /// dictionary, if any. Other storage formats ignore the dictionary.
+    pub fn from_transactions_with_zstd_dictionary(
+        transactions: Vec<Transaction>,
+        storage_format: StorageFormat,
+        zstd_dictionary_id: Option<u32>,
     ) -> Self {
         let mut bytes = Vec::new();
         let starting_version = transactions
@@ -220,6 +372,14 @@ impl FileEntry {
                     .expect("Lz4 compression failed.");
                 FileEntry::Lz4CompressionProto(compressed.finish().0)
             },
+            StorageFormat::ZstdCompressedProto => {
+                let t = TransactionsInStorage {
+                    starting_version: Some(transactions.first().unwrap().version),
+                    transactions,
+                };
+                t.encode(&mut bytes).expect("proto serialization failed.");
+                FileEntry::ZstdCompressionProto(zstd_compress(&bytes, zstd_dictionary_id))
+            },
             StorageFormat::Base64UncompressedProto => {
                 panic!("Base64UncompressedProto is not supported.")
             },
@@ -257,6 +417,12 @@ impl FileEntry {
                     file_prefix, starting_version
                 )
             },
+            StorageFormat::ZstdCompressedProto => {
+                format!(
+                    "compressed_files/zstd/{}_{}.bin",
+                    file_prefix, starting_version
+                )
+            },
             StorageFormat::JsonBase64UncompressedProto => {
                 format!("files/{}.json", starting_version)
             },
@@ -266,8 +432,8 @@ impl FileEntry {
         }
     }
 
-    pub fn into_transactions_in_storage(self) -> TransactionsInStorage {
-        match self {
+    pub fn into_transactions_in_storage(self) -> Result<TransactionsInStorage> {
+        let transactions_in_storage = match self {
             FileEntry::Lz4CompressionProto(bytes) => {
                 let mut decompressor = Decoder::new(&bytes[..]).expect("Lz4 decompression failed.");
                 let mut decompressed = Vec::new();
@@ -277,6 +443,11 @@ impl FileEntry {
                 TransactionsInStorage::decode(decompressed.as_slice())
                     .expect("proto deserialization failed.")
             },
+            FileEntry::ZstdCompressionProto(bytes) => {
+                let decompressed = zstd_decompress(&bytes)?;
+                TransactionsInStorage::decode(decompressed.as_slice())
+                    .expect("proto deserialization failed.")
+            },
             FileEntry::JsonBase64UncompressedProto(bytes) => {
                 let file: TransactionsLegacyFile =
                     serde_json::from_slice(bytes.as_slice()).expect("json deserialization failed.");
@@ -295,7 +466,8 @@ impl FileEntry {
                     transactions,
                 }
             },
-        }
+        };
+        Ok(transactions_in_storage)
     }
 }
 
@@ -316,7 +488,7 @@ mod tests {
             CacheEntry::from_transaction(transaction, StorageFormat::Base64UncompressedProto);
         // Make sure data is compressed.
         assert_ne!(cache_entry.size(), transaction_size);
-        let deserialized_transaction = cache_entry.into_transaction();
+        let deserialized_transaction = cache_entry.into_transaction().unwrap();
         assert_eq!(transaction_clone, deserialized_transaction);
     }
 
@@ -333,7 +505,24 @@ mod tests {
             CacheEntry::from_transaction(transaction, StorageFormat::Lz4CompressedProto);
         let compressed_size = cache_entry.size();
         assert!(compressed_size != proto_size);
-        let deserialized_transaction = cache_entry.into_transaction();
+        let deserialized_transaction = cache_entry.into_transaction().unwrap();
+        assert_eq!(transaction_clone, deserialized_transaction);
+    }
+
+    #[test]
+    fn test_cache_entry_builder_zstd_compressed_proto() {
+        let transaction = Transaction {
+            version: 42,
+            epoch: 333,
+            ..Transaction::default()
+        };
+        let transaction_clone = transaction.clone();
+        let proto_size = transaction.encoded_len();
+        let cache_entry =
+            CacheEntry::from_transaction(transaction, StorageFormat::ZstdCompressedProto);
+        let compressed_size = cache_entry.size();
+        assert!(compressed_size != proto_size);
+        let deserialized_transaction = cache_entry.into_transaction().unwrap();
         assert_eq!(transaction_clone, deserialized_transaction);
     }
 
@@ -376,7 +565,7 @@ mod tests {
             transactions.clone(),
             StorageFormat::JsonBase64UncompressedProto,
         );
-        let deserialized_transactions = file_entry.into_transactions_in_storage();
+        let deserialized_transactions = file_entry.into_transactions_in_storage().unwrap();
         for (i, transaction) in transactions.iter().enumerate() {
             assert_eq!(transaction, &deserialized_transactions.transactions[i]);
         }
@@ -399,12 +588,150 @@ mod tests {
         let file_entry =
             FileEntry::from_transactions(transactions.clone(), StorageFormat::Lz4CompressedProto);
         assert_ne!(file_entry.size(), transactions_in_storage_size);
-        let deserialized_transactions = file_entry.into_transactions_in_storage();
+        let deserialized_transactions = file_entry.into_transactions_in_storage().unwrap();
+        for (i, transaction) in transactions.iter().enumerate() {
+            assert_eq!(transaction, &deserialized_transactions.transactions[i]);
+        }
+    }
+
+    #[test]
+    fn test_file_entry_builder_zstd_compressed_proto() {
+        let transactions = (1000..2000)
+            .map(|version| Transaction {
+                version,
+                epoch: 333,
+                ..Transaction::default()
+            })
+            .collect::<Vec<Transaction>>();
+        let transactions_in_storage = TransactionsInStorage {
+            starting_version: Some(1000),
+            transactions: transactions.clone(),
+        };
+        let transactions_in_storage_size = transactions_in_storage.encoded_len();
+        let file_entry =
+            FileEntry::from_transactions(transactions.clone(), StorageFormat::ZstdCompressedProto);
+        assert!(file_entry.size() < transactions_in_storage_size);
+        let deserialized_transactions = file_entry.into_transactions_in_storage().unwrap();
         for (i, transaction) in transactions.iter().enumerate() {
             assert_eq!(transaction, &deserialized_transactions.transactions[i]);
         }
     }
 
+    #[test]
+    fn test_zstd_dictionary_round_trip() {
+        let samples = (0..2000)
+            .map(|version| {
+                Transaction {
+                    version,
+                    epoch: version / 100,
+                    block_height: version / 10,
+                    ..Transaction::default()
+                }
+                .encode_to_vec()
+            })
+            .collect::<Vec<Vec<u8>>>();
+        let dictionary = train_zstd_dictionary(&samples, 1024).unwrap();
+        let dictionary_id = register_zstd_dictionary(dictionary).unwrap();
+
+        let transaction = Transaction {
+            version: 4242,
+            epoch: 42,
+            block_height: 424,
+            ..Transaction::default()
+        };
+        let cache_entry = CacheEntry::from_transaction_with_zstd_dictionary(
+            transaction.clone(),
+            StorageFormat::ZstdCompressedProto,
+            Some(dictionary_id),
+        );
+        assert_eq!(
+            zstd::zstd_safe::get_dict_id_from_frame(&cache_entry.into_inner()).map(|id| id.get()),
+            Some(dictionary_id)
+        );
+        let cache_entry = CacheEntry::from_transaction_with_zstd_dictionary(
+            transaction.clone(),
+            StorageFormat::ZstdCompressedProto,
+            Some(dictionary_id),
+        );
+        assert_eq!(transaction, cache_entry.into_transaction().unwrap());
+
+        // Registering a dictionary doesn't change how other writers compress.
+        let cache_entry =
+            CacheEntry::from_transaction(transaction.clone(), StorageFormat::ZstdCompressedProto);
+        assert_eq!(
+            zstd::zstd_safe::get_dict_id_from_frame(&cache_entry.into_inner()),
+            None
+        );
+
+        let transactions: Vec<Transaction> = (1000..2000)
+            .map(|version| Transaction {
+                version,
+                ..Transaction::default()
+            })
+            .collect();
+        let bytes = FileEntry::from_transactions_with_zstd_dictionary(
+            transactions.clone(),
+            StorageFormat::ZstdCompressedProto,
+            Some(dictionary_id),
+        )
+        .into_inner();
+        assert_eq!(
+            zstd::zstd_safe::get_dict_id_from_frame(&bytes).map(|id| id.get()),
+            Some(dictionary_id)
+        );
+        let file_entry = FileEntry::new(bytes, StorageFormat::ZstdCompressedProto);
+        assert_eq!(
+            file_entry
+                .into_transactions_in_storage()
+                .unwrap()
+                .transactions,
+            transactions
+        );
+    }
+
+    #[test]
+    fn test_zstd_decompression_with_unknown_dictionary_fails() {
+        let samples = (0..2000)
+            .map(|version| {
+                Transaction {
+                    version,
+                    epoch: version / 100,
+                    block_height: version / 10,
+                    ..Transaction::default()
+                }
+                .encode_to_vec()
+            })
+            .collect::<Vec<Vec<u8>>>();
+        // The dictionary is never registered, as if the data was written by another process.
+        let dictionary = train_zstd_dictionary(&samples, 1024).unwrap();
+        let bytes = zstd::bulk::Compressor::with_dictionary(ZSTD_COMPRESSION_LEVEL, &dictionary)
+            .unwrap()
+            .compress(&samples[42])
+            .unwrap();
+
+        let cache_entry = CacheEntry::new(bytes.clone(), StorageFormat::ZstdCompressedProto);
+        assert!(cache_entry.into_transaction().is_err());
+        let file_entry = FileEntry::new(bytes, StorageFormat::ZstdCompressedProto);
+        assert!(file_entry.into_transactions_in_storage().is_err());
+    }
+
+    #[test]
+    fn test_register_zstd_dictionary_without_id_fails() {
+        assert!(register_zstd_dictionary(b"raw content dictionary".to_vec()).is_err());
+    }
+
+    #[test]
+    fn test_compression_algorithm_storage_format() {
+        assert_eq!(
+            CompressionAlgorithm::default().storage_format(),
+            StorageFormat::Lz4CompressedProto
+        );
+        assert_eq!(
+            CompressionAlgorithm::Zstd.storage_format(),
+            StorageFormat::ZstdCompressedProto
+        );
+    }
+
     #[test]
     fn test_cache_entry_key_to_string_lz4_compressed_proto() {
         assert_eq!(
@@ -413,6 +740,14 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_cache_entry_key_to_string_zstd_compressed_proto() {
+        assert_eq!(
+            CacheEntry::build_key(42, StorageFormat::ZstdCompressedProto),
+            "zs:42"
+        );
+    }
+
     #[test]
     fn test_cache_entry_key_to_string_base64_uncompressed_proto() {
         assert_eq!(
@@ -435,6 +770,14 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_file_entry_key_to_string_zstd_compressed_proto() {
+        assert_eq!(
+            FileEntry::build_key(42, StorageFormat::ZstdCompressedProto),
+            "compressed_files/zstd/3d1bff1ba654ca5fdb6ac1370533d876_0.bin"
+        );
+    }
+
     #[test]
     #[should_panic]
     fn test_file_entry_key_to_string_base64_uncompressed_proto() {
@@ -1,6 +1,8 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::compression_util::{load_zstd_dictionary, CompressionAlgorithm, StorageFormat};
+use anyhow::Result;
 use serde::{Deserialize, Serialize};
 /// Common configuration for Indexer GRPC Store.
 use std::path::PathBuf;
@@ -13,6 +15,37 @@ pub struct GcsFileStore {
     pub gcs_file_store_service_account_key_path: String,
     #[serde(default = "default_enable_compression")]
     pub enable_compression: bool,
+    // Only used when compression is enabled.
+    #[serde(default)]
+    pub compression_algorithm: CompressionAlgorithm,
+    // Optional zstd dictionary; this store compresses new zstd entries with it. Entries
+    // compressed with it can only be read by processes that load the same dictionary.
+    pub zstd_dictionary_path: Option<PathBuf>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct S3FileStore {
+    pub s3_file_store_bucket_name: String,
+    pub s3_file_store_bucket_sub_dir: Option<PathBuf>,
+    #[serde(default = "default_s3_region")]
+    pub s3_file_store_region: String,
+    // Custom endpoint for S3 compatible stores, e.g., MinIO. Credentials are read from the
+    // standard AWS environment variables, profile or instance metadata.
+    pub s3_file_store_endpoint: Option<String>,
+    // Required by most self-hosted S3 compatible stores.
+    #[serde(default)]
+    pub s3_file_store_force_path_style: bool,
+    #[serde(default = "default_s3_max_attempts")]
+    pub s3_file_store_max_attempts: u32,
+    #[serde(default = "default_s3_multipart_part_size_bytes")]
+    pub s3_file_store_multipart_part_size_bytes: usize,
+    #[serde(default = "default_enable_compression")]
+    pub enable_compression: bool,
+    // Only used when compression is enabled.
+    #[serde(default)]
+    pub compression_algorithm: CompressionAlgorithm,
+    // See `GcsFileStore::zstd_dictionary_path`.
+    pub zstd_dictionary_path: Option<PathBuf>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -20,16 +53,34 @@ pub struct LocalFileStore {
     pub local_file_store_path: PathBuf,
     #[serde(default = "default_enable_compression")]
     pub enable_compression: bool,
+    // Only used when compression is enabled.
+    #[serde(default)]
+    pub compression_algorithm: CompressionAlgorithm,
+    // See `GcsFileStore::zstd_dictionary_path`.
+    pub zstd_dictionary_path: Option<PathBuf>,
 }
 
 const fn default_enable_compression() -> bool {
     false
 }
 
+fn default_s3_region() -> String {
+    "us-east-1".to_string()
+}
+
+const fn default_s3_max_attempts() -> u32 {
+    5
+}
+
+const fn default_s3_multipart_part_size_bytes() -> usize {
+    16 * 1024 * 1024
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(tag = "file_store_type")]
 pub enum IndexerGrpcFileStoreConfig {
     GcsFileStore(GcsFileStore),
+    S3FileStore(S3FileStore),
     LocalFileStore(LocalFileStore),
 }
 
@@ -38,29 +89,110 @@ impl Default for IndexerGrpcFileStoreConfig {
         IndexerGrpcFileStoreConfig::LocalFileStore(LocalFileStore {
             local_file_store_path: std::env::current_dir().unwrap(),
             enable_compression: false,
+            compression_algorithm: CompressionAlgorithm::default(),
+            zstd_dictionary_path: None,
         })
     }
 }
 
 impl IndexerGrpcFileStoreConfig {
+    /// Creates the operator of this file store. New zstd files are compressed with the
+    /// dictionary configured for this store, if any, so that multiple stores with different
+    /// dictionaries can be used in one process.
     pub fn create(&self) -> Box<dyn crate::file_store_operator::FileStoreOperator> {
+        let zstd_dictionary_id = self
+            .load_zstd_dictionary()
+            .expect("Failed to load zstd dictionary.");
         match self {
-            IndexerGrpcFileStoreConfig::GcsFileStore(gcs_file_store) => {
-                Box::new(crate::file_store_operator::gcs::GcsFileStoreOperator::new(
+            IndexerGrpcFileStoreConfig::GcsFileStore(gcs_file_store) => Box::new(
+                crate::file_store_operator::gcs::GcsFileStoreOperator::new(
                     gcs_file_store.gcs_file_store_bucket_name.clone(),
                     gcs_file_store.gcs_file_store_bucket_sub_dir.clone(),
                     gcs_file_store
                         .gcs_file_store_service_account_key_path
                         .clone(),
                     gcs_file_store.enable_compression,
-                ))
-            },
+                    gcs_file_store.compression_algorithm,
+                )
+                .with_zstd_dictionary(zstd_dictionary_id),
+            ),
+            IndexerGrpcFileStoreConfig::S3FileStore(s3_file_store) => Box::new(
+                crate::file_store_operator::s3::S3FileStoreOperator::new(
+                    s3_file_store.s3_file_store_bucket_name.clone(),
+                    s3_file_store.s3_file_store_bucket_sub_dir.clone(),
+                    crate::file_store_operator::s3::S3ClientConfig {
+                        region: s3_file_store.s3_file_store_region.clone(),
+                        endpoint: s3_file_store.s3_file_store_endpoint.clone(),
+                        force_path_style: s3_file_store.s3_file_store_force_path_style,
+                        max_attempts: s3_file_store.s3_file_store_max_attempts,
+                        multipart_part_size_bytes: s3_file_store
+                            .s3_file_store_multipart_part_size_bytes,
+                    },
+                    s3_file_store.enable_compression,
+                    s3_file_store.compression_algorithm,
+                )
+                .with_zstd_dictionary(zstd_dictionary_id),
+            ),
             IndexerGrpcFileStoreConfig::LocalFileStore(local_file_store) => Box::new(
                 crate::file_store_operator::local::LocalFileStoreOperator::new(
                     local_file_store.local_file_store_path.clone(),
                     local_file_store.enable_compression,
-                ),
+                    local_file_store.compression_algorithm,
+                )
+                .with_zstd_dictionary(zstd_dictionary_id),
             ),
         }
     }
+
+    pub fn storage_format(&self) -> StorageFormat {
+        let (enable_compression, compression_algorithm) = match self {
+            IndexerGrpcFileStoreConfig::GcsFileStore(gcs_file_store) => (
+                gcs_file_store.enable_compression,
+                gcs_file_store.compression_algorithm,
+            ),
+            IndexerGrpcFileStoreConfig::S3FileStore(s3_file_store) => (
+                s3_file_store.enable_compression,
+                s3_file_store.compression_algorithm,
+            ),
+            IndexerGrpcFileStoreConfig::LocalFileStore(local_file_store) => (
+                local_file_store.enable_compression,
+                local_file_store.compression_algorithm,
+            ),
+        };
+        if enable_compression {
+            compression_algorithm.storage_format()
+        } else {
+            StorageFormat::JsonBase64UncompressedProto
+        }
+    }
+
+    pub fn zstd_dictionary_path(&self) -> Option<&PathBuf> {
+        match self {
+            IndexerGrpcFileStoreConfig::GcsFileStore(gcs_file_store) => {
+                gcs_file_store.zstd_dictionary_path.as_ref()
+            },
+            IndexerGrpcFileStoreConfig::S3FileStore(s3_file_store) => {
+                s3_file_store.zstd_dictionary_path.as_ref()
+            },
+            IndexerGrpcFileStoreConfig::LocalFileStore(local_file_store) => {
+                local_file_store.zstd_dictionary_path.as_ref()
+            },
+        }
+    }
+
+    /// Loads the configured zstd dictionary, if any, so that entries compressed with it can be
+    /// read. Returns the id of the dictionary, which writers of this store select explicitly.
+    /// This has to be called before any zstd entry is read.
+    pub fn load_zstd_dictionary(&self) -> Result<Option<u32>> {
+        let Some(path) = self.zstd_dictionary_path() else {
+            return Ok(None);
+        };
+        let dictionary_id = load_zstd_dictionary(path)?;
+        tracing::info!(
+            dictionary_id = dictionary_id,
+            path = path.display().to_string(),
+            "[Indexer File] Loaded zstd dictionary."
+        );
+        Ok(Some(dictionary_id))
+    }
 }
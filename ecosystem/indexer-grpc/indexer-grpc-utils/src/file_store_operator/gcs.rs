@@ -2,7 +2,10 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    compression_util::{FileEntry, FileStoreMetadata, StorageFormat, FILE_ENTRY_TRANSACTION_COUNT},
+    compression_util::{
+        CompressionAlgorithm, FileEntry, FileStoreMetadata, StorageFormat,
+        FILE_ENTRY_TRANSACTION_COUNT,
+    },
     counters::{log_grpc_step, IndexerGrpcStep},
     file_store_operator::{FileStoreOperator, METADATA_FILE_NAME},
 };
@@ -22,6 +25,7 @@ pub struct GcsFileStoreOperator {
     bucket_sub_dir: Option<PathBuf>,
     file_store_metadata_last_updated: std::time::Instant,
     storage_format: StorageFormat,
+    zstd_dictionary_id: Option<u32>,
     metadata_file_path: PathBuf,
 }
 
@@ -31,10 +35,11 @@ impl GcsFileStoreOperator {
         bucket_sub_dir: Option<PathBuf>,
         service_account_path: String,
         enable_compression: bool,
+        compression_algorithm: CompressionAlgorithm,
     ) -> Self {
         env::set_var(SERVICE_ACCOUNT_ENV_VAR, service_account_path);
         let storage_format = if enable_compression {
-            StorageFormat::Lz4CompressedProto
+            compression_algorithm.storage_format()
         } else {
             StorageFormat::JsonBase64UncompressedProto
         };
@@ -55,10 +60,17 @@ impl GcsFileStoreOperator {
             bucket_sub_dir,
             file_store_metadata_last_updated: std::time::Instant::now(),
             storage_format,
+            zstd_dictionary_id: None,
             metadata_file_path,
         }
     }
 
+    /// Compresses new zstd files with the given registered dictionary instead of none.
+    pub fn with_zstd_dictionary(mut self, zstd_dictionary_id: Option<u32>) -> Self {
+        self.zstd_dictionary_id = zstd_dictionary_id;
+        self
+    }
+
     /// Given a version number, builds the key path for the file entry. This is dependent on the storage format and whether we opt
     /// to use a sub directory, in the case of a shared bucket. The key path can be used directly as a GCS bucket file path.
     fn get_file_entry_key_path(&self, version: u64) -> String {
@@ -219,7 +231,11 @@ impl FileStoreOperator for GcsFileStoreOperator {
         );
         let start_time = std::time::Instant::now();
         let bucket_name = self.bucket_name.clone();
-        let file_entry = FileEntry::from_transactions(transactions, self.storage_format);
+        let file_entry = FileEntry::from_transactions_with_zstd_dictionary(
+            transactions,
+            self.storage_format,
+            self.zstd_dictionary_id,
+        );
         let file_entry_key_path = self.get_file_entry_key_path(start_version);
         log_grpc_step(
             "file_worker",
@@ -2,7 +2,10 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    compression_util::{FileEntry, FileStoreMetadata, StorageFormat, FILE_ENTRY_TRANSACTION_COUNT},
+    compression_util::{
+        CompressionAlgorithm, FileEntry, FileStoreMetadata, StorageFormat,
+        FILE_ENTRY_TRANSACTION_COUNT,
+    },
     file_store_operator::{
         FileStoreOperator, FILE_STORE_UPDATE_FREQUENCY_SECS, METADATA_FILE_NAME,
     },
@@ -18,12 +21,17 @@ pub struct LocalFileStoreOperator {
     /// The timestamp of the latest metadata update; this is to avoid too frequent metadata update.
     latest_metadata_update_timestamp: Option<std::time::Instant>,
     storage_format: StorageFormat,
+    zstd_dictionary_id: Option<u32>,
 }
 
 impl LocalFileStoreOperator {
-    pub fn new(path: PathBuf, enable_compression: bool) -> Self {
+    pub fn new(
+        path: PathBuf,
+        enable_compression: bool,
+        compression_algorithm: CompressionAlgorithm,
+    ) -> Self {
         let storage_format = if enable_compression {
-            StorageFormat::Lz4CompressedProto
+            compression_algorithm.storage_format()
         } else {
             StorageFormat::JsonBase64UncompressedProto
         };
@@ -31,8 +39,15 @@ impl LocalFileStoreOperator {
             path,
             latest_metadata_update_timestamp: None,
             storage_format,
+            zstd_dictionary_id: None,
         }
     }
+
+    /// Compresses new zstd files with the given registered dictionary instead of none.
+    pub fn with_zstd_dictionary(mut self, zstd_dictionary_id: Option<u32>) -> Self {
+        self.zstd_dictionary_id = zstd_dictionary_id;
+        self
+    }
 }
 
 #[async_trait::async_trait]
@@ -167,7 +182,11 @@ impl FileStoreOperator for LocalFileStoreOperator {
         for i in transactions.chunks(FILE_ENTRY_TRANSACTION_COUNT as usize) {
             let current_batch = i.iter().cloned().collect_vec();
             let starting_version = current_batch.first().unwrap().version;
-            let file_entry = FileEntry::from_transactions(current_batch, self.storage_format);
+            let file_entry = FileEntry::from_transactions_with_zstd_dictionary(
+                current_batch,
+                self.storage_format,
+                self.zstd_dictionary_id,
+            );
             let file_entry_key =
                 FileEntry::build_key(starting_version, self.storage_format).to_string();
             let txns_path = self.path.join(file_entry_key.as_str());
@@ -12,6 +12,8 @@ pub use gcs::*;
 pub mod local;
 use crate::counters::TRANSACTION_STORE_FETCH_RETRIES;
 pub use local::*;
+pub mod s3;
+pub use s3::*;
 
 const METADATA_FILE_NAME: &str = "metadata.json";
 const FILE_STORE_UPDATE_FREQUENCY_SECS: u64 = 5;
@@ -71,7 +73,7 @@ pub trait FileStoreOperator: Send + Sync {
             FileEntry::new(bytes, storage_format).into_transactions_in_storage()
         })
         .await
-        .context("Converting storage bytes to FileEntry transactions thread panicked")?;
+        .context("Converting storage bytes to FileEntry transactions thread panicked")??;
 
         let decoding_duration = decoding_start_time.elapsed().as_secs_f64();
         Ok((
@@ -0,0 +1,671 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    compression_util::{
+        CompressionAlgorithm, FileEntry, FileStoreMetadata, StorageFormat,
+        FILE_ENTRY_TRANSACTION_COUNT,
+    },
+    counters::{log_grpc_step, IndexerGrpcStep},
+    file_store_operator::{FileStoreOperator, METADATA_FILE_NAME},
+};
+use anyhow::{bail, Context};
+use aptos_protos::transaction::v1::Transaction;
+use aws_config::{retry::RetryConfig, BehaviorVersion, Region};
+use aws_sdk_s3::{
+    primitives::ByteStream,
+    types::{CompletedMultipartUpload, CompletedPart},
+    Client,
+};
+use std::{path::PathBuf, sync::Arc};
+use tokio::sync::OnceCell;
+
+const JSON_FILE_TYPE: &str = "application/json";
+const FILE_STORE_METADATA_TIMEOUT_MILLIS: u128 = 200;
+/// S3 requires every part of a multipart upload, except the last one, to be at least 5 MiB.
+pub const S3_MIN_MULTIPART_PART_SIZE_BYTES: usize = 5 * 1024 * 1024;
+
+/// Connection settings for an S3 compatible object store (e.g., AWS S3 or MinIO).
+#[derive(Clone, Debug)]
+pub struct S3ClientConfig {
+    pub region: String,
+    /// Custom endpoint of the object store. If not set, the AWS endpoint for the region is used.
+    pub endpoint: Option<String>,
+    /// Whether to address the bucket in the path (e.g., `http://host/bucket/key`) instead of
+    /// the host name. Most self-hosted stores, such as MinIO, require this.
+    pub force_path_style: bool,
+    /// The maximum number of attempts for each request, including the initial attempt.
+    pub max_attempts: u32,
+    /// Files larger than this are uploaded with multipart uploads, in parts of this size.
+    pub multipart_part_size_bytes: usize,
+}
+
+#[derive(Clone)]
+pub struct S3FileStoreOperator {
+    bucket_name: String,
+    bucket_sub_dir: Option<PathBuf>,
+    client_config: S3ClientConfig,
+    // The client is created lazily, because loading the credentials requires an async context.
+    client: Arc<OnceCell<Client>>,
+    file_store_metadata_last_updated: std::time::Instant,
+    storage_format: StorageFormat,
+    zstd_dictionary_id: Option<u32>,
+    metadata_file_path: String,
+}
+
+impl S3FileStoreOperator {
+    pub fn new(
+        bucket_name: String,
+        bucket_sub_dir: Option<PathBuf>,
+        client_config: S3ClientConfig,
+        enable_compression: bool,
+        compression_algorithm: CompressionAlgorithm,
+    ) -> Self {
+        assert!(
+            client_config.multipart_part_size_bytes >= S3_MIN_MULTIPART_PART_SIZE_BYTES,
+            "The multipart part size has to be at least {} bytes.",
+            S3_MIN_MULTIPART_PART_SIZE_BYTES
+        );
+        let storage_format = if enable_compression {
+            compression_algorithm.storage_format()
+        } else {
+            StorageFormat::JsonBase64UncompressedProto
+        };
+        let metadata_file_path = Self::build_key_path(&bucket_sub_dir, METADATA_FILE_NAME.into());
+        Self {
+            bucket_name,
+            bucket_sub_dir,
+            client_config,
+            client: Arc::new(OnceCell::new()),
+            file_store_metadata_last_updated: std::time::Instant::now(),
+            storage_format,
+            zstd_dictionary_id: None,
+            metadata_file_path,
+        }
+    }
+
+    /// Compresses new zstd files with the given registered dictionary instead of none.
+    pub fn with_zstd_dictionary(mut self, zstd_dictionary_id: Option<u32>) -> Self {
+        self.zstd_dictionary_id = zstd_dictionary_id;
+        self
+    }
+
+    /// Prefixes the key with the sub directory, in the case of a shared bucket. Object keys
+    /// always use `/` as the separator, regardless of the platform.
+    fn build_key_path(bucket_sub_dir: &Option<PathBuf>, key: String) -> String {
+        match bucket_sub_dir {
+            Some(sub_dir) => {
+                let sub_dir = sub_dir.to_string_lossy();
+                format!("{}/{}", sub_dir.trim_end_matches('/'), key)
+            },
+            None => key,
+        }
+    }
+
+    /// Given a version number, builds the key path for the file entry. The key path can be used
+    /// directly as an S3 object key.
+    fn get_file_entry_key_path(&self, version: u64) -> String {
+        let file_entry_key = FileEntry::build_key(version, self.storage_format).to_string();
+        Self::build_key_path(&self.bucket_sub_dir, file_entry_key)
+    }
+
+    async fn client(&self) -> &Client {
+        self.client
+            .get_or_init(|| async {
+                let mut loader = aws_config::defaults(BehaviorVersion::latest())
+                    .region(Region::new(self.client_config.region.clone()))
+                    .retry_config(
+                        RetryConfig::standard().with_max_attempts(self.client_config.max_attempts),
+                    );
+                if let Some(endpoint) = &self.client_config.endpoint {
+                    loader = loader.endpoint_url(endpoint);
+                }
+                let sdk_config = loader.load().await;
+                let s3_config = aws_sdk_s3::config::Builder::from(&sdk_config)
+                    .force_path_style(self.client_config.force_path_style)
+                    .build();
+                Client::from_conf(s3_config)
+            })
+            .await
+    }
+
+    /// Downloads the object with the given key. Returns None if the object doesn't exist.
+    async fn get_object(&self, key: &str) -> anyhow::Result<Option<Vec<u8>>> {
+        let output = match self
+            .client()
+            .await
+            .get_object()
+            .bucket(&self.bucket_name)
+            .key(key)
+            .send()
+            .await
+        {
+            Ok(output) => output,
+            Err(err) => {
+                if err
+                    .as_service_error()
+                    .map_or(false, |err| err.is_no_such_key())
+                {
+                    return Ok(None);
+                }
+                return Err(err.into());
+            },
+        };
+        let bytes = output
+            .body
+            .collect()
+            .await
+            .context("Failed to read the object body.")?;
+        Ok(Some(bytes.into_bytes().to_vec()))
+    }
+
+    /// Uploads the object with the given key, using a multipart upload if it is larger than the
+    /// configured part size.
+    async fn put_object(&self, key: &str, bytes: Vec<u8>) -> anyhow::Result<()> {
+        if bytes.len() <= self.client_config.multipart_part_size_bytes {
+            self.client()
+                .await
+                .put_object()
+                .bucket(&self.bucket_name)
+                .key(key)
+                .content_type(JSON_FILE_TYPE)
+                .body(ByteStream::from(bytes))
+                .send()
+                .await?;
+            return Ok(());
+        }
+
+        let upload = self
+            .client()
+            .await
+            .create_multipart_upload()
+            .bucket(&self.bucket_name)
+            .key(key)
+            .content_type(JSON_FILE_TYPE)
+            .send()
+            .await?;
+        let upload_id = upload
+            .upload_id()
+            .context("Multipart upload is missing an upload id.")?
+            .to_string();
+
+        match self.upload_parts(key, &upload_id, bytes).await {
+            Ok(()) => Ok(()),
+            Err(err) => {
+                // Abort the upload so the store doesn't keep the uploaded parts around.
+                if let Err(abort_err) = self
+                    .client()
+                    .await
+                    .abort_multipart_upload()
+                    .bucket(&self.bucket_name)
+                    .key(key)
+                    .upload_id(&upload_id)
+                    .send()
+                    .await
+                {
+                    tracing::warn!(
+                        key = key,
+                        upload_id = upload_id.as_str(),
+                        error = ?abort_err,
+                        "[Indexer File] Failed to abort multipart upload."
+                    );
+                }
+                Err(err)
+            },
+        }
+    }
+
+    async fn upload_parts(&self, key: &str, upload_id: &str, bytes: Vec<u8>) -> anyhow::Result<()> {
+        let mut completed_parts = vec![];
+        for (index, chunk) in bytes
+            .chunks(self.client_config.multipart_part_size_bytes)
+            .enumerate()
+        {
+            // Part numbers start at 1.
+            let part_number = (index + 1) as i32;
+            let part = self
+                .client()
+                .await
+                .upload_part()
+                .bucket(&self.bucket_name)
+                .key(key)
+                .upload_id(upload_id)
+                .part_number(part_number)
+                .body(ByteStream::from(chunk.to_vec()))
+                .send()
+                .await?;
+            completed_parts.push(
+                CompletedPart::builder()
+                    .set_e_tag(part.e_tag().map(|e_tag| e_tag.to_string()))
+                    .part_number(part_number)
+                    .build(),
+            );
+        }
+
+        self.client()
+            .await
+            .complete_multipart_upload()
+            .bucket(&self.bucket_name)
+            .key(key)
+            .upload_id(upload_id)
+            .multipart_upload(
+                CompletedMultipartUpload::builder()
+                    .set_parts(Some(completed_parts))
+                    .build(),
+            )
+            .send()
+            .await?;
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl FileStoreOperator for S3FileStoreOperator {
+    /// Bootstraps the file store operator. This is required before any other operations.
+    async fn verify_storage_bucket_existence(&self) {
+        tracing::info!(
+            bucket_name = self.bucket_name,
+            "Before file store operator starts, verify the bucket exists."
+        );
+        // Verifies the bucket exists.
+        self.client()
+            .await
+            .head_bucket()
+            .bucket(&self.bucket_name)
+            .send()
+            .await
+            .expect("Failed to read bucket.");
+    }
+
+    fn storage_format(&self) -> StorageFormat {
+        self.storage_format
+    }
+
+    fn store_name(&self) -> &str {
+        "S3"
+    }
+
+    async fn get_raw_file(&self, version: u64) -> anyhow::Result<Vec<u8>> {
+        let file_entry_key_path = self.get_file_entry_key_path(version);
+        match self.get_object(&file_entry_key_path).await {
+            Ok(Some(file)) => Ok(file),
+            Ok(None) => bail!(
+                "[Indexer File] Transactions file not found. Gap might happen between cache and file store. {}",
+                file_entry_key_path
+            ),
+            Err(err) => bail!(
+                "[Indexer File] Error happens when downloading transaction file. {}",
+                err
+            ),
+        }
+    }
+
+    /// Gets the metadata from the file store. Operator will panic if error happens when accessing the metadata file(except not found).
+    async fn get_file_store_metadata(&self) -> Option<FileStoreMetadata> {
+        match self.get_object(&self.metadata_file_path).await {
+            Ok(Some(metadata)) => {
+                let metadata: FileStoreMetadata =
+                    serde_json::from_slice(&metadata).expect("Expected metadata to be valid JSON.");
+                Some(metadata)
+            },
+            // Metadata is not found.
+            Ok(None) => None,
+            Err(err) => {
+                panic!(
+                    "[Indexer File] Error happens when accessing metadata file. {}",
+                    err
+                );
+            },
+        }
+    }
+
+    /// If the file store is empty, the metadata will be created; otherwise, return the existing metadata.
+    async fn update_file_store_metadata_with_timeout(
+        &mut self,
+        expected_chain_id: u64,
+        version: u64,
+    ) -> anyhow::Result<()> {
+        if let Some(metadata) = self.get_file_store_metadata().await {
+            assert_eq!(metadata.chain_id, expected_chain_id, "Chain ID mismatch.");
+            assert_eq!(
+                metadata.storage_format, self.storage_format,
+                "Storage format mismatch."
+            );
+        }
+        if self.file_store_metadata_last_updated.elapsed().as_millis()
+            < FILE_STORE_METADATA_TIMEOUT_MILLIS
+        {
+            bail!("File store metadata is updated too frequently.")
+        }
+        self.update_file_store_metadata_internal(expected_chain_id, version)
+            .await?;
+        Ok(())
+    }
+
+    /// Updates the file store metadata. This is only performed by the operator when new file transactions are uploaded.
+    async fn update_file_store_metadata_internal(
+        &mut self,
+        chain_id: u64,
+        version: u64,
+    ) -> anyhow::Result<()> {
+        let metadata = FileStoreMetadata::new(chain_id, version, self.storage_format);
+        // If the metadata is not updated, the indexer will be restarted.
+        self.put_object(
+            &self.metadata_file_path,
+            serde_json::to_vec(&metadata).unwrap(),
+        )
+        .await?;
+        self.file_store_metadata_last_updated = std::time::Instant::now();
+        Ok(())
+    }
+
+    /// Uploads the transactions to the file store. The transactions are grouped into batches of BLOB_STORAGE_SIZE.
+    /// Updates the file store metadata after the upload.
+    async fn upload_transaction_batch(
+        &mut self,
+        _chain_id: u64,
+        transactions: Vec<Transaction>,
+    ) -> anyhow::Result<(u64, u64)> {
+        let start_version = transactions.first().unwrap().version;
+        let end_version = transactions.last().unwrap().version;
+        let batch_size = transactions.len();
+        anyhow::ensure!(
+            start_version % FILE_ENTRY_TRANSACTION_COUNT == 0,
+            "Starting version has to be a multiple of BLOB_STORAGE_SIZE."
+        );
+        anyhow::ensure!(
+            batch_size == FILE_ENTRY_TRANSACTION_COUNT as usize,
+            "The number of transactions to upload has to be multiplier of BLOB_STORAGE_SIZE."
+        );
+        let start_time = std::time::Instant::now();
+        let file_entry = FileEntry::from_transactions_with_zstd_dictionary(
+            transactions,
+            self.storage_format,
+            self.zstd_dictionary_id,
+        );
+        let file_entry_key_path = self.get_file_entry_key_path(start_version);
+        log_grpc_step(
+            "file_worker",
+            IndexerGrpcStep::FileStoreEncodedTxns,
+            Some(start_version as i64),
+            Some((start_version + FILE_ENTRY_TRANSACTION_COUNT - 1) as i64),
+            None,
+            None,
+            Some(start_time.elapsed().as_secs_f64()),
+            None,
+            Some(FILE_ENTRY_TRANSACTION_COUNT as i64),
+            None,
+        );
+        self.put_object(&file_entry_key_path, file_entry.into_inner())
+            .await?;
+        Ok((start_version, end_version))
+    }
+
+    fn clone_box(&self) -> Box<dyn FileStoreOperator> {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::{
+        service::{make_service_fn, service_fn},
+        Body, Method, Request, Response, Server, StatusCode,
+    };
+    use std::{
+        collections::{BTreeMap, HashMap},
+        convert::Infallible,
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Mutex,
+        },
+    };
+
+    fn create_operator(
+        bucket_sub_dir: Option<PathBuf>,
+        endpoint: Option<String>,
+        multipart_part_size_bytes: usize,
+    ) -> S3FileStoreOperator {
+        S3FileStoreOperator::new(
+            "indexer-grpc-test".to_string(),
+            bucket_sub_dir,
+            S3ClientConfig {
+                region: "us-east-1".to_string(),
+                endpoint,
+                force_path_style: true,
+                max_attempts: 3,
+                multipart_part_size_bytes,
+            },
+            false,
+            CompressionAlgorithm::default(),
+        )
+    }
+
+    #[test]
+    fn test_key_paths() {
+        let operator = create_operator(None, None, S3_MIN_MULTIPART_PART_SIZE_BYTES);
+        assert_eq!(operator.metadata_file_path, "metadata.json");
+        assert_eq!(
+            operator.get_file_entry_key_path(1000),
+            FileEntry::build_key(1000, StorageFormat::JsonBase64UncompressedProto)
+        );
+
+        let operator = create_operator(
+            Some(PathBuf::from("mainnet/")),
+            None,
+            S3_MIN_MULTIPART_PART_SIZE_BYTES,
+        );
+        assert_eq!(operator.metadata_file_path, "mainnet/metadata.json");
+        assert_eq!(
+            operator.get_file_entry_key_path(1000),
+            format!(
+                "mainnet/{}",
+                FileEntry::build_key(1000, StorageFormat::JsonBase64UncompressedProto)
+            )
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "The multipart part size has to be at least")]
+    fn test_multipart_part_size_too_small() {
+        create_operator(None, None, 1024);
+    }
+
+    /// An in-process mock of the subset of the S3 API used by the operator, with path style
+    /// addressing: `HEAD /bucket`, `GET` and `PUT /bucket/key`, and multipart uploads.
+    #[derive(Default)]
+    struct MockS3 {
+        objects: Mutex<HashMap<String, Vec<u8>>>,
+        uploads: Mutex<HashMap<String, BTreeMap<u32, Vec<u8>>>>,
+        next_upload_id: AtomicU64,
+        num_multipart_uploads: AtomicU64,
+    }
+
+    impl MockS3 {
+        /// Starts serving on an ephemeral port and returns the endpoint.
+        fn start(self: &Arc<Self>) -> String {
+            let mock = self.clone();
+            let make_svc = make_service_fn(move |_| {
+                let mock = mock.clone();
+                async move { Ok::<_, Infallible>(service_fn(move |req| mock.clone().handle(req))) }
+            });
+            let server = Server::bind(&"127.0.0.1:0".parse().unwrap()).serve(make_svc);
+            let endpoint = format!("http://{}", server.local_addr());
+            tokio::spawn(server);
+            endpoint
+        }
+
+        async fn handle(self: Arc<Self>, req: Request<Body>) -> Result<Response<Body>, Infallible> {
+            let method = req.method().clone();
+            let path = req.uri().path().trim_start_matches('/').to_string();
+            let query: HashMap<String, String> =
+                url::form_urlencoded::parse(req.uri().query().unwrap_or("").as_bytes())
+                    .into_owned()
+                    .collect();
+            let aws_chunked = req
+                .headers()
+                .get("content-encoding")
+                .map_or(false, |encoding| {
+                    encoding.to_str().unwrap_or("").contains("aws-chunked")
+                });
+            let body = hyper::body::to_bytes(req.into_body())
+                .await
+                .unwrap()
+                .to_vec();
+            let body = if aws_chunked {
+                decode_aws_chunked(&body)
+            } else {
+                body
+            };
+
+            // Requests for the bucket itself, i.e., `HeadBucket`.
+            let Some((_bucket, key)) = path.split_once('/') else {
+                return Ok(Response::new(Body::empty()));
+            };
+            let response = match (method, query.get("uploadId")) {
+                (Method::GET, _) | (Method::HEAD, _) => {
+                    match self.objects.lock().unwrap().get(key) {
+                        Some(object) => Response::new(Body::from(object.clone())),
+                        None => Response::builder()
+                            .status(StatusCode::NOT_FOUND)
+                            .body(Body::from(
+                                "<Error><Code>NoSuchKey</Code><Message>Not found</Message></Error>",
+                            ))
+                            .unwrap(),
+                    }
+                },
+                (Method::PUT, Some(upload_id)) => {
+                    let part_number: u32 = query["partNumber"].parse().unwrap();
+                    self.uploads
+                        .lock()
+                        .unwrap()
+                        .get_mut(upload_id)
+                        .unwrap()
+                        .insert(part_number, body);
+                    Response::builder()
+                        .header("ETag", format!("\"{}-{}\"", upload_id, part_number))
+                        .body(Body::empty())
+                        .unwrap()
+                },
+                (Method::PUT, None) => {
+                    self.objects.lock().unwrap().insert(key.to_string(), body);
+                    Response::new(Body::empty())
+                },
+                (Method::POST, None) => {
+                    assert!(query.contains_key("uploads"));
+                    let upload_id = self
+                        .next_upload_id
+                        .fetch_add(1, Ordering::SeqCst)
+                        .to_string();
+                    self.uploads
+                        .lock()
+                        .unwrap()
+                        .insert(upload_id.clone(), BTreeMap::new());
+                    Response::new(Body::from(format!(
+                        "<InitiateMultipartUploadResult><Key>{}</Key><UploadId>{}</UploadId></InitiateMultipartUploadResult>",
+                        key, upload_id
+                    )))
+                },
+                (Method::POST, Some(upload_id)) => {
+                    let parts = self.uploads.lock().unwrap().remove(upload_id).unwrap();
+                    self.objects
+                        .lock()
+                        .unwrap()
+                        .insert(key.to_string(), parts.into_values().flatten().collect());
+                    self.num_multipart_uploads.fetch_add(1, Ordering::SeqCst);
+                    Response::new(Body::from(format!(
+                        "<CompleteMultipartUploadResult><Key>{}</Key></CompleteMultipartUploadResult>",
+                        key
+                    )))
+                },
+                (Method::DELETE, Some(upload_id)) => {
+                    self.uploads.lock().unwrap().remove(upload_id);
+                    Response::builder()
+                        .status(StatusCode::NO_CONTENT)
+                        .body(Body::empty())
+                        .unwrap()
+                },
+                _ => Response::builder()
+                    .status(StatusCode::NOT_IMPLEMENTED)
+                    .body(Body::empty())
+                    .unwrap(),
+            };
+            Ok(response)
+        }
+    }
+
+    /// Decodes a body sent with `Content-Encoding: aws-chunked`, i.e., chunks of the form
+    /// `<hex length>[;extensions]\r\n<data>\r\n`, terminated by an empty chunk and trailers.
+    fn decode_aws_chunked(mut body: &[u8]) -> Vec<u8> {
+        let mut decoded = vec![];
+        loop {
+            let header_end = body.windows(2).position(|w| w == b"\r\n").unwrap();
+            let header = std::str::from_utf8(&body[..header_end]).unwrap();
+            let length = usize::from_str_radix(header.split(';').next().unwrap(), 16).unwrap();
+            if length == 0 {
+                return decoded;
+            }
+            let data_start = header_end + 2;
+            decoded.extend_from_slice(&body[data_start..data_start + length]);
+            body = &body[data_start + length + 2..];
+        }
+    }
+
+    #[tokio::test]
+    async fn test_s3_round_trip() {
+        std::env::set_var("AWS_ACCESS_KEY_ID", "test");
+        std::env::set_var("AWS_SECRET_ACCESS_KEY", "test");
+        let mock = Arc::new(MockS3::default());
+        let mut operator = create_operator(
+            Some(PathBuf::from("test")),
+            Some(mock.start()),
+            S3_MIN_MULTIPART_PART_SIZE_BYTES,
+        );
+        operator.verify_storage_bucket_existence().await;
+        assert!(operator.get_file_store_metadata().await.is_none());
+        assert!(operator.get_raw_file(0).await.is_err());
+
+        // Upload a batch that is large enough to require a multipart upload.
+        let transactions: Vec<Transaction> = (0..FILE_ENTRY_TRANSACTION_COUNT)
+            .map(|version| Transaction {
+                version,
+                info: Some(aptos_protos::transaction::v1::TransactionInfo {
+                    hash: vec![version as u8; 16 * 1024],
+                    ..Default::default()
+                }),
+                ..Default::default()
+            })
+            .collect();
+        let (start_version, end_version) = operator
+            .upload_transaction_batch(1, transactions.clone())
+            .await
+            .unwrap();
+        assert_eq!(start_version, 0);
+        assert_eq!(end_version, FILE_ENTRY_TRANSACTION_COUNT - 1);
+        assert_eq!(mock.num_multipart_uploads.load(Ordering::SeqCst), 1);
+        assert!(mock.uploads.lock().unwrap().is_empty());
+        assert!(mock
+            .objects
+            .lock()
+            .unwrap()
+            .contains_key(&operator.get_file_entry_key_path(0)));
+        assert_eq!(operator.get_transactions(0, 0).await.unwrap(), transactions);
+
+        // The metadata is small enough for a single request.
+        operator
+            .update_file_store_metadata_internal(1, FILE_ENTRY_TRANSACTION_COUNT)
+            .await
+            .unwrap();
+        assert_eq!(mock.num_multipart_uploads.load(Ordering::SeqCst), 1);
+        assert!(mock
+            .objects
+            .lock()
+            .unwrap()
+            .contains_key("test/metadata.json"));
+        assert_eq!(
+            operator.get_latest_version().await,
+            Some(FILE_ENTRY_TRANSACTION_COUNT)
+        );
+    }
+}
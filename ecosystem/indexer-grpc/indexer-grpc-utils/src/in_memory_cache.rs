@@ -362,14 +362,13 @@ where
                     values.len()
                 )));
             }
-            let transactions = values
+            values
                 .into_iter()
                 .map(|v| {
                     let cache_entry = CacheEntry::new(v, storage_format);
                     cache_entry.into_transaction()
                 })
-                .collect();
-            Ok(transactions)
+                .collect()
         }));
     }
     let task_count = tasks.len();
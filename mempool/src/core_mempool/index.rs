@@ -7,7 +7,9 @@ use crate::core_mempool::transaction::{MempoolTransaction, SequenceInfo, Timelin
 use crate::{
     counters,
     logging::{LogEntry, LogSchema},
-    shared_mempool::types::{MultiBucketTimelineIndexIds, TimelineIndexIdentifier},
+    shared_mempool::types::{
+        MempoolFeeBucketStats, MultiBucketTimelineIndexIds, TimelineIndexIdentifier,
+    },
 };
 use aptos_consensus_types::common::TransactionSummary;
 use aptos_crypto::HashValue;
@@ -409,6 +411,65 @@ impl MultiBucketTimelineIndex {
     }
 }
 
+/// FeeBucketStatsIndex keeps track of the number of transactions (and parked transactions)
+/// in each fee bucket. The counts are updated incrementally, so that mempool stats can be
+/// generated without iterating over all transactions in Mempool.
+pub struct FeeBucketStatsIndex {
+    fee_buckets: Vec<MempoolFeeBucketStats>,
+}
+
+impl FeeBucketStatsIndex {
+    pub(crate) fn new(bucket_mins: &[u64]) -> Self {
+        let fee_buckets = bucket_mins
+            .iter()
+            .map(|min_ranking_score| MempoolFeeBucketStats {
+                min_ranking_score: *min_ranking_score,
+                ..Default::default()
+            })
+            .collect();
+        Self { fee_buckets }
+    }
+
+    pub(crate) fn insert(&mut self, txn: &MempoolTransaction) {
+        if let Some(fee_bucket) = self.get_fee_bucket(txn.ranking_score) {
+            fee_bucket.num_transactions += 1;
+        }
+    }
+
+    pub(crate) fn remove(&mut self, txn: &MempoolTransaction) {
+        if let Some(fee_bucket) = self.get_fee_bucket(txn.ranking_score) {
+            fee_bucket.num_transactions = fee_bucket.num_transactions.saturating_sub(1);
+        }
+    }
+
+    pub(crate) fn park(&mut self, txn: &MempoolTransaction) {
+        if let Some(fee_bucket) = self.get_fee_bucket(txn.ranking_score) {
+            fee_bucket.num_parked_transactions += 1;
+        }
+    }
+
+    pub(crate) fn unpark(&mut self, txn: &MempoolTransaction) {
+        if let Some(fee_bucket) = self.get_fee_bucket(txn.ranking_score) {
+            fee_bucket.num_parked_transactions =
+                fee_bucket.num_parked_transactions.saturating_sub(1);
+        }
+    }
+
+    pub(crate) fn fee_buckets(&self) -> &[MempoolFeeBucketStats] {
+        &self.fee_buckets
+    }
+
+    /// Returns the fee bucket for the given ranking score (i.e., the
+    /// bucket with the highest minimum ranking score not above it).
+    fn get_fee_bucket(&mut self, ranking_score: u64) -> Option<&mut MempoolFeeBucketStats> {
+        let index = self
+            .fee_buckets
+            .partition_point(|fee_bucket| fee_bucket.min_ranking_score <= ranking_score)
+            .checked_sub(1)?;
+        self.fee_buckets.get_mut(index)
+    }
+}
+
 /// ParkingLotIndex keeps track of "not_ready" transactions, e.g., transactions that
 /// can't be included in the next block because their sequence number is too high.
 /// We keep a separate index to be able to efficiently evict them when Mempool is full.
@@ -430,7 +491,9 @@ impl ParkingLotIndex {
         }
     }
 
-    pub(crate) fn insert(&mut self, txn: &mut MempoolTransaction) {
+    /// Inserts the transaction into the parking lot. Returns true iff
+    /// the transaction was not already parked.
+    pub(crate) fn insert(&mut self, txn: &mut MempoolTransaction) -> bool {
         if txn.insertion_info.park_time.is_none() {
             txn.insertion_info.park_time = Some(SystemTime::now());
         }
@@ -450,7 +513,7 @@ impl ParkingLotIndex {
                         "Parking lot invariant violated: for account {}, account index exists but missing entry in data",
                         sender
                     );
-                    return;
+                    return false;
                 }
             },
             None => {
@@ -466,14 +529,19 @@ impl ParkingLotIndex {
         if is_new_entry {
             self.size += 1;
         }
+        is_new_entry
     }
 
-    pub(crate) fn remove(&mut self, txn: &MempoolTransaction) {
+    /// Removes the transaction from the parking lot. Returns true iff
+    /// the transaction was parked.
+    pub(crate) fn remove(&mut self, txn: &MempoolTransaction) -> bool {
         let sender = &txn.txn.sender();
+        let mut is_removed = false;
         if let Some(index) = self.account_indices.get(sender).cloned() {
             if let Some((_account, txns)) = self.data.get_mut(index) {
                 if txns.remove(&(txn.txn.sequence_number(), txn.get_committed_hash())) {
                     self.size -= 1;
+                    is_removed = true;
                 }
 
                 // maintain DS invariant
@@ -489,6 +557,7 @@ impl ParkingLotIndex {
                 }
             }
         }
+        is_removed
     }
 
     pub(crate) fn contains(&self, account: &AccountAddress, seq_num: u64, hash: HashValue) -> bool {
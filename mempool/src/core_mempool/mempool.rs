@@ -14,7 +14,8 @@ use crate::{
     logging::{LogEntry, LogSchema, TxnsLog},
     network::BroadcastPeerPriority,
     shared_mempool::types::{
-        MempoolSenderBucket, MultiBucketTimelineIndexIds, TimelineIndexIdentifier,
+        AccountPendingTransactions, MempoolSenderBucket, MempoolStats, MultiBucketTimelineIndexIds,
+        TimelineIndexIdentifier,
     },
 };
 use aptos_config::config::NodeConfig;
@@ -588,6 +589,19 @@ impl Mempool {
         self.transactions.gen_snapshot()
     }
 
+    /// Returns all pending transactions in mempool for the given account
+    pub(crate) fn get_account_transactions(
+        &self,
+        address: &AccountAddress,
+    ) -> AccountPendingTransactions {
+        self.transactions.get_account_transactions(address)
+    }
+
+    /// Returns the stats of all transactions in mempool, grouped by fee bucket
+    pub(crate) fn gen_stats(&self) -> MempoolStats {
+        self.transactions.gen_stats()
+    }
+
     #[cfg(test)]
     pub fn get_parking_lot_size(&self) -> usize {
         self.transactions.get_parking_lot_size()
@@ -5,8 +5,8 @@
 use crate::{
     core_mempool::{
         index::{
-            AccountTransactions, MultiBucketTimelineIndex, ParkingLotIndex, PriorityIndex,
-            PriorityQueueIter, TTLIndex,
+            AccountTransactions, FeeBucketStatsIndex, MultiBucketTimelineIndex, ParkingLotIndex,
+            PriorityIndex, PriorityQueueIter, TTLIndex,
         },
         mempool::Mempool,
         transaction::{InsertionInfo, MempoolTransaction, TimelineState},
@@ -15,7 +15,8 @@ use crate::{
     logging::{LogEntry, LogEvent, LogSchema, TxnsLog},
     network::BroadcastPeerPriority,
     shared_mempool::types::{
-        MempoolSenderBucket, MultiBucketTimelineIndexIds, TimelineIndexIdentifier,
+        AccountPendingTransactions, MempoolSenderBucket, MempoolStats, MultiBucketTimelineIndexIds,
+        PendingTransactionInfo, PendingTransactionStatus, TimelineIndexIdentifier,
     },
 };
 use aptos_config::config::MempoolConfig;
@@ -71,6 +72,8 @@ pub struct TransactionStore {
     num_sender_buckets: MempoolSenderBucket,
     // keeps track of "non-ready" txns (transactions that can't be included in next block)
     parking_lot_index: ParkingLotIndex,
+    // Index for tracking the number of (parked) transactions in each fee bucket.
+    fee_bucket_stats_index: FeeBucketStatsIndex,
     // Index for looking up transaction by hash.
     // Transactions are stored by AccountAddress + sequence number.
     // This index stores map of transaction committed hash to (AccountAddress, sequence number) pair.
@@ -114,6 +117,7 @@ impl TransactionStore {
             timeline_index,
             num_sender_buckets: config.num_sender_buckets,
             parking_lot_index: ParkingLotIndex::new(),
+            fee_bucket_stats_index: FeeBucketStatsIndex::new(&config.broadcast_buckets),
             hash_index: HashMap::new(),
             // estimated size in bytes
             size_bytes: 0,
@@ -299,6 +303,7 @@ impl TransactionStore {
                 .insert(txn.get_committed_hash(), (txn.get_sender(), txn_seq_num));
             self.sequence_numbers.insert(txn.get_sender(), acc_seq_num);
             self.size_bytes += txn.get_estimated_bytes();
+            self.fee_bucket_stats_index.insert(&txn);
             txns.insert(txn_seq_num, txn);
             self.track_indices();
         }
@@ -505,7 +510,9 @@ impl TransactionStore {
 
                 // Remove txn from parking lot after it has been promoted to
                 // priority_index / timeline_index, i.e., txn status is ready.
-                self.parking_lot_index.remove(txn);
+                if self.parking_lot_index.remove(txn) {
+                    self.fee_bucket_stats_index.unpark(txn);
+                }
                 min_seq += 1;
             }
 
@@ -514,7 +521,9 @@ impl TransactionStore {
                 match txn.timeline_state {
                     TimelineState::Ready(_) => {},
                     _ => {
-                        self.parking_lot_index.insert(txn);
+                        if self.parking_lot_index.insert(txn) {
+                            self.fee_bucket_stats_index.park(txn);
+                        }
                         parking_lot_txns += 1;
                     },
                 }
@@ -617,7 +626,10 @@ impl TransactionStore {
                 )
             })
             .remove(txn);
-        self.parking_lot_index.remove(txn);
+        if self.parking_lot_index.remove(txn) {
+            self.fee_bucket_stats_index.unpark(txn);
+        }
+        self.fee_bucket_stats_index.remove(txn);
         self.hash_index.remove(&txn.get_committed_hash());
         self.size_bytes -= txn.get_estimated_bytes();
 
@@ -814,7 +826,9 @@ impl TransactionStore {
                     });
                 // mark all following txns as non-ready, i.e. park them
                 for (_, t) in txns.range_mut((park_range_start, park_range_end)) {
-                    self.parking_lot_index.insert(t);
+                    if self.parking_lot_index.insert(t) {
+                        self.fee_bucket_stats_index.park(t);
+                    }
                     self.priority_index.remove(t);
                     let sender_bucket = sender_bucket(&t.get_sender(), self.num_sender_buckets);
                     self.timeline_index
@@ -890,6 +904,63 @@ impl TransactionStore {
         txns_log
     }
 
+    /// Returns all transactions in the store for the given account (ordered by sequence number)
+    pub(crate) fn get_account_transactions(
+        &self,
+        address: &AccountAddress,
+    ) -> AccountPendingTransactions {
+        let transactions = self
+            .transactions
+            .get(address)
+            .map(|txns| {
+                txns.iter()
+                    .map(|(seq_num, txn)| PendingTransactionInfo {
+                        transaction: txn.txn.clone(),
+                        status: self.get_pending_status(address, *seq_num, txn),
+                        insertion_time: txn.insertion_info.insertion_time,
+                        ranking_score: txn.ranking_score,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        AccountPendingTransactions {
+            account_sequence_number: self.sequence_numbers.get(address).copied(),
+            transactions,
+        }
+    }
+
+    /// Returns the stats of all transactions in the store, grouped by fee bucket.
+    /// Note: the stats are tracked incrementally, so this doesn't iterate over the store.
+    pub(crate) fn gen_stats(&self) -> MempoolStats {
+        let fee_buckets = self.fee_bucket_stats_index.fee_buckets().to_vec();
+        MempoolStats {
+            num_transactions: self.system_ttl_index.size() as u64,
+            num_parked_transactions: self.parking_lot_index.size() as u64,
+            size_bytes: self.size_bytes as u64,
+            capacity: self.capacity as u64,
+            capacity_bytes: self.capacity_bytes as u64,
+            fee_buckets,
+        }
+    }
+
+    /// Returns the pending status (i.e., ready or parked) of the given transaction
+    fn get_pending_status(
+        &self,
+        account: &AccountAddress,
+        seq_num: u64,
+        txn: &MempoolTransaction,
+    ) -> PendingTransactionStatus {
+        if self
+            .parking_lot_index
+            .contains(account, seq_num, txn.get_committed_hash())
+        {
+            PendingTransactionStatus::Parked
+        } else {
+            PendingTransactionStatus::Ready
+        }
+    }
+
     #[cfg(test)]
     pub(crate) fn get_parking_lot_size(&self) -> usize {
         self.parking_lot_index.size()
@@ -67,6 +67,8 @@ pub const SUCCESS_LABEL: &str = "success";
 // Bounded executor task labels
 pub const CLIENT_EVENT_LABEL: &str = "client_event";
 pub const CLIENT_EVENT_GET_TXN_LABEL: &str = "client_event_get_txn";
+pub const CLIENT_EVENT_GET_ACCOUNT_TXNS_LABEL: &str = "client_event_get_account_txns";
+pub const CLIENT_EVENT_GET_STATS_LABEL: &str = "client_event_get_stats";
 pub const RECONFIG_EVENT_LABEL: &str = "reconfig";
 pub const PEER_BROADCAST_EVENT_LABEL: &str = "peer_broadcast";
 
@@ -74,6 +76,15 @@ pub const PEER_BROADCAST_EVENT_LABEL: &str = "peer_broadcast";
 pub const SPAWN_LABEL: &str = "spawn";
 pub const START_LABEL: &str = "start";
 
+// Mempool journal operation labels
+pub const JOURNAL_APPEND_LABEL: &str = "append";
+pub const JOURNAL_APPEND_DROPPED_LABEL: &str = "append_dropped";
+pub const JOURNAL_COMPACTION_LABEL: &str = "compaction";
+pub const JOURNAL_ERROR_LABEL: &str = "error";
+pub const JOURNAL_REPLAYED_LABEL: &str = "replayed";
+pub const JOURNAL_REPLAY_EXPIRED_LABEL: &str = "replay_expired";
+pub const JOURNAL_TRUNCATION_LABEL: &str = "truncation";
+
 // Mempool network msg failure type labels:
 pub const BROADCAST_TXNS: &str = "broadcast_txns";
 pub const ACK_TXNS: &str = "ack_txns";
@@ -668,6 +679,35 @@ pub fn active_upstream_peers(network_id: &NetworkId) -> IntGauge {
     ACTIVE_UPSTREAM_PEERS_COUNT.with_label_values(&[network_id.as_str()])
 }
 
+/// Counter for the operations performed on the mempool journal
+static MEMPOOL_JOURNAL_OPERATIONS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_mempool_journal_operations",
+        "Number of operations performed on the mempool journal, by type",
+        &["operation"]
+    )
+    .unwrap()
+});
+
+pub fn mempool_journal_operation_inc(operation: &'static str) {
+    mempool_journal_operation_inc_by(operation, 1);
+}
+
+pub fn mempool_journal_operation_inc_by(operation: &'static str, count: u64) {
+    MEMPOOL_JOURNAL_OPERATIONS
+        .with_label_values(&[operation])
+        .inc_by(count);
+}
+
+/// Gauge for the current size (in bytes) of the mempool journal
+pub static MEMPOOL_JOURNAL_SIZE_BYTES: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "aptos_mempool_journal_size_bytes",
+        "Current size (in bytes) of the mempool journal"
+    )
+    .unwrap()
+});
+
 /// Duration of each run of the event loop.
 pub static MAIN_LOOP: Lazy<DurationHistogram> = Lazy::new(|| {
     DurationHistogram::new(
@@ -156,6 +156,8 @@ pub enum LogEntry {
     ReconfigUpdate,
     JsonRpc,
     GetTransaction,
+    GetAccountTransactions,
+    GetMempoolStats,
     GetBlock,
     QuorumStore,
     StateSyncCommit,
@@ -173,6 +175,7 @@ pub enum LogEntry {
     DBError,
     UnexpectedNetworkMsg,
     MempoolSnapshot,
+    MempoolJournal,
 }
 
 #[derive(Clone, Copy, Serialize)]
@@ -10,6 +10,7 @@ use crate::{
     logging::{LogEntry, LogEvent, LogSchema},
     network::{BroadcastPeerPriority, MempoolSyncMsg},
     shared_mempool::{
+        journal::MempoolJournalWriter,
         tasks::{self, process_committed_transactions},
         types::{
             notify_subscribers, MempoolMessageId, ScheduledBroadcast, SharedMempool,
@@ -50,7 +51,10 @@ use std::{
     },
     time::{Duration, Instant, SystemTime},
 };
-use tokio::{runtime::Handle, time::interval};
+use tokio::{
+    runtime::Handle,
+    time::{interval, interval_at},
+};
 use tokio_stream::wrappers::IntervalStream;
 
 /// Coordinator that handles inbound network events and outbound txn broadcasts.
@@ -104,6 +108,15 @@ pub(crate) async fn coordinator<NetworkClient, TransactionValidator, ConfigProvi
     )
     .await;
 
+    // Replay the mempool journal (if enabled) and start compacting it periodically
+    if let Some(journal) = smp.journal.clone() {
+        tasks::replay_mempool_journal(&smp, &journal).await;
+        executor.spawn(journal_compaction_job(
+            journal,
+            smp.config.journal_compaction_interval_secs,
+        ));
+    }
+
     loop {
         let _timer = counters::MAIN_LOOP.start_timer();
         ::futures::select! {
@@ -145,6 +158,7 @@ fn spawn_commit_notification_handler<NetworkClient, TransactionValidator>(
     let mempool = smp.mempool.clone();
     let mempool_validator = smp.validator.clone();
     let use_case_history = smp.use_case_history.clone();
+    let journal = smp.journal.clone();
     let num_committed_txns_recieved_since_peers_updated = smp
         .network_interface
         .num_committed_txns_received_since_peers_updated
@@ -156,6 +170,7 @@ fn spawn_commit_notification_handler<NetworkClient, TransactionValidator>(
                 &mempool,
                 &mempool_validator,
                 &use_case_history,
+                &journal,
                 commit_notification,
                 &num_committed_txns_recieved_since_peers_updated,
             );
@@ -217,6 +232,47 @@ async fn handle_client_request<NetworkClient, TransactionValidator>(
                 ))
                 .await;
         },
+        MempoolClientRequest::GetAccountTransactions(address, callback) => {
+            // This timer measures how long it took for the bounded executor to *schedule* the
+            // task.
+            let _timer = counters::task_spawn_latency_timer(
+                counters::CLIENT_EVENT_GET_ACCOUNT_TXNS_LABEL,
+                counters::SPAWN_LABEL,
+            );
+            // This timer measures how long it took for the task to go from scheduled to started.
+            let task_start_timer = counters::task_spawn_latency_timer(
+                counters::CLIENT_EVENT_GET_ACCOUNT_TXNS_LABEL,
+                counters::START_LABEL,
+            );
+            bounded_executor
+                .spawn(tasks::process_client_get_account_transactions(
+                    smp.clone(),
+                    address,
+                    callback,
+                    task_start_timer,
+                ))
+                .await;
+        },
+        MempoolClientRequest::GetMempoolStats(callback) => {
+            // This timer measures how long it took for the bounded executor to *schedule* the
+            // task.
+            let _timer = counters::task_spawn_latency_timer(
+                counters::CLIENT_EVENT_GET_STATS_LABEL,
+                counters::SPAWN_LABEL,
+            );
+            // This timer measures how long it took for the task to go from scheduled to started.
+            let task_start_timer = counters::task_spawn_latency_timer(
+                counters::CLIENT_EVENT_GET_STATS_LABEL,
+                counters::START_LABEL,
+            );
+            bounded_executor
+                .spawn(tasks::process_client_get_mempool_stats(
+                    smp.clone(),
+                    callback,
+                    task_start_timer,
+                ))
+                .await;
+        },
     }
 }
 
@@ -226,6 +282,7 @@ fn handle_commit_notification<TransactionValidator>(
     mempool: &Arc<Mutex<CoreMempool>>,
     mempool_validator: &Arc<RwLock<TransactionValidator>>,
     use_case_history: &Arc<Mutex<UseCaseHistory>>,
+    journal: &Option<MempoolJournalWriter>,
     msg: MempoolCommitNotification,
     num_committed_txns_recieved_since_peers_updated: &Arc<AtomicU64>,
 ) where
@@ -245,6 +302,14 @@ fn handle_commit_notification<TransactionValidator>(
     );
     num_committed_txns_recieved_since_peers_updated
         .fetch_add(msg.transactions.len() as u64, Ordering::Relaxed);
+    if let Some(journal) = journal {
+        journal.notify_committed(
+            msg.transactions
+                .iter()
+                .map(|transaction| (transaction.sender, transaction.sequence_number))
+                .collect(),
+        );
+    }
     process_committed_transactions(
         mempool,
         use_case_history,
@@ -465,3 +530,18 @@ pub(crate) async fn snapshot_job(mempool: Arc<Mutex<CoreMempool>>, snapshot_inte
         trace!(LogSchema::new(LogEntry::MempoolSnapshot).txns(snapshot));
     }
 }
+
+/// Periodically compacts the mempool journal to drop committed and expired transactions
+pub(crate) async fn journal_compaction_job(
+    journal: MempoolJournalWriter,
+    compaction_interval_secs: u64,
+) {
+    let compaction_interval = Duration::from_secs(compaction_interval_secs);
+    let mut interval = IntervalStream::new(interval_at(
+        tokio::time::Instant::now() + compaction_interval,
+        compaction_interval,
+    ));
+    while let Some(_interval) = interval.next().await {
+        journal.compact();
+    }
+}
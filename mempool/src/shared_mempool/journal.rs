@@ -0,0 +1,454 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! An on-disk journal of the transactions accepted into mempool. The journal is
+//! replayed (and the transactions revalidated) when the node restarts, so that
+//! pending transactions are not lost across restarts.
+//!
+//! The journal is a simple append-only file of length-prefixed, BCS-encoded
+//! entries. It is periodically compacted (i.e., rewritten from its own contents)
+//! to drop expired, committed and superseded transactions, and it is bounded in
+//! size: once the size limit is hit, new entries are dropped until the next
+//! compaction frees up space.
+//!
+//! All journal I/O is performed by a dedicated writer task (on the blocking
+//! thread pool), so mempool never holds its lock or blocks the async executor
+//! while the journal is written.
+
+use crate::{
+    counters,
+    logging::{LogEntry, LogSchema},
+};
+use anyhow::{anyhow, Result};
+use aptos_infallible::Mutex;
+use aptos_logger::prelude::*;
+use aptos_types::{account_address::AccountAddress, transaction::SignedTransaction};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    fs::{self, File, OpenOptions},
+    io::{BufReader, BufWriter, ErrorKind, Read, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+use tokio::{
+    runtime::Handle,
+    sync::{mpsc, oneshot},
+};
+
+/// The number of bytes used to encode the length of each journal entry
+const ENTRY_LENGTH_BYTES: u64 = 4;
+
+/// The maximum number of pending requests for the journal writer
+const JOURNAL_REQUEST_CHANNEL_SIZE: usize = 1_024;
+
+/// A single transaction persisted to the journal
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct JournalEntry {
+    pub insertion_time_usecs: u64, // The time (since the epoch) the txn was accepted
+    pub transaction: SignedTransaction,
+}
+
+impl JournalEntry {
+    pub fn new(insertion_time: Duration, transaction: SignedTransaction) -> Self {
+        Self {
+            insertion_time_usecs: insertion_time.as_micros() as u64,
+            transaction,
+        }
+    }
+
+    /// Returns true iff the entry has expired at the given time. An entry
+    /// expires when either the transaction expiration time, or the system
+    /// TTL (measured from the original insertion time), has passed.
+    pub fn is_expired(&self, now: Duration, system_transaction_timeout: Duration) -> bool {
+        let expiration_time = Duration::from_secs(self.transaction.expiration_timestamp_secs());
+        let system_expiration_time = Duration::from_micros(self.insertion_time_usecs)
+            .saturating_add(system_transaction_timeout);
+        expiration_time <= now || system_expiration_time <= now
+    }
+}
+
+/// The file (and current size) of the journal
+struct JournalFile {
+    file: BufWriter<File>,
+    size_bytes: u64,
+}
+
+/// The on-disk mempool journal. Note: all operations perform blocking file
+/// I/O, so the journal should only be accessed via the `MempoolJournalWriter`.
+pub struct MempoolJournal {
+    journal_path: PathBuf,
+    max_size_bytes: u64,
+    journal_file: Mutex<JournalFile>,
+}
+
+impl MempoolJournal {
+    /// Opens (or creates) the journal at the given path
+    pub fn open(journal_path: &Path, max_size_bytes: u64) -> Result<Self> {
+        if let Some(parent) = journal_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let journal_file = open_journal_file(journal_path, max_size_bytes)?;
+        counters::MEMPOOL_JOURNAL_SIZE_BYTES.set(journal_file.size_bytes as i64);
+
+        Ok(Self {
+            journal_path: journal_path.to_path_buf(),
+            max_size_bytes,
+            journal_file: Mutex::new(journal_file),
+        })
+    }
+
+    /// Appends the given entries to the journal. Entries that would
+    /// push the journal over its size limit are dropped.
+    pub fn append(&self, entries: &[JournalEntry]) -> Result<()> {
+        if entries.is_empty() {
+            return Ok(());
+        }
+
+        let mut journal_file = self.journal_file.lock();
+        for entry in entries {
+            let entry_bytes = bcs::to_bytes(entry)?;
+            let entry_size = ENTRY_LENGTH_BYTES + entry_bytes.len() as u64;
+            if journal_file.size_bytes + entry_size > self.max_size_bytes {
+                counters::mempool_journal_operation_inc(counters::JOURNAL_APPEND_DROPPED_LABEL);
+                continue;
+            }
+
+            write_entry(&mut journal_file.file, &entry_bytes)?;
+            journal_file.size_bytes += entry_size;
+            counters::mempool_journal_operation_inc(counters::JOURNAL_APPEND_LABEL);
+        }
+        journal_file.file.flush()?;
+        counters::MEMPOOL_JOURNAL_SIZE_BYTES.set(journal_file.size_bytes as i64);
+
+        Ok(())
+    }
+
+    /// Reads all entries from the journal. If the journal ends with a
+    /// partially written (or corrupt) entry, e.g., because the node crashed
+    /// mid-write, the entries read up to that point are returned.
+    pub fn read_entries(&self) -> Result<Vec<JournalEntry>> {
+        // Hold the file lock to ensure we don't read partial appends
+        let mut journal_file = self.journal_file.lock();
+        journal_file.file.flush()?;
+        let (entries, _) = read_journal_entries(&self.journal_path, self.max_size_bytes)?;
+        Ok(entries)
+    }
+
+    /// Rewrites the journal to drop all entries that have expired at the given
+    /// time, that have been committed (i.e., their sequence numbers are below
+    /// the given committed sequence numbers), or that have been superseded by
+    /// a later entry with the same sender and sequence number. Returns the
+    /// number of entries remaining in the journal.
+    pub fn compact(
+        &self,
+        now: Duration,
+        system_transaction_timeout: Duration,
+        committed_sequence_numbers: &HashMap<AccountAddress, u64>,
+    ) -> Result<usize> {
+        let mut journal_file = self.journal_file.lock();
+        journal_file.file.flush()?;
+        let (entries, _) = read_journal_entries(&self.journal_path, self.max_size_bytes)?;
+
+        // Only keep the latest unexpired and uncommitted entry for each transaction
+        let mut transaction_keys = HashSet::new();
+        let mut entries: Vec<_> = entries
+            .into_iter()
+            .rev()
+            .filter(|entry| {
+                let sender = entry.transaction.sender();
+                let sequence_number = entry.transaction.sequence_number();
+                let is_committed = committed_sequence_numbers
+                    .get(&sender)
+                    .map_or(false, |committed_sequence_number| {
+                        sequence_number < *committed_sequence_number
+                    });
+                !is_committed
+                    && !entry.is_expired(now, system_transaction_timeout)
+                    && transaction_keys.insert((sender, sequence_number))
+            })
+            .collect();
+        entries.reverse();
+
+        // Write the entries to a temporary file
+        let temp_path = self.journal_path.with_extension("tmp");
+        let mut temp_file = BufWriter::new(File::create(&temp_path)?);
+        let mut size_bytes = 0;
+        let mut num_entries = 0;
+        for entry in entries.iter() {
+            let entry_bytes = bcs::to_bytes(entry)?;
+            let entry_size = ENTRY_LENGTH_BYTES + entry_bytes.len() as u64;
+            if size_bytes + entry_size > self.max_size_bytes {
+                counters::mempool_journal_operation_inc(counters::JOURNAL_APPEND_DROPPED_LABEL);
+                continue;
+            }
+
+            write_entry(&mut temp_file, &entry_bytes)?;
+            size_bytes += entry_size;
+            num_entries += 1;
+        }
+        temp_file.flush()?;
+        temp_file.get_ref().sync_all()?;
+        drop(temp_file);
+
+        // Atomically replace the journal and reopen the file
+        fs::rename(&temp_path, &self.journal_path)?;
+        *journal_file = open_journal_file(&self.journal_path, self.max_size_bytes)?;
+
+        counters::mempool_journal_operation_inc(counters::JOURNAL_COMPACTION_LABEL);
+        counters::MEMPOOL_JOURNAL_SIZE_BYTES.set(journal_file.size_bytes as i64);
+
+        Ok(num_entries)
+    }
+}
+
+/// A request for the journal writer task
+enum JournalRequest {
+    Append(Vec<JournalEntry>),          // Append the entries to the journal
+    Commit(Vec<(AccountAddress, u64)>), // Note the committed (sender, sequence number) pairs
+    Compact,                            // Compact the journal
+    Flush(oneshot::Sender<()>),         // Notify the sender once all prior requests are done
+}
+
+/// A handle to the journal writer task. All requests are queued (without
+/// blocking) and processed in order by the writer task, which performs the
+/// journal I/O on the blocking thread pool. If the queue is full, requests
+/// are dropped (the journal is best effort).
+#[derive(Clone)]
+pub struct MempoolJournalWriter {
+    journal: Arc<MempoolJournal>,
+    request_sender: mpsc::Sender<JournalRequest>,
+}
+
+impl MempoolJournalWriter {
+    /// Creates a new journal writer and spawns the writer task
+    pub fn new(
+        journal: Arc<MempoolJournal>,
+        system_transaction_timeout: Duration,
+        executor: &Handle,
+    ) -> Self {
+        let (request_sender, request_receiver) = mpsc::channel(JOURNAL_REQUEST_CHANNEL_SIZE);
+        executor.spawn(run_journal_writer(
+            journal.clone(),
+            request_receiver,
+            system_transaction_timeout,
+        ));
+
+        Self {
+            journal,
+            request_sender,
+        }
+    }
+
+    /// Queues the given entries to be appended to the journal
+    pub fn append(&self, entries: Vec<JournalEntry>) {
+        if entries.is_empty() {
+            return;
+        }
+
+        let num_entries = entries.len() as u64;
+        if self
+            .request_sender
+            .try_send(JournalRequest::Append(entries))
+            .is_err()
+        {
+            counters::mempool_journal_operation_inc_by(
+                counters::JOURNAL_APPEND_DROPPED_LABEL,
+                num_entries,
+            );
+        }
+    }
+
+    /// Notes the given committed transactions (i.e., sender and sequence
+    /// number pairs), so that they are dropped by the next compaction.
+    pub fn notify_committed(&self, committed_transactions: Vec<(AccountAddress, u64)>) {
+        if !committed_transactions.is_empty() {
+            self.send_request(JournalRequest::Commit(committed_transactions));
+        }
+    }
+
+    /// Queues a compaction of the journal
+    pub fn compact(&self) {
+        self.send_request(JournalRequest::Compact);
+    }
+
+    /// Waits until all previously queued requests have been processed
+    pub async fn flush(&self) {
+        let (callback, callback_receiver) = oneshot::channel();
+        if self
+            .request_sender
+            .send(JournalRequest::Flush(callback))
+            .await
+            .is_ok()
+        {
+            let _ = callback_receiver.await;
+        }
+    }
+
+    /// Reads all entries from the journal (after all previously
+    /// queued requests have been processed).
+    pub async fn read_entries(&self) -> Result<Vec<JournalEntry>> {
+        self.flush().await;
+        let journal = self.journal.clone();
+        run_blocking(move || journal.read_entries()).await
+    }
+
+    /// Sends the given request to the writer task (if the queue isn't full)
+    fn send_request(&self, request: JournalRequest) {
+        if let Err(error) = self.request_sender.try_send(request) {
+            counters::mempool_journal_operation_inc(counters::JOURNAL_ERROR_LABEL);
+            warn!(LogSchema::new(LogEntry::MempoolJournal)
+                .error(&anyhow!("Failed to send the journal request: {}", error)));
+        }
+    }
+}
+
+/// Processes the journal requests (in order) until all writers are dropped
+async fn run_journal_writer(
+    journal: Arc<MempoolJournal>,
+    mut request_receiver: mpsc::Receiver<JournalRequest>,
+    system_transaction_timeout: Duration,
+) {
+    // The committed sequence numbers (i.e., the next sequence number) for each
+    // sender, since the last compaction.
+    let mut committed_sequence_numbers: HashMap<AccountAddress, u64> = HashMap::new();
+
+    while let Some(request) = request_receiver.recv().await {
+        let result = match request {
+            JournalRequest::Append(entries) => {
+                let journal = journal.clone();
+                run_blocking(move || journal.append(&entries)).await
+            },
+            JournalRequest::Commit(committed_transactions) => {
+                for (sender, sequence_number) in committed_transactions {
+                    let next_sequence_number = sequence_number.saturating_add(1);
+                    committed_sequence_numbers
+                        .entry(sender)
+                        .and_modify(|committed_sequence_number| {
+                            *committed_sequence_number =
+                                (*committed_sequence_number).max(next_sequence_number)
+                        })
+                        .or_insert(next_sequence_number);
+                }
+                Ok(())
+            },
+            JournalRequest::Compact => {
+                let journal = journal.clone();
+                let committed_sequence_numbers = std::mem::take(&mut committed_sequence_numbers);
+                let now = aptos_infallible::duration_since_epoch();
+                run_blocking(move || {
+                    journal
+                        .compact(now, system_transaction_timeout, &committed_sequence_numbers)
+                        .map(|_| ())
+                })
+                .await
+            },
+            JournalRequest::Flush(callback) => {
+                let _ = callback.send(());
+                Ok(())
+            },
+        };
+
+        if let Err(error) = result {
+            counters::mempool_journal_operation_inc(counters::JOURNAL_ERROR_LABEL);
+            error!(LogSchema::new(LogEntry::MempoolJournal).error(&error));
+        }
+    }
+}
+
+/// Runs the given journal operation on the blocking thread pool
+async fn run_blocking<T, F>(operation: F) -> Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T> + Send + 'static,
+{
+    tokio::task::spawn_blocking(operation)
+        .await
+        .map_err(|error| anyhow!("The journal operation failed to complete: {:?}", error))?
+}
+
+/// Opens the journal file for appending. A partially written (or corrupt) tail
+/// is truncated first, as entries appended after it could never be read.
+fn open_journal_file(journal_path: &Path, max_size_bytes: u64) -> Result<JournalFile> {
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(journal_path)?;
+    let (_, size_bytes) = read_journal_entries(journal_path, max_size_bytes)?;
+    if file.metadata()?.len() > size_bytes {
+        counters::mempool_journal_operation_inc(counters::JOURNAL_TRUNCATION_LABEL);
+        file.set_len(size_bytes)?;
+        file.sync_all()?;
+    }
+    Ok(JournalFile {
+        file: BufWriter::new(file),
+        size_bytes,
+    })
+}
+
+/// Reads all complete entries from the journal file at the given path. Returns
+/// the entries, and the size (in bytes) of the journal up to the last of them.
+fn read_journal_entries(
+    journal_path: &Path,
+    max_size_bytes: u64,
+) -> Result<(Vec<JournalEntry>, u64)> {
+    let mut reader = BufReader::new(File::open(journal_path)?);
+    let mut entries = vec![];
+    let mut size_bytes = 0;
+    loop {
+        match read_entry(&mut reader, max_size_bytes) {
+            Ok(Some((entry, entry_size))) => {
+                entries.push(entry);
+                size_bytes += entry_size;
+            },
+            Ok(None) => break,
+            Err(error) => {
+                warn!(LogSchema::new(LogEntry::MempoolJournal)
+                    .error(&error)
+                    .num_txns(entries.len()));
+                break;
+            },
+        }
+    }
+
+    Ok((entries, size_bytes))
+}
+
+/// Writes a single length-prefixed entry
+fn write_entry<W: Write>(writer: &mut W, entry_bytes: &[u8]) -> Result<()> {
+    writer.write_all(&(entry_bytes.len() as u32).to_le_bytes())?;
+    writer.write_all(entry_bytes)?;
+    Ok(())
+}
+
+/// Reads a single length-prefixed entry, and returns it with its size (in bytes).
+/// Returns None at the end of the journal. Entries that could not fit into the
+/// journal (i.e., with a corrupt length) are rejected before they are read.
+fn read_entry<R: Read>(reader: &mut R, max_size_bytes: u64) -> Result<Option<(JournalEntry, u64)>> {
+    let mut length_bytes = [0u8; ENTRY_LENGTH_BYTES as usize];
+    match reader.read_exact(&mut length_bytes) {
+        Ok(()) => {},
+        Err(error) if error.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+        Err(error) => return Err(error.into()),
+    }
+
+    let entry_length = u32::from_le_bytes(length_bytes) as u64;
+    let entry_size = ENTRY_LENGTH_BYTES + entry_length;
+    if entry_size > max_size_bytes {
+        return Err(anyhow!(
+            "The journal entry size ({} bytes) exceeds the journal size limit ({} bytes)",
+            entry_size,
+            max_size_bytes
+        ));
+    }
+
+    let mut entry_bytes = vec![0u8; entry_length as usize];
+    reader
+        .read_exact(&mut entry_bytes)
+        .map_err(|error| anyhow!("Failed to read a journal entry: {:?}", error))?;
+    let entry = bcs::from_bytes(&entry_bytes)
+        .map_err(|error| anyhow!("Failed to deserialize a journal entry: {:?}", error))?;
+
+    Ok(Some((entry, entry_size)))
+}
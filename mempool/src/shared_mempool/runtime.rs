@@ -4,9 +4,11 @@
 
 use crate::{
     core_mempool::CoreMempool,
+    logging::{LogEntry, LogSchema},
     network::MempoolSyncMsg,
     shared_mempool::{
         coordinator::{coordinator, gc_coordinator, snapshot_job},
+        journal::{MempoolJournal, MempoolJournalWriter},
         types::{MempoolEventsReceiver, SharedMempool, SharedMempoolNotification},
     },
     QuorumStoreRequest,
@@ -14,7 +16,7 @@ use crate::{
 use aptos_config::config::{NodeConfig, NodeType};
 use aptos_event_notifications::{DbBackedOnChainConfig, ReconfigNotificationListener};
 use aptos_infallible::{Mutex, RwLock};
-use aptos_logger::Level;
+use aptos_logger::{error, Level};
 use aptos_mempool_notifications::MempoolNotificationListener;
 use aptos_network::application::{
     interface::{NetworkClient, NetworkServiceEvents},
@@ -24,7 +26,7 @@ use aptos_storage_interface::DbReader;
 use aptos_types::on_chain_config::OnChainConfigProvider;
 use aptos_vm_validator::vm_validator::{PooledVMValidator, TransactionValidation};
 use futures::channel::mpsc::{Receiver, UnboundedSender};
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 use tokio::runtime::{Handle, Runtime};
 
 /// Bootstrap of SharedMempool.
@@ -51,6 +53,7 @@ pub(crate) fn start_shared_mempool<TransactionValidator, ConfigProvider>(
     ConfigProvider: OnChainConfigProvider,
 {
     let node_type = NodeType::extract_from_config(config);
+    let journal = open_mempool_journal(config, executor);
     let smp: SharedMempool<NetworkClient<MempoolSyncMsg>, TransactionValidator> =
         SharedMempool::new(
             mempool.clone(),
@@ -60,6 +63,7 @@ pub(crate) fn start_shared_mempool<TransactionValidator, ConfigProvider>(
             validator,
             subscribers,
             node_type,
+            journal,
         );
 
     executor.spawn(coordinator(
@@ -87,6 +91,35 @@ pub(crate) fn start_shared_mempool<TransactionValidator, ConfigProvider>(
     }
 }
 
+/// Opens the mempool journal (if enabled) and spawns the journal writer. If the
+/// journal cannot be opened, an error is logged and mempool runs without a journal.
+fn open_mempool_journal(config: &NodeConfig, executor: &Handle) -> Option<MempoolJournalWriter> {
+    if !config.mempool.enable_journal {
+        return None;
+    }
+
+    let journal_path = config.storage.dir().join(&config.mempool.journal_path);
+    match MempoolJournal::open(&journal_path, config.mempool.journal_max_size_bytes) {
+        Ok(journal) => {
+            let system_transaction_timeout =
+                Duration::from_secs(config.mempool.system_transaction_timeout_secs);
+            Some(MempoolJournalWriter::new(
+                Arc::new(journal),
+                system_transaction_timeout,
+                executor,
+            ))
+        },
+        Err(error) => {
+            error!(
+                LogSchema::new(LogEntry::MempoolJournal).error(&error),
+                "Failed to open the mempool journal at {:?}! Running without a journal.",
+                journal_path
+            );
+            None
+        },
+    }
+}
+
 pub fn bootstrap(
     config: &NodeConfig,
     db: Arc<dyn DbReader>,
@@ -10,9 +10,10 @@ use crate::{
     logging::{LogEntry, LogEvent, LogSchema},
     network::{BroadcastError, BroadcastPeerPriority, MempoolSyncMsg},
     shared_mempool::{
+        journal::{JournalEntry, MempoolJournalWriter},
         types::{
-            notify_subscribers, ScheduledBroadcast, SharedMempool, SharedMempoolNotification,
-            SubmissionStatusBundle,
+            notify_subscribers, AccountPendingTransactions, MempoolStats, ScheduledBroadcast,
+            SharedMempool, SharedMempoolNotification, SubmissionStatusBundle,
         },
         use_case_history::UseCaseHistory,
     },
@@ -30,6 +31,7 @@ use aptos_metrics_core::HistogramTimer;
 use aptos_network::application::interface::NetworkClientInterface;
 use aptos_storage_interface::state_view::LatestDbStateCheckpointView;
 use aptos_types::{
+    account_address::AccountAddress,
     mempool_status::{MempoolStatus, MempoolStatusCode},
     on_chain_config::{OnChainConfigPayload, OnChainConfigProvider, OnChainConsensusConfig},
     transaction::SignedTransaction,
@@ -174,6 +176,49 @@ pub(crate) async fn process_client_get_transaction<NetworkClient, TransactionVal
     }
 }
 
+/// Processes a get request for all pending transactions of an account from a client
+pub(crate) async fn process_client_get_account_transactions<NetworkClient, TransactionValidator>(
+    smp: SharedMempool<NetworkClient, TransactionValidator>,
+    address: AccountAddress,
+    callback: oneshot::Sender<AccountPendingTransactions>,
+    timer: HistogramTimer,
+) where
+    NetworkClient: NetworkClientInterface<MempoolSyncMsg>,
+    TransactionValidator: TransactionValidation,
+{
+    timer.stop_and_record();
+    let account_transactions = smp.mempool.lock().get_account_transactions(&address);
+
+    if callback.send(account_transactions).is_err() {
+        warn!(LogSchema::event_log(
+            LogEntry::GetAccountTransactions,
+            LogEvent::CallbackFail
+        ));
+        counters::CLIENT_CALLBACK_FAIL.inc();
+    }
+}
+
+/// Processes a get request for the mempool stats from a client
+pub(crate) async fn process_client_get_mempool_stats<NetworkClient, TransactionValidator>(
+    smp: SharedMempool<NetworkClient, TransactionValidator>,
+    callback: oneshot::Sender<MempoolStats>,
+    timer: HistogramTimer,
+) where
+    NetworkClient: NetworkClientInterface<MempoolSyncMsg>,
+    TransactionValidator: TransactionValidation,
+{
+    timer.stop_and_record();
+    let mempool_stats = smp.mempool.lock().gen_stats();
+
+    if callback.send(mempool_stats).is_err() {
+        warn!(LogSchema::event_log(
+            LogEntry::GetMempoolStats,
+            LogEvent::CallbackFail
+        ));
+        counters::CLIENT_CALLBACK_FAIL.inc();
+    }
+}
+
 /// Processes transactions from other nodes.
 pub(crate) async fn process_transaction_broadcast<NetworkClient, TransactionValidator>(
     smp: SharedMempool<NetworkClient, TransactionValidator>,
@@ -351,6 +396,66 @@ where
     statuses
 }
 
+/// Replays the transactions persisted to the mempool journal (e.g., on startup).
+/// Expired transactions are dropped, and the remaining transactions are revalidated
+/// before being inserted into mempool. The journal is compacted afterwards.
+pub(crate) async fn replay_mempool_journal<NetworkClient, TransactionValidator>(
+    smp: &SharedMempool<NetworkClient, TransactionValidator>,
+    journal: &MempoolJournalWriter,
+) where
+    NetworkClient: NetworkClientInterface<MempoolSyncMsg>,
+    TransactionValidator: TransactionValidation,
+{
+    let journal_entries = match journal.read_entries().await {
+        Ok(journal_entries) => journal_entries,
+        Err(error) => {
+            counters::mempool_journal_operation_inc(counters::JOURNAL_ERROR_LABEL);
+            error!(LogSchema::new(LogEntry::MempoolJournal).error(&error));
+            return;
+        },
+    };
+
+    // Drop all expired transactions
+    let now = aptos_infallible::duration_since_epoch();
+    let system_transaction_timeout =
+        Duration::from_secs(smp.config.system_transaction_timeout_secs);
+    let num_journal_entries = journal_entries.len();
+    let transactions: Vec<_> = journal_entries
+        .into_iter()
+        .filter(|entry| !entry.is_expired(now, system_transaction_timeout))
+        .map(|entry| (entry.transaction, None, None))
+        .collect();
+    let num_expired = num_journal_entries - transactions.len();
+    counters::mempool_journal_operation_inc_by(
+        counters::JOURNAL_REPLAY_EXPIRED_LABEL,
+        num_expired as u64,
+    );
+
+    // Revalidate and insert the remaining transactions
+    if !transactions.is_empty() {
+        let statuses =
+            process_incoming_transactions(smp, transactions, TimelineState::NotReady, false);
+        let num_accepted = statuses
+            .iter()
+            .filter(|(_, (mempool_status, _))| mempool_status.code == MempoolStatusCode::Accepted)
+            .count();
+        counters::mempool_journal_operation_inc_by(
+            counters::JOURNAL_REPLAYED_LABEL,
+            num_accepted as u64,
+        );
+        info!(
+            LogSchema::new(LogEntry::MempoolJournal).num_txns(num_accepted),
+            "Replayed {} of {} journaled transactions ({} expired)",
+            num_accepted,
+            num_journal_entries,
+            num_expired
+        );
+    }
+
+    // Compact the journal to drop the expired and duplicate entries
+    journal.compact();
+}
+
 /// Perfoms VM validation on the transactions and inserts those that passes
 /// validation into the mempool.
 #[cfg(not(feature = "consensus-only-perf-test"))]
@@ -378,6 +483,7 @@ fn validate_and_add_transactions<NetworkClient, TransactionValidator>(
         .map(|t| smp.validator.read().validate_transaction(t.0.clone()))
         .collect::<Vec<_>>();
     vm_validation_timer.stop_and_record();
+    let mut journal_entries = vec![];
     {
         let mut mempool = smp.mempool.lock();
         for (idx, (transaction, sequence_info, ready_time_at_sender, priority)) in
@@ -396,6 +502,14 @@ fn validate_and_add_transactions<NetworkClient, TransactionValidator>(
                             ready_time_at_sender,
                             priority.clone(),
                         );
+                        if smp.journal.is_some()
+                            && mempool_status.code == MempoolStatusCode::Accepted
+                        {
+                            journal_entries.push(JournalEntry::new(
+                                aptos_infallible::duration_since_epoch(),
+                                transaction.clone(),
+                            ));
+                        }
                         statuses.push((transaction, (mempool_status, None)));
                     },
                     Some(validation_status) => {
@@ -419,6 +533,11 @@ fn validate_and_add_transactions<NetworkClient, TransactionValidator>(
             }
         }
     }
+
+    // Queue the accepted transactions to be persisted to the journal
+    if let Some(journal) = &smp.journal {
+        journal.append(journal_entries);
+    }
 }
 
 /// In consensus-only mode, insert transactions into the mempool directly
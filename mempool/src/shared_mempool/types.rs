@@ -6,7 +6,7 @@
 use crate::{
     core_mempool::CoreMempool,
     network::{MempoolNetworkInterface, MempoolSyncMsg},
-    shared_mempool::use_case_history::UseCaseHistory,
+    shared_mempool::{journal::MempoolJournalWriter, use_case_history::UseCaseHistory},
 };
 use anyhow::Result;
 use aptos_config::{
@@ -21,7 +21,8 @@ use aptos_infallible::{Mutex, RwLock};
 use aptos_network::application::interface::NetworkClientInterface;
 use aptos_storage_interface::DbReader;
 use aptos_types::{
-    mempool_status::MempoolStatus, transaction::SignedTransaction, vm_status::DiscardedVMStatus,
+    account_address::AccountAddress, mempool_status::MempoolStatus, transaction::SignedTransaction,
+    vm_status::DiscardedVMStatus,
 };
 use aptos_vm_validator::vm_validator::TransactionValidation;
 use futures::{
@@ -55,6 +56,7 @@ pub(crate) struct SharedMempool<NetworkClient, TransactionValidator> {
     pub subscribers: Vec<UnboundedSender<SharedMempoolNotification>>,
     pub broadcast_within_validator_network: Arc<RwLock<bool>>,
     pub use_case_history: Arc<Mutex<UseCaseHistory>>,
+    pub journal: Option<MempoolJournalWriter>,
 }
 
 impl<
@@ -70,6 +72,7 @@ impl<
         validator: Arc<RwLock<TransactionValidator>>,
         subscribers: Vec<UnboundedSender<SharedMempoolNotification>>,
         node_type: NodeType,
+        journal: Option<MempoolJournalWriter>,
     ) -> Self {
         let network_interface =
             MempoolNetworkInterface::new(network_client, node_type, config.clone());
@@ -86,6 +89,7 @@ impl<
             subscribers,
             broadcast_within_validator_network: Arc::new(RwLock::new(true)),
             use_case_history: Arc::new(Mutex::new(use_case_history)),
+            journal,
         }
     }
 
@@ -237,6 +241,55 @@ pub type SubmissionStatusBundle = (SignedTransaction, SubmissionStatus);
 pub enum MempoolClientRequest {
     SubmitTransaction(SignedTransaction, oneshot::Sender<Result<SubmissionStatus>>),
     GetTransactionByHash(HashValue, oneshot::Sender<Option<SignedTransaction>>),
+    GetAccountTransactions(AccountAddress, oneshot::Sender<AccountPendingTransactions>),
+    GetMempoolStats(oneshot::Sender<MempoolStats>),
+}
+
+/// The status of a transaction that is pending in mempool
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PendingTransactionStatus {
+    /// The transaction can be included in the next block
+    Ready,
+    /// The transaction can't be included in the next block (e.g., because
+    /// there is a gap between it and the account's sequence number)
+    Parked,
+}
+
+/// A transaction that is pending in mempool (along with its metadata)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PendingTransactionInfo {
+    pub transaction: SignedTransaction,
+    pub status: PendingTransactionStatus,
+    pub insertion_time: SystemTime,
+    pub ranking_score: u64,
+}
+
+/// All transactions that are pending in mempool for a single account
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AccountPendingTransactions {
+    // The account sequence number (as last seen by mempool), if known
+    pub account_sequence_number: Option<u64>,
+    // The pending transactions, ordered by sequence number
+    pub transactions: Vec<PendingTransactionInfo>,
+}
+
+/// The stats of the transactions in a single mempool fee bucket
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MempoolFeeBucketStats {
+    pub min_ranking_score: u64,
+    pub num_transactions: u64,
+    pub num_parked_transactions: u64,
+}
+
+/// The stats of all transactions in mempool
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MempoolStats {
+    pub num_transactions: u64,
+    pub num_parked_transactions: u64,
+    pub size_bytes: u64,
+    pub capacity: u64,
+    pub capacity_bytes: u64,
+    pub fee_buckets: Vec<MempoolFeeBucketStats>,
 }
 
 pub type MempoolClientSender = mpsc::Sender<MempoolClientRequest>;
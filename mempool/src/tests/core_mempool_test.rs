@@ -5,6 +5,9 @@
 use crate::{
     core_mempool::{sender_bucket, CoreMempool, MempoolTransaction, SubmittedBy, TimelineState},
     network::BroadcastPeerPriority,
+    shared_mempool::types::{
+        AccountPendingTransactions, MempoolFeeBucketStats, PendingTransactionStatus,
+    },
     tests::common::{
         add_signed_txn, add_txn, add_txns_to_mempool, setup_mempool,
         setup_mempool_with_broadcast_buckets, txn_bytes_len, TestTransaction,
@@ -1029,6 +1032,96 @@ fn test_get_transaction_by_hash_after_the_txn_is_updated() {
     assert_eq!(txn_by_new_hash, Some(new_txn));
 }
 
+#[test]
+fn test_get_account_transactions() {
+    let mut pool = setup_mempool().0;
+    let txns = add_txns_to_mempool(&mut pool, vec![
+        TestTransaction::new(1, 0, 1),
+        TestTransaction::new(1, 1, 1),
+        TestTransaction::new(1, 3, 1),
+        TestTransaction::new(0, 0, 1),
+    ]);
+
+    // Verify the account's transactions are returned in order (with txn 3 parked)
+    let account_transactions = pool.get_account_transactions(&txns[0].sender());
+    assert_eq!(account_transactions.account_sequence_number, Some(0));
+    let transactions: Vec<_> = account_transactions
+        .transactions
+        .iter()
+        .map(|info| (info.transaction.clone(), info.status))
+        .collect();
+    assert_eq!(transactions, vec![
+        (txns[0].clone(), PendingTransactionStatus::Ready),
+        (txns[1].clone(), PendingTransactionStatus::Ready),
+        (txns[2].clone(), PendingTransactionStatus::Parked),
+    ]);
+
+    // Verify an unknown account has no transactions
+    let account_transactions = pool.get_account_transactions(&TestTransaction::get_address(2));
+    assert_eq!(account_transactions, AccountPendingTransactions::default());
+}
+
+#[test]
+fn test_gen_stats() {
+    let mut pool = setup_mempool_with_broadcast_buckets(vec![0, 101, 201]).0;
+    add_txns_to_mempool(&mut pool, vec![
+        TestTransaction::new(1, 0, 1),   // bucket 0
+        TestTransaction::new(1, 1, 100), // bucket 0
+        TestTransaction::new(1, 3, 200), // bucket 1 (parked)
+        TestTransaction::new(1, 5, 300), // bucket 2 (parked)
+        TestTransaction::new(0, 0, 300), // bucket 2
+    ]);
+
+    // Verify the stats are grouped by fee bucket
+    let stats = pool.gen_stats();
+    assert_eq!(stats.num_transactions, 5);
+    assert_eq!(stats.num_parked_transactions, 2);
+    assert_eq!(stats.fee_buckets, vec![
+        MempoolFeeBucketStats {
+            min_ranking_score: 0,
+            num_transactions: 2,
+            num_parked_transactions: 0,
+        },
+        MempoolFeeBucketStats {
+            min_ranking_score: 101,
+            num_transactions: 1,
+            num_parked_transactions: 1,
+        },
+        MempoolFeeBucketStats {
+            min_ranking_score: 201,
+            num_transactions: 2,
+            num_parked_transactions: 1,
+        },
+    ]);
+
+    // Fill the sequence number gap (unparking a transaction) and commit
+    // the first transactions, and verify the stats are updated.
+    add_txns_to_mempool(&mut pool, vec![
+        TestTransaction::new(1, 2, 150), // bucket 1
+    ]);
+    pool.commit_transaction(&TestTransaction::get_address(1), 1);
+    let stats = pool.gen_stats();
+    assert_eq!(stats.num_transactions, 4);
+    assert_eq!(stats.num_parked_transactions, 1);
+    assert_eq!(stats.fee_buckets, vec![
+        MempoolFeeBucketStats {
+            min_ranking_score: 0,
+            num_transactions: 0,
+            num_parked_transactions: 0,
+        },
+        MempoolFeeBucketStats {
+            min_ranking_score: 101,
+            num_transactions: 2,
+            num_parked_transactions: 0,
+        },
+        MempoolFeeBucketStats {
+            min_ranking_score: 201,
+            num_transactions: 2,
+            num_parked_transactions: 1,
+        },
+    ]);
+}
+
 #[test]
 fn test_bytes_limit() {
     let mut config = NodeConfig::generate_random_config();
@@ -0,0 +1,352 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    core_mempool::CoreMempool,
+    network::MempoolSyncMsg,
+    shared_mempool::{
+        journal::{JournalEntry, MempoolJournal, MempoolJournalWriter},
+        tasks,
+        types::SharedMempool,
+    },
+    tests::common::TestTransaction,
+};
+use aptos_config::{
+    config::{NodeConfig, NodeType},
+    network_id::NetworkId,
+};
+use aptos_infallible::{Mutex, RwLock};
+use aptos_network::{
+    application::{interface::NetworkClient, storage::PeersAndMetadata},
+    protocols::wire::handshake::v1::ProtocolId::MempoolDirectSend,
+};
+use aptos_storage_interface::mock::MockDbReaderWriter;
+use aptos_temppath::TempPath;
+use aptos_types::account_address::AccountAddress;
+use aptos_vm_validator::mocks::mock_vm_validator::MockVMValidator;
+use std::{
+    collections::HashMap,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::PathBuf,
+    sync::Arc,
+    time::Duration,
+};
+use tokio::runtime::Handle;
+
+#[test]
+fn test_journal_append_and_read() {
+    // Create a journal and append several entries
+    let (_temp_dir, journal_path) = create_journal_path();
+    let journal = MempoolJournal::open(&journal_path, u64::MAX).unwrap();
+    let entries = create_journal_entries(5);
+    journal.append(&entries[..2]).unwrap();
+    journal.append(&entries[2..]).unwrap();
+
+    // Verify the entries are read back in order
+    assert_eq!(journal.read_entries().unwrap(), entries);
+
+    // Reopen the journal and verify the entries are persisted
+    drop(journal);
+    let journal = MempoolJournal::open(&journal_path, u64::MAX).unwrap();
+    assert_eq!(journal.read_entries().unwrap(), entries);
+}
+
+#[test]
+fn test_journal_size_limit() {
+    // Create a journal that can only hold a few entries
+    let (_temp_dir, journal_path) = create_journal_path();
+    let entries = create_journal_entries(10);
+    let entry_size = 4 + bcs::to_bytes(&entries[0]).unwrap().len() as u64;
+    let journal = MempoolJournal::open(&journal_path, entry_size * 3).unwrap();
+
+    // Append all entries and verify only the first few are persisted
+    journal.append(&entries).unwrap();
+    assert_eq!(journal.read_entries().unwrap(), entries[..3].to_vec());
+    assert_eq!(fs::metadata(&journal_path).unwrap().len(), entry_size * 3);
+
+    // Compact the journal (expiring all but the first entry) and verify
+    // space is freed up for new entries.
+    journal
+        .compact(
+            Duration::from_secs(1),
+            Duration::from_secs(1),
+            &HashMap::new(),
+        )
+        .unwrap();
+    journal.append(&entries[5..]).unwrap();
+    assert_eq!(journal.read_entries().unwrap(), vec![
+        entries[1].clone(),
+        entries[2].clone(),
+        entries[5].clone(),
+    ]);
+}
+
+#[test]
+fn test_journal_truncated_entry() {
+    // Create a journal and append several entries
+    let (_temp_dir, journal_path) = create_journal_path();
+    let journal = MempoolJournal::open(&journal_path, u64::MAX).unwrap();
+    let entries = create_journal_entries(5);
+    journal.append(&entries[..3]).unwrap();
+    drop(journal);
+
+    // Emulate a partially written entry (e.g., a crash mid-write)
+    let mut journal_file = OpenOptions::new().append(true).open(&journal_path).unwrap();
+    journal_file.write_all(&100u32.to_le_bytes()).unwrap();
+    journal_file.write_all(&[1, 2, 3]).unwrap();
+    drop(journal_file);
+
+    // Verify the complete entries are still read
+    let journal = MempoolJournal::open(&journal_path, u64::MAX).unwrap();
+    assert_eq!(journal.read_entries().unwrap(), entries[..3].to_vec());
+
+    // Verify the partial entry was truncated, and new entries are appended after the others
+    journal.append(&entries[3..]).unwrap();
+    drop(journal);
+    let journal = MempoolJournal::open(&journal_path, u64::MAX).unwrap();
+    assert_eq!(journal.read_entries().unwrap(), entries);
+}
+
+#[test]
+fn test_journal_corrupt_entry_length() {
+    // Create a journal and append several entries
+    let (_temp_dir, journal_path) = create_journal_path();
+    let max_size_bytes = 1024 * 1024;
+    let journal = MempoolJournal::open(&journal_path, max_size_bytes).unwrap();
+    let entries = create_journal_entries(5);
+    journal.append(&entries[..3]).unwrap();
+    drop(journal);
+    let journal_size = fs::metadata(&journal_path).unwrap().len();
+
+    // Emulate a corrupt length prefix that exceeds the journal size limit
+    let mut journal_file = OpenOptions::new().append(true).open(&journal_path).unwrap();
+    journal_file.write_all(&u32::MAX.to_le_bytes()).unwrap();
+    journal_file.write_all(&[1, 2, 3]).unwrap();
+    drop(journal_file);
+
+    // Verify the corrupt entry is dropped, and new entries can still be read
+    let journal = MempoolJournal::open(&journal_path, max_size_bytes).unwrap();
+    assert_eq!(fs::metadata(&journal_path).unwrap().len(), journal_size);
+    assert_eq!(journal.read_entries().unwrap(), entries[..3].to_vec());
+    journal.append(&entries[3..]).unwrap();
+    assert_eq!(journal.read_entries().unwrap(), entries);
+}
+
+#[test]
+fn test_journal_compaction() {
+    // Create a journal and append several entries
+    let (_temp_dir, journal_path) = create_journal_path();
+    let journal = MempoolJournal::open(&journal_path, u64::MAX).unwrap();
+    let entries = create_journal_entries(8);
+    journal.append(&entries).unwrap();
+
+    // Append an entry that supersedes an existing entry (same sender and sequence number)
+    let superseding_entry = JournalEntry::new(
+        Duration::from_secs(100),
+        TestTransaction::new(3, 7, 2).make_signed_transaction(),
+    );
+    journal.append(&[superseding_entry.clone()]).unwrap();
+
+    // Compact the journal, dropping the expired entries (0 and 1) and the
+    // committed entries (sender 2 below sequence number 3, i.e., entry 2).
+    let now = Duration::from_secs(2);
+    let system_transaction_timeout = Duration::from_secs(1);
+    let committed_sequence_numbers = HashMap::from([
+        (TestTransaction::get_address(2), 3),
+        (AccountAddress::random(), 10),
+    ]);
+    let num_entries = journal
+        .compact(now, system_transaction_timeout, &committed_sequence_numbers)
+        .unwrap();
+
+    // Verify only the remaining (latest) entries are kept in order
+    let expected_entries = vec![
+        entries[3].clone(),
+        entries[4].clone(),
+        entries[5].clone(),
+        entries[6].clone(),
+        superseding_entry,
+    ];
+    assert_eq!(num_entries, expected_entries.len());
+    assert_eq!(journal.read_entries().unwrap(), expected_entries);
+
+    // Append another entry and verify it is written after the compacted entries
+    journal.append(&entries[..1]).unwrap();
+    let mut expected_entries = expected_entries;
+    expected_entries.push(entries[0].clone());
+    assert_eq!(journal.read_entries().unwrap(), expected_entries);
+}
+
+#[tokio::test]
+async fn test_journal_writer() {
+    // Create a journal writer
+    let (_temp_dir, journal_path) = create_journal_path();
+    let journal = Arc::new(MempoolJournal::open(&journal_path, u64::MAX).unwrap());
+    let journal_writer = MempoolJournalWriter::new(
+        journal.clone(),
+        Duration::from_secs(u64::MAX),
+        &Handle::current(),
+    );
+
+    // Queue several appends and verify the entries are written in order
+    let entries = create_journal_entries(6);
+    journal_writer.append(entries[..3].to_vec());
+    journal_writer.append(entries[3..].to_vec());
+    assert_eq!(journal_writer.read_entries().await.unwrap(), entries);
+
+    // Notify the writer of committed transactions (for the first two senders)
+    journal_writer.notify_committed(vec![
+        (TestTransaction::get_address(0), 0),
+        (TestTransaction::get_address(0), 4),
+        (TestTransaction::get_address(1), 1),
+    ]);
+
+    // Queue a compaction and verify the committed entries were dropped
+    journal_writer.compact();
+    assert_eq!(journal_writer.read_entries().await.unwrap(), vec![
+        entries[2].clone(),
+        entries[3].clone(),
+        entries[5].clone(),
+    ]);
+
+    // Verify the entries are persisted once the writer is flushed
+    journal_writer.flush().await;
+    assert_eq!(journal.read_entries().unwrap(), vec![
+        entries[2].clone(),
+        entries[3].clone(),
+        entries[5].clone(),
+    ]);
+}
+
+#[test]
+fn test_journal_entry_expiration() {
+    let now = Duration::from_secs(1_000);
+    let system_transaction_timeout = Duration::from_secs(100);
+
+    // Verify an unexpired entry
+    let transaction =
+        TestTransaction::new(0, 0, 1).make_signed_transaction_with_expiration_time(2_000);
+    let entry = JournalEntry::new(Duration::from_secs(950), transaction.clone());
+    assert!(!entry.is_expired(now, system_transaction_timeout));
+
+    // Verify an entry that has exceeded the system TTL
+    let entry = JournalEntry::new(Duration::from_secs(900), transaction);
+    assert!(entry.is_expired(now, system_transaction_timeout));
+
+    // Verify an entry with an expired transaction
+    let transaction =
+        TestTransaction::new(0, 0, 1).make_signed_transaction_with_expiration_time(999);
+    let entry = JournalEntry::new(Duration::from_secs(950), transaction);
+    assert!(entry.is_expired(now, system_transaction_timeout));
+}
+
+#[tokio::test]
+async fn test_replay_mempool_journal() {
+    // Create a journal with valid and expired transactions
+    let (_temp_dir, journal_path) = create_journal_path();
+    let journal = MempoolJournal::open(&journal_path, u64::MAX).unwrap();
+    let now = aptos_infallible::duration_since_epoch();
+    let valid_entries = vec![
+        JournalEntry::new(now, TestTransaction::new(0, 0, 1).make_signed_transaction()),
+        JournalEntry::new(now, TestTransaction::new(1, 0, 1).make_signed_transaction()),
+    ];
+    let expired_entries = vec![
+        JournalEntry::new(
+            now,
+            TestTransaction::new(2, 0, 1).make_signed_transaction_with_expiration_time(0),
+        ),
+        JournalEntry::new(
+            Duration::ZERO,
+            TestTransaction::new(3, 0, 1).make_signed_transaction(),
+        ),
+    ];
+    journal.append(&valid_entries).unwrap();
+    journal.append(&expired_entries).unwrap();
+
+    // Replay the journal into an empty mempool
+    let smp = create_shared_mempool(Arc::new(journal));
+    let journal = smp.journal.clone().unwrap();
+    tasks::replay_mempool_journal(&smp, &journal).await;
+
+    // Verify that only the valid transactions were inserted into mempool
+    {
+        let mempool = smp.mempool.lock();
+        for entry in valid_entries.iter() {
+            let transaction = &entry.transaction;
+            assert_eq!(
+                mempool.get_by_hash(transaction.committed_hash()),
+                Some(transaction.clone())
+            );
+        }
+        for entry in expired_entries.iter() {
+            assert!(mempool
+                .get_by_hash(entry.transaction.committed_hash())
+                .is_none());
+        }
+    }
+
+    // Verify that the journal was compacted to only hold the valid transactions
+    let mut expected_transactions: Vec<_> = valid_entries
+        .iter()
+        .map(|entry| entry.transaction.clone())
+        .collect();
+    expected_transactions.sort_by_key(|transaction| transaction.sender());
+    let mut journal_transactions: Vec<_> = journal
+        .read_entries()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|entry| entry.transaction)
+        .collect();
+    journal_transactions.sort_by_key(|transaction| transaction.sender());
+    assert_eq!(journal_transactions, expected_transactions);
+}
+
+/// Creates a temporary directory and returns the journal path inside it
+fn create_journal_path() -> (TempPath, PathBuf) {
+    let temp_dir = TempPath::new();
+    temp_dir.create_as_dir().unwrap();
+    let journal_path = temp_dir.path().join("mempool_journal.bin");
+    (temp_dir, journal_path)
+}
+
+/// Creates the specified number of journal entries (for different senders)
+fn create_journal_entries(num_entries: usize) -> Vec<JournalEntry> {
+    (0..num_entries)
+        .map(|index| {
+            let transaction = TestTransaction::new(index % 4, index as u64, 1);
+            JournalEntry::new(
+                Duration::from_secs(index as u64),
+                transaction.make_signed_transaction(),
+            )
+        })
+        .collect()
+}
+
+/// Creates a shared mempool (with mock storage and validation) using the given journal
+fn create_shared_mempool(
+    journal: Arc<MempoolJournal>,
+) -> SharedMempool<NetworkClient<MempoolSyncMsg>, MockVMValidator> {
+    let config = NodeConfig::default();
+    let system_transaction_timeout =
+        Duration::from_secs(config.mempool.system_transaction_timeout_secs);
+    let journal_writer =
+        MempoolJournalWriter::new(journal, system_transaction_timeout, &Handle::current());
+    let network_client = NetworkClient::new(
+        vec![MempoolDirectSend],
+        vec![],
+        HashMap::new(),
+        PeersAndMetadata::new(&[NetworkId::Validator]),
+    );
+    SharedMempool::new(
+        Arc::new(Mutex::new(CoreMempool::new(&config))),
+        config.mempool.clone(),
+        network_client,
+        Arc::new(MockDbReaderWriter),
+        Arc::new(RwLock::new(MockVMValidator)),
+        vec![],
+        NodeType::extract_from_config(&config),
+        Some(journal_writer),
+    )
+}
@@ -0,0 +1,357 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{from_base64, to_base64, CryptoKVStorage, Error, GetResponse, KVStorage};
+use aes_gcm::{
+    aead::{Aead, Payload},
+    Aes256Gcm, KeyInit, Nonce,
+};
+use aptos_temppath::TempPath;
+use aptos_time_service::{TimeService, TimeServiceTrait};
+use argon2::{Algorithm, Argon2, Params, Version};
+use rand::{rngs::OsRng, RngCore};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    fs::{self, File, OpenOptions},
+    io::{Read, Write},
+    path::{Path, PathBuf},
+};
+use zeroize::Zeroizing;
+
+/// The version of the sealed file format
+const SEALED_DATA_VERSION: u32 = 1;
+
+/// The default Argon2id parameters (as recommended by OWASP)
+const DEFAULT_MEMORY_COST_KIB: u32 = 19 * 1024;
+const DEFAULT_ITERATIONS: u32 = 2;
+const DEFAULT_PARALLELISM: u32 = 1;
+
+/// The maximum Argon2id parameters accepted from a sealed file. These bound the memory and time
+/// spent deriving the key, so that a tampered file can't exhaust the resources of the node.
+const MAX_MEMORY_COST_KIB: u32 = 1024 * 1024; // 1 GiB
+const MAX_ITERATIONS: u32 = 64;
+const MAX_PARALLELISM: u32 = 16;
+
+/// The lengths (in bytes) of the key derivation salt, the AES-256 key and the AES-GCM nonce
+const SALT_LENGTH: usize = 32;
+const KEY_LENGTH: usize = 32;
+const NONCE_LENGTH: usize = 12;
+
+/// EncryptedOnDiskStorage is a variant of OnDiskStorage that seals the file it persists to with
+/// AES-256-GCM, using a key derived from a passphrase with Argon2id. This allows operators that
+/// cannot run Vault to avoid keeping key material in plaintext on disk.
+///
+/// The key derivation parameters (including a random salt) are stored alongside the ciphertext.
+/// The key is derived once when the storage is opened (and whenever the passphrase is rotated),
+/// so reads and writes only pay the cost of the AEAD. A fresh nonce is used for every write, and
+/// all writes are atomic (i.e., the sealed file is written to a temporary file and renamed).
+///
+/// Like OnDiskStorage, this is intended for single threads (or must be wrapped by a
+/// Arc<RwLock<>>), and it provides no permission checks.
+pub struct EncryptedOnDiskStorage {
+    file_path: PathBuf,
+    temp_path: TempPath,
+    sealing_key: SealingKey,
+    time_service: TimeService,
+}
+
+impl EncryptedOnDiskStorage {
+    /// Opens the sealed storage file at the given path, creating it if it doesn't exist. If the
+    /// file already exists, it must have been sealed with the given passphrase.
+    pub fn new(file_path: PathBuf, passphrase: &[u8]) -> Result<Self, Error> {
+        Self::new_with_time_service(file_path, passphrase, TimeService::real())
+    }
+
+    fn new_with_time_service(
+        file_path: PathBuf,
+        passphrase: &[u8],
+        time_service: TimeService,
+    ) -> Result<Self, Error> {
+        if passphrase.is_empty() {
+            return Err(Error::InternalError(
+                "The secure storage passphrase cannot be empty".into(),
+            ));
+        }
+
+        // The parent will be one when only a filename is supplied. Therefore use the current
+        // working directory provided by PathBuf::new().
+        let file_dir = file_path
+            .parent()
+            .map_or(PathBuf::new(), |p| p.to_path_buf());
+        let temp_path = TempPath::new_with_temp_dir(file_dir);
+
+        // Derive the key from the existing file (verifying the passphrase), or create a new file
+        match read_sealed_data(&file_path)? {
+            Some(sealed_data) => {
+                let sealing_key = SealingKey::derive(passphrase, sealed_data.kdf.clone())?;
+                sealing_key.open(&sealed_data)?;
+                Ok(Self {
+                    file_path,
+                    temp_path,
+                    sealing_key,
+                    time_service,
+                })
+            },
+            None => {
+                let sealing_key = SealingKey::derive(passphrase, KdfParams::new_random())?;
+                let storage = Self {
+                    file_path,
+                    temp_path,
+                    sealing_key,
+                    time_service,
+                };
+                storage.write(&HashMap::new())?;
+                Ok(storage)
+            },
+        }
+    }
+
+    /// Re-seals the storage file with a key derived from the given passphrase and a fresh salt.
+    /// Rotating to the same passphrase still rotates the underlying key.
+    pub fn rotate_passphrase(&mut self, new_passphrase: &[u8]) -> Result<(), Error> {
+        if new_passphrase.is_empty() {
+            return Err(Error::InternalError(
+                "The secure storage passphrase cannot be empty".into(),
+            ));
+        }
+
+        let data = self.read()?;
+        self.sealing_key = SealingKey::derive(new_passphrase, KdfParams::new_random())?;
+        self.write(&data)
+    }
+
+    /// Imports all values from the given (plaintext) OnDiskStorage file, preserving their last
+    /// update times. Existing values with the same keys are overwritten. Returns the number of
+    /// imported values. Note: the plaintext file is left untouched, and should be securely
+    /// deleted by the caller.
+    pub fn import_from_plaintext(&mut self, plaintext_path: &Path) -> Result<usize, Error> {
+        let mut contents = String::new();
+        File::open(plaintext_path)?.read_to_string(&mut contents)?;
+        let plaintext_data: HashMap<String, Value> = if contents.is_empty() {
+            HashMap::new()
+        } else {
+            serde_json::from_str(&contents)?
+        };
+
+        let num_values = plaintext_data.len();
+        let mut data = self.read()?;
+        data.extend(plaintext_data);
+        self.write(&data)?;
+        Ok(num_values)
+    }
+
+    fn read(&self) -> Result<HashMap<String, Value>, Error> {
+        let sealed_data = read_sealed_data(&self.file_path)?.ok_or_else(|| {
+            Error::InternalError(format!(
+                "Sealed storage file is missing or empty: {:?}",
+                self.file_path
+            ))
+        })?;
+
+        // The file may have been re-sealed (e.g., by a passphrase rotation) since it was opened
+        if sealed_data.kdf != self.sealing_key.kdf {
+            return Err(Error::PermissionDenied);
+        }
+        let plaintext = self.sealing_key.open(&sealed_data)?;
+        Ok(serde_json::from_slice(&plaintext)?)
+    }
+
+    fn write(&self, data: &HashMap<String, Value>) -> Result<(), Error> {
+        let plaintext = Zeroizing::new(serde_json::to_vec(data)?);
+        let sealed_data = self.sealing_key.seal(&plaintext)?;
+        let contents = serde_json::to_vec(&sealed_data)?;
+
+        let mut options = OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options.open(self.temp_path.path())?;
+        file.write_all(&contents)?;
+        file.sync_all()?;
+        fs::rename(&self.temp_path, &self.file_path)?;
+        Ok(())
+    }
+}
+
+impl KVStorage for EncryptedOnDiskStorage {
+    fn available(&self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn get<V: DeserializeOwned>(&self, key: &str) -> Result<GetResponse<V>, Error> {
+        let mut data = self.read()?;
+        data.remove(key)
+            .ok_or_else(|| Error::KeyNotSet(key.to_string()))
+            .and_then(|value| serde_json::from_value(value).map_err(|e| e.into()))
+    }
+
+    fn set<V: Serialize>(&mut self, key: &str, value: V) -> Result<(), Error> {
+        let now = self.time_service.now_secs();
+        let mut data = self.read()?;
+        data.insert(
+            key.to_string(),
+            serde_json::to_value(&GetResponse::new(value, now))?,
+        );
+        self.write(&data)
+    }
+
+    #[cfg(any(test, feature = "testing"))]
+    fn reset_and_clear(&mut self) -> Result<(), Error> {
+        self.write(&HashMap::new())
+    }
+}
+
+impl CryptoKVStorage for EncryptedOnDiskStorage {}
+
+/// The Argon2id parameters used to derive the sealing key from the passphrase
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+struct KdfParams {
+    #[serde(serialize_with = "to_base64", deserialize_with = "from_base64")]
+    salt: Vec<u8>,
+    memory_cost_kib: u32,
+    iterations: u32,
+    parallelism: u32,
+}
+
+impl KdfParams {
+    fn new_random() -> Self {
+        let mut salt = vec![0u8; SALT_LENGTH];
+        OsRng.fill_bytes(&mut salt);
+        Self {
+            salt,
+            memory_cost_kib: DEFAULT_MEMORY_COST_KIB,
+            iterations: DEFAULT_ITERATIONS,
+            parallelism: DEFAULT_PARALLELISM,
+        }
+    }
+
+    /// Verifies the parameters are within the accepted bounds
+    fn verify(&self) -> Result<(), Error> {
+        if self.salt.len() != SALT_LENGTH {
+            return Err(Error::SerializationError(format!(
+                "Invalid salt length: {}",
+                self.salt.len()
+            )));
+        }
+        if self.memory_cost_kib > MAX_MEMORY_COST_KIB
+            || self.iterations > MAX_ITERATIONS
+            || self.parallelism > MAX_PARALLELISM
+        {
+            return Err(Error::SerializationError(format!(
+                "Argon2 parameters exceed the maximum allowed (memory cost: {} KiB, iterations: {}, \
+                parallelism: {})",
+                self.memory_cost_kib, self.iterations, self.parallelism
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// The contents of a sealed storage file
+#[derive(Debug, Deserialize, Serialize)]
+struct SealedData {
+    version: u32,
+    kdf: KdfParams,
+    #[serde(serialize_with = "to_base64", deserialize_with = "from_base64")]
+    nonce: Vec<u8>,
+    #[serde(serialize_with = "to_base64", deserialize_with = "from_base64")]
+    ciphertext: Vec<u8>,
+}
+
+/// Reads the sealed data from the given file. Returns None if the file doesn't exist or is empty.
+fn read_sealed_data(file_path: &Path) -> Result<Option<SealedData>, Error> {
+    if !file_path.exists() {
+        return Ok(None);
+    }
+
+    let mut contents = String::new();
+    File::open(file_path)?.read_to_string(&mut contents)?;
+    if contents.is_empty() {
+        return Ok(None);
+    }
+
+    let sealed_data: SealedData = serde_json::from_str(&contents)?;
+    if sealed_data.version != SEALED_DATA_VERSION {
+        return Err(Error::SerializationError(format!(
+            "Unsupported sealed storage version: {}",
+            sealed_data.version
+        )));
+    }
+    sealed_data.kdf.verify()?;
+    Ok(Some(sealed_data))
+}
+
+/// The AES-256-GCM key derived from the passphrase (and the parameters used to derive it)
+struct SealingKey {
+    cipher: Aes256Gcm,
+    kdf: KdfParams,
+}
+
+impl SealingKey {
+    fn derive(passphrase: &[u8], kdf: KdfParams) -> Result<Self, Error> {
+        let params = Params::new(
+            kdf.memory_cost_kib,
+            kdf.iterations,
+            kdf.parallelism,
+            Some(KEY_LENGTH),
+        )
+        .map_err(|error| Error::InternalError(format!("Invalid Argon2 parameters: {}", error)))?;
+
+        // The derived key bytes are zeroized once the cipher has been initialized
+        let mut key = Zeroizing::new([0u8; KEY_LENGTH]);
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(passphrase, &kdf.salt, key.as_mut())
+            .map_err(|error| Error::InternalError(format!("Failed to derive key: {}", error)))?;
+        let cipher = Aes256Gcm::new_from_slice(key.as_ref()).expect("Key length is correct.");
+
+        Ok(Self { cipher, kdf })
+    }
+
+    /// Returns the additional authenticated data, which binds the ciphertext to the file format
+    /// version and the key derivation parameters.
+    fn associated_data(&self) -> Result<Vec<u8>, Error> {
+        Ok(bcs::to_bytes(&(SEALED_DATA_VERSION, &self.kdf))?)
+    }
+
+    fn seal(&self, plaintext: &[u8]) -> Result<SealedData, Error> {
+        let mut nonce = vec![0u8; NONCE_LENGTH];
+        OsRng.fill_bytes(&mut nonce);
+        let ciphertext = self
+            .cipher
+            .encrypt(Nonce::from_slice(&nonce), Payload {
+                msg: plaintext,
+                aad: &self.associated_data()?,
+            })
+            .map_err(|error| Error::InternalError(format!("Failed to seal storage: {}", error)))?;
+
+        Ok(SealedData {
+            version: SEALED_DATA_VERSION,
+            kdf: self.kdf.clone(),
+            nonce,
+            ciphertext,
+        })
+    }
+
+    /// Decrypts the sealed data. Fails with PermissionDenied if the passphrase is incorrect (or
+    /// the data has been tampered with).
+    fn open(&self, sealed_data: &SealedData) -> Result<Zeroizing<Vec<u8>>, Error> {
+        if sealed_data.nonce.len() != NONCE_LENGTH {
+            return Err(Error::SerializationError(format!(
+                "Invalid nonce length: {}",
+                sealed_data.nonce.len()
+            )));
+        }
+        self.cipher
+            .decrypt(Nonce::from_slice(&sealed_data.nonce), Payload {
+                msg: &sealed_data.ciphertext,
+                aad: &self.associated_data()?,
+            })
+            .map(Zeroizing::new)
+            .map_err(|_| Error::PermissionDenied)
+    }
+}
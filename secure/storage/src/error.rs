@@ -48,6 +48,12 @@ impl From<bcs::Error> for Error {
     }
 }
 
+impl From<cryptoki::error::Error> for Error {
+    fn from(error: cryptoki::error::Error) -> Self {
+        Self::InternalError(format!("PKCS#11 error: {}", error))
+    }
+}
+
 impl From<serde_json::Error> for Error {
     fn from(error: serde_json::Error) -> Self {
         Self::SerializationError(format!("{}", error))
@@ -0,0 +1,509 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{CryptoStorage, Error, GetResponse, KVStorage, PublicKeyResponse};
+use aptos_crypto::{
+    ed25519::{Ed25519PrivateKey, Ed25519PublicKey, Ed25519Signature},
+    hash::CryptoHash,
+    traits::signing_message,
+    PrivateKey, SigningKey, Uniform,
+};
+use aptos_infallible::Mutex;
+use aptos_time_service::{TimeService, TimeServiceTrait};
+use cryptoki::{
+    context::{CInitializeArgs, Pkcs11},
+    error::{Error as Pkcs11Error, RvError},
+    mechanism::{aead::GcmParams, Mechanism},
+    object::{Attribute, AttributeType, KeyType, ObjectClass, ObjectHandle},
+    session::{Session, UserType},
+    types::AuthPin,
+};
+use once_cell::sync::Lazy;
+use rand::{rngs::OsRng, Rng, SeedableRng};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+/// The application tag attached to all data objects created by this storage
+const APPLICATION: &[u8] = b"aptos-secure-storage";
+/// The id attached to all key pairs created by this storage
+const KEY_PAIR_ID: &[u8] = b"aptos-secure-storage-key";
+/// The label of the AES key used to wrap all values stored in the token
+const WRAPPING_KEY_LABEL: &[u8] = b"aptos-secure-storage-wrapping-key";
+
+/// The DER encoding of the Ed25519 curve OID (1.3.101.112), used for CKA_EC_PARAMS
+const ED25519_EC_PARAMS: &[u8] = &[0x06, 0x03, 0x2B, 0x65, 0x70];
+/// The length (in bytes) of the AES-GCM nonces, and the length (in bits) of the tags
+const GCM_NONCE_LENGTH: usize = 12;
+const GCM_TAG_BITS: u64 = 128;
+
+/// The loaded PKCS#11 libraries. A library may only be initialized once per process, and it is
+/// finalized when its context is dropped, so contexts are shared by all storage instances.
+static PKCS11_CONTEXTS: Lazy<Mutex<HashMap<PathBuf, Pkcs11>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Pkcs11Storage offers secure storage backed by a hardware security module (HSM), or any other
+/// token that is accessible via a PKCS#11 library (e.g., SoftHSM for testing).
+///
+/// Ed25519 keys created via `create_key` are (by default) generated inside the token as
+/// non-extractable keys, and all signing operations with them are performed inside the token.
+/// Such keys can never be exported. Key material that must leave the token (e.g., imported keys,
+/// the BLS12-381 consensus key used by safety rules or the x25519 network identity key, neither of
+/// which has a standard PKCS#11 mechanism) is instead stored as data objects, encrypted with a
+/// non-extractable AES-256 key that never leaves the token. The same applies to all other
+/// key/value data.
+///
+/// If `extractable_keys` is set, `create_key` generates keys outside the token and wraps them like
+/// all other values. This is required for keys that must be exported (e.g., network identities).
+pub struct Pkcs11Storage {
+    extractable_keys: bool,
+    session: Mutex<Session>,
+    time_service: TimeService,
+    wrapping_key: ObjectHandle,
+}
+
+impl Pkcs11Storage {
+    /// Loads the given PKCS#11 library, opens a session with the token identified by the given
+    /// label and logs in as the user. The wrapping key is created in the token if it does not
+    /// already exist.
+    pub fn new(
+        library_path: &Path,
+        token_label: &str,
+        pin: String,
+        extractable_keys: bool,
+    ) -> Result<Self, Error> {
+        Self::new_with_time_service(
+            library_path,
+            token_label,
+            pin,
+            extractable_keys,
+            TimeService::real(),
+        )
+    }
+
+    fn new_with_time_service(
+        library_path: &Path,
+        token_label: &str,
+        pin: String,
+        extractable_keys: bool,
+        time_service: TimeService,
+    ) -> Result<Self, Error> {
+        let pkcs11 = get_or_load_library(library_path)?;
+
+        // Find the slot holding the token
+        let mut token_slot = None;
+        for slot in pkcs11.get_slots_with_token()? {
+            if pkcs11.get_token_info(slot)?.label() == token_label {
+                token_slot = Some(slot);
+                break;
+            }
+        }
+        let slot = token_slot.ok_or_else(|| {
+            Error::InternalError(format!("PKCS#11 token not found: {}", token_label))
+        })?;
+
+        let session = pkcs11.open_rw_session(slot)?;
+        // The login state is shared by all sessions of the process
+        match session.login(UserType::User, Some(&AuthPin::new(pin))) {
+            Ok(()) | Err(Pkcs11Error::Pkcs11(RvError::UserAlreadyLoggedIn)) => {},
+            Err(Pkcs11Error::Pkcs11(RvError::PinIncorrect)) => return Err(Error::PermissionDenied),
+            Err(error) => return Err(error.into()),
+        }
+        let wrapping_key = get_or_create_wrapping_key(&session)?;
+
+        Ok(Self {
+            extractable_keys,
+            session: Mutex::new(session),
+            time_service,
+            wrapping_key,
+        })
+    }
+
+    /// Returns the handles of all data objects stored under the given key
+    fn find_data_objects(session: &Session, key: &str) -> Result<Vec<ObjectHandle>, Error> {
+        Ok(session.find_objects(&[
+            Attribute::Class(ObjectClass::DATA),
+            Attribute::Application(APPLICATION.to_vec()),
+            Attribute::Label(key.as_bytes().to_vec()),
+        ])?)
+    }
+
+    /// Returns the handles of all key objects of the given class stored under the given name
+    fn find_key_objects(
+        session: &Session,
+        name: &str,
+        class: ObjectClass,
+    ) -> Result<Vec<ObjectHandle>, Error> {
+        Ok(session.find_objects(&[
+            Attribute::Class(class),
+            Attribute::Id(KEY_PAIR_ID.to_vec()),
+            Attribute::Label(name.as_bytes().to_vec()),
+        ])?)
+    }
+
+    /// Returns the private key handle of the token key pair with the given name (if any)
+    fn token_private_key(&self, name: &str) -> Result<Option<ObjectHandle>, Error> {
+        let session = self.session.lock();
+        Ok(
+            Self::find_key_objects(&session, name, ObjectClass::PRIVATE_KEY)?
+                .first()
+                .copied(),
+        )
+    }
+
+    /// Encrypts the given value with the wrapping key. The key is used as the additional
+    /// authenticated data, so that encrypted values cannot be swapped between keys.
+    fn encrypt(&self, session: &Session, key: &str, value: &[u8]) -> Result<Vec<u8>, Error> {
+        let mut nonce = [0u8; GCM_NONCE_LENGTH];
+        session.generate_random_slice(&mut nonce)?;
+        let params = GcmParams::new(&nonce, key.as_bytes(), GCM_TAG_BITS.into());
+        let ciphertext = session.encrypt(&Mechanism::AesGcm(params), self.wrapping_key, value)?;
+
+        let mut encrypted_value = nonce.to_vec();
+        encrypted_value.extend(ciphertext);
+        Ok(encrypted_value)
+    }
+
+    /// Decrypts a value encrypted with `encrypt`
+    fn decrypt(
+        &self,
+        session: &Session,
+        key: &str,
+        encrypted_value: &[u8],
+    ) -> Result<Vec<u8>, Error> {
+        if encrypted_value.len() < GCM_NONCE_LENGTH {
+            return Err(Error::SerializationError(format!(
+                "Encrypted value for {} is too short",
+                key
+            )));
+        }
+        let (nonce, ciphertext) = encrypted_value.split_at(GCM_NONCE_LENGTH);
+        let params = GcmParams::new(nonce, key.as_bytes(), GCM_TAG_BITS.into());
+        Ok(session.decrypt(&Mechanism::AesGcm(params), self.wrapping_key, ciphertext)?)
+    }
+
+    /// Destroys the token key pair with the given name (if any)
+    fn destroy_token_key_pair(session: &Session, name: &str) -> Result<(), Error> {
+        for class in [ObjectClass::PRIVATE_KEY, ObjectClass::PUBLIC_KEY] {
+            for handle in Self::find_key_objects(session, name, class)? {
+                session.destroy_object(handle)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Generates a new non-extractable Ed25519 key pair in the token with the given name
+    fn generate_token_key_pair(&mut self, name: &str) -> Result<Ed25519PublicKey, Error> {
+        let label = name.as_bytes().to_vec();
+        let public_key_template = [
+            Attribute::Token(true),
+            Attribute::Private(false),
+            Attribute::Id(KEY_PAIR_ID.to_vec()),
+            Attribute::Label(label.clone()),
+            Attribute::EcParams(ED25519_EC_PARAMS.to_vec()),
+            Attribute::Verify(true),
+        ];
+        let private_key_template = [
+            Attribute::Token(true),
+            Attribute::Private(true),
+            Attribute::Id(KEY_PAIR_ID.to_vec()),
+            Attribute::Label(label),
+            Attribute::Sensitive(true),
+            Attribute::Extractable(false),
+            Attribute::Sign(true),
+        ];
+
+        let public_key = {
+            let session = self.session.lock();
+            let (public_key_handle, _) = session.generate_key_pair(
+                &Mechanism::EccEdwardsKeyPairGen,
+                &public_key_template,
+                &private_key_template,
+            )?;
+            let attributes =
+                session.get_attributes(public_key_handle, &[AttributeType::EcPoint])?;
+            match attributes.first() {
+                Some(Attribute::EcPoint(ec_point)) => public_key_from_ec_point(ec_point)?,
+                _ => {
+                    return Err(Error::InternalError(format!(
+                        "Missing public key for token key: {}",
+                        name
+                    )))
+                },
+            }
+        };
+
+        // Track the public key (and the time it was created) alongside the token key
+        self.set(name, public_key.clone())?;
+        Ok(public_key)
+    }
+}
+
+impl KVStorage for Pkcs11Storage {
+    fn available(&self) -> Result<(), Error> {
+        self.session.lock().get_session_info()?;
+        Ok(())
+    }
+
+    fn get<V: DeserializeOwned>(&self, key: &str) -> Result<GetResponse<V>, Error> {
+        let session = self.session.lock();
+        let handle = *Self::find_data_objects(&session, key)?
+            .first()
+            .ok_or_else(|| Error::KeyNotSet(key.to_string()))?;
+        let attributes = session.get_attributes(handle, &[AttributeType::Value])?;
+        let encrypted_value = match attributes.first() {
+            Some(Attribute::Value(value)) => value,
+            _ => return Err(Error::KeyNotSet(key.to_string())),
+        };
+
+        let value = self.decrypt(&session, key, encrypted_value)?;
+        Ok(serde_json::from_slice(&value)?)
+    }
+
+    fn set<V: Serialize>(&mut self, key: &str, value: V) -> Result<(), Error> {
+        let now = self.time_service.now_secs();
+        let value = serde_json::to_vec(&GetResponse::new(value, now))?;
+
+        // Create the new object before destroying the old one, so that a failure
+        // part way through never loses the existing value.
+        let session = self.session.lock();
+        let existing_handles = Self::find_data_objects(&session, key)?;
+        let encrypted_value = self.encrypt(&session, key, &value)?;
+        session.create_object(&[
+            Attribute::Class(ObjectClass::DATA),
+            Attribute::Token(true),
+            Attribute::Private(true),
+            Attribute::Application(APPLICATION.to_vec()),
+            Attribute::Label(key.as_bytes().to_vec()),
+            Attribute::Value(encrypted_value),
+        ])?;
+        for handle in existing_handles {
+            session.destroy_object(handle)?;
+        }
+        Ok(())
+    }
+
+    #[cfg(any(test, feature = "testing"))]
+    fn reset_and_clear(&mut self) -> Result<(), Error> {
+        let session = self.session.lock();
+        let mut handles = session.find_objects(&[
+            Attribute::Class(ObjectClass::DATA),
+            Attribute::Application(APPLICATION.to_vec()),
+        ])?;
+        handles.extend(session.find_objects(&[Attribute::Id(KEY_PAIR_ID.to_vec())])?);
+        for handle in handles {
+            session.destroy_object(handle)?;
+        }
+        Ok(())
+    }
+}
+
+impl CryptoStorage for Pkcs11Storage {
+    fn create_key(&mut self, name: &str) -> Result<Ed25519PublicKey, Error> {
+        if self.token_private_key(name)?.is_some() {
+            return Err(Error::KeyAlreadyExists(name.into()));
+        }
+
+        if self.extractable_keys {
+            let (private_key, public_key) = new_ed25519_key_pair();
+            self.import_private_key(name, private_key)?;
+            Ok(public_key)
+        } else {
+            self.generate_token_key_pair(name)
+        }
+    }
+
+    fn export_private_key(&self, name: &str) -> Result<Ed25519PrivateKey, Error> {
+        if self.token_private_key(name)?.is_some() {
+            return Err(Error::PermissionDenied);
+        }
+        self.get(name).map(|v| v.value)
+    }
+
+    fn import_private_key(&mut self, name: &str, key: Ed25519PrivateKey) -> Result<(), Error> {
+        if self.token_private_key(name)?.is_some() {
+            return Err(Error::KeyAlreadyExists(name.into()));
+        }
+        self.set(name, key)
+    }
+
+    fn export_private_key_for_version(
+        &self,
+        name: &str,
+        version: Ed25519PublicKey,
+    ) -> Result<Ed25519PrivateKey, Error> {
+        let versioned_name = self.get_versioned_name(name, &version)?;
+        self.export_private_key(&versioned_name)
+    }
+
+    fn get_public_key(&self, name: &str) -> Result<PublicKeyResponse, Error> {
+        if self.token_private_key(name)?.is_some() {
+            let response = self.get(name)?;
+            return Ok(PublicKeyResponse {
+                last_update: response.last_update,
+                public_key: response.value,
+            });
+        }
+
+        let response = self.get(name)?;
+        let key: Ed25519PrivateKey = response.value;
+        Ok(PublicKeyResponse {
+            last_update: response.last_update,
+            public_key: key.public_key(),
+        })
+    }
+
+    fn get_public_key_previous_version(&self, name: &str) -> Result<Ed25519PublicKey, Error> {
+        match self.get_public_key(&get_previous_version_name(name)) {
+            Ok(response) => Ok(response.public_key),
+            Err(Error::KeyNotSet(_)) => Err(Error::KeyVersionNotFound(
+                name.into(),
+                "previous version".into(),
+            )),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn rotate_key(&mut self, name: &str) -> Result<Ed25519PublicKey, Error> {
+        let previous_name = get_previous_version_name(name);
+        if self.token_private_key(name)?.is_none() {
+            // The key is wrapped, so rotate it in the same way as any other KV backed key
+            let private_key: Ed25519PrivateKey = self.get(name)?.value;
+            let (new_private_key, new_public_key) = new_ed25519_key_pair();
+            self.set(&previous_name, private_key)?;
+            self.set(name, new_private_key)?;
+            return Ok(new_public_key);
+        }
+
+        // Relabel the current token key pair as the previous version
+        let public_key = self.get_public_key(name)?.public_key;
+        {
+            let session = self.session.lock();
+            Self::destroy_token_key_pair(&session, &previous_name)?;
+            for class in [ObjectClass::PRIVATE_KEY, ObjectClass::PUBLIC_KEY] {
+                for handle in Self::find_key_objects(&session, name, class)? {
+                    session.update_attributes(handle, &[Attribute::Label(
+                        previous_name.as_bytes().to_vec(),
+                    )])?;
+                }
+            }
+        }
+        self.set(&previous_name, public_key)?;
+
+        self.generate_token_key_pair(name)
+    }
+
+    fn sign<T: CryptoHash + Serialize>(
+        &self,
+        name: &str,
+        message: &T,
+    ) -> Result<Ed25519Signature, Error> {
+        match self.token_private_key(name)? {
+            Some(handle) => {
+                let message = signing_message(message)
+                    .map_err(|err| Error::SerializationError(err.to_string()))?;
+                let signature = self
+                    .session
+                    .lock()
+                    .sign(&Mechanism::Eddsa, handle, &message)?;
+                Ed25519Signature::try_from(signature.as_slice())
+                    .map_err(|err| Error::SerializationError(err.to_string()))
+            },
+            None => self
+                .export_private_key(name)?
+                .sign(message)
+                .map_err(|err| Error::SerializationError(err.to_string())),
+        }
+    }
+
+    fn sign_using_version<T: CryptoHash + Serialize>(
+        &self,
+        name: &str,
+        version: Ed25519PublicKey,
+        message: &T,
+    ) -> Result<Ed25519Signature, Error> {
+        let versioned_name = self.get_versioned_name(name, &version)?;
+        self.sign(&versioned_name, message)
+    }
+}
+
+impl Pkcs11Storage {
+    /// Returns the name under which the given version of the named key is stored
+    fn get_versioned_name(&self, name: &str, version: &Ed25519PublicKey) -> Result<String, Error> {
+        if &self.get_public_key(name)?.public_key == version {
+            return Ok(name.into());
+        }
+
+        let previous_name = get_previous_version_name(name);
+        match self.get_public_key(&previous_name) {
+            Ok(response) if &response.public_key == version => Ok(previous_name),
+            Ok(_) | Err(Error::KeyNotSet(_)) => {
+                Err(Error::KeyVersionNotFound(name.into(), version.to_string()))
+            },
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// Returns the context of the given PKCS#11 library, loading and initializing it if required
+fn get_or_load_library(library_path: &Path) -> Result<Pkcs11, Error> {
+    let mut contexts = PKCS11_CONTEXTS.lock();
+    if let Some(pkcs11) = contexts.get(library_path) {
+        return Ok(pkcs11.clone());
+    }
+
+    let pkcs11 = Pkcs11::new(library_path)?;
+    pkcs11.initialize(CInitializeArgs::OsThreads)?;
+    contexts.insert(library_path.to_path_buf(), pkcs11.clone());
+    Ok(pkcs11)
+}
+
+/// Returns the handle of the wrapping key, creating it in the token if it doesn't exist
+fn get_or_create_wrapping_key(session: &Session) -> Result<ObjectHandle, Error> {
+    let template = [
+        Attribute::Class(ObjectClass::SECRET_KEY),
+        Attribute::KeyType(KeyType::AES),
+        Attribute::Label(WRAPPING_KEY_LABEL.to_vec()),
+    ];
+    if let Some(handle) = session.find_objects(&template)?.first() {
+        return Ok(*handle);
+    }
+
+    Ok(session.generate_key(&Mechanism::AesKeyGen, &[
+        Attribute::Token(true),
+        Attribute::Private(true),
+        Attribute::Label(WRAPPING_KEY_LABEL.to_vec()),
+        Attribute::ValueLen(32.into()),
+        Attribute::Sensitive(true),
+        Attribute::Extractable(false),
+        Attribute::Encrypt(true),
+        Attribute::Decrypt(true),
+    ])?)
+}
+
+/// Parses an Ed25519 public key from a CKA_EC_POINT value. Tokens return either the raw key
+/// bytes or the key bytes wrapped in a DER octet string.
+fn public_key_from_ec_point(ec_point: &[u8]) -> Result<Ed25519PublicKey, Error> {
+    let key_bytes = match ec_point {
+        [0x04, 0x20, key_bytes @ ..] if key_bytes.len() == 32 => key_bytes,
+        key_bytes => key_bytes,
+    };
+    Ed25519PublicKey::try_from(key_bytes).map_err(|err| Error::SerializationError(err.to_string()))
+}
+
+/// Private helper method to generate a new ed25519 key pair using entropy from the OS.
+fn new_ed25519_key_pair() -> (Ed25519PrivateKey, Ed25519PublicKey) {
+    let mut seed_rng = OsRng;
+    let mut rng = rand::rngs::StdRng::from_seed(seed_rng.gen());
+    let private_key = Ed25519PrivateKey::generate(&mut rng);
+    let public_key = private_key.public_key();
+    (private_key, public_key)
+}
+
+/// Private helper method to get the name of the previous version of the given key pair, as held in
+/// secure cryptographic storage.
+fn get_previous_version_name(name: &str) -> String {
+    format!("{}_previous", name)
+}
@@ -0,0 +1,119 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    tests::suite, CryptoStorage, EncryptedOnDiskStorage, Error, KVStorage, OnDiskStorage, Storage,
+};
+use aptos_temppath::TempPath;
+use std::fs;
+
+const PASSPHRASE: &[u8] = b"correct horse battery staple";
+const VALUE_KEY: &str = "value_key";
+const CRYPTO_KEY: &str = "crypto_key";
+
+#[test]
+fn encrypted_on_disk() {
+    let path_buf = TempPath::new().path().to_path_buf();
+    let mut storage = Storage::from(EncryptedOnDiskStorage::new(path_buf, PASSPHRASE).unwrap());
+    suite::execute_all_storage_tests(&mut storage);
+}
+
+#[test]
+fn test_reopen_and_wrong_passphrase() {
+    // Write a value and a key to the storage
+    let temp_path = TempPath::new();
+    let mut storage = EncryptedOnDiskStorage::new(temp_path.path().into(), PASSPHRASE).unwrap();
+    storage.set(VALUE_KEY, "secret_value".to_string()).unwrap();
+    let public_key = storage.create_key(CRYPTO_KEY).unwrap();
+
+    // Verify nothing is persisted in plaintext
+    let contents = fs::read_to_string(temp_path.path()).unwrap();
+    assert!(!contents.contains(VALUE_KEY));
+    assert!(!contents.contains("secret_value"));
+
+    // Reopen the storage and verify the data is still readable
+    let storage = EncryptedOnDiskStorage::new(temp_path.path().into(), PASSPHRASE).unwrap();
+    assert_eq!(
+        storage.get::<String>(VALUE_KEY).unwrap().value,
+        "secret_value"
+    );
+    assert_eq!(
+        storage.get_public_key(CRYPTO_KEY).unwrap().public_key,
+        public_key
+    );
+
+    // Verify the storage can't be opened with a different passphrase
+    assert_eq!(
+        EncryptedOnDiskStorage::new(temp_path.path().into(), b"wrong passphrase")
+            .err()
+            .unwrap(),
+        Error::PermissionDenied
+    );
+}
+
+#[test]
+fn test_rotate_passphrase() {
+    let temp_path = TempPath::new();
+    let mut storage = EncryptedOnDiskStorage::new(temp_path.path().into(), PASSPHRASE).unwrap();
+    storage.set(VALUE_KEY, 42u64).unwrap();
+
+    // Rotate the passphrase and verify the data is still readable
+    let new_passphrase = b"new passphrase";
+    storage.rotate_passphrase(new_passphrase).unwrap();
+    assert_eq!(storage.get::<u64>(VALUE_KEY).unwrap().value, 42);
+    storage.set(VALUE_KEY, 43u64).unwrap();
+
+    // Verify only the new passphrase can open the storage
+    EncryptedOnDiskStorage::new(temp_path.path().into(), PASSPHRASE)
+        .err()
+        .unwrap();
+    let storage = EncryptedOnDiskStorage::new(temp_path.path().into(), new_passphrase).unwrap();
+    assert_eq!(storage.get::<u64>(VALUE_KEY).unwrap().value, 43);
+}
+
+#[test]
+fn test_import_from_plaintext() {
+    // Write data to a plaintext storage
+    let plaintext_path = TempPath::new();
+    let mut plaintext_storage = OnDiskStorage::new(plaintext_path.path().into());
+    plaintext_storage.set(VALUE_KEY, 10u64).unwrap();
+    let public_key = plaintext_storage.create_key(CRYPTO_KEY).unwrap();
+    let last_update = plaintext_storage.get::<u64>(VALUE_KEY).unwrap().last_update;
+
+    // Import the data into an encrypted storage and verify it matches
+    let temp_path = TempPath::new();
+    let mut storage = EncryptedOnDiskStorage::new(temp_path.path().into(), PASSPHRASE).unwrap();
+    assert_eq!(
+        storage
+            .import_from_plaintext(plaintext_path.path())
+            .unwrap(),
+        2
+    );
+    let value = storage.get::<u64>(VALUE_KEY).unwrap();
+    assert_eq!(value.value, 10);
+    assert_eq!(value.last_update, last_update);
+    assert_eq!(
+        storage.get_public_key(CRYPTO_KEY).unwrap().public_key,
+        public_key
+    );
+}
+
+#[test]
+fn test_excessive_kdf_params() {
+    let temp_path = TempPath::new();
+    EncryptedOnDiskStorage::new(temp_path.path().into(), PASSPHRASE).unwrap();
+
+    // Tamper with the key derivation parameters of the sealed file
+    let contents = fs::read_to_string(temp_path.path()).unwrap();
+    let mut sealed_data: serde_json::Value = serde_json::from_str(&contents).unwrap();
+    sealed_data["kdf"]["memory_cost_kib"] = u32::MAX.into();
+    fs::write(temp_path.path(), serde_json::to_vec(&sealed_data).unwrap()).unwrap();
+
+    // Verify the storage refuses to derive a key with the tampered parameters
+    match EncryptedOnDiskStorage::new(temp_path.path().into(), PASSPHRASE) {
+        Err(Error::SerializationError(error)) => {
+            assert!(error.contains("exceed the maximum allowed"))
+        },
+        result => panic!("Expected a serialization error, got: {:?}", result.err()),
+    }
+}
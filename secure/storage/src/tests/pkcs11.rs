@@ -0,0 +1,141 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{tests::suite, CryptoStorage, Error, KVStorage, Namespaced, Pkcs11Storage, Storage};
+use aptos_crypto::{test_utils::TestAptosCrypto, Signature};
+use std::{env, path::PathBuf};
+
+/// Pkcs11Storage environment variables (see `docker/testutils/init_softhsm_token.sh`)
+const PKCS11_LIBRARY: &str = "APTOS_PKCS11_LIBRARY";
+const PKCS11_TOKEN_LABEL: &str = "APTOS_PKCS11_TOKEN_LABEL";
+const PKCS11_PIN: &str = "APTOS_PKCS11_PIN";
+
+/// Pkcs11Storage namespace constants
+const PKCS11_NAMESPACE_1: &str = "namespace_1";
+const PKCS11_NAMESPACE_2: &str = "namespace_2";
+
+/// Pkcs11Storage key names
+const CRYPTO_KEY: &str = "crypto_key";
+
+/// This holds the canonical list of PKCS#11 storage tests. Similar to vault, each test uses the
+/// same token, so the tests are run sequentially and the storage is reset after each test.
+const PKCS11_TESTS: &[fn()] = &[
+    test_suite_multiple_namespaces,
+    test_suite_no_namespaces,
+    test_token_key_export_denied,
+    test_token_key_sign_and_rotate,
+];
+
+/// A test for verifying Pkcs11Storage properly implements the SecureStorage API. This test
+/// depends on an initialized PKCS#11 token (e.g., SoftHSM), which can be created by using the
+/// provided script in `docker/testutils/init_softhsm_token.sh`.
+#[test]
+fn execute_storage_tests_pkcs11() {
+    if env::var(PKCS11_LIBRARY).is_err() {
+        return;
+    }
+    let mut storage = create_pkcs11_storage(false);
+    storage.reset_and_clear().unwrap();
+
+    for test in PKCS11_TESTS.iter() {
+        test();
+        storage.reset_and_clear().unwrap();
+    }
+}
+
+/// Runs the test suite on a Pkcs11Storage instance that does not use distinct namespaces.
+/// The suite exports keys, so keys must be extractable.
+fn test_suite_no_namespaces() {
+    let mut storage = Storage::from(create_pkcs11_storage(true));
+    suite::execute_all_storage_tests(&mut storage);
+}
+
+/// Runs the test suite on Pkcs11Storage instances that use distinct namespaces
+fn test_suite_multiple_namespaces() {
+    let mut storage_1 = Storage::from(Namespaced::new(
+        PKCS11_NAMESPACE_1,
+        Box::new(Storage::from(create_pkcs11_storage(true))),
+    ));
+    let mut storage_2 = Storage::from(Namespaced::new(
+        PKCS11_NAMESPACE_2,
+        Box::new(Storage::from(create_pkcs11_storage(true))),
+    ));
+
+    suite::execute_all_storage_tests(&mut storage_1);
+    suite::execute_all_storage_tests(&mut storage_2);
+}
+
+/// Verifies that keys generated inside the token cannot be exported or overwritten
+fn test_token_key_export_denied() {
+    let mut storage = create_pkcs11_storage(false);
+    let public_key = storage.create_key(CRYPTO_KEY).unwrap();
+
+    assert_eq!(
+        storage.export_private_key(CRYPTO_KEY).unwrap_err(),
+        Error::PermissionDenied
+    );
+    assert_eq!(
+        storage
+            .export_private_key_for_version(CRYPTO_KEY, public_key)
+            .unwrap_err(),
+        Error::PermissionDenied
+    );
+    assert_eq!(
+        storage.create_key(CRYPTO_KEY).unwrap_err(),
+        Error::KeyAlreadyExists(CRYPTO_KEY.into())
+    );
+}
+
+/// Verifies that keys generated inside the token sign correctly, and that previous versions
+/// remain usable after a rotation.
+fn test_token_key_sign_and_rotate() {
+    let mut storage = create_pkcs11_storage(false);
+    let public_key = storage.create_key(CRYPTO_KEY).unwrap();
+    assert_eq!(
+        storage.get_public_key(CRYPTO_KEY).unwrap().public_key,
+        public_key
+    );
+
+    // Sign a message inside the token and verify the signature
+    let message = TestAptosCrypto("Hello, World".to_string());
+    let signature = storage.sign(CRYPTO_KEY, &message).unwrap();
+    signature.verify(&message, &public_key).unwrap();
+
+    // Rotate the key and verify both versions can sign
+    let new_public_key = storage.rotate_key(CRYPTO_KEY).unwrap();
+    assert_ne!(new_public_key, public_key);
+    assert_eq!(
+        storage.get_public_key_previous_version(CRYPTO_KEY).unwrap(),
+        public_key
+    );
+    let new_signature = storage.sign(CRYPTO_KEY, &message).unwrap();
+    new_signature.verify(&message, &new_public_key).unwrap();
+    let previous_signature = storage
+        .sign_using_version(CRYPTO_KEY, public_key, &message)
+        .unwrap();
+    assert_eq!(previous_signature, signature);
+
+    // Rotate again and verify the oldest version is no longer available
+    let newest_public_key = storage.rotate_key(CRYPTO_KEY).unwrap();
+    storage
+        .sign_using_version(CRYPTO_KEY, new_public_key, &message)
+        .unwrap()
+        .verify(&message, &new_public_key)
+        .unwrap();
+    storage
+        .sign_using_version(CRYPTO_KEY, newest_public_key, &message)
+        .unwrap();
+    storage
+        .sign_using_version(CRYPTO_KEY, public_key, &message)
+        .unwrap_err();
+}
+
+fn create_pkcs11_storage(extractable_keys: bool) -> Pkcs11Storage {
+    Pkcs11Storage::new(
+        &PathBuf::from(env::var(PKCS11_LIBRARY).unwrap()),
+        &env::var(PKCS11_TOKEN_LABEL).unwrap(),
+        env::var(PKCS11_PIN).unwrap(),
+        extractable_keys,
+    )
+    .unwrap()
+}
@@ -36,13 +36,16 @@ use aptos_storage_service_types::{
     requests::{
         DataRequest, EpochEndingLedgerInfoRequest, NewTransactionOutputsWithProofRequest,
         NewTransactionsOrOutputsWithProofRequest, NewTransactionsWithProofRequest,
-        StateValuesWithProofRequest, StorageServiceRequest,
-        SubscribeTransactionOutputsWithProofRequest,
+        StateValuesByFilterWithProofRequest, StateValuesFilter, StateValuesWithProofRequest,
+        StorageServiceRequest, SubscribeTransactionOutputsWithProofRequest,
         SubscribeTransactionsOrOutputsWithProofRequest, SubscribeTransactionsWithProofRequest,
         SubscriptionStreamMetadata, TransactionOutputsWithProofRequest,
         TransactionsOrOutputsWithProofRequest, TransactionsWithProofRequest,
     },
-    responses::{StorageServerSummary, StorageServiceResponse, TransactionOrOutputListWithProof},
+    responses::{
+        ServerProtocolVersion, StateValuesByFilterWithProof, StorageServerSummary,
+        StorageServiceResponse, TransactionOrOutputListWithProof,
+    },
     Epoch, StorageServiceMessage,
 };
 use aptos_time_service::TimeService;
@@ -203,6 +206,63 @@ impl AptosDataClient {
         self.peer_states.update_summary(peer, summary)
     }
 
+    /// Update a peer's storage server protocol version
+    pub fn update_peer_server_protocol_version(&self, peer: PeerNetworkId, protocol_version: u64) {
+        self.peer_states
+            .update_server_protocol_version(peer, protocol_version)
+    }
+
+    /// Fetches the storage server protocol version of every connected peer
+    /// whose version is still unknown. This allows requests that require a
+    /// newer protocol version to be routed only to peers that support them
+    /// (peers are never sent requests they may be unable to decode).
+    async fn fetch_unknown_server_protocol_versions(&self, request_timeout_ms: u64) {
+        // Identify the connected peers with unknown protocol versions
+        let connected_peers = match self.get_all_connected_peers() {
+            Ok(connected_peers) => connected_peers,
+            Err(_) => return, // There are no peers to query
+        };
+        let peer_to_states = self.peer_states.get_peer_to_states();
+        let peers_to_query = connected_peers.into_iter().filter(|peer| {
+            peer_to_states.get(peer).map_or(true, |peer_state| {
+                peer_state.get_server_protocol_version().is_none()
+            })
+        });
+
+        // Request the protocol versions from the peers concurrently
+        let mut pending_requests = FuturesUnordered::new();
+        for peer in peers_to_query {
+            let data_request = DataRequest::GetServerProtocolVersion;
+            let storage_request =
+                StorageServiceRequest::new(data_request, self.data_client_config.use_compression);
+            pending_requests.push(async move {
+                let result: crate::error::Result<Response<ServerProtocolVersion>> = self
+                    .send_request_to_peer_and_decode(peer, storage_request, request_timeout_ms)
+                    .await;
+                (peer, result)
+            });
+        }
+
+        // Update the protocol versions of the peers that responded
+        while let Some((peer, result)) = pending_requests.next().await {
+            match result {
+                Ok(response) => {
+                    let protocol_version = response.into_payload().protocol_version;
+                    self.update_peer_server_protocol_version(peer, protocol_version);
+                },
+                Err(error) => {
+                    warn!(
+                        (LogSchema::new(LogEntry::StorageServiceResponse)
+                            .event(LogEvent::ResponseError)
+                            .message("Failed to fetch the server protocol version!")
+                            .error(&error)
+                            .peer(&peer))
+                    );
+                },
+            }
+        }
+    }
+
     /// Recompute and update the global data summary cache
     pub fn update_global_summary_cache(&self) -> crate::error::Result<(), Error> {
         // Before calculating the summary, we should garbage collect
@@ -1013,6 +1073,31 @@ impl AptosDataClientInterface for AptosDataClient {
             .await
     }
 
+    async fn get_state_values_by_filter_with_proof(
+        &self,
+        version: u64,
+        start_index: u64,
+        end_index: u64,
+        filter: StateValuesFilter,
+        request_timeout_ms: u64,
+    ) -> crate::error::Result<Response<StateValuesByFilterWithProof>> {
+        let data_request =
+            DataRequest::GetStateValuesByFilterWithProof(StateValuesByFilterWithProofRequest {
+                version,
+                start_index,
+                end_index,
+                filter,
+            });
+
+        // Only peers that support filtered state value requests can service
+        // the request, so ensure the protocol versions of all peers are known.
+        self.fetch_unknown_server_protocol_versions(request_timeout_ms)
+            .await;
+
+        self.create_and_send_storage_request(request_timeout_ms, data_request)
+            .await
+    }
+
     async fn get_transaction_outputs_with_proof(
         &self,
         proof_version: Version,
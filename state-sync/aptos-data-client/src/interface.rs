@@ -2,7 +2,11 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{error, error::Error, global_summary::GlobalDataSummary};
-use aptos_storage_service_types::{responses::TransactionOrOutputListWithProof, Epoch};
+use aptos_storage_service_types::{
+    requests::StateValuesFilter,
+    responses::{StateValuesByFilterWithProof, TransactionOrOutputListWithProof},
+    Epoch,
+};
 use aptos_types::{
     ledger_info::LedgerInfoWithSignatures,
     state_store::state_value::StateValueChunkWithProof,
@@ -87,6 +91,21 @@ pub trait AptosDataClientInterface {
         request_timeout_ms: u64,
     ) -> error::Result<Response<StateValueChunkWithProof>>;
 
+    /// Fetches the state values (at the specified version) that match the
+    /// given filter, each with a proof. The server scans the state values
+    /// from start to end index (inclusive) and only returns the matches. In
+    /// some cases, fewer state values may be scanned (e.g., to tolerate
+    /// network or chunk limits). If the data cannot be fetched, an error
+    /// is returned.
+    async fn get_state_values_by_filter_with_proof(
+        &self,
+        version: u64,
+        start_index: u64,
+        end_index: u64,
+        filter: StateValuesFilter,
+        request_timeout_ms: u64,
+    ) -> error::Result<Response<StateValuesByFilterWithProof>>;
+
     /// Fetches a transaction output list with proof, with transaction
     /// outputs from start to end versions (inclusive). The proof is relative
     /// to the specified `proof_version`. In some cases, fewer outputs may be
@@ -14,6 +14,7 @@ use aptos_config::{
 use aptos_logger::prelude::*;
 use aptos_storage_service_types::{
     requests::StorageServiceRequest, responses::StorageServerSummary,
+    STATE_VALUES_BY_FILTER_PROTOCOL_VERSION,
 };
 use aptos_time_service::TimeService;
 use dashmap::DashMap;
@@ -73,6 +74,10 @@ pub struct PeerState {
     /// The number of requests sent to this peer (by data request label)
     sent_requests_by_type: Arc<DashMap<String, u64>>,
 
+    /// The storage server protocol version run by this peer, or `None` if
+    /// we haven't requested it yet.
+    server_protocol_version: Option<u64>,
+
     /// The latest observed advertised data for this peer, or `None` if we
     /// haven't polled them yet.
     storage_summary: Option<StorageServerSummary>,
@@ -87,6 +92,7 @@ impl PeerState {
             data_client_config,
             received_responses_by_type: Arc::new(DashMap::new()),
             sent_requests_by_type: Arc::new(DashMap::new()),
+            server_protocol_version: None,
             storage_summary: None,
             score: STARTING_SCORE,
         }
@@ -115,6 +121,11 @@ impl PeerState {
         self.score
     }
 
+    /// Returns the storage server protocol version run by the peer (if known)
+    pub fn get_server_protocol_version(&self) -> Option<u64> {
+        self.server_protocol_version
+    }
+
     /// Returns the storage summary for the peer
     pub fn get_storage_summary(&self) -> Option<StorageServerSummary> {
         self.storage_summary.clone()
@@ -148,6 +159,13 @@ impl PeerState {
         }
     }
 
+    /// Returns true iff the peer is known to support filtered state value requests
+    fn supports_state_values_by_filter(&self) -> bool {
+        self.server_protocol_version.map_or(false, |version| {
+            version >= STATE_VALUES_BY_FILTER_PROTOCOL_VERSION
+        })
+    }
+
     /// Returns true iff the peer is currently ignored
     fn is_ignored(&self) -> bool {
         // Only ignore peers if the config allows it
@@ -173,6 +191,11 @@ impl PeerState {
         self.score = f64::max(self.score * multiplier, MIN_SCORE);
     }
 
+    /// Updates the storage server protocol version for the peer
+    fn update_server_protocol_version(&mut self, server_protocol_version: u64) {
+        self.server_protocol_version = Some(server_protocol_version);
+    }
+
     /// Updates the storage summary for the peer
     fn update_storage_summary(&mut self, storage_summary: StorageServerSummary) {
         self.storage_summary = Some(storage_summary);
@@ -214,6 +237,14 @@ impl PeerStates {
 
         // Check if the peer can service the request
         if let Some(peer_state) = self.peer_to_state.get(peer) {
+            // Filtered state value requests are only sent to peers known
+            // to run a storage server protocol version that supports them.
+            if request.data_request.is_state_values_by_filter_request()
+                && !peer_state.supports_state_values_by_filter()
+            {
+                return false;
+            }
+
             return match peer_state.get_storage_summary_if_not_ignored() {
                 Some(storage_summary) => {
                     storage_summary.can_service(&self.data_client_config, time_service, request)
@@ -329,6 +360,18 @@ impl PeerStates {
             .update_storage_summary(storage_summary);
     }
 
+    /// Updates the storage server protocol version for the given peer
+    pub fn update_server_protocol_version(
+        &self,
+        peer: PeerNetworkId,
+        server_protocol_version: u64,
+    ) {
+        self.peer_to_state
+            .entry(peer)
+            .or_insert(PeerState::new(self.data_client_config.clone()))
+            .update_server_protocol_version(server_protocol_version);
+    }
+
     /// Garbage collects the peer states to remove data for disconnected peers
     pub fn garbage_collect_peer_states(&self, connected_peers: HashSet<PeerNetworkId>) {
         self.peer_to_state
@@ -31,7 +31,9 @@ use aptos_storage_interface::DbReader;
 use aptos_storage_service_client::StorageServiceClient;
 use aptos_storage_service_server::network::{NetworkRequest, ResponseSender};
 use aptos_storage_service_types::{
-    responses::TransactionOrOutputListWithProof, Epoch, StorageServiceMessage,
+    requests::StateValuesFilter,
+    responses::{StateValuesByFilterWithProof, TransactionOrOutputListWithProof},
+    Epoch, StorageServiceMessage,
 };
 use aptos_time_service::{MockTimeService, TimeService};
 use aptos_types::{
@@ -315,6 +317,15 @@ mock! {
             request_timeout_ms: u64,
         ) -> Result<Response<StateValueChunkWithProof>>;
 
+        async fn get_state_values_by_filter_with_proof(
+            &self,
+            version: u64,
+            start_index: u64,
+            end_index: u64,
+            filter: StateValuesFilter,
+            request_timeout_ms: u64,
+        ) -> Result<Response<StateValuesByFilterWithProof>>;
+
         async fn get_transaction_outputs_with_proof(
             &self,
             proof_version: Version,
@@ -14,13 +14,20 @@ use aptos_config::{
     config::{AptosDataClientConfig, AptosDataMultiFetchConfig},
     network_id::{NetworkId, PeerNetworkId},
 };
+use aptos_crypto::HashValue;
 use aptos_storage_service_server::network::NetworkRequest;
 use aptos_storage_service_types::{
-    requests::DataRequest,
-    responses::{CompleteDataRange, DataResponse, StorageServerSummary, StorageServiceResponse},
-    StorageServiceError,
+    requests::{
+        DataRequest, StateValuesByFilterWithProofRequest, StateValuesFilter, StorageServiceRequest,
+    },
+    responses::{
+        CompleteDataRange, DataResponse, ServerProtocolVersion, StateValuesByFilterWithProof,
+        StorageServerSummary, StorageServiceResponse,
+    },
+    StorageServiceError, STATE_VALUES_BY_FILTER_PROTOCOL_VERSION,
 };
-use aptos_types::transaction::TransactionListWithProof;
+use aptos_time_service::TimeService;
+use aptos_types::{account_address::AccountAddress, transaction::TransactionListWithProof};
 use claims::{assert_err, assert_matches, assert_ok};
 use maplit::hashset;
 use rand::{rngs::OsRng, Rng};
@@ -678,6 +685,112 @@ async fn single_good_peer_across_priorities() {
     }
 }
 
+#[tokio::test]
+async fn state_values_by_filter_protocol_version() {
+    // Create the mock network and client
+    let (mut mock_network, _, client, _) = MockNetwork::new(None, None, None);
+
+    // Add an old and a new peer (with the same priority, on the same network)
+    let (old_peer, network_id) =
+        utils::add_peer_to_network(PeerPriority::HighPriority, &mut mock_network);
+    let (new_peer, new_network_id) =
+        utils::add_peer_to_network(PeerPriority::HighPriority, &mut mock_network);
+    assert_eq!(network_id, new_network_id);
+
+    // Advertise states for both peers (at version 100)
+    let version = 100;
+    let mut storage_summary = utils::create_storage_summary(version);
+    storage_summary.data_summary.states = Some(CompleteDataRange::new(0, version).unwrap());
+    client.update_peer_storage_summary(old_peer, storage_summary.clone());
+    client.update_peer_storage_summary(new_peer, storage_summary);
+    client.update_global_summary_cache().unwrap();
+
+    // Verify neither peer can service a filter request (their protocol versions are unknown)
+    let filter = StateValuesFilter::new(vec![AccountAddress::ONE], vec![]);
+    let storage_request = StorageServiceRequest::new(
+        DataRequest::GetStateValuesByFilterWithProof(StateValuesByFilterWithProofRequest {
+            version,
+            start_index: 0,
+            end_index: 10,
+            filter: filter.clone(),
+        }),
+        true,
+    );
+    let peer_states = client.get_peer_states();
+    for peer in [old_peer, new_peer] {
+        assert!(!peer_states.can_service_request(&peer, TimeService::mock(), &storage_request));
+    }
+
+    // Spawn a handler for both peers
+    tokio::spawn(async move {
+        while let Some(network_request) = mock_network.next_request(network_id).await {
+            // Determine the response based on the request and the peer
+            let peer_network_id = network_request.peer_network_id;
+            let data_response = match network_request.storage_service_request.data_request {
+                DataRequest::GetServerProtocolVersion => {
+                    let protocol_version = if peer_network_id == old_peer {
+                        STATE_VALUES_BY_FILTER_PROTOCOL_VERSION - 1
+                    } else {
+                        STATE_VALUES_BY_FILTER_PROTOCOL_VERSION
+                    };
+                    DataResponse::ServerProtocolVersion(ServerProtocolVersion { protocol_version })
+                },
+                DataRequest::GetStateValuesByFilterWithProof(_) => {
+                    // Verify the old peer is never sent the request
+                    assert_eq!(peer_network_id, new_peer);
+                    DataResponse::StateValuesByFilterWithProof(StateValuesByFilterWithProof {
+                        version,
+                        first_index: 0,
+                        last_index: 10,
+                        root_hash: HashValue::zero(),
+                        state_values: vec![],
+                    })
+                },
+                _ => panic!(
+                    "Unexpected storage request: {:?}",
+                    network_request.storage_service_request
+                ),
+            };
+
+            // Send the response
+            let storage_response = StorageServiceResponse::new(
+                data_response,
+                network_request.storage_service_request.use_compression,
+            )
+            .unwrap();
+            network_request.response_sender.send(Ok(storage_response));
+        }
+    });
+
+    // Send several filter requests and verify the responses
+    let data_client_config = AptosDataClientConfig::default();
+    for _ in 0..5 {
+        let response = client
+            .get_state_values_by_filter_with_proof(
+                version,
+                0,
+                10,
+                filter.clone(),
+                data_client_config.response_timeout_ms,
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.payload.last_index, 10);
+    }
+
+    // Verify the protocol versions were recorded and only the new peer can service the request
+    let peer_to_states = peer_states.get_peer_to_states();
+    assert_eq!(
+        peer_to_states
+            .get(&old_peer)
+            .unwrap()
+            .get_server_protocol_version(),
+        Some(STATE_VALUES_BY_FILTER_PROTOCOL_VERSION - 1)
+    );
+    assert!(!peer_states.can_service_request(&old_peer, TimeService::mock(), &storage_request));
+    assert!(peer_states.can_service_request(&new_peer, TimeService::mock(), &storage_request));
+}
+
 /// Emulates network latencies by sleeping for some amount of time.
 /// If no duration is specified, the sleep duration is randomly chosen.
 async fn emulate_network_latencies(sleep_duration_ms: Option<u64>) {
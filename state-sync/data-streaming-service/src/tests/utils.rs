@@ -18,12 +18,15 @@ use aptos_storage_service_types::{
     requests::{
         DataRequest, EpochEndingLedgerInfoRequest, NewTransactionOutputsWithProofRequest,
         NewTransactionsOrOutputsWithProofRequest, NewTransactionsWithProofRequest,
-        StateValuesWithProofRequest, SubscribeTransactionOutputsWithProofRequest,
+        StateValuesByFilterWithProofRequest, StateValuesFilter, StateValuesWithProofRequest,
+        SubscribeTransactionOutputsWithProofRequest,
         SubscribeTransactionsOrOutputsWithProofRequest, SubscribeTransactionsWithProofRequest,
         SubscriptionStreamMetadata, TransactionOutputsWithProofRequest,
         TransactionsOrOutputsWithProofRequest, TransactionsWithProofRequest,
     },
-    responses::{CompleteDataRange, TransactionOrOutputListWithProof},
+    responses::{
+        CompleteDataRange, StateValuesByFilterWithProof, TransactionOrOutputListWithProof,
+    },
     Epoch,
 };
 use aptos_types::{
@@ -327,6 +330,45 @@ impl AptosDataClientInterface for MockAptosDataClient {
         Ok(create_data_client_response(state_value_chunk_with_proof))
     }
 
+    async fn get_state_values_by_filter_with_proof(
+        &self,
+        version: Version,
+        start_index: u64,
+        end_index: u64,
+        filter: StateValuesFilter,
+        request_timeout_ms: u64,
+    ) -> Result<Response<StateValuesByFilterWithProof>, aptos_data_client::error::Error> {
+        // Verify the request timeout
+        let data_request =
+            DataRequest::GetStateValuesByFilterWithProof(StateValuesByFilterWithProofRequest {
+                version,
+                start_index,
+                end_index,
+                filter,
+            });
+        self.verify_request_timeout_value(request_timeout_ms, false, false, data_request);
+
+        // Emulate network latencies
+        self.emulate_network_latencies().await;
+
+        // Calculate the last index based on if we should limit the chunk size
+        let end_index = self.calculate_last_index(start_index, end_index);
+
+        // Create an empty response (the streaming service never requests filtered states)
+        let state_values_by_filter_with_proof = StateValuesByFilterWithProof {
+            version,
+            first_index: start_index,
+            last_index: end_index,
+            root_hash: HashValue::zero(),
+            state_values: vec![],
+        };
+
+        // Create and send a data client response
+        Ok(create_data_client_response(
+            state_values_by_filter_with_proof,
+        ))
+    }
+
     async fn get_epoch_ending_ledger_infos(
         &self,
         start_epoch: Epoch,
@@ -0,0 +1,642 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    error::Error,
+    logging::{LogEntry, LogSchema},
+};
+use anyhow::{anyhow, ensure};
+use aptos_backup_cli::{
+    backup_types::{
+        epoch_ending::manifest::EpochEndingBackup,
+        state_snapshot::{incremental::StateSnapshotRecordReader, manifest::StateSnapshotBackup},
+        transaction::manifest::{TransactionBackup, TransactionChunk},
+    },
+    metadata::{
+        cache::{sync_and_load, MetadataCacheOpt},
+        view::MetadataView,
+        TransactionBackupMeta,
+    },
+    storage::{
+        command_adapter::{CommandAdapter, CommandAdapterOpt},
+        encrypted::{EncryptedStorage, EncryptionOpt},
+        local_fs::LocalFs,
+        object_storage::{AzureBlobOpt, ObjectStorage, S3Opt},
+        BackupStorage,
+    },
+    utils::{read_record_bytes::ReadRecordBytes, storage_ext::BackupStorageExt},
+};
+use aptos_config::config::{BackupStorageConfig, StateSyncBackupConfig};
+use aptos_crypto::{
+    hash::{CryptoHash, ACCUMULATOR_PLACEHOLDER_HASH},
+    HashValue,
+};
+use aptos_data_streaming_service::{
+    data_notification::{DataNotification, DataPayload},
+    data_stream::{DataStreamId, DataStreamListener},
+};
+use aptos_infallible::Mutex;
+use aptos_logger::prelude::*;
+use aptos_types::{
+    contract_event::ContractEvent,
+    ledger_info::LedgerInfoWithSignatures,
+    proof::{
+        TransactionAccumulatorRangeProof, TransactionInfoListWithProof, TransactionInfoWithProof,
+    },
+    state_store::state_value::StateValueChunkWithProof,
+    transaction::{
+        Transaction, TransactionAuxiliaryData, TransactionInfo, TransactionOutput,
+        TransactionOutputListWithProof, Version,
+    },
+    write_set::WriteSet,
+};
+use futures::{channel::mpsc, SinkExt};
+use std::{
+    future::Future,
+    path::Path,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+use tokio::task::JoinHandle;
+
+// The maximum number of notifications buffered on a backup data stream
+const MAX_BACKUP_STREAM_CHANNEL_SIZE: usize = 10;
+
+/// A source of bootstrapping data backed by a backup storage (as written by
+/// the backup-cli). The data is handed to the bootstrapper through data
+/// streams that look exactly like the streams of the data streaming service,
+/// so that the same verification and storage pipeline can be reused.
+pub struct BackupDataSource {
+    // The config of the backup storage to restore from
+    backup_config: StateSyncBackupConfig,
+
+    // The backup storage (initialized lazily)
+    backup_storage: Option<Arc<dyn BackupStorage>>,
+
+    // The metadata of all backups in the storage (initialized lazily)
+    metadata_view: Option<Arc<MetadataView>>,
+
+    // The task feeding the currently active data stream (if any)
+    active_stream_task: Option<JoinHandle<()>>,
+
+    // The id of the next data stream to create
+    next_data_stream_id: DataStreamId,
+
+    // The id of the next data notification to send
+    next_notification_id: Arc<AtomicU64>,
+
+    // The error of the last data stream whose producer failed (if any)
+    stream_error: Arc<Mutex<Option<(DataStreamId, String)>>>,
+}
+
+impl BackupDataSource {
+    pub fn new(backup_config: StateSyncBackupConfig) -> Self {
+        Self {
+            backup_config,
+            backup_storage: None,
+            metadata_view: None,
+            active_stream_task: None,
+            next_data_stream_id: 0,
+            next_notification_id: Arc::new(AtomicU64::new(0)),
+            stream_error: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Returns the highest epoch that has ended in the backup (if any)
+    pub async fn get_highest_epoch_ending_epoch(&mut self) -> Result<Option<u64>, Error> {
+        let (_, metadata_view) = self.get_backup().await?;
+        Ok(metadata_view
+            .all_epoch_ending_backups()
+            .iter()
+            .map(|backup| backup.last_epoch)
+            .max())
+    }
+
+    /// Returns the versions of all state snapshots in the backup
+    pub async fn get_state_snapshot_versions(&mut self) -> Result<Vec<Version>, Error> {
+        let (_, metadata_view) = self.get_backup().await?;
+        Ok(metadata_view
+            .all_state_snapshot_backups()
+            .iter()
+            .map(|backup| backup.version)
+            .collect())
+    }
+
+    /// Streams all epoch ending ledger infos in the backup, starting at `start_epoch`
+    pub async fn get_all_epoch_ending_ledger_infos(
+        &mut self,
+        start_epoch: u64,
+    ) -> Result<DataStreamListener, Error> {
+        let (backup_storage, metadata_view) = self.get_backup().await?;
+        let epoch_ending_backups = metadata_view
+            .all_epoch_ending_backups()
+            .iter()
+            .filter(|backup| backup.last_epoch >= start_epoch)
+            .cloned()
+            .collect::<Vec<_>>();
+
+        Ok(self.create_data_stream(move |mut sender| async move {
+            let mut next_epoch = start_epoch;
+            for backup in epoch_ending_backups {
+                let manifest: EpochEndingBackup =
+                    backup_storage.load_json_file(&backup.manifest).await?;
+                manifest.verify()?;
+
+                for chunk in manifest.chunks {
+                    if chunk.last_epoch < next_epoch {
+                        continue; // The chunk overlaps with the ones already sent
+                    }
+                    let ledger_infos = read_records::<LedgerInfoWithSignatures>(
+                        &backup_storage,
+                        &chunk.ledger_infos,
+                    )
+                    .await?
+                    .into_iter()
+                    .filter(|ledger_info| ledger_info.ledger_info().epoch() >= next_epoch)
+                    .collect::<Vec<_>>();
+                    next_epoch = chunk.last_epoch + 1;
+
+                    if !ledger_infos.is_empty() {
+                        sender
+                            .send(DataPayload::EpochEndingLedgerInfos(ledger_infos))
+                            .await?;
+                    }
+                }
+            }
+            Ok(())
+        }))
+    }
+
+    /// Streams the single transaction output (and proof) at the version of
+    /// the state snapshot, as required to restore the snapshot. The proof is
+    /// relative to the epoch ending ledger info at the snapshot version.
+    pub async fn get_transaction_output_to_sync(
+        &mut self,
+        version: Version,
+    ) -> Result<DataStreamListener, Error> {
+        let (backup_storage, metadata_view) = self.get_backup().await?;
+        let manifest_handle = metadata_view
+            .expect_state_snapshot(version)
+            .map_err(|error| Error::BackupStorageError(error.to_string()))?
+            .manifest;
+        let transaction_backups = metadata_view.all_transaction_backups().to_vec();
+
+        Ok(self.create_data_stream(move |mut sender| async move {
+            // Load the transaction info (and proof) from the snapshot
+            let manifest: StateSnapshotBackup =
+                backup_storage.load_json_file(&manifest_handle).await?;
+            let (transaction_info_with_proof, _): (
+                TransactionInfoWithProof,
+                LedgerInfoWithSignatures,
+            ) = backup_storage.load_bcs_file(&manifest.proof).await?;
+
+            // Load the transaction and output from the transaction backups
+            let chunk = find_transaction_chunk(&backup_storage, &transaction_backups, version)
+                .await?
+                .ok_or_else(|| anyhow!("No transaction backup found at version {}.", version))?;
+            let (transactions_and_outputs, _) =
+                read_transaction_chunk(&backup_storage, &chunk, version, version).await?;
+
+            // Split the accumulator proof into the siblings on the left and right of the leaf
+            let (mut left_siblings, mut right_siblings) = (vec![], vec![]);
+            for (level, sibling) in transaction_info_with_proof
+                .ledger_info_to_transaction_info_proof()
+                .siblings()
+                .iter()
+                .enumerate()
+            {
+                if (version >> level) & 1 == 1 {
+                    left_siblings.push(*sibling);
+                } else {
+                    right_siblings.push(*sibling);
+                }
+            }
+            let transaction_info_list_with_proof = TransactionInfoListWithProof::new(
+                TransactionAccumulatorRangeProof::new(left_siblings, right_siblings),
+                vec![transaction_info_with_proof.transaction_info().clone()],
+            );
+
+            sender
+                .send(DataPayload::TransactionOutputsWithProof(
+                    TransactionOutputListWithProof::new(
+                        transactions_and_outputs,
+                        Some(version),
+                        transaction_info_list_with_proof,
+                    ),
+                ))
+                .await?;
+            Ok(())
+        }))
+    }
+
+    /// Streams all state values of the snapshot at the given version,
+    /// starting at `start_index`.
+    pub async fn get_all_state_values(
+        &mut self,
+        version: Version,
+        start_index: u64,
+    ) -> Result<DataStreamListener, Error> {
+        let (backup_storage, metadata_view) = self.get_backup().await?;
+        let manifest_handle = metadata_view
+            .expect_state_snapshot(version)
+            .map_err(|error| Error::BackupStorageError(error.to_string()))?
+            .manifest;
+
+        Ok(self.create_data_stream(move |mut sender| async move {
+            let manifest: StateSnapshotBackup =
+                backup_storage.load_json_file(&manifest_handle).await?;
+            let root_hash = manifest.root_hash;
+            let chunks = manifest.chunks.clone();
+            let mut record_reader =
+                StateSnapshotRecordReader::from_manifest(backup_storage.clone(), manifest).await?;
+
+            // Skip the chunks that have already been processed
+            let start_index = start_index as usize;
+            if let Some(last_skipped_chunk) = chunks
+                .iter()
+                .take_while(|chunk| chunk.last_idx < start_index)
+                .last()
+            {
+                record_reader
+                    .skip_until(last_skipped_chunk.last_key)
+                    .await?;
+            }
+
+            for chunk in chunks
+                .into_iter()
+                .skip_while(|chunk| chunk.last_idx < start_index)
+            {
+                let mut raw_values = record_reader
+                    .next_chunk()
+                    .await?
+                    .ok_or_else(|| anyhow!("Missing state snapshot chunk {}.", chunk.blobs))?;
+                let proof = backup_storage.load_bcs_file(&chunk.proof).await?;
+
+                // The first chunk might have been partially processed. Its range
+                // proof still holds for the remaining state values.
+                let (first_index, first_key) = if chunk.first_idx < start_index {
+                    raw_values.drain(..start_index - chunk.first_idx);
+                    let (first_state_key, _) = raw_values
+                        .first()
+                        .ok_or_else(|| anyhow!("Missing state value at index {}.", start_index))?;
+                    (start_index, first_state_key.hash())
+                } else {
+                    (chunk.first_idx, chunk.first_key)
+                };
+
+                sender
+                    .send(DataPayload::StateValuesWithProof(
+                        StateValueChunkWithProof {
+                            first_index: first_index as u64,
+                            last_index: chunk.last_idx as u64,
+                            first_key,
+                            last_key: chunk.last_key,
+                            raw_values,
+                            proof,
+                            root_hash,
+                        },
+                    ))
+                    .await?;
+            }
+            Ok(())
+        }))
+    }
+
+    /// Streams the transaction outputs in the backup starting at `start_version`,
+    /// up to the end of the backup chunk holding `start_version` or the given
+    /// epoch ending ledger info (whichever comes first). Returns the stream and
+    /// the ledger info the outputs are proven against, or None if the backup
+    /// holds no transactions at `start_version`.
+    ///
+    /// The range proofs are rebuilt for the streamed outputs: the left siblings
+    /// are the `frozen_subtree_roots` of the local transaction accumulator (i.e.,
+    /// before `start_version`), and the right siblings are either taken from the
+    /// backup chunk or are placeholders (if the outputs end the epoch).
+    pub async fn get_transaction_outputs(
+        &mut self,
+        start_version: Version,
+        frozen_subtree_roots: Vec<HashValue>,
+        epoch_ending_ledger_info: Option<LedgerInfoWithSignatures>,
+    ) -> Result<Option<(DataStreamListener, LedgerInfoWithSignatures)>, Error> {
+        let (backup_storage, metadata_view) = self.get_backup().await?;
+        let chunk = match find_transaction_chunk(
+            &backup_storage,
+            metadata_view.all_transaction_backups(),
+            start_version,
+        )
+        .await
+        .map_err(|error| Error::BackupStorageError(error.to_string()))?
+        {
+            Some(chunk) => chunk,
+            None => return Ok(None),
+        };
+
+        // Identify the end version and the proof ledger info of the outputs
+        let (chunk_range_proof, chunk_ledger_info): (
+            TransactionAccumulatorRangeProof,
+            LedgerInfoWithSignatures,
+        ) = backup_storage
+            .load_bcs_file(&chunk.proof)
+            .await
+            .map_err(|error| Error::BackupStorageError(error.to_string()))?;
+        let (end_version, proof_ledger_info, right_siblings) = match epoch_ending_ledger_info {
+            Some(epoch_ending_ledger_info)
+                if epoch_ending_ledger_info.ledger_info().version() <= chunk.last_version =>
+            {
+                let end_version = epoch_ending_ledger_info.ledger_info().version();
+                (
+                    end_version,
+                    epoch_ending_ledger_info,
+                    placeholder_right_siblings(end_version),
+                )
+            },
+            _ => (
+                chunk.last_version,
+                chunk_ledger_info,
+                chunk_range_proof.right_siblings().clone(),
+            ),
+        };
+        let range_proof = TransactionAccumulatorRangeProof::new(
+            frozen_subtree_roots.into_iter().rev().collect(),
+            right_siblings,
+        );
+
+        let data_stream = self.create_data_stream(move |mut sender| async move {
+            let (transactions_and_outputs, transaction_infos) =
+                read_transaction_chunk(&backup_storage, &chunk, start_version, end_version).await?;
+            sender
+                .send(DataPayload::TransactionOutputsWithProof(
+                    TransactionOutputListWithProof::new(
+                        transactions_and_outputs,
+                        Some(start_version),
+                        TransactionInfoListWithProof::new(range_proof, transaction_infos),
+                    ),
+                ))
+                .await?;
+            Ok(())
+        });
+        Ok(Some((data_stream, proof_ledger_info)))
+    }
+
+    /// Returns (and clears) the error that failed the given data stream (if any)
+    pub fn take_stream_error(&self, data_stream_id: DataStreamId) -> Option<Error> {
+        let mut stream_error = self.stream_error.lock();
+        match stream_error.as_ref() {
+            Some((failed_stream_id, _)) if *failed_stream_id == data_stream_id => stream_error
+                .take()
+                .map(|(_, error)| Error::BackupStorageError(error)),
+            _ => None,
+        }
+    }
+
+    /// Terminates the currently active data stream (if any)
+    pub fn terminate_stream(&mut self) {
+        if let Some(active_stream_task) = self.active_stream_task.take() {
+            active_stream_task.abort();
+        }
+    }
+
+    /// Returns the backup storage and metadata view, initializing them if required
+    async fn get_backup(&mut self) -> Result<(Arc<dyn BackupStorage>, Arc<MetadataView>), Error> {
+        if let (Some(backup_storage), Some(metadata_view)) =
+            (&self.backup_storage, &self.metadata_view)
+        {
+            return Ok((backup_storage.clone(), metadata_view.clone()));
+        }
+
+        let backup_storage = self
+            .initialize_backup_storage()
+            .await
+            .map_err(|error| Error::BackupStorageError(error.to_string()))?;
+        let metadata_view = sync_and_load(
+            &MetadataCacheOpt::new(self.backup_config.metadata_cache_dir.as_ref()),
+            backup_storage.clone(),
+            self.backup_config.concurrent_downloads,
+        )
+        .await
+        .map_err(|error| {
+            Error::BackupStorageError(format!(
+                "Failed to load the backup metadata! Error: {:?}",
+                error
+            ))
+        })?;
+        let metadata_view = Arc::new(metadata_view);
+        info!(LogSchema::new(LogEntry::BackupDataSource).message(&format!(
+            "Loaded the backup metadata! Backup storage state: {}",
+            metadata_view
+                .get_storage_state()
+                .map(|storage_state| storage_state.to_string())
+                .unwrap_or_default()
+        )));
+
+        self.backup_storage = Some(backup_storage.clone());
+        self.metadata_view = Some(metadata_view.clone());
+        Ok((backup_storage, metadata_view))
+    }
+
+    /// Creates the backup storage specified by the config
+    async fn initialize_backup_storage(&self) -> anyhow::Result<Arc<dyn BackupStorage>> {
+        let backup_storage: Arc<dyn BackupStorage> = match &self.backup_config.backup_storage {
+            Some(BackupStorageConfig::LocalFs(dir)) => Arc::new(LocalFs::new(dir.clone())),
+            Some(BackupStorageConfig::CommandAdapter(config)) => Arc::new(
+                CommandAdapter::new_with_opt(parse_storage_opt::<CommandAdapterOpt>(config)?)
+                    .await?,
+            ),
+            Some(BackupStorageConfig::S3(config)) => {
+                Arc::new(ObjectStorage::new_with_s3_opt(parse_storage_opt::<S3Opt>(config)?).await?)
+            },
+            Some(BackupStorageConfig::AzureBlob(config)) => Arc::new(
+                ObjectStorage::new_with_azure_blob_opt(parse_storage_opt::<AzureBlobOpt>(config)?)
+                    .await?,
+            ),
+            None => return Err(anyhow!("No backup storage is configured!")),
+        };
+
+        let encryption_opt = EncryptionOpt {
+            encryption_key_file: self.backup_config.encryption_key_file.clone(),
+            encryption_key_secure_backend_config: None,
+            encryption_key_name: String::new(),
+        };
+        Ok(match encryption_opt.load_key().await? {
+            Some(key) => Arc::new(EncryptedStorage::new(backup_storage, key)),
+            None => backup_storage,
+        })
+    }
+
+    /// Creates a new data stream fed by the given producer. The producer is
+    /// run on its own task, and the stream is ended once it completes. If the
+    /// producer fails, the error is recorded for `take_stream_error()`.
+    fn create_data_stream<Producer, ProducerFuture>(
+        &mut self,
+        producer: Producer,
+    ) -> DataStreamListener
+    where
+        Producer: FnOnce(BackupDataSender) -> ProducerFuture,
+        ProducerFuture: Future<Output = anyhow::Result<()>> + Send + 'static,
+    {
+        // Terminate any previous stream
+        self.terminate_stream();
+
+        // Create the data stream
+        let data_stream_id = self.next_data_stream_id;
+        self.next_data_stream_id += 1;
+        let (notification_sender, notification_receiver) =
+            mpsc::channel(MAX_BACKUP_STREAM_CHANNEL_SIZE);
+        let mut sender = BackupDataSender {
+            notification_sender,
+            next_notification_id: self.next_notification_id.clone(),
+        };
+
+        // Spawn the producer
+        let producer_future = producer(sender.clone());
+        let stream_error = self.stream_error.clone();
+        self.active_stream_task = Some(tokio::spawn(async move {
+            match producer_future.await {
+                Ok(()) => {
+                    let _ = sender.send(DataPayload::EndOfStream).await;
+                },
+                Err(error) => {
+                    warn!(LogSchema::new(LogEntry::BackupDataSource).message(&format!(
+                        "Failed to stream data from the backup storage! Error: {:?}",
+                        error
+                    )));
+
+                    // Record the error for the bootstrapper. The stream is kept
+                    // open (but idle), as the listener can't handle a closed
+                    // channel. The bootstrapper resets the stream on the error.
+                    *stream_error.lock() = Some((
+                        data_stream_id,
+                        format!(
+                            "Failed to stream data from the backup storage! Error: {:?}",
+                            error
+                        ),
+                    ));
+                    futures::future::pending::<()>().await;
+                },
+            }
+        }));
+
+        DataStreamListener::new(data_stream_id, notification_receiver)
+    }
+}
+
+/// A simple wrapper for sending data notifications along a backup data stream
+#[derive(Clone)]
+struct BackupDataSender {
+    notification_sender: mpsc::Sender<DataNotification>,
+    next_notification_id: Arc<AtomicU64>,
+}
+
+impl BackupDataSender {
+    async fn send(&mut self, data_payload: DataPayload) -> anyhow::Result<()> {
+        let notification_id = self.next_notification_id.fetch_add(1, Ordering::Relaxed);
+        self.notification_sender
+            .send(DataNotification::new(notification_id, data_payload))
+            .await?;
+        Ok(())
+    }
+}
+
+/// Parses the storage option from the given config file path
+fn parse_storage_opt<T: FromStr<Err = &'static str>>(config: &Path) -> anyhow::Result<T> {
+    T::from_str(&config.to_string_lossy()).map_err(|error| anyhow!(error))
+}
+
+/// Returns the right siblings of the last leaf in an accumulator that holds
+/// exactly the leaves up to `last_version` (i.e., all placeholders).
+pub(crate) fn placeholder_right_siblings(last_version: Version) -> Vec<HashValue> {
+    let root_level = u64::BITS - last_version.leading_zeros();
+    (0..root_level)
+        .filter(|level| (last_version >> level) & 1 == 0)
+        .map(|_| *ACCUMULATOR_PLACEHOLDER_HASH)
+        .collect()
+}
+
+/// Returns the transaction backup chunk holding the given version (if any)
+async fn find_transaction_chunk(
+    backup_storage: &Arc<dyn BackupStorage>,
+    transaction_backups: &[TransactionBackupMeta],
+    version: Version,
+) -> anyhow::Result<Option<TransactionChunk>> {
+    let transaction_backup = match transaction_backups
+        .iter()
+        .find(|backup| backup.first_version <= version && version <= backup.last_version)
+    {
+        Some(transaction_backup) => transaction_backup,
+        None => return Ok(None),
+    };
+
+    let manifest: TransactionBackup = backup_storage
+        .load_json_file(&transaction_backup.manifest)
+        .await?;
+    manifest.verify()?;
+    Ok(manifest
+        .chunks
+        .into_iter()
+        .find(|chunk| chunk.first_version <= version && version <= chunk.last_version))
+}
+
+/// Reads the transactions, outputs and infos in the given chunk, between
+/// `start_version` and `end_version` (inclusive).
+async fn read_transaction_chunk(
+    backup_storage: &Arc<dyn BackupStorage>,
+    chunk: &TransactionChunk,
+    start_version: Version,
+    end_version: Version,
+) -> anyhow::Result<(Vec<(Transaction, TransactionOutput)>, Vec<TransactionInfo>)> {
+    ensure!(
+        chunk.first_version <= start_version
+            && start_version <= end_version
+            && end_version <= chunk.last_version,
+        "Versions [{}, {}] are not in the chunk [{}, {}].",
+        start_version,
+        end_version,
+        chunk.first_version,
+        chunk.last_version,
+    );
+
+    let records = read_records::<(Transaction, TransactionInfo, Vec<ContractEvent>, WriteSet)>(
+        backup_storage,
+        &chunk.transactions,
+    )
+    .await?;
+    ensure!(
+        records.len() as u64 == chunk.last_version - chunk.first_version + 1,
+        "Expecting {} transactions in chunk {}, got {}.",
+        chunk.last_version - chunk.first_version + 1,
+        chunk.transactions,
+        records.len(),
+    );
+
+    Ok(records
+        .into_iter()
+        .skip((start_version - chunk.first_version) as usize)
+        .take((end_version - start_version + 1) as usize)
+        .map(|(transaction, transaction_info, events, write_set)| {
+            let transaction_output = TransactionOutput::new(
+                write_set,
+                events,
+                transaction_info.gas_used(),
+                transaction_info.status().clone().into(),
+                TransactionAuxiliaryData::default(),
+            );
+            ((transaction, transaction_output), transaction_info)
+        })
+        .unzip())
+}
+
+/// Reads all BCS serialized records in the given file
+async fn read_records<T: serde::de::DeserializeOwned>(
+    backup_storage: &Arc<dyn BackupStorage>,
+    file_handle: &str,
+) -> anyhow::Result<Vec<T>> {
+    let mut file = backup_storage.open_for_read(file_handle).await?;
+    let mut records = vec![];
+    while let Some(record_bytes) = file.read_record_bytes().await? {
+        records.push(bcs::from_bytes(&record_bytes)?);
+    }
+    Ok(records)
+}
@@ -3,6 +3,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
+    backup_data_source::BackupDataSource,
     driver::DriverConfiguration,
     error::Error,
     logging::{LogEntry, LogSchema},
@@ -288,9 +289,13 @@ impl StateValueSyncer {
 
 /// A simple component that manages the bootstrapping of the node
 pub struct Bootstrapper<MetadataStorage, StorageSyncer, StreamingClient> {
-    // The currently active data stream (provided by the data streaming service)
+    // The currently active data stream (provided by the data streaming service,
+    // or the backup data source if restoring from a backup).
     active_data_stream: Option<DataStreamListener>,
 
+    // The source of data when restoring from a backup
+    backup_data_source: BackupDataSource,
+
     // The channel used to notify a listener of successful bootstrapping
     bootstrap_notifier_channel: Option<oneshot::Sender<Result<(), Error>>>,
 
@@ -344,9 +349,13 @@ impl<
             .expect("Unable to fetch latest epoch state!");
         let verified_epoch_states = VerifiedEpochStates::new(latest_epoch_state);
 
+        // Create the backup data source (only used when restoring from a backup)
+        let backup_data_source = BackupDataSource::new(driver_configuration.backup_config.clone());
+
         Self {
             state_value_syncer: StateValueSyncer::new(),
             active_data_stream: None,
+            backup_data_source,
             bootstrap_notifier_channel: None,
             bootstrapped: false,
             driver_configuration,
@@ -365,6 +374,15 @@ impl<
         self.driver_configuration.config.bootstrapping_mode
     }
 
+    /// Returns true iff the bootstrapper is currently syncing a state snapshot
+    /// (i.e., the transaction output at the snapshot version and the state values).
+    fn is_syncing_state_snapshot(&self) -> bool {
+        let bootstrapping_mode = self.get_bootstrapping_mode();
+        bootstrapping_mode.is_fast_sync()
+            || (bootstrapping_mode.is_restore_from_backup()
+                && self.state_value_syncer.ledger_info_to_sync.is_some())
+    }
+
     /// Returns true iff the node has already completed bootstrapping
     pub fn is_bootstrapped(&self) -> bool {
         self.bootstrapped
@@ -460,13 +478,23 @@ impl<
 
         // Always fetch the new epoch ending ledger infos first
         if self.should_fetch_epoch_ending_ledger_infos() {
-            return self
-                .fetch_epoch_ending_ledger_infos(global_data_summary)
-                .await;
+            return if self.get_bootstrapping_mode().is_restore_from_backup() {
+                self.fetch_epoch_ending_ledger_infos_from_backup().await
+            } else {
+                self.fetch_epoch_ending_ledger_infos(global_data_summary)
+                    .await
+            };
         }
 
-        // Get the highest synced and known ledger info versions
+        // Get the highest synced version
         let highest_synced_version = utils::fetch_latest_synced_version(self.storage.clone())?;
+
+        // If we're restoring from a backup, the network is not consulted
+        if self.get_bootstrapping_mode().is_restore_from_backup() {
+            return self.fetch_missing_backup_data(highest_synced_version).await;
+        }
+
+        // Get the highest known ledger info version
         let highest_known_ledger_info = self.get_highest_known_ledger_info()?;
         let highest_known_ledger_version = highest_known_ledger_info.ledger_info().version();
 
@@ -578,6 +606,124 @@ impl<
         }
     }
 
+    /// Fetches all missing data from the backup storage in order to bootstrap the node
+    async fn fetch_missing_backup_data(
+        &mut self,
+        highest_synced_version: Version,
+    ) -> Result<(), Error> {
+        // If we're syncing a new node, restore the latest state snapshot (if any)
+        if highest_synced_version == GENESIS_TRANSACTION_VERSION {
+            let snapshot_sync_target =
+                match self.metadata_storage.previous_snapshot_sync_target()? {
+                    Some(target) => {
+                        if self.metadata_storage.is_snapshot_sync_complete(&target)? {
+                            None // The snapshot was already restored
+                        } else {
+                            Some((target, true)) // Continue restoring the snapshot
+                        }
+                    },
+                    None => self
+                        .get_latest_backup_snapshot_target()
+                        .await?
+                        .map(|target| (target, false)),
+                };
+            if let Some((target, existing_snapshot_progress)) = snapshot_sync_target {
+                return self
+                    .fetch_missing_state_values(target, existing_snapshot_progress)
+                    .await;
+            }
+        }
+
+        // Any snapshot has now been restored, so we only need transaction outputs
+        if self.state_value_syncer.ledger_info_to_sync.is_some() {
+            self.state_value_syncer = StateValueSyncer::new();
+        }
+
+        // Fetch the outputs that follow the highest synced version (up to the
+        // end of the current epoch, at most). The outputs are proven against
+        // the local transaction accumulator.
+        let next_version = highest_synced_version.checked_add(1).ok_or_else(|| {
+            Error::IntegerOverflow("The next output version has overflown!".into())
+        })?;
+        let accumulator_summary = self
+            .storage
+            .get_accumulator_summary(highest_synced_version)
+            .map_err(|error| {
+                Error::StorageError(format!(
+                    "Failed to get the accumulator summary at version {:?}! Error: {:?}",
+                    highest_synced_version, error
+                ))
+            })?;
+        let epoch_ending_ledger_info = self
+            .verified_epoch_states
+            .next_epoch_ending_version(highest_synced_version)
+            .and_then(|version| {
+                self.verified_epoch_states
+                    .get_epoch_ending_ledger_info(version)
+            });
+        let (data_stream, proof_ledger_info) = match self
+            .backup_data_source
+            .get_transaction_outputs(
+                next_version,
+                accumulator_summary.0.frozen_subtree_roots().clone(),
+                epoch_ending_ledger_info.clone(),
+            )
+            .await?
+        {
+            Some(data_stream_and_proof_ledger_info) => data_stream_and_proof_ledger_info,
+            None => {
+                // There's nothing left in the backup. The continuous syncer
+                // will take control and sync the rest from the network.
+                info!(LogSchema::new(LogEntry::Bootstrapper).message(&format!(
+                    "The backup holds no transactions beyond version {}. Restoring from the backup is complete!",
+                    highest_synced_version
+                )));
+                return self.bootstrapping_complete().await;
+            },
+        };
+
+        // Verify the proof ledger info (unless it's an already verified epoch ending ledger info)
+        let epoch_state = utils::fetch_latest_epoch_state(self.storage.clone())?;
+        if epoch_ending_ledger_info.as_ref() != Some(&proof_ledger_info) {
+            if let Err(error) = epoch_state.verify(&proof_ledger_info) {
+                self.backup_data_source.terminate_stream();
+                return Err(Error::VerificationError(format!(
+                    "The backup ledger info failed verification! Ledger info: {:?}, error: {:?}",
+                    proof_ledger_info, error
+                )));
+            }
+        }
+
+        self.speculative_stream_state = Some(SpeculativeStreamState::new(
+            epoch_state,
+            Some(proof_ledger_info),
+            highest_synced_version,
+        ));
+        self.active_data_stream = Some(data_stream);
+
+        Ok(())
+    }
+
+    /// Returns the epoch ending ledger info of the latest state snapshot in the
+    /// backup (if any). Snapshots that are not at verified epoch ending versions
+    /// are ignored.
+    async fn get_latest_backup_snapshot_target(
+        &mut self,
+    ) -> Result<Option<LedgerInfoWithSignatures>, Error> {
+        let snapshot_versions = self
+            .backup_data_source
+            .get_state_snapshot_versions()
+            .await?;
+        Ok(snapshot_versions
+            .into_iter()
+            .filter(|version| *version > GENESIS_TRANSACTION_VERSION)
+            .filter_map(|version| {
+                self.verified_epoch_states
+                    .get_epoch_ending_ledger_info(version)
+            })
+            .max_by_key(|ledger_info| ledger_info.ledger_info().version()))
+    }
+
     /// Attempts to fetch a data notification from the active stream
     async fn fetch_next_data_notification(&mut self) -> Result<DataNotification, Error> {
         let max_stream_wait_time_ms = self.driver_configuration.config.max_stream_wait_time_ms;
@@ -588,6 +734,22 @@ impl<
             self.active_data_stream.as_mut(),
         )
         .await;
+        if result.is_err() && self.get_bootstrapping_mode().is_restore_from_backup() {
+            // If the backup stream failed, reset it and return the error (instead
+            // of waiting for the stream to time out too many times).
+            let stream_error = self
+                .active_data_stream
+                .as_ref()
+                .and_then(|active_data_stream| {
+                    self.backup_data_source
+                        .take_stream_error(active_data_stream.data_stream_id)
+                });
+            if let Some(stream_error) = stream_error {
+                warn!("Resetting the currently active backup data stream due to an error!");
+                self.reset_active_stream(None).await?;
+                return Err(stream_error);
+            }
+        }
         if matches!(result, Err(Error::CriticalDataStreamTimeout(_))) {
             // If the stream has timed out too many times, we need to reset it
             warn!("Resetting the currently active data stream due to too many timeouts!");
@@ -683,15 +845,22 @@ impl<
 
         // Fetch the data that we're missing
         let target_ledger_info_version = target_ledger_info.ledger_info().version();
+        let restore_from_backup = self.get_bootstrapping_mode().is_restore_from_backup();
         let data_stream = if self.state_value_syncer.transaction_output_to_sync.is_none() {
             // Fetch the transaction info first, before the states
-            self.streaming_client
-                .get_all_transaction_outputs(
-                    target_ledger_info_version,
-                    target_ledger_info_version,
-                    target_ledger_info_version,
-                )
-                .await?
+            if restore_from_backup {
+                self.backup_data_source
+                    .get_transaction_output_to_sync(target_ledger_info_version)
+                    .await?
+            } else {
+                self.streaming_client
+                    .get_all_transaction_outputs(
+                        target_ledger_info_version,
+                        target_ledger_info_version,
+                        target_ledger_info_version,
+                    )
+                    .await?
+            }
         } else {
             // Identify the next state index to fetch
             let next_state_index_to_process = if existing_snapshot_progress {
@@ -715,12 +884,18 @@ impl<
             // Fetch the missing state values
             self.state_value_syncer
                 .update_next_state_index_to_process(next_state_index_to_process);
-            self.streaming_client
-                .get_all_state_values(
-                    target_ledger_info_version,
-                    Some(next_state_index_to_process),
-                )
-                .await?
+            if restore_from_backup {
+                self.backup_data_source
+                    .get_all_state_values(target_ledger_info_version, next_state_index_to_process)
+                    .await?
+            } else {
+                self.streaming_client
+                    .get_all_state_values(
+                        target_ledger_info_version,
+                        Some(next_state_index_to_process),
+                    )
+                    .await?
+            }
         };
         self.active_data_stream = Some(data_stream);
 
@@ -827,20 +1002,7 @@ impl<
             })?;
 
         // Fetch the highest epoch end known locally
-        let highest_known_ledger_info = self.get_highest_known_ledger_info()?;
-        let highest_known_ledger_info = highest_known_ledger_info.ledger_info();
-        let highest_local_epoch_end = if highest_known_ledger_info.ends_epoch() {
-            highest_known_ledger_info.epoch()
-        } else if highest_known_ledger_info.epoch() > 0 {
-            highest_known_ledger_info
-                .epoch()
-                .checked_sub(1)
-                .ok_or_else(|| {
-                    Error::IntegerOverflow("The highest local epoch end has overflown!".into())
-                })?
-        } else {
-            unreachable!("Genesis should always end the first epoch!");
-        };
+        let highest_local_epoch_end = self.get_highest_local_epoch_end()?;
 
         // Compare the highest local epoch end to the highest advertised epoch end
         if highest_local_epoch_end < highest_advertised_epoch_end {
@@ -873,6 +1035,77 @@ impl<
         Ok(())
     }
 
+    /// Fetches all epoch ending ledger infos (from the current epoch to the
+    /// maximum that can be found in the backup storage).
+    async fn fetch_epoch_ending_ledger_infos_from_backup(&mut self) -> Result<(), Error> {
+        // If our storage has already synced beyond our waypoint, the waypoint is verified
+        let latest_ledger_info = utils::fetch_latest_synced_ledger_info(self.storage.clone())?;
+        let waypoint_version = self.driver_configuration.waypoint.version();
+        if latest_ledger_info.ledger_info().version() >= waypoint_version {
+            self.verified_epoch_states
+                .set_verified_waypoint(waypoint_version);
+        }
+
+        // Compare the highest local epoch end to the highest epoch end in the backup
+        let highest_local_epoch_end = self.get_highest_local_epoch_end()?;
+        let highest_backup_epoch_end = self
+            .backup_data_source
+            .get_highest_epoch_ending_epoch()
+            .await?;
+        match highest_backup_epoch_end {
+            Some(highest_backup_epoch_end)
+                if highest_local_epoch_end < highest_backup_epoch_end =>
+            {
+                info!(LogSchema::new(LogEntry::Bootstrapper).message(&format!(
+                    "Found higher epoch ending ledger infos in the backup! Local: {:?}, backup: {:?}",
+                    highest_local_epoch_end, highest_backup_epoch_end
+                )));
+                let next_epoch_end = highest_local_epoch_end.checked_add(1).ok_or_else(|| {
+                    Error::IntegerOverflow("The next epoch end has overflown!".into())
+                })?;
+                let epoch_ending_stream = self
+                    .backup_data_source
+                    .get_all_epoch_ending_ledger_infos(next_epoch_end)
+                    .await?;
+                self.active_data_stream = Some(epoch_ending_stream);
+            },
+            _ if self.verified_epoch_states.verified_waypoint() => {
+                info!(LogSchema::new(LogEntry::Bootstrapper)
+                    .message("No new epoch ending ledger infos to fetch from the backup!"));
+                self.verified_epoch_states
+                    .set_fetched_epoch_ending_ledger_infos();
+            },
+            _ => {
+                return Err(Error::UnsatisfiableWaypoint(format!(
+                    "Our waypoint is unverified, but the backup holds no higher epoch ending ledger infos! \
+                    Highest local epoch end: {:?}, highest backup epoch end: {:?}",
+                    highest_local_epoch_end, highest_backup_epoch_end
+                )));
+            },
+        }
+
+        Ok(())
+    }
+
+    /// Returns the highest epoch that has ended (known locally)
+    fn get_highest_local_epoch_end(&self) -> Result<u64, Error> {
+        let highest_known_ledger_info = self.get_highest_known_ledger_info()?;
+        let highest_known_ledger_info = highest_known_ledger_info.ledger_info();
+        let highest_local_epoch_end = if highest_known_ledger_info.ends_epoch() {
+            highest_known_ledger_info.epoch()
+        } else if highest_known_ledger_info.epoch() > 0 {
+            highest_known_ledger_info
+                .epoch()
+                .checked_sub(1)
+                .ok_or_else(|| {
+                    Error::IntegerOverflow("The highest local epoch end has overflown!".into())
+                })?
+        } else {
+            unreachable!("Genesis should always end the first epoch!");
+        };
+        Ok(highest_local_epoch_end)
+    }
+
     /// Verifies that connected peers have advertised data beyond our waypoint
     /// or that our waypoint is trivially satisfiable.
     fn verify_waypoint_is_satisfiable(
@@ -963,8 +1196,7 @@ impl<
         state_value_chunk_with_proof: StateValueChunkWithProof,
     ) -> Result<(), Error> {
         // Verify that we're expecting state value payloads
-        let bootstrapping_mode = self.get_bootstrapping_mode();
-        if self.should_fetch_epoch_ending_ledger_infos() || !bootstrapping_mode.is_fast_sync() {
+        if self.should_fetch_epoch_ending_ledger_infos() || !self.is_syncing_state_snapshot() {
             self.reset_active_stream(Some(NotificationAndFeedback::new(
                 notification_id,
                 NotificationFeedback::InvalidPayloadData,
@@ -1118,8 +1350,9 @@ impl<
     ) -> Result<(), Error> {
         // Verify that we're expecting transaction or output payloads
         let bootstrapping_mode = self.get_bootstrapping_mode();
+        let syncing_state_snapshot = self.is_syncing_state_snapshot();
         if self.should_fetch_epoch_ending_ledger_infos()
-            || (bootstrapping_mode.is_fast_sync()
+            || (syncing_state_snapshot
                 && self.state_value_syncer.transaction_output_to_sync.is_some())
         {
             self.reset_active_stream(Some(NotificationAndFeedback::new(
@@ -1132,8 +1365,8 @@ impl<
             ));
         }
 
-        // If we're fast syncing, we expect a single transaction info
-        if bootstrapping_mode.is_fast_sync() {
+        // If we're syncing a state snapshot, we expect a single transaction info
+        if syncing_state_snapshot {
             return self
                 .verify_transaction_info_to_sync(
                     notification_metadata.notification_id,
@@ -1172,7 +1405,8 @@ impl<
 
         // Execute/apply and commit the transactions/outputs
         let num_transactions_or_outputs = match bootstrapping_mode {
-            BootstrappingMode::ApplyTransactionOutputsFromGenesis => {
+            BootstrappingMode::ApplyTransactionOutputsFromGenesis
+            | BootstrappingMode::RestoreFromBackup => {
                 if let Some(transaction_outputs_with_proof) = transaction_outputs_with_proof {
                     utils::apply_transaction_outputs(
                         self.storage_synchronizer.clone(),
@@ -1371,7 +1605,8 @@ impl<
     ) -> Result<Option<LedgerInfoWithSignatures>, Error> {
         // Calculate the payload end version
         let num_versions = match self.get_bootstrapping_mode() {
-            BootstrappingMode::ApplyTransactionOutputsFromGenesis => {
+            BootstrappingMode::ApplyTransactionOutputsFromGenesis
+            | BootstrappingMode::RestoreFromBackup => {
                 if let Some(transaction_outputs_with_proof) = transaction_outputs_with_proof {
                     transaction_outputs_with_proof
                         .transactions_and_outputs
@@ -1532,13 +1767,18 @@ impl<
         notification_and_feedback: Option<NotificationAndFeedback>,
     ) -> Result<(), Error> {
         if let Some(active_data_stream) = &self.active_data_stream {
-            let data_stream_id = active_data_stream.data_stream_id;
-            utils::terminate_stream_with_feedback(
-                &mut self.streaming_client,
-                data_stream_id,
-                notification_and_feedback,
-            )
-            .await?;
+            if self.get_bootstrapping_mode().is_restore_from_backup() {
+                // Backup data streams are not owned by the streaming service
+                self.backup_data_source.terminate_stream();
+            } else {
+                let data_stream_id = active_data_stream.data_stream_id;
+                utils::terminate_stream_with_feedback(
+                    &mut self.streaming_client,
+                    data_stream_id,
+                    notification_and_feedback,
+                )
+                .await?;
+            }
         }
 
         self.active_data_stream = None;
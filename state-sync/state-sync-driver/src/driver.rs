@@ -16,15 +16,20 @@ use crate::{
         ConsensusNotificationHandler, ErrorNotification, ErrorNotificationListener,
         MempoolNotificationHandler, StorageServiceNotificationHandler,
     },
+    partial_state_storage::PartialStateStore,
+    partial_state_syncer::PartialStateSyncer,
     storage_synchronizer::StorageSynchronizerInterface,
     utils,
     utils::{OutputFallbackHandler, PENDING_DATA_LOG_FREQ_SECS},
 };
-use aptos_config::config::{ConsensusObserverConfig, RoleType, StateSyncDriverConfig};
+use aptos_config::config::{
+    ConsensusObserverConfig, PartialStateSyncConfig, RoleType, StateSyncBackupConfig,
+    StateSyncDriverConfig,
+};
 use aptos_consensus_notifications::{
     ConsensusCommitNotification, ConsensusNotification, ConsensusSyncNotification,
 };
-use aptos_data_client::interface::AptosDataClientInterface;
+use aptos_data_client::{global_summary::GlobalDataSummary, interface::AptosDataClientInterface};
 use aptos_data_streaming_service::streaming_client::{
     DataStreamingClient, NotificationAndFeedback, NotificationFeedback,
 };
@@ -53,9 +58,15 @@ pub struct DriverConfiguration {
     // The config file of the driver
     pub config: StateSyncDriverConfig,
 
+    // The config for bootstrapping from a backup
+    pub backup_config: StateSyncBackupConfig,
+
     // The config for consensus observer
     pub consensus_observer_config: ConsensusObserverConfig,
 
+    // The config for syncing filtered states
+    pub partial_state_config: PartialStateSyncConfig,
+
     // The role of the node
     pub role: RoleType,
 
@@ -66,13 +77,17 @@ pub struct DriverConfiguration {
 impl DriverConfiguration {
     pub fn new(
         config: StateSyncDriverConfig,
+        backup_config: StateSyncBackupConfig,
         consensus_observer_config: ConsensusObserverConfig,
+        partial_state_config: PartialStateSyncConfig,
         role: RoleType,
         waypoint: Waypoint,
     ) -> Self {
         Self {
             config,
+            backup_config,
             consensus_observer_config,
+            partial_state_config,
             role,
             waypoint,
         }
@@ -118,6 +133,9 @@ pub struct StateSyncDriver<
     // The handler for notifications to mempool
     mempool_notification_handler: MempoolNotificationHandler<MempoolNotifier>,
 
+    // The component that syncs the tracked states (if only filtered states are synced)
+    partial_state_syncer: Option<PartialStateSyncer<DataClient>>,
+
     // The timestamp at which the driver started executing
     start_time: Option<Instant>,
 
@@ -168,6 +186,7 @@ impl<
         aptos_data_client: DataClient,
         streaming_client: StreamingClient,
         storage: Arc<dyn DbReader>,
+        partial_state_store: Option<PartialStateStore>,
         time_service: TimeService,
     ) -> Self {
         let output_fallback_handler =
@@ -187,6 +206,23 @@ impl<
             storage.clone(),
             storage_synchronizer.clone(),
         );
+        let partial_state_syncer = if driver_configuration
+            .config
+            .bootstrapping_mode
+            .is_partial_state_sync()
+        {
+            let partial_state_store = partial_state_store
+                .expect("The partial state store is required to sync filtered states!");
+            Some(PartialStateSyncer::new(
+                driver_configuration.partial_state_config.clone(),
+                partial_state_store,
+                aptos_data_client.clone(),
+                storage.clone(),
+                time_service.clone(),
+            ))
+        } else {
+            None
+        };
 
         Self {
             bootstrapper,
@@ -199,6 +235,7 @@ impl<
             error_notification_listener,
             event_subscription_service,
             mempool_notification_handler,
+            partial_state_syncer,
             start_time: None,
             storage,
             storage_service_notification_handler,
@@ -604,6 +641,11 @@ impl<
             return self.check_auto_bootstrapping().await;
         }
 
+        // If we only sync filtered states, refresh the tracked states
+        if self.partial_state_syncer.is_some() {
+            return self.drive_partial_state_syncer(&global_data_summary).await;
+        }
+
         // Check the progress of any sync requests
         if let Err(error) = self.check_sync_request_progress().await {
             warn!(LogSchema::new(LogEntry::Driver)
@@ -661,4 +703,41 @@ impl<
             }
         };
     }
+
+    /// Drives progress of the partial state syncer, and marks the node
+    /// as bootstrapped once the tracked states have been synced.
+    async fn drive_partial_state_syncer(&mut self, global_data_summary: &GlobalDataSummary) {
+        let partial_state_syncer = match self.partial_state_syncer.as_mut() {
+            Some(partial_state_syncer) => partial_state_syncer,
+            None => return,
+        };
+
+        // Refresh the tracked states
+        metrics::increment_counter(
+            &metrics::EXECUTING_COMPONENT,
+            ExecutingComponent::PartialStateSyncer.get_label(),
+        );
+        if let Err(error) = partial_state_syncer
+            .drive_progress(global_data_summary)
+            .await
+        {
+            sample!(
+                SampleRate::Duration(Duration::from_secs(DRIVER_ERROR_LOG_FREQ_SECS)),
+                warn!(LogSchema::new(LogEntry::Driver)
+                    .error(&error)
+                    .message("Error found when driving progress of the partial state syncer!"));
+            );
+            metrics::increment_counter(&metrics::PARTIAL_STATE_SYNCER_ERRORS, error.get_label());
+            return;
+        }
+
+        // Notify any bootstrap listeners once the tracked states have been synced
+        if partial_state_syncer.has_synced_states() && !self.bootstrapper.is_bootstrapped() {
+            if let Err(error) = self.bootstrapper.bootstrapping_complete().await {
+                warn!(LogSchema::new(LogEntry::Driver)
+                    .error(&error)
+                    .message("Failed to mark bootstrapping as complete!"));
+            }
+        }
+    }
 }
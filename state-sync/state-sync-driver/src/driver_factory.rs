@@ -10,6 +10,7 @@ use crate::{
         CommitNotification, CommitNotificationListener, ConsensusNotificationHandler,
         ErrorNotificationListener, MempoolNotificationHandler, StorageServiceNotificationHandler,
     },
+    partial_state_storage::PartialStateStore,
     storage_synchronizer::StorageSynchronizer,
 };
 use aptos_config::config::NodeConfig;
@@ -57,6 +58,7 @@ impl DriverFactory {
         event_subscription_service: EventSubscriptionService,
         aptos_data_client: AptosDataClient,
         streaming_service_client: StreamingServiceClient,
+        partial_state_store: Option<PartialStateStore>,
         time_service: TimeService,
     ) -> Self {
         let (driver_factory, _) = Self::create_and_spawn_driver_internal(
@@ -72,6 +74,7 @@ impl DriverFactory {
             event_subscription_service,
             aptos_data_client,
             streaming_service_client,
+            partial_state_store,
             time_service,
         );
         driver_factory
@@ -98,6 +101,7 @@ impl DriverFactory {
         mut event_subscription_service: EventSubscriptionService,
         aptos_data_client: AptosDataClient,
         streaming_service_client: StreamingServiceClient,
+        partial_state_store: Option<PartialStateStore>,
         time_service: TimeService,
     ) -> (Self, UnboundedSender<CommitNotification>) {
         // Notify subscribers of the initial on-chain config values
@@ -158,7 +162,9 @@ impl DriverFactory {
         // Create the driver configuration
         let driver_configuration = DriverConfiguration::new(
             node_config.state_sync.state_sync_driver,
+            node_config.state_sync_backup.clone(),
             node_config.consensus_observer,
+            node_config.state_sync_partial.clone(),
             node_config.base.role,
             waypoint,
         );
@@ -178,6 +184,7 @@ impl DriverFactory {
             aptos_data_client,
             streaming_service_client,
             storage.reader,
+            partial_state_store,
             time_service,
         );
 
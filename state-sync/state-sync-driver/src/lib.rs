@@ -4,6 +4,7 @@
 
 #![forbid(unsafe_code)]
 
+mod backup_data_source;
 mod bootstrapper;
 mod continuous_syncer;
 mod driver;
@@ -14,6 +15,8 @@ mod logging;
 pub mod metadata_storage;
 pub mod metrics;
 mod notification_handlers;
+pub mod partial_state_storage;
+pub mod partial_state_syncer;
 mod storage_synchronizer;
 mod utils;
 
@@ -28,11 +28,13 @@ impl<'a> LogSchema<'a> {
 #[serde(rename_all = "snake_case")]
 pub enum LogEntry {
     AutoBootstrapping,
+    BackupDataSource,
     Bootstrapper,
     ClientNotification,
     ConsensusNotification,
     Driver,
     NotificationHandler,
+    PartialStateSyncer,
     StorageSynchronizer,
     SynchronizerNotification,
 }
@@ -36,6 +36,7 @@ pub enum ExecutingComponent {
     Bootstrapper,
     Consensus,
     ContinuousSyncer,
+    PartialStateSyncer,
 }
 
 impl ExecutingComponent {
@@ -44,6 +45,7 @@ impl ExecutingComponent {
             ExecutingComponent::Bootstrapper => "bootstrapper",
             ExecutingComponent::Consensus => "consensus",
             ExecutingComponent::ContinuousSyncer => "continuous_syncer",
+            ExecutingComponent::PartialStateSyncer => "partial_state_syncer",
         }
     }
 }
@@ -98,6 +100,16 @@ pub static CONTINUOUS_SYNCER_ERRORS: Lazy<IntCounterVec> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Counter for state sync partial state syncer errors
+pub static PARTIAL_STATE_SYNCER_ERRORS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_state_sync_partial_state_syncer_errors",
+        "Counters related to state sync partial state syncer errors",
+        &["error_label"]
+    )
+    .unwrap()
+});
+
 /// Counters related to the state sync driver
 pub static DRIVER_COUNTERS: Lazy<IntCounterVec> = Lazy::new(|| {
     register_int_counter_vec!(
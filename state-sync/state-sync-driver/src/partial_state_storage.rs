@@ -0,0 +1,587 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    error::Error,
+    partial_state_storage::database_schema::{
+        MetadataKey, MetadataSchema, MetadataValue, PrefixSeekKey, StateValueSchema,
+    },
+};
+use anyhow::{anyhow, ensure, Result};
+use aptos_logger::prelude::*;
+use aptos_schemadb::{
+    define_schema,
+    schema::{KeyCodec, SeekKeyCodec, ValueCodec},
+    ColumnFamilyName, Options, SchemaBatch, DB,
+};
+use aptos_storage_interface::{AptosDbError, DbReader};
+use aptos_storage_service_types::requests::StateValuesFilter;
+use aptos_types::{
+    account_config::NewBlockEvent,
+    contract_event::ContractEvent,
+    ledger_info::LedgerInfoWithSignatures,
+    state_store::{
+        state_key::{prefix::StateKeyPrefix, StateKey},
+        state_storage_usage::StateStorageUsage,
+        state_value::StateValue,
+    },
+    transaction::Version,
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, mem::size_of, path::Path, sync::Arc, time::Instant};
+
+/// The name of the partial state sync db file
+pub const PARTIAL_STATE_SYNC_DB_NAME: &str = "partial_state_sync_db";
+
+/// The names of the metadata and state value column families
+const METADATA_CF_NAME: ColumnFamilyName = "metadata";
+const STATE_VALUE_CF_NAME: ColumnFamilyName = "state_value";
+
+/// The progress of partial state syncing, i.e., the range of versions at
+/// which the tracked states are held by the store.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PartialStateProgress {
+    pub filter: StateValuesFilter, // The filter used to identify the tracked states
+    pub snapshot_version: Version, // The version of the initial (filtered) state snapshot
+    pub synced_ledger_info: LedgerInfoWithSignatures, // The ledger info the tracked states are synced to
+    pub synced_block: Option<(Version, ContractEvent)>, // The first version and new block event of the block ending at the synced version (if known)
+}
+
+impl PartialStateProgress {
+    pub fn new(
+        filter: StateValuesFilter,
+        snapshot_version: Version,
+        synced_ledger_info: LedgerInfoWithSignatures,
+        synced_block: Option<(Version, ContractEvent)>,
+    ) -> Self {
+        Self {
+            filter,
+            snapshot_version,
+            synced_ledger_info,
+            synced_block,
+        }
+    }
+
+    /// Returns the version the tracked states are synced to
+    pub fn synced_version(&self) -> Version {
+        self.synced_ledger_info.ledger_info().version()
+    }
+
+    /// Returns true iff the tracked states are held at the given version
+    pub fn contains_version(&self, version: Version) -> bool {
+        self.snapshot_version <= version && version <= self.synced_version()
+    }
+}
+
+/// A store for the tracked states that uses a RocksDB backend to persist
+/// data. State values are versioned (in the same way as the main database),
+/// so readers are always served a consistent view at any synced version,
+/// even while newer versions are being written.
+#[derive(Clone)]
+pub struct PartialStateStore {
+    database: Arc<DB>,
+}
+
+impl PartialStateStore {
+    pub fn new<P: AsRef<Path> + Clone>(db_root_path: P) -> Self {
+        // Set the options to create the database if it's missing
+        let mut options = Options::default();
+        options.create_if_missing(true);
+        options.create_missing_column_families(true);
+
+        // Open the database
+        let partial_state_db_path = db_root_path.as_ref().join(PARTIAL_STATE_SYNC_DB_NAME);
+        let instant = Instant::now();
+        let database = DB::open(
+            partial_state_db_path.clone(),
+            "partial_state_sync",
+            vec![METADATA_CF_NAME, STATE_VALUE_CF_NAME],
+            &options,
+        )
+        .unwrap_or_else(|error| {
+            panic!(
+                "Failed to open/create the partial state sync database at: {:?}. Error: {:?}",
+                partial_state_db_path, error
+            )
+        });
+        info!(
+            "Opened the partial state sync database at: {:?}, in {:?} ms",
+            partial_state_db_path,
+            instant.elapsed().as_millis()
+        );
+
+        let database = Arc::new(database);
+        Self { database }
+    }
+
+    /// Returns the partial state sync progress (if any)
+    pub fn get_progress(&self) -> Result<Option<PartialStateProgress>, Error> {
+        let metadata_key = MetadataKey::PartialStateProgress;
+        let maybe_metadata_value =
+            self.database
+                .get::<MetadataSchema>(&metadata_key)
+                .map_err(|error| {
+                    Error::StorageError(format!(
+                        "Failed to read metadata value for key: {:?}. Error: {:?}",
+                        metadata_key, error
+                    ))
+                })?;
+        Ok(maybe_metadata_value.map(|metadata_value| {
+            let MetadataValue::PartialStateProgress(progress) = metadata_value;
+            progress
+        }))
+    }
+
+    /// Returns the version the tracked states are synced to (if any)
+    pub fn get_synced_version(&self) -> Result<Option<Version>, Error> {
+        Ok(self
+            .get_progress()?
+            .map(|progress| progress.synced_version()))
+    }
+
+    /// Returns the latest value (and the version at which it was written) of
+    /// the given state key, at or before the specified version.
+    pub fn get_state_value_with_version(
+        &self,
+        state_key: &StateKey,
+        version: Version,
+    ) -> Result<Option<(Version, StateValue)>, Error> {
+        let mut iter = self
+            .database
+            .iter::<StateValueSchema>()
+            .map_err(|error| storage_error("create a state value iterator", error))?;
+        iter.seek(&(state_key.clone(), version))
+            .map_err(|error| storage_error("seek the state value iterator", error))?;
+
+        // Other state keys may share the encoded key prefix, so skip over them
+        let encoded_key = state_key.encoded();
+        for entry in iter {
+            let ((entry_key, entry_version), state_value) =
+                entry.map_err(|error| storage_error("read a state value", error))?;
+            if !entry_key.encoded().starts_with(encoded_key) {
+                break;
+            }
+            if &entry_key == state_key && entry_version <= version {
+                return Ok(state_value.map(|state_value| (entry_version, state_value)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Returns the values of all state keys with the given encoded prefix at
+    /// the specified version (starting at the cursor key, if one is given).
+    pub fn get_prefixed_state_values(
+        &self,
+        encoded_prefix: &[u8],
+        cursor: Option<&StateKey>,
+        version: Version,
+    ) -> Result<Vec<(StateKey, StateValue)>, Error> {
+        let mut iter = self
+            .database
+            .iter::<StateValueSchema>()
+            .map_err(|error| storage_error("create a state value iterator", error))?;
+        let seek_result = match cursor {
+            Some(state_key) => iter.seek(&(state_key.clone(), Version::MAX)),
+            None => iter.seek(&PrefixSeekKey(encoded_prefix.to_vec())),
+        };
+        seek_result.map_err(|error| storage_error("seek the state value iterator", error))?;
+
+        // Versions are sorted in descending order for each state key, so the
+        // first entry at (or before) the version is the latest value.
+        let mut visited_keys = HashSet::new();
+        let mut state_values = vec![];
+        for entry in iter {
+            let ((state_key, entry_version), state_value) =
+                entry.map_err(|error| storage_error("read a state value", error))?;
+            if !state_key.encoded().starts_with(encoded_prefix) {
+                break;
+            }
+            if entry_version > version || visited_keys.contains(&state_key) {
+                continue;
+            }
+
+            visited_keys.insert(state_key.clone());
+            if let Some(state_value) = state_value {
+                state_values.push((state_key, state_value));
+            }
+        }
+
+        Ok(state_values)
+    }
+
+    /// Atomically writes the given state values (at their versions) and
+    /// updates the progress (if specified).
+    pub fn write_state_values(
+        &self,
+        state_values: Vec<(StateKey, Version, Option<StateValue>)>,
+        progress: Option<PartialStateProgress>,
+    ) -> Result<(), Error> {
+        // Create the schema batch
+        let batch = SchemaBatch::new();
+        for (state_key, version, state_value) in state_values {
+            batch
+                .put::<StateValueSchema>(&(state_key, version), &state_value)
+                .map_err(|error| storage_error("batch put a state value", error))?;
+        }
+        if let Some(progress) = progress {
+            batch
+                .put::<MetadataSchema>(
+                    &MetadataKey::PartialStateProgress,
+                    &MetadataValue::PartialStateProgress(progress),
+                )
+                .map_err(|error| storage_error("batch put the progress", error))?;
+        }
+
+        // Write the schema batch to the database
+        self.database
+            .write_schemas(batch)
+            .map_err(|error| storage_error("write the partial state schemas", error))
+    }
+
+    /// Removes all tracked states and the progress from the store
+    pub fn reset(&self) -> Result<(), Error> {
+        // Delete all state values
+        let batch = SchemaBatch::new();
+        let mut iter = self
+            .database
+            .iter::<StateValueSchema>()
+            .map_err(|error| storage_error("create a state value iterator", error))?;
+        iter.seek_to_first();
+        for entry in iter {
+            let (key, _) = entry.map_err(|error| storage_error("read a state value", error))?;
+            batch
+                .delete::<StateValueSchema>(&key)
+                .map_err(|error| storage_error("batch delete a state value", error))?;
+        }
+
+        // Delete the progress
+        batch
+            .delete::<MetadataSchema>(&MetadataKey::PartialStateProgress)
+            .map_err(|error| storage_error("batch delete the progress", error))?;
+
+        // Write the schema batch to the database
+        self.database
+            .write_schemas(batch)
+            .map_err(|error| storage_error("reset the partial state store", error))
+    }
+}
+
+/// Returns a storage error for the failed operation
+fn storage_error(operation: &str, error: impl std::fmt::Debug) -> Error {
+    Error::StorageError(format!(
+        "Failed to {} in the partial state store! Error: {:?}",
+        operation, error
+    ))
+}
+
+/// A database reader that serves the tracked states (at the versions held
+/// by the partial state store) and delegates all other reads to the given
+/// database. This allows the API to serve the tracked states, even though
+/// partial state syncing never writes to the main database.
+///
+/// Note: the synced ledger info is only served once the block ending at
+/// the synced version is known (i.e., after the first incremental update).
+/// Reads of untracked states at the synced versions return an error.
+pub struct PartialStateDbReader {
+    db_reader: Arc<dyn DbReader>,
+    partial_state_store: PartialStateStore,
+}
+
+impl PartialStateDbReader {
+    pub fn new(db_reader: Arc<dyn DbReader>, partial_state_store: PartialStateStore) -> Self {
+        Self {
+            db_reader,
+            partial_state_store,
+        }
+    }
+
+    /// Returns the progress iff the tracked states can be served
+    fn get_served_progress(&self) -> aptos_storage_interface::Result<Option<PartialStateProgress>> {
+        let progress = self
+            .partial_state_store
+            .get_progress()
+            .map_err(|error| AptosDbError::Other(error.to_string()))?;
+        Ok(progress.filter(|progress| progress.synced_block.is_some()))
+    }
+
+    /// Returns the progress iff the tracked states are served at the given version
+    fn get_served_progress_at_version(
+        &self,
+        version: Version,
+    ) -> aptos_storage_interface::Result<Option<PartialStateProgress>> {
+        Ok(self
+            .get_served_progress()?
+            .filter(|progress| progress.contains_version(version)))
+    }
+}
+
+impl DbReader for PartialStateDbReader {
+    fn get_read_delegatee(&self) -> &dyn DbReader {
+        self.db_reader.as_ref()
+    }
+
+    fn get_latest_ledger_info_option(
+        &self,
+    ) -> aptos_storage_interface::Result<Option<LedgerInfoWithSignatures>> {
+        match self.get_served_progress()? {
+            Some(progress) => Ok(Some(progress.synced_ledger_info)),
+            None => self.db_reader.get_latest_ledger_info_option(),
+        }
+    }
+
+    fn get_latest_state_checkpoint_version(
+        &self,
+    ) -> aptos_storage_interface::Result<Option<Version>> {
+        match self.get_served_progress()? {
+            Some(progress) => Ok(Some(progress.synced_version())),
+            None => self.db_reader.get_latest_state_checkpoint_version(),
+        }
+    }
+
+    fn get_state_value_by_version(
+        &self,
+        state_key: &StateKey,
+        version: Version,
+    ) -> aptos_storage_interface::Result<Option<StateValue>> {
+        Ok(self
+            .get_state_value_with_version_by_version(state_key, version)?
+            .map(|(_, state_value)| state_value))
+    }
+
+    fn get_state_value_with_version_by_version(
+        &self,
+        state_key: &StateKey,
+        version: Version,
+    ) -> aptos_storage_interface::Result<Option<(Version, StateValue)>> {
+        let progress = match self.get_served_progress_at_version(version)? {
+            Some(progress) => progress,
+            None => {
+                return self
+                    .db_reader
+                    .get_state_value_with_version_by_version(state_key, version)
+            },
+        };
+
+        if !progress.filter.matches(state_key) {
+            return Err(AptosDbError::NotFound(format!(
+                "State key {:?} is not tracked by partial state sync!",
+                state_key
+            )));
+        }
+        self.partial_state_store
+            .get_state_value_with_version(state_key, version)
+            .map_err(|error| AptosDbError::Other(error.to_string()))
+    }
+
+    fn get_prefixed_state_value_iterator(
+        &self,
+        key_prefix: &StateKeyPrefix,
+        cursor: Option<&StateKey>,
+        version: Version,
+    ) -> aptos_storage_interface::Result<
+        Box<dyn Iterator<Item = aptos_storage_interface::Result<(StateKey, StateValue)>> + '_>,
+    > {
+        let progress = match self.get_served_progress_at_version(version)? {
+            Some(progress) => progress,
+            None => {
+                return self
+                    .db_reader
+                    .get_prefixed_state_value_iterator(key_prefix, cursor, version)
+            },
+        };
+
+        let encoded_prefix = key_prefix.encode()?;
+        if !progress.filter.matches_all_with_prefix(&encoded_prefix) {
+            return Err(AptosDbError::NotFound(format!(
+                "State key prefix {:?} is not tracked by partial state sync!",
+                key_prefix
+            )));
+        }
+        let state_values = self
+            .partial_state_store
+            .get_prefixed_state_values(&encoded_prefix, cursor, version)
+            .map_err(|error| AptosDbError::Other(error.to_string()))?;
+        Ok(Box::new(state_values.into_iter().map(Ok)))
+    }
+
+    fn get_block_info_by_version(
+        &self,
+        version: Version,
+    ) -> aptos_storage_interface::Result<(Version, Version, NewBlockEvent)> {
+        let progress = match self.get_served_progress_at_version(version)? {
+            Some(progress) => progress,
+            None => return self.db_reader.get_block_info_by_version(version),
+        };
+
+        // Only the block ending at the synced version is known
+        match progress.synced_block {
+            Some((block_start_version, new_block_event)) if block_start_version <= version => {
+                let new_block_event = NewBlockEvent::try_from(&new_block_event)?;
+                Ok((
+                    block_start_version,
+                    progress.synced_version(),
+                    new_block_event,
+                ))
+            },
+            _ => Err(AptosDbError::NotFound(format!(
+                "Block info for version {} is not known by partial state sync!",
+                version
+            ))),
+        }
+    }
+
+    fn get_state_storage_usage(
+        &self,
+        version: Option<Version>,
+    ) -> aptos_storage_interface::Result<StateStorageUsage> {
+        if let Some(version) = version {
+            if self.get_served_progress_at_version(version)?.is_some() {
+                return Ok(StateStorageUsage::new_untracked());
+            }
+        }
+        self.db_reader.get_state_storage_usage(version)
+    }
+}
+
+/// The raw schema formats used by the database
+pub mod database_schema {
+    use super::*;
+
+    // This defines a physical storage schema for any metadata.
+    //
+    // The key will be a bcs serialized MetadataKey type.
+    // The value will be a bcs serialized MetadataValue type.
+    //
+    // |<-------key------->|<-----value----->|
+    // |   metadata key    | metadata value  |
+    define_schema!(MetadataSchema, MetadataKey, MetadataValue, METADATA_CF_NAME);
+
+    // This defines a physical storage schema for the tracked state values.
+    //
+    // The key is the encoded state key, followed by the bitwise negated
+    // version (in big endian), so that the latest versions sort first.
+    // The value is a bcs serialized optional state value (None if deleted).
+    //
+    // |<-------- key -------->|<---- value ---->|
+    // |  state key  | !version |   state value   |
+    define_schema!(
+        StateValueSchema,
+        (StateKey, Version),
+        Option<StateValue>,
+        STATE_VALUE_CF_NAME
+    );
+
+    /// A metadata key that can be inserted into the database
+    #[derive(Debug, Deserialize, Eq, PartialEq, Serialize)]
+    #[repr(u8)]
+    pub enum MetadataKey {
+        PartialStateProgress, // The progress of partial state syncing
+    }
+
+    /// A metadata value that can be inserted into the database
+    #[derive(Debug, Deserialize, Eq, PartialEq, Serialize)]
+    #[repr(u8)]
+    pub enum MetadataValue {
+        PartialStateProgress(PartialStateProgress), // The progress of partial state syncing
+    }
+
+    /// A seek key for the first state value with the given encoded prefix
+    #[derive(Debug)]
+    pub struct PrefixSeekKey(pub Vec<u8>);
+
+    impl KeyCodec<MetadataSchema> for MetadataKey {
+        fn encode_key(&self) -> Result<Vec<u8>> {
+            bcs::to_bytes(self).map_err(|error| {
+                anyhow!(
+                    "Failed to encode metadata key: {:?}. Error: {:?}",
+                    self,
+                    error
+                )
+            })
+        }
+
+        fn decode_key(data: &[u8]) -> Result<Self> {
+            bcs::from_bytes::<MetadataKey>(data).map_err(|error| {
+                anyhow!(
+                    "Failed to decode metadata key: {:?}. Error: {:?}",
+                    data,
+                    error
+                )
+            })
+        }
+    }
+
+    impl ValueCodec<MetadataSchema> for MetadataValue {
+        fn encode_value(&self) -> Result<Vec<u8>> {
+            bcs::to_bytes(self).map_err(|error| {
+                anyhow!(
+                    "Failed to encode metadata value: {:?}. Error: {:?}",
+                    self,
+                    error
+                )
+            })
+        }
+
+        fn decode_value(data: &[u8]) -> Result<Self> {
+            bcs::from_bytes::<MetadataValue>(data).map_err(|error| {
+                anyhow!(
+                    "Failed to decode metadata value: {:?}. Error: {:?}",
+                    data,
+                    error
+                )
+            })
+        }
+    }
+
+    impl KeyCodec<StateValueSchema> for (StateKey, Version) {
+        fn encode_key(&self) -> Result<Vec<u8>> {
+            let mut encoded_key = self.0.encoded().to_vec();
+            encoded_key.extend((!self.1).to_be_bytes());
+            Ok(encoded_key)
+        }
+
+        fn decode_key(data: &[u8]) -> Result<Self> {
+            const VERSION_SIZE: usize = size_of::<Version>();
+            ensure!(
+                data.len() > VERSION_SIZE,
+                "Failed to decode state value key! Unexpected length: {:?}",
+                data.len()
+            );
+
+            let state_key_length = data.len() - VERSION_SIZE;
+            let state_key = StateKey::decode(&data[..state_key_length])?;
+            let mut version_bytes = [0u8; VERSION_SIZE];
+            version_bytes.copy_from_slice(&data[state_key_length..]);
+            Ok((state_key, !Version::from_be_bytes(version_bytes)))
+        }
+    }
+
+    impl ValueCodec<StateValueSchema> for Option<StateValue> {
+        fn encode_value(&self) -> Result<Vec<u8>> {
+            bcs::to_bytes(self).map_err(|error| {
+                anyhow!(
+                    "Failed to encode state value: {:?}. Error: {:?}",
+                    self,
+                    error
+                )
+            })
+        }
+
+        fn decode_value(data: &[u8]) -> Result<Self> {
+            bcs::from_bytes::<Option<StateValue>>(data).map_err(|error| {
+                anyhow!(
+                    "Failed to decode state value: {:?}. Error: {:?}",
+                    data,
+                    error
+                )
+            })
+        }
+    }
+
+    impl SeekKeyCodec<StateValueSchema> for PrefixSeekKey {
+        fn encode_seek_key(&self) -> Result<Vec<u8>> {
+            Ok(self.0.clone())
+        }
+    }
+}
@@ -0,0 +1,493 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    error::Error,
+    logging::{LogEntry, LogSchema},
+    partial_state_storage::{PartialStateProgress, PartialStateStore},
+    utils,
+};
+use aptos_config::config::PartialStateSyncConfig;
+use aptos_crypto::HashValue;
+use aptos_data_client::{
+    global_summary::GlobalDataSummary,
+    interface::{AptosDataClientInterface, ResponseContext, ResponseError},
+};
+use aptos_logger::prelude::*;
+use aptos_storage_interface::DbReader;
+use aptos_storage_service_types::requests::StateValuesFilter;
+use aptos_time_service::{TimeService, TimeServiceTrait};
+use aptos_types::{
+    account_config::NewBlockEvent,
+    epoch_change::Verifier,
+    epoch_state::EpochState,
+    ledger_info::LedgerInfoWithSignatures,
+    state_store::{state_key::StateKey, state_value::StateValue},
+    transaction::Version,
+    write_set::TransactionWrite,
+};
+use std::{
+    collections::BTreeMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// A component that keeps the states of the tracked accounts up-to-date,
+/// without executing any transactions or writing to the main database.
+///
+/// The first sync takes a filtered state snapshot: the syncer (i) verifies
+/// the highest advertised ledger info (using the epoch ending ledger infos
+/// from the latest epoch state in storage); (ii) fetches and verifies the
+/// state checkpoint root hash at the ledger info version; and (iii) fetches
+/// the filtered state values from peers, verifying each against the root hash.
+///
+/// Every subsequent refresh syncs incrementally from the last synced version:
+/// the syncer fetches the transaction outputs up to the (verified) target,
+/// verifies them against the target ledger info and applies the write ops
+/// that touch the tracked states. The tracked states are written to the
+/// partial state store, which the API reads from.
+///
+/// Completeness: the incremental updates are complete, because the output
+/// proofs cover every version in the range and each write set is verified
+/// against its transaction info. The initial snapshot is not: peers can prove
+/// that each returned state value exists, but not that all matching states
+/// were returned. A state omitted from the snapshot will only be recovered
+/// once it is written again. Moreover, table items cannot be attributed to
+/// an account, so they are never tracked.
+pub struct PartialStateSyncer<DataClient> {
+    // The client used to fetch data from peers
+    aptos_data_client: DataClient,
+
+    // The config for partial state syncing
+    config: PartialStateSyncConfig,
+
+    // The filter used to identify the tracked states
+    filter: StateValuesFilter,
+
+    // The time at which the tracked states were last refreshed
+    last_refresh_time: Option<Instant>,
+
+    // The latest verified epoch state
+    latest_epoch_state: Option<EpochState>,
+
+    // The store of tracked states (read by the API)
+    partial_state_store: PartialStateStore,
+
+    // The interface to read from storage
+    storage: Arc<dyn DbReader>,
+
+    // The time service
+    time_service: TimeService,
+}
+
+impl<DataClient: AptosDataClientInterface + Send + Clone + 'static> PartialStateSyncer<DataClient> {
+    pub fn new(
+        config: PartialStateSyncConfig,
+        partial_state_store: PartialStateStore,
+        aptos_data_client: DataClient,
+        storage: Arc<dyn DbReader>,
+        time_service: TimeService,
+    ) -> Self {
+        let filter = StateValuesFilter::new(config.get_tracked_accounts(), vec![]);
+        Self {
+            aptos_data_client,
+            config,
+            filter,
+            last_refresh_time: None,
+            latest_epoch_state: None,
+            partial_state_store,
+            storage,
+            time_service,
+        }
+    }
+
+    /// Returns true iff the tracked states have been synced at least once
+    pub fn has_synced_states(&self) -> bool {
+        matches!(self.partial_state_store.get_synced_version(), Ok(Some(_)))
+    }
+
+    /// Refreshes the tracked states (if the refresh interval has elapsed)
+    pub async fn drive_progress(
+        &mut self,
+        global_data_summary: &GlobalDataSummary,
+    ) -> Result<(), Error> {
+        // Check if the tracked states should be refreshed
+        let now = self.time_service.now();
+        if let Some(last_refresh_time) = self.last_refresh_time {
+            let refresh_interval = Duration::from_millis(self.config.refresh_interval_ms);
+            if now.duration_since(last_refresh_time) < refresh_interval {
+                return Ok(());
+            }
+        }
+
+        // Identify the highest advertised ledger info to sync to
+        let target_ledger_info = global_data_summary
+            .advertised_data
+            .highest_synced_ledger_info()
+            .ok_or_else(|| {
+                Error::AdvertisedDataError(
+                    "Unable to find the highest synced ledger info in the advertised data!".into(),
+                )
+            })?;
+        let target_version = target_ledger_info.ledger_info().version();
+
+        // Verify we're not already synced to the target
+        let progress = self.get_progress_for_filter()?;
+        if let Some(progress) = &progress {
+            if progress.synced_version() >= target_version {
+                self.last_refresh_time = Some(now);
+                return Ok(());
+            }
+        }
+
+        // Verify the target and sync the tracked states
+        self.verify_target_ledger_info(&target_ledger_info).await?;
+        match progress {
+            Some(progress) => {
+                self.sync_state_updates(progress, &target_ledger_info)
+                    .await?
+            },
+            None => self.sync_state_snapshot(&target_ledger_info).await?,
+        }
+        self.last_refresh_time = Some(now);
+
+        Ok(())
+    }
+
+    /// Returns the progress of the tracked states. If the tracked accounts
+    /// have changed since the last sync, the store is reset (so that the
+    /// new accounts are synced from a new snapshot).
+    fn get_progress_for_filter(&self) -> Result<Option<PartialStateProgress>, Error> {
+        match self.partial_state_store.get_progress()? {
+            Some(progress) if progress.filter != self.filter => {
+                info!(
+                    LogSchema::new(LogEntry::PartialStateSyncer).message(&format!(
+                        "The tracked accounts have changed! Resetting the synced states. Old filter: {:?}, new filter: {:?}",
+                        progress.filter, self.filter
+                    ))
+                );
+                self.partial_state_store.reset()?;
+                Ok(None)
+            },
+            progress => Ok(progress),
+        }
+    }
+
+    /// Syncs the tracked states from a filtered state snapshot at the target
+    async fn sync_state_snapshot(
+        &self,
+        target_ledger_info: &LedgerInfoWithSignatures,
+    ) -> Result<(), Error> {
+        // Fetch the tracked states at the target version
+        let target_version = target_ledger_info.ledger_info().version();
+        let state_root_hash = self.fetch_state_root_hash(target_ledger_info).await?;
+        let state_values = self
+            .fetch_filtered_state_values(target_version, state_root_hash)
+            .await?;
+
+        // Persist the tracked states and the progress
+        let num_state_values = state_values.len();
+        let state_values = state_values
+            .into_iter()
+            .map(|(state_key, state_value)| (state_key, target_version, Some(state_value)))
+            .collect();
+        let progress = PartialStateProgress::new(
+            self.filter.clone(),
+            target_version,
+            target_ledger_info.clone(),
+            None,
+        );
+        self.partial_state_store
+            .write_state_values(state_values, Some(progress))?;
+        info!(
+            LogSchema::new(LogEntry::PartialStateSyncer).message(&format!(
+                "Synced a snapshot of {:?} tracked state values at version: {:?}",
+                num_state_values, target_version
+            ))
+        );
+
+        Ok(())
+    }
+
+    /// Syncs the tracked states from the last synced version to the target,
+    /// by applying the (verified) write ops that touch the tracked states.
+    async fn sync_state_updates(
+        &self,
+        progress: PartialStateProgress,
+        target_ledger_info: &LedgerInfoWithSignatures,
+    ) -> Result<(), Error> {
+        let target_version = target_ledger_info.ledger_info().version();
+        let mut next_version = progress.synced_version() + 1;
+        let mut num_state_updates = 0;
+        let mut synced_block = None;
+        while next_version <= target_version {
+            // Fetch the transaction outputs up to the target
+            let response = self
+                .aptos_data_client
+                .get_transaction_outputs_with_proof(
+                    target_version,
+                    next_version,
+                    target_version,
+                    self.config.request_timeout_ms,
+                )
+                .await?;
+            let (context, output_list_with_proof) = response.into_parts();
+
+            // Verify the transaction outputs against the target ledger info
+            let num_outputs = output_list_with_proof.transactions_and_outputs.len() as u64;
+            if num_outputs == 0 || next_version + num_outputs - 1 > target_version {
+                return Err(notify_bad_response(
+                    context,
+                    ResponseError::InvalidData,
+                    format!(
+                        "Received an unexpected number of transaction outputs: {:?}",
+                        num_outputs
+                    ),
+                ));
+            }
+            if let Err(error) =
+                output_list_with_proof.verify(target_ledger_info.ledger_info(), Some(next_version))
+            {
+                return Err(notify_bad_response(
+                    context,
+                    ResponseError::ProofVerificationError,
+                    format!("Failed to verify the transaction outputs: {:?}", error),
+                ));
+            }
+
+            // Extract the tracked state updates and the latest block
+            let mut state_values = vec![];
+            for (index, (_, output)) in output_list_with_proof
+                .transactions_and_outputs
+                .iter()
+                .enumerate()
+            {
+                let version = next_version + index as u64;
+                for (state_key, write_op) in output.write_set() {
+                    if self.filter.matches(state_key) {
+                        state_values.push((state_key.clone(), version, write_op.as_state_value()));
+                    }
+                }
+                if let Some(new_block_event) = output
+                    .events()
+                    .iter()
+                    .find(|event| NewBlockEvent::try_from(*event).is_ok())
+                {
+                    synced_block = Some((version, new_block_event.clone()));
+                }
+            }
+            num_state_updates += state_values.len();
+            next_version += num_outputs;
+
+            // Persist the state updates. The progress is only updated once the
+            // target is reached (reads at older versions are never affected).
+            let progress = (next_version > target_version).then(|| {
+                PartialStateProgress::new(
+                    self.filter.clone(),
+                    progress.snapshot_version,
+                    target_ledger_info.clone(),
+                    synced_block.clone(),
+                )
+            });
+            self.partial_state_store
+                .write_state_values(state_values, progress)?;
+        }
+
+        info!(
+            LogSchema::new(LogEntry::PartialStateSyncer).message(&format!(
+                "Synced {:?} tracked state updates up to version: {:?}",
+                num_state_updates, target_version
+            ))
+        );
+
+        Ok(())
+    }
+
+    /// Verifies the target ledger info by ratcheting the latest epoch
+    /// state forward (using the epoch ending ledger infos from peers).
+    async fn verify_target_ledger_info(
+        &mut self,
+        target_ledger_info: &LedgerInfoWithSignatures,
+    ) -> Result<(), Error> {
+        let mut epoch_state = match &self.latest_epoch_state {
+            Some(epoch_state) => epoch_state.clone(),
+            None => utils::fetch_latest_epoch_state(self.storage.clone())?,
+        };
+
+        // Fetch and verify the epoch ending ledger infos up to the target epoch
+        let target_epoch = target_ledger_info.ledger_info().epoch();
+        while epoch_state.epoch < target_epoch {
+            let response = self
+                .aptos_data_client
+                .get_epoch_ending_ledger_infos(
+                    epoch_state.epoch,
+                    target_epoch - 1,
+                    self.config.request_timeout_ms,
+                )
+                .await?;
+            let (context, epoch_ending_ledger_infos) = response.into_parts();
+            if epoch_ending_ledger_infos.is_empty() {
+                return Err(notify_bad_response(
+                    context,
+                    ResponseError::InvalidData,
+                    "Received an empty list of epoch ending ledger infos!".into(),
+                ));
+            }
+
+            for ledger_info in epoch_ending_ledger_infos {
+                if let Err(error) = epoch_state.verify(&ledger_info) {
+                    return Err(notify_bad_response(
+                        context,
+                        ResponseError::ProofVerificationError,
+                        format!("Failed to verify the epoch ending ledger info: {:?}", error),
+                    ));
+                }
+                match ledger_info.ledger_info().next_epoch_state() {
+                    Some(next_epoch_state) => epoch_state = next_epoch_state.clone(),
+                    None => {
+                        return Err(notify_bad_response(
+                            context,
+                            ResponseError::InvalidData,
+                            "The ledger info does not end an epoch!".into(),
+                        ));
+                    },
+                }
+            }
+        }
+
+        // Verify the target ledger info
+        epoch_state.verify(target_ledger_info).map_err(|error| {
+            Error::VerificationError(format!(
+                "Failed to verify the target ledger info: {:?}",
+                error
+            ))
+        })?;
+        self.latest_epoch_state = Some(epoch_state);
+
+        Ok(())
+    }
+
+    /// Fetches the state checkpoint root hash at the target ledger info
+    /// version (proven by the transaction info at the same version).
+    async fn fetch_state_root_hash(
+        &self,
+        target_ledger_info: &LedgerInfoWithSignatures,
+    ) -> Result<HashValue, Error> {
+        let target_version = target_ledger_info.ledger_info().version();
+        let response = self
+            .aptos_data_client
+            .get_transaction_outputs_with_proof(
+                target_version,
+                target_version,
+                target_version,
+                self.config.request_timeout_ms,
+            )
+            .await?;
+        let (context, output_list_with_proof) = response.into_parts();
+
+        // Verify the transaction output list against the target ledger info
+        if let Err(error) =
+            output_list_with_proof.verify(target_ledger_info.ledger_info(), Some(target_version))
+        {
+            return Err(notify_bad_response(
+                context,
+                ResponseError::ProofVerificationError,
+                format!(
+                    "Failed to verify the target transaction output: {:?}",
+                    error
+                ),
+            ));
+        }
+
+        // Extract the state checkpoint hash
+        let transaction_info = output_list_with_proof
+            .proof
+            .transaction_infos
+            .first()
+            .ok_or_else(|| {
+                Error::VerificationError("Target transaction info does not exist!".into())
+            })?;
+        transaction_info
+            .ensure_state_checkpoint_hash()
+            .map_err(|error| {
+                Error::VerificationError(format!("State checkpoint must exist! Error: {:?}", error))
+            })
+    }
+
+    /// Fetches all state values that match the filter at the target version,
+    /// verifying each against the given state root hash.
+    async fn fetch_filtered_state_values(
+        &self,
+        version: Version,
+        state_root_hash: HashValue,
+    ) -> Result<BTreeMap<StateKey, StateValue>, Error> {
+        let number_of_states = self
+            .aptos_data_client
+            .get_number_of_states(version, self.config.request_timeout_ms)
+            .await?
+            .into_payload();
+
+        // Scan all state values (the filtering happens on the peers)
+        let mut state_values = BTreeMap::new();
+        let mut start_index = 0;
+        while start_index < number_of_states {
+            let response = self
+                .aptos_data_client
+                .get_state_values_by_filter_with_proof(
+                    version,
+                    start_index,
+                    number_of_states - 1,
+                    self.filter.clone(),
+                    self.config.request_timeout_ms,
+                )
+                .await?;
+            let (context, state_values_by_filter) = response.into_parts();
+
+            // Verify the scanned indices
+            if state_values_by_filter.version != version
+                || state_values_by_filter.first_index != start_index
+                || state_values_by_filter.last_index < start_index
+                || state_values_by_filter.last_index >= number_of_states
+            {
+                return Err(notify_bad_response(
+                    context,
+                    ResponseError::InvalidData,
+                    format!(
+                        "Received unexpected filtered state values! Version: {:?}, first index: {:?}, last index: {:?}",
+                        state_values_by_filter.version,
+                        state_values_by_filter.first_index,
+                        state_values_by_filter.last_index
+                    ),
+                ));
+            }
+
+            // Verify the state value proofs
+            if let Err(error) = state_values_by_filter.verify(state_root_hash, &self.filter) {
+                return Err(notify_bad_response(
+                    context,
+                    ResponseError::ProofVerificationError,
+                    format!("Failed to verify the filtered state values: {:?}", error),
+                ));
+            }
+
+            start_index = state_values_by_filter.last_index + 1;
+            for (state_key, state_value, _) in state_values_by_filter.state_values {
+                state_values.insert(state_key, state_value);
+            }
+        }
+
+        Ok(state_values)
+    }
+}
+
+/// Notifies the data client of a bad response and returns a verification error
+fn notify_bad_response(
+    context: ResponseContext,
+    response_error: ResponseError,
+    error_message: String,
+) -> Error {
+    context
+        .response_callback
+        .notify_bad_response(response_error);
+    Error::VerificationError(error_message)
+}
@@ -0,0 +1,376 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    backup_data_source::{placeholder_right_siblings, BackupDataSource},
+    error::Error,
+    tests::utils::{
+        create_epoch_ending_ledger_info_for_epoch, create_local_backup_config, create_transaction,
+        save_backup_metadata, write_backup_file, write_backup_records, write_epoch_ending_backup,
+    },
+};
+use aptos_backup_cli::{
+    backup_types::transaction::manifest::{TransactionBackup, TransactionChunk},
+    metadata::Metadata,
+    storage::{local_fs::LocalFs, BackupStorage},
+};
+use aptos_crypto::{
+    hash::{CryptoHash, ACCUMULATOR_PLACEHOLDER_HASH},
+    HashValue,
+};
+use aptos_data_streaming_service::{
+    data_notification::DataPayload, data_stream::DataStreamListener,
+};
+use aptos_temppath::TempPath;
+use aptos_types::{
+    aggregate_signature::AggregateSignature,
+    block_info::BlockInfo,
+    contract_event::ContractEvent,
+    ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
+    proof::{accumulator::InMemoryTransactionAccumulator, TransactionAccumulatorRangeProof},
+    transaction::{ExecutionStatus, Transaction, TransactionInfo, Version},
+    write_set::WriteSet,
+};
+use claims::{assert_matches, assert_none};
+use futures::StreamExt;
+use std::{sync::Arc, time::Duration};
+
+#[test]
+fn test_epoch_ending_range_proofs() {
+    for num_leaves in 1..=40u64 {
+        // Create an accumulator with the given number of leaves
+        let leaves: Vec<_> = (0..num_leaves).map(|_| HashValue::random()).collect();
+        let accumulator = InMemoryTransactionAccumulator::new_empty().append(&leaves);
+        let last_version = num_leaves - 1;
+
+        // Verify the range proofs for all ranges that end at the last leaf
+        for first_version in 0..num_leaves {
+            let frozen_subtree_roots = InMemoryTransactionAccumulator::new_empty()
+                .append(&leaves[..first_version as usize])
+                .frozen_subtree_roots()
+                .clone();
+            let range_proof = TransactionAccumulatorRangeProof::new(
+                frozen_subtree_roots.into_iter().rev().collect(),
+                placeholder_right_siblings(last_version),
+            );
+            range_proof
+                .verify(
+                    accumulator.root_hash(),
+                    Some(first_version),
+                    &leaves[first_version as usize..],
+                )
+                .unwrap();
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_epoch_ending_ledger_infos() {
+    // Create a backup with two overlapping epoch ending backups
+    let (_backup_dir, backup_storage, mut backup_data_source) = create_local_backup();
+    let ledger_infos: Vec<_> = (0..=5)
+        .map(|epoch| create_epoch_ending_ledger_info_for_epoch(epoch, epoch * 10))
+        .collect();
+    write_epoch_ending_backup(&backup_storage, &ledger_infos[0..=3]).await;
+    write_epoch_ending_backup(&backup_storage, &ledger_infos[2..=5]).await;
+
+    // Verify the highest epoch ending epoch
+    assert_eq!(
+        backup_data_source
+            .get_highest_epoch_ending_epoch()
+            .await
+            .unwrap(),
+        Some(5)
+    );
+
+    // Stream the epoch ending ledger infos and verify there are no duplicates
+    let data_stream = backup_data_source
+        .get_all_epoch_ending_ledger_infos(1)
+        .await
+        .unwrap();
+    let mut streamed_ledger_infos = vec![];
+    for data_payload in read_data_stream(data_stream).await {
+        match data_payload {
+            DataPayload::EpochEndingLedgerInfos(epoch_ending_ledger_infos) => {
+                streamed_ledger_infos.extend(epoch_ending_ledger_infos)
+            },
+            data_payload => panic!("Unexpected payload: {:?}", data_payload),
+        }
+    }
+    assert_eq!(streamed_ledger_infos, ledger_infos[1..].to_vec());
+}
+
+#[tokio::test]
+async fn test_missing_manifest() {
+    // Create a backup with metadata that points to a missing manifest
+    let (_backup_dir, backup_storage, mut backup_data_source) = create_local_backup();
+    let metadata =
+        Metadata::new_epoch_ending_backup(0, 1, 0, 10, "missing/epoch_ending.manifest".into());
+    save_backup_metadata(&backup_storage, metadata).await;
+
+    // Stream the epoch ending ledger infos
+    let data_stream = backup_data_source
+        .get_all_epoch_ending_ledger_infos(0)
+        .await
+        .unwrap();
+    let data_stream_id = data_stream.data_stream_id;
+
+    // Verify the error of the stream is recorded
+    let mut stream_error = None;
+    for _ in 0..100 {
+        stream_error = backup_data_source.take_stream_error(data_stream_id);
+        if stream_error.is_some() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    assert_matches!(stream_error, Some(Error::BackupStorageError(_)));
+
+    // Verify the error is only returned once (and only for the failed stream)
+    assert_none!(backup_data_source.take_stream_error(data_stream_id));
+    assert_none!(backup_data_source.take_stream_error(data_stream_id + 1));
+}
+
+#[tokio::test]
+async fn test_transaction_outputs() {
+    // Create the transactions and the ledger info (at the end of the epoch)
+    let num_transactions = 5;
+    let records: Vec<_> = (0..num_transactions)
+        .map(|_| create_transaction_record())
+        .collect();
+    let transaction_info_hashes: Vec<_> = records
+        .iter()
+        .map(|(_, transaction_info, _, _)| transaction_info.hash())
+        .collect();
+    let last_version = num_transactions - 1;
+    let ledger_info = create_ledger_info(0, last_version, &transaction_info_hashes);
+
+    // Create a backup holding the transactions
+    let (_backup_dir, backup_storage, mut backup_data_source) = create_local_backup();
+    write_transaction_backup(&backup_storage, &records, &ledger_info).await;
+
+    // Stream the outputs from all versions and verify the proofs
+    for start_version in 0..num_transactions {
+        let frozen_subtree_roots =
+            create_frozen_subtree_roots(&transaction_info_hashes[..start_version as usize]);
+        let (data_stream, proof_ledger_info) = backup_data_source
+            .get_transaction_outputs(start_version, frozen_subtree_roots, None)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(proof_ledger_info, ledger_info);
+        verify_transaction_outputs(
+            data_stream,
+            &ledger_info,
+            start_version,
+            num_transactions - start_version,
+        )
+        .await;
+    }
+
+    // Verify there are no outputs beyond the backup
+    assert_none!(backup_data_source
+        .get_transaction_outputs(
+            num_transactions,
+            create_frozen_subtree_roots(&transaction_info_hashes),
+            None
+        )
+        .await
+        .unwrap());
+}
+
+#[tokio::test]
+async fn test_transaction_outputs_epoch_ending() {
+    // Create the transactions and the ledger infos (the first epoch ends in the middle)
+    let num_transactions = 6;
+    let records: Vec<_> = (0..num_transactions)
+        .map(|_| create_transaction_record())
+        .collect();
+    let transaction_info_hashes: Vec<_> = records
+        .iter()
+        .map(|(_, transaction_info, _, _)| transaction_info.hash())
+        .collect();
+    let epoch_ending_version = 3;
+    let epoch_ending_ledger_info = create_ledger_info(
+        0,
+        epoch_ending_version,
+        &transaction_info_hashes[..=epoch_ending_version as usize],
+    );
+    let ledger_info = create_ledger_info(1, num_transactions - 1, &transaction_info_hashes);
+
+    // Create a backup holding the transactions
+    let (_backup_dir, backup_storage, mut backup_data_source) = create_local_backup();
+    write_transaction_backup(&backup_storage, &records, &ledger_info).await;
+
+    // Stream the outputs up to the end of the epoch and verify the proofs
+    for start_version in 0..=epoch_ending_version {
+        let frozen_subtree_roots =
+            create_frozen_subtree_roots(&transaction_info_hashes[..start_version as usize]);
+        let (data_stream, proof_ledger_info) = backup_data_source
+            .get_transaction_outputs(
+                start_version,
+                frozen_subtree_roots,
+                Some(epoch_ending_ledger_info.clone()),
+            )
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(proof_ledger_info, epoch_ending_ledger_info);
+        verify_transaction_outputs(
+            data_stream,
+            &epoch_ending_ledger_info,
+            start_version,
+            epoch_ending_version - start_version + 1,
+        )
+        .await;
+    }
+}
+
+/// Creates a local backup storage and a data source that restores from it
+fn create_local_backup() -> (TempPath, Arc<dyn BackupStorage>, BackupDataSource) {
+    let backup_dir = TempPath::new();
+    backup_dir.create_as_dir().unwrap();
+    let backup_storage: Arc<dyn BackupStorage> =
+        Arc::new(LocalFs::new(backup_dir.path().join("backup")));
+    let backup_config = create_local_backup_config(
+        &backup_dir.path().join("backup"),
+        &backup_dir.path().join("metadata_cache"),
+    );
+    (
+        backup_dir,
+        backup_storage,
+        BackupDataSource::new(backup_config),
+    )
+}
+
+/// Returns the frozen subtree roots of an accumulator holding the given leaves
+fn create_frozen_subtree_roots(leaves: &[HashValue]) -> Vec<HashValue> {
+    InMemoryTransactionAccumulator::new_empty()
+        .append(leaves)
+        .frozen_subtree_roots()
+        .clone()
+}
+
+/// Creates a ledger info for the accumulator holding the given transaction infos
+fn create_ledger_info(
+    epoch: u64,
+    version: Version,
+    transaction_info_hashes: &[HashValue],
+) -> LedgerInfoWithSignatures {
+    let accumulator = InMemoryTransactionAccumulator::new_empty().append(transaction_info_hashes);
+    let block_info = BlockInfo::new(
+        epoch,
+        0,
+        HashValue::zero(),
+        accumulator.root_hash(),
+        version,
+        0,
+        None,
+    );
+    LedgerInfoWithSignatures::new(
+        LedgerInfo::new(block_info, HashValue::zero()),
+        AggregateSignature::empty(),
+    )
+}
+
+/// Creates a transaction backup record, with a transaction info that matches
+/// the transaction and its (empty) output.
+fn create_transaction_record() -> (Transaction, TransactionInfo, Vec<ContractEvent>, WriteSet) {
+    let transaction = create_transaction();
+    let write_set = WriteSet::default();
+    let transaction_info = TransactionInfo::new(
+        transaction.hash(),
+        write_set.hash(),
+        *ACCUMULATOR_PLACEHOLDER_HASH,
+        None,
+        0,
+        ExecutionStatus::Success,
+    );
+    (transaction, transaction_info, vec![], write_set)
+}
+
+/// Reads all payloads from the given data stream, up to the end of the stream
+async fn read_data_stream(mut data_stream: DataStreamListener) -> Vec<DataPayload> {
+    let mut data_payloads = vec![];
+    loop {
+        let data_notification = tokio::time::timeout(Duration::from_secs(10), data_stream.next())
+            .await
+            .unwrap()
+            .unwrap();
+        match data_notification.data_payload {
+            DataPayload::EndOfStream => return data_payloads,
+            data_payload => data_payloads.push(data_payload),
+        }
+    }
+}
+
+/// Verifies that the data stream holds the expected transaction outputs,
+/// and that they are proven by the given ledger info.
+async fn verify_transaction_outputs(
+    data_stream: DataStreamListener,
+    ledger_info: &LedgerInfoWithSignatures,
+    start_version: Version,
+    num_outputs: u64,
+) {
+    let data_payloads = read_data_stream(data_stream).await;
+    assert_eq!(data_payloads.len(), 1);
+    match &data_payloads[0] {
+        DataPayload::TransactionOutputsWithProof(outputs_with_proof) => {
+            assert_eq!(
+                outputs_with_proof.transactions_and_outputs.len() as u64,
+                num_outputs
+            );
+            outputs_with_proof
+                .verify(ledger_info.ledger_info(), Some(start_version))
+                .unwrap();
+        },
+        data_payload => panic!("Unexpected payload: {:?}", data_payload),
+    }
+}
+
+/// Writes a transaction backup (with a single chunk) holding the given records
+async fn write_transaction_backup(
+    backup_storage: &Arc<dyn BackupStorage>,
+    records: &[(Transaction, TransactionInfo, Vec<ContractEvent>, WriteSet)],
+    ledger_info: &LedgerInfoWithSignatures,
+) {
+    let last_version = records.len() as Version - 1;
+    let backup_handle = backup_storage
+        .create_backup(&"transaction_0-".parse().unwrap())
+        .await
+        .unwrap();
+
+    // Write the transactions, the proof and the manifest
+    let transactions_handle =
+        write_backup_records(backup_storage, &backup_handle, "0-.chunk", records).await;
+    let range_proof =
+        TransactionAccumulatorRangeProof::new(vec![], placeholder_right_siblings(last_version));
+    let proof_handle = write_backup_file(
+        backup_storage,
+        &backup_handle,
+        "0-.proof",
+        &bcs::to_bytes(&(range_proof, ledger_info)).unwrap(),
+    )
+    .await;
+    let manifest = TransactionBackup {
+        first_version: 0,
+        last_version,
+        chunks: vec![TransactionChunk {
+            first_version: 0,
+            last_version,
+            transactions: transactions_handle,
+            proof: proof_handle,
+        }],
+    };
+    let manifest_handle = write_backup_file(
+        backup_storage,
+        &backup_handle,
+        "transaction.manifest",
+        &serde_json::to_vec(&manifest).unwrap(),
+    )
+    .await;
+
+    // Save the metadata
+    let metadata = Metadata::new_transaction_backup(0, last_version, manifest_handle);
+    save_backup_metadata(backup_storage, metadata).await;
+}
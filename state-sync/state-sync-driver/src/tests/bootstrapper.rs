@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    bootstrapper::{Bootstrapper, GENESIS_TRANSACTION_VERSION},
+    bootstrapper::{Bootstrapper, VerifiedEpochStates, GENESIS_TRANSACTION_VERSION},
     driver::DriverConfiguration,
     error::Error,
     tests::{
@@ -12,22 +12,29 @@ use crate::{
         },
         utils::{
             create_data_stream_listener, create_empty_epoch_state, create_epoch_ending_ledger_info,
-            create_epoch_ending_ledger_info_for_epoch, create_full_node_driver_configuration,
-            create_global_summary, create_global_summary_with_version,
+            create_epoch_ending_ledger_info_for_epoch, create_epoch_state,
+            create_full_node_driver_configuration, create_global_summary,
+            create_global_summary_with_version, create_local_backup_config,
             create_output_list_with_proof, create_random_epoch_ending_ledger_info,
-            create_transaction_list_with_proof,
+            create_transaction_list_with_proof, save_backup_metadata, write_epoch_ending_backup,
         },
     },
     utils::OutputFallbackHandler,
 };
+use aptos_backup_cli::{
+    metadata::Metadata,
+    storage::{local_fs::LocalFs, BackupStorage},
+};
 use aptos_config::config::BootstrappingMode;
 use aptos_data_client::global_summary::GlobalDataSummary;
 use aptos_data_streaming_service::{
     data_notification::{DataNotification, DataPayload, NotificationId},
     streaming_client::{NotificationAndFeedback, NotificationFeedback},
 };
+use aptos_temppath::TempPath;
 use aptos_time_service::TimeService;
 use aptos_types::{
+    ledger_info::LedgerInfoWithSignatures,
     transaction::{TransactionOutputListWithProof, Version},
     waypoint::Waypoint,
 };
@@ -953,6 +960,111 @@ async fn test_fetch_epoch_ending_ledger_infos_waypoint_mismatch() {
         .unwrap();
 }
 
+#[tokio::test]
+async fn test_restore_from_backup_epoch_ending_ledger_infos() {
+    // Create a backup holding the epoch ending ledger infos up to the waypoint
+    let backup_dir = TempPath::new();
+    backup_dir.create_as_dir().unwrap();
+    let backup_storage = create_local_backup_storage(&backup_dir);
+    let waypoint_epoch = 3;
+    let epoch_ending_ledger_infos: Vec<_> = (1..=waypoint_epoch)
+        .map(|epoch| create_random_epoch_ending_ledger_info(epoch, epoch))
+        .collect();
+    write_epoch_ending_backup(&backup_storage, &epoch_ending_ledger_infos).await;
+
+    // Create the bootstrapper (the streaming client is never used)
+    let driver_configuration = create_restore_driver_configuration(
+        &backup_dir,
+        epoch_ending_ledger_infos.last().unwrap(),
+        None,
+    );
+    let (mut bootstrapper, _) = create_bootstrapper(
+        driver_configuration,
+        create_mock_streaming_client(),
+        None,
+        true,
+    );
+
+    // Start at the epoch that follows genesis
+    *bootstrapper.get_verified_epoch_states() = VerifiedEpochStates::new(create_epoch_state(1));
+
+    // Drive progress to initialize the epoch ending data stream
+    let global_data_summary = GlobalDataSummary::empty();
+    drive_progress(&mut bootstrapper, &global_data_summary, false)
+        .await
+        .unwrap();
+
+    // Drive progress to process the epoch ending ledger infos and the end of stream
+    drive_progress(&mut bootstrapper, &global_data_summary, false)
+        .await
+        .unwrap();
+
+    // Verify the bootstrapper verified the waypoint and all ledger infos
+    let verified_epoch_states = bootstrapper.get_verified_epoch_states().clone();
+    assert!(verified_epoch_states.verified_waypoint());
+    let verified_ledger_infos = verified_epoch_states.all_epoch_ending_ledger_infos();
+    assert_eq!(verified_ledger_infos.len() as u64, waypoint_epoch);
+    for epoch_ending_ledger_info in epoch_ending_ledger_infos {
+        assert!(verified_ledger_infos.contains(&epoch_ending_ledger_info));
+    }
+
+    // Drive progress and verify there are no more ledger infos to fetch from the backup
+    drive_progress(&mut bootstrapper, &global_data_summary, false)
+        .await
+        .unwrap();
+    let verified_epoch_states = bootstrapper.get_verified_epoch_states().clone();
+    assert!(verified_epoch_states.fetched_epoch_ending_ledger_infos());
+    assert!(!bootstrapper.is_bootstrapped());
+}
+
+#[tokio::test]
+async fn test_restore_from_backup_stream_error() {
+    // Create a backup with metadata that points to a missing manifest
+    let backup_dir = TempPath::new();
+    backup_dir.create_as_dir().unwrap();
+    let backup_storage = create_local_backup_storage(&backup_dir);
+    let waypoint_epoch = 3;
+    let metadata = Metadata::new_epoch_ending_backup(
+        1,
+        waypoint_epoch,
+        1,
+        waypoint_epoch,
+        "missing/epoch_ending.manifest".into(),
+    );
+    save_backup_metadata(&backup_storage, metadata).await;
+
+    // Create the bootstrapper (with a stream timeout of 1 second)
+    let waypoint_ledger_info =
+        create_random_epoch_ending_ledger_info(waypoint_epoch, waypoint_epoch);
+    let driver_configuration =
+        create_restore_driver_configuration(&backup_dir, &waypoint_ledger_info, Some(1000));
+    let (mut bootstrapper, _) = create_bootstrapper(
+        driver_configuration,
+        create_mock_streaming_client(),
+        None,
+        true,
+    );
+
+    // Drive progress to initialize the epoch ending data stream
+    let global_data_summary = GlobalDataSummary::empty();
+    drive_progress(&mut bootstrapper, &global_data_summary, false)
+        .await
+        .unwrap();
+
+    // Drive progress and verify the backup error is returned
+    for _ in 0..2 {
+        let error = drive_progress(&mut bootstrapper, &global_data_summary, false)
+            .await
+            .unwrap_err();
+        assert_matches!(error, Error::BackupStorageError(_));
+
+        // Drive progress to verify the stream was reset (and is initialized again)
+        drive_progress(&mut bootstrapper, &global_data_summary, false)
+            .await
+            .unwrap();
+    }
+}
+
 #[tokio::test]
 async fn test_snapshot_sync_epoch_change() {
     // Create test data
@@ -1686,6 +1798,31 @@ fn create_bootstrapper_with_storage(
     )
 }
 
+/// Creates a local backup storage in the given directory
+fn create_local_backup_storage(backup_dir: &TempPath) -> Arc<dyn BackupStorage> {
+    Arc::new(LocalFs::new(backup_dir.path().join("backup")))
+}
+
+/// Creates a driver configuration that restores from the local backup in
+/// the given directory, with a waypoint at the given ledger info.
+fn create_restore_driver_configuration(
+    backup_dir: &TempPath,
+    waypoint_ledger_info: &LedgerInfoWithSignatures,
+    max_stream_wait_time_ms: Option<u64>,
+) -> DriverConfiguration {
+    let mut driver_configuration = create_full_node_driver_configuration();
+    driver_configuration.config.bootstrapping_mode = BootstrappingMode::RestoreFromBackup;
+    if let Some(max_stream_wait_time_ms) = max_stream_wait_time_ms {
+        driver_configuration.config.max_stream_wait_time_ms = max_stream_wait_time_ms;
+    }
+    driver_configuration.backup_config = create_local_backup_config(
+        &backup_dir.path().join("backup"),
+        &backup_dir.path().join("metadata_cache"),
+    );
+    driver_configuration.waypoint = Waypoint::new_any(waypoint_ledger_info.ledger_info());
+    driver_configuration
+}
+
 /// Drives progress for the given bootstrapper. If `until_bootstrapped`
 /// is true this method will continue to drive the bootstrapper until
 /// bootstrapping is complete.
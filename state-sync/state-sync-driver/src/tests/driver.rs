@@ -405,6 +405,7 @@ async fn create_driver_for_tests(
             event_subscription_service,
             aptos_data_client,
             streaming_service_client,
+            None,
             time_service.clone(),
         );
 
@@ -99,6 +99,7 @@ fn test_new_initialized_configs() {
         event_subscription_service,
         aptos_data_client,
         streaming_service_client,
+        None,
         TimeService::mock(),
     );
 
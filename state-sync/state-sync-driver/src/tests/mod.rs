@@ -2,11 +2,13 @@
 // Parts of the project are originally copyright © Meta Platforms, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+mod backup_data_source;
 mod bootstrapper;
 mod continuous_syncer;
 mod driver;
 mod driver_factory;
 mod metadata_storage;
 mod mocks;
+mod partial_state_syncer;
 mod storage_synchronizer;
 mod utils;
@@ -0,0 +1,290 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    partial_state_storage::{PartialStateDbReader, PartialStateProgress, PartialStateStore},
+    tests::{
+        mocks::create_mock_db_reader,
+        utils::{create_event, create_ledger_info_at_version},
+    },
+};
+use aptos_storage_interface::{AptosDbError, DbReader};
+use aptos_storage_service_types::requests::StateValuesFilter;
+use aptos_temppath::TempPath;
+use aptos_types::{
+    account_address::AccountAddress,
+    state_store::{
+        state_key::{prefix::StateKeyPrefix, StateKey},
+        state_value::StateValue,
+    },
+    transaction::Version,
+};
+use claims::{assert_matches, assert_none};
+use move_core_types::identifier::Identifier;
+use std::sync::Arc;
+
+#[test]
+fn test_partial_state_store_persistence() {
+    // Open a new partial state store
+    let temp_path = TempPath::new();
+    temp_path.create_as_dir().unwrap();
+    let partial_state_store = PartialStateStore::new(temp_path.path());
+
+    // Verify the store is empty
+    assert_none!(partial_state_store.get_progress().unwrap());
+    assert_none!(partial_state_store.get_synced_version().unwrap());
+
+    // Write the tracked states and the progress
+    let account = AccountAddress::random();
+    let state_key = create_module_state_key(account, "module");
+    let state_value = StateValue::new_legacy(vec![1, 2, 3].into());
+    let progress = create_progress(vec![account], 100, 100, false);
+    partial_state_store
+        .write_state_values(
+            vec![(state_key.clone(), 100, Some(state_value.clone()))],
+            Some(progress.clone()),
+        )
+        .unwrap();
+
+    // Re-open the store and verify the tracked states were persisted
+    drop(partial_state_store);
+    let partial_state_store = PartialStateStore::new(temp_path.path());
+    assert_eq!(partial_state_store.get_progress().unwrap(), Some(progress));
+    assert_eq!(partial_state_store.get_synced_version().unwrap(), Some(100));
+    assert_eq!(
+        partial_state_store
+            .get_state_value_with_version(&state_key, 100)
+            .unwrap(),
+        Some((100, state_value))
+    );
+}
+
+#[test]
+fn test_partial_state_store_versioned_reads() {
+    // Open a new partial state store
+    let temp_path = TempPath::new();
+    temp_path.create_as_dir().unwrap();
+    let partial_state_store = PartialStateStore::new(temp_path.path());
+
+    // Write a state value, update it and then delete it
+    let state_key = create_module_state_key(AccountAddress::random(), "module");
+    let state_value_1 = StateValue::new_legacy(vec![1].into());
+    let state_value_2 = StateValue::new_legacy(vec![2].into());
+    partial_state_store
+        .write_state_values(
+            vec![
+                (state_key.clone(), 10, Some(state_value_1.clone())),
+                (state_key.clone(), 20, Some(state_value_2.clone())),
+                (state_key.clone(), 30, None),
+            ],
+            None,
+        )
+        .unwrap();
+
+    // Verify the state value is read correctly at each version
+    for (version, expected_state_value) in [
+        (5, None),
+        (10, Some((10, state_value_1.clone()))),
+        (15, Some((10, state_value_1))),
+        (20, Some((20, state_value_2.clone()))),
+        (29, Some((20, state_value_2))),
+        (30, None),
+        (Version::MAX, None),
+    ] {
+        assert_eq!(
+            partial_state_store
+                .get_state_value_with_version(&state_key, version)
+                .unwrap(),
+            expected_state_value
+        );
+    }
+
+    // Verify other state keys are not returned
+    let other_state_key = create_module_state_key(AccountAddress::random(), "module");
+    assert_none!(partial_state_store
+        .get_state_value_with_version(&other_state_key, 20)
+        .unwrap());
+}
+
+#[test]
+fn test_partial_state_store_prefixed_reads() {
+    // Open a new partial state store
+    let temp_path = TempPath::new();
+    temp_path.create_as_dir().unwrap();
+    let partial_state_store = PartialStateStore::new(temp_path.path());
+
+    // Write state values for two accounts
+    let account = AccountAddress::random();
+    let other_account = AccountAddress::random();
+    let state_key_1 = create_module_state_key(account, "module_1");
+    let state_key_2 = create_module_state_key(account, "module_2");
+    let other_state_key = create_module_state_key(other_account, "module_1");
+    let state_value = StateValue::new_legacy(vec![1, 2, 3].into());
+    partial_state_store
+        .write_state_values(
+            vec![
+                (state_key_1.clone(), 10, Some(state_value.clone())),
+                (state_key_2.clone(), 20, Some(state_value.clone())),
+                (state_key_1.clone(), 30, None),
+                (other_state_key, 10, Some(state_value.clone())),
+            ],
+            None,
+        )
+        .unwrap();
+
+    // Verify the prefixed state values are read correctly at each version
+    let encoded_prefix = StateKeyPrefix::from(account).encode().unwrap();
+    let mut state_values = partial_state_store
+        .get_prefixed_state_values(&encoded_prefix, None, 20)
+        .unwrap();
+    state_values.sort_by(|(state_key_1, _), (state_key_2, _)| state_key_1.cmp(state_key_2));
+    let mut expected_state_values = vec![
+        (state_key_1.clone(), state_value.clone()),
+        (state_key_2.clone(), state_value.clone()),
+    ];
+    expected_state_values
+        .sort_by(|(state_key_1, _), (state_key_2, _)| state_key_1.cmp(state_key_2));
+    assert_eq!(state_values, expected_state_values);
+    assert_eq!(
+        partial_state_store
+            .get_prefixed_state_values(&encoded_prefix, None, 10)
+            .unwrap(),
+        vec![(state_key_1, state_value.clone())]
+    );
+    assert_eq!(
+        partial_state_store
+            .get_prefixed_state_values(&encoded_prefix, None, 30)
+            .unwrap(),
+        vec![(state_key_2, state_value)]
+    );
+}
+
+#[test]
+fn test_partial_state_store_reset() {
+    // Open a new partial state store
+    let temp_path = TempPath::new();
+    temp_path.create_as_dir().unwrap();
+    let partial_state_store = PartialStateStore::new(temp_path.path());
+
+    // Write the tracked states and the progress
+    let account = AccountAddress::random();
+    let state_key = create_module_state_key(account, "module");
+    let state_value = StateValue::new_legacy(vec![1, 2, 3].into());
+    partial_state_store
+        .write_state_values(
+            vec![(state_key.clone(), 100, Some(state_value))],
+            Some(create_progress(vec![account], 100, 100, true)),
+        )
+        .unwrap();
+
+    // Reset the store and verify everything was removed
+    partial_state_store.reset().unwrap();
+    assert_none!(partial_state_store.get_progress().unwrap());
+    assert_none!(partial_state_store
+        .get_state_value_with_version(&state_key, 100)
+        .unwrap());
+}
+
+#[test]
+fn test_partial_state_db_reader() {
+    // Open a new partial state store
+    let temp_path = TempPath::new();
+    temp_path.create_as_dir().unwrap();
+    let partial_state_store = PartialStateStore::new(temp_path.path());
+
+    // Create a mock db reader that returns the main database ledger info
+    let main_ledger_info = create_ledger_info_at_version(10);
+    let mut mock_db_reader = create_mock_db_reader();
+    let ledger_info = main_ledger_info.clone();
+    mock_db_reader
+        .expect_get_latest_ledger_info_option()
+        .returning(move || Ok(Some(ledger_info.clone())));
+    let db_reader =
+        PartialStateDbReader::new(Arc::new(mock_db_reader), partial_state_store.clone());
+
+    // Write the tracked states (without the synced block)
+    let account = AccountAddress::random();
+    let state_key = create_module_state_key(account, "module");
+    let state_value = StateValue::new_legacy(vec![1, 2, 3].into());
+    partial_state_store
+        .write_state_values(
+            vec![(state_key.clone(), 100, Some(state_value.clone()))],
+            Some(create_progress(vec![account], 100, 100, false)),
+        )
+        .unwrap();
+
+    // Verify the main database ledger info is still served
+    assert_eq!(
+        db_reader.get_latest_ledger_info_option().unwrap(),
+        Some(main_ledger_info)
+    );
+
+    // Update the progress (with the synced block)
+    let progress = create_progress(vec![account], 100, 200, true);
+    partial_state_store
+        .write_state_values(vec![], Some(progress.clone()))
+        .unwrap();
+
+    // Verify the synced ledger info and tracked states are now served
+    assert_eq!(
+        db_reader.get_latest_ledger_info_option().unwrap(),
+        Some(progress.synced_ledger_info)
+    );
+    assert_eq!(
+        db_reader.get_latest_state_checkpoint_version().unwrap(),
+        Some(200)
+    );
+    assert_eq!(
+        db_reader
+            .get_state_value_by_version(&state_key, 150)
+            .unwrap(),
+        Some(state_value.clone())
+    );
+    let encoded_prefix = StateKeyPrefix::from(account);
+    let state_values: Vec<_> = db_reader
+        .get_prefixed_state_value_iterator(&encoded_prefix, None, 200)
+        .unwrap()
+        .map(|result| result.unwrap())
+        .collect();
+    assert_eq!(state_values, vec![(state_key, state_value)]);
+    assert_matches!(
+        db_reader.get_block_info_by_version(150),
+        Err(AptosDbError::NotFound(_))
+    );
+
+    // Verify untracked states are not served
+    let untracked_state_key = create_module_state_key(AccountAddress::random(), "module");
+    assert_matches!(
+        db_reader.get_state_value_by_version(&untracked_state_key, 150),
+        Err(AptosDbError::NotFound(_))
+    );
+    let untracked_prefix = StateKeyPrefix::from(AccountAddress::random());
+    assert_matches!(
+        db_reader
+            .get_prefixed_state_value_iterator(&untracked_prefix, None, 150)
+            .err(),
+        Some(AptosDbError::NotFound(_))
+    );
+}
+
+/// Creates a module state key for the given account and module name
+fn create_module_state_key(account: AccountAddress, module_name: &str) -> StateKey {
+    StateKey::module(&account, &Identifier::new(module_name).unwrap())
+}
+
+/// Creates a partial state progress for the given accounts and versions
+fn create_progress(
+    account_addresses: Vec<AccountAddress>,
+    snapshot_version: Version,
+    synced_version: Version,
+    include_synced_block: bool,
+) -> PartialStateProgress {
+    let filter = StateValuesFilter::new(account_addresses, vec![]);
+    let synced_block = include_synced_block.then(|| (synced_version, create_event(None)));
+    PartialStateProgress::new(
+        filter,
+        snapshot_version,
+        create_ledger_info_at_version(synced_version),
+        synced_block,
+    )
+}
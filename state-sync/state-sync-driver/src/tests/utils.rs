@@ -3,7 +3,15 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::driver::DriverConfiguration;
-use aptos_config::config::{ConsensusObserverConfig, RoleType, StateSyncDriverConfig};
+use aptos_backup_cli::{
+    backup_types::epoch_ending::manifest::{EpochEndingBackup, EpochEndingChunk},
+    metadata::Metadata,
+    storage::{BackupHandleRef, BackupStorage, FileHandle},
+};
+use aptos_config::config::{
+    BackupStorageConfig, ConsensusObserverConfig, PartialStateSyncConfig, RoleType,
+    StateSyncBackupConfig, StateSyncDriverConfig,
+};
 use aptos_crypto::{
     ed25519::{Ed25519PrivateKey, Ed25519Signature},
     HashValue, PrivateKey, Uniform,
@@ -43,6 +51,9 @@ use aptos_types::{
 use futures::{channel::mpsc, StreamExt};
 use move_core_types::language_storage::TypeTag;
 use rand::{rngs::OsRng, Rng};
+use serde::Serialize;
+use std::{path::Path, sync::Arc};
+use tokio::io::AsyncWriteExt;
 
 /// Creates a new data stream listener and notification sender pair
 pub fn create_data_stream_listener() -> (mpsc::Sender<DataNotification>, DataStreamListener) {
@@ -85,18 +96,34 @@ pub fn create_event(event_key: Option<EventKey>) -> ContractEvent {
 /// Creates a test driver configuration for full nodes
 pub fn create_full_node_driver_configuration() -> DriverConfiguration {
     let config = StateSyncDriverConfig::default();
+    let backup_config = StateSyncBackupConfig::default();
     let consensus_observer_config = ConsensusObserverConfig::default();
+    let partial_state_config = PartialStateSyncConfig::default();
     let role = RoleType::FullNode;
     let waypoint = Waypoint::default();
 
     DriverConfiguration {
         config,
+        backup_config,
         consensus_observer_config,
+        partial_state_config,
         role,
         waypoint,
     }
 }
 
+/// Creates a backup config that restores from the given local backup directory
+pub fn create_local_backup_config(
+    backup_dir: &Path,
+    metadata_cache_dir: &Path,
+) -> StateSyncBackupConfig {
+    StateSyncBackupConfig {
+        backup_storage: Some(BackupStorageConfig::LocalFs(backup_dir.to_path_buf())),
+        metadata_cache_dir: Some(metadata_cache_dir.to_path_buf()),
+        ..StateSyncBackupConfig::default()
+    }
+}
+
 /// Creates a global data summary with the highest ended epoch
 pub fn create_global_summary(highest_ended_epoch: Epoch) -> GlobalDataSummary {
     let mut global_data_summary = GlobalDataSummary::empty();
@@ -305,3 +332,96 @@ pub async fn verify_commit_notification(
         expected_highest_synced_version
     );
 }
+
+/// Saves the given metadata line to the backup
+pub async fn save_backup_metadata(backup_storage: &Arc<dyn BackupStorage>, metadata: Metadata) {
+    backup_storage
+        .save_metadata_line(&metadata.name(), &metadata.to_text_line().unwrap())
+        .await
+        .unwrap();
+}
+
+/// Writes the given bytes to a new file in the backup
+pub async fn write_backup_file(
+    backup_storage: &Arc<dyn BackupStorage>,
+    backup_handle: &BackupHandleRef,
+    file_name: &str,
+    bytes: &[u8],
+) -> FileHandle {
+    let (file_handle, mut file) = backup_storage
+        .create_for_write(backup_handle, &file_name.parse().unwrap())
+        .await
+        .unwrap();
+    file.write_all(bytes).await.unwrap();
+    file.shutdown().await.unwrap();
+    file_handle
+}
+
+/// Writes the given records to a new file in the backup (each record is BCS
+/// serialized and prefixed by its length, as done by the backup-cli).
+pub async fn write_backup_records<T: Serialize>(
+    backup_storage: &Arc<dyn BackupStorage>,
+    backup_handle: &BackupHandleRef,
+    file_name: &str,
+    records: &[T],
+) -> FileHandle {
+    let mut bytes = vec![];
+    for record in records {
+        let record_bytes = bcs::to_bytes(record).unwrap();
+        bytes.extend((record_bytes.len() as u32).to_be_bytes());
+        bytes.extend(record_bytes);
+    }
+    write_backup_file(backup_storage, backup_handle, file_name, &bytes).await
+}
+
+/// Writes an epoch ending backup (with a single chunk) holding the given ledger infos
+pub async fn write_epoch_ending_backup(
+    backup_storage: &Arc<dyn BackupStorage>,
+    ledger_infos: &[LedgerInfoWithSignatures],
+) {
+    let first_ledger_info = ledger_infos.first().unwrap().ledger_info();
+    let last_ledger_info = ledger_infos.last().unwrap().ledger_info();
+    let (first_epoch, last_epoch) = (first_ledger_info.epoch(), last_ledger_info.epoch());
+
+    // Write the ledger infos and the manifest
+    let backup_handle = backup_storage
+        .create_backup(
+            &format!("epoch_ending_{}-{}", first_epoch, last_epoch)
+                .parse()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let ledger_infos_handle =
+        write_backup_records(backup_storage, &backup_handle, "0-.chunk", ledger_infos).await;
+    let manifest = EpochEndingBackup {
+        first_epoch,
+        last_epoch,
+        waypoints: ledger_infos
+            .iter()
+            .map(|ledger_info| Waypoint::new_any(ledger_info.ledger_info()))
+            .collect(),
+        chunks: vec![EpochEndingChunk {
+            first_epoch,
+            last_epoch,
+            ledger_infos: ledger_infos_handle,
+        }],
+    };
+    let manifest_handle = write_backup_file(
+        backup_storage,
+        &backup_handle,
+        "epoch_ending.manifest",
+        &serde_json::to_vec(&manifest).unwrap(),
+    )
+    .await;
+
+    // Save the metadata
+    let metadata = Metadata::new_epoch_ending_backup(
+        first_epoch,
+        last_epoch,
+        first_ledger_info.version(),
+        last_ledger_info.version(),
+        manifest_handle,
+    );
+    save_backup_metadata(backup_storage, metadata).await;
+}
@@ -21,14 +21,14 @@ use aptos_logger::{debug, sample, sample::SampleRate, trace, warn};
 use aptos_network::protocols::wire::handshake::v1::ProtocolId;
 use aptos_storage_service_types::{
     requests::{
-        DataRequest, EpochEndingLedgerInfoRequest, StateValuesWithProofRequest,
-        StorageServiceRequest, TransactionOutputsWithProofRequest,
+        DataRequest, EpochEndingLedgerInfoRequest, StateValuesByFilterWithProofRequest,
+        StateValuesWithProofRequest, StorageServiceRequest, TransactionOutputsWithProofRequest,
         TransactionsOrOutputsWithProofRequest, TransactionsWithProofRequest,
     },
     responses::{
         DataResponse, ServerProtocolVersion, StorageServerSummary, StorageServiceResponse,
     },
-    StorageServiceError,
+    StorageServiceError, STATE_VALUES_BY_FILTER_PROTOCOL_VERSION,
 };
 use aptos_time_service::TimeService;
 use aptos_types::transaction::Version;
@@ -39,7 +39,7 @@ use std::{sync::Arc, time::Duration};
 
 /// Storage server constants
 const ERROR_LOG_FREQUENCY_SECS: u64 = 5; // The frequency to log errors
-const STORAGE_SERVER_VERSION: u64 = 1;
+const STORAGE_SERVER_VERSION: u64 = STATE_VALUES_BY_FILTER_PROTOCOL_VERSION; // The latest protocol version (adds filtered state value requests)
 const SUMMARY_LOG_FREQUENCY_SECS: u64 = 5; // The frequency to log the storage server summary (secs)
 
 /// The `Handler` is the "pure" inbound request handler. It contains all the
@@ -394,6 +394,9 @@ impl<T: StorageReaderInterface> Handler<T> {
             DataRequest::GetStateValuesWithProof(request) => {
                 self.get_state_value_chunk_with_proof(request)
             },
+            DataRequest::GetStateValuesByFilterWithProof(request) => {
+                self.get_state_values_by_filter_with_proof(request)
+            },
             DataRequest::GetEpochEndingLedgerInfos(request) => {
                 self.get_epoch_ending_ledger_infos(request)
             },
@@ -458,6 +461,23 @@ impl<T: StorageReaderInterface> Handler<T> {
         ))
     }
 
+    fn get_state_values_by_filter_with_proof(
+        &self,
+        request: &StateValuesByFilterWithProofRequest,
+    ) -> aptos_storage_service_types::Result<DataResponse, Error> {
+        let state_values_by_filter_with_proof =
+            self.storage.get_state_values_by_filter_with_proof(
+                request.version,
+                request.start_index,
+                request.end_index,
+                &request.filter,
+            )?;
+
+        Ok(DataResponse::StateValuesByFilterWithProof(
+            state_values_by_filter_with_proof,
+        ))
+    }
+
     fn get_epoch_ending_ledger_infos(
         &self,
         request: &EpochEndingLedgerInfoRequest,
@@ -5,13 +5,21 @@ use crate::{error::Error, metrics::increment_network_frame_overflow};
 use aptos_config::config::StorageServiceConfig;
 use aptos_logger::debug;
 use aptos_storage_interface::{AptosDbError, DbReader, Result as StorageResult};
-use aptos_storage_service_types::responses::{
-    CompleteDataRange, DataResponse, DataSummary, TransactionOrOutputListWithProof,
+use aptos_storage_service_types::{
+    requests::StateValuesFilter,
+    responses::{
+        CompleteDataRange, DataResponse, DataSummary, StateValuesByFilterWithProof,
+        TransactionOrOutputListWithProof,
+    },
 };
 use aptos_types::{
     epoch_change::EpochChangeProof,
     ledger_info::LedgerInfoWithSignatures,
-    state_store::state_value::StateValueChunkWithProof,
+    proof::SparseMerkleProof,
+    state_store::{
+        state_key::StateKey,
+        state_value::{StateValue, StateValueChunkWithProof},
+    },
     transaction::{TransactionListWithProof, TransactionOutputListWithProof, Version},
 };
 use serde::Serialize;
@@ -89,6 +97,18 @@ pub trait StorageReaderInterface: Clone + Send + 'static {
         start_index: u64,
         end_index: u64,
     ) -> aptos_storage_service_types::Result<StateValueChunkWithProof, Error>;
+
+    /// Returns the state values (each with a proof) that match the given
+    /// `filter`, found by scanning the state values from the specified
+    /// `start_index` to `end_index` (inclusive). In some cases, less state
+    /// values may be scanned (e.g., due to network or chunk limits).
+    fn get_state_values_by_filter_with_proof(
+        &self,
+        version: u64,
+        start_index: u64,
+        end_index: u64,
+        filter: &StateValuesFilter,
+    ) -> aptos_storage_service_types::Result<StateValuesByFilterWithProof, Error>;
 }
 
 /// The underlying implementation of the StorageReaderInterface, used by the
@@ -498,6 +518,95 @@ impl StorageReaderInterface for StorageReader {
             version, start_index, end_index
         )))
     }
+
+    fn get_state_values_by_filter_with_proof(
+        &self,
+        version: u64,
+        start_index: u64,
+        end_index: u64,
+        filter: &StateValuesFilter,
+    ) -> aptos_storage_service_types::Result<StateValuesByFilterWithProof, Error> {
+        // Verify the filter is not empty
+        if filter.is_empty() {
+            return Err(Error::InvalidRequest(
+                "The state values filter cannot be empty!".into(),
+            ));
+        }
+
+        // Calculate the number of state values to scan
+        let expected_num_state_values = inclusive_range_len(start_index, end_index)?;
+        let max_num_state_values = self.config.max_state_chunk_size;
+        let num_state_values_to_scan = min(expected_num_state_values, max_num_state_values);
+
+        // Scan the state values and fetch a proof for each match
+        let state_value_chunk_with_proof = self
+            .storage
+            .get_state_value_chunk_with_proof(
+                version,
+                start_index as usize,
+                num_state_values_to_scan as usize,
+            )
+            .map_err(|error| Error::StorageErrorEncountered(error.to_string()))?;
+        let first_index = state_value_chunk_with_proof.first_index;
+        let mut last_index = state_value_chunk_with_proof.last_index;
+        let mut matching_state_values: Vec<(u64, (StateKey, StateValue, SparseMerkleProof))> =
+            vec![];
+        for (offset, (state_key, state_value)) in state_value_chunk_with_proof
+            .raw_values
+            .into_iter()
+            .enumerate()
+        {
+            if filter.matches(&state_key) {
+                let (_, proof) = self
+                    .storage
+                    .get_state_value_with_proof_by_version(&state_key, version)
+                    .map_err(|error| Error::StorageErrorEncountered(error.to_string()))?;
+                let state_index = first_index + offset as u64;
+                matching_state_values.push((state_index, (state_key, state_value, proof)));
+            }
+        }
+
+        // Attempt to serve the request
+        loop {
+            let state_values_by_filter_with_proof = StateValuesByFilterWithProof {
+                version,
+                first_index,
+                last_index,
+                root_hash: state_value_chunk_with_proof.root_hash,
+                state_values: matching_state_values
+                    .iter()
+                    .map(|(_, state_value)| state_value.clone())
+                    .collect(),
+            };
+            let num_state_values = matching_state_values.len();
+            if num_state_values <= 1 {
+                return Ok(state_values_by_filter_with_proof); // We cannot return less than a single item
+            }
+
+            // Attempt to divide up the response if it overflows the message size
+            let (overflow_frame, num_bytes) = check_overflow_network_frame(
+                &state_values_by_filter_with_proof,
+                self.config.max_network_chunk_bytes,
+            )?;
+            if !overflow_frame {
+                return Ok(state_values_by_filter_with_proof);
+            } else {
+                increment_network_frame_overflow(
+                    DataResponse::StateValuesByFilterWithProof(state_values_by_filter_with_proof)
+                        .get_label(),
+                );
+                let new_num_state_values = num_state_values / 2;
+                debug!("The response with {:?} filtered state values was too large (num bytes: {:?}). Retrying with {:?}.",
+                    num_state_values, num_bytes, new_num_state_values);
+
+                // Only return the first half of the matches (and update the last scanned index)
+                matching_state_values.truncate(new_num_state_values);
+                if let Some((state_index, _)) = matching_state_values.last() {
+                    last_index = *state_index;
+                }
+            }
+        }
+    }
 }
 
 // A simple macro that wraps each storage read call with a timer
@@ -572,6 +681,12 @@ impl DbReader for TimedStorageReader {
 
         fn get_state_item_count(&self, version: Version) -> StorageResult<usize>;
 
+        fn get_state_value_with_proof_by_version(
+            &self,
+            state_key: &StateKey,
+            version: Version,
+        ) -> StorageResult<(Option<StateValue>, SparseMerkleProof)>;
+
         fn get_state_value_chunk_with_proof(
             &self,
             version: Version,
@@ -9,7 +9,7 @@ use aptos_storage_service_types::{
 use claims::assert_matches;
 
 // Useful test constants
-const PROTOCOL_VERSION: u64 = 1;
+const PROTOCOL_VERSION: u64 = 2;
 
 #[tokio::test]
 async fn test_get_server_protocol_version() {
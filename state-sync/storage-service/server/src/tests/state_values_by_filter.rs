@@ -0,0 +1,284 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::tests::{
+    mock,
+    mock::{MockClient, MockDatabaseReader},
+    utils,
+};
+use aptos_config::config::StorageServiceConfig;
+use aptos_crypto::hash::HashValue;
+use aptos_storage_service_types::{
+    requests::{DataRequest, StateValuesByFilterWithProofRequest, StateValuesFilter},
+    responses::{DataResponse, StateValuesByFilterWithProof, StorageServiceResponse},
+    StorageServiceError,
+};
+use aptos_types::{
+    account_address::AccountAddress,
+    account_config::{AccountResource, CoinStoreResource},
+    proof::{definition::SparseMerkleRangeProof, SparseMerkleProof},
+    state_store::{
+        state_key::StateKey,
+        state_value::{StateValue, StateValueChunkWithProof},
+    },
+};
+use claims::assert_matches;
+use mockall::predicate::eq;
+
+#[tokio::test]
+async fn test_get_state_values_by_filter_with_proof() {
+    // Create test data
+    let version = 101;
+    let start_index = 100;
+    let tracked_account = AccountAddress::random();
+    let raw_values = create_state_keys_and_values(vec![
+        AccountAddress::random(),
+        tracked_account,
+        AccountAddress::random(),
+        tracked_account,
+    ]);
+    let state_value_chunk_with_proof = create_state_value_chunk(start_index, raw_values.clone());
+
+    // Create the mock db reader
+    let mut db_reader = mock::create_mock_db_reader();
+    expect_get_state_value_chunk_with_proof(
+        &mut db_reader,
+        version,
+        start_index,
+        raw_values.len() as u64,
+        state_value_chunk_with_proof.clone(),
+    );
+    let matching_state_values: Vec<_> = [&raw_values[1], &raw_values[3]]
+        .into_iter()
+        .map(|(state_key, state_value)| {
+            let proof = SparseMerkleProof::new(None, vec![HashValue::random()]);
+            expect_get_state_value_with_proof_by_version(
+                &mut db_reader,
+                state_key.clone(),
+                version,
+                state_value.clone(),
+                proof.clone(),
+            );
+            (state_key.clone(), state_value.clone(), proof)
+        })
+        .collect();
+
+    // Create the storage client and server
+    let (mut mock_client, mut service, _, _, _) = MockClient::new(Some(db_reader), None);
+    utils::update_storage_server_summary(&mut service, version, 10);
+    tokio::spawn(service.start());
+
+    // Process a request to fetch the filtered state values
+    let filter = StateValuesFilter::new(vec![tracked_account], vec![]);
+    let end_index = start_index + raw_values.len() as u64 - 1;
+    let response = get_state_values_by_filter_with_proof(
+        &mut mock_client,
+        version,
+        start_index,
+        end_index,
+        filter,
+    )
+    .await
+    .unwrap();
+
+    // Verify the response only contains the matching state values
+    assert_matches!(response, StorageServiceResponse::RawResponse(_));
+    assert_eq!(
+        response.get_data_response().unwrap(),
+        DataResponse::StateValuesByFilterWithProof(StateValuesByFilterWithProof {
+            version,
+            first_index: start_index,
+            last_index: end_index,
+            root_hash: state_value_chunk_with_proof.root_hash,
+            state_values: matching_state_values,
+        })
+    );
+}
+
+#[tokio::test]
+async fn test_get_state_values_by_filter_with_proof_chunk_limit() {
+    // Create test data
+    let max_state_chunk_size = StorageServiceConfig::default().max_state_chunk_size;
+    let version = 101;
+    let start_index = 0;
+    let raw_values = create_state_keys_and_values(vec![AccountAddress::random()]);
+    let state_value_chunk_with_proof = create_state_value_chunk(start_index, raw_values);
+
+    // Create the mock db reader (the scan should be limited to the max chunk size)
+    let mut db_reader = mock::create_mock_db_reader();
+    expect_get_state_value_chunk_with_proof(
+        &mut db_reader,
+        version,
+        start_index,
+        max_state_chunk_size,
+        state_value_chunk_with_proof.clone(),
+    );
+
+    // Create the storage client and server
+    let (mut mock_client, mut service, _, _, _) = MockClient::new(Some(db_reader), None);
+    utils::update_storage_server_summary(&mut service, version, 10);
+    tokio::spawn(service.start());
+
+    // Process a request to scan more state values than the max chunk size
+    let filter = StateValuesFilter::new(vec![AccountAddress::random()], vec![]);
+    let response = get_state_values_by_filter_with_proof(
+        &mut mock_client,
+        version,
+        start_index,
+        max_state_chunk_size * 10,
+        filter,
+    )
+    .await
+    .unwrap();
+
+    // Verify that no state values were returned
+    match response.get_data_response().unwrap() {
+        DataResponse::StateValuesByFilterWithProof(state_values_by_filter) => {
+            assert!(state_values_by_filter.state_values.is_empty());
+            assert_eq!(
+                state_values_by_filter.last_index,
+                state_value_chunk_with_proof.last_index
+            );
+        },
+        _ => panic!("Expected filtered state values but got: {:?}", response),
+    }
+}
+
+#[tokio::test]
+async fn test_get_state_values_by_filter_with_proof_invalid() {
+    // Create the storage client and server
+    let (mut mock_client, mut service, _, _, _) = MockClient::new(None, None);
+    utils::update_storage_server_summary(&mut service, 101, 10);
+    tokio::spawn(service.start());
+
+    // Test invalid ranges
+    let start_index = 100;
+    for end_index in [0, 99] {
+        let filter = StateValuesFilter::new(vec![AccountAddress::random()], vec![]);
+        let response = get_state_values_by_filter_with_proof(
+            &mut mock_client,
+            0,
+            start_index,
+            end_index,
+            filter,
+        )
+        .await
+        .unwrap_err();
+        assert_matches!(response, StorageServiceError::InvalidRequest(_));
+    }
+
+    // Test an empty filter
+    let response = get_state_values_by_filter_with_proof(
+        &mut mock_client,
+        101,
+        0,
+        start_index,
+        StateValuesFilter::default(),
+    )
+    .await
+    .unwrap_err();
+    assert_matches!(response, StorageServiceError::InvalidRequest(_));
+}
+
+#[tokio::test]
+async fn test_get_state_values_by_filter_with_proof_not_serviceable() {
+    // Create the storage client and server (that cannot service the request)
+    let version = 101;
+    let (mut mock_client, mut service, _, _, _) = MockClient::new(None, None);
+    utils::update_storage_server_summary(&mut service, version - 1, 10);
+    tokio::spawn(service.start());
+
+    // Process a request to fetch the filtered state values
+    let filter = StateValuesFilter::new(vec![AccountAddress::random()], vec![]);
+    let response = get_state_values_by_filter_with_proof(&mut mock_client, version, 0, 99, filter)
+        .await
+        .unwrap_err();
+
+    // Verify the request is not serviceable
+    assert_matches!(response, StorageServiceError::InvalidRequest(_));
+}
+
+/// Creates a state value chunk (starting at the given index) with the given values
+fn create_state_value_chunk(
+    start_index: u64,
+    raw_values: Vec<(StateKey, StateValue)>,
+) -> StateValueChunkWithProof {
+    StateValueChunkWithProof {
+        first_index: start_index,
+        last_index: start_index + raw_values.len() as u64 - 1,
+        first_key: HashValue::random(),
+        last_key: HashValue::random(),
+        raw_values,
+        proof: SparseMerkleRangeProof::new(vec![]),
+        root_hash: HashValue::random(),
+    }
+}
+
+/// Creates a pair of resources for each of the given accounts
+fn create_state_keys_and_values(accounts: Vec<AccountAddress>) -> Vec<(StateKey, StateValue)> {
+    accounts
+        .into_iter()
+        .enumerate()
+        .map(|(index, account)| {
+            let state_key = if index % 2 == 0 {
+                StateKey::resource_typed::<AccountResource>(&account).unwrap()
+            } else {
+                StateKey::resource_typed::<CoinStoreResource>(&account).unwrap()
+            };
+            let state_value = StateValue::new_legacy(vec![index as u8; 10].into());
+            (state_key, state_value)
+        })
+        .collect()
+}
+
+/// Sets an expectation on the given mock db for a call to fetch a state value chunk
+fn expect_get_state_value_chunk_with_proof(
+    mock_db: &mut MockDatabaseReader,
+    version: u64,
+    start_index: u64,
+    chunk_size: u64,
+    state_value_chunk_with_proof: StateValueChunkWithProof,
+) {
+    mock_db
+        .expect_get_state_value_chunk_with_proof()
+        .times(1)
+        .with(
+            eq(version),
+            eq(start_index as usize),
+            eq(chunk_size as usize),
+        )
+        .returning(move |_, _, _| Ok(state_value_chunk_with_proof.clone()));
+}
+
+/// Sets an expectation on the given mock db for a call to fetch a state value with proof
+fn expect_get_state_value_with_proof_by_version(
+    mock_db: &mut MockDatabaseReader,
+    state_key: StateKey,
+    version: u64,
+    state_value: StateValue,
+    proof: SparseMerkleProof,
+) {
+    mock_db
+        .expect_get_state_value_with_proof_by_version()
+        .times(1)
+        .with(eq(state_key), eq(version))
+        .returning(move |_, _| Ok((Some(state_value.clone()), proof.clone())));
+}
+
+/// Sends a filtered state values request and processes the response
+async fn get_state_values_by_filter_with_proof(
+    mock_client: &mut MockClient,
+    version: u64,
+    start_index: u64,
+    end_index: u64,
+    filter: StateValuesFilter,
+) -> Result<StorageServiceResponse, StorageServiceError> {
+    let data_request =
+        DataRequest::GetStateValuesByFilterWithProof(StateValuesByFilterWithProofRequest {
+            version,
+            start_index,
+            end_index,
+            filter,
+        });
+    utils::send_storage_request(mock_client, false, data_request).await
+}
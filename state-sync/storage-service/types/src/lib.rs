@@ -19,6 +19,11 @@ mod tests;
 /// (if the request/response requires compression).
 const COMPRESSION_SUFFIX_LABEL: &str = "_compressed";
 
+/// The first storage server protocol version that supports filtered state
+/// value requests (i.e., `GetStateValuesByFilterWithProof`). Clients must
+/// never send these requests to servers running an older protocol version.
+pub const STATE_VALUES_BY_FILTER_PROTOCOL_VERSION: u64 = 2;
+
 /// A type alias for different epochs.
 pub type Epoch = u64;
 
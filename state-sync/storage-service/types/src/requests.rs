@@ -2,7 +2,11 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::COMPRESSION_SUFFIX_LABEL;
-use aptos_types::transaction::Version;
+use aptos_types::{
+    account_address::AccountAddress,
+    state_store::state_key::{inner::StateKeyInner, prefix::StateKeyPrefix, StateKey},
+    transaction::Version,
+};
 use serde::{Deserialize, Serialize};
 
 /// A storage service request.
@@ -47,6 +51,7 @@ pub enum DataRequest {
     SubscribeTransactionOutputsWithProof(SubscribeTransactionOutputsWithProofRequest), // Subscribes to transaction outputs with a proof
     SubscribeTransactionsOrOutputsWithProof(SubscribeTransactionsOrOutputsWithProofRequest), // Subscribes to transactions or outputs with a proof
     SubscribeTransactionsWithProof(SubscribeTransactionsWithProofRequest), // Subscribes to transactions with a proof
+    GetStateValuesByFilterWithProof(StateValuesByFilterWithProofRequest), // Fetches a filtered list of states with proofs
 }
 
 impl DataRequest {
@@ -73,6 +78,7 @@ impl DataRequest {
                 "subscribe_transactions_or_outputs_with_proof"
             },
             Self::SubscribeTransactionsWithProof(_) => "subscribe_transactions_with_proof",
+            Self::GetStateValuesByFilterWithProof(_) => "get_state_values_by_filter_with_proof",
         }
     }
 
@@ -86,6 +92,10 @@ impl DataRequest {
         matches!(self, &Self::GetServerProtocolVersion)
     }
 
+    pub fn is_state_values_by_filter_request(&self) -> bool {
+        matches!(self, &Self::GetStateValuesByFilterWithProof(_))
+    }
+
     pub fn is_storage_summary_request(&self) -> bool {
         matches!(self, &Self::GetStorageServerSummary)
     }
@@ -130,6 +140,72 @@ pub struct StateValuesWithProofRequest {
     pub end_index: u64,   // The index to stop fetching state values (inclusive)
 }
 
+/// A storage service request for fetching the state values (at a specified
+/// version) that match the given filter. The server scans the state values
+/// between the start and end index (inclusive) and only returns the matches.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct StateValuesByFilterWithProofRequest {
+    pub version: u64,              // The version to fetch the state values at
+    pub start_index: u64,          // The index to start scanning state values (inclusive)
+    pub end_index: u64,            // The index to stop scanning state values (inclusive)
+    pub filter: StateValuesFilter, // The filter that state keys must match
+}
+
+/// A filter over state keys. A state key matches the filter if it belongs
+/// to one of the account addresses, or if its encoding starts with one of
+/// the state key prefixes (see `StateKeyPrefix::encode()`).
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct StateValuesFilter {
+    pub account_addresses: Vec<AccountAddress>, // The accounts to fetch resources and modules for
+    pub state_key_prefixes: Vec<Vec<u8>>,       // The encoded state key prefixes to fetch
+}
+
+impl StateValuesFilter {
+    pub fn new(account_addresses: Vec<AccountAddress>, state_key_prefixes: Vec<Vec<u8>>) -> Self {
+        Self {
+            account_addresses,
+            state_key_prefixes,
+        }
+    }
+
+    /// Returns true iff the filter will not match any state keys
+    pub fn is_empty(&self) -> bool {
+        self.account_addresses.is_empty() && self.state_key_prefixes.is_empty()
+    }
+
+    /// Returns true iff the given state key matches the filter. Note: table
+    /// items are not owned by an account, so they can only be matched by prefix.
+    pub fn matches(&self, state_key: &StateKey) -> bool {
+        if let StateKeyInner::AccessPath(access_path) = state_key.inner() {
+            if self.account_addresses.contains(&access_path.address) {
+                return true;
+            }
+        }
+
+        let encoded_key = state_key.encoded();
+        self.state_key_prefixes
+            .iter()
+            .any(|prefix| encoded_key.starts_with(prefix))
+    }
+
+    /// Returns true iff every state key with the given encoded prefix (see
+    /// `StateKeyPrefix::encode()`) is guaranteed to match the filter.
+    pub fn matches_all_with_prefix(&self, encoded_prefix: &[u8]) -> bool {
+        let matches_account = self.account_addresses.iter().any(|account_address| {
+            StateKeyPrefix::from(*account_address)
+                .encode()
+                .map_or(false, |account_prefix| {
+                    encoded_prefix.starts_with(&account_prefix)
+                })
+        });
+        matches_account
+            || self
+                .state_key_prefixes
+                .iter()
+                .any(|prefix| encoded_prefix.starts_with(prefix))
+    }
+}
+
 /// A storage service request for fetching a transaction output list with a
 /// corresponding proof.
 #[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
@@ -2,13 +2,17 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    requests::DataRequest::{
-        GetEpochEndingLedgerInfos, GetNewTransactionOutputsWithProof,
-        GetNewTransactionsOrOutputsWithProof, GetNewTransactionsWithProof,
-        GetNumberOfStatesAtVersion, GetServerProtocolVersion, GetStateValuesWithProof,
-        GetStorageServerSummary, GetTransactionOutputsWithProof, GetTransactionsOrOutputsWithProof,
-        GetTransactionsWithProof, SubscribeTransactionOutputsWithProof,
-        SubscribeTransactionsOrOutputsWithProof, SubscribeTransactionsWithProof,
+    requests::{
+        DataRequest::{
+            GetEpochEndingLedgerInfos, GetNewTransactionOutputsWithProof,
+            GetNewTransactionsOrOutputsWithProof, GetNewTransactionsWithProof,
+            GetNumberOfStatesAtVersion, GetServerProtocolVersion, GetStateValuesByFilterWithProof,
+            GetStateValuesWithProof, GetStorageServerSummary, GetTransactionOutputsWithProof,
+            GetTransactionsOrOutputsWithProof, GetTransactionsWithProof,
+            SubscribeTransactionOutputsWithProof, SubscribeTransactionsOrOutputsWithProof,
+            SubscribeTransactionsWithProof,
+        },
+        StateValuesFilter,
     },
     responses::Error::DegenerateRangeError,
     Epoch, StorageServiceRequest, COMPRESSION_SUFFIX_LABEL,
@@ -17,11 +21,16 @@ use aptos_compression::{client::CompressionClient, CompressedData};
 use aptos_config::config::{
     AptosDataClientConfig, StorageServiceConfig, MAX_APPLICATION_MESSAGE_SIZE,
 };
+use aptos_crypto::{hash::CryptoHash, HashValue};
 use aptos_time_service::{TimeService, TimeServiceTrait};
 use aptos_types::{
     epoch_change::EpochChangeProof,
     ledger_info::LedgerInfoWithSignatures,
-    state_store::state_value::StateValueChunkWithProof,
+    proof::SparseMerkleProof,
+    state_store::{
+        state_key::StateKey,
+        state_value::{StateValue, StateValueChunkWithProof},
+    },
     transaction::{TransactionListWithProof, TransactionOutputListWithProof, Version},
 };
 use num_traits::{PrimInt, Zero};
@@ -139,6 +148,7 @@ pub enum DataResponse {
     TransactionsWithProof(TransactionListWithProof),
     NewTransactionsOrOutputsWithProof((TransactionOrOutputListWithProof, LedgerInfoWithSignatures)),
     TransactionsOrOutputsWithProof(TransactionOrOutputListWithProof),
+    StateValuesByFilterWithProof(StateValuesByFilterWithProof),
 }
 
 impl DataResponse {
@@ -156,6 +166,7 @@ impl DataResponse {
             Self::TransactionsWithProof(_) => "transactions_with_proof",
             Self::NewTransactionsOrOutputsWithProof(_) => "new_transactions_or_outputs_with_proof",
             Self::TransactionsOrOutputsWithProof(_) => "transactions_or_outputs_with_proof",
+            Self::StateValuesByFilterWithProof(_) => "state_values_by_filter_with_proof",
         }
     }
 }
@@ -193,6 +204,21 @@ impl TryFrom<StorageServiceResponse> for StateValueChunkWithProof {
     }
 }
 
+impl TryFrom<StorageServiceResponse> for StateValuesByFilterWithProof {
+    type Error = crate::responses::Error;
+
+    fn try_from(response: StorageServiceResponse) -> crate::Result<Self, Self::Error> {
+        let data_response = response.get_data_response()?;
+        match data_response {
+            DataResponse::StateValuesByFilterWithProof(inner) => Ok(inner),
+            _ => Err(Error::UnexpectedResponseError(format!(
+                "expected state_values_by_filter_with_proof, found {}",
+                data_response.get_label()
+            ))),
+        }
+    }
+}
+
 impl TryFrom<StorageServiceResponse> for EpochChangeProof {
     type Error = crate::responses::Error;
 
@@ -347,6 +373,58 @@ impl TryFrom<StorageServiceResponse> for TransactionOrOutputListWithProof {
     }
 }
 
+/// A list of state values (at a specific version) that matched a state
+/// values filter, each with a sparse merkle proof relative to the root hash.
+/// The first and last index denote the range of state values scanned by the
+/// server (inclusive), so that clients can resume scanning at `last_index + 1`.
+///
+/// Note: the proofs only guarantee that each returned state value exists in
+/// the state tree. They do not guarantee that every match was returned.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct StateValuesByFilterWithProof {
+    pub version: Version,     // The version of the state values
+    pub first_index: u64,     // The first state index scanned by the server
+    pub last_index: u64,      // The last state index scanned by the server (inclusive)
+    pub root_hash: HashValue, // The root hash of the sparse merkle tree at the version
+    pub state_values: Vec<(StateKey, StateValue, SparseMerkleProof)>, // The matching states and proofs
+}
+
+impl StateValuesByFilterWithProof {
+    /// Verifies that every state value is included in the state tree with
+    /// the given root hash, and that every state key matches the filter.
+    pub fn verify(
+        &self,
+        expected_root_hash: HashValue,
+        filter: &StateValuesFilter,
+    ) -> crate::Result<(), Error> {
+        if self.root_hash != expected_root_hash {
+            return Err(Error::UnexpectedResponseError(format!(
+                "State root hash mismatch! Expected: {:?}, found: {:?}",
+                expected_root_hash, self.root_hash
+            )));
+        }
+
+        for (state_key, state_value, proof) in &self.state_values {
+            if !filter.matches(state_key) {
+                return Err(Error::UnexpectedResponseError(format!(
+                    "State key does not match the filter: {:?}",
+                    state_key
+                )));
+            }
+            proof
+                .verify(expected_root_hash, state_key.hash(), Some(state_value))
+                .map_err(|error| {
+                    Error::UnexpectedResponseError(format!(
+                        "Failed to verify the state value proof for key: {:?}. Error: {:?}",
+                        state_key, error
+                    ))
+                })?;
+        }
+
+        Ok(())
+    }
+}
+
 /// The protocol version run by this server. Clients request this first to
 /// identify what API calls and data requests the server supports.
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
@@ -488,6 +566,20 @@ impl DataSummary {
 
                 can_serve_states && can_create_proof
             },
+            GetStateValuesByFilterWithProof(request) => {
+                let can_serve_states = self
+                    .states
+                    .map(|range| range.contains(request.version))
+                    .unwrap_or(false);
+
+                let can_create_proof = self
+                    .synced_ledger_info
+                    .as_ref()
+                    .map(|li| li.ledger_info().version() >= request.version)
+                    .unwrap_or(false);
+
+                can_serve_states && can_create_proof
+            },
             GetTransactionOutputsWithProof(request) => {
                 let desired_range =
                     match CompleteDataRange::new(request.start_version, request.end_version) {
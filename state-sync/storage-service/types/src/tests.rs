@@ -5,21 +5,29 @@ use crate::{
     requests::{
         DataRequest, EpochEndingLedgerInfoRequest, NewTransactionOutputsWithProofRequest,
         NewTransactionsOrOutputsWithProofRequest, NewTransactionsWithProofRequest,
-        StateValuesWithProofRequest, SubscribeTransactionOutputsWithProofRequest,
+        StateValuesFilter, StateValuesWithProofRequest,
+        SubscribeTransactionOutputsWithProofRequest,
         SubscribeTransactionsOrOutputsWithProofRequest, SubscribeTransactionsWithProofRequest,
         SubscriptionStreamMetadata, TransactionOutputsWithProofRequest,
         TransactionsOrOutputsWithProofRequest, TransactionsWithProofRequest,
     },
-    responses::{CompleteDataRange, DataSummary, ProtocolMetadata},
+    responses::{CompleteDataRange, DataSummary, ProtocolMetadata, StateValuesByFilterWithProof},
     Epoch, StorageServiceRequest,
 };
 use aptos_config::config::AptosDataClientConfig;
-use aptos_crypto::hash::HashValue;
+use aptos_crypto::hash::{CryptoHash, HashValue};
 use aptos_time_service::{TimeService, TimeServiceTrait};
 use aptos_types::{
+    account_address::AccountAddress,
+    account_config::AccountResource,
     aggregate_signature::AggregateSignature,
     block_info::BlockInfo,
     ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
+    proof::{SparseMerkleLeafNode, SparseMerkleProof},
+    state_store::{
+        state_key::{prefix::StateKeyPrefix, StateKey},
+        state_value::StateValue,
+    },
     transaction::Version,
 };
 use claims::{assert_err, assert_ok};
@@ -466,6 +474,103 @@ fn test_data_summary_can_service_state_chunk_request() {
     }
 }
 
+#[test]
+fn test_state_values_filter_matches() {
+    // Create several state keys for different accounts
+    let account_1 = AccountAddress::random();
+    let account_2 = AccountAddress::random();
+    let account_3 = AccountAddress::random();
+    let resource_key_1 = StateKey::resource_typed::<AccountResource>(&account_1).unwrap();
+    let resource_key_2 = StateKey::resource_typed::<AccountResource>(&account_2).unwrap();
+    let resource_key_3 = StateKey::resource_typed::<AccountResource>(&account_3).unwrap();
+    let table_item_key = StateKey::raw(&[1, 2, 3]);
+
+    // Verify that an empty filter matches nothing
+    let empty_filter = StateValuesFilter::default();
+    assert!(empty_filter.is_empty());
+    for state_key in [&resource_key_1, &resource_key_2, &table_item_key] {
+        assert!(!empty_filter.matches(state_key));
+    }
+
+    // Verify that account filters only match the given accounts
+    let account_filter = StateValuesFilter::new(vec![account_1], vec![]);
+    assert!(account_filter.matches(&resource_key_1));
+    assert!(!account_filter.matches(&resource_key_2));
+    assert!(!account_filter.matches(&table_item_key));
+
+    // Verify that prefix filters only match the given prefixes
+    let account_2_prefix = StateKeyPrefix::from(account_2).encode().unwrap();
+    let prefix_filter = StateValuesFilter::new(vec![account_1], vec![account_2_prefix]);
+    assert!(prefix_filter.matches(&resource_key_1));
+    assert!(prefix_filter.matches(&resource_key_2));
+    assert!(!prefix_filter.matches(&resource_key_3));
+    assert!(!prefix_filter.matches(&table_item_key));
+}
+
+#[test]
+fn test_state_values_filter_matches_all_with_prefix() {
+    // Create the account prefixes for several accounts
+    let account_1 = AccountAddress::random();
+    let account_2 = AccountAddress::random();
+    let account_3 = AccountAddress::random();
+    let account_1_prefix = StateKeyPrefix::from(account_1).encode().unwrap();
+    let account_2_prefix = StateKeyPrefix::from(account_2).encode().unwrap();
+    let account_3_prefix = StateKeyPrefix::from(account_3).encode().unwrap();
+
+    // Verify that an empty filter matches no prefixes
+    let empty_filter = StateValuesFilter::default();
+    assert!(!empty_filter.matches_all_with_prefix(&account_1_prefix));
+
+    // Verify that account and prefix filters only match the given prefixes
+    let filter = StateValuesFilter::new(vec![account_1], vec![account_2_prefix.clone()]);
+    assert!(filter.matches_all_with_prefix(&account_1_prefix));
+    assert!(filter.matches_all_with_prefix(&account_2_prefix));
+    assert!(!filter.matches_all_with_prefix(&account_3_prefix));
+
+    // Verify that longer prefixes match, but shorter prefixes do not
+    let mut longer_prefix = account_1_prefix.clone();
+    longer_prefix.extend([1, 2, 3]);
+    assert!(filter.matches_all_with_prefix(&longer_prefix));
+    assert!(!filter.matches_all_with_prefix(&account_1_prefix[..1]));
+}
+
+#[test]
+fn test_state_values_by_filter_verify() {
+    // Create a state tree that holds a single state value
+    let account = AccountAddress::random();
+    let state_key = StateKey::resource_typed::<AccountResource>(&account).unwrap();
+    let state_value = StateValue::new_legacy(vec![1, 2, 3].into());
+    let leaf_node = SparseMerkleLeafNode::new(state_key.hash(), state_value.hash());
+    let root_hash = leaf_node.hash();
+
+    // Create a filtered state values response
+    let proof = SparseMerkleProof::new(Some(leaf_node), vec![]);
+    let state_values_by_filter = StateValuesByFilterWithProof {
+        version: 10,
+        first_index: 0,
+        last_index: 0,
+        root_hash,
+        state_values: vec![(state_key, state_value, proof)],
+    };
+
+    // Verify the response against the correct root hash and filter
+    let filter = StateValuesFilter::new(vec![account], vec![]);
+    assert_ok!(state_values_by_filter.verify(root_hash, &filter));
+
+    // Verify the response fails against an incorrect root hash
+    assert_err!(state_values_by_filter.verify(HashValue::random(), &filter));
+
+    // Verify the response fails if the state keys don't match the filter
+    let other_filter = StateValuesFilter::new(vec![AccountAddress::random()], vec![]);
+    assert_err!(state_values_by_filter.verify(root_hash, &other_filter));
+
+    // Verify the response fails if the proof is invalid
+    let mut invalid_state_values_by_filter = state_values_by_filter.clone();
+    invalid_state_values_by_filter.state_values[0].2 =
+        SparseMerkleProof::new(Some(leaf_node), vec![HashValue::random()]);
+    assert_err!(invalid_state_values_by_filter.verify(root_hash, &filter));
+}
+
 #[test]
 fn test_protocol_metadata_service() {
     // Create the protocol metadata
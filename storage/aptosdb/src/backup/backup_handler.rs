@@ -11,6 +11,7 @@ use crate::{
     state_store::StateStore,
 };
 use aptos_crypto::hash::HashValue;
+use aptos_jellyfish_merkle::delta_iterator::{DeltaItem, JellyfishMerkleDeltaIterator};
 use aptos_storage_interface::{db_ensure as ensure, AptosDbError, Result};
 use aptos_types::{
     contract_event::ContractEvent,
@@ -144,6 +145,50 @@ impl BackupHandler {
         Ok(Box::new(iterator))
     }
 
+    /// Iterate through the items of a state snapshot which changed since the state snapshot at
+    /// `base_version`, in the same index range as `get_state_item_iter()`. Only the state tree
+    /// nodes created after `base_version` are visited, and the items under the other nodes are
+    /// returned as ranges of items identical to those in the snapshot at `base_version`.
+    pub fn get_state_snapshot_delta_iter(
+        &self,
+        base_version: Version,
+        version: Version,
+        start_idx: usize,
+        limit: usize,
+    ) -> Result<impl Iterator<Item = Result<StateSnapshotDeltaRecord>> + Send> {
+        let state_store = Arc::clone(&self.state_store);
+        let mut leaf_idx = start_idx;
+        let iterator = JellyfishMerkleDeltaIterator::new(
+            Arc::clone(&self.state_store.state_merkle_db),
+            base_version,
+            version,
+            start_idx,
+            limit,
+        )?
+        .map(move |res| {
+            let item = res?;
+            leaf_idx += item.leaf_count();
+            BACKUP_STATE_SNAPSHOT_VERSION.set(version as i64);
+            BACKUP_STATE_SNAPSHOT_LEAF_IDX.set((leaf_idx - 1) as i64);
+            Ok(match item {
+                DeltaItem::Changed(_key_hash, (key, value_version)) => {
+                    let value = state_store.expect_value_by_version(&key, value_version)?;
+                    StateSnapshotDeltaRecord::Changed(key, value)
+                },
+                DeltaItem::Unchanged {
+                    first_key,
+                    last_key,
+                    leaf_count,
+                } => StateSnapshotDeltaRecord::Unchanged {
+                    first_key,
+                    last_key,
+                    leaf_count,
+                },
+            })
+        });
+        Ok(Box::new(iterator))
+    }
+
     /// Gets the proof that proves a range of accounts.
     pub fn get_account_state_range_proof(
         &self,
@@ -204,6 +249,29 @@ impl BackupHandler {
     }
 }
 
+/// A record of an incremental state snapshot, see `BackupHandler::get_state_snapshot_delta_iter()`.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum StateSnapshotDeltaRecord {
+    /// A state item whose leaf in the state tree was created after the base version.
+    Changed(StateKey, StateValue),
+    /// `leaf_count` consecutive state items, from key hash `first_key` to `last_key` (inclusive),
+    /// which are the same as in the snapshot at the base version.
+    Unchanged {
+        first_key: HashValue,
+        last_key: HashValue,
+        leaf_count: usize,
+    },
+}
+
+impl StateSnapshotDeltaRecord {
+    pub fn leaf_count(&self) -> usize {
+        match self {
+            Self::Changed(..) => 1,
+            Self::Unchanged { leaf_count, .. } => *leaf_count,
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct DbState {
     pub epoch: u64,
@@ -262,7 +262,7 @@ impl DbReader for StateStore {
 }
 
 impl StateDb {
-    fn expect_value_by_version(
+    pub(crate) fn expect_value_by_version(
         &self,
         state_key: &StateKey,
         version: Version,
@@ -14,6 +14,7 @@ use crate::{
 };
 use anyhow::{anyhow, ensure, Result};
 use aptos_crypto::{hash::CryptoHash, HashValue};
+use aptos_db::backup::backup_handler::StateSnapshotDeltaRecord;
 use aptos_logger::prelude::*;
 use aptos_metrics_core::TimerHelper;
 use aptos_types::{
@@ -27,7 +28,10 @@ use clap::Parser;
 use futures::{StreamExt, TryStream, TryStreamExt};
 use once_cell::sync::Lazy;
 use std::{convert::TryInto, str::FromStr, sync::Arc, time::Instant};
-use tokio::{io::AsyncWriteExt, sync::mpsc::Sender};
+use tokio::{
+    io::{AsyncRead, AsyncWriteExt},
+    sync::mpsc::Sender,
+};
 use tokio_stream::wrappers::ReceiverStream;
 
 #[derive(Parser)]
@@ -37,6 +41,13 @@ pub struct StateSnapshotBackupOpt {
         help = "Epoch at the end of which a state snapshot is to be taken."
     )]
     pub epoch: u64,
+    #[clap(
+        long = "base-state-manifest",
+        help = "Manifest of an older state snapshot backup. If set, the snapshot is taken \
+        incrementally, only storing the state items whose state tree nodes changed since that \
+        snapshot."
+    )]
+    pub base_manifest: Option<FileHandle>,
 }
 
 struct Chunk {
@@ -47,27 +58,80 @@ struct Chunk {
     last_idx: usize,
 }
 
+/// Max number of state items requested from the backup service at a time, which also caps the
+/// number of state items covered by a chunk of an incremental snapshot, so that reassembling it
+/// on restore doesn't read too many records from the base snapshot.
+const MAX_ITEMS_PER_REQUEST: usize = if cfg!(test) { 2 } else { 100_000 };
+
+/// Kinds of records in the chunks of a state snapshot.
+#[derive(Clone, Copy)]
+enum RecordKind {
+    /// `(StateKey, StateValue)`, in a full snapshot.
+    Full,
+    /// `StateSnapshotDeltaRecord`, in an incremental snapshot.
+    Delta,
+}
+
+impl RecordKind {
+    /// Returns the number of state items a record covers.
+    fn num_items(self, record: &[u8]) -> Result<usize> {
+        Ok(match self {
+            Self::Full => 1,
+            Self::Delta => {
+                let leaf_count = bcs::from_bytes::<StateSnapshotDeltaRecord>(record)?.leaf_count();
+                ensure!(leaf_count > 0, "Empty range of unchanged state items.");
+                leaf_count
+            },
+        })
+    }
+
+    /// Returns the keys of the first and the last state items a record covers.
+    fn parse_keys(self, record: &[u8]) -> Result<(HashValue, HashValue)> {
+        let key_hash = match self {
+            Self::Full => bcs::from_bytes::<(StateKey, StateValue)>(record)?.0.hash(),
+            Self::Delta => match bcs::from_bytes(record)? {
+                StateSnapshotDeltaRecord::Changed(key, _) => key.hash(),
+                StateSnapshotDeltaRecord::Unchanged {
+                    first_key,
+                    last_key,
+                    ..
+                } => return Ok((first_key, last_key)),
+            },
+        };
+        Ok((key_hash, key_hash))
+    }
+}
+
 struct ChunkerState<RecordStream> {
     record_stream: Option<RecordStream>,
     buf: BytesMut,
     chunk_first_key: HashValue,
     prev_record_len: usize,
+    /// Index of the last state item covered by the records in `buf`.
     current_idx: usize,
     chunk_first_idx: usize,
     max_chunk_size: usize,
+    max_chunk_items: usize,
+    record_kind: RecordKind,
 }
 
 impl<RecordStream> ChunkerState<RecordStream>
 where
     RecordStream: TryStream<Ok = Bytes, Error = anyhow::Error> + Unpin,
 {
-    async fn new(mut record_stream: RecordStream, max_chunk_size: usize) -> Result<Self> {
+    async fn new(
+        mut record_stream: RecordStream,
+        max_chunk_size: usize,
+        max_chunk_items: usize,
+        record_kind: RecordKind,
+    ) -> Result<Self> {
         let first_record = record_stream
             .try_next()
             .await?
             .ok_or_else(|| anyhow!("State is empty."))?;
 
-        let chunk_first_key = Self::parse_key(&first_record)?;
+        let (chunk_first_key, _) = record_kind.parse_keys(&first_record)?;
+        let num_items = record_kind.num_items(&first_record)?;
         let prev_record_len = first_record.len();
 
         let mut buf = BytesMut::new();
@@ -79,9 +143,11 @@ where
             buf,
             chunk_first_key,
             prev_record_len,
-            current_idx: 0,
+            current_idx: num_items - 1,
             chunk_first_idx: 0,
             max_chunk_size,
+            max_chunk_items,
+            record_kind,
         })
     }
 
@@ -96,11 +162,16 @@ where
         while let Some(record_bytes) = input.try_next().await? {
             let _timer = BACKUP_TIMER.timer_with(&["state_snapshot_process_records"]);
 
-            // If buf + current_record exceeds max_chunk_size, dump current buf to a new chunk
-            let chunk_cut_opt = should_cut_chunk(&self.buf, &record_bytes, self.max_chunk_size)
+            let num_items = self.record_kind.num_items(&record_bytes)?;
+
+            // If buf + current_record exceeds max_chunk_size or max_chunk_items, dump current buf
+            // to a new chunk
+            let chunk_items = self.current_idx + 1 - self.chunk_first_idx;
+            let chunk_cut_opt = (should_cut_chunk(&self.buf, &record_bytes, self.max_chunk_size)
+                || chunk_items + num_items > self.max_chunk_items)
                 .then(|| {
                     let bytes = self.buf.split().freeze();
-                    let last_key = Self::parse_key(&bytes[bytes.len() - self.prev_record_len..])?;
+                    let last_key = self.parse_last_key(&bytes)?;
 
                     let chunk = Chunk {
                         bytes,
@@ -111,7 +182,7 @@ where
                     };
 
                     self.chunk_first_idx = self.current_idx + 1;
-                    self.chunk_first_key = Self::parse_key(&record_bytes)?;
+                    self.chunk_first_key = self.record_kind.parse_keys(&record_bytes)?.0;
 
                     Result::<_>::Ok(chunk)
                 })
@@ -122,7 +193,7 @@ where
             self.buf
                 .put_slice(&(record_bytes.len() as u32).to_be_bytes());
             self.buf.extend(record_bytes);
-            self.current_idx += 1;
+            self.current_idx += num_items;
 
             // Return the full chunk if found
             if let Some(chunk) = chunk_cut_opt {
@@ -145,6 +216,8 @@ where
             current_idx,
             chunk_first_idx,
             max_chunk_size: _,
+            max_chunk_items: _,
+            record_kind,
         } = self;
         ensure!(
             state_snapshot_file.is_none(),
@@ -153,7 +226,7 @@ where
         ensure!(!buf.is_empty(), "Last chunk can't be empty");
 
         let bytes = buf.freeze();
-        let last_key = Self::parse_key(&bytes[bytes.len() - prev_record_len..])?;
+        let (_, last_key) = record_kind.parse_keys(&bytes[bytes.len() - prev_record_len..])?;
 
         Ok(Chunk {
             bytes,
@@ -164,9 +237,11 @@ where
         })
     }
 
-    fn parse_key(record: &[u8]) -> Result<HashValue> {
-        let (key, _): (StateKey, StateValue) = bcs::from_bytes(record)?;
-        Ok(key.hash())
+    fn parse_last_key(&self, bytes: &[u8]) -> Result<HashValue> {
+        let (_, last_key) = self
+            .record_kind
+            .parse_keys(&bytes[bytes.len() - self.prev_record_len..])?;
+        Ok(last_key)
     }
 }
 
@@ -178,9 +253,17 @@ impl<RecordStream> Chunker<RecordStream>
 where
     RecordStream: TryStream<Ok = Bytes, Error = anyhow::Error> + Unpin,
 {
-    async fn new(record_stream: RecordStream, max_chunk_size: usize) -> Result<Self> {
+    async fn new(
+        record_stream: RecordStream,
+        max_chunk_size: usize,
+        max_chunk_items: usize,
+        record_kind: RecordKind,
+    ) -> Result<Self> {
         Ok(Self {
-            state: Some(ChunkerState::new(record_stream, max_chunk_size).await?),
+            state: Some(
+                ChunkerState::new(record_stream, max_chunk_size, max_chunk_items, record_kind)
+                    .await?,
+            ),
         })
     }
 
@@ -200,6 +283,9 @@ where
 pub struct StateSnapshotBackupController {
     epoch: u64,
     version: Option<Version>, // initialize before using
+    base_manifest: Option<FileHandle>,
+    /// Version of the base snapshot, and the number of incremental snapshots in a row up to it.
+    base: Option<(Version, usize)>, // initialize before using
     max_chunk_size: usize,
     client: Arc<BackupServiceClient>,
     storage: Arc<dyn BackupStorage>,
@@ -216,6 +302,8 @@ impl StateSnapshotBackupController {
         Self {
             epoch: opt.epoch,
             version: None,
+            base_manifest: opt.base_manifest,
+            base: None,
             max_chunk_size: global_opt.max_chunk_size,
             client,
             storage,
@@ -240,8 +328,42 @@ impl StateSnapshotBackupController {
             .create_backup_with_random_suffix(&self.backup_name())
             .await?;
 
+        if let Some(base_manifest) = &self.base_manifest {
+            let base: StateSnapshotBackup = self.storage.load_json_file(base_manifest).await?;
+            ensure!(
+                base.version <= self.version(),
+                "Base snapshot at version {} is newer than version {}.",
+                base.version,
+                self.version(),
+            );
+            let num_incremental = self.num_incremental(base.base.clone()).await? + 1;
+            info!(
+                base_manifest = base_manifest,
+                base_version = base.version,
+                num_incremental = num_incremental,
+                "Taking incremental state snapshot."
+            );
+            self.base = Some((base.version, num_incremental));
+        }
+
         let record_stream = Box::pin(self.record_stream(self.concurrent_data_requests).await?);
-        let chunker = Chunker::new(record_stream, self.max_chunk_size).await?;
+        // Chunks of an incremental snapshot are also capped by the number of state items, because
+        // a range of unchanged state items takes little space in the chunk.
+        let chunker = match self.base {
+            Some(_) => Chunker::new(
+                record_stream,
+                self.max_chunk_size,
+                MAX_ITEMS_PER_REQUEST,
+                RecordKind::Delta,
+            ),
+            None => Chunker::new(
+                record_stream,
+                self.max_chunk_size,
+                usize::MAX,
+                RecordKind::Full,
+            ),
+        }
+        .await?;
 
         let start = Instant::now();
         let chunk_stream = futures::stream::try_unfold(chunker, |mut chunker| async {
@@ -269,14 +391,27 @@ impl StateSnapshotBackupController {
         self.write_manifest(&backup_handle, chunks).await
     }
 
+    /// Returns the number of incremental snapshots in a row up to the snapshot whose base is
+    /// `base_manifest`.
+    async fn num_incremental(&self, mut base_manifest: Option<FileHandle>) -> Result<usize> {
+        let mut num_incremental = 0;
+        while let Some(manifest) = base_manifest {
+            let manifest: StateSnapshotBackup = self.storage.load_json_file(&manifest).await?;
+            base_manifest = manifest.base;
+            num_incremental += 1;
+        }
+        Ok(num_incremental)
+    }
+
     async fn record_stream(
         &self,
         concurrency: usize,
     ) -> Result<impl TryStream<Ok = Bytes, Error = anyhow::Error, Item = Result<Bytes>>> {
-        const CHUNK_SIZE: usize = if cfg!(test) { 2 } else { 100_000 };
+        const CHUNK_SIZE: usize = MAX_ITEMS_PER_REQUEST;
 
         let count = self.client.get_state_item_count(self.version()).await?;
         let version = self.version();
+        let base_version = self.base.map(|(base_version, _)| base_version);
         let client = self.client.clone();
 
         let chunks_stream = futures::stream::unfold(0, move |start_idx| async move {
@@ -298,6 +433,7 @@ impl StateSnapshotBackupController {
                 // spawn and forget, propagate error through channel
                 let _join_handle = tokio::spawn(send_records(
                     client.clone(),
+                    base_version,
                     version,
                     start_idx,
                     chunk_size,
@@ -316,34 +452,62 @@ impl StateSnapshotBackupController {
 
 async fn send_records(
     client: Arc<BackupServiceClient>,
+    base_version: Option<Version>,
     version: Version,
     start_idx: usize,
     chunk_size: usize,
     sender: Sender<Result<Bytes>>,
 ) {
-    if let Err(err) = send_records_inner(client, version, start_idx, chunk_size, &sender).await {
+    if let Err(err) = send_records_inner(
+        client,
+        base_version,
+        version,
+        start_idx,
+        chunk_size,
+        &sender,
+    )
+    .await
+    {
         let _ = sender.send(Err(err)).await;
     }
 }
 
+/// Sends the records of `chunk_size` state items, or of the changes to them since the snapshot
+/// at `base_version` if it's set.
 async fn send_records_inner(
     client: Arc<BackupServiceClient>,
+    base_version: Option<Version>,
     version: Version,
     start_idx: usize,
     chunk_size: usize,
     sender: &Sender<Result<Bytes>>,
 ) -> Result<()> {
     let _timer = BACKUP_TIMER.timer_with(&["state_snapshot_record_stream_all"]);
-    let mut input = client
-        .get_state_snapshot_chunk(version, start_idx, chunk_size)
-        .await?;
+    let (mut input, record_kind): (Box<dyn AsyncRead + Send + Unpin>, _) = match base_version {
+        Some(base_version) => (
+            Box::new(
+                client
+                    .get_state_snapshot_delta_chunk(base_version, version, start_idx, chunk_size)
+                    .await?,
+            ),
+            RecordKind::Delta,
+        ),
+        None => (
+            Box::new(
+                client
+                    .get_state_snapshot_chunk(version, start_idx, chunk_size)
+                    .await?,
+            ),
+            RecordKind::Full,
+        ),
+    };
     let mut count = 0;
     while let Some(record_bytes) = {
         let _timer = BACKUP_TIMER.timer_with(&["state_snapshot_read_record_bytes"]);
         input.read_record_bytes().await?
     } {
         let _timer = BACKUP_TIMER.timer_with(&["state_snapshot_record_stream_send_bytes"]);
-        count += 1;
+        count += record_kind.num_items(&record_bytes)?;
         sender.send(Ok(record_bytes)).await?;
     }
     ensure!(
@@ -468,6 +632,7 @@ impl StateSnapshotBackupController {
             root_hash: txn_info.transaction_info().ensure_state_checkpoint_hash()?,
             chunks,
             proof: proof_handle,
+            base: self.base_manifest.clone(),
         };
 
         let (manifest_handle, mut manifest_file) = self
@@ -483,6 +648,7 @@ impl StateSnapshotBackupController {
             self.epoch,
             self.version(),
             manifest_handle.clone(),
+            self.base.map_or(0, |(_, num_incremental)| num_incremental),
         );
         self.storage
             .save_metadata_line(&metadata.name(), &metadata.to_text_line()?)
@@ -0,0 +1,204 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    backup_types::state_snapshot::manifest::{StateSnapshotBackup, StateSnapshotChunk},
+    storage::{BackupStorage, FileHandle, FileHandleRef},
+    utils::{read_record_bytes::ReadRecordBytes, storage_ext::BackupStorageExt},
+};
+use anyhow::{ensure, Result};
+use aptos_crypto::{hash::CryptoHash, HashValue};
+use aptos_db::backup::backup_handler::StateSnapshotDeltaRecord;
+use aptos_types::{
+    state_store::{state_key::StateKey, state_value::StateValue},
+    transaction::Version,
+};
+use futures::{future::BoxFuture, FutureExt};
+use serde::de::DeserializeOwned;
+use std::{collections::VecDeque, sync::Arc};
+
+/// Reads the records of a state snapshot chunk by chunk, in key hash order. Chunks of an
+/// incremental snapshot are reassembled by taking the records unchanged since the base snapshot
+/// from the base snapshot, recursively down to a full snapshot.
+///
+/// Nothing is verified against the base snapshots themselves: the reassembled chunks are
+/// verified against the range proofs of the top snapshot when restored.
+pub struct StateSnapshotRecordReader {
+    storage: Arc<dyn BackupStorage>,
+    version: Version,
+    chunks: VecDeque<StateSnapshotChunk>,
+    base: Option<Box<StateSnapshotRecordReader>>,
+    /// Records of the current chunk not yet returned by `read_until()`.
+    buffered: VecDeque<(StateKey, StateValue)>,
+}
+
+impl StateSnapshotRecordReader {
+    pub fn open(
+        storage: Arc<dyn BackupStorage>,
+        manifest_handle: FileHandle,
+    ) -> BoxFuture<'static, Result<Self>> {
+        async move {
+            let manifest: StateSnapshotBackup = storage.load_json_file(&manifest_handle).await?;
+            Self::from_manifest(storage, manifest).await
+        }
+        .boxed()
+    }
+
+    pub async fn from_manifest(
+        storage: Arc<dyn BackupStorage>,
+        manifest: StateSnapshotBackup,
+    ) -> Result<Self> {
+        let base = match manifest.base {
+            Some(base_manifest_handle) => {
+                let base = Self::open(storage.clone(), base_manifest_handle).await?;
+                ensure!(
+                    base.version <= manifest.version,
+                    "Base snapshot at version {} is newer than the snapshot at version {}.",
+                    base.version,
+                    manifest.version,
+                );
+                Some(Box::new(base))
+            },
+            None => None,
+        };
+
+        Ok(Self {
+            storage,
+            version: manifest.version,
+            chunks: manifest.chunks.into(),
+            base,
+            buffered: VecDeque::new(),
+        })
+    }
+
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    /// Returns the full records of the next chunk in the manifest.
+    pub fn next_chunk(&mut self) -> BoxFuture<'_, Result<Option<Vec<(StateKey, StateValue)>>>> {
+        async move {
+            let chunk = match self.chunks.pop_front() {
+                Some(chunk) => chunk,
+                None => return Ok(None),
+            };
+            let records = match self.base.as_mut() {
+                None => read_records(&self.storage, &chunk.blobs).await?,
+                Some(base) => {
+                    let delta = read_records(&self.storage, &chunk.blobs).await?;
+                    reassemble(base, delta).await?
+                },
+            };
+            ensure!(
+                records.len() == chunk.last_idx + 1 - chunk.first_idx,
+                "Expecting {} records in chunk {}, got {}.",
+                chunk.last_idx + 1 - chunk.first_idx,
+                chunk.blobs,
+                records.len(),
+            );
+            Ok(Some(records))
+        }
+        .boxed()
+    }
+
+    /// Returns the records up to `last_key` (inclusive) which haven't been returned yet.
+    pub fn read_until(
+        &mut self,
+        last_key: HashValue,
+    ) -> BoxFuture<'_, Result<Vec<(StateKey, StateValue)>>> {
+        async move {
+            let mut records = Vec::new();
+            loop {
+                while let Some((key, _)) = self.buffered.front() {
+                    if key.hash() > last_key {
+                        return Ok(records);
+                    }
+                    records.extend(self.buffered.pop_front());
+                }
+                match self.next_chunk().await? {
+                    Some(chunk) => self.buffered = chunk.into(),
+                    None => return Ok(records),
+                }
+            }
+        }
+        .boxed()
+    }
+
+    /// Skips the chunks ending at or before `key`, for resuming a restore. Base snapshots are
+    /// positioned so that the next chunk can be reassembled without reading the skipped ones.
+    pub fn skip_until(&mut self, key: HashValue) -> BoxFuture<'_, Result<()>> {
+        self.skip_until_impl(key, false)
+    }
+
+    fn skip_until_impl(&mut self, key: HashValue, skip_records: bool) -> BoxFuture<'_, Result<()>> {
+        async move {
+            let mut last_skipped_key = None;
+            while let Some(chunk) = self.chunks.front() {
+                if chunk.last_key > key {
+                    break;
+                }
+                last_skipped_key = Some(chunk.last_key);
+                self.chunks.pop_front();
+            }
+            if let (Some(base), Some(last_skipped_key)) = (self.base.as_mut(), last_skipped_key) {
+                base.skip_until_impl(last_skipped_key, true).await?;
+            }
+            if skip_records {
+                // The next chunk might straddle `key`, drop the part before it.
+                self.read_until(key).await?;
+            }
+            Ok(())
+        }
+        .boxed()
+    }
+}
+
+async fn read_records<T: DeserializeOwned>(
+    storage: &Arc<dyn BackupStorage>,
+    file_handle: &FileHandleRef,
+) -> Result<Vec<T>> {
+    let mut file = storage.open_for_read(file_handle).await?;
+    let mut records = Vec::new();
+    while let Some(record_bytes) = file.read_record_bytes().await? {
+        records.push(bcs::from_bytes(&record_bytes)?);
+    }
+    Ok(records)
+}
+
+/// Reassembles the records of an incremental snapshot chunk, by taking the unchanged ranges of
+/// records from the base snapshot.
+async fn reassemble(
+    base: &mut StateSnapshotRecordReader,
+    delta: Vec<StateSnapshotDeltaRecord>,
+) -> Result<Vec<(StateKey, StateValue)>> {
+    let mut records = Vec::with_capacity(delta.iter().map(|r| r.leaf_count()).sum());
+    for record in delta {
+        match record {
+            StateSnapshotDeltaRecord::Changed(key, value) => records.push((key, value)),
+            StateSnapshotDeltaRecord::Unchanged {
+                first_key,
+                last_key,
+                leaf_count,
+            } => {
+                // Records of the base snapshot before `first_key` have been changed or deleted.
+                let base_records: Vec<_> = base
+                    .read_until(last_key)
+                    .await?
+                    .into_iter()
+                    .filter(|(key, _)| key.hash() >= first_key)
+                    .collect();
+                ensure!(
+                    base_records.len() == leaf_count,
+                    "Expecting {} records from {} to {} in base snapshot at version {}, got {}.",
+                    leaf_count,
+                    first_key,
+                    last_key,
+                    base.version(),
+                    base_records.len(),
+                );
+                records.extend(base_records);
+            },
+        }
+    }
+    Ok(records)
+}
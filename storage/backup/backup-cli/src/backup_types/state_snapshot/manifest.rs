@@ -9,7 +9,7 @@ use serde::{Deserialize, Serialize};
 
 /// A chunk of a state snapshot manifest, representing accounts in the key range
 /// [`first_key`, `last_key`] (right side inclusive).
-#[derive(Deserialize, Serialize)]
+#[derive(Clone, Deserialize, Serialize)]
 pub struct StateSnapshotChunk {
     /// index of the first account in this chunk over all accounts.
     pub first_idx: usize,
@@ -20,7 +20,8 @@ pub struct StateSnapshotChunk {
     /// key of the last account in this chunk.
     pub last_key: HashValue,
     /// Repeated `len(record) + record` where `record` is BCS serialized tuple
-    /// `(key, state_value)`
+    /// `(key, state_value)`, or BCS serialized `StateSnapshotDeltaRecord` if the snapshot is
+    /// incremental, covering the same accounts.
     pub blobs: FileHandle,
     /// BCS serialized `SparseMerkleRangeProof` that proves this chunk adds up to the root hash
     /// indicated in the backup (`StateSnapshotBackup::root_hash`).
@@ -28,7 +29,7 @@ pub struct StateSnapshotChunk {
 }
 
 /// State snapshot backup manifest, representing a complete state view at specified version.
-#[derive(Deserialize, Serialize)]
+#[derive(Clone, Deserialize, Serialize)]
 pub struct StateSnapshotBackup {
     /// Version at which this state snapshot is taken.
     pub version: Version,
@@ -49,4 +50,11 @@ pub struct StateSnapshotBackup {
     /// `EpochStateBackup` recovered prior to this to the DB; Requiring it to be in the same epoch
     /// limits the requirement on such `EpochStateBackup` to no older than the same epoch.
     pub proof: FileHandle,
+    /// Manifest of an older snapshot this one is taken on top of. If set, the chunks only hold
+    /// the state items whose state tree leaves were created since the base snapshot, and refer to
+    /// the base snapshot for the items under the tree nodes unchanged since then. The full chunks
+    /// are reassembled from the chain of base snapshots on restore, and verified against the
+    /// proofs of this snapshot.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base: Option<FileHandle>,
 }
@@ -4,7 +4,11 @@
 
 use crate::{
     backup_types::{
-        epoch_ending::restore::EpochHistory, state_snapshot::manifest::StateSnapshotBackup,
+        epoch_ending::restore::EpochHistory,
+        state_snapshot::{
+            incremental::StateSnapshotRecordReader,
+            manifest::{StateSnapshotBackup, StateSnapshotChunk},
+        },
     },
     metrics::{
         restore::{
@@ -31,7 +35,7 @@ use aptos_types::{
     access_path::Path,
     ledger_info::LedgerInfoWithSignatures,
     on_chain_config::Features,
-    proof::TransactionInfoWithProof,
+    proof::{SparseMerkleRangeProof, TransactionInfoWithProof},
     state_store::{
         state_key::{inner::StateKeyInner, StateKey},
         state_value::StateValue,
@@ -40,7 +44,7 @@ use aptos_types::{
     vm::configs::aptos_prod_verifier_config,
 };
 use clap::Parser;
-use futures::{stream, TryStreamExt};
+use futures::{stream, stream::BoxStream, StreamExt, TryStreamExt};
 use move_binary_format::CompiledModule;
 use move_bytecode_verifier::verify_module_with_config;
 use std::sync::Arc;
@@ -58,6 +62,16 @@ pub struct StateSnapshotRestoreOpt {
     pub restore_mode: StateSnapshotRestoreMode,
 }
 
+type ChunkStream = BoxStream<
+    'static,
+    Result<(
+        usize,
+        StateSnapshotChunk,
+        Vec<(StateKey, StateValue)>,
+        SparseMerkleRangeProof,
+    )>,
+>;
+
 pub struct StateSnapshotRestoreController {
     storage: Arc<dyn BackupStorage>,
     run_mode: Arc<RestoreRunMode>,
@@ -163,6 +177,24 @@ impl StateSnapshotRestoreController {
         let total_chunks = manifest.chunks.len();
 
         let resume_point_opt = receiver.lock().as_mut().unwrap().previous_key_hash()?;
+        let incremental_reader = match &manifest.base {
+            Some(base_manifest) => {
+                info!(
+                    base_manifest = base_manifest,
+                    "Reassembling incremental state snapshot."
+                );
+                let mut reader = StateSnapshotRecordReader::from_manifest(
+                    self.storage.clone(),
+                    manifest.clone(),
+                )
+                .await?;
+                if let Some(resume_point) = resume_point_opt {
+                    reader.skip_until(resume_point).await?;
+                }
+                Some(reader)
+            },
+            None => None,
+        };
         let chunks = if let Some(resume_point) = resume_point_opt {
             manifest
                 .chunks
@@ -183,20 +215,10 @@ impl StateSnapshotRestoreController {
 
         let start_idx = chunks.first().map_or(0, |chunk| chunk.first_idx);
 
-        let storage = self.storage.clone();
-        let futs_iter = chunks.into_iter().enumerate().map(|(chunk_idx, chunk)| {
-            let storage = storage.clone();
-            async move {
-                tokio::spawn(async move {
-                    let blobs = Self::read_state_value(&storage, chunk.blobs.clone()).await?;
-                    let proof = storage.load_bcs_file(&chunk.proof).await?;
-                    Result::<_>::Ok((chunk_idx, chunk, blobs, proof))
-                })
-                .await?
-            }
-        });
-        let con = self.concurrent_downloads;
-        let mut futs_stream = stream::iter(futs_iter).buffered_x(con * 2, con);
+        let mut futs_stream = match incremental_reader {
+            Some(reader) => self.incremental_chunk_stream(chunks, reader),
+            None => self.full_chunk_stream(chunks),
+        };
         let mut start = None;
         while let Some((chunk_idx, chunk, mut blobs, proof)) = futs_stream.try_next().await? {
             start = start.or_else(|| Some(Instant::now()));
@@ -232,6 +254,55 @@ impl StateSnapshotRestoreController {
         Ok(())
     }
 
+    /// Chunks are downloaded concurrently.
+    fn full_chunk_stream(&self, chunks: Vec<StateSnapshotChunk>) -> ChunkStream {
+        let storage = self.storage.clone();
+        let futs_iter = chunks
+            .into_iter()
+            .enumerate()
+            .map(move |(chunk_idx, chunk)| {
+                let storage = storage.clone();
+                async move {
+                    tokio::spawn(async move {
+                        let blobs = Self::read_state_value(&storage, chunk.blobs.clone()).await?;
+                        let proof = storage.load_bcs_file(&chunk.proof).await?;
+                        Result::<_>::Ok((chunk_idx, chunk, blobs, proof))
+                    })
+                    .await?
+                }
+            });
+        let con = self.concurrent_downloads;
+        stream::iter(futs_iter).buffered_x(con * 2, con).boxed()
+    }
+
+    /// Chunks are reassembled one by one, since the base snapshots are read sequentially.
+    fn incremental_chunk_stream(
+        &self,
+        chunks: Vec<StateSnapshotChunk>,
+        reader: StateSnapshotRecordReader,
+    ) -> ChunkStream {
+        let storage = self.storage.clone();
+        stream::try_unfold(
+            (chunks.into_iter().enumerate(), reader),
+            move |(mut chunks, mut reader)| {
+                let storage = storage.clone();
+                async move {
+                    let (chunk_idx, chunk) = match chunks.next() {
+                        Some(next) => next,
+                        None => return Ok(None),
+                    };
+                    let blobs = reader
+                        .next_chunk()
+                        .await?
+                        .ok_or_else(|| anyhow!("Missing chunk {}.", chunk_idx))?;
+                    let proof = storage.load_bcs_file(&chunk.proof).await?;
+                    Ok(Some(((chunk_idx, chunk, blobs, proof), (chunks, reader))))
+                }
+            },
+        )
+        .boxed()
+    }
+
     fn validate_modules(blob: &[(StateKey, StateValue)]) {
         // TODO: Instead of using default features, fetch them from the the state.
         let features = Features::default();
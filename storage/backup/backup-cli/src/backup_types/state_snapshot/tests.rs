@@ -5,9 +5,11 @@
 use crate::{
     backup_types::state_snapshot::{
         backup::{StateSnapshotBackupController, StateSnapshotBackupOpt},
+        incremental::StateSnapshotRecordReader,
+        manifest::{StateSnapshotBackup, StateSnapshotChunk},
         restore::{StateSnapshotRestoreController, StateSnapshotRestoreOpt},
     },
-    storage::{local_fs::LocalFs, BackupStorage},
+    storage::{local_fs::LocalFs, BackupHandleRef, BackupStorage, ShellSafeName},
     utils::{
         backup_service_client::BackupServiceClient,
         test_utils::{start_local_backup_service, tmp_db_with_random_content},
@@ -15,11 +17,17 @@ use crate::{
         RocksdbOpt, TrustedWaypointOpt,
     },
 };
-use aptos_db::{state_restore::StateSnapshotRestoreMode, AptosDB};
+use aptos_crypto::{hash::CryptoHash, HashValue};
+use aptos_db::{
+    backup::backup_handler::StateSnapshotDeltaRecord, state_restore::StateSnapshotRestoreMode,
+    AptosDB,
+};
 use aptos_storage_interface::DbReader;
 use aptos_temppath::TempPath;
-use std::{convert::TryInto, sync::Arc};
-use tokio::time::Duration;
+use aptos_types::state_store::{state_key::StateKey, state_value::StateValue};
+use serde::Serialize;
+use std::{convert::TryInto, str::FromStr, sync::Arc};
+use tokio::{io::AsyncWriteExt, time::Duration};
 
 #[test]
 fn end_to_end() {
@@ -61,7 +69,10 @@ fn end_to_end() {
     let manifest_handle = rt
         .block_on(
             StateSnapshotBackupController::new(
-                StateSnapshotBackupOpt { epoch },
+                StateSnapshotBackupOpt {
+                    epoch,
+                    base_manifest: None,
+                },
                 GlobalBackupOpt {
                     max_chunk_size: 500,
                     concurrent_data_requests: 2,
@@ -111,3 +122,262 @@ fn end_to_end() {
 
     rt.shutdown_timeout(Duration::from_secs(1));
 }
+
+#[test]
+fn incremental_end_to_end() {
+    let (_src_db_dir, src_db, _blocks) = tmp_db_with_random_content();
+    let tgt_db_dir = TempPath::new();
+    tgt_db_dir.create_as_dir().unwrap();
+    let backup_dir = TempPath::new();
+    backup_dir.create_as_dir().unwrap();
+    let store: Arc<dyn BackupStorage> = Arc::new(LocalFs::new(backup_dir.path().to_path_buf()));
+
+    let epoch = src_db
+        .get_latest_ledger_info()
+        .unwrap()
+        .ledger_info()
+        .next_block_epoch()
+        - 1;
+    let version = src_db
+        .get_epoch_ending_ledger_infos(epoch, epoch + 1)
+        .unwrap()
+        .ledger_info_with_sigs
+        .pop()
+        .unwrap()
+        .ledger_info()
+        .version();
+    let state_root_hash = src_db
+        .get_transactions(version, 1, version, false)
+        .unwrap()
+        .proof
+        .transaction_infos
+        .pop()
+        .unwrap()
+        .state_checkpoint_hash()
+        .unwrap();
+
+    let (rt, port) = start_local_backup_service(src_db);
+    let client = Arc::new(BackupServiceClient::new(format!(
+        "http://localhost:{}",
+        port
+    )));
+    let backup = |epoch, base_manifest| {
+        rt.block_on(
+            StateSnapshotBackupController::new(
+                StateSnapshotBackupOpt {
+                    epoch,
+                    base_manifest,
+                },
+                GlobalBackupOpt {
+                    max_chunk_size: 500,
+                    concurrent_data_requests: 2,
+                },
+                client.clone(),
+                Arc::clone(&store),
+            )
+            .run(),
+        )
+        .unwrap()
+    };
+    // A full snapshot at the previous epoch, and a chain of two incremental ones on top of it.
+    let full_manifest = backup(epoch.saturating_sub(1), None);
+    let incremental_manifest = backup(epoch, Some(full_manifest));
+    let manifest_handle = backup(epoch, Some(incremental_manifest));
+
+    rt.block_on(
+        StateSnapshotRestoreController::new(
+            StateSnapshotRestoreOpt {
+                manifest_handle,
+                version,
+                validate_modules: false,
+                restore_mode: StateSnapshotRestoreMode::Default,
+            },
+            GlobalRestoreOpt {
+                dry_run: false,
+                db_dir: Some(tgt_db_dir.path().to_path_buf()),
+                target_version: None, // max
+                trusted_waypoints: TrustedWaypointOpt::default(),
+                rocksdb_opt: RocksdbOpt::default(),
+                concurrent_downloads: ConcurrentDownloadsOpt::default(),
+                replay_concurrency_level: ReplayConcurrencyLevelOpt::default(),
+                enable_state_indices: false,
+            }
+            .try_into()
+            .unwrap(),
+            store,
+            None, /* epoch_history */
+        )
+        .run(),
+    )
+    .unwrap();
+
+    let tgt_db = AptosDB::new_readonly_for_test(&tgt_db_dir);
+    assert_eq!(
+        tgt_db
+            .get_state_snapshot_before(version + 1)
+            .unwrap()
+            .unwrap(),
+        (version, state_root_hash)
+    );
+
+    rt.shutdown_timeout(Duration::from_secs(1));
+}
+
+fn sorted_records(records: &[(u8, u8)]) -> Vec<(StateKey, StateValue)> {
+    let mut records: Vec<_> = records
+        .iter()
+        .map(|(k, v)| {
+            (
+                StateKey::raw(&[*k]),
+                StateValue::new_legacy(vec![*v].into()),
+            )
+        })
+        .collect();
+    records.sort_by_key(|(k, _)| k.hash());
+    records
+}
+
+async fn write_chunk<T: Serialize>(
+    store: &Arc<dyn BackupStorage>,
+    backup_handle: &BackupHandleRef,
+    name: &str,
+    first_idx: usize,
+    records: &[T],
+    keys: &[(StateKey, StateValue)],
+) -> StateSnapshotChunk {
+    let (blobs, mut file) = store
+        .create_for_write(backup_handle, &ShellSafeName::from_str(name).unwrap())
+        .await
+        .unwrap();
+    for record in records {
+        let bytes = bcs::to_bytes(record).unwrap();
+        file.write_all(&(bytes.len() as u32).to_be_bytes())
+            .await
+            .unwrap();
+        file.write_all(&bytes).await.unwrap();
+    }
+    file.shutdown().await.unwrap();
+    StateSnapshotChunk {
+        first_idx,
+        last_idx: first_idx + keys.len() - 1,
+        first_key: keys.first().unwrap().0.hash(),
+        last_key: keys.last().unwrap().0.hash(),
+        proof: blobs.clone(),
+        blobs,
+    }
+}
+
+fn unchanged(records: &[(StateKey, StateValue)]) -> StateSnapshotDeltaRecord {
+    StateSnapshotDeltaRecord::Unchanged {
+        first_key: records.first().unwrap().0.hash(),
+        last_key: records.last().unwrap().0.hash(),
+        leaf_count: records.len(),
+    }
+}
+
+#[tokio::test]
+async fn test_reassemble_incremental_chunks() {
+    let backup_dir = TempPath::new();
+    backup_dir.create_as_dir().unwrap();
+    let store: Arc<dyn BackupStorage> = Arc::new(LocalFs::new(backup_dir.path().to_path_buf()));
+    let backup_handle = store
+        .create_backup(&ShellSafeName::from_str("state").unwrap())
+        .await
+        .unwrap();
+    let manifest = |version, chunks, base| StateSnapshotBackup {
+        version,
+        epoch: 0,
+        root_hash: HashValue::zero(),
+        chunks,
+        proof: "proof".to_string(),
+        base,
+    };
+
+    let base = sorted_records(&[(1, 1), (2, 2), (3, 3), (4, 4), (5, 5), (6, 6)]);
+    let base_manifest = manifest(
+        1,
+        vec![
+            write_chunk(&store, &backup_handle, "base_0", 0, &base[..3], &base[..3]).await,
+            write_chunk(&store, &backup_handle, "base_1", 3, &base[3..], &base[3..]).await,
+        ],
+        None,
+    );
+    let (base_manifest_handle, mut file) = store
+        .create_for_write(
+            &backup_handle,
+            &ShellSafeName::from_str("base.manifest").unwrap(),
+        )
+        .await
+        .unwrap();
+    file.write_all(&serde_json::to_vec(&base_manifest).unwrap())
+        .await
+        .unwrap();
+    file.shutdown().await.unwrap();
+
+    // The second item changed and the fifth one was deleted since the base snapshot.
+    let mut records = base.clone();
+    records[1].1 = StateValue::new_legacy(vec![20].into());
+    records.remove(4);
+    let delta_0 = vec![
+        unchanged(&base[..1]),
+        StateSnapshotDeltaRecord::Changed(records[1].0.clone(), records[1].1.clone()),
+        unchanged(&base[2..4]),
+    ];
+    let delta_1 = vec![unchanged(&base[5..])];
+    let incremental_manifest = manifest(
+        2,
+        vec![
+            write_chunk(
+                &store,
+                &backup_handle,
+                "delta_0",
+                0,
+                &delta_0,
+                &records[..4],
+            )
+            .await,
+            write_chunk(
+                &store,
+                &backup_handle,
+                "delta_1",
+                4,
+                &delta_1,
+                &records[4..],
+            )
+            .await,
+        ],
+        Some(base_manifest_handle.clone()),
+    );
+
+    let mut reader =
+        StateSnapshotRecordReader::from_manifest(store.clone(), incremental_manifest.clone())
+            .await
+            .unwrap();
+    assert_eq!(reader.next_chunk().await.unwrap().unwrap(), records[..4]);
+    assert_eq!(reader.next_chunk().await.unwrap().unwrap(), records[4..]);
+    assert!(reader.next_chunk().await.unwrap().is_none());
+
+    // Resuming after the first chunk.
+    let mut reader =
+        StateSnapshotRecordReader::from_manifest(store.clone(), incremental_manifest.clone())
+            .await
+            .unwrap();
+    reader.skip_until(records[3].0.hash()).await.unwrap();
+    assert_eq!(reader.next_chunk().await.unwrap().unwrap(), records[4..]);
+
+    // A range of unchanged items not matching the base snapshot.
+    let bad_delta = vec![StateSnapshotDeltaRecord::Unchanged {
+        first_key: base[0].0.hash(),
+        last_key: base[1].0.hash(),
+        leaf_count: 3,
+    }];
+    let bad_manifest = manifest(
+        2,
+        vec![write_chunk(&store, &backup_handle, "bad_0", 0, &bad_delta, &base[..3]).await],
+        Some(base_manifest_handle),
+    );
+    let mut reader = StateSnapshotRecordReader::from_manifest(store.clone(), bad_manifest)
+        .await
+        .unwrap();
+    assert!(reader.next_chunk().await.is_err());
+}
@@ -21,7 +21,7 @@ use crate::{
 };
 use anyhow::{anyhow, ensure, Result};
 use aptos_db::backup::backup_handler::DbState;
-use aptos_infallible::duration_since_epoch;
+use aptos_infallible::{duration_since_epoch, Mutex};
 use aptos_logger::prelude::*;
 use aptos_types::transaction::Version;
 use clap::Parser;
@@ -56,6 +56,16 @@ pub struct BackupCoordinatorOpt {
         is already at 19, then snapshot at 15 will be taken instead of at 10 (not at 18)."
     )]
     pub state_snapshot_interval_epochs: usize,
+    #[clap(
+        long,
+        default_value_t = 0,
+        help = "Max number of incremental state snapshots taken in a row, each only storing the \
+        state items changed since the previous snapshot, before a full snapshot is taken again. \
+        A longer chain saves storage but makes restoring slower, since all snapshots in the chain \
+        are read. The chain continues from the latest state snapshot in the backup storage, as \
+        recorded in the metadata. 0 disables incremental state snapshots."
+    )]
+    pub max_incremental_state_snapshots: usize,
     // Defaulting to 1M, which converts to a 20 minutes delay of a transaction showing up in a backup,
     // from a 1K TPS chain, and a few minutes replay time.
     #[clap(
@@ -89,6 +99,10 @@ pub struct BackupCoordinator {
     global_opt: GlobalBackupOpt,
     metadata_cache_opt: MetadataCacheOpt,
     state_snapshot_interval_epochs: usize,
+    max_incremental_state_snapshots: usize,
+    /// Manifest of the latest state snapshot, and the number of incremental snapshots in a row up
+    /// to it. Loaded from the metadata on start, and updated as snapshots are taken.
+    last_state_snapshot: Mutex<Option<(FileHandle, usize)>>,
     transaction_batch_size: usize,
     concurrent_downloads: usize,
 }
@@ -107,6 +121,8 @@ impl BackupCoordinator {
             global_opt,
             metadata_cache_opt: opt.metadata_cache_opt,
             state_snapshot_interval_epochs: opt.state_snapshot_interval_epochs,
+            max_incremental_state_snapshots: opt.max_incremental_state_snapshots,
+            last_state_snapshot: Mutex::new(None),
             transaction_batch_size: opt.transaction_batch_size,
             concurrent_downloads: opt.concurrent_downloads.get(),
         }
@@ -114,13 +130,16 @@ impl BackupCoordinator {
 
     pub async fn run(&self) -> Result<()> {
         // Connect to both the local node and the backup storage.
-        let backup_state = metadata::cache::sync_and_load(
+        let metadata_view = metadata::cache::sync_and_load(
             &self.metadata_cache_opt,
             Arc::clone(&self.storage),
             self.concurrent_downloads,
         )
-        .await?
-        .get_storage_state()?;
+        .await?;
+        let backup_state = metadata_view.get_storage_state()?;
+        *self.last_state_snapshot.lock() = metadata_view
+            .select_state_snapshot(Version::MAX)?
+            .map(|snapshot| (snapshot.manifest, snapshot.num_incremental));
 
         // On new DbState retrieved:
         // `watch_db_state` informs `backup_epoch_endings` via channel 1,
@@ -255,14 +274,27 @@ impl BackupCoordinator {
             return Ok(last_snapshot_epoch_in_backup);
         }
 
-        StateSnapshotBackupController::new(
-            StateSnapshotBackupOpt { epoch },
+        let last_state_snapshot = self.last_state_snapshot.lock().clone();
+        let (base_manifest, num_incremental) = match last_state_snapshot {
+            Some((manifest, num_incremental))
+                if num_incremental < self.max_incremental_state_snapshots =>
+            {
+                (Some(manifest), num_incremental + 1)
+            },
+            _ => (None, 0),
+        };
+        let manifest = StateSnapshotBackupController::new(
+            StateSnapshotBackupOpt {
+                epoch,
+                base_manifest,
+            },
             self.global_opt.clone(),
             Arc::clone(&self.client),
             Arc::clone(&self.storage),
         )
         .run()
         .await?;
+        *self.last_state_snapshot.lock() = Some((manifest, num_incremental));
 
         Ok(Some(epoch))
     }
@@ -6,3 +6,4 @@ pub mod backup;
 pub mod replay_verify;
 pub mod restore;
 pub mod verify;
+pub mod verify_storage;
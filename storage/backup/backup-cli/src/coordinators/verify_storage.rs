@@ -0,0 +1,589 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    backup_types::{
+        epoch_ending::{
+            manifest::{EpochEndingBackup, EpochEndingChunk},
+            restore::EpochHistory,
+        },
+        state_snapshot::{
+            manifest::StateSnapshotBackup,
+            restore::{StateSnapshotRestoreController, StateSnapshotRestoreOpt},
+        },
+        transaction::{manifest::TransactionBackup, restore::LoadedChunk},
+    },
+    metadata,
+    metadata::{
+        cache::MetadataCacheOpt, EpochEndingBackupMeta, StateSnapshotBackupMeta,
+        TransactionBackupMeta,
+    },
+    storage::{BackupStorage, FileHandle, FileHandleRef},
+    utils::{
+        read_record_bytes::ReadRecordBytes, storage_ext::BackupStorageExt, stream::StreamX,
+        GlobalRestoreOptions, RestoreRunMode, TrustedWaypointOpt,
+    },
+};
+use anyhow::{anyhow, ensure, Result};
+use aptos_db::state_restore::StateSnapshotRestoreMode;
+use aptos_logger::prelude::*;
+use aptos_types::{
+    ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
+    proof::TransactionInfoWithProof,
+    transaction::Version,
+    waypoint::Waypoint,
+};
+use futures::StreamExt;
+use itertools::Itertools;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+    sync::Arc,
+};
+
+/// Audits every backup referenced by the metadata in a backup storage, independently of each
+/// other: every file is read and re-hashed, every proof is checked, and every LedgerInfo is
+/// verified against the epoch ending backups. Unlike `VerifyCoordinator`, it doesn't stop at the
+/// first problem, but collects all of them in a `StorageAuditReport`.
+pub struct VerifyStorageCoordinator {
+    storage: Arc<dyn BackupStorage>,
+    metadata_cache_opt: MetadataCacheOpt,
+    trusted_waypoints_opt: TrustedWaypointOpt,
+    concurrent_downloads: usize,
+    /// Last epoch expected to be covered by the epoch ending backups.
+    expected_end_epoch: Option<u64>,
+    /// Last version expected to be covered by the transaction backups.
+    expected_end_version: Option<Version>,
+    output: Option<PathBuf>,
+}
+
+impl VerifyStorageCoordinator {
+    pub fn new(
+        storage: Arc<dyn BackupStorage>,
+        metadata_cache_opt: MetadataCacheOpt,
+        trusted_waypoints_opt: TrustedWaypointOpt,
+        concurrent_downloads: usize,
+        expected_end_epoch: Option<u64>,
+        expected_end_version: Option<Version>,
+        output: Option<PathBuf>,
+    ) -> Self {
+        Self {
+            storage,
+            metadata_cache_opt,
+            trusted_waypoints_opt,
+            concurrent_downloads,
+            expected_end_epoch,
+            expected_end_version,
+            output,
+        }
+    }
+
+    /// Writes the report as JSON to the output file (or stdout), and fails if it's not clean.
+    pub async fn run(self) -> Result<()> {
+        info!("Verify storage coordinator started.");
+        let output = self.output.clone();
+        let report = self.audit().await?;
+
+        let json = serde_json::to_string_pretty(&report)?;
+        match output {
+            Some(path) => std::fs::write(&path, json)?,
+            None => println!("{}", json),
+        }
+
+        let num_issues = report.num_issues();
+        ensure!(
+            num_issues == 0,
+            "Backup storage audit found {} issue(s).",
+            num_issues,
+        );
+        info!("Verify storage coordinator exiting with success.");
+        Ok(())
+    }
+
+    pub async fn audit(self) -> Result<StorageAuditReport> {
+        let metadata_view = metadata::cache::sync_and_load(
+            &self.metadata_cache_opt,
+            Arc::clone(&self.storage),
+            self.concurrent_downloads,
+        )
+        .await?;
+        let trusted_waypoints = Arc::new(self.trusted_waypoints_opt.clone().verify()?);
+
+        // Unless specified, the epoch ending backups are expected to cover the epochs of all state
+        // snapshots, and the transaction backups to cover all versions the other backups are at.
+        let latest_snapshot = metadata_view.select_state_snapshot(Version::MAX)?;
+        let expected_end_epoch = self
+            .expected_end_epoch
+            .or_else(|| latest_snapshot.as_ref().map(|s| s.epoch));
+        let expected_end_version = self.expected_end_version.or_else(|| {
+            metadata_view
+                .all_epoch_ending_backups()
+                .iter()
+                .map(|b| b.last_version)
+                .chain(latest_snapshot.as_ref().map(|s| s.version))
+                .max()
+        });
+
+        let mut report = StorageAuditReport {
+            epoch_gaps: find_gaps(
+                metadata_view
+                    .all_epoch_ending_backups()
+                    .iter()
+                    .map(|b| (b.first_epoch, b.last_epoch)),
+                expected_end_epoch,
+            ),
+            version_gaps: find_gaps(
+                metadata_view
+                    .all_transaction_backups()
+                    .iter()
+                    .map(|b| (b.first_version, b.last_version)),
+                expected_end_version,
+            ),
+            ..Default::default()
+        };
+
+        let mut verified_epoch_endings = BTreeMap::new();
+        for backup in metadata_view.all_epoch_ending_backups() {
+            report.epoch_ending_backups.push(
+                self.audit_epoch_ending_backup(
+                    backup,
+                    &trusted_waypoints,
+                    &mut verified_epoch_endings,
+                )
+                .await,
+            );
+        }
+        // Only the epochs continuous from genesis can be used to verify other LedgerInfos.
+        let epoch_history = Arc::new(EpochHistory {
+            epoch_endings: verified_epoch_endings
+                .into_values()
+                .enumerate()
+                .take_while(|(idx, li)| li.epoch() == *idx as u64)
+                .map(|(_, li)| li)
+                .collect(),
+            trusted_waypoints: trusted_waypoints.clone(),
+        });
+        info!(
+            num_epochs = epoch_history.epoch_endings.len(),
+            "Epoch history verified."
+        );
+
+        for backup in metadata_view.all_transaction_backups() {
+            report
+                .transaction_backups
+                .push(self.audit_transaction_backup(backup, &epoch_history).await);
+        }
+
+        let global_opt = GlobalRestoreOptions {
+            target_version: Version::MAX,
+            trusted_waypoints,
+            run_mode: Arc::new(RestoreRunMode::Verify),
+            concurrent_downloads: self.concurrent_downloads,
+            replay_concurrency_level: 0, // won't replay, doesn't matter
+        };
+        for backup in metadata_view.all_state_snapshot_backups() {
+            report.state_snapshot_backups.push(
+                self.audit_state_snapshot_backup(backup, &global_opt, &epoch_history)
+                    .await,
+            );
+        }
+
+        Ok(report)
+    }
+
+    async fn audit_epoch_ending_backup(
+        &self,
+        backup: &EpochEndingBackupMeta,
+        trusted_waypoints: &HashMap<Version, Waypoint>,
+        verified_epoch_endings: &mut BTreeMap<u64, LedgerInfo>,
+    ) -> BackupAudit {
+        info!(manifest = backup.manifest, "Auditing epoch ending backup.");
+        let mut audit =
+            BackupAudit::new(&backup.manifest, backup.first_version, backup.last_version)
+                .with_epochs(backup.first_epoch, backup.last_epoch);
+
+        let manifest = match self.load_epoch_ending_manifest(backup).await {
+            Ok(manifest) => manifest,
+            Err(e) => return audit.with_error(e),
+        };
+        let mut waypoints = manifest.waypoints.iter();
+        for chunk in &manifest.chunks {
+            let lis = match self.read_epoch_ending_chunk(chunk).await {
+                Ok(lis) => lis,
+                Err(e) => {
+                    audit.add_error(anyhow!("Chunk {}: {:#}", chunk.ledger_infos, e));
+                    // Keep the waypoints in step with the epochs in the following chunks.
+                    waypoints.nth((chunk.last_epoch - chunk.first_epoch) as usize);
+                    continue;
+                },
+            };
+
+            for (epoch, li) in (chunk.first_epoch..).zip(lis) {
+                let waypoint = waypoints.next().expect("Checked by manifest.verify().");
+                match verify_epoch_ending_ledger_info(
+                    epoch,
+                    &li,
+                    waypoint,
+                    trusted_waypoints,
+                    verified_epoch_endings,
+                ) {
+                    Ok(()) => {
+                        verified_epoch_endings.insert(epoch, li.ledger_info().clone());
+                    },
+                    Err(e) => audit.add_error(anyhow!("Epoch {}: {:#}", epoch, e)),
+                }
+            }
+        }
+
+        audit
+    }
+
+    async fn load_epoch_ending_manifest(
+        &self,
+        backup: &EpochEndingBackupMeta,
+    ) -> Result<EpochEndingBackup> {
+        let manifest: EpochEndingBackup = self.storage.load_json_file(&backup.manifest).await?;
+        manifest.verify()?;
+        ensure!(
+            manifest.first_epoch == backup.first_epoch && manifest.last_epoch == backup.last_epoch,
+            "Epoch range in manifest [{}, {}] doesn't match that in metadata [{}, {}].",
+            manifest.first_epoch,
+            manifest.last_epoch,
+            backup.first_epoch,
+            backup.last_epoch,
+        );
+        Ok(manifest)
+    }
+
+    async fn read_epoch_ending_chunk(
+        &self,
+        chunk: &EpochEndingChunk,
+    ) -> Result<Vec<LedgerInfoWithSignatures>> {
+        let lis: Vec<LedgerInfoWithSignatures> =
+            read_records(&self.storage, &chunk.ledger_infos).await?;
+        ensure!(
+            chunk.first_epoch + lis.len() as u64 == chunk.last_epoch + 1,
+            "Expecting {} LedgerInfos, got {}.",
+            chunk.last_epoch + 1 - chunk.first_epoch,
+            lis.len(),
+        );
+        Ok(lis)
+    }
+
+    async fn audit_transaction_backup(
+        &self,
+        backup: &TransactionBackupMeta,
+        epoch_history: &Arc<EpochHistory>,
+    ) -> BackupAudit {
+        info!(manifest = backup.manifest, "Auditing transaction backup.");
+        let mut audit =
+            BackupAudit::new(&backup.manifest, backup.first_version, backup.last_version);
+
+        let manifest = match self.load_transaction_manifest(backup).await {
+            Ok(manifest) => manifest,
+            Err(e) => return audit.with_error(e),
+        };
+        let futs = manifest.chunks.into_iter().map(|chunk| async move {
+            let transactions = chunk.transactions.clone();
+            let res: Result<()> = async {
+                let loaded = LoadedChunk::load(chunk, &self.storage, Some(epoch_history)).await?;
+                ensure_verifiable(epoch_history, &loaded.ledger_info)
+            }
+            .await;
+            res.map_err(|e| anyhow!("Chunk {}: {:#}", transactions, e))
+        });
+        let errors: Vec<_> = futures::stream::iter(futs)
+            .buffered_x(
+                self.concurrent_downloads * 2, /* buffer size */
+                self.concurrent_downloads,     /* concurrency */
+            )
+            .filter_map(|res| async move { res.err() })
+            .collect()
+            .await;
+        errors.into_iter().for_each(|e| audit.add_error(e));
+
+        audit
+    }
+
+    async fn load_transaction_manifest(
+        &self,
+        backup: &TransactionBackupMeta,
+    ) -> Result<TransactionBackup> {
+        let manifest: TransactionBackup = self.storage.load_json_file(&backup.manifest).await?;
+        manifest.verify()?;
+        ensure!(
+            manifest.first_version == backup.first_version
+                && manifest.last_version == backup.last_version,
+            "Version range in manifest [{}, {}] doesn't match that in metadata [{}, {}].",
+            manifest.first_version,
+            manifest.last_version,
+            backup.first_version,
+            backup.last_version,
+        );
+        Ok(manifest)
+    }
+
+    async fn audit_state_snapshot_backup(
+        &self,
+        backup: &StateSnapshotBackupMeta,
+        global_opt: &GlobalRestoreOptions,
+        epoch_history: &Arc<EpochHistory>,
+    ) -> BackupAudit {
+        info!(
+            manifest = backup.manifest,
+            "Auditing state snapshot backup."
+        );
+        let audit = BackupAudit::new(&backup.manifest, backup.version, backup.version)
+            .with_epochs(backup.epoch, backup.epoch);
+
+        let res: Result<()> = async {
+            let manifest: StateSnapshotBackup =
+                self.storage.load_json_file(&backup.manifest).await?;
+            ensure!(
+                manifest.version == backup.version && manifest.epoch == backup.epoch,
+                "Version {} and epoch {} in manifest don't match those in metadata.",
+                manifest.version,
+                manifest.epoch,
+            );
+            let (_, li): (TransactionInfoWithProof, LedgerInfoWithSignatures) =
+                self.storage.load_bcs_file(&manifest.proof).await?;
+            ensure_verifiable(epoch_history, &li)?;
+
+            // Verify mode restores into a mock store, reading and verifying every chunk against
+            // the range proofs.
+            StateSnapshotRestoreController::new(
+                StateSnapshotRestoreOpt {
+                    manifest_handle: backup.manifest.clone(),
+                    version: backup.version,
+                    validate_modules: false,
+                    restore_mode: StateSnapshotRestoreMode::Default,
+                },
+                global_opt.clone(),
+                Arc::clone(&self.storage),
+                Some(Arc::clone(epoch_history)),
+            )
+            .run()
+            .await
+        }
+        .await;
+
+        match res {
+            Ok(()) => audit,
+            Err(e) => audit.with_error(e),
+        }
+    }
+}
+
+/// Checks an epoch ending LedgerInfo against the waypoint in the manifest, and then either a
+/// trusted waypoint or the validator set of the previous epoch.
+fn verify_epoch_ending_ledger_info(
+    epoch: u64,
+    li: &LedgerInfoWithSignatures,
+    waypoint: &Waypoint,
+    trusted_waypoints: &HashMap<Version, Waypoint>,
+    verified_epoch_endings: &BTreeMap<u64, LedgerInfo>,
+) -> Result<()> {
+    ensure!(
+        li.ledger_info().epoch() == epoch,
+        "LedgerInfo has epoch {}.",
+        li.ledger_info().epoch(),
+    );
+    ensure!(
+        li.ledger_info().ends_epoch(),
+        "LedgerInfo is not one at an epoch ending.",
+    );
+    let wp_li = Waypoint::new_epoch_boundary(li.ledger_info())?;
+    ensure!(
+        *waypoint == wp_li,
+        "Waypoints don't match. In manifest: {}, In chunk: {}",
+        waypoint,
+        wp_li,
+    );
+    if let Some(verified) = verified_epoch_endings.get(&epoch) {
+        ensure!(
+            verified == li.ledger_info(),
+            "LedgerInfo conflicts with the one in another epoch ending backup.",
+        );
+    }
+
+    if let Some(wp_trusted) = trusted_waypoints.get(&wp_li.version()) {
+        ensure!(
+            *wp_trusted == wp_li,
+            "Waypoints don't match. In backup: {}, trusted: {}",
+            wp_li,
+            wp_trusted,
+        );
+    } else if epoch > 0 {
+        verified_epoch_endings
+            .get(&(epoch - 1))
+            .ok_or_else(|| {
+                anyhow!(
+                    "Signatures can't be verified, epoch {} is not verified.",
+                    epoch - 1
+                )
+            })?
+            .next_epoch_state()
+            .ok_or_else(|| anyhow!("Next epoch state not found from LI at epoch {}.", epoch - 1))?
+            .verify(li)?;
+    }
+    Ok(())
+}
+
+/// `EpochHistory::verify_ledger_info()` lets LedgerInfos newer than the history pass, which is
+/// not good enough for an audit.
+fn ensure_verifiable(epoch_history: &EpochHistory, li: &LedgerInfoWithSignatures) -> Result<()> {
+    ensure!(
+        li.ledger_info().epoch() <= epoch_history.epoch_endings.len() as u64,
+        "LedgerInfo at epoch {} can't be verified, verified epoch endings cover {} epoch(s).",
+        li.ledger_info().epoch(),
+        epoch_history.epoch_endings.len(),
+    );
+    Ok(())
+}
+
+async fn read_records<T: DeserializeOwned>(
+    storage: &Arc<dyn BackupStorage>,
+    file_handle: &FileHandleRef,
+) -> Result<Vec<T>> {
+    let mut file = storage.open_for_read(file_handle).await?;
+    let mut records = Vec::new();
+    while let Some(record_bytes) = file.read_record_bytes().await? {
+        records.push(bcs::from_bytes(&record_bytes)?);
+    }
+    Ok(records)
+}
+
+/// Returns the ranges between 0 and `expected_last` (or the end of the given inclusive ranges if
+/// it's further) not covered by any of the given ranges.
+pub fn find_gaps(
+    ranges: impl IntoIterator<Item = (u64, u64)>,
+    expected_last: Option<u64>,
+) -> Vec<Gap> {
+    let mut gaps = Vec::new();
+    let mut next = Some(0);
+    for (first, last) in ranges.into_iter().sorted() {
+        // `None` once the ranges reach u64::MAX.
+        let start = match next {
+            Some(start) => start,
+            None => break,
+        };
+        if first > start {
+            gaps.push(Gap {
+                first: start,
+                last: first - 1,
+            });
+        }
+        next = last.checked_add(1).map(|end| end.max(start));
+    }
+    if let (Some(start), Some(expected_last)) = (next, expected_last) {
+        if expected_last >= start {
+            gaps.push(Gap {
+                first: start,
+                last: expected_last,
+            });
+        }
+    }
+    gaps
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct StorageAuditReport {
+    pub epoch_ending_backups: Vec<BackupAudit>,
+    pub transaction_backups: Vec<BackupAudit>,
+    pub state_snapshot_backups: Vec<BackupAudit>,
+    /// Epochs not covered by any epoch ending backup.
+    pub epoch_gaps: Vec<Gap>,
+    /// Versions not covered by any transaction backup.
+    pub version_gaps: Vec<Gap>,
+}
+
+impl StorageAuditReport {
+    pub fn num_issues(&self) -> usize {
+        self.epoch_ending_backups
+            .iter()
+            .chain(&self.transaction_backups)
+            .chain(&self.state_snapshot_backups)
+            .map(|b| b.errors.len())
+            .sum::<usize>()
+            + self.epoch_gaps.len()
+            + self.version_gaps.len()
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct BackupAudit {
+    pub manifest: FileHandle,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first_epoch: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_epoch: Option<u64>,
+    pub first_version: Version,
+    pub last_version: Version,
+    pub errors: Vec<String>,
+}
+
+impl BackupAudit {
+    fn new(manifest: &FileHandleRef, first_version: Version, last_version: Version) -> Self {
+        Self {
+            manifest: manifest.to_string(),
+            first_epoch: None,
+            last_epoch: None,
+            first_version,
+            last_version,
+            errors: Vec::new(),
+        }
+    }
+
+    fn with_epochs(mut self, first_epoch: u64, last_epoch: u64) -> Self {
+        self.first_epoch = Some(first_epoch);
+        self.last_epoch = Some(last_epoch);
+        self
+    }
+
+    fn add_error(&mut self, error: anyhow::Error) {
+        warn!(manifest = self.manifest, error = ?error, "Audit issue found.");
+        self.errors.push(format!("{:#}", error));
+    }
+
+    fn with_error(mut self, error: anyhow::Error) -> Self {
+        self.add_error(error);
+        self
+    }
+}
+
+/// An inclusive range of epochs or versions.
+#[derive(Debug, Eq, PartialEq, Serialize)]
+pub struct Gap {
+    pub first: u64,
+    pub last: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::coordinators::verify_storage::{find_gaps, Gap};
+
+    fn gap(first: u64, last: u64) -> Gap {
+        Gap { first, last }
+    }
+
+    #[test]
+    fn test_find_gaps() {
+        assert!(find_gaps(vec![], None).is_empty());
+        assert_eq!(find_gaps(vec![], Some(5)), vec![gap(0, 5)]);
+        assert!(find_gaps(vec![(0, 9), (10, 19)], None).is_empty());
+        assert!(find_gaps(vec![(10, 19), (0, 9)], Some(19)).is_empty());
+        assert!(find_gaps(vec![(0, 15), (5, 9)], Some(12)).is_empty());
+        assert_eq!(find_gaps(vec![(5, 9), (15, 19)], None), vec![
+            gap(0, 4),
+            gap(10, 14)
+        ]);
+        // Missing range after the last backup.
+        assert_eq!(find_gaps(vec![(0, 9), (15, 19)], Some(29)), vec![
+            gap(10, 14),
+            gap(20, 29)
+        ]);
+        assert_eq!(find_gaps(vec![(0, 9)], Some(10)), vec![gap(10, 10)]);
+        assert!(find_gaps(vec![(0, u64::MAX)], Some(u64::MAX)).is_empty());
+    }
+}
@@ -39,11 +39,17 @@ impl Metadata {
         })
     }
 
-    pub fn new_state_snapshot_backup(epoch: u64, version: Version, manifest: FileHandle) -> Self {
+    pub fn new_state_snapshot_backup(
+        epoch: u64,
+        version: Version,
+        manifest: FileHandle,
+        num_incremental: usize,
+    ) -> Self {
         Self::StateSnapshotBackup(StateSnapshotBackupMeta {
             epoch,
             version,
             manifest,
+            num_incremental,
         })
     }
 
@@ -187,6 +193,9 @@ pub struct StateSnapshotBackupMeta {
     pub epoch: u64,
     pub version: Version,
     pub manifest: FileHandle,
+    /// Number of incremental snapshots in a row up to this one, 0 if this is a full snapshot.
+    #[serde(default)]
+    pub num_incremental: usize,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize, Eq, PartialEq, Ord, PartialOrd)]
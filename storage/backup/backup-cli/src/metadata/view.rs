@@ -101,6 +101,21 @@ impl MetadataView {
         })
     }
 
+    /// All epoch ending backups in the metadata, sorted, including overlapping ones.
+    pub fn all_epoch_ending_backups(&self) -> &[EpochEndingBackupMeta] {
+        &self.epoch_ending_backups
+    }
+
+    /// All state snapshot backups in the metadata, sorted.
+    pub fn all_state_snapshot_backups(&self) -> &[StateSnapshotBackupMeta] {
+        &self.state_snapshot_backups
+    }
+
+    /// All transaction backups in the metadata, sorted, including overlapping ones.
+    pub fn all_transaction_backups(&self) -> &[TransactionBackupMeta] {
+        &self.transaction_backups
+    }
+
     pub fn select_latest_compaction_timestamps(&self) -> Option<CompactionTimestampsMeta> {
         self.compaction_timestamps.clone()
     }
@@ -0,0 +1,581 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+#[cfg(test)]
+mod tests;
+
+use crate::{
+    storage::{
+        BackupHandle, BackupHandleRef, BackupStorage, FileHandle, FileHandleRef, ShellSafeName,
+        TextLine,
+    },
+    utils::error_notes::ErrorNotes,
+};
+use aes_gcm::{aead::Aead, Aes256Gcm, KeyInit, Nonce};
+use anyhow::{bail, ensure, format_err, Result};
+use aptos_config::config::SecureBackend;
+use aptos_secure_storage::{KVStorage, Storage};
+use async_trait::async_trait;
+use clap::Parser;
+use rand::random;
+use sha2::{Digest, Sha256};
+use std::{
+    io,
+    io::Cursor,
+    path::PathBuf,
+    pin::Pin,
+    sync::Arc,
+    task::{ready, Context, Poll},
+};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, ReadBuf};
+
+#[derive(Parser, Clone, Debug, Default)]
+pub struct EncryptionOpt {
+    #[clap(
+        long,
+        help = "File holding a hex encoded 256-bit key. If set, all backup files and metadata \
+        lines are encrypted with AES-256-GCM on the client side before reaching the storage, and \
+        decrypted when read back."
+    )]
+    pub encryption_key_file: Option<PathBuf>,
+    #[clap(
+        long,
+        conflicts_with = "encryption_key_file",
+        help = "YAML config of a secure storage backend (e.g. Vault) holding the hex encoded \
+        256-bit encryption key under the name specified by --encryption-key-name. Alternative to \
+        --encryption-key-file."
+    )]
+    pub encryption_key_secure_backend_config: Option<PathBuf>,
+    #[clap(long, default_value = "backup_encryption_key")]
+    pub encryption_key_name: String,
+}
+
+impl EncryptionOpt {
+    /// Returns None if encryption is not configured.
+    pub async fn load_key(&self) -> Result<Option<EncryptionKey>> {
+        if let Some(path) = &self.encryption_key_file {
+            let content = tokio::fs::read_to_string(path).await.err_notes(path)?;
+            return Ok(Some(EncryptionKey::from_hex(content.trim())?));
+        }
+        if let Some(path) = &self.encryption_key_secure_backend_config {
+            let content = tokio::fs::read(path).await.err_notes(path)?;
+            let backend: SecureBackend = serde_yaml::from_slice(&content)?;
+            let key = Storage::from(&backend)
+                .get::<String>(&self.encryption_key_name)?
+                .value;
+            return Ok(Some(EncryptionKey::from_hex(key.trim())?));
+        }
+        Ok(None)
+    }
+}
+
+/// A 256-bit AES key. Every file is encrypted with its own key derived from this and a random
+/// salt, so nonces never repeat across files.
+pub struct EncryptionKey([u8; Self::LENGTH]);
+
+impl EncryptionKey {
+    pub const LENGTH: usize = 32;
+
+    pub fn new(bytes: [u8; Self::LENGTH]) -> Self {
+        Self(bytes)
+    }
+
+    pub fn from_hex(hex_str: &str) -> Result<Self> {
+        let bytes = hex::decode(hex_str).map_err(|e| format_err!("Invalid key: {}", e))?;
+        ensure!(
+            bytes.len() == Self::LENGTH,
+            "Expecting a {}-byte key, got {} bytes.",
+            Self::LENGTH,
+            bytes.len(),
+        );
+        let mut key = [0u8; Self::LENGTH];
+        key.copy_from_slice(&bytes);
+        Ok(Self(key))
+    }
+
+    fn file_cipher(&self, salt: &[u8]) -> Aes256Gcm {
+        let file_key = Sha256::new()
+            .chain(FILE_KEY_DOMAIN)
+            .chain(self.0)
+            .chain(salt)
+            .finalize();
+        Aes256Gcm::new_from_slice(&file_key).expect("Key length is correct.")
+    }
+}
+
+// An encrypted file is `FILE_MAGIC || salt || segment*`, where each segment is
+// `u32_be(last_flag | len) || AES-256-GCM(plaintext)`. The plaintext is cut into segments of at
+// most SEGMENT_SIZE bytes, and the nonce of a segment is its index with the last flag, so
+// reordered, truncated or extended files fail the authentication.
+const FILE_MAGIC: &[u8] = b"APTOS-BACKUP-ENCRYPTED-V1\n";
+const FILE_KEY_DOMAIN: &[u8] = b"APTOS::BackupFileKey";
+const SALT_LEN: usize = 32;
+const HEADER_LEN: usize = FILE_MAGIC.len() + SALT_LEN;
+const SEGMENT_SIZE: usize = 64 * 1024;
+const TAG_LEN: usize = 16;
+const LAST_SEGMENT_FLAG: u32 = 1 << 31;
+// Each metadata line is encrypted on its own, so the storage is free to combine metadata files.
+const LINE_PREFIX: &str = "aptos-backup-encrypted-line-v1:";
+
+fn segment_nonce(index: u64, last: bool) -> [u8; 12] {
+    let mut nonce = [0u8; 12];
+    nonce[..8].copy_from_slice(&index.to_be_bytes());
+    nonce[11] = last as u8;
+    nonce
+}
+
+fn new_file_header() -> (Vec<u8>, [u8; SALT_LEN]) {
+    let salt: [u8; SALT_LEN] = random();
+    let mut header = FILE_MAGIC.to_vec();
+    header.extend_from_slice(&salt);
+    (header, salt)
+}
+
+fn parse_segment_header(bytes: &[u8]) -> io::Result<(usize, bool)> {
+    let header = u32::from_be_bytes(bytes.try_into().expect("Segment header is 4 bytes."));
+    let len = (header & !LAST_SEGMENT_FLAG) as usize;
+    if !(TAG_LEN..=SEGMENT_SIZE + TAG_LEN).contains(&len) {
+        return Err(invalid_data(format!("Invalid segment length {}.", len)));
+    }
+    Ok((len, header & LAST_SEGMENT_FLAG != 0))
+}
+
+fn invalid_data(msg: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.into())
+}
+
+struct SegmentSealer {
+    cipher: Aes256Gcm,
+    next_index: u64,
+}
+
+impl SegmentSealer {
+    fn seal(&mut self, plaintext: &[u8], last: bool) -> io::Result<Vec<u8>> {
+        let ciphertext = self
+            .cipher
+            .encrypt(
+                Nonce::from_slice(&segment_nonce(self.next_index, last)),
+                plaintext,
+            )
+            .map_err(|e| io::Error::other(format!("Encryption: {}", e)))?;
+        self.next_index += 1;
+
+        let mut header = ciphertext.len() as u32;
+        if last {
+            header |= LAST_SEGMENT_FLAG;
+        }
+        let mut segment = header.to_be_bytes().to_vec();
+        segment.extend(ciphertext);
+        Ok(segment)
+    }
+}
+
+struct SegmentOpener {
+    cipher: Aes256Gcm,
+    next_index: u64,
+}
+
+impl SegmentOpener {
+    fn new(key: &EncryptionKey, header: &[u8]) -> io::Result<Self> {
+        if !header.starts_with(FILE_MAGIC) {
+            return Err(invalid_data("Not an encrypted backup file."));
+        }
+        Ok(Self {
+            cipher: key.file_cipher(&header[FILE_MAGIC.len()..]),
+            next_index: 0,
+        })
+    }
+
+    fn open(&mut self, ciphertext: &[u8], last: bool) -> io::Result<Vec<u8>> {
+        let plaintext = self
+            .cipher
+            .decrypt(
+                Nonce::from_slice(&segment_nonce(self.next_index, last)),
+                ciphertext,
+            )
+            .map_err(|_| {
+                invalid_data(format!(
+                    "Failed to decrypt segment {}, wrong key or corrupted file.",
+                    self.next_index
+                ))
+            })?;
+        self.next_index += 1;
+        Ok(plaintext)
+    }
+}
+
+fn encrypt_bytes(key: &EncryptionKey, plaintext: &[u8]) -> io::Result<Vec<u8>> {
+    let (mut output, salt) = new_file_header();
+    let mut sealer = SegmentSealer {
+        cipher: key.file_cipher(&salt),
+        next_index: 0,
+    };
+    let mut segments = plaintext.chunks(SEGMENT_SIZE).peekable();
+    if segments.peek().is_none() {
+        output.extend(sealer.seal(&[], true)?);
+    }
+    while let Some(segment) = segments.next() {
+        output.extend(sealer.seal(segment, segments.peek().is_none())?);
+    }
+    Ok(output)
+}
+
+fn decrypt_bytes(key: &EncryptionKey, bytes: &[u8]) -> io::Result<Vec<u8>> {
+    let truncated = || invalid_data("Encrypted content truncated.");
+    if bytes.len() < HEADER_LEN {
+        return Err(truncated());
+    }
+    let mut opener = SegmentOpener::new(key, &bytes[..HEADER_LEN])?;
+    let mut rest = &bytes[HEADER_LEN..];
+    let mut plaintext = Vec::new();
+    loop {
+        if rest.len() < 4 {
+            return Err(truncated());
+        }
+        let (len, last) = parse_segment_header(&rest[..4])?;
+        rest = &rest[4..];
+        if rest.len() < len {
+            return Err(truncated());
+        }
+        plaintext.extend(opener.open(&rest[..len], last)?);
+        rest = &rest[len..];
+        if last {
+            break;
+        }
+    }
+    if !rest.is_empty() {
+        return Err(invalid_data("Unexpected data after the last segment."));
+    }
+    Ok(plaintext)
+}
+
+/// A BackupStorage wrapping another one, encrypting everything written to it and decrypting
+/// everything read from it, so the underlying storage only ever sees ciphertext.
+///
+/// Files are encrypted in segments as they are streamed, and metadata lines are encrypted one by
+/// one into hex encoded lines. Anything not encrypted with the same key fails to be read.
+pub struct EncryptedStorage {
+    inner: Arc<dyn BackupStorage>,
+    key: Arc<EncryptionKey>,
+}
+
+impl EncryptedStorage {
+    pub fn new(inner: Arc<dyn BackupStorage>, key: EncryptionKey) -> Self {
+        Self {
+            inner,
+            key: Arc::new(key),
+        }
+    }
+
+    fn encrypt_line(&self, line: &TextLine) -> Result<TextLine> {
+        let plaintext = line.as_ref().trim_end_matches('\n');
+        let ciphertext = encrypt_bytes(&self.key, plaintext.as_bytes())?;
+        TextLine::new(&format!("{}{}", LINE_PREFIX, hex::encode(ciphertext)))
+    }
+
+    fn decrypt_metadata_file(
+        &self,
+        content: &[u8],
+        file_handle: &FileHandleRef,
+    ) -> Result<Vec<u8>> {
+        let mut plaintext = Vec::with_capacity(content.len() / 2);
+        for line in std::str::from_utf8(content)?.lines() {
+            let ciphertext = line.strip_prefix(LINE_PREFIX).ok_or_else(|| {
+                format_err!("Unencrypted metadata line found in {}.", file_handle)
+            })?;
+            let line =
+                decrypt_bytes(&self.key, &hex::decode(ciphertext)?).err_notes(file_handle)?;
+            plaintext.extend(line);
+            plaintext.push(b'\n');
+        }
+        Ok(plaintext)
+    }
+}
+
+#[async_trait]
+impl BackupStorage for EncryptedStorage {
+    async fn create_backup(&self, name: &ShellSafeName) -> Result<BackupHandle> {
+        self.inner.create_backup(name).await
+    }
+
+    async fn create_for_write(
+        &self,
+        backup_handle: &BackupHandleRef,
+        name: &ShellSafeName,
+    ) -> Result<(FileHandle, Box<dyn AsyncWrite + Send + Unpin>)> {
+        let (file_handle, file) = self.inner.create_for_write(backup_handle, name).await?;
+        Ok((
+            file_handle,
+            Box::new(EncryptingWriter::new(file, &self.key)),
+        ))
+    }
+
+    async fn open_for_read(
+        &self,
+        file_handle: &FileHandleRef,
+    ) -> Result<Box<dyn AsyncRead + Send + Unpin>> {
+        let mut file = self.inner.open_for_read(file_handle).await?;
+
+        // Peek at the beginning of the file to tell a data file from a metadata file.
+        let mut prefix = vec![0u8; FILE_MAGIC.len().max(LINE_PREFIX.len())];
+        let mut filled = 0;
+        while filled < prefix.len() {
+            let read = file
+                .read(&mut prefix[filled..])
+                .await
+                .err_notes(file_handle)?;
+            if read == 0 {
+                break;
+            }
+            filled += read;
+        }
+        prefix.truncate(filled);
+
+        if prefix.is_empty() {
+            bail!("File {} is empty.", file_handle)
+        } else if prefix.starts_with(FILE_MAGIC) {
+            let file = Cursor::new(prefix).chain(file);
+            Ok(Box::new(DecryptingReader::new(file, self.key.clone())))
+        } else if prefix.starts_with(LINE_PREFIX.as_bytes()) {
+            let mut content = prefix;
+            file.read_to_end(&mut content)
+                .await
+                .err_notes(file_handle)?;
+            let plaintext = self.decrypt_metadata_file(&content, file_handle)?;
+            Ok(Box::new(Cursor::new(plaintext)))
+        } else {
+            bail!("File {} is not encrypted.", file_handle)
+        }
+    }
+
+    async fn list_metadata_files(&self) -> Result<Vec<FileHandle>> {
+        self.inner.list_metadata_files().await
+    }
+
+    async fn backup_metadata_file(&self, file_handle: &FileHandleRef) -> Result<()> {
+        self.inner.backup_metadata_file(file_handle).await
+    }
+
+    async fn save_metadata_lines(
+        &self,
+        name: &ShellSafeName,
+        lines: &[TextLine],
+    ) -> Result<FileHandle> {
+        let lines = lines
+            .iter()
+            .map(|line| self.encrypt_line(line))
+            .collect::<Result<Vec<_>>>()?;
+        self.inner.save_metadata_lines(name, &lines).await
+    }
+}
+
+/// Buffers the plaintext into segments, and writes out each segment encrypted once it's full.
+/// The last segment is sealed on shutdown.
+struct EncryptingWriter {
+    inner: Box<dyn AsyncWrite + Send + Unpin>,
+    sealer: SegmentSealer,
+    plaintext: Vec<u8>,
+    output: Vec<u8>,
+    output_pos: usize,
+    sealed_last: bool,
+}
+
+impl EncryptingWriter {
+    fn new(inner: Box<dyn AsyncWrite + Send + Unpin>, key: &EncryptionKey) -> Self {
+        let (header, salt) = new_file_header();
+        Self {
+            inner,
+            sealer: SegmentSealer {
+                cipher: key.file_cipher(&salt),
+                next_index: 0,
+            },
+            plaintext: Vec::with_capacity(SEGMENT_SIZE),
+            output: header,
+            output_pos: 0,
+            sealed_last: false,
+        }
+    }
+
+    fn poll_write_output(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        while self.output_pos < self.output.len() {
+            let written =
+                ready!(Pin::new(&mut self.inner).poll_write(cx, &self.output[self.output_pos..]))?;
+            if written == 0 {
+                return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+            }
+            self.output_pos += written;
+        }
+        self.output.clear();
+        self.output_pos = 0;
+        Poll::Ready(Ok(()))
+    }
+
+    fn seal_plaintext(&mut self, last: bool) -> io::Result<()> {
+        self.output = self.sealer.seal(&self.plaintext, last)?;
+        self.output_pos = 0;
+        self.plaintext.clear();
+        Ok(())
+    }
+}
+
+impl AsyncWrite for EncryptingWriter {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        assert!(!this.sealed_last, "Already shut down.");
+        loop {
+            ready!(this.poll_write_output(cx))?;
+            if this.plaintext.len() < SEGMENT_SIZE {
+                break;
+            }
+            this.seal_plaintext(false)?;
+        }
+        let len = buf.len().min(SEGMENT_SIZE - this.plaintext.len());
+        this.plaintext.extend_from_slice(&buf[..len]);
+        Poll::Ready(Ok(len))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        ready!(this.poll_write_output(cx))?;
+        Pin::new(&mut this.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        ready!(this.poll_write_output(cx))?;
+        if !this.sealed_last {
+            this.seal_plaintext(true)?;
+            this.sealed_last = true;
+            ready!(this.poll_write_output(cx))?;
+        }
+        Pin::new(&mut this.inner).poll_shutdown(cx)
+    }
+}
+
+enum ReadState {
+    Header,
+    SegmentHeader,
+    Segment { len: usize, last: bool },
+    Trailer,
+    Done,
+}
+
+/// Reads and authenticates the encrypted file segment by segment. The read fails if the file is
+/// modified, truncated or extended.
+struct DecryptingReader<R> {
+    inner: R,
+    key: Arc<EncryptionKey>,
+    opener: Option<SegmentOpener>,
+    state: ReadState,
+    input: Vec<u8>,
+    input_filled: usize,
+    plaintext: Vec<u8>,
+    plaintext_pos: usize,
+}
+
+impl<R: AsyncRead + Unpin> DecryptingReader<R> {
+    fn new(inner: R, key: Arc<EncryptionKey>) -> Self {
+        Self {
+            inner,
+            key,
+            opener: None,
+            state: ReadState::Header,
+            input: Vec::new(),
+            input_filled: 0,
+            plaintext: Vec::new(),
+            plaintext_pos: 0,
+        }
+    }
+
+    /// Reads until `len` bytes are in `self.input`. Returns false if the file ends before that.
+    fn poll_fill(&mut self, cx: &mut Context<'_>, len: usize) -> Poll<io::Result<bool>> {
+        self.input.resize(len, 0);
+        while self.input_filled < len {
+            let mut buf = ReadBuf::new(&mut self.input[self.input_filled..]);
+            ready!(Pin::new(&mut self.inner).poll_read(cx, &mut buf))?;
+            let read = buf.filled().len();
+            if read == 0 {
+                return Poll::Ready(Ok(false));
+            }
+            self.input_filled += read;
+        }
+        Poll::Ready(Ok(true))
+    }
+
+    fn take_input(&mut self) -> Vec<u8> {
+        self.input_filled = 0;
+        std::mem::take(&mut self.input)
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for DecryptingReader<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        if buf.remaining() == 0 {
+            return Poll::Ready(Ok(()));
+        }
+        let truncated = || Poll::Ready(Err(invalid_data("Encrypted file truncated.")));
+        loop {
+            if this.plaintext_pos < this.plaintext.len() {
+                let len = buf
+                    .remaining()
+                    .min(this.plaintext.len() - this.plaintext_pos);
+                buf.put_slice(&this.plaintext[this.plaintext_pos..this.plaintext_pos + len]);
+                this.plaintext_pos += len;
+                return Poll::Ready(Ok(()));
+            }
+            match this.state {
+                ReadState::Header => {
+                    if !ready!(this.poll_fill(cx, HEADER_LEN))? {
+                        return truncated();
+                    }
+                    let header = this.take_input();
+                    this.opener = Some(SegmentOpener::new(&this.key, &header)?);
+                    this.state = ReadState::SegmentHeader;
+                },
+                ReadState::SegmentHeader => {
+                    if !ready!(this.poll_fill(cx, 4))? {
+                        return truncated();
+                    }
+                    let (len, last) = parse_segment_header(&this.take_input())?;
+                    this.state = ReadState::Segment { len, last };
+                },
+                ReadState::Segment { len, last } => {
+                    if !ready!(this.poll_fill(cx, len))? {
+                        return truncated();
+                    }
+                    let ciphertext = this.take_input();
+                    this.plaintext = this
+                        .opener
+                        .as_mut()
+                        .expect("Header must have been read.")
+                        .open(&ciphertext, last)?;
+                    this.plaintext_pos = 0;
+                    this.state = if last {
+                        ReadState::Trailer
+                    } else {
+                        ReadState::SegmentHeader
+                    };
+                },
+                ReadState::Trailer => {
+                    if ready!(this.poll_fill(cx, 1))? {
+                        return Poll::Ready(Err(invalid_data(
+                            "Unexpected data after the last segment.",
+                        )));
+                    }
+                    this.take_input();
+                    this.state = ReadState::Done;
+                },
+                ReadState::Done => return Poll::Ready(Ok(())),
+            }
+        }
+    }
+}
@@ -0,0 +1,169 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use crate::storage::{
+    local_fs::LocalFs,
+    test_util::{
+        arb_backups, arb_metadata_files, test_save_and_list_metadata_files_impl,
+        test_write_and_read_impl,
+    },
+};
+use aptos_temppath::TempPath;
+use proptest::prelude::*;
+use std::str::FromStr;
+use tokio::{io::AsyncWriteExt, runtime::Runtime};
+
+fn new_storage(dir: &TempPath) -> (Arc<dyn BackupStorage>, EncryptedStorage) {
+    dir.create_as_dir().unwrap();
+    let inner: Arc<dyn BackupStorage> = Arc::new(LocalFs::new(dir.path().to_path_buf()));
+    let storage = EncryptedStorage::new(inner.clone(), EncryptionKey::new(random()));
+    (inner, storage)
+}
+
+async fn write_file(storage: &dyn BackupStorage, name: &str, content: &[u8]) -> FileHandle {
+    let backup_handle = storage
+        .create_backup(&ShellSafeName::from_str("backup").unwrap())
+        .await
+        .unwrap();
+    let (file_handle, mut file) = storage
+        .create_for_write(&backup_handle, &ShellSafeName::from_str(name).unwrap())
+        .await
+        .unwrap();
+    file.write_all(content).await.unwrap();
+    file.shutdown().await.unwrap();
+    file_handle
+}
+
+async fn read_file(storage: &dyn BackupStorage, file_handle: &FileHandleRef) -> Result<Vec<u8>> {
+    let mut content = Vec::new();
+    storage
+        .open_for_read(file_handle)
+        .await?
+        .read_to_end(&mut content)
+        .await?;
+    Ok(content)
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(10))]
+
+    #[test]
+    fn test_write_and_read(
+        backups in arb_backups()
+    ) {
+        let tmpdir = TempPath::new();
+        let (_inner, store) = new_storage(&tmpdir);
+
+        let rt = Runtime::new().unwrap();
+        rt.block_on(test_write_and_read_impl(Box::new(store), backups));
+    }
+
+    #[test]
+    fn test_save_list_metadata_files(
+        input in arb_metadata_files(),
+    ) {
+        let tmpdir = TempPath::new();
+        let (_inner, store) = new_storage(&tmpdir);
+
+        let rt = Runtime::new().unwrap();
+        rt.block_on(test_save_and_list_metadata_files_impl(Box::new(store), input));
+    }
+
+    #[test]
+    fn test_encrypt_decrypt_bytes(
+        plaintext in proptest::collection::vec(any::<u8>(), 0..(SEGMENT_SIZE * 3)),
+    ) {
+        let key = EncryptionKey::new(random());
+        let ciphertext = encrypt_bytes(&key, &plaintext).unwrap();
+        prop_assert_eq!(decrypt_bytes(&key, &ciphertext).unwrap(), plaintext);
+    }
+}
+
+#[tokio::test]
+async fn test_multi_segment_file() {
+    let tmpdir = TempPath::new();
+    let (inner, storage) = new_storage(&tmpdir);
+    for len in [0, 1, SEGMENT_SIZE, SEGMENT_SIZE + 1, SEGMENT_SIZE * 5 / 2] {
+        let content: Vec<u8> = (0..len).map(|i| i as u8).collect();
+        let file_handle = write_file(&storage, &format!("file_{}", len), &content).await;
+
+        assert_eq!(read_file(&storage, &file_handle).await.unwrap(), content);
+        let ciphertext = read_file(inner.as_ref(), &file_handle).await.unwrap();
+        assert!(ciphertext.starts_with(FILE_MAGIC));
+        assert_eq!(decrypt_bytes(&storage.key, &ciphertext).unwrap(), content);
+    }
+}
+
+#[tokio::test]
+async fn test_tampered_file() {
+    let tmpdir = TempPath::new();
+    let (inner, storage) = new_storage(&tmpdir);
+    let content: Vec<u8> = (0..SEGMENT_SIZE * 2).map(|i| i as u8).collect();
+    let file_handle = write_file(&storage, "file", &content).await;
+    let ciphertext = read_file(inner.as_ref(), &file_handle).await.unwrap();
+    let path = tmpdir.path().join(&file_handle);
+
+    let mut modified = ciphertext.clone();
+    *modified.last_mut().unwrap() ^= 1;
+    std::fs::write(&path, &modified).unwrap();
+    assert!(read_file(&storage, &file_handle).await.is_err());
+
+    // Drops the last segment, ending the file on a full segment.
+    std::fs::write(
+        &path,
+        &ciphertext[..HEADER_LEN + 4 + SEGMENT_SIZE + TAG_LEN],
+    )
+    .unwrap();
+    assert!(read_file(&storage, &file_handle).await.is_err());
+
+    let mut extended = ciphertext.clone();
+    extended.push(0);
+    std::fs::write(&path, &extended).unwrap();
+    assert!(read_file(&storage, &file_handle).await.is_err());
+
+    std::fs::write(&path, &ciphertext).unwrap();
+    assert_eq!(read_file(&storage, &file_handle).await.unwrap(), content);
+}
+
+#[tokio::test]
+async fn test_wrong_key_or_plaintext() {
+    let tmpdir = TempPath::new();
+    let (inner, storage) = new_storage(&tmpdir);
+    let file_handle = write_file(&storage, "file", b"content").await;
+    let metadata_handle = storage
+        .save_metadata_line(
+            &ShellSafeName::from_str("metadata").unwrap(),
+            &TextLine::new("line").unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let other_storage = EncryptedStorage::new(inner.clone(), EncryptionKey::new(random()));
+    assert!(read_file(&other_storage, &file_handle).await.is_err());
+    assert!(read_file(&other_storage, &metadata_handle).await.is_err());
+
+    let plaintext_handle = write_file(inner.as_ref(), "plaintext_file", b"plaintext content").await;
+    assert!(read_file(&storage, &plaintext_handle).await.is_err());
+}
+
+#[tokio::test]
+async fn test_empty_file() {
+    let tmpdir = TempPath::new();
+    let (inner, storage) = new_storage(&tmpdir);
+    let empty_handle = write_file(inner.as_ref(), "empty_file", b"").await;
+    let err = read_file(&storage, &empty_handle).await.unwrap_err();
+    assert!(err.to_string().contains("is empty"));
+
+    // An encrypted file with empty content still carries the header and reads back fine.
+    let file_handle = write_file(&storage, "file", b"").await;
+    assert!(read_file(&storage, &file_handle).await.unwrap().is_empty());
+}
+
+#[test]
+fn test_key_from_hex() {
+    let key = EncryptionKey::from_hex(&"ab".repeat(EncryptionKey::LENGTH)).unwrap();
+    assert_eq!(key.0, [0xAB; EncryptionKey::LENGTH]);
+    assert!(EncryptionKey::from_hex(&"ab".repeat(EncryptionKey::LENGTH - 1)).is_err());
+    assert!(EncryptionKey::from_hex("not hex").is_err());
+}
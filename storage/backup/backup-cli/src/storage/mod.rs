@@ -3,7 +3,9 @@
 // SPDX-License-Identifier: Apache-2.0
 
 pub mod command_adapter;
+pub mod encrypted;
 pub mod local_fs;
+pub mod object_storage;
 
 #[cfg(test)]
 mod test_util;
@@ -12,7 +14,9 @@ mod tests;
 
 use crate::storage::{
     command_adapter::{CommandAdapter, CommandAdapterOpt},
+    encrypted::{EncryptedStorage, EncryptionOpt},
     local_fs::{LocalFs, LocalFsOpt},
+    object_storage::{AzureBlobOpt, ObjectStorage, S3Opt},
 };
 use anyhow::{ensure, Result};
 use async_trait::async_trait;
@@ -198,6 +202,18 @@ pub enum StorageOpt {
     https://github.com/aptos-labs/aptos-core/tree/main/storage/backup/backup-cli/src/storage/command_adapter/sample_configs/"
     )]
     CommandAdapter(CommandAdapterOpt),
+    #[clap(
+        about = "Select the native S3 backup storage type, which talks to S3 or an S3 compatible \
+    store directly, with parallel multipart uploads and checksum verification. See a sample config \
+    here: https://github.com/aptos-labs/aptos-core/tree/main/storage/backup/backup-cli/src/storage/object_storage/sample_configs/"
+    )]
+    S3(S3Opt),
+    #[clap(
+        about = "Select the native Azure Blob backup storage type, which talks to Azure Blob Storage \
+    directly, with parallel block uploads and checksum verification. See a sample config here: \
+    https://github.com/aptos-labs/aptos-core/tree/main/storage/backup/backup-cli/src/storage/object_storage/sample_configs/"
+    )]
+    AzureBlob(AzureBlobOpt),
 }
 
 impl StorageOpt {
@@ -205,6 +221,10 @@ impl StorageOpt {
         Ok(match self {
             StorageOpt::LocalFs(opt) => Arc::new(LocalFs::new_with_opt(opt)),
             StorageOpt::CommandAdapter(opt) => Arc::new(CommandAdapter::new_with_opt(opt).await?),
+            StorageOpt::S3(opt) => Arc::new(ObjectStorage::new_with_s3_opt(opt).await?),
+            StorageOpt::AzureBlob(opt) => {
+                Arc::new(ObjectStorage::new_with_azure_blob_opt(opt).await?)
+            },
         })
     }
 }
@@ -213,7 +233,7 @@ impl StorageOpt {
 #[clap(group(
     ArgGroup::new("storage")
     .required(true)
-    .args(&["local_fs_dir", "command_adapter_config"]),
+    .args(&["local_fs_dir", "command_adapter_config", "s3_config", "azure_blob_config"]),
 ))]
 pub struct DBToolStorageOpt {
     #[clap(
@@ -229,14 +249,39 @@ pub struct DBToolStorageOpt {
     https://github.com/aptos-labs/aptos-networks/tree/main/testnet/backups "
     )]
     command_adapter_config: Option<CommandAdapterOpt>,
+    #[clap(
+        long,
+        help = "Select the native S3 backup storage type, which talks to S3 or an S3 compatible \
+    store directly. See a sample config here: \
+    https://github.com/aptos-labs/aptos-core/tree/main/storage/backup/backup-cli/src/storage/object_storage/sample_configs/"
+    )]
+    s3_config: Option<S3Opt>,
+    #[clap(
+        long,
+        help = "Select the native Azure Blob backup storage type, which talks to Azure Blob Storage \
+    directly. See a sample config here: \
+    https://github.com/aptos-labs/aptos-core/tree/main/storage/backup/backup-cli/src/storage/object_storage/sample_configs/"
+    )]
+    azure_blob_config: Option<AzureBlobOpt>,
+    #[clap(flatten)]
+    encryption: EncryptionOpt,
 }
 
 impl DBToolStorageOpt {
     pub async fn init_storage(self) -> Result<Arc<dyn BackupStorage>> {
-        Ok(if self.local_fs_dir.is_some() {
+        let storage: Arc<dyn BackupStorage> = if self.local_fs_dir.is_some() {
             Arc::new(LocalFs::new_with_opt(self.local_fs_dir.unwrap()))
+        } else if self.s3_config.is_some() {
+            Arc::new(ObjectStorage::new_with_s3_opt(self.s3_config.unwrap()).await?)
+        } else if self.azure_blob_config.is_some() {
+            Arc::new(ObjectStorage::new_with_azure_blob_opt(self.azure_blob_config.unwrap()).await?)
         } else {
             Arc::new(CommandAdapter::new_with_opt(self.command_adapter_config.unwrap()).await?)
+        };
+
+        Ok(match self.encryption.load_key().await? {
+            Some(key) => Arc::new(EncryptedStorage::new(storage, key)),
+            None => storage,
         })
     }
 }
@@ -0,0 +1,149 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{storage::object_storage::BackupObjectStore, utils::error_notes::ErrorNotes};
+use anyhow::Result;
+use object_store::{
+    aws::{AmazonS3Builder, Checksum},
+    azure::MicrosoftAzureBuilder,
+    RetryConfig,
+};
+use serde::{de::DeserializeOwned, Deserialize};
+use std::{path::Path, sync::Arc, time::Duration};
+use tokio::io::AsyncReadExt;
+
+/// Controls how files are transferred to and from the object store.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TransferConfig {
+    /// Files larger than this are uploaded in parts of this size with a multipart upload.
+    #[serde(default = "TransferConfig::default_part_size_bytes")]
+    pub part_size_bytes: usize,
+    /// Max number of parts of a single file being uploaded concurrently.
+    #[serde(default = "TransferConfig::default_max_concurrency")]
+    pub max_concurrency: usize,
+    /// Every request, including the upload of each part, is retried independently, so a
+    /// transient failure doesn't restart the upload of the whole file.
+    #[serde(default = "TransferConfig::default_max_retries")]
+    pub max_retries: usize,
+    #[serde(default = "TransferConfig::default_retry_timeout_secs")]
+    pub retry_timeout_secs: u64,
+}
+
+impl TransferConfig {
+    // S3 requires all parts but the last to be at least 5 MiB.
+    pub const MIN_PART_SIZE_BYTES: usize = 5 * 1024 * 1024;
+
+    fn default_part_size_bytes() -> usize {
+        16 * 1024 * 1024
+    }
+
+    fn default_max_concurrency() -> usize {
+        8
+    }
+
+    fn default_max_retries() -> usize {
+        10
+    }
+
+    fn default_retry_timeout_secs() -> u64 {
+        180
+    }
+
+    fn retry_config(&self) -> RetryConfig {
+        RetryConfig {
+            max_retries: self.max_retries,
+            retry_timeout: Duration::from_secs(self.retry_timeout_secs),
+            ..Default::default()
+        }
+    }
+}
+
+impl Default for TransferConfig {
+    fn default() -> Self {
+        Self {
+            part_size_bytes: Self::default_part_size_bytes(),
+            max_concurrency: Self::default_max_concurrency(),
+            max_retries: Self::default_max_retries(),
+            retry_timeout_secs: Self::default_retry_timeout_secs(),
+        }
+    }
+}
+
+/// Config of an S3 or S3 compatible bucket. Credentials are read from the standard AWS
+/// environment variables, e.g. `AWS_ACCESS_KEY_ID` and `AWS_SECRET_ACCESS_KEY`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct S3Config {
+    pub bucket: String,
+    /// All files are stored under this prefix, e.g. "backup1/e1".
+    #[serde(default)]
+    pub prefix: String,
+    pub region: Option<String>,
+    /// Endpoint of an S3 compatible store, e.g. "http://127.0.0.1:9000" for a local MinIO.
+    pub endpoint: Option<String>,
+    #[serde(default)]
+    pub allow_http: bool,
+    #[serde(default)]
+    pub transfer: TransferConfig,
+}
+
+impl S3Config {
+    pub fn build(&self) -> Result<Arc<dyn BackupObjectStore>> {
+        let mut builder = AmazonS3Builder::from_env()
+            .with_bucket_name(&self.bucket)
+            .with_allow_http(self.allow_http)
+            .with_retry(self.transfer.retry_config())
+            // Every request carries a SHA-256 checksum of its body, verified by the server.
+            .with_checksum_algorithm(Checksum::SHA256);
+        if let Some(region) = &self.region {
+            builder = builder.with_region(region);
+        }
+        if let Some(endpoint) = &self.endpoint {
+            builder = builder.with_endpoint(endpoint);
+        }
+        Ok(Arc::new(builder.build()?))
+    }
+}
+
+/// Config of an Azure Blob Storage container. Credentials are read from the standard Azure
+/// environment variables, e.g. `AZURE_STORAGE_ACCOUNT_KEY` or `AZURE_STORAGE_SAS_KEY`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AzureBlobConfig {
+    pub account: String,
+    pub container: String,
+    /// All files are stored under this prefix, e.g. "backup1/e1".
+    #[serde(default)]
+    pub prefix: String,
+    /// Custom endpoint, e.g. of a private link.
+    pub endpoint: Option<String>,
+    /// Use the local Azurite emulator instead, mainly for tests.
+    #[serde(default)]
+    pub use_emulator: bool,
+    #[serde(default)]
+    pub transfer: TransferConfig,
+}
+
+impl AzureBlobConfig {
+    pub fn build(&self) -> Result<Arc<dyn BackupObjectStore>> {
+        let mut builder = MicrosoftAzureBuilder::from_env()
+            .with_account(&self.account)
+            .with_container_name(&self.container)
+            .with_use_emulator(self.use_emulator)
+            .with_retry(self.transfer.retry_config());
+        if let Some(endpoint) = &self.endpoint {
+            builder = builder.with_endpoint(endpoint.clone());
+        }
+        Ok(Arc::new(builder.build()?))
+    }
+}
+
+pub async fn load_from_file<T: DeserializeOwned>(path: &Path) -> Result<T> {
+    let path_str = path.to_str().unwrap_or_default();
+    let mut file = tokio::fs::File::open(path).await.err_notes(path_str)?;
+    let mut content = Vec::new();
+    file.read_to_end(&mut content).await.err_notes(path_str)?;
+
+    Ok(serde_yaml::from_slice(&content)?)
+}
@@ -0,0 +1,660 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+pub mod config;
+
+#[cfg(test)]
+mod tests;
+
+use crate::{
+    storage::{
+        object_storage::config::{load_from_file, AzureBlobConfig, S3Config, TransferConfig},
+        BackupHandle, BackupHandleRef, BackupStorage, FileHandle, FileHandleRef, ShellSafeName,
+        TextLine,
+    },
+    utils::error_notes::ErrorNotes,
+};
+use anyhow::{ensure, format_err, Result};
+use async_trait::async_trait;
+use bytes::Bytes;
+use clap::Parser;
+use futures::{stream::FuturesOrdered, Future, StreamExt, TryStreamExt};
+use object_store::{
+    multipart::{MultipartStore, PartId},
+    path::Path,
+    MultipartId, ObjectStore,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    io,
+    path::PathBuf,
+    pin::Pin,
+    str::FromStr,
+    sync::Arc,
+    task::{ready, Context, Poll},
+};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, DuplexStream, ReadBuf},
+    sync::oneshot,
+    task::JoinHandle,
+};
+use tokio_util::compat::FuturesAsyncReadCompatExt;
+
+/// An object store that also exposes the multipart upload API, which writes use to resume
+/// interrupted uploads.
+pub trait BackupObjectStore: ObjectStore + MultipartStore {}
+
+impl<T: ObjectStore + MultipartStore> BackupObjectStore for T {}
+
+#[derive(Parser, Clone, Debug, Serialize, Deserialize)]
+pub struct S3Opt {
+    #[clap(long = "config", help = "Config file for the S3 backup store.")]
+    config: PathBuf,
+}
+
+impl FromStr for S3Opt {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(S3Opt {
+            config: PathBuf::from(s),
+        })
+    }
+}
+
+#[derive(Parser, Clone, Debug, Serialize, Deserialize)]
+pub struct AzureBlobOpt {
+    #[clap(long = "config", help = "Config file for the Azure Blob backup store.")]
+    config: PathBuf,
+}
+
+impl FromStr for AzureBlobOpt {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(AzureBlobOpt {
+            config: PathBuf::from(s),
+        })
+    }
+}
+
+/// A BackupStorage that talks to an object store (S3 or Azure Blob) through its API directly.
+///
+/// Large files are uploaded with multipart uploads, with parts uploaded in parallel and retried
+/// individually. The progress of every multipart upload is saved under `uploads/`, so writing
+/// the same file again after an interrupted upload only uploads the parts that are missing or
+/// differ. The SHA-256 of every file is saved next to it under `checksums/`, and verified when
+/// the file is read back to the end.
+pub struct ObjectStorage {
+    store: Arc<dyn BackupObjectStore>,
+    prefix: Path,
+    transfer_config: TransferConfig,
+}
+
+impl ObjectStorage {
+    const CHECKSUMS_DIR: &'static str = "checksums";
+    const CHECKSUM_SUFFIX: &'static str = ".sha256";
+    const METADATA_BACKUP_DIR: &'static str = "metadata_backup";
+    const METADATA_DIR: &'static str = "metadata";
+    const UPLOADS_DIR: &'static str = "uploads";
+    const UPLOAD_STATE_SUFFIX: &'static str = ".upload.json";
+
+    pub fn new(
+        store: Arc<dyn BackupObjectStore>,
+        prefix: &str,
+        transfer_config: TransferConfig,
+    ) -> Result<Self> {
+        ensure!(
+            transfer_config.part_size_bytes >= TransferConfig::MIN_PART_SIZE_BYTES,
+            "Part size has to be at least {} bytes.",
+            TransferConfig::MIN_PART_SIZE_BYTES,
+        );
+        ensure!(
+            transfer_config.max_concurrency >= 1,
+            "Max concurrency has to be at least 1.",
+        );
+        Ok(Self {
+            store,
+            prefix: Path::from(prefix),
+            transfer_config,
+        })
+    }
+
+    pub async fn new_with_s3_opt(opt: S3Opt) -> Result<Self> {
+        let config: S3Config = load_from_file(&opt.config).await?;
+        Self::new(config.build()?, &config.prefix, config.transfer.clone())
+    }
+
+    pub async fn new_with_azure_blob_opt(opt: AzureBlobOpt) -> Result<Self> {
+        let config: AzureBlobConfig = load_from_file(&opt.config).await?;
+        Self::new(config.build()?, &config.prefix, config.transfer.clone())
+    }
+
+    /// Path of the object holding the file, file handles are relative to the prefix.
+    fn object_path(&self, file_handle: &FileHandleRef) -> Path {
+        file_handle
+            .split('/')
+            .fold(self.prefix.clone(), |path, part| path.child(part))
+    }
+
+    fn checksum_path(&self, file_handle: &FileHandleRef) -> Path {
+        self.object_path(&format!(
+            "{}/{}{}",
+            Self::CHECKSUMS_DIR,
+            file_handle,
+            Self::CHECKSUM_SUFFIX
+        ))
+    }
+
+    fn upload_state_path(&self, file_handle: &FileHandleRef) -> Path {
+        self.object_path(&format!(
+            "{}/{}{}",
+            Self::UPLOADS_DIR,
+            file_handle,
+            Self::UPLOAD_STATE_SUFFIX
+        ))
+    }
+
+    async fn put_with_checksum(&self, file_handle: &FileHandleRef, content: Bytes) -> Result<()> {
+        let checksum = hex::encode(Sha256::digest(&content));
+        self.store
+            .put(&self.object_path(file_handle), content.into())
+            .await
+            .err_notes(file_handle)?;
+        self.store
+            .put(&self.checksum_path(file_handle), checksum.into())
+            .await
+            .err_notes(file_handle)?;
+        Ok(())
+    }
+
+    /// Returns None if the file was written without a checksum, e.g. by the command adapter.
+    async fn get_checksum(&self, file_handle: &FileHandleRef) -> Result<Option<String>> {
+        match self.store.get(&self.checksum_path(file_handle)).await {
+            Ok(result) => {
+                let bytes = result.bytes().await.err_notes(file_handle)?;
+                Ok(Some(String::from_utf8(bytes.to_vec())?))
+            },
+            Err(object_store::Error::NotFound { .. }) => Ok(None),
+            Err(e) => Err(e).err_notes(file_handle)?,
+        }
+    }
+
+    async fn rename_if_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        match self.store.rename(from, to).await {
+            Ok(()) | Err(object_store::Error::NotFound { .. }) => Ok(()),
+            Err(e) => Err(e).err_notes(from)?,
+        }
+    }
+}
+
+#[async_trait]
+impl BackupStorage for ObjectStorage {
+    async fn create_backup(&self, name: &ShellSafeName) -> Result<BackupHandle> {
+        // There are no directories in object stores, nothing to create.
+        Ok(name.to_string())
+    }
+
+    async fn create_for_write(
+        &self,
+        backup_handle: &BackupHandleRef,
+        name: &ShellSafeName,
+    ) -> Result<(FileHandle, Box<dyn AsyncWrite + Send + Unpin>)> {
+        let file_handle = format!("{}/{}", backup_handle, name.as_ref());
+        let (writer, reader) = tokio::io::duplex(WRITE_BUFFER_BYTES);
+        let (finish_tx, finish_rx) = oneshot::channel();
+        let upload = Upload {
+            store: self.store.clone(),
+            path: self.object_path(&file_handle),
+            checksum_path: self.checksum_path(&file_handle),
+            state_path: self.upload_state_path(&file_handle),
+            transfer_config: self.transfer_config.clone(),
+        };
+        let upload = tokio::spawn(async move { upload.run(reader, finish_rx).await });
+        Ok((
+            file_handle,
+            Box::new(UploadWriter {
+                inner: writer,
+                finish_tx: Some(finish_tx),
+                upload: Some(upload),
+            }),
+        ))
+    }
+
+    async fn open_for_read(
+        &self,
+        file_handle: &FileHandleRef,
+    ) -> Result<Box<dyn AsyncRead + Send + Unpin>> {
+        let expected_checksum = self.get_checksum(file_handle).await?;
+        let reader = self
+            .store
+            .get(&self.object_path(file_handle))
+            .await
+            .err_notes(file_handle)?
+            .into_stream()
+            .map_err(io::Error::from)
+            .into_async_read()
+            .compat();
+        Ok(Box::new(ChecksumReader::new(
+            reader,
+            expected_checksum,
+            file_handle.to_string(),
+        )))
+    }
+
+    async fn list_metadata_files(&self) -> Result<Vec<FileHandle>> {
+        let metadata_dir = self.prefix.child(Self::METADATA_DIR);
+        let objects: Vec<_> = self
+            .store
+            .list(Some(&metadata_dir))
+            .try_collect()
+            .await
+            .err_notes(&metadata_dir)?;
+        objects
+            .into_iter()
+            .map(|object| {
+                let name = object
+                    .location
+                    .filename()
+                    .ok_or_else(|| format_err!("Unexpected object {}", object.location))?;
+                Ok(format!("{}/{}", Self::METADATA_DIR, name))
+            })
+            .collect()
+    }
+
+    /// file_handle are expected to be the return results from list_metadata_files
+    /// file_handle is a path with `metadata` in the path, Ex: metadata/epoch_ending_1.meta
+    async fn backup_metadata_file(&self, file_handle: &FileHandleRef) -> Result<()> {
+        let name = file_handle
+            .strip_prefix(&format!("{}/", Self::METADATA_DIR))
+            .ok_or_else(|| format_err!("Not a metadata file: {}", file_handle))?;
+        ensure!(!name.contains('/'), "Not a metadata file: {}", file_handle);
+        let backup_file_handle = format!("{}/{}", Self::METADATA_BACKUP_DIR, name);
+
+        self.store
+            .rename(
+                &self.object_path(file_handle),
+                &self.object_path(&backup_file_handle),
+            )
+            .await
+            .err_notes(file_handle)?;
+        self.rename_if_exists(
+            &self.checksum_path(file_handle),
+            &self.checksum_path(&backup_file_handle),
+        )
+        .await
+    }
+
+    async fn save_metadata_lines(
+        &self,
+        name: &ShellSafeName,
+        lines: &[TextLine],
+    ) -> Result<FileHandle> {
+        let content = lines
+            .iter()
+            .map(|e| e.as_ref())
+            .collect::<Vec<&str>>()
+            .join("");
+        let file_handle = format!("{}/{}", Self::METADATA_DIR, name.as_ref());
+        self.put_with_checksum(&file_handle, content.into()).await?;
+        Ok(file_handle)
+    }
+}
+
+/// Size of the buffer between a writer and the task uploading what's written.
+const WRITE_BUFFER_BYTES: usize = 1024 * 1024;
+
+/// Progress of a multipart upload, saved after every uploaded part so that an interrupted
+/// upload can be resumed by writing the same file again.
+#[derive(Debug, Deserialize, Serialize)]
+struct UploadState {
+    upload_id: MultipartId,
+    part_size_bytes: usize,
+    /// Parts uploaded so far, in order.
+    parts: Vec<UploadedPart>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct UploadedPart {
+    sha256: String,
+    content_id: String,
+}
+
+/// Uploads everything written to an `UploadWriter`, and the checksum of it once the writer is
+/// shut down.
+struct Upload {
+    store: Arc<dyn BackupObjectStore>,
+    path: Path,
+    checksum_path: Path,
+    state_path: Path,
+    transfer_config: TransferConfig,
+}
+
+impl Upload {
+    async fn run(self, mut reader: DuplexStream, finish_rx: oneshot::Receiver<()>) -> Result<()> {
+        let part_size = self.transfer_config.part_size_bytes;
+        let first_part = read_part(&mut reader, part_size).await?;
+        let checksum = if first_part.len() < part_size {
+            // Fits in a single request, nothing to resume.
+            finish_rx
+                .await
+                .map_err(|_| Self::not_finished(&self.path))?;
+            let checksum = hex::encode(Sha256::digest(&first_part));
+            self.store
+                .put(&self.path, first_part.into())
+                .await
+                .err_notes(&self.path)?;
+            if let Some(state) = self.load_state().await? {
+                self.discard(state).await?;
+            }
+            checksum
+        } else {
+            self.run_multipart(first_part, reader, finish_rx).await?
+        };
+        self.store
+            .put(&self.checksum_path, checksum.into())
+            .await
+            .err_notes(&self.checksum_path)?;
+        Ok(())
+    }
+
+    /// Uploads the parts, skipping the ones uploaded with the same content by a previous
+    /// attempt, and returns the checksum of the whole file.
+    async fn run_multipart(
+        &self,
+        first_part: Vec<u8>,
+        mut reader: DuplexStream,
+        finish_rx: oneshot::Receiver<()>,
+    ) -> Result<String> {
+        let part_size = self.transfer_config.part_size_bytes;
+        let mut state = match self.load_state().await? {
+            Some(state) if state.part_size_bytes == part_size => state,
+            stale_state => {
+                if let Some(stale_state) = stale_state {
+                    self.discard(stale_state).await?;
+                }
+                let state = UploadState {
+                    upload_id: self
+                        .store
+                        .create_multipart(&self.path)
+                        .await
+                        .err_notes(&self.path)?,
+                    part_size_bytes: part_size,
+                    parts: vec![],
+                };
+                self.save_state(&state).await?;
+                state
+            },
+        };
+
+        let mut hasher = Sha256::new();
+        let mut uploads = FuturesOrdered::new();
+        let mut next_part = Some(first_part);
+        let mut num_parts = 0;
+        let mut num_finished_parts = 0;
+        loop {
+            let part = match next_part.take() {
+                Some(part) => part,
+                None => read_part(&mut reader, part_size).await?,
+            };
+            // The last part is the first one not filled up, it's empty if the size of the file
+            // is a multiple of the part size.
+            let is_last = part.len() < part_size;
+            if part.is_empty() {
+                break;
+            }
+            hasher.update(&part);
+            let sha256 = hex::encode(Sha256::digest(&part));
+            let uploaded = match state.parts.get(num_parts) {
+                Some(uploaded) if uploaded.sha256 == sha256 => Some(PartId {
+                    content_id: uploaded.content_id.clone(),
+                }),
+                Some(_) => {
+                    // The content changed, the parts uploaded before can't be trusted anymore.
+                    state.parts.truncate(num_parts);
+                    self.save_state(&state).await?;
+                    None
+                },
+                None => None,
+            };
+            uploads.push_back(self.upload_part(
+                state.upload_id.clone(),
+                num_parts,
+                part,
+                sha256,
+                uploaded,
+            ));
+            num_parts += 1;
+
+            // Keeps at most `max_concurrency` parts in memory.
+            while uploads.len() >= self.transfer_config.max_concurrency {
+                let uploaded = uploads.next().await.expect("Uploads can't be empty.");
+                self.record_part(&mut state, num_finished_parts, uploaded?)
+                    .await?;
+                num_finished_parts += 1;
+            }
+            if is_last {
+                break;
+            }
+        }
+        while let Some(uploaded) = uploads.next().await {
+            self.record_part(&mut state, num_finished_parts, uploaded?)
+                .await?;
+            num_finished_parts += 1;
+        }
+        state.parts.truncate(num_parts);
+
+        finish_rx
+            .await
+            .map_err(|_| Self::not_finished(&self.path))?;
+        // Forgets the upload before completing it, so that a failed completion restarts the
+        // upload instead of resuming one that might be completed already.
+        self.delete_state().await?;
+        let parts = state
+            .parts
+            .into_iter()
+            .map(|part| PartId {
+                content_id: part.content_id,
+            })
+            .collect();
+        self.store
+            .complete_multipart(&self.path, &state.upload_id, parts)
+            .await
+            .err_notes(&self.path)?;
+        Ok(hex::encode(hasher.finalize()))
+    }
+
+    fn upload_part(
+        &self,
+        upload_id: MultipartId,
+        part_idx: usize,
+        part: Vec<u8>,
+        sha256: String,
+        uploaded: Option<PartId>,
+    ) -> impl Future<Output = Result<UploadedPart>> {
+        let store = self.store.clone();
+        let path = self.path.clone();
+        async move {
+            let part_id = match uploaded {
+                Some(part_id) => part_id,
+                None => store
+                    .put_part(&path, &upload_id, part_idx, part.into())
+                    .await
+                    .err_notes(&path)?,
+            };
+            Ok(UploadedPart {
+                sha256,
+                content_id: part_id.content_id,
+            })
+        }
+    }
+
+    async fn record_part(
+        &self,
+        state: &mut UploadState,
+        part_idx: usize,
+        part: UploadedPart,
+    ) -> Result<()> {
+        if part_idx < state.parts.len() {
+            state.parts[part_idx] = part;
+        } else {
+            state.parts.push(part);
+        }
+        self.save_state(state).await
+    }
+
+    async fn load_state(&self) -> Result<Option<UploadState>> {
+        match self.store.get(&self.state_path).await {
+            Ok(result) => {
+                let bytes = result.bytes().await.err_notes(&self.state_path)?;
+                Ok(Some(serde_json::from_slice(&bytes)?))
+            },
+            Err(object_store::Error::NotFound { .. }) => Ok(None),
+            Err(e) => Err(e).err_notes(&self.state_path)?,
+        }
+    }
+
+    async fn save_state(&self, state: &UploadState) -> Result<()> {
+        self.store
+            .put(&self.state_path, serde_json::to_vec(state)?.into())
+            .await
+            .err_notes(&self.state_path)?;
+        Ok(())
+    }
+
+    async fn delete_state(&self) -> Result<()> {
+        match self.store.delete(&self.state_path).await {
+            Ok(()) | Err(object_store::Error::NotFound { .. }) => Ok(()),
+            Err(e) => Err(e).err_notes(&self.state_path)?,
+        }
+    }
+
+    /// Aborts an upload that can't be resumed.
+    async fn discard(&self, state: UploadState) -> Result<()> {
+        self.delete_state().await?;
+        self.store
+            .abort_multipart(&self.path, &state.upload_id)
+            .await
+            .err_notes(&self.path)?;
+        Ok(())
+    }
+
+    fn not_finished(path: &Path) -> anyhow::Error {
+        format_err!("Writer of {} was dropped before shutdown.", path)
+    }
+}
+
+async fn read_part(reader: &mut DuplexStream, part_size: usize) -> io::Result<Vec<u8>> {
+    let mut part = Vec::with_capacity(part_size);
+    reader.take(part_size as u64).read_to_end(&mut part).await?;
+    Ok(part)
+}
+
+/// Hands everything written to the task uploading the file. Shutting the writer down finishes
+/// the upload, and fails if the upload failed. If the writer is dropped before, the upload is
+/// left unfinished, to be resumed.
+struct UploadWriter {
+    inner: DuplexStream,
+    finish_tx: Option<oneshot::Sender<()>>,
+    upload: Option<JoinHandle<Result<()>>>,
+}
+
+impl UploadWriter {
+    fn poll_upload(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let Some(upload) = self.upload.as_mut() else {
+            return Poll::Ready(Err(io::Error::new(
+                io::ErrorKind::Other,
+                "Upload already finished.",
+            )));
+        };
+        let result = ready!(Pin::new(upload).poll(cx));
+        self.upload = None;
+        Poll::Ready(match result {
+            Ok(Ok(())) => Ok(()),
+            Ok(Err(e)) => Err(io::Error::new(io::ErrorKind::Other, e)),
+            Err(e) => Err(io::Error::new(io::ErrorKind::Other, e)),
+        })
+    }
+}
+
+impl AsyncWrite for UploadWriter {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        match ready!(Pin::new(&mut self.inner).poll_write(cx, buf)) {
+            Ok(written) => Poll::Ready(Ok(written)),
+            // The upload stopped reading because it failed, surfaces the reason.
+            Err(e) => {
+                ready!(self.poll_upload(cx))?;
+                Poll::Ready(Err(e))
+            },
+        }
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        if self.finish_tx.is_some() {
+            ready!(Pin::new(&mut self.inner).poll_shutdown(cx))?;
+            // The upload might have failed already, which the result below reports.
+            let _ = self.finish_tx.take().expect("Checked above.").send(());
+        }
+        self.poll_upload(cx)
+    }
+}
+
+/// Hashes everything read from the file, and fails the read at the end of the file if the
+/// content doesn't match the saved checksum.
+struct ChecksumReader<R> {
+    inner: R,
+    hasher: Sha256,
+    expected_checksum: Option<String>,
+    file_handle: FileHandle,
+}
+
+impl<R> ChecksumReader<R> {
+    fn new(inner: R, expected_checksum: Option<String>, file_handle: FileHandle) -> Self {
+        Self {
+            inner,
+            hasher: Sha256::new(),
+            expected_checksum,
+            file_handle,
+        }
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for ChecksumReader<R> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        if buf.remaining() == 0 {
+            return Poll::Ready(Ok(()));
+        }
+        let filled = buf.filled().len();
+        ready!(Pin::new(&mut self.inner).poll_read(cx, buf))?;
+        let read = &buf.filled()[filled..];
+        if !read.is_empty() {
+            self.hasher.update(read);
+        } else if let Some(expected_checksum) = self.expected_checksum.take() {
+            // Nothing read into a non-empty buffer means the end of the file.
+            let checksum = hex::encode(self.hasher.finalize_reset());
+            if checksum != expected_checksum {
+                return Poll::Ready(Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "Checksum mismatch for {}, expected {}, got {}.",
+                        self.file_handle, expected_checksum, checksum
+                    ),
+                )));
+            }
+        }
+        Poll::Ready(Ok(()))
+    }
+}
@@ -0,0 +1,345 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use crate::storage::test_util::{
+    arb_backups, arb_metadata_files, test_save_and_list_metadata_files_impl,
+    test_write_and_read_impl,
+};
+use futures::stream::BoxStream;
+use object_store::{
+    memory::InMemory, GetOptions, GetResult, ListResult, MultipartUpload, ObjectMeta,
+    PutMultipartOpts, PutOptions, PutPayload, PutResult,
+};
+use proptest::prelude::*;
+use std::{
+    fmt::{self, Display, Formatter},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+};
+use tokio::{io::AsyncWriteExt, runtime::Runtime};
+
+fn new_storage(store: Arc<dyn BackupObjectStore>) -> ObjectStorage {
+    ObjectStorage::new(store, "backup1/e1", TransferConfig {
+        part_size_bytes: TransferConfig::MIN_PART_SIZE_BYTES,
+        ..Default::default()
+    })
+    .unwrap()
+}
+
+#[test]
+fn test_invalid_transfer_config() {
+    for transfer_config in [
+        TransferConfig {
+            part_size_bytes: TransferConfig::MIN_PART_SIZE_BYTES - 1,
+            ..Default::default()
+        },
+        TransferConfig {
+            max_concurrency: 0,
+            ..Default::default()
+        },
+    ] {
+        let store = Arc::new(InMemory::new());
+        assert!(ObjectStorage::new(store, "backup1/e1", transfer_config).is_err());
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(10))]
+
+    #[test]
+    fn test_write_and_read(
+        backups in arb_backups()
+    ) {
+        let store = new_storage(Arc::new(InMemory::new()));
+
+        let rt = Runtime::new().unwrap();
+        rt.block_on(test_write_and_read_impl(Box::new(store), backups));
+    }
+
+    #[test]
+    fn test_save_list_metadata_files(
+        input in arb_metadata_files(),
+    ) {
+        let store = new_storage(Arc::new(InMemory::new()));
+
+        let rt = Runtime::new().unwrap();
+        rt.block_on(test_save_and_list_metadata_files_impl(Box::new(store), input));
+    }
+}
+
+#[tokio::test]
+async fn test_multipart_write_and_read() {
+    let storage = new_storage(Arc::new(InMemory::new()));
+    let backup_handle = storage
+        .create_backup(&ShellSafeName::from_str("backup").unwrap())
+        .await
+        .unwrap();
+    let (file_handle, mut file) = storage
+        .create_for_write(&backup_handle, &ShellSafeName::from_str("file").unwrap())
+        .await
+        .unwrap();
+    // Spans multiple parts.
+    let content: Vec<u8> = (0..(TransferConfig::MIN_PART_SIZE_BYTES * 5 / 2))
+        .map(|i| i as u8)
+        .collect();
+    for chunk in content.chunks(100_000) {
+        file.write_all(chunk).await.unwrap();
+    }
+    file.shutdown().await.unwrap();
+
+    let mut buf = Vec::new();
+    storage
+        .open_for_read(&file_handle)
+        .await
+        .unwrap()
+        .read_to_end(&mut buf)
+        .await
+        .unwrap();
+    assert_eq!(buf, content);
+}
+
+#[tokio::test]
+async fn test_read_detects_corruption() {
+    let store: Arc<dyn BackupObjectStore> = Arc::new(InMemory::new());
+    let storage = new_storage(store.clone());
+    let (file_handle, mut file) = storage
+        .create_for_write("backup", &ShellSafeName::from_str("file").unwrap())
+        .await
+        .unwrap();
+    file.write_all(b"content").await.unwrap();
+    file.shutdown().await.unwrap();
+
+    store
+        .put(&storage.object_path(&file_handle), "corrupted".into())
+        .await
+        .unwrap();
+    let mut buf = Vec::new();
+    let err = storage
+        .open_for_read(&file_handle)
+        .await
+        .unwrap()
+        .read_to_end(&mut buf)
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+}
+
+#[tokio::test]
+async fn test_backup_metadata_file() {
+    let storage = new_storage(Arc::new(InMemory::new()));
+    let file_handle = storage
+        .save_metadata_line(
+            &ShellSafeName::from_str("epoch_ending_1.meta").unwrap(),
+            &TextLine::new("line").unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(file_handle, "metadata/epoch_ending_1.meta");
+
+    storage.backup_metadata_file(&file_handle).await.unwrap();
+    assert!(storage.list_metadata_files().await.unwrap().is_empty());
+    let mut buf = String::new();
+    storage
+        .open_for_read("metadata_backup/epoch_ending_1.meta")
+        .await
+        .unwrap()
+        .read_to_string(&mut buf)
+        .await
+        .unwrap();
+    assert_eq!(buf, "line\n");
+}
+
+/// Counts the uploaded parts, and fails uploading the part `failing_part_idx`.
+#[derive(Debug, Default)]
+struct FlakyStore {
+    inner: InMemory,
+    failing_part_idx: Mutex<Option<usize>>,
+    num_uploaded_parts: AtomicUsize,
+}
+
+impl Display for FlakyStore {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "FlakyStore({})", self.inner)
+    }
+}
+
+#[async_trait]
+impl ObjectStore for FlakyStore {
+    async fn put_opts(
+        &self,
+        location: &Path,
+        payload: PutPayload,
+        opts: PutOptions,
+    ) -> object_store::Result<PutResult> {
+        self.inner.put_opts(location, payload, opts).await
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &Path,
+        opts: PutMultipartOpts,
+    ) -> object_store::Result<Box<dyn MultipartUpload>> {
+        self.inner.put_multipart_opts(location, opts).await
+    }
+
+    async fn get_opts(
+        &self,
+        location: &Path,
+        options: GetOptions,
+    ) -> object_store::Result<GetResult> {
+        self.inner.get_opts(location, options).await
+    }
+
+    async fn delete(&self, location: &Path) -> object_store::Result<()> {
+        self.inner.delete(location).await
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'_, object_store::Result<ObjectMeta>> {
+        self.inner.list(prefix)
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> object_store::Result<ListResult> {
+        self.inner.list_with_delimiter(prefix).await
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> object_store::Result<()> {
+        self.inner.copy(from, to).await
+    }
+
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> object_store::Result<()> {
+        self.inner.copy_if_not_exists(from, to).await
+    }
+}
+
+#[async_trait]
+impl MultipartStore for FlakyStore {
+    async fn create_multipart(&self, path: &Path) -> object_store::Result<MultipartId> {
+        self.inner.create_multipart(path).await
+    }
+
+    async fn put_part(
+        &self,
+        path: &Path,
+        id: &MultipartId,
+        part_idx: usize,
+        data: PutPayload,
+    ) -> object_store::Result<PartId> {
+        if *self.failing_part_idx.lock().unwrap() == Some(part_idx) {
+            return Err(object_store::Error::Generic {
+                store: "FlakyStore",
+                source: "Injected failure.".into(),
+            });
+        }
+        let part_id = self.inner.put_part(path, id, part_idx, data).await?;
+        self.num_uploaded_parts.fetch_add(1, Ordering::SeqCst);
+        Ok(part_id)
+    }
+
+    async fn complete_multipart(
+        &self,
+        path: &Path,
+        id: &MultipartId,
+        parts: Vec<PartId>,
+    ) -> object_store::Result<PutResult> {
+        self.inner.complete_multipart(path, id, parts).await
+    }
+
+    async fn abort_multipart(&self, path: &Path, id: &MultipartId) -> object_store::Result<()> {
+        self.inner.abort_multipart(path, id).await
+    }
+}
+
+async fn write_file(storage: &ObjectStorage, content: &[u8]) -> io::Result<FileHandle> {
+    let (file_handle, mut file) = storage
+        .create_for_write("backup", &ShellSafeName::from_str("file").unwrap())
+        .await
+        .unwrap();
+    file.write_all(content).await?;
+    file.shutdown().await?;
+    Ok(file_handle)
+}
+
+async fn read_file(storage: &ObjectStorage, file_handle: &FileHandleRef) -> Vec<u8> {
+    let mut buf = Vec::new();
+    storage
+        .open_for_read(file_handle)
+        .await
+        .unwrap()
+        .read_to_end(&mut buf)
+        .await
+        .unwrap();
+    buf
+}
+
+/// Three parts, the last one partial.
+fn multipart_content(seed: u8) -> Vec<u8> {
+    (0..(TransferConfig::MIN_PART_SIZE_BYTES * 5 / 2))
+        .map(|i| (i as u8).wrapping_add(seed))
+        .collect()
+}
+
+#[tokio::test]
+async fn test_resume_interrupted_upload() {
+    let store = Arc::new(FlakyStore::default());
+    let storage = new_storage(store.clone());
+    let content = multipart_content(0);
+
+    *store.failing_part_idx.lock().unwrap() = Some(2);
+    write_file(&storage, &content).await.unwrap_err();
+    assert_eq!(store.num_uploaded_parts.load(Ordering::SeqCst), 2);
+    assert!(matches!(
+        store.head(&storage.object_path("backup/file")).await,
+        Err(object_store::Error::NotFound { .. })
+    ));
+
+    // Only the failed part is uploaded again.
+    *store.failing_part_idx.lock().unwrap() = None;
+    let file_handle = write_file(&storage, &content).await.unwrap();
+    assert_eq!(store.num_uploaded_parts.load(Ordering::SeqCst), 3);
+    assert_eq!(read_file(&storage, &file_handle).await, content);
+    assert!(matches!(
+        store.head(&storage.upload_state_path(&file_handle)).await,
+        Err(object_store::Error::NotFound { .. })
+    ));
+}
+
+#[tokio::test]
+async fn test_resume_upload_with_changed_content() {
+    let store = Arc::new(FlakyStore::default());
+    let storage = new_storage(store.clone());
+
+    *store.failing_part_idx.lock().unwrap() = Some(2);
+    write_file(&storage, &multipart_content(0))
+        .await
+        .unwrap_err();
+    assert_eq!(store.num_uploaded_parts.load(Ordering::SeqCst), 2);
+
+    // The first part is the same, the rest differs and is uploaded again.
+    *store.failing_part_idx.lock().unwrap() = None;
+    let mut content = multipart_content(0);
+    content[TransferConfig::MIN_PART_SIZE_BYTES + 1] ^= 1;
+    let file_handle = write_file(&storage, &content).await.unwrap();
+    assert_eq!(store.num_uploaded_parts.load(Ordering::SeqCst), 4);
+    assert_eq!(read_file(&storage, &file_handle).await, content);
+}
+
+#[tokio::test]
+async fn test_small_file_discards_unfinished_upload() {
+    let store = Arc::new(FlakyStore::default());
+    let storage = new_storage(store.clone());
+
+    *store.failing_part_idx.lock().unwrap() = Some(2);
+    write_file(&storage, &multipart_content(0))
+        .await
+        .unwrap_err();
+
+    let file_handle = write_file(&storage, b"small").await.unwrap();
+    assert_eq!(read_file(&storage, &file_handle).await, b"small");
+    assert!(matches!(
+        store.head(&storage.upload_state_path(&file_handle)).await,
+        Err(object_store::Error::NotFound { .. })
+    ));
+}
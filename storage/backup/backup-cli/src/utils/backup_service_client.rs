@@ -124,6 +124,20 @@ impl BackupServiceClient {
         .await
     }
 
+    pub async fn get_state_snapshot_delta_chunk(
+        &self,
+        base_version: Version,
+        version: Version,
+        start_idx: usize,
+        limit: usize,
+    ) -> Result<impl AsyncRead> {
+        self.get(
+            "state_snapshot_delta_chunk",
+            &format!("{}/{}/{}/{}", base_version, version, start_idx, limit),
+        )
+        .await
+    }
+
     pub async fn get_state_root_proof(&self, version: Version) -> Result<Vec<u8>> {
         let mut buf = Vec::new();
         self.get("state_root_proof", &format!("{}", version))
@@ -12,6 +12,7 @@ use aptos_backup_cli::{
     coordinators::{
         backup::{BackupCoordinator, BackupCoordinatorOpt},
         verify::VerifyCoordinator,
+        verify_storage::VerifyStorageCoordinator,
     },
     metadata::{cache, cache::MetadataCacheOpt},
     storage::DBToolStorageOpt,
@@ -41,6 +42,11 @@ pub enum Command {
     Query(OneShotQueryType),
     #[clap(about = "verify the backup through restoring with the backup files")]
     Verify(VerifyOpt),
+    #[clap(
+        about = "Audit every backup in the storage, without stopping at the first problem, and \
+        output a JSON report of the integrity issues and coverage gaps found."
+    )]
+    VerifyStorage(VerifyStorageOpt),
 }
 
 #[derive(Parser)]
@@ -165,6 +171,36 @@ pub struct VerifyOpt {
     output_transaction_analysis: Option<PathBuf>,
 }
 
+#[derive(Parser)]
+pub struct VerifyStorageOpt {
+    #[clap(flatten)]
+    metadata_cache_opt: MetadataCacheOpt,
+    #[clap(flatten)]
+    trusted_waypoints_opt: TrustedWaypointOpt,
+    #[clap(flatten)]
+    storage: DBToolStorageOpt,
+    #[clap(flatten)]
+    concurrent_downloads: ConcurrentDownloadsOpt,
+    #[clap(
+        long,
+        help = "Last epoch the epoch ending backups are expected to cover. Defaults to the epoch \
+        of the latest state snapshot backup."
+    )]
+    expected_end_epoch: Option<u64>,
+    #[clap(
+        long,
+        help = "Last version the transaction backups are expected to cover. Defaults to the latest \
+        version of the epoch ending and state snapshot backups."
+    )]
+    expected_end_version: Option<Version>,
+    #[clap(
+        long,
+        value_parser,
+        help = "Write the report to this file instead of stdout."
+    )]
+    output: Option<PathBuf>,
+}
+
 impl Command {
     pub async fn run(self) -> Result<()> {
         match self {
@@ -250,6 +286,19 @@ impl Command {
                 .run()
                 .await?
             },
+            Command::VerifyStorage(opt) => {
+                VerifyStorageCoordinator::new(
+                    opt.storage.init_storage().await?,
+                    opt.metadata_cache_opt,
+                    opt.trusted_waypoints_opt,
+                    opt.concurrent_downloads.get(),
+                    opt.expected_end_epoch,
+                    opt.expected_end_version,
+                    opt.output,
+                )
+                .run()
+                .await?
+            },
         }
         Ok(())
     }
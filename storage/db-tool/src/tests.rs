@@ -47,6 +47,15 @@ fn test_various_cmd_parsing() {
     ]);
 
     run_cmd(&["aptos-db-tool", "backup", "verify", "--local-fs-dir", "."]);
+    run_cmd(&[
+        "aptos-db-tool",
+        "backup",
+        "verify-storage",
+        "--local-fs-dir",
+        ".",
+        "--output",
+        "report.json",
+    ]);
     run_cmd(&[
         "aptos-db-tool",
         "replay-verify",
@@ -74,11 +83,14 @@ fn run_cmd(args: &[&str]) {
 mod dbtool_tests {
     use crate::DBTool;
     use aptos_backup_cli::{
-        coordinators::backup::BackupCompactor,
+        coordinators::{
+            backup::BackupCompactor,
+            verify_storage::{Gap, VerifyStorageCoordinator},
+        },
         metadata,
         metadata::{cache::MetadataCacheOpt, view::MetadataView},
         storage::{local_fs::LocalFs, BackupStorage},
-        utils::test_utils::start_local_backup_service,
+        utils::{test_utils::start_local_backup_service, TrustedWaypointOpt},
     };
     use aptos_config::config::{RocksdbConfigs, StorageDirPaths};
     use aptos_db::AptosDB;
@@ -279,6 +291,145 @@ mod dbtool_tests {
         rt.shutdown_timeout(Duration::from_secs(1));
     }
 
+    #[test]
+    fn test_verify_storage() {
+        let db = test_execution_with_storage_impl();
+        let latest_epoch = db.get_latest_epoch_state().unwrap().epoch;
+        let backup_dir = TempPath::new();
+        backup_dir.create_as_dir().unwrap();
+        let store: Arc<dyn BackupStorage> = Arc::new(LocalFs::new(backup_dir.path().to_path_buf()));
+        let (rt, port) = start_local_backup_service(db);
+        let server_addr = format!(" http://localhost:{}", port);
+        let backup_dir_str = backup_dir.path().to_str().unwrap();
+        let latest_epoch_str = latest_epoch.to_string();
+
+        let oneoff_backups: [&[&str]; 4] = [
+            &[
+                "epoch-ending",
+                "--start-epoch",
+                "0",
+                "--end-epoch",
+                latest_epoch_str.as_str(),
+            ],
+            &["state-snapshot", "--state-snapshot-epoch", "1"],
+            // Leaves versions [15, 19] not backed up.
+            &[
+                "transaction",
+                "--start-version",
+                "0",
+                "--num_transactions",
+                "15",
+            ],
+            &[
+                "transaction",
+                "--start-version",
+                "20",
+                "--num_transactions",
+                "10",
+            ],
+        ];
+        for args in oneoff_backups {
+            let mut cmd = vec![
+                "aptos-db-tool",
+                "backup",
+                "oneoff",
+                "--backup-service-address",
+                server_addr.as_str(),
+            ];
+            cmd.extend_from_slice(args);
+            cmd.extend_from_slice(&["--local-fs-dir", backup_dir_str]);
+            rt.block_on(DBTool::try_parse_from(cmd).unwrap().run())
+                .unwrap();
+        }
+
+        let metadata_cache_dir = TempPath::new();
+        let report = rt
+            .block_on(
+                VerifyStorageCoordinator::new(
+                    Arc::clone(&store),
+                    MetadataCacheOpt::new(Some(metadata_cache_dir.path().to_path_buf())),
+                    TrustedWaypointOpt::default(),
+                    2,
+                    None,
+                    None,
+                    None,
+                )
+                .audit(),
+            )
+            .unwrap();
+        assert_eq!(report.epoch_ending_backups.len(), 1);
+        assert_eq!(report.state_snapshot_backups.len(), 1);
+        assert_eq!(report.transaction_backups.len(), 2);
+        assert!(report.epoch_gaps.is_empty());
+        assert_eq!(report.version_gaps, vec![Gap {
+            first: 15,
+            last: 19
+        }]);
+        assert_eq!(report.num_issues(), 1);
+
+        // Epochs and versions after the last backups are missing if expected.
+        let report = rt
+            .block_on(
+                VerifyStorageCoordinator::new(
+                    Arc::clone(&store),
+                    MetadataCacheOpt::new(Some(metadata_cache_dir.path().to_path_buf())),
+                    TrustedWaypointOpt::default(),
+                    2,
+                    Some(latest_epoch + 1),
+                    Some(39),
+                    None,
+                )
+                .audit(),
+            )
+            .unwrap();
+        assert_eq!(report.epoch_gaps, vec![Gap {
+            first: latest_epoch,
+            last: latest_epoch + 1
+        }]);
+        assert_eq!(report.version_gaps, vec![
+            Gap {
+                first: 15,
+                last: 19
+            },
+            Gap {
+                first: 30,
+                last: 39
+            }
+        ]);
+        assert_eq!(report.num_issues(), 3);
+
+        // Corrupt a transaction chunk.
+        let txn_manifest = &report.transaction_backups[1].manifest;
+        let chunk_path = fs::read_dir(backup_dir.path().join(txn_manifest).parent().unwrap())
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .find(|path| path.extension().map_or(false, |ext| ext == "chunk"))
+            .unwrap();
+        let mut content = fs::read(&chunk_path).unwrap();
+        *content.last_mut().unwrap() ^= 1;
+        fs::write(&chunk_path, content).unwrap();
+
+        let report_path = backup_dir.path().join("report.json");
+        assert!(rt
+            .block_on(
+                DBTool::try_parse_from([
+                    "aptos-db-tool",
+                    "backup",
+                    "verify-storage",
+                    "--local-fs-dir",
+                    backup_dir_str,
+                    "--output",
+                    report_path.to_str().unwrap(),
+                ])
+                .unwrap()
+                .run(),
+            )
+            .is_err());
+        let report = fs::read_to_string(&report_path).unwrap();
+        assert!(report.contains(chunk_path.file_name().unwrap().to_str().unwrap()));
+        rt.shutdown_timeout(Duration::from_secs(1));
+    }
+
     #[cfg(test)]
     fn db_restore_test_setup(
         start: Version,
@@ -0,0 +1,167 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    delta_iterator::{DeltaItem, JellyfishMerkleDeltaIterator},
+    mock_tree_store::MockTreeStore,
+    test_helper::{gen_value, ValueBlob},
+    JellyfishMerkleTree,
+};
+use aptos_crypto::HashValue;
+use aptos_storage_interface::Result;
+use aptos_types::transaction::Version;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::{collections::BTreeMap, sync::Arc};
+
+type Snapshot = BTreeMap<HashValue, (ValueBlob, Version)>;
+
+/// Commits random updates at versions 0 to `num_versions - 1`, and returns the leaves of the tree
+/// at each version.
+fn build_tree(num_versions: usize) -> (Arc<MockTreeStore<ValueBlob>>, Vec<Snapshot>) {
+    let db = Arc::new(MockTreeStore::default());
+    let tree = JellyfishMerkleTree::new(&*db);
+    let mut rng = StdRng::from_seed([1; 32]);
+
+    let mut snapshots = Vec::new();
+    let mut leaves = Snapshot::new();
+    for version in 0..num_versions as Version {
+        let num_updates = if version == 0 { 100 } else { 5 };
+        let mut updates = BTreeMap::new();
+        for _ in 0..num_updates {
+            if !leaves.is_empty() && rng.gen_bool(0.5) {
+                // Update or delete an existing key.
+                let idx = rng.gen_range(0, leaves.len());
+                let key = *leaves.keys().nth(idx).unwrap();
+                let value = rng.gen_bool(0.5).then(gen_value);
+                updates.insert(key, value);
+            } else {
+                updates.insert(HashValue::random_with_rng(&mut rng), Some(gen_value()));
+            }
+        }
+
+        let (_root_hash, batch) = tree
+            .put_value_set_test(
+                updates.iter().map(|(k, v)| (*k, v.as_ref())).collect(),
+                version,
+            )
+            .unwrap();
+        db.write_tree_update_batch(batch).unwrap();
+
+        for (key, value) in updates {
+            match value {
+                Some((_, value_key)) => leaves.insert(key, (value_key, version)),
+                None => leaves.remove(&key),
+            };
+        }
+        snapshots.push(leaves.clone());
+    }
+    (db, snapshots)
+}
+
+/// Reassembles the leaves from the delta items and the leaves at the base version.
+fn reassemble(
+    items: &[DeltaItem<ValueBlob>],
+    base: &Snapshot,
+) -> Vec<(HashValue, (ValueBlob, Version))> {
+    let mut leaves = Vec::new();
+    for item in items {
+        match item {
+            DeltaItem::Changed(key, value_index) => leaves.push((*key, value_index.clone())),
+            DeltaItem::Unchanged {
+                first_key,
+                last_key,
+                leaf_count,
+            } => {
+                let range: Vec<_> = base
+                    .range(*first_key..=*last_key)
+                    .map(|(k, v)| (*k, v.clone()))
+                    .collect();
+                assert_eq!(range.len(), *leaf_count);
+                leaves.extend(range);
+            },
+        }
+    }
+    leaves
+}
+
+fn delta_items(
+    db: &Arc<MockTreeStore<ValueBlob>>,
+    base_version: Version,
+    version: Version,
+    start_idx: usize,
+    limit: usize,
+) -> Vec<DeltaItem<ValueBlob>> {
+    JellyfishMerkleDeltaIterator::new(Arc::clone(db), base_version, version, start_idx, limit)
+        .unwrap()
+        .collect::<Result<Vec<_>>>()
+        .unwrap()
+}
+
+#[test]
+fn test_delta_iterator_reassembles_snapshot() {
+    let (db, snapshots) = build_tree(5);
+    for version in 0..snapshots.len() {
+        for base_version in 0..=version {
+            let leaves = &snapshots[version];
+            for (start_idx, limit) in [(0, usize::MAX), (0, 7), (13, 30), (leaves.len() - 1, 10)] {
+                let items = delta_items(
+                    &db,
+                    base_version as Version,
+                    version as Version,
+                    start_idx,
+                    limit,
+                );
+                let expected: Vec<_> = leaves
+                    .iter()
+                    .skip(start_idx)
+                    .take(limit)
+                    .map(|(k, v)| (*k, v.clone()))
+                    .collect();
+                assert_eq!(
+                    items.iter().map(DeltaItem::leaf_count).sum::<usize>(),
+                    expected.len()
+                );
+                assert_eq!(reassemble(&items, &snapshots[base_version]), expected);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_delta_iterator_only_visits_changed_leaves() {
+    let (db, snapshots) = build_tree(2);
+
+    // Nothing changed since the snapshot itself.
+    let items = delta_items(&db, 1, 1, 0, usize::MAX);
+    assert_eq!(items.len(), 1);
+    assert!(
+        matches!(items[0], DeltaItem::Unchanged { leaf_count, .. } if leaf_count == snapshots[1].len())
+    );
+
+    // Only the leaves written at version 1 are yielded on their own.
+    let changed: Vec<_> = delta_items(&db, 0, 1, 0, usize::MAX)
+        .into_iter()
+        .filter_map(|item| match item {
+            DeltaItem::Changed(key, (_, version)) => Some((key, version)),
+            DeltaItem::Unchanged { .. } => None,
+        })
+        .collect();
+    let written_at_version_1: Vec<_> = snapshots[1]
+        .iter()
+        .filter(|(_, (_, version))| *version == 1)
+        .map(|(key, (_, version))| (*key, *version))
+        .collect();
+    // A leaf moved to a different position in the tree counts as changed as well.
+    assert!(written_at_version_1
+        .iter()
+        .all(|leaf| changed.contains(leaf)));
+    assert!(changed.len() < snapshots[1].len() / 2);
+}
+
+#[test]
+fn test_delta_iterator_out_of_range() {
+    let (db, snapshots) = build_tree(2);
+    assert!(delta_items(&db, 0, 1, snapshots[1].len(), 10).is_empty());
+    assert!(delta_items(&db, 0, 1, 0, 0).is_empty());
+    assert!(JellyfishMerkleDeltaIterator::new(Arc::clone(&db), 1, 0, 0, 10).is_err());
+}
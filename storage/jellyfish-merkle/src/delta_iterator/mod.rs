@@ -0,0 +1,199 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module implements `JellyfishMerkleDeltaIterator`. Initialized with a base version and a
+//! version, the iterator goes through a range of leaves of the tree at the version, in key order,
+//! and only visits the nodes created after the base version. Each leaf created after the base
+//! version is yielded on its own, while the leaves under a node not changed since the base version
+//! are yielded as a single range, because they are the same in the tree at the base version.
+
+#[cfg(test)]
+mod delta_iterator_test;
+
+use crate::{
+    node_type::{Node, NodeKey},
+    TreeReader,
+};
+use aptos_crypto::HashValue;
+use aptos_storage_interface::{db_ensure as ensure, db_other_bail, AptosDbError, Result};
+use aptos_types::{nibble::ROOT_NIBBLE_HEIGHT, transaction::Version};
+use std::{marker::PhantomData, sync::Arc};
+
+/// An item yielded by `JellyfishMerkleDeltaIterator`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DeltaItem<K> {
+    /// A leaf created after the base version, with its value index.
+    Changed(HashValue, (K, Version)),
+    /// `leaf_count` consecutive leaves, from `first_key` to `last_key` (inclusive), which are
+    /// under a node not changed since the base version.
+    Unchanged {
+        first_key: HashValue,
+        last_key: HashValue,
+        leaf_count: usize,
+    },
+}
+
+impl<K> DeltaItem<K> {
+    pub fn leaf_count(&self) -> usize {
+        match self {
+            Self::Changed(..) => 1,
+            Self::Unchanged { leaf_count, .. } => *leaf_count,
+        }
+    }
+}
+
+/// A subtree to visit, with the index of its leftmost leaf in the whole tree.
+struct SubtreeToVisit {
+    node_key: NodeKey,
+    first_idx: usize,
+    leaf_count: usize,
+}
+
+/// The `JellyfishMerkleDeltaIterator` implementation.
+pub struct JellyfishMerkleDeltaIterator<R, K> {
+    /// The storage engine from which we can read nodes using node keys.
+    reader: Arc<R>,
+
+    /// Nodes with a version no greater than this are considered unchanged.
+    base_version: Version,
+
+    /// Index of the first leaf to visit.
+    start_idx: usize,
+
+    /// Index of the leaf after the last one to visit.
+    end_idx: usize,
+
+    /// The stack used for depth first traversal, with the leftmost subtree on top.
+    stack: Vec<SubtreeToVisit>,
+
+    phantom_value: PhantomData<K>,
+}
+
+impl<R, K> JellyfishMerkleDeltaIterator<R, K>
+where
+    R: TreeReader<K>,
+    K: crate::Key,
+{
+    /// Constructs a new iterator going through at most `limit` leaves of the tree at `version`,
+    /// starting from the leaf at `start_idx`.
+    pub fn new(
+        reader: Arc<R>,
+        base_version: Version,
+        version: Version,
+        start_idx: usize,
+        limit: usize,
+    ) -> Result<Self> {
+        ensure!(
+            base_version <= version,
+            "Base version {} is newer than version {}.",
+            base_version,
+            version,
+        );
+        let root_key = NodeKey::new_empty_path(version);
+        let leaf_count = reader.get_node(&root_key)?.leaf_count();
+        Ok(Self {
+            reader,
+            base_version,
+            start_idx,
+            end_idx: start_idx.saturating_add(limit).min(leaf_count),
+            stack: vec![SubtreeToVisit {
+                node_key: root_key,
+                first_idx: 0,
+                leaf_count,
+            }],
+            phantom_value: PhantomData,
+        })
+    }
+
+    /// Returns the key of the leaf at `idx` under the node at `node_key`.
+    fn leaf_key_at(&self, node_key: &NodeKey, mut idx: usize) -> Result<HashValue> {
+        let mut node_key = node_key.clone();
+        for _ in 0..=ROOT_NIBBLE_HEIGHT {
+            match self.reader.get_node(&node_key)? {
+                Node::Leaf(leaf_node) => {
+                    ensure!(idx == 0, "Bug: Leaf index out of range.");
+                    return Ok(leaf_node.account_key());
+                },
+                Node::Internal(internal_node) => {
+                    let mut child_key = None;
+                    for (nibble, child) in internal_node.children_sorted() {
+                        if idx < child.leaf_count() {
+                            child_key = Some(node_key.gen_child_node_key(child.version, *nibble));
+                            break;
+                        }
+                        idx -= child.leaf_count();
+                    }
+                    match child_key {
+                        Some(child_key) => node_key = child_key,
+                        None => db_other_bail!("Bug: Leaf index out of range."),
+                    }
+                },
+                Node::Null => db_other_bail!("Unexpected null node at {:?}.", node_key),
+            }
+        }
+        db_other_bail!("Bug: potential infinite loop.");
+    }
+
+    fn next_impl(&mut self) -> Result<Option<DeltaItem<K>>> {
+        while let Some(subtree) = self.stack.pop() {
+            let SubtreeToVisit {
+                node_key,
+                first_idx,
+                leaf_count,
+            } = subtree;
+            let lo = first_idx.max(self.start_idx);
+            let hi = (first_idx + leaf_count).min(self.end_idx);
+            if lo >= hi {
+                continue;
+            }
+
+            if node_key.version() <= self.base_version {
+                // The node was already in the tree at the base version, so is the whole subtree.
+                return Ok(Some(DeltaItem::Unchanged {
+                    first_key: self.leaf_key_at(&node_key, lo - first_idx)?,
+                    last_key: self.leaf_key_at(&node_key, hi - 1 - first_idx)?,
+                    leaf_count: hi - lo,
+                }));
+            }
+
+            match self.reader.get_node(&node_key)? {
+                Node::Leaf(leaf_node) => {
+                    return Ok(Some(DeltaItem::Changed(
+                        leaf_node.account_key(),
+                        leaf_node.value_index().clone(),
+                    )));
+                },
+                Node::Internal(internal_node) => {
+                    let mut child_first_idx = first_idx;
+                    let children: Vec<_> = internal_node
+                        .children_sorted()
+                        .map(|(nibble, child)| {
+                            let subtree = SubtreeToVisit {
+                                node_key: node_key.gen_child_node_key(child.version, *nibble),
+                                first_idx: child_first_idx,
+                                leaf_count: child.leaf_count(),
+                            };
+                            child_first_idx += child.leaf_count();
+                            subtree
+                        })
+                        .collect();
+                    self.stack.extend(children.into_iter().rev());
+                },
+                Node::Null => db_other_bail!("Unexpected null node at {:?}.", node_key),
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl<R, K> Iterator for JellyfishMerkleDeltaIterator<R, K>
+where
+    R: TreeReader<K>,
+    K: crate::Key,
+{
+    type Item = Result<DeltaItem<K>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_impl().transpose()
+    }
+}
@@ -0,0 +1,46 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+/// Escapes the characters which are not allowed verbatim in XML text and attribute values.
+///
+/// Characters which are not allowed in XML documents at all, such as most control characters,
+/// are replaced by their Rust escape sequence, e.g. `\u{1b}`.
+pub fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\t' | '\n' | '\r' => escaped.push(c),
+            '\u{0}'..='\u{1f}' | '\u{fffe}' | '\u{ffff}' => escaped.extend(c.escape_unicode()),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("plain text"), "plain text");
+        assert_eq!(
+            escape(r#"<a href="x">&amp;</a>"#),
+            "&lt;a href=&quot;x&quot;&gt;&amp;amp;&lt;/a&gt;"
+        );
+    }
+
+    #[test]
+    fn test_escape_illegal_characters() {
+        assert_eq!(escape("a\tb\nc\r\n"), "a\tb\nc\r\n");
+        assert_eq!(
+            escape("\u{1b}[31mred\u{1b}[0m\u{0}"),
+            "\\u{1b}[31mred\\u{1b}[0m\\u{0}"
+        );
+        assert_eq!(escape("\u{fffe}\u{ffff}é"), "\\u{fffe}\\u{ffff}é");
+    }
+}
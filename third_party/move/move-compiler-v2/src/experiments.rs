@@ -152,6 +152,11 @@ pub static EXPERIMENTS: Lazy<BTreeMap<String, Experiment>> = Lazy::new(|| {
             description: "Whether to check for unused assignments".to_string(),
             default: Inherited(Experiment::CHECKS.to_string()),
         },
+        Experiment {
+            name: Experiment::LINT_CHECKS.to_string(),
+            description: "Whether to run lint checks (see `move_compiler_v2::lint`)".to_string(),
+            default: Given(false),
+        },
         Experiment {
             name: Experiment::VARIABLE_COALESCING.to_string(),
             description: "Whether to run variable coalescing".to_string(),
@@ -217,6 +222,7 @@ impl Experiment {
     pub const KEEP_INLINE_FUNS: &'static str = "keep-inline-funs";
     pub const KEEP_UNINIT_ANNOTATIONS: &'static str = "keep-uninit-annotations";
     pub const LAMBDA_LIFTING: &'static str = "lambda-lifting";
+    pub const LINT_CHECKS: &'static str = "lint-checks";
     pub const OPTIMIZE: &'static str = "optimize";
     pub const PEEPHOLE_OPTIMIZATION: &'static str = "peephole-optimization";
     pub const RECURSIVE_TYPE_CHECK: &'static str = "recursive-type-check";
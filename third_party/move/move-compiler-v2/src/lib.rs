@@ -12,6 +12,7 @@ mod file_format_generator;
 pub mod flow_insensitive_checkers;
 pub mod function_checker;
 pub mod inliner;
+pub mod lint;
 pub mod logging;
 pub mod options;
 pub mod pipeline;
@@ -24,6 +25,7 @@ use crate::{
         lambda_lifter, lambda_lifter::LambdaLiftingOptions, rewrite_target::RewritingScope,
         seqs_in_binop_checker, spec_checker, spec_rewriter, EnvProcessorPipeline,
     },
+    lint::BytecodeLintProcessor,
     pipeline::{
         ability_processor::AbilityProcessor, avail_copies_analysis::AvailCopiesAnalysisProcessor,
         copy_propagation::CopyPropagation, dead_store_elimination::DeadStoreElimination,
@@ -323,6 +325,11 @@ pub fn check_and_rewrite_pipeline<'a, 'b>(
         });
     }
 
+    if !for_v1_model && options.experiment_on(Experiment::LINT_CHECKS) {
+        // Lints are run before inlining, so they see the code as written.
+        env_pipeline.add("lint checks", |env| lint::run_exp_lints(env));
+    }
+
     if options.experiment_on(Experiment::INLINING) {
         let keep_inline_funs = options.experiment_on(Experiment::KEEP_INLINE_FUNS);
         env_pipeline.add("inlining", {
@@ -404,6 +411,11 @@ pub fn bytecode_pipeline(env: &GlobalEnv) -> FunctionTargetPipeline {
         pipeline.add_processor(Box::new(UnusedAssignmentChecker {}));
     }
 
+    if options.experiment_on(Experiment::LINT_CHECKS) {
+        pipeline.add_processor(Box::new(LiveVarAnalysisProcessor::new(false)));
+        pipeline.add_processor(Box::new(BytecodeLintProcessor {}));
+    }
+
     // Reference check is always run, but the processor decides internally
     // based on `Experiment::REFERENCE_SAFETY` whether to report errors.
     pipeline.add_processor(Box::new(LiveVarAnalysisProcessor::new(false)));
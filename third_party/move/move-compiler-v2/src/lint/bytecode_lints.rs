@@ -0,0 +1,58 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Lints which are checked on the stackless bytecode.
+//! Prerequisite: live variable annotation.
+
+use crate::{
+    lint::{BytecodeLint, Lint, LintContext},
+    pipeline::livevar_analysis_processor::LiveVarAnnotation,
+};
+use move_stackless_bytecode::{
+    function_target::FunctionTarget,
+    stackless_bytecode::{AssignKind, Bytecode},
+};
+
+/// Reports an explicit `copy x` of a local which is not used afterwards, so the value could
+/// be moved instead.
+pub struct UnnecessaryCopy;
+
+impl Lint for UnnecessaryCopy {
+    fn name(&self) -> &'static str {
+        "unnecessary_copy"
+    }
+
+    fn description(&self) -> &'static str {
+        "explicit `copy` of a local which is not used afterwards"
+    }
+}
+
+impl BytecodeLint for UnnecessaryCopy {
+    fn check(&self, cx: &LintContext, target: &FunctionTarget) {
+        let live_vars = target
+            .get_annotations()
+            .get::<LiveVarAnnotation>()
+            .expect("live variable annotation");
+        for (offset, bytecode) in target.get_bytecode().iter().enumerate() {
+            if let Bytecode::Assign(id, _, src, AssignKind::Copy) = bytecode {
+                // Only check user defined variables.
+                let Some(src_name) = target.data.local_names.get(src) else {
+                    continue;
+                };
+                let src_name = src_name.display(target.symbol_pool()).to_string();
+                let live_after = &live_vars.get_info_at(offset as u16).after;
+                if !src_name.starts_with('_') && !live_after.contains_key(src) {
+                    cx.report(
+                        self,
+                        &target.get_bytecode_loc(*id),
+                        &format!(
+                            "unnecessary `copy` of `{}`, which is not used afterwards; \
+                             consider removing the `copy`",
+                            src_name
+                        ),
+                    )
+                }
+            }
+        }
+    }
+}
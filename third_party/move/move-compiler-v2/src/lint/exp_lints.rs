@@ -0,0 +1,213 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Lints which are checked on the model AST, before inlining.
+
+use crate::lint::{ExpLint, Lint, LintContext};
+use move_model::{
+    ast::{Exp, ExpData, Operation, Pattern, Value},
+    ty::Type,
+};
+
+/// Strips blocks consisting of a single expression, as in `{ e }`.
+fn strip_sequence(exp: &Exp) -> &Exp {
+    match exp.as_ref() {
+        ExpData::Sequence(_, exps) if exps.len() == 1 => strip_sequence(&exps[0]),
+        _ => exp,
+    }
+}
+
+/// Returns the boolean literal of the expression, if it is one.
+fn as_bool_literal(exp: &Exp) -> Option<bool> {
+    match strip_sequence(exp).as_ref() {
+        ExpData::Value(_, Value::Bool(b)) => Some(*b),
+        _ => None,
+    }
+}
+
+/// Reports `*&e` and `*&mut e`, which is the same as `e`.
+pub struct NeedlessBorrow;
+
+impl Lint for NeedlessBorrow {
+    fn name(&self) -> &'static str {
+        "needless_borrow"
+    }
+
+    fn description(&self) -> &'static str {
+        "dereference of a borrow, `*&e` is the same as `e`"
+    }
+}
+
+impl ExpLint for NeedlessBorrow {
+    fn visit_exp(&self, cx: &LintContext, exp: &ExpData) {
+        if let ExpData::Call(id, Operation::Deref, args) = exp {
+            if let ExpData::Call(_, Operation::Borrow(_), _) = strip_sequence(&args[0]).as_ref() {
+                cx.report(
+                    self,
+                    &cx.env().get_node_loc(*id),
+                    "needless borrow: dereferencing a borrow of an expression is the same as \
+                     the expression itself",
+                )
+            }
+        }
+    }
+}
+
+/// Reports `&*r` and `&mut *r`, where the borrow has the same kind as the reference `r`.
+pub struct RedundantRefDeref;
+
+impl Lint for RedundantRefDeref {
+    fn name(&self) -> &'static str {
+        "redundant_ref_deref"
+    }
+
+    fn description(&self) -> &'static str {
+        "borrow of a dereference, `&*r` is the same as `r`"
+    }
+}
+
+impl ExpLint for RedundantRefDeref {
+    fn visit_exp(&self, cx: &LintContext, exp: &ExpData) {
+        if let ExpData::Call(id, Operation::Borrow(kind), args) = exp {
+            if let ExpData::Call(_, Operation::Deref, deref_args) =
+                strip_sequence(&args[0]).as_ref()
+            {
+                let env = cx.env();
+                if matches!(
+                    env.get_node_type(deref_args[0].node_id()),
+                    Type::Reference(ref_kind, _) if ref_kind == *kind
+                ) {
+                    cx.report(
+                        self,
+                        &env.get_node_loc(*id),
+                        "redundant borrow of a dereference: the reference can be used directly",
+                    )
+                }
+            }
+        }
+    }
+}
+
+/// Reports `x = x`.
+pub struct SelfAssignment;
+
+impl Lint for SelfAssignment {
+    fn name(&self) -> &'static str {
+        "self_assignment"
+    }
+
+    fn description(&self) -> &'static str {
+        "assignment of a variable to itself, `x = x`"
+    }
+}
+
+impl ExpLint for SelfAssignment {
+    fn visit_exp(&self, cx: &LintContext, exp: &ExpData) {
+        if let ExpData::Assign(id, Pattern::Var(_, lhs), rhs) = exp {
+            let is_self = match strip_sequence(rhs).as_ref() {
+                ExpData::LocalVar(_, rhs) => rhs == lhs,
+                // Function parameters are represented as temporaries.
+                ExpData::Temporary(_, idx) => cx
+                    .fun()
+                    .get_parameters()
+                    .get(*idx)
+                    .map_or(false, |param| param.0 == *lhs),
+                _ => false,
+            };
+            if is_self {
+                let env = cx.env();
+                cx.report(
+                    self,
+                    &env.get_node_loc(*id),
+                    &format!(
+                        "self-assignment of `{}` has no effect",
+                        lhs.display(env.symbol_pool())
+                    ),
+                )
+            }
+        }
+    }
+}
+
+/// Reports boolean expressions which can be written more simply.
+pub struct SimplifiableBooleanExpression;
+
+impl Lint for SimplifiableBooleanExpression {
+    fn name(&self) -> &'static str {
+        "simplifiable_boolean_expression"
+    }
+
+    fn description(&self) -> &'static str {
+        "boolean expression with literal operands or double negation which can be simplified"
+    }
+}
+
+impl ExpLint for SimplifiableBooleanExpression {
+    fn visit_exp(&self, cx: &LintContext, exp: &ExpData) {
+        let msg = match exp {
+            ExpData::IfElse(_, _, then, else_) => {
+                match (as_bool_literal(then), as_bool_literal(else_)) {
+                    (Some(true), Some(false)) => {
+                        "`if (c) true else false` can be simplified to `c`"
+                    },
+                    (Some(false), Some(true)) => {
+                        "`if (c) false else true` can be simplified to `!c`"
+                    },
+                    _ => return,
+                }
+            },
+            ExpData::Call(_, Operation::Eq | Operation::Neq, args)
+                if as_bool_literal(&args[0]).is_some() != as_bool_literal(&args[1]).is_some() =>
+            {
+                "comparison with a boolean literal can be simplified to the operand or its \
+                 negation"
+            },
+            ExpData::Call(_, Operation::And | Operation::Or, args)
+                if args.iter().any(|arg| as_bool_literal(arg).is_some()) =>
+            {
+                "boolean operator with a literal operand can be simplified"
+            },
+            ExpData::Call(_, Operation::Not, args)
+                if matches!(
+                    strip_sequence(&args[0]).as_ref(),
+                    ExpData::Call(_, Operation::Not, _)
+                ) =>
+            {
+                "double negation `!!e` can be simplified to `e`"
+            },
+            _ => return,
+        };
+        cx.report(self, &cx.env().get_node_loc(exp.node_id()), msg)
+    }
+}
+
+/// Reports comparisons where both operands are constants, which always have the same result.
+pub struct ConstantComparison;
+
+impl Lint for ConstantComparison {
+    fn name(&self) -> &'static str {
+        "constant_comparison"
+    }
+
+    fn description(&self) -> &'static str {
+        "comparison of two constants, which always has the same result"
+    }
+}
+
+impl ExpLint for ConstantComparison {
+    fn visit_exp(&self, cx: &LintContext, exp: &ExpData) {
+        use Operation::*;
+        if let ExpData::Call(id, Eq | Neq | Lt | Gt | Le | Ge, args) = exp {
+            if args
+                .iter()
+                .all(|arg| matches!(strip_sequence(arg).as_ref(), ExpData::Value(..)))
+            {
+                cx.report(
+                    self,
+                    &cx.env().get_node_loc(*id),
+                    "comparison of two constants always has the same result",
+                )
+            }
+        }
+    }
+}
@@ -0,0 +1,271 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Implements a pluggable lint framework on top of the model AST and the stackless bytecode.
+//!
+//! Lints are not correctness checks: they report code which is legal, but likely not what the
+//! author intended, or which can be written more simply. They only run when the
+//! `lint-checks` experiment is on (e.g. via `aptos move lint`), and only on target modules.
+//!
+//! There are two kinds of lints:
+//! - `ExpLint`s visit each expression of a function body before inlining, so they see the code
+//!   as written by the user. They are run by the env pipeline step `run_exp_lints`.
+//! - `BytecodeLint`s inspect the stackless bytecode of a function, and can use the annotations
+//!   of previous pipeline steps (e.g. live variables). They are run by the
+//!   `BytecodeLintProcessor`.
+//!
+//! New lints are added by implementing one of the traits and registering an instance in
+//! `exp_lints()` or `bytecode_lints()`.
+//!
+//! Lints can be suppressed or turned into errors in source, on a module or a function, with
+//! `#[lint::allow(name, ..)]` and `#[lint::deny(name, ..)]`. Attributes on a function take
+//! precedence over those on its module.
+//!
+//! Note that an unused `acquires` annotation is not a lint: it is already reported as an error
+//! by the acquires checker.
+
+mod bytecode_lints;
+mod exp_lints;
+
+use codespan_reporting::diagnostic::Severity;
+use move_compiler::shared::known_attributes::LintAttribute;
+use move_model::{
+    ast::{Attribute, ExpData},
+    model::{FunctionEnv, GlobalEnv, Loc, ModuleEnv},
+};
+use move_stackless_bytecode::{
+    function_target::{FunctionData, FunctionTarget},
+    function_target_pipeline::{FunctionTargetProcessor, FunctionTargetsHolder},
+};
+use std::collections::BTreeMap;
+
+/// Common interface of all lints.
+pub trait Lint {
+    /// The name of the lint, as used in `#[lint::allow(..)]` and `#[lint::deny(..)]`.
+    fn name(&self) -> &'static str;
+
+    /// A one-line description of what the lint reports.
+    fn description(&self) -> &'static str;
+}
+
+/// A lint which is checked on each expression of a function body.
+pub trait ExpLint: Lint {
+    /// Visits one expression. Subexpressions are visited separately.
+    fn visit_exp(&self, cx: &LintContext, exp: &ExpData);
+}
+
+/// A lint which is checked on the stackless bytecode of a function.
+pub trait BytecodeLint: Lint {
+    /// Checks the given function target.
+    fn check(&self, cx: &LintContext, target: &FunctionTarget);
+}
+
+/// Returns all lints which are checked on the AST.
+pub fn exp_lints() -> Vec<Box<dyn ExpLint>> {
+    vec![
+        Box::new(exp_lints::NeedlessBorrow),
+        Box::new(exp_lints::RedundantRefDeref),
+        Box::new(exp_lints::SelfAssignment),
+        Box::new(exp_lints::SimplifiableBooleanExpression),
+        Box::new(exp_lints::ConstantComparison),
+    ]
+}
+
+/// Returns all lints which are checked on the stackless bytecode.
+pub fn bytecode_lints() -> Vec<Box<dyn BytecodeLint>> {
+    vec![Box::new(bytecode_lints::UnnecessaryCopy)]
+}
+
+/// Returns the names and descriptions of all registered lints, sorted by name.
+pub fn all_lints() -> Vec<(&'static str, &'static str)> {
+    let mut lints = exp_lints()
+        .iter()
+        .map(|l| (l.name(), l.description()))
+        .chain(bytecode_lints().iter().map(|l| (l.name(), l.description())))
+        .collect::<Vec<_>>();
+    lints.sort();
+    lints
+}
+
+/// The level at which a lint is reported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LintLevel {
+    /// The lint is not reported.
+    Allow,
+    /// The lint is reported as a warning. This is the default.
+    Warn,
+    /// The lint is reported as an error.
+    Deny,
+}
+
+/// The context in which lints of a single function are checked.
+pub struct LintContext<'env> {
+    env: &'env GlobalEnv,
+    fun: FunctionEnv<'env>,
+    levels: BTreeMap<String, LintLevel>,
+}
+
+impl<'env> LintContext<'env> {
+    /// Creates the context for the given function, determining lint levels from the
+    /// attributes of the function and its module. If `report_attribute_errors` is set,
+    /// malformed lint attributes on the function are reported.
+    fn new(fun: &FunctionEnv<'env>, report_attribute_errors: bool) -> Self {
+        let env = fun.module_env.env;
+        let mut levels = BTreeMap::new();
+        collect_lint_levels(env, fun.module_env.get_attributes(), false, &mut levels);
+        collect_lint_levels(
+            env,
+            fun.get_attributes(),
+            report_attribute_errors,
+            &mut levels,
+        );
+        Self {
+            env,
+            fun: fun.clone(),
+            levels,
+        }
+    }
+
+    /// Returns the global environment.
+    pub fn env(&self) -> &'env GlobalEnv {
+        self.env
+    }
+
+    /// Returns the function which is checked.
+    pub fn fun(&self) -> &FunctionEnv<'env> {
+        &self.fun
+    }
+
+    /// Returns the level at which the given lint is reported.
+    pub fn level(&self, lint: &dyn Lint) -> LintLevel {
+        self.levels
+            .get(lint.name())
+            .copied()
+            .unwrap_or(LintLevel::Warn)
+    }
+
+    /// Reports a finding of the given lint at `loc`, unless the lint is allowed.
+    pub fn report(&self, lint: &dyn Lint, loc: &Loc, msg: &str) {
+        let severity = match self.level(lint) {
+            LintLevel::Allow => return,
+            LintLevel::Warn => Severity::Warning,
+            LintLevel::Deny => Severity::Error,
+        };
+        self.env
+            .diag_with_notes(severity, loc, &format!("[lint] {}", msg), vec![format!(
+                "to suppress this lint, add `#[{}({})]` to the enclosing function or module",
+                LintAttribute::ALLOW,
+                lint.name()
+            )]);
+    }
+}
+
+/// Collects the lint levels given by `#[lint::allow(..)]` and `#[lint::deny(..)]` in `attrs`
+/// into `levels`, overriding levels which are already present.
+fn collect_lint_levels(
+    env: &GlobalEnv,
+    attrs: &[Attribute],
+    report_errors: bool,
+    levels: &mut BTreeMap<String, LintLevel>,
+) {
+    let known_lints = all_lints();
+    for attr in attrs {
+        let attr_name = env.symbol_pool().string(attr.name());
+        let level = match attr_name.as_str() {
+            LintAttribute::ALLOW => LintLevel::Allow,
+            LintAttribute::DENY => LintLevel::Deny,
+            _ => continue,
+        };
+        let args = match attr {
+            Attribute::Apply(_, _, args) => args,
+            Attribute::Assign(id, _, _) => {
+                if report_errors {
+                    env.diag(
+                        Severity::Warning,
+                        &env.get_node_loc(*id),
+                        &format!(
+                            "expected a list of lint names, as in `#[{}(..)]`",
+                            attr_name
+                        ),
+                    );
+                }
+                continue;
+            },
+        };
+        for arg in args {
+            let lint_name = env.symbol_pool().string(arg.name());
+            let is_known = matches!(arg, Attribute::Apply(_, _, nested) if nested.is_empty())
+                && known_lints
+                    .iter()
+                    .any(|(name, _)| *name == lint_name.as_str());
+            if is_known {
+                levels.insert(lint_name.to_string(), level);
+            } else if report_errors {
+                env.diag(
+                    Severity::Warning,
+                    &env.get_node_loc(arg.node_id()),
+                    &format!("unknown lint `{}`", lint_name),
+                );
+            }
+        }
+    }
+}
+
+/// Runs all AST lints on the target modules of the environment.
+pub fn run_exp_lints(env: &GlobalEnv) {
+    let lints = exp_lints();
+    for module in env.get_modules() {
+        if module.is_target() {
+            check_module_attributes(&module);
+            for fun in module.get_functions() {
+                let cx = LintContext::new(&fun, true);
+                if let Some(def) = fun.get_def() {
+                    def.visit_pre_order(&mut |exp| {
+                        for lint in &lints {
+                            lint.visit_exp(&cx, exp)
+                        }
+                        true
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// Reports malformed lint attributes on the module.
+fn check_module_attributes(module: &ModuleEnv) {
+    collect_lint_levels(
+        module.env,
+        module.get_attributes(),
+        true,
+        &mut BTreeMap::new(),
+    );
+}
+
+/// A processor which runs all bytecode lints. Requires live variable annotations.
+pub struct BytecodeLintProcessor {}
+
+impl FunctionTargetProcessor for BytecodeLintProcessor {
+    fn process(
+        &self,
+        _targets: &mut FunctionTargetsHolder,
+        func_env: &FunctionEnv,
+        data: FunctionData,
+        _scc_opt: Option<&[FunctionEnv]>,
+    ) -> FunctionData {
+        if func_env.is_native() || !func_env.module_env.is_target() {
+            return data;
+        }
+        // Attribute errors have already been reported by the AST lints.
+        let cx = LintContext::new(func_env, false);
+        let target = FunctionTarget::new(func_env, &data);
+        for lint in bytecode_lints() {
+            lint.check(&cx, &target)
+        }
+        data
+    }
+
+    fn name(&self) -> String {
+        "BytecodeLintProcessor".to_string()
+    }
+}
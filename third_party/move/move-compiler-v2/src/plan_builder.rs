@@ -17,7 +17,10 @@ use codespan_reporting::diagnostic::Severity;
 use move_command_line_common::{address::NumericalAddress, parser::NumberFormat};
 use move_compiler::{
     shared::known_attributes::{AttributeKind, TestingAttribute},
-    unit_test::{ExpectedFailure, ExpectedMoveError, ModuleTestPlan, TestCase},
+    unit_test::{
+        ExpectedFailure, ExpectedMoveError, ModuleTestPlan, RandomArgumentType, TestArgument,
+        TestCase,
+    },
 };
 use move_core_types::{
     identifier::Identifier, language_storage::ModuleId, value::MoveValue, vm_status::StatusCode,
@@ -26,7 +29,7 @@ use move_model::{
     ast::{Address, Attribute, AttributeValue, ModuleName, Value},
     model::{FunctionEnv, GlobalEnv, Loc, ModuleEnv, Parameter},
     symbol::Symbol,
-    ty::{PrimitiveType, Type},
+    ty::{PrimitiveType, ReferenceKind, Type},
 };
 use num::{BigInt, ToPrimitive};
 use std::collections::BTreeMap;
@@ -117,12 +120,26 @@ fn build_test_info(
     let attrs = function.get_attributes();
     let expected_failure_name = env.symbol_pool().make(TestingAttribute::EXPECTED_FAILURE);
     let test_name = env.symbol_pool().make(TestingAttribute::TEST);
+    let fuzz_name = env.symbol_pool().make(TestingAttribute::FUZZ);
     let test_only_name = env.symbol_pool().make(TestingAttribute::TEST_ONLY);
 
     let test_attribute_opt = attrs.iter().find(|a| a.name() == test_name);
+    let fuzz_attribute_opt = attrs.iter().find(|a| a.name() == fuzz_name);
     let abort_attribute_opt = attrs.iter().find(|a| a.name() == expected_failure_name);
 
-    let test_attribute = match test_attribute_opt {
+    // A #[test] function cannot also be annotated #[fuzz]
+    if let (Some(test_attribute), Some(fuzz_attribute)) = (test_attribute_opt, fuzz_attribute_opt) {
+        let msg = "Function annotated as both #[test(...)] and #[fuzz(...)]. You need to declare \
+                   it as either one or the other";
+        let fuzz_loc = env.get_node_loc(fuzz_attribute.node_id());
+        let test_loc = env.get_node_loc(test_attribute.node_id());
+        env.error_with_labels(&fn_id_loc, "invalid usage of known attribute", vec![
+            (fuzz_loc, msg.to_string()),
+            (test_loc, "Previously annotated here".to_string()),
+        ]);
+    }
+
+    let test_attribute = match test_attribute_opt.or(fuzz_attribute_opt) {
         None => {
             // expected failures cannot be annotated on non-#[test] functions
             if let Some(abort_attribute) = abort_attribute_opt {
@@ -159,13 +176,34 @@ fn build_test_info(
     }
 
     let test_annotation_params = parse_test_attribute(env, test_attribute, 0);
+    let is_random_test = fuzz_attribute_opt.is_some() || has_random_args(env, test_attribute);
 
     let mut arguments = Vec::new();
+    let mut random_args = Vec::new();
     for param in function.get_parameters_ref() {
-        let Parameter(var, _ty, var_loc) = &param;
+        let Parameter(var, ty, var_loc) = &param;
+        let var_name = || env.symbol_pool().string(*var).to_string();
 
         match test_annotation_params.get(var) {
+            Some(value) if is_random_test => {
+                random_args.push((var_name(), TestArgument::Assigned(value.clone())))
+            },
             Some(value) => arguments.push(value.clone()),
+            None if is_random_test => match convert_random_argument_type(ty) {
+                Some(ty) => random_args.push((var_name(), TestArgument::Random(ty))),
+                None => {
+                    let unsupported_type_msg = "Unsupported type for a random test argument. \
+                                                Expected a primitive type, 'signer', '&signer' \
+                                                or a vector of those";
+                    env.error_with_labels(&fn_id_loc, "unable to generate test", vec![
+                        (var_loc.clone(), unsupported_type_msg.to_string()),
+                        (
+                            test_attribute_loc.clone(),
+                            "Random arguments requested here".to_string(),
+                        ),
+                    ]);
+                },
+            },
             None => {
                 let missing_param_msg = "Missing test parameter assignment in test. Expected a \
                                          parameter to be assigned in this attribute";
@@ -190,6 +228,51 @@ fn build_test_info(
         test_name: fn_name_str.to_string(),
         arguments,
         expected_failure,
+        random_args: is_random_test.then_some(random_args),
+    })
+}
+
+// Returns true if the test is annotated with #[test(random_args)], in which case the parameters
+// which are not assigned in the attribute get randomly generated arguments. Tests annotated with
+// #[fuzz] always get random arguments.
+fn has_random_args(env: &GlobalEnv, test_attribute: &Attribute) -> bool {
+    match test_attribute {
+        Attribute::Apply(_, _, attrs) => attrs.iter().any(|attr| {
+            matches!(attr, Attribute::Apply(_, sym, args)
+                if args.is_empty()
+                    && env.symbol_pool().string(*sym).as_str() == TestingAttribute::RANDOM_ARGS)
+        }),
+        Attribute::Assign(..) => false,
+    }
+}
+
+fn convert_random_argument_type(ty: &Type) -> Option<RandomArgumentType> {
+    use RandomArgumentType as R;
+    Some(match ty {
+        Type::Primitive(prim_ty) => match prim_ty {
+            PrimitiveType::Bool => R::Bool,
+            PrimitiveType::U8 => R::U8,
+            PrimitiveType::U16 => R::U16,
+            PrimitiveType::U32 => R::U32,
+            PrimitiveType::U64 => R::U64,
+            PrimitiveType::U128 => R::U128,
+            PrimitiveType::U256 => R::U256,
+            PrimitiveType::Address => R::Address,
+            PrimitiveType::Signer => R::Signer,
+            _ => return None,
+        },
+        // only signers can be passed by reference
+        Type::Reference(ReferenceKind::Immutable, inner_ty)
+            if matches!(**inner_ty, Type::Primitive(PrimitiveType::Signer)) =>
+        {
+            R::Signer
+        },
+        Type::Vector(elem_ty) => match convert_random_argument_type(elem_ty)? {
+            // signers cannot be passed in vectors
+            R::Signer => return None,
+            elem_ty => R::Vector(Box::new(elem_ty)),
+        },
+        _ => return None,
     })
 }
 
@@ -203,6 +286,14 @@ fn parse_test_attribute(
     depth: usize,
 ) -> BTreeMap<Symbol, MoveValue> {
     match test_attribute {
+        // handled by `has_random_args`
+        Attribute::Apply(_, sym, args)
+            if depth == 1
+                && args.is_empty()
+                && env.symbol_pool().string(*sym).as_str() == TestingAttribute::RANDOM_ARGS =>
+        {
+            BTreeMap::new()
+        },
         Attribute::Apply(id, _, _) if depth > 0 => {
             let aloc = env.get_node_loc(*id);
             env.error(&aloc, "Unexpected nested attribute in test declaration");
@@ -210,7 +301,8 @@ fn parse_test_attribute(
         },
         Attribute::Apply(_id, sym, vec) => {
             assert!(
-                *TestingAttribute::TEST == env.symbol_pool().string(*sym).to_string(),
+                [TestingAttribute::TEST, TestingAttribute::FUZZ]
+                    .contains(&env.symbol_pool().string(*sym).as_str()),
                 "ICE: We should only be parsing a raw test attribute"
             );
             vec.iter()
@@ -0,0 +1,53 @@
+module 0x42::exp_lints {
+    fun needless_borrow(x: u64): u64 {
+        *&x
+    }
+
+    fun redundant_ref_deref(r: &u64): &u64 {
+        &*r
+    }
+
+    fun redundant_mut_ref_deref(r: &mut u64): &mut u64 {
+        &mut *r
+    }
+
+    fun self_assignment(x: u64): u64 {
+        let y = x + 1;
+        y = y;
+        x = x;
+        x + y
+    }
+
+    fun bool_if(c: bool): bool {
+        if (c) true else false
+    }
+
+    fun bool_if_not(c: bool): bool {
+        if (c) { false } else { true }
+    }
+
+    fun bool_eq(c: bool): bool {
+        c == true
+    }
+
+    fun bool_double_not(c: bool): bool {
+        !!c
+    }
+
+    fun bool_and(c: bool): bool {
+        c && true
+    }
+
+    fun bool_or(c: bool): bool {
+        false || c
+    }
+
+    fun constant_comparison(): bool {
+        1 == 2
+    }
+
+    fun no_lints(x: u64, r: &u64, c: bool): bool {
+        let y = *r + x;
+        y > 1 && c
+    }
+}
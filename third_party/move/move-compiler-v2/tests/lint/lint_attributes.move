@@ -0,0 +1,26 @@
+#[lint::allow(needless_borrow)]
+module 0x42::lint_attributes {
+    fun allowed_by_module(x: u64): u64 {
+        *&x
+    }
+
+    #[lint::deny(needless_borrow)]
+    fun denied_by_function(x: u64): u64 {
+        *&x
+    }
+
+    #[lint::allow(constant_comparison, simplifiable_boolean_expression)]
+    fun allowed_by_function(c: bool): bool {
+        1 == 2 || c == true
+    }
+
+    #[lint::deny(constant_comparison)]
+    fun denied_constant_comparison(): bool {
+        1 < 2
+    }
+
+    #[lint::allow(no_such_lint)]
+    fun unknown_lint(c: bool): bool {
+        !!c
+    }
+}
@@ -0,0 +1,30 @@
+module 0x42::unnecessary_copy {
+    fun consume(x: u64): u64 {
+        x
+    }
+
+    fun copy_last_use(x: u64): u64 {
+        consume(copy x)
+    }
+
+    fun copy_to_local(x: u64): u64 {
+        let y = copy x;
+        y
+    }
+
+    fun copy_needed(x: u64): u64 {
+        consume(copy x) + x
+    }
+
+    fun copy_in_loop(x: u64): u64 {
+        let i = 0;
+        while (i < 10) {
+            i = i + consume(copy x);
+        };
+        i
+    }
+
+    fun copy_underscore(_x: u64): u64 {
+        consume(copy _x)
+    }
+}
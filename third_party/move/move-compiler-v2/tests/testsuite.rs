@@ -159,6 +159,19 @@ const TEST_CONFIGS: Lazy<BTreeMap<&str, TestConfig>> = Lazy::new(|| {
             dump_bytecode: DumpLevel::None,
             dump_bytecode_filter: None,
         },
+        // Tests for lints
+        TestConfig {
+            name: "lint",
+            runner: |p| run_test(p, get_config_by_name("lint")),
+            include: vec!["/lint/"],
+            exclude: vec![],
+            exp_suffix: None,
+            options: opts.clone().set_experiment(Experiment::LINT_CHECKS, true),
+            stop_after: StopAfter::BytecodePipeline(Some("BytecodeLintProcessor")),
+            dump_ast: DumpLevel::None,
+            dump_bytecode: DumpLevel::None,
+            dump_bytecode_filter: None,
+        },
         // Tests for lambda lifting
         TestConfig {
             name: "lambda-lifting",
@@ -219,19 +219,22 @@ fn create_test_poison(mloc: Loc) -> P::ModuleMember {
 }
 
 // A module member should be removed if:
-// * It is annotated as a test function (test_only, test, abort) and test mode is not set; or
-// * If it is a library and is annotated as #[test]
+// * It is annotated as a test function (test_only, test, fuzz, abort) and test mode is not set; or
+// * If it is a library and is annotated as #[test] or #[fuzz]
 fn should_remove_node(env: &CompilationEnv, attrs: &[P::Attributes], is_source_def: bool) -> bool {
     use known_attributes::TestingAttribute;
     let flattened_attrs: Vec<_> = attrs.iter().flat_map(test_attributes).collect();
-    let is_test_only = flattened_attrs
-        .iter()
-        .any(|attr| matches!(attr.1, TestingAttribute::Test | TestingAttribute::TestOnly));
+    let is_test_only = flattened_attrs.iter().any(|attr| {
+        matches!(
+            attr.1,
+            TestingAttribute::Test | TestingAttribute::Fuzz | TestingAttribute::TestOnly
+        )
+    });
     is_test_only && !env.flags().keep_testing_functions()
         || (!is_source_def
             && flattened_attrs
                 .iter()
-                .any(|attr| attr.1 == TestingAttribute::Test))
+                .any(|attr| matches!(attr.1, TestingAttribute::Test | TestingAttribute::Fuzz)))
 }
 
 fn test_attributes(attrs: &P::Attributes) -> Vec<(Loc, known_attributes::TestingAttribute)> {
@@ -244,7 +247,8 @@ fn test_attributes(attrs: &P::Attributes) -> Vec<(Loc, known_attributes::Testing
                 KnownAttribute::Testing(test_attr) => Some((attr.loc, test_attr)),
                 KnownAttribute::Verification(_)
                 | KnownAttribute::Native(_)
-                | KnownAttribute::Deprecation(_) => None,
+                | KnownAttribute::Deprecation(_)
+                | KnownAttribute::Lint(_) => None,
             },
         )
         .collect()
@@ -36,6 +36,32 @@ pub struct TestCase {
     pub test_name: TestName,
     pub arguments: Vec<MoveValue>,
     pub expected_failure: Option<ExpectedFailure>,
+    // for tests annotated with #[test(random_args)] or #[fuzz], the name and argument of each
+    // parameter. `arguments` is empty for such tests
+    pub random_args: Option<Vec<(String, TestArgument)>>,
+}
+
+#[derive(Debug, Clone)]
+pub enum TestArgument {
+    // a value assigned in the #[test] attribute
+    Assigned(MoveValue),
+    // a value of this type is generated by the test runner
+    Random(RandomArgumentType),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RandomArgumentType {
+    Bool,
+    U8,
+    U16,
+    U32,
+    U64,
+    U128,
+    U256,
+    Address,
+    // passed as `signer` or `&signer`
+    Signer,
+    Vector(Box<RandomArgumentType>),
 }
 
 #[derive(Debug, Clone)]
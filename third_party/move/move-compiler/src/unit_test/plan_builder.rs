@@ -8,13 +8,18 @@ use crate::{
     expansion::ast::{
         self as E, Address, Attribute, AttributeValue, ModuleAccess_, ModuleIdent, ModuleIdent_,
     },
+    hlir::ast as H,
+    naming::ast::BuiltinTypeName_,
     parser::ast::ConstantName,
     shared::{
         known_attributes::{AttributeKind, KnownAttribute, TestingAttribute},
         unique_map::UniqueMap,
         CompilationEnv, Identifier, NumericalAddress,
     },
-    unit_test::{ExpectedFailure, ExpectedMoveError, ModuleTestPlan, TestCase},
+    unit_test::{
+        ExpectedFailure, ExpectedMoveError, ModuleTestPlan, RandomArgumentType, TestArgument,
+        TestCase,
+    },
 };
 use move_core_types::{
     account_address::AccountAddress as MoveAddress, language_storage::ModuleId, u256::U256,
@@ -124,10 +129,23 @@ fn build_test_info<'func>(
     const IN_THIS_TEST_MSG: &str = "Error found in this test";
 
     let test_attribute_opt = get_attrs(TestingAttribute::Test);
+    let fuzz_attribute_opt = get_attrs(TestingAttribute::Fuzz);
     let abort_attribute_opt = get_attrs(TestingAttribute::ExpectedFailure);
     let test_only_attribute_opt = get_attrs(TestingAttribute::TestOnly);
 
-    let test_attribute = match test_attribute_opt {
+    // A #[test] function cannot also be annotated #[fuzz]
+    if let (Some(test_attribute), Some(fuzz_attribute)) = (test_attribute_opt, fuzz_attribute_opt) {
+        let msg = "Function annotated as both #[test(...)] and #[fuzz(...)]. You need to declare \
+                   it as either one or the other";
+        context.env.add_diag(diag!(
+            Attributes::InvalidUsage,
+            (fuzz_attribute.loc, msg),
+            (test_attribute.loc, PREVIOUSLY_ANNOTATED_MSG),
+            (fn_loc, IN_THIS_TEST_MSG),
+        ))
+    }
+
+    let test_attribute = match test_attribute_opt.or(fuzz_attribute_opt) {
         None => {
             // expected failures cannot be annotated on non-#[test] functions
             if let Some(abort_attribute) = abort_attribute_opt {
@@ -158,10 +176,30 @@ fn build_test_info<'func>(
     }
 
     let test_annotation_params = parse_test_attribute(context, test_attribute, 0);
+    let is_random_test = fuzz_attribute_opt.is_some() || has_random_args(test_attribute);
     let mut arguments = Vec::new();
-    for (var, _) in &function.signature.parameters {
+    let mut random_args = Vec::new();
+    for (var, ty) in &function.signature.parameters {
         match test_annotation_params.get(&var.value()) {
+            Some(value) if is_random_test => random_args.push((
+                var.value().to_string(),
+                TestArgument::Assigned(value.clone()),
+            )),
             Some(value) => arguments.push(value.clone()),
+            None if is_random_test => match convert_random_argument_type(ty) {
+                Some(ty) => random_args.push((var.value().to_string(), TestArgument::Random(ty))),
+                None => {
+                    let unsupported_type_msg = "Unsupported type for a random test argument. \
+                                                Expected a primitive type, 'signer', '&signer' \
+                                                or a vector of those";
+                    context.env.add_diag(diag!(
+                        Attributes::InvalidTest,
+                        (ty.loc, unsupported_type_msg),
+                        (var.loc(), "Corresponding to this parameter"),
+                        (fn_loc, IN_THIS_TEST_MSG),
+                    ))
+                },
+            },
             None => {
                 let missing_param_msg = "Missing test parameter assignment in test. Expected a \
                                          parameter to be assigned in this attribute";
@@ -184,6 +222,64 @@ fn build_test_info<'func>(
         test_name: fn_name.to_string(),
         arguments,
         expected_failure,
+        random_args: is_random_test.then_some(random_args),
+    })
+}
+
+// Returns true if the attribute declares a test, i.e. is #[test] or #[fuzz]
+fn is_test_attribute_name(name: &str) -> bool {
+    name == TestingAttribute::Test.name() || name == TestingAttribute::Fuzz.name()
+}
+
+// Returns true if the test is annotated with #[test(random_args)], in which case the parameters
+// which are not assigned in the attribute get randomly generated arguments. Tests annotated with
+// #[fuzz] always get random arguments.
+fn has_random_args(sp!(_, test_attribute): &E::Attribute) -> bool {
+    match test_attribute {
+        E::Attribute_::Parameterized(_, attributes) => {
+            attributes.iter().any(|(_, _, sp!(_, attr))| {
+                matches!(attr, E::Attribute_::Name(nm)
+                    if nm.value.as_str() == TestingAttribute::RANDOM_ARGS)
+            })
+        },
+        _ => false,
+    }
+}
+
+fn convert_random_argument_type(sp!(_, ty): &H::SingleType) -> Option<RandomArgumentType> {
+    match ty {
+        H::SingleType_::Base(base_ty) => convert_random_argument_base_type(base_ty),
+        // only signers can be passed by reference
+        H::SingleType_::Ref(false, base_ty) => match convert_random_argument_base_type(base_ty)? {
+            RandomArgumentType::Signer => Some(RandomArgumentType::Signer),
+            _ => None,
+        },
+        H::SingleType_::Ref(true, _) => None,
+    }
+}
+
+fn convert_random_argument_base_type(sp!(_, ty): &H::BaseType) -> Option<RandomArgumentType> {
+    use BuiltinTypeName_ as B;
+    use RandomArgumentType as R;
+    let H::BaseType_::Apply(_, sp!(_, H::TypeName_::Builtin(sp!(_, builtin))), ty_args) = ty else {
+        return None;
+    };
+    Some(match (builtin, ty_args.as_slice()) {
+        (B::Bool, []) => R::Bool,
+        (B::U8, []) => R::U8,
+        (B::U16, []) => R::U16,
+        (B::U32, []) => R::U32,
+        (B::U64, []) => R::U64,
+        (B::U128, []) => R::U128,
+        (B::U256, []) => R::U256,
+        (B::Address, []) => R::Address,
+        (B::Signer, []) => R::Signer,
+        (B::Vector, [elem_ty]) => match convert_random_argument_base_type(elem_ty)? {
+            // signers cannot be passed in vectors
+            R::Signer => return None,
+            elem_ty => R::Vector(Box::new(elem_ty)),
+        },
+        _ => return None,
     })
 }
 
@@ -199,6 +295,10 @@ fn parse_test_attribute(
     use E::Attribute_ as EA;
 
     match test_attribute {
+        // handled by `has_random_args`
+        EA::Name(nm) if depth == 1 && nm.value.as_str() == TestingAttribute::RANDOM_ARGS => {
+            BTreeMap::new()
+        },
         EA::Name(_) | EA::Parameterized(_, _) if depth > 0 => {
             context.env.add_diag(diag!(
                 Attributes::InvalidTest,
@@ -208,7 +308,7 @@ fn parse_test_attribute(
         },
         EA::Name(nm) => {
             assert!(
-                nm.value.as_str() == TestingAttribute::Test.name() && depth == 0,
+                is_test_attribute_name(nm.value.as_str()) && depth == 0,
                 "ICE: We should only be parsing a raw test attribute"
             );
             BTreeMap::new()
@@ -240,7 +340,7 @@ fn parse_test_attribute(
         },
         EA::Parameterized(nm, attributes) => {
             assert!(
-                nm.value.as_str() == TestingAttribute::Test.name() && depth == 0,
+                is_test_attribute_name(nm.value.as_str()) && depth == 0,
                 "ICE: We should only be parsing a raw test attribute"
             );
             attributes
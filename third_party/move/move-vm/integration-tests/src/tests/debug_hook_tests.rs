@@ -0,0 +1,281 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::compiler::{as_module, compile_units};
+use move_binary_format::{errors::VMResult, file_format::Bytecode};
+use move_core_types::{
+    account_address::AccountAddress,
+    identifier::Identifier,
+    language_storage::{ModuleId, StructTag, TypeTag},
+    value::MoveValue,
+};
+use move_vm_runtime::{
+    debug_hook::{clear_debug_hook, set_debug_hook, DebugFrame, DebugHook, DebugState},
+    module_traversal::*,
+    move_vm::MoveVM,
+};
+use move_vm_test_utils::InMemoryStorage;
+use move_vm_types::gas::UnmeteredGasMeter;
+use std::sync::{Arc, Mutex};
+
+const TEST_ADDR: AccountAddress = AccountAddress::new([42; AccountAddress::LENGTH]);
+
+/// The debug hook is installed for the whole process, so tests installing one must not run
+/// concurrently.
+static HOOK_LOCK: Mutex<()> = Mutex::new(());
+
+const CODE: &str = r#"
+    module {{ADDR}}::debug_hook_tests {
+        struct R has key { value: u64 }
+
+        fun add(a: u64, b: u64): u64 {
+            a + b
+        }
+
+        fun call_add(x: u64): u64 {
+            let y = x * 2;
+            add(x, y)
+        }
+
+        fun deref(r: &u64): u64 {
+            *r
+        }
+
+        fun call_deref(x: u64): u64 {
+            deref(&x)
+        }
+
+        fun id<T: drop>(x: T): T {
+            x
+        }
+
+        fun call_id(): u8 {
+            id<u8>(1)
+        }
+
+        fun publish(s: signer) {
+            move_to(&s, R { value: 7 })
+        }
+
+        fun read(addr: address): u64 acquires R {
+            borrow_global<R>(addr).value
+        }
+
+        fun has(addr: address): bool {
+            exists<R>(addr)
+        }
+    }
+"#;
+
+/// An instruction of the test module, as observed by the hook.
+struct ExecutedInstruction {
+    function: String,
+    pc: u16,
+    depth: usize,
+    instr: Bytecode,
+    frames: Vec<DebugFrame>,
+}
+
+/// A hook recording the instructions of the test module, and the reads of its resource `R`.
+#[derive(Default)]
+struct Recorder {
+    instructions: Mutex<Vec<ExecutedInstruction>>,
+    reads: Mutex<Vec<(AccountAddress, Option<Vec<u8>>)>>,
+}
+
+impl DebugHook for Recorder {
+    fn on_instruction(&self, state: &DebugState) {
+        // Other tests may execute code while the hook is installed.
+        if state.module_id() != Some(&module_id()) {
+            return;
+        }
+        self.instructions.lock().unwrap().push(ExecutedInstruction {
+            function: state.function_name().to_string(),
+            pc: state.pc(),
+            depth: state.stack_depth(),
+            instr: state.instruction().clone(),
+            frames: state.frames(),
+        });
+    }
+
+    fn on_resource_read(&self, addr: AccountAddress, ty: &TypeTag, value: Option<&[u8]>) {
+        if *ty == resource_type() {
+            self.reads
+                .lock()
+                .unwrap()
+                .push((addr, value.map(<[u8]>::to_vec)));
+        }
+    }
+}
+
+fn module_id() -> ModuleId {
+    ModuleId::new(TEST_ADDR, Identifier::new("debug_hook_tests").unwrap())
+}
+
+fn resource_type() -> TypeTag {
+    TypeTag::Struct(Box::new(StructTag {
+        address: TEST_ADDR,
+        module: Identifier::new("debug_hook_tests").unwrap(),
+        name: Identifier::new("R").unwrap(),
+        type_args: vec![],
+    }))
+}
+
+fn serialize(value: MoveValue) -> Vec<u8> {
+    value.simple_serialize().unwrap()
+}
+
+/// Calls the functions of the test module in one session with a recording hook installed, and
+/// returns the hook together with the first return value of each call.
+fn run(calls: Vec<(&str, Vec<MoveValue>)>) -> (Arc<Recorder>, Vec<Option<Vec<u8>>>) {
+    let code = CODE.replace("{{ADDR}}", &format!("0x{}", TEST_ADDR.to_hex()));
+    let mut units = compile_units(&code).unwrap();
+    let m = as_module(units.pop().unwrap());
+    let mut blob = vec![];
+    m.serialize(&mut blob).unwrap();
+
+    let mut storage = InMemoryStorage::new();
+    storage.publish_or_overwrite_module(module_id(), blob);
+    let vm = MoveVM::new(vec![]);
+    let mut sess = vm.new_session(&storage);
+    let traversal_storage = TraversalStorage::new();
+
+    let _guard = HOOK_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    let recorder = Arc::new(Recorder::default());
+    set_debug_hook(recorder.clone());
+    let results = calls
+        .into_iter()
+        .map(|(name, args)| {
+            let result = sess.execute_function_bypass_visibility(
+                &module_id(),
+                &Identifier::new(name).unwrap(),
+                vec![],
+                args.into_iter().map(serialize).collect::<Vec<_>>(),
+                &mut UnmeteredGasMeter,
+                &mut TraversalContext::new(&traversal_storage),
+            );
+            result.map(|values| {
+                values
+                    .return_values
+                    .into_iter()
+                    .next()
+                    .map(|(bytes, _)| bytes)
+            })
+        })
+        .collect::<VMResult<Vec<_>>>();
+    clear_debug_hook();
+
+    (recorder, results.unwrap())
+}
+
+#[test]
+fn test_hook_observes_instructions_and_call_stack() {
+    let (recorder, results) = run(vec![("call_add", vec![MoveValue::U64(3)])]);
+    assert_eq!(results, vec![Some(serialize(MoveValue::U64(9)))]);
+
+    let instructions = recorder.instructions.lock().unwrap();
+    let first = &instructions[0];
+    assert_eq!(
+        (first.function.as_str(), first.pc, first.depth),
+        ("call_add", 0, 1)
+    );
+    let last = instructions.last().unwrap();
+    assert_eq!(last.function, "call_add");
+    assert!(matches!(last.instr, Bytecode::Ret));
+    for instruction in instructions.iter() {
+        let expected_depth = if instruction.function == "add" { 2 } else { 1 };
+        assert_eq!(instruction.depth, expected_depth);
+        assert_eq!(instruction.frames.len(), expected_depth);
+    }
+
+    // On entry of the callee, the stack shows the callee first, and the caller at its call.
+    let entry = instructions
+        .iter()
+        .find(|instruction| instruction.function == "add")
+        .unwrap();
+    assert_eq!(entry.pc, 0);
+    let (callee, caller) = (&entry.frames[0], &entry.frames[1]);
+    assert_eq!(callee.function_name, "add");
+    assert_eq!(callee.module_id, Some(module_id()));
+    assert_eq!(callee.pc, 0);
+    assert_eq!(caller.function_name, "call_add");
+    assert!(instructions.iter().any(|instruction| {
+        instruction.function == "call_add"
+            && instruction.pc == caller.pc
+            && matches!(instruction.instr, Bytecode::Call(_))
+    }));
+
+    for (local, expected) in callee.locals.iter().zip([3, 6]) {
+        assert_eq!(local.ty, Some(TypeTag::U64));
+        assert!(!local.is_reference);
+        assert_eq!(local.value, Some(serialize(MoveValue::U64(expected))));
+    }
+}
+
+#[test]
+fn test_hook_reads_through_references() {
+    let (recorder, results) = run(vec![("call_deref", vec![MoveValue::U64(5)])]);
+    assert_eq!(results, vec![Some(serialize(MoveValue::U64(5)))]);
+
+    let instructions = recorder.instructions.lock().unwrap();
+    let entry = instructions
+        .iter()
+        .find(|instruction| instruction.function == "deref")
+        .unwrap();
+    let local = &entry.frames[0].locals[0];
+    assert_eq!(local.ty, Some(TypeTag::U64));
+    assert!(local.is_reference);
+    assert_eq!(local.value, Some(serialize(MoveValue::U64(5))));
+}
+
+#[test]
+fn test_hook_instantiates_local_types() {
+    let (recorder, _) = run(vec![("call_id", vec![])]);
+
+    let instructions = recorder.instructions.lock().unwrap();
+    let entry = instructions
+        .iter()
+        .find(|instruction| instruction.function == "id")
+        .unwrap();
+    let local = &entry.frames[0].locals[0];
+    assert_eq!(local.ty, Some(TypeTag::U8));
+    assert_eq!(local.value, Some(serialize(MoveValue::U8(1))));
+}
+
+#[test]
+fn test_hook_observes_resource_reads() {
+    let addr = AccountAddress::from_hex_literal("0xcafe").unwrap();
+    let other = AccountAddress::from_hex_literal("0xbeef").unwrap();
+    let (recorder, results) = run(vec![
+        ("publish", vec![MoveValue::Signer(addr)]),
+        ("read", vec![MoveValue::Address(addr)]),
+        ("has", vec![MoveValue::Address(other)]),
+    ]);
+    assert_eq!(results, vec![
+        None,
+        Some(serialize(MoveValue::U64(7))),
+        Some(serialize(MoveValue::Bool(false))),
+    ]);
+
+    // `move_to` checks that the resource does not exist yet, then `borrow_global` reads it. The
+    // BCS encoding of `R` is the one of its only field.
+    let value = serialize(MoveValue::U64(7));
+    assert_eq!(*recorder.reads.lock().unwrap(), vec![
+        (addr, None),
+        (addr, Some(value)),
+        (other, None),
+    ]);
+}
+
+#[test]
+fn test_cleared_hook_is_not_called() {
+    let recorder = Arc::new(Recorder::default());
+    {
+        let _guard = HOOK_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+        set_debug_hook(recorder.clone());
+        clear_debug_hook();
+    }
+    let (_, results) = run(vec![("call_add", vec![MoveValue::U64(1)])]);
+    assert_eq!(results, vec![Some(serialize(MoveValue::U64(3)))]);
+    assert!(recorder.instructions.lock().unwrap().is_empty());
+}
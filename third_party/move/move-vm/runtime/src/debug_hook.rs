@@ -0,0 +1,257 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! A hook through which an external debugger can observe and suspend execution.
+//!
+//! Like the tracing in `tracing.rs`, this is only compiled into debug builds, or with the
+//! `debugging` feature. A debugger installs a [`DebugHook`] with [`set_debug_hook`]. The
+//! interpreter then calls the hook before each instruction and on each read of global storage.
+//! Execution does not continue until the hook returns, so a debugger implements breakpoints
+//! and stepping by blocking in [`DebugHook::on_instruction`] until the user resumes.
+
+use crate::{
+    interpreter::Interpreter,
+    loader::{Loader, ModuleStorageAdapter, Resolver},
+    LoadedFunction,
+};
+use move_binary_format::file_format::{Bytecode, FunctionDefinitionIndex};
+use move_core_types::{
+    account_address::AccountAddress,
+    language_storage::{ModuleId, TypeTag},
+};
+use move_vm_types::{
+    loaded_data::runtime_types::Type,
+    values::{GlobalValue, Locals, Reference, Value},
+};
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+/// Callbacks invoked by the interpreter while a debug hook is installed.
+pub trait DebugHook: Send + Sync {
+    /// Called before the instruction at the current program counter is executed.
+    fn on_instruction(&self, state: &DebugState);
+
+    /// Called when a resource is read from global storage. `value` is the BCS encoding of the
+    /// resource, or `None` if it does not exist.
+    fn on_resource_read(&self, _addr: AccountAddress, _ty: &TypeTag, _value: Option<&[u8]>) {}
+}
+
+static DEBUG_HOOK: Lazy<RwLock<Option<Arc<dyn DebugHook>>>> = Lazy::new(|| RwLock::new(None));
+
+/// Avoids taking the lock on every instruction when no hook is installed.
+static DEBUG_HOOK_INSTALLED: AtomicBool = AtomicBool::new(false);
+
+/// Installs the hook for all VMs in this process, replacing any previously installed hook.
+pub fn set_debug_hook(hook: Arc<dyn DebugHook>) {
+    *DEBUG_HOOK.write() = Some(hook);
+    DEBUG_HOOK_INSTALLED.store(true, Ordering::SeqCst);
+}
+
+/// Removes the installed hook, if any.
+pub fn clear_debug_hook() {
+    DEBUG_HOOK_INSTALLED.store(false, Ordering::SeqCst);
+    *DEBUG_HOOK.write() = None;
+}
+
+fn debug_hook() -> Option<Arc<dyn DebugHook>> {
+    if DEBUG_HOOK_INSTALLED.load(Ordering::Relaxed) {
+        DEBUG_HOOK.read().clone()
+    } else {
+        None
+    }
+}
+
+/// A frame of the call stack, with its locals decoded into types and BCS values.
+#[derive(Clone, Debug)]
+pub struct DebugFrame {
+    /// The module of the function, or `None` for a script.
+    pub module_id: Option<ModuleId>,
+    pub function_name: String,
+    pub function_index: FunctionDefinitionIndex,
+    /// The instruction which is executed next. For callers, this is the call instruction.
+    pub pc: u16,
+    /// The parameters of the function, followed by its other locals.
+    pub locals: Vec<DebugLocal>,
+}
+
+/// A local of a frame.
+#[derive(Clone, Debug)]
+pub struct DebugLocal {
+    /// The type of the local, or of the referenced value for a reference. `None` if the type
+    /// cannot be converted into a type tag.
+    pub ty: Option<TypeTag>,
+    pub is_reference: bool,
+    /// The BCS encoding of the value, or of the referenced value for a reference. `None` if the
+    /// local has not been assigned, has been moved from, or cannot be serialized.
+    pub value: Option<Vec<u8>>,
+}
+
+/// The state of execution passed to [`DebugHook::on_instruction`]. Frames are only decoded on
+/// request, as the hook is called for every instruction.
+pub struct DebugState<'a> {
+    function: &'a LoadedFunction,
+    locals: &'a Locals,
+    pc: u16,
+    instr: &'a Bytecode,
+    resolver: &'a Resolver<'a>,
+    interp: &'a Interpreter,
+}
+
+impl<'a> DebugState<'a> {
+    /// Returns the module of the current function, or `None` for a script.
+    pub fn module_id(&self) -> Option<&ModuleId> {
+        self.function.module_id()
+    }
+
+    /// Returns the name of the current function.
+    pub fn function_name(&self) -> &str {
+        self.function.name()
+    }
+
+    /// Returns the index of the current function in its module.
+    pub fn function_index(&self) -> FunctionDefinitionIndex {
+        self.function.index()
+    }
+
+    /// Returns the program counter of the instruction about to be executed.
+    pub fn pc(&self) -> u16 {
+        self.pc
+    }
+
+    /// Returns the instruction about to be executed.
+    pub fn instruction(&self) -> &Bytecode {
+        self.instr
+    }
+
+    /// Returns the number of frames on the call stack, including the current one.
+    pub fn stack_depth(&self) -> usize {
+        self.interp.debug_call_stack().count() + 1
+    }
+
+    /// Returns the frames of the call stack, innermost first.
+    pub fn frames(&self) -> Vec<DebugFrame> {
+        let loader = self.resolver.loader();
+        let module_store = self.resolver.module_store();
+        let mut frames = vec![debug_frame(
+            loader,
+            module_store,
+            self.function,
+            self.locals,
+            self.pc,
+        )];
+        frames.extend(
+            self.interp
+                .debug_call_stack()
+                .rev()
+                .map(|(function, locals, pc)| {
+                    debug_frame(loader, module_store, function, locals, pc)
+                }),
+        );
+        frames
+    }
+}
+
+fn debug_frame(
+    loader: &Loader,
+    module_store: &ModuleStorageAdapter,
+    function: &LoadedFunction,
+    locals: &Locals,
+    pc: u16,
+) -> DebugFrame {
+    let ty_builder = loader.ty_builder();
+    let locals = function
+        .local_tys()
+        .iter()
+        .enumerate()
+        .map(|(idx, ty)| {
+            let ty = ty_builder.create_ty_with_subst(ty, function.ty_args()).ok();
+            let (ty, is_reference) = match ty {
+                Some(Type::Reference(inner) | Type::MutableReference(inner)) => {
+                    (Some(*inner), true)
+                },
+                ty => (ty, false),
+            };
+            let value = ty.as_ref().and_then(|ty| {
+                let value = locals.copy_loc(idx).ok()?;
+                let value = if is_reference {
+                    value.value_as::<Reference>().ok()?.read_ref().ok()?
+                } else {
+                    value
+                };
+                serialize_value(loader, module_store, ty, &value)
+            });
+            DebugLocal {
+                ty: ty.and_then(|ty| loader.type_to_type_tag(&ty).ok()),
+                is_reference,
+                value,
+            }
+        })
+        .collect();
+    DebugFrame {
+        module_id: function.module_id().cloned(),
+        function_name: function.name().to_string(),
+        function_index: function.index(),
+        pc,
+        locals,
+    }
+}
+
+fn serialize_value(
+    loader: &Loader,
+    module_store: &ModuleStorageAdapter,
+    ty: &Type,
+    value: &Value,
+) -> Option<Vec<u8>> {
+    let layout = loader.type_to_type_layout(ty, module_store).ok()?;
+    value.simple_serialize(&layout)
+}
+
+/// Calls the installed hook, if any, before an instruction is executed.
+pub(crate) fn on_instruction(
+    function: &LoadedFunction,
+    locals: &Locals,
+    pc: u16,
+    instr: &Bytecode,
+    resolver: &Resolver,
+    interp: &Interpreter,
+) {
+    if let Some(hook) = debug_hook() {
+        hook.on_instruction(&DebugState {
+            function,
+            locals,
+            pc,
+            instr,
+            resolver,
+            interp,
+        })
+    }
+}
+
+/// Calls the installed hook, if any, after a resource has been loaded from global storage.
+pub(crate) fn on_resource_read(
+    loader: &Loader,
+    module_store: &ModuleStorageAdapter,
+    addr: AccountAddress,
+    ty: &Type,
+    gv: &GlobalValue,
+) {
+    if let Some(hook) = debug_hook() {
+        let tag = match loader.type_to_type_tag(ty) {
+            Ok(tag) => tag,
+            Err(_) => return,
+        };
+        let value = if gv.exists().unwrap_or(false) {
+            gv.borrow_global()
+                .and_then(|r| r.value_as::<Reference>()?.read_ref())
+                .ok()
+                .and_then(|value| serialize_value(loader, module_store, ty, &value))
+        } else {
+            None
+        };
+        hook.on_resource_read(addr, &tag, value.as_deref())
+    }
+}
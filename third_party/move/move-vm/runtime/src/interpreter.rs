@@ -757,6 +757,8 @@ impl Interpreter {
     ) -> PartialVMResult<&'c mut GlobalValue> {
         match data_store.load_resource(loader, addr, ty, module_store) {
             Ok((gv, load_res)) => {
+                #[cfg(any(debug_assertions, feature = "debugging"))]
+                crate::debug_hook::on_resource_read(loader, module_store, addr, ty, gv);
                 if let Some(bytes_loaded) = load_res {
                     gas_meter.charge_load_resource(
                         addr,
@@ -1045,6 +1047,18 @@ impl Interpreter {
         Ok(())
     }
 
+    /// Returns the function, locals and program counter of each frame on the call stack,
+    /// outermost first. The frame which is currently executing is not on the stack.
+    #[cfg(any(debug_assertions, feature = "debugging"))]
+    pub(crate) fn debug_call_stack(
+        &self,
+    ) -> impl DoubleEndedIterator<Item = (&LoadedFunction, &Locals, u16)> {
+        self.call_stack
+            .0
+            .iter()
+            .map(|frame| (&frame.function, &frame.locals, frame.pc))
+    }
+
     /// Generate a string which is the status of the interpreter: call stack, current bytecode
     /// stream, locals and operand stack.
     ///
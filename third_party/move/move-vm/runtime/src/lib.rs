@@ -28,6 +28,8 @@ pub mod module_traversal;
 // Only include debugging functionality in debug builds
 #[cfg(any(debug_assertions, feature = "debugging"))]
 mod debug;
+#[cfg(any(debug_assertions, feature = "debugging"))]
+pub mod debug_hook;
 
 mod access_control;
 
@@ -6,8 +6,9 @@
 use crate::debug::DebugContext;
 #[cfg(any(debug_assertions, feature = "debugging"))]
 use crate::{
+    debug_hook,
     interpreter::Interpreter,
-    loader::{LoadedFunction, Loader},
+    loader::{LoadedFunction, Resolver},
 };
 #[cfg(any(debug_assertions, feature = "debugging"))]
 use ::{
@@ -71,7 +72,7 @@ pub(crate) fn trace(
     locals: &Locals,
     pc: u16,
     instr: &Bytecode,
-    loader: &Loader,
+    resolver: &Resolver,
     interp: &Interpreter,
 ) {
     if *TRACING_ENABLED {
@@ -88,11 +89,16 @@ pub(crate) fn trace(
         }
     }
     if *DEBUGGING_ENABLED {
-        DEBUG_CONTEXT
-            .lock()
-            .unwrap()
-            .debug_loop(function, locals, pc, instr, loader, interp);
+        DEBUG_CONTEXT.lock().unwrap().debug_loop(
+            function,
+            locals,
+            pc,
+            instr,
+            resolver.loader(),
+            interp,
+        );
     }
+    debug_hook::on_instruction(function, locals, pc, instr, resolver, interp);
 }
 
 #[macro_export]
@@ -100,13 +106,6 @@ macro_rules! trace {
     ($function_desc:expr, $locals:expr, $pc:expr, $instr:tt, $resolver:expr, $interp:expr) => {
         // Only include this code in debug releases
         #[cfg(any(debug_assertions, feature = "debugging"))]
-        $crate::tracing::trace(
-            &$function_desc,
-            $locals,
-            $pc,
-            &$instr,
-            $resolver.loader(),
-            $interp,
-        )
+        $crate::tracing::trace(&$function_desc, $locals, $pc, &$instr, $resolver, $interp)
     };
 }
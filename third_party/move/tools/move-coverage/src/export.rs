@@ -0,0 +1,685 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Export of coverage information in formats understood by generic coverage tooling:
+//! [LCOV](https://github.com/linux-test-project/lcov) tracefiles and
+//! [Cobertura](https://cobertura.github.io/cobertura/) XML reports.
+//!
+//! Instruction counts from an `ExecCoverageMap` are mapped to source lines via the source map
+//! of each module. A line's hit count is the maximum count over the instructions located on
+//! it. Each conditional branch instruction contributes two branches (taken and fall-through).
+//! The hit count of a branch is the execution count of its target instruction if the branch is
+//! the only way to reach it, and otherwise derived from the count of the other branch. If both
+//! targets can also be reached in other ways (e.g. join points or loop heads), the hit counts
+//! of the branches are unknown.
+
+#![forbid(unsafe_code)]
+
+use crate::coverage_map::{ExecCoverageMap, FunctionCoverage};
+use anyhow::{format_err, Result};
+use move_binary_format::{
+    access::ModuleAccess,
+    file_format::{Bytecode, CodeOffset, FunctionDefinitionIndex},
+    CompiledModule,
+};
+use move_bytecode_source_map::source_map::SourceMap;
+use move_command_line_common::{files::FileHash, xml};
+use std::{
+    collections::BTreeMap,
+    fmt, fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// The supported export formats.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum ExportFormat {
+    Lcov,
+    Cobertura,
+}
+
+impl fmt::Display for ExportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ExportFormat::Lcov => "lcov",
+            ExportFormat::Cobertura => "cobertura",
+        })
+    }
+}
+
+/// Coverage of a single function, by source line.
+#[derive(Debug, Default)]
+pub struct FunctionReport {
+    pub name: String,
+    /// The line of the function definition.
+    pub line: u32,
+    /// The number of times the function was entered.
+    pub hits: u64,
+    /// Hit counts by line.
+    pub lines: BTreeMap<u32, u64>,
+    /// Hit counts of the branches of conditional jumps, by line of the jump. `None` if the
+    /// jump was never executed, or if the hit count of the branch is unknown.
+    pub branches: BTreeMap<u32, Vec<Option<u64>>>,
+}
+
+impl FunctionReport {
+    /// Computes the coverage of a function from the execution counts of its instructions, and
+    /// their source lines (`None` for instructions located in other files).
+    fn new(
+        name: String,
+        line: u32,
+        code: &[Bytecode],
+        count_at: impl Fn(CodeOffset) -> u64,
+        line_of: impl Fn(CodeOffset) -> Option<u32>,
+    ) -> Self {
+        // The function entry counts as a predecessor of the first instruction.
+        let mut num_predecessors = vec![0; code.len()];
+        if let Some(entry) = num_predecessors.first_mut() {
+            *entry += 1;
+        }
+        for offset in 0..code.len() {
+            for successor in Bytecode::get_successors(offset as CodeOffset, code) {
+                num_predecessors[successor as usize] += 1;
+            }
+        }
+
+        let mut fun = FunctionReport {
+            name,
+            line,
+            hits: count_at(0),
+            ..FunctionReport::default()
+        };
+        for (offset, instr) in code.iter().enumerate() {
+            let offset = offset as CodeOffset;
+            let Some(line) = line_of(offset) else {
+                continue;
+            };
+            let count = count_at(offset);
+            let line_hits = fun.lines.entry(line).or_insert(0);
+            *line_hits = (*line_hits).max(count);
+            if let Bytecode::BrTrue(target) | Bytecode::BrFalse(target) = instr {
+                // A branch is the only way to reach its target if that has no other
+                // predecessor, in which case the target's count is the branch's count.
+                let exact_count = |successor: CodeOffset| {
+                    (*target != offset + 1 && num_predecessors.get(successor as usize) == Some(&1))
+                        .then(|| count_at(successor).min(count))
+                };
+                let (taken, fall_through) = match (exact_count(*target), exact_count(offset + 1)) {
+                    _ if count == 0 => (None, None),
+                    (Some(taken), Some(fall_through)) => (Some(taken), Some(fall_through)),
+                    (Some(taken), None) => (Some(taken), Some(count - taken)),
+                    (None, Some(fall_through)) => (Some(count - fall_through), Some(fall_through)),
+                    (None, None) => (None, None),
+                };
+                fun.branches
+                    .entry(line)
+                    .or_default()
+                    .extend([taken, fall_through]);
+            }
+        }
+        fun
+    }
+}
+
+/// Coverage of a single module.
+#[derive(Debug, Default)]
+pub struct ModuleReport {
+    /// The fully qualified name of the module, e.g. `0x1::vector`.
+    pub name: String,
+    pub functions: Vec<FunctionReport>,
+}
+
+impl ModuleReport {
+    /// Returns the hit counts by line of all functions.
+    pub fn lines(&self) -> BTreeMap<u32, u64> {
+        let mut lines = BTreeMap::new();
+        for fun in &self.functions {
+            merge_lines(&mut lines, &fun.lines);
+        }
+        lines
+    }
+
+    /// Returns the branches of all functions, by line.
+    pub fn branches(&self) -> BTreeMap<u32, Vec<Option<u64>>> {
+        let mut branches = BTreeMap::new();
+        for fun in &self.functions {
+            merge_branches(&mut branches, &fun.branches);
+        }
+        branches
+    }
+}
+
+/// Coverage of the modules defined in a single source file.
+#[derive(Debug, Default)]
+pub struct FileReport {
+    pub modules: Vec<ModuleReport>,
+}
+
+impl FileReport {
+    /// Returns the hit counts by line of all modules in the file.
+    pub fn lines(&self) -> BTreeMap<u32, u64> {
+        let mut lines = BTreeMap::new();
+        for module in &self.modules {
+            merge_lines(&mut lines, &module.lines());
+        }
+        lines
+    }
+
+    /// Returns the branches of all modules in the file, by line.
+    pub fn branches(&self) -> BTreeMap<u32, Vec<Option<u64>>> {
+        let mut branches = BTreeMap::new();
+        for module in &self.modules {
+            merge_branches(&mut branches, &module.branches());
+        }
+        branches
+    }
+}
+
+/// A coverage report over a set of modules, organized by source file.
+#[derive(Debug, Default)]
+pub struct CoverageReport {
+    /// The directory relative to which source paths are reported.
+    source_root: PathBuf,
+    files: BTreeMap<PathBuf, FileReport>,
+}
+
+impl CoverageReport {
+    /// Creates an empty report. Source files are reported relative to `source_root` if they
+    /// are located below it, and with their full path otherwise.
+    pub fn new(source_root: &Path) -> Self {
+        Self {
+            source_root: source_root.to_path_buf(),
+            files: BTreeMap::new(),
+        }
+    }
+
+    /// Adds the coverage of `module`, which is defined in the source file at `source_path`.
+    pub fn add_module(
+        &mut self,
+        module: &CompiledModule,
+        source_map: &SourceMap,
+        source_path: &Path,
+        coverage_map: &ExecCoverageMap,
+    ) -> Result<()> {
+        let source = fs::read_to_string(source_path)
+            .map_err(|e| format_err!("Unable to read source file {:?}: {}", source_path, e))?;
+        let lines = LineIndex::new(&source);
+        let module_id = module.self_id();
+        let module_map = coverage_map
+            .module_maps
+            .get(&(*module_id.address(), module_id.name().to_owned()));
+
+        let mut report = ModuleReport {
+            name: format!(
+                "{}::{}",
+                module_id.address().short_str_lossless(),
+                module_id.name()
+            ),
+            functions: vec![],
+        };
+        for (idx, fdef) in module.function_defs().iter().enumerate() {
+            let code = match &fdef.code {
+                Some(code) => &code.code,
+                // Natives have no code to cover.
+                None => continue,
+            };
+            let fdef_idx = FunctionDefinitionIndex(idx as u16);
+            let fn_name = module.identifier_at(module.function_handle_at(fdef.function).name);
+            let empty = FunctionCoverage::new();
+            let fn_coverage = module_map
+                .and_then(|m| m.get_function_coverage(fn_name))
+                .unwrap_or(&empty);
+            let count_at = |offset: CodeOffset| -> u64 {
+                fn_coverage.get(&(offset as u64)).copied().unwrap_or(0)
+            };
+            // Locations of inlined code may point into other files, skip those.
+            let line_of = |offset: CodeOffset| -> Option<u32> {
+                let loc = source_map.get_code_location(fdef_idx, offset).ok()?;
+                (loc.file_hash() == lines.file_hash).then(|| lines.line_of(loc.start()))
+            };
+
+            let definition_loc = source_map
+                .get_function_source_map(fdef_idx)?
+                .definition_location;
+            let fun = FunctionReport::new(
+                fn_name.to_string(),
+                lines.line_of(definition_loc.start()),
+                code,
+                count_at,
+                line_of,
+            );
+            report.functions.push(fun);
+        }
+
+        let path = source_path
+            .strip_prefix(&self.source_root)
+            .unwrap_or(source_path)
+            .to_path_buf();
+        self.files.entry(path).or_default().modules.push(report);
+        Ok(())
+    }
+
+    /// Returns the reported source files.
+    pub fn files(&self) -> &BTreeMap<PathBuf, FileReport> {
+        &self.files
+    }
+
+    /// Writes the report in the given format.
+    pub fn write<W: Write>(&self, format: ExportFormat, writer: &mut W) -> io::Result<()> {
+        match format {
+            ExportFormat::Lcov => self.write_lcov(writer),
+            ExportFormat::Cobertura => self.write_cobertura(writer),
+        }
+    }
+
+    /// Writes the report as an LCOV tracefile, with one record per source file.
+    pub fn write_lcov<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        for (path, file) in &self.files {
+            writeln!(writer, "TN:")?;
+            writeln!(writer, "SF:{}", path.display())?;
+            let functions = file
+                .modules
+                .iter()
+                .flat_map(|m| m.functions.iter().map(move |f| (m, f)))
+                .collect::<Vec<_>>();
+            for (module, fun) in &functions {
+                writeln!(writer, "FN:{},{}::{}", fun.line, module.name, fun.name)?;
+            }
+            for (module, fun) in &functions {
+                writeln!(writer, "FNDA:{},{}::{}", fun.hits, module.name, fun.name)?;
+            }
+            writeln!(writer, "FNF:{}", functions.len())?;
+            writeln!(
+                writer,
+                "FNH:{}",
+                functions.iter().filter(|(_, f)| f.hits > 0).count()
+            )?;
+
+            let branches = file.branches();
+            let (mut found, mut hit) = (0, 0);
+            for (line, line_branches) in &branches {
+                // Each conditional jump has two branches, which form one block.
+                for (idx, taken) in line_branches.iter().enumerate() {
+                    let taken = match taken {
+                        Some(count) => count.to_string(),
+                        None => "-".to_string(),
+                    };
+                    writeln!(writer, "BRDA:{},{},{},{}", line, idx / 2, idx % 2, taken)?;
+                }
+                found += line_branches.len();
+                hit += count_hit_branches(line_branches);
+            }
+            writeln!(writer, "BRF:{}", found)?;
+            writeln!(writer, "BRH:{}", hit)?;
+
+            let lines = file.lines();
+            for (line, hits) in &lines {
+                writeln!(writer, "DA:{},{}", line, hits)?;
+            }
+            writeln!(writer, "LF:{}", lines.len())?;
+            writeln!(writer, "LH:{}", lines.values().filter(|h| **h > 0).count())?;
+            writeln!(writer, "end_of_record")?;
+        }
+        Ok(())
+    }
+
+    /// Writes the report as a Cobertura XML report. Each module is reported as a class, and
+    /// each function as a method of it.
+    pub fn write_cobertura<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        let all = Rates::new(
+            self.files.values().map(|f| f.lines()),
+            self.files.values().map(|f| f.branches()),
+        );
+        writeln!(writer, r#"<?xml version="1.0" ?>"#)?;
+        writeln!(
+            writer,
+            r#"<!DOCTYPE coverage SYSTEM "http://cobertura.sourceforge.net/xml/coverage-04.dtd">"#
+        )?;
+        writeln!(
+            writer,
+            r#"<coverage {} lines-covered="{}" lines-valid="{}" branches-covered="{}" branches-valid="{}" complexity="0" version="1" timestamp="{}">"#,
+            all.attributes(),
+            all.lines_covered,
+            all.lines_valid,
+            all.branches_covered,
+            all.branches_valid,
+            timestamp
+        )?;
+        writeln!(writer, "  <sources>")?;
+        writeln!(
+            writer,
+            "    <source>{}</source>",
+            xml::escape(&self.source_root.display().to_string())
+        )?;
+        writeln!(writer, "  </sources>")?;
+        writeln!(writer, "  <packages>")?;
+        writeln!(
+            writer,
+            r#"    <package name="{}" {} complexity="0">"#,
+            xml::escape(&self.source_root.display().to_string()),
+            all.attributes()
+        )?;
+        writeln!(writer, "      <classes>")?;
+        for (path, file) in &self.files {
+            for module in &file.modules {
+                let lines = module.lines();
+                let branches = module.branches();
+                let rates = Rates::new([lines.clone()], [branches.clone()]);
+                writeln!(
+                    writer,
+                    r#"        <class name="{}" filename="{}" {} complexity="0">"#,
+                    xml::escape(&module.name),
+                    xml::escape(&path.display().to_string()),
+                    rates.attributes()
+                )?;
+                writeln!(writer, "          <methods>")?;
+                for fun in &module.functions {
+                    let rates = Rates::new([fun.lines.clone()], [fun.branches.clone()]);
+                    writeln!(
+                        writer,
+                        r#"            <method name="{}" signature="" {} complexity="0">"#,
+                        xml::escape(&fun.name),
+                        rates.attributes()
+                    )?;
+                    write_cobertura_lines(writer, "              ", &fun.lines, &fun.branches)?;
+                    writeln!(writer, "            </method>")?;
+                }
+                writeln!(writer, "          </methods>")?;
+                write_cobertura_lines(writer, "          ", &lines, &branches)?;
+                writeln!(writer, "        </class>")?;
+            }
+        }
+        writeln!(writer, "      </classes>")?;
+        writeln!(writer, "    </package>")?;
+        writeln!(writer, "  </packages>")?;
+        writeln!(writer, "</coverage>")
+    }
+}
+
+/// Writes a Cobertura `<lines>` element.
+fn write_cobertura_lines<W: Write>(
+    writer: &mut W,
+    indent: &str,
+    lines: &BTreeMap<u32, u64>,
+    branches: &BTreeMap<u32, Vec<Option<u64>>>,
+) -> io::Result<()> {
+    writeln!(writer, "{}<lines>", indent)?;
+    for (line, hits) in lines {
+        match branches.get(line) {
+            Some(line_branches) => {
+                let covered = count_hit_branches(line_branches);
+                writeln!(
+                    writer,
+                    r#"{}  <line number="{}" hits="{}" branch="true" condition-coverage="{}% ({}/{})"/>"#,
+                    indent,
+                    line,
+                    hits,
+                    covered * 100 / line_branches.len(),
+                    covered,
+                    line_branches.len()
+                )?;
+            },
+            None => writeln!(
+                writer,
+                r#"{}  <line number="{}" hits="{}" branch="false"/>"#,
+                indent, line, hits
+            )?,
+        }
+    }
+    writeln!(writer, "{}</lines>", indent)
+}
+
+/// Line and branch totals, from which Cobertura rates are computed.
+struct Rates {
+    lines_covered: usize,
+    lines_valid: usize,
+    branches_covered: usize,
+    branches_valid: usize,
+}
+
+impl Rates {
+    fn new(
+        lines: impl IntoIterator<Item = BTreeMap<u32, u64>>,
+        branches: impl IntoIterator<Item = BTreeMap<u32, Vec<Option<u64>>>>,
+    ) -> Self {
+        let mut rates = Rates {
+            lines_covered: 0,
+            lines_valid: 0,
+            branches_covered: 0,
+            branches_valid: 0,
+        };
+        for lines in lines {
+            rates.lines_valid += lines.len();
+            rates.lines_covered += lines.values().filter(|h| **h > 0).count();
+        }
+        for branches in branches {
+            for line_branches in branches.values() {
+                rates.branches_valid += line_branches.len();
+                rates.branches_covered += count_hit_branches(line_branches);
+            }
+        }
+        rates
+    }
+
+    /// Returns the `line-rate` and `branch-rate` attributes.
+    fn attributes(&self) -> String {
+        format!(
+            r#"line-rate="{:.4}" branch-rate="{:.4}""#,
+            rate(self.lines_covered, self.lines_valid),
+            rate(self.branches_covered, self.branches_valid)
+        )
+    }
+}
+
+/// Returns `covered / valid`, where nothing to cover counts as fully covered.
+fn rate(covered: usize, valid: usize) -> f64 {
+    if valid == 0 {
+        1.0
+    } else {
+        covered as f64 / valid as f64
+    }
+}
+
+fn count_hit_branches(branches: &[Option<u64>]) -> usize {
+    branches
+        .iter()
+        .filter(|taken| matches!(taken, Some(count) if *count > 0))
+        .count()
+}
+
+fn merge_lines(into: &mut BTreeMap<u32, u64>, lines: &BTreeMap<u32, u64>) {
+    for (line, hits) in lines {
+        let entry = into.entry(*line).or_insert(0);
+        *entry = (*entry).max(*hits);
+    }
+}
+
+fn merge_branches(
+    into: &mut BTreeMap<u32, Vec<Option<u64>>>,
+    branches: &BTreeMap<u32, Vec<Option<u64>>>,
+) {
+    for (line, line_branches) in branches {
+        into.entry(*line)
+            .or_default()
+            .extend(line_branches.iter().copied());
+    }
+}
+
+/// Maps byte offsets of a source file to 1-based line numbers.
+struct LineIndex {
+    file_hash: FileHash,
+    line_starts: Vec<u32>,
+}
+
+impl LineIndex {
+    fn new(source: &str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(
+                source
+                    .match_indices('\n')
+                    .map(|(offset, _)| offset as u32 + 1),
+            )
+            .collect();
+        Self {
+            file_hash: FileHash::new(source),
+            line_starts,
+        }
+    }
+
+    fn line_of(&self, offset: u32) -> u32 {
+        self.line_starts.partition_point(|start| *start <= offset) as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Computes the coverage of `code`, given the execution count and line of each instruction.
+    fn function_report(code: &[Bytecode], counts: &[u64], lines: &[Option<u32>]) -> FunctionReport {
+        FunctionReport::new(
+            "f".to_string(),
+            1,
+            code,
+            |offset| counts[offset as usize],
+            |offset| lines[offset as usize],
+        )
+    }
+
+    fn report(source_root: &str, functions: Vec<FunctionReport>) -> CoverageReport {
+        let mut report = CoverageReport::new(Path::new(source_root));
+        report
+            .files
+            .entry(PathBuf::from("sources/m.move"))
+            .or_default()
+            .modules
+            .push(ModuleReport {
+                name: "0x1::m".to_string(),
+                functions,
+            });
+        report
+    }
+
+    #[test]
+    fn test_line_hits() {
+        let fun = function_report(
+            &[
+                Bytecode::LdTrue,
+                Bytecode::BrFalse(3),
+                Bytecode::Nop,
+                Bytecode::Nop,
+                Bytecode::Ret,
+            ],
+            &[2, 2, 1, 0, 2],
+            &[Some(1), Some(1), Some(2), None, Some(3)],
+        );
+        assert_eq!(fun.hits, 2);
+        // Instructions located in other files are skipped.
+        assert_eq!(fun.lines, BTreeMap::from([(1, 2), (2, 1), (3, 2)]));
+    }
+
+    #[test]
+    fn test_branch_hits_at_loop_head() {
+        // The taken branch jumps back to the loop head, which is also reached from the entry.
+        let fun = function_report(
+            &[Bytecode::LdTrue, Bytecode::BrTrue(0), Bytecode::Ret],
+            &[5, 5, 1],
+            &[Some(1), Some(1), Some(2)],
+        );
+        assert_eq!(fun.branches, BTreeMap::from([(1, vec![Some(4), Some(1)])]));
+    }
+
+    #[test]
+    fn test_branch_hits_at_join_point() {
+        // The taken branch jumps to the join point of an `if` without an `else`.
+        let fun = function_report(
+            &[
+                Bytecode::LdTrue,
+                Bytecode::BrFalse(4),
+                Bytecode::Nop,
+                Bytecode::Branch(4),
+                Bytecode::Ret,
+            ],
+            &[3, 3, 1, 1, 3],
+            &[Some(1), Some(1), Some(2), Some(2), Some(3)],
+        );
+        assert_eq!(fun.branches, BTreeMap::from([(1, vec![Some(2), Some(1)])]));
+    }
+
+    #[test]
+    fn test_unknown_branch_hits() {
+        // Both branches lead to the same instruction.
+        let code = [Bytecode::LdTrue, Bytecode::BrTrue(2), Bytecode::Ret];
+        let lines = [Some(1), Some(1), Some(1)];
+        let fun = function_report(&code, &[1, 1, 1], &lines);
+        assert_eq!(fun.branches, BTreeMap::from([(1, vec![None, None])]));
+        // The branches of jumps which were never executed are unknown too.
+        let fun = function_report(&code, &[0, 0, 0], &lines);
+        assert_eq!(fun.branches, BTreeMap::from([(1, vec![None, None])]));
+    }
+
+    fn example_function() -> FunctionReport {
+        FunctionReport {
+            name: "f".to_string(),
+            line: 1,
+            hits: 1,
+            lines: BTreeMap::from([(1, 1), (2, 0)]),
+            branches: BTreeMap::from([(1, vec![Some(1), Some(0)]), (2, vec![None, None])]),
+        }
+    }
+
+    #[test]
+    fn test_write_lcov() {
+        let mut output = vec![];
+        report("/root", vec![example_function()])
+            .write(ExportFormat::Lcov, &mut output)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "TN:\n\
+             SF:sources/m.move\n\
+             FN:1,0x1::m::f\n\
+             FNDA:1,0x1::m::f\n\
+             FNF:1\n\
+             FNH:1\n\
+             BRDA:1,0,0,1\n\
+             BRDA:1,0,1,0\n\
+             BRDA:2,0,0,-\n\
+             BRDA:2,0,1,-\n\
+             BRF:4\n\
+             BRH:1\n\
+             DA:1,1\n\
+             DA:2,0\n\
+             LF:2\n\
+             LH:1\n\
+             end_of_record\n"
+        );
+    }
+
+    #[test]
+    fn test_write_cobertura() {
+        let mut output = vec![];
+        report("/root/<a&b>", vec![example_function()])
+            .write(ExportFormat::Cobertura, &mut output)
+            .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains(
+            r#"lines-covered="1" lines-valid="2" branches-covered="1" branches-valid="4""#
+        ));
+        assert!(output.contains(
+            r#"<package name="/root/&lt;a&amp;b&gt;" line-rate="0.5000" branch-rate="0.2500""#
+        ));
+        assert!(output.contains("<source>/root/&lt;a&amp;b&gt;</source>"));
+        assert!(output.contains(
+            r#"<line number="1" hits="1" branch="true" condition-coverage="50% (1/2)"/>"#
+        ));
+        assert!(output.contains(
+            r#"<line number="2" hits="0" branch="true" condition-coverage="0% (0/2)"/>"#
+        ));
+    }
+}
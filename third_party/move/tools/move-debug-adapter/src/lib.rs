@@ -0,0 +1,26 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! A [Debug Adapter Protocol](https://microsoft.github.io/debug-adapter-protocol/) server
+//! for the Move VM, which lets any DAP client set breakpoints, step through Move code, and
+//! inspect the call stack, locals and the resources read from global storage.
+//!
+//! The server is installed as the debug hook of the VM (see
+//! `move_vm_runtime::debug_hook`), and serves a single client over TCP:
+//!
+//! ```ignore
+//! let session = DebugSession::start(port, DebugSources::from_test_plan(&test_plan))?;
+//! // ... execute Move code ...
+//! session.finish();
+//! ```
+//!
+//! Since the hook is global, code should be executed on a single thread while a session is
+//! active.
+
+mod protocol;
+mod session;
+mod sources;
+mod variables;
+
+pub use session::DebugSession;
+pub use sources::{DebugSources, ModuleFetcher};
@@ -0,0 +1,113 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! The wire format of the Debug Adapter Protocol: JSON messages, each preceded by a
+//! `Content-Length` header.
+
+use anyhow::{bail, Context, Result};
+use serde_json::{json, Value};
+use std::io::{BufRead, Read, Write};
+
+const CONTENT_LENGTH: &str = "Content-Length:";
+
+/// Reads the next message. Returns `None` if the stream was closed.
+pub fn read_message<R: BufRead>(reader: &mut R) -> Result<Option<Value>> {
+    let mut content_length = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some(len) = line.strip_prefix(CONTENT_LENGTH) {
+            content_length = Some(
+                len.trim()
+                    .parse::<usize>()
+                    .context("invalid Content-Length header")?,
+            );
+        }
+    }
+    let Some(content_length) = content_length else {
+        bail!("message without Content-Length header")
+    };
+    let mut content = vec![0; content_length];
+    reader.read_exact(&mut content)?;
+    Ok(Some(serde_json::from_slice(&content)?))
+}
+
+/// Writes a message.
+pub fn write_message<W: Write>(writer: &mut W, message: &Value) -> Result<()> {
+    let content = serde_json::to_string(message)?;
+    write!(
+        writer,
+        "{} {}\r\n\r\n{}",
+        CONTENT_LENGTH,
+        content.len(),
+        content
+    )?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Creates the response to the request with the given sequence number and command.
+pub fn response(seq: i64, request_seq: i64, command: &str, result: Result<Value>) -> Value {
+    match result {
+        Ok(body) => json!({
+            "seq": seq,
+            "type": "response",
+            "request_seq": request_seq,
+            "command": command,
+            "success": true,
+            "body": body,
+        }),
+        Err(err) => json!({
+            "seq": seq,
+            "type": "response",
+            "request_seq": request_seq,
+            "command": command,
+            "success": false,
+            "message": err.to_string(),
+        }),
+    }
+}
+
+/// Creates an event.
+pub fn event(seq: i64, event: &str, body: Value) -> Value {
+    json!({
+        "seq": seq,
+        "type": "event",
+        "event": event,
+        "body": body,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let messages = [
+            json!({"seq": 1, "type": "request", "command": "initialize"}),
+            event(2, "stopped", json!({"reason": "breakpoint", "threadId": 1})),
+        ];
+        let mut buf = vec![];
+        for message in &messages {
+            write_message(&mut buf, message).unwrap();
+        }
+        let mut reader = buf.as_slice();
+        for message in &messages {
+            assert_eq!(read_message(&mut reader).unwrap().as_ref(), Some(message));
+        }
+        assert!(read_message(&mut reader).unwrap().is_none());
+    }
+
+    #[test]
+    fn missing_content_length() {
+        let mut reader = "Content-Type: json\r\n\r\n{}".as_bytes();
+        assert!(read_message(&mut reader).is_err());
+    }
+}
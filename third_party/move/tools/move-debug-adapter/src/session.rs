@@ -0,0 +1,1097 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! A debug session: serves the requests of a client on one thread, while the VM runs on
+//! another. The VM is suspended inside the debug hook whenever a breakpoint is hit or a step
+//! completes, until the client resumes execution.
+
+use crate::{
+    protocol::{event, read_message, response, write_message},
+    sources::{DebugSources, SourceKey},
+    variables::{decode, Variable},
+};
+use anyhow::{anyhow, bail, Result};
+use move_binary_format::file_format::FunctionDefinitionIndex;
+use move_core_types::{
+    account_address::AccountAddress,
+    language_storage::{ModuleId, TypeTag},
+};
+use move_vm_runtime::debug_hook::{
+    clear_debug_hook, set_debug_hook, DebugFrame, DebugHook, DebugState,
+};
+use serde_json::{json, Value};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::{BufReader, Write},
+    net::{TcpListener, TcpStream},
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicI64, Ordering},
+        Arc, Condvar, Mutex,
+    },
+    thread,
+};
+
+/// The VM runs on a single thread as far as the client is concerned.
+const THREAD_ID: i64 = 1;
+
+/// How execution continues after it has been resumed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum Step {
+    /// Run until the next breakpoint.
+    #[default]
+    Continue,
+    /// Stop at the next line, entering calls.
+    In,
+    /// Stop at the next line of the frame at the given depth, or of one of its callers.
+    Over(usize),
+    /// Stop once the frame at the given depth has returned.
+    Out(usize),
+}
+
+#[derive(Clone)]
+struct GlobalRead {
+    addr: AccountAddress,
+    ty: TypeTag,
+    value: Option<Vec<u8>>,
+}
+
+/// The state of a suspended VM.
+struct Stopped {
+    frames: Vec<DebugFrame>,
+    global_reads: Vec<GlobalRead>,
+    /// The variables shown by the client. The reference of a list of variables is its index
+    /// plus one.
+    variables: Vec<Vec<Variable>>,
+}
+
+impl Stopped {
+    fn add_variables(&mut self, variables: Vec<Variable>) -> usize {
+        self.variables.push(variables);
+        self.variables.len()
+    }
+}
+
+/// Where execution is about to continue.
+struct Position<'a> {
+    module_id: Option<&'a ModuleId>,
+    function_name: &'a str,
+    function_index: FunctionDefinitionIndex,
+    pc: u16,
+    /// The number of frames on the call stack, including the current one.
+    depth: usize,
+}
+
+impl<'a> Position<'a> {
+    fn of(vm_state: &'a DebugState) -> Self {
+        Self {
+            module_id: vm_state.module_id(),
+            function_name: vm_state.function_name(),
+            function_index: vm_state.function_index(),
+            pc: vm_state.pc(),
+            depth: vm_state.stack_depth(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct State {
+    configured: bool,
+    disconnected: bool,
+    stop_on_entry: bool,
+    pause_requested: bool,
+    step: Step,
+    breakpoints: BTreeMap<SourceKey, BTreeSet<usize>>,
+    function_breakpoints: BTreeSet<String>,
+    /// The line executed last in each frame on the call stack, outermost first. A breakpoint
+    /// or step only stops execution when the line of a frame changes.
+    lines: Vec<Option<(SourceKey, usize)>>,
+    /// The resources read from global storage since the outermost function was entered.
+    global_reads: Vec<GlobalRead>,
+    stopped: Option<Stopped>,
+}
+
+impl State {
+    /// Returns whether execution may be stopped, i.e. whether locations need to be tracked.
+    fn is_active(&self) -> bool {
+        self.stop_on_entry
+            || self.pause_requested
+            || self.step != Step::Continue
+            || !self.breakpoints.is_empty()
+            || !self.function_breakpoints.is_empty()
+    }
+
+    fn stopped(&mut self) -> Result<&mut Stopped> {
+        self.stopped
+            .as_mut()
+            .ok_or_else(|| anyhow!("execution is not stopped"))
+    }
+}
+
+/// A Debug Adapter Protocol session with a single client.
+pub struct DebugSession {
+    sources: DebugSources,
+    writer: Mutex<Box<dyn Write + Send>>,
+    seq: AtomicI64,
+    state: Mutex<State>,
+    /// Notified whenever the client changes the state.
+    state_changed: Condvar,
+}
+
+impl DebugSession {
+    /// Waits for a client to connect on `port`, and returns once the client has set its
+    /// breakpoints. The session is installed as the debug hook of the VM, so all code
+    /// executed afterwards in this process runs under the debugger, until [`Self::finish`]
+    /// is called.
+    pub fn start(port: u16, sources: DebugSources) -> Result<Arc<Self>> {
+        let listener = TcpListener::bind(("127.0.0.1", port))?;
+        eprintln!("Waiting for a debugger to attach on port {}", port);
+        let (stream, _) = listener.accept()?;
+        let session = Arc::new(Self::new(sources, Box::new(stream.try_clone()?)));
+        let serving = session.clone();
+        thread::spawn(move || serving.serve(stream));
+
+        let mut state = session.state.lock().unwrap();
+        while !state.configured && !state.disconnected {
+            state = session.state_changed.wait(state).unwrap();
+        }
+        drop(state);
+        set_debug_hook(session.clone());
+        Ok(session)
+    }
+
+    fn new(sources: DebugSources, writer: Box<dyn Write + Send>) -> Self {
+        Self {
+            sources,
+            writer: Mutex::new(writer),
+            seq: AtomicI64::new(1),
+            state: Mutex::new(State::default()),
+            state_changed: Condvar::new(),
+        }
+    }
+
+    /// Removes the debug hook and tells the client that execution has finished.
+    pub fn finish(&self) {
+        clear_debug_hook();
+        self.send_event("terminated", json!({}));
+    }
+
+    fn next_seq(&self) -> i64 {
+        self.seq.fetch_add(1, Ordering::Relaxed)
+    }
+
+    fn send(&self, message: &Value) {
+        // A client which went away is handled by the reading side of the connection.
+        let _ = write_message(&mut *self.writer.lock().unwrap(), message);
+    }
+
+    fn send_event(&self, name: &str, body: Value) {
+        self.send(&event(self.next_seq(), name, body))
+    }
+
+    fn serve(&self, stream: TcpStream) {
+        let mut reader = BufReader::new(stream);
+        while let Ok(Some(request)) = read_message(&mut reader) {
+            let command = request["command"].as_str().unwrap_or_default();
+            let result = self.handle_request(command, &request["arguments"]);
+            self.send(&response(
+                self.next_seq(),
+                request["seq"].as_i64().unwrap_or_default(),
+                command,
+                result,
+            ));
+            if command == "initialize" {
+                self.send_event("initialized", json!({}));
+            }
+        }
+        self.disconnect();
+    }
+
+    /// Lets execution run to the end without stopping.
+    fn disconnect(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.disconnected = true;
+        state.stopped = None;
+        self.state_changed.notify_all();
+    }
+
+    fn handle_request(&self, command: &str, args: &Value) -> Result<Value> {
+        let mut state = self.state.lock().unwrap();
+        match command {
+            "initialize" => Ok(json!({
+                "supportsConfigurationDoneRequest": true,
+                "supportsFunctionBreakpoints": true,
+            })),
+            "launch" | "attach" => {
+                state.stop_on_entry = args["stopOnEntry"].as_bool().unwrap_or_default();
+                Ok(json!({}))
+            },
+            "setBreakpoints" => self.set_breakpoints(&mut state, args),
+            "setFunctionBreakpoints" => {
+                state.function_breakpoints = args["breakpoints"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|bp| bp["name"].as_str().map(str::to_string))
+                    .collect();
+                let verified = state
+                    .function_breakpoints
+                    .iter()
+                    .map(|_| json!({"verified": true}))
+                    .collect::<Vec<_>>();
+                Ok(json!({ "breakpoints": verified }))
+            },
+            "setExceptionBreakpoints" => Ok(json!({ "breakpoints": [] })),
+            "configurationDone" => {
+                state.configured = true;
+                self.state_changed.notify_all();
+                Ok(json!({}))
+            },
+            "threads" => Ok(json!({ "threads": [{"id": THREAD_ID, "name": "main"}] })),
+            "stackTrace" => self.stack_trace(&mut state),
+            "scopes" => self.scopes(&mut state, args),
+            "variables" => Self::variables(&mut state, args),
+            "source" => {
+                let reference = args["sourceReference"].as_i64().unwrap_or_default();
+                let content = self
+                    .sources
+                    .bytecode_source(reference)
+                    .ok_or_else(|| anyhow!("unknown source reference {}", reference))?;
+                Ok(json!({ "content": content }))
+            },
+            "continue" => self.resume(&mut state, |_| Step::Continue),
+            "next" => self.resume(&mut state, Step::Over),
+            "stepIn" => self.resume(&mut state, |_| Step::In),
+            "stepOut" => self.resume(&mut state, Step::Out),
+            "pause" => {
+                state.pause_requested = true;
+                Ok(json!({}))
+            },
+            "disconnect" | "terminate" => {
+                drop(state);
+                self.disconnect();
+                Ok(json!({}))
+            },
+            _ => bail!("unsupported request `{}`", command),
+        }
+    }
+
+    fn set_breakpoints(&self, state: &mut State, args: &Value) -> Result<Value> {
+        let source = &args["source"];
+        let key = match (source["path"].as_str(), source["sourceReference"].as_i64()) {
+            (_, Some(reference)) if reference > 0 => SourceKey::Bytecode(reference),
+            (Some(path), _) => {
+                let path = PathBuf::from(path);
+                SourceKey::File(std::fs::canonicalize(&path).unwrap_or(path))
+            },
+            _ => bail!("breakpoints must be set in a file or a bytecode source"),
+        };
+        let lines = args["breakpoints"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|bp| bp["line"].as_u64().map(|line| line as usize))
+            .collect::<BTreeSet<_>>();
+        let breakpoints = lines
+            .iter()
+            .map(|line| {
+                json!({
+                    "verified": self.sources.has_code_at(&key, *line),
+                    "line": line,
+                })
+            })
+            .collect::<Vec<_>>();
+        if lines.is_empty() {
+            state.breakpoints.remove(&key);
+        } else {
+            state.breakpoints.insert(key, lines);
+        }
+        Ok(json!({ "breakpoints": breakpoints }))
+    }
+
+    fn resume(&self, state: &mut State, step: impl FnOnce(usize) -> Step) -> Result<Value> {
+        let depth = state.stopped()?.frames.len();
+        state.step = step(depth);
+        state.stopped = None;
+        self.state_changed.notify_all();
+        Ok(json!({ "allThreadsContinued": true }))
+    }
+
+    fn source_json(&self, source: &SourceKey) -> Value {
+        let name = self.sources.source_name(source);
+        match source {
+            SourceKey::File(path) => json!({ "name": name, "path": path }),
+            SourceKey::Bytecode(reference) => json!({ "name": name, "sourceReference": reference }),
+        }
+    }
+
+    fn stack_trace(&self, state: &mut State) -> Result<Value> {
+        let frames = state
+            .stopped()?
+            .frames
+            .iter()
+            .enumerate()
+            .map(|(id, frame)| {
+                let name = match &frame.module_id {
+                    Some(module_id) => self.sources.function_name(module_id, frame.function_index),
+                    None => frame.function_name.clone(),
+                };
+                let mut json = json!({ "id": id, "name": name, "line": 0, "column": 0 });
+                if let Some(loc) =
+                    self.sources
+                        .location(frame.module_id.as_ref(), frame.function_index, frame.pc)
+                {
+                    json["source"] = self.source_json(&loc.source);
+                    json["line"] = json!(loc.line);
+                    json["column"] = json!(loc.column);
+                }
+                json
+            })
+            .collect::<Vec<_>>();
+        Ok(json!({ "stackFrames": frames, "totalFrames": frames.len() }))
+    }
+
+    fn scopes(&self, state: &mut State, args: &Value) -> Result<Value> {
+        let stopped = state.stopped()?;
+        let frame_id = args["frameId"].as_u64().unwrap_or_default() as usize;
+        let frame = stopped
+            .frames
+            .get(frame_id)
+            .ok_or_else(|| anyhow!("unknown frame {}", frame_id))?;
+        let locals = frame
+            .locals
+            .iter()
+            .enumerate()
+            .map(|(idx, local)| {
+                let name = self
+                    .sources
+                    .local_name(frame.module_id.as_ref(), frame.function_index, idx)
+                    .unwrap_or_else(|| format!("l{}", idx));
+                let mut var = decode(
+                    &self.sources,
+                    name,
+                    local.ty.as_ref(),
+                    local.value.as_deref(),
+                );
+                if local.is_reference {
+                    var.ty = format!("&{}", var.ty);
+                }
+                var
+            })
+            .collect();
+        let global_reads = stopped
+            .global_reads
+            .iter()
+            .map(|read| {
+                let name = format!("@{} {}", read.addr.short_str_lossless(), read.ty);
+                let mut var = decode(&self.sources, name, Some(&read.ty), read.value.as_deref());
+                if read.value.is_none() {
+                    var.value = "<does not exist>".to_string();
+                }
+                var
+            })
+            .collect();
+        let locals_ref = stopped.add_variables(locals);
+        let global_reads_ref = stopped.add_variables(global_reads);
+        Ok(json!({ "scopes": [
+            { "name": "Locals", "variablesReference": locals_ref, "expensive": false },
+            { "name": "Global Reads", "variablesReference": global_reads_ref, "expensive": false },
+        ]}))
+    }
+
+    fn variables(state: &mut State, args: &Value) -> Result<Value> {
+        let stopped = state.stopped()?;
+        let reference = args["variablesReference"].as_u64().unwrap_or_default() as usize;
+        let variables = reference
+            .checked_sub(1)
+            .and_then(|idx| stopped.variables.get(idx))
+            .cloned()
+            .ok_or_else(|| anyhow!("unknown variables reference {}", reference))?;
+        let variables = variables
+            .into_iter()
+            .map(|var| {
+                let reference = if var.children.is_empty() {
+                    0
+                } else {
+                    stopped.add_variables(var.children)
+                };
+                json!({
+                    "name": var.name,
+                    "value": var.value,
+                    "type": var.ty,
+                    "variablesReference": reference,
+                })
+            })
+            .collect::<Vec<_>>();
+        Ok(json!({ "variables": variables }))
+    }
+
+    /// Returns why execution should stop before the current instruction, if it should.
+    fn stop_reason(&self, state: &mut State, position: &Position) -> Option<&'static str> {
+        let depth = position.depth;
+        let line = self
+            .sources
+            .location(position.module_id, position.function_index, position.pc)
+            .map(|loc| (loc.source, loc.line));
+        state.lines.resize(depth, None);
+        let new_line = line.is_none() || state.lines[depth - 1] != line;
+        state.lines[depth - 1] = line.clone();
+
+        if std::mem::take(&mut state.stop_on_entry) {
+            return Some("entry");
+        }
+        if std::mem::take(&mut state.pause_requested) {
+            return Some("pause");
+        }
+        let step_done = match state.step {
+            Step::Continue => false,
+            Step::In => new_line,
+            Step::Over(step_depth) => new_line && depth <= step_depth,
+            Step::Out(step_depth) => depth < step_depth,
+        };
+        if step_done {
+            return Some("step");
+        }
+        if new_line {
+            if let Some((source, line)) = &line {
+                if state
+                    .breakpoints
+                    .get(source)
+                    .map_or(false, |lines| lines.contains(line))
+                {
+                    return Some("breakpoint");
+                }
+            }
+        }
+        if position.pc == 0 && !state.function_breakpoints.is_empty() {
+            let name = match position.module_id {
+                Some(module_id) => self
+                    .sources
+                    .function_name(module_id, position.function_index),
+                None => position.function_name.to_string(),
+            };
+            if state
+                .function_breakpoints
+                .iter()
+                .any(|bp| name == *bp || name.ends_with(&format!("::{}", bp)))
+            {
+                return Some("function breakpoint");
+            }
+        }
+        None
+    }
+
+    /// Stops execution before the instruction at `position` if it should stop, and tells the
+    /// client. Returns the reason execution stopped, if it did.
+    fn stop_at(
+        &self,
+        state: &mut State,
+        position: &Position,
+        frames: impl FnOnce() -> Vec<DebugFrame>,
+    ) -> Option<&'static str> {
+        if state.disconnected {
+            return None;
+        }
+        if position.depth == 1 && position.pc == 0 {
+            state.global_reads.clear();
+        }
+        if !state.is_active() {
+            state.lines.clear();
+            return None;
+        }
+        let reason = self.stop_reason(state, position)?;
+        state.step = Step::Continue;
+        state.stopped = Some(Stopped {
+            frames: frames(),
+            global_reads: state.global_reads.clone(),
+            variables: vec![],
+        });
+        self.send_event(
+            "stopped",
+            json!({ "reason": reason, "threadId": THREAD_ID, "allThreadsStopped": true }),
+        );
+        Some(reason)
+    }
+
+    /// Called before each instruction. If execution stops, suspends it until the client
+    /// resumes it.
+    fn on_position(&self, position: &Position, frames: impl FnOnce() -> Vec<DebugFrame>) {
+        let mut state = self.state.lock().unwrap();
+        if self.stop_at(&mut state, position, frames).is_some() {
+            while state.stopped.is_some() {
+                state = self.state_changed.wait(state).unwrap();
+            }
+        }
+    }
+}
+
+impl DebugHook for DebugSession {
+    fn on_instruction(&self, vm_state: &DebugState) {
+        self.on_position(&Position::of(vm_state), || vm_state.frames())
+    }
+
+    fn on_resource_read(&self, addr: AccountAddress, ty: &TypeTag, value: Option<&[u8]>) {
+        let mut state = self.state.lock().unwrap();
+        if !state.disconnected {
+            state.global_reads.push(GlobalRead {
+                addr,
+                ty: ty.clone(),
+                value: value.map(<[u8]>::to_vec),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sources::tests::Example;
+    use move_binary_format::file_format::Bytecode;
+    use move_core_types::{identifier::Identifier, language_storage::StructTag, value::MoveValue};
+    use move_vm_runtime::debug_hook::DebugLocal;
+    use std::{
+        io,
+        time::{Duration, Instant},
+    };
+
+    /// The messages sent to the client.
+    #[derive(Clone, Default)]
+    struct Output(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Output {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Output {
+        fn events(&self) -> Vec<Value> {
+            let bytes = self.0.lock().unwrap().clone();
+            let mut reader = bytes.as_slice();
+            let mut events = vec![];
+            while let Some(message) = read_message(&mut reader).unwrap() {
+                if message["type"] == "event" {
+                    events.push(message);
+                }
+            }
+            events
+        }
+    }
+
+    fn session(example: &mut Example) -> (Arc<DebugSession>, Output) {
+        let output = Output::default();
+        let sources = std::mem::take(&mut example.sources);
+        let session = DebugSession::new(sources, Box::new(output.clone()));
+        (Arc::new(session), output)
+    }
+
+    fn request(session: &DebugSession, command: &str, args: Value) -> Value {
+        session.handle_request(command, &args).unwrap()
+    }
+
+    fn position<'a>(
+        example: &Example,
+        module_id: &'a ModuleId,
+        function: &'a str,
+        pc: u16,
+        depth: usize,
+    ) -> Position<'a> {
+        Position {
+            module_id: Some(module_id),
+            function_name: function,
+            function_index: example.function_index(function),
+            pc,
+            depth,
+        }
+    }
+
+    fn line(session: &DebugSession, example: &Example, function: &str, pc: u16) -> usize {
+        session
+            .sources
+            .location(
+                Some(&example.module_id()),
+                example.function_index(function),
+                pc,
+            )
+            .unwrap()
+            .line
+    }
+
+    /// Returns the offset of the first instruction of a function located at `line`.
+    fn first_pc_at(
+        session: &DebugSession,
+        example: &Example,
+        function: &str,
+        target_line: usize,
+    ) -> u16 {
+        (0..example.code(function).len() as u16)
+            .find(|pc| line(session, example, function, *pc) == target_line)
+            .unwrap()
+    }
+
+    fn debug_frame(
+        example: &Example,
+        function: &str,
+        pc: u16,
+        locals: Vec<DebugLocal>,
+    ) -> DebugFrame {
+        DebugFrame {
+            module_id: Some(example.module_id()),
+            function_name: function.to_string(),
+            function_index: example.function_index(function),
+            pc,
+            locals,
+        }
+    }
+
+    fn stop(reason: &'static str, function: &str, pc: u16) -> (&'static str, String, u16) {
+        (reason, function.to_string(), pc)
+    }
+
+    /// Executes `compute` of the example module, calling the session before each instruction
+    /// like the VM does. Calls are followed, and all other instructions continue with the next
+    /// one. When execution stops, the next of the `commands` resumes it, or `continue` once
+    /// there are none left. Returns where execution stopped.
+    fn run(
+        session: &DebugSession,
+        example: &Example,
+        commands: &[&str],
+    ) -> Vec<(&'static str, String, u16)> {
+        let module_id = example.module_id();
+        let mut commands = commands.iter().copied();
+        let mut stops = vec![];
+        // The call stack, outermost first.
+        let mut stack = vec![("compute".to_string(), 0)];
+        while let Some((function, pc)) = stack.last().cloned() {
+            let reason = session.stop_at(
+                &mut session.state.lock().unwrap(),
+                &position(example, &module_id, &function, pc, stack.len()),
+                || {
+                    stack
+                        .iter()
+                        .rev()
+                        .map(|(function, pc)| debug_frame(example, function, *pc, vec![]))
+                        .collect()
+                },
+            );
+            if let Some(reason) = reason {
+                stops.push(stop(reason, &function, pc));
+                request(session, commands.next().unwrap_or("continue"), json!({}));
+            }
+            match &example.code(&function)[pc as usize] {
+                Bytecode::Call(handle) => {
+                    let handle = example.module.function_handle_at(*handle);
+                    let callee = example.module.identifier_at(handle.name).to_string();
+                    stack.push((callee, 0));
+                },
+                Bytecode::Ret => {
+                    stack.pop();
+                    if let Some((_, caller_pc)) = stack.last_mut() {
+                        *caller_pc += 1;
+                    }
+                },
+                _ => stack.last_mut().unwrap().1 += 1,
+            }
+        }
+        stops
+    }
+
+    #[test]
+    fn test_runs_without_stopping() {
+        let mut example = Example::compile(false);
+        let (session, output) = session(&mut example);
+        assert!(run(&session, &example, &[]).is_empty());
+        assert!(output.events().is_empty());
+        // Execution is not stopped, so there is nothing to inspect or resume
+        assert!(session.handle_request("stackTrace", &json!({})).is_err());
+        assert!(session.handle_request("continue", &json!({})).is_err());
+        assert!(session.handle_request("unknown", &json!({})).is_err());
+    }
+
+    #[test]
+    fn test_step_in() {
+        // Without a source map, each instruction is on a line of its own.
+        let mut example = Example::compile(false);
+        let (session, output) = session(&mut example);
+        request(&session, "launch", json!({ "stopOnEntry": true }));
+        let call = example.call_pcs("compute")[0];
+        let commands = vec!["stepIn"; call as usize + 2];
+
+        let mut expected = vec![stop("entry", "compute", 0)];
+        expected.extend((1..=call).map(|pc| stop("step", "compute", pc)));
+        expected.extend([stop("step", "add", 0), stop("step", "add", 1)]);
+        assert_eq!(run(&session, &example, &commands), expected);
+
+        let events = output.events();
+        assert_eq!(events.len(), expected.len());
+        assert_eq!(events[0]["event"], "stopped");
+        assert_eq!(events[0]["body"]["reason"], "entry");
+        assert_eq!(events[1]["body"]["reason"], "step");
+    }
+
+    #[test]
+    fn test_step_in_stops_on_new_lines() {
+        let mut example = Example::compile(true);
+        let (session, _) = session(&mut example);
+        request(&session, "launch", json!({ "stopOnEntry": true }));
+        let add_line = line(&session, &example, "add", 0);
+        let next_line_pc = (1..example.code("add").len() as u16)
+            .find(|pc| line(&session, &example, "add", *pc) != add_line)
+            .unwrap();
+
+        // The instructions on the line of the call are skipped, and so is the rest of the
+        // first line of the callee.
+        assert_eq!(run(&session, &example, &["stepIn", "stepIn"]), vec![
+            stop("entry", "compute", 0),
+            stop("step", "add", 0),
+            stop("step", "add", next_line_pc),
+        ]);
+    }
+
+    #[test]
+    fn test_step_over() {
+        let mut example = Example::compile(false);
+        let (session, _) = session(&mut example);
+        request(&session, "launch", json!({ "stopOnEntry": true }));
+        let call = example.call_pcs("compute")[0];
+        let commands = vec!["next"; call as usize + 2];
+
+        // The callee runs without stopping.
+        let mut expected = vec![stop("entry", "compute", 0)];
+        expected.extend((1..=call + 2).map(|pc| stop("step", "compute", pc)));
+        assert_eq!(run(&session, &example, &commands), expected);
+    }
+
+    #[test]
+    fn test_step_over_return() {
+        let mut example = Example::compile(false);
+        let module_id = example.module_id();
+        let (session, _) = session(&mut example);
+        let ret = example.code("add").len() as u16 - 1;
+        let SourceKey::Bytecode(reference) = session
+            .sources
+            .location(Some(&module_id), example.function_index("add"), 0)
+            .unwrap()
+            .source
+        else {
+            panic!("expected a bytecode source");
+        };
+        let response = request(
+            &session,
+            "setBreakpoints",
+            json!({
+                "source": { "sourceReference": reference },
+                "breakpoints": [{ "line": ret + 1 }],
+            }),
+        );
+        assert_eq!(
+            response,
+            json!({ "breakpoints": [{ "verified": true, "line": ret + 1 }] })
+        );
+
+        // Stepping over the return stops in the caller, right after the call.
+        let call = example.call_pcs("compute")[0];
+        assert_eq!(run(&session, &example, &["next"]), vec![
+            stop("breakpoint", "add", ret),
+            stop("step", "compute", call + 1),
+            stop("breakpoint", "add", ret),
+        ]);
+    }
+
+    #[test]
+    fn test_step_out() {
+        let mut example = Example::compile(false);
+        let (session, _) = session(&mut example);
+        request(
+            &session,
+            "setFunctionBreakpoints",
+            json!({ "breakpoints": [{ "name": "add" }] }),
+        );
+        let call = example.call_pcs("compute")[0];
+        assert_eq!(run(&session, &example, &["stepOut"]), vec![
+            stop("function breakpoint", "add", 0),
+            stop("step", "compute", call + 1),
+            stop("function breakpoint", "add", 0),
+        ]);
+    }
+
+    #[test]
+    fn test_function_breakpoints() {
+        let mut example = Example::compile(false);
+        let (session, _) = session(&mut example);
+        let response = request(
+            &session,
+            "setFunctionBreakpoints",
+            json!({ "breakpoints": [
+                { "name": "0x42::example::compute" },
+                { "name": "ample::add" },
+            ]}),
+        );
+        assert_eq!(response["breakpoints"].as_array().unwrap().len(), 2);
+        // Names match fully qualified, or by trailing segments of their path, but not by parts of
+        // a segment
+        assert_eq!(run(&session, &example, &[]), vec![stop(
+            "function breakpoint",
+            "compute",
+            0
+        )]);
+
+        request(
+            &session,
+            "setFunctionBreakpoints",
+            json!({ "breakpoints": [{ "name": "example::add" }] }),
+        );
+        assert_eq!(run(&session, &example, &[]), vec![
+            stop("function breakpoint", "add", 0),
+            stop("function breakpoint", "add", 0),
+        ]);
+    }
+
+    #[test]
+    fn test_line_breakpoints() {
+        let mut example = Example::compile(true);
+        let (session, _) = session(&mut example);
+        let path = example.path.to_string_lossy().to_string();
+        let response = request(
+            &session,
+            "setBreakpoints",
+            json!({
+                "source": { "path": path },
+                "breakpoints": [{ "line": 11 }, { "line": 6 }, { "line": 8 }],
+            }),
+        );
+        // Line 8 is empty
+        assert_eq!(
+            response,
+            json!({ "breakpoints": [
+                { "verified": true, "line": 6 },
+                { "verified": false, "line": 8 },
+                { "verified": true, "line": 11 },
+            ]})
+        );
+
+        // Each breakpoint stops once per execution of its line
+        let add_pc = first_pc_at(&session, &example, "add", 6);
+        let compute_pc = first_pc_at(&session, &example, "compute", 11);
+        assert_eq!(run(&session, &example, &[]), vec![
+            stop("breakpoint", "add", add_pc),
+            stop("breakpoint", "compute", compute_pc),
+            stop("breakpoint", "add", add_pc),
+        ]);
+
+        // Breakpoints are replaced per source
+        request(
+            &session,
+            "setBreakpoints",
+            json!({ "source": { "path": path }, "breakpoints": [] }),
+        );
+        assert!(run(&session, &example, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_pause() {
+        let mut example = Example::compile(false);
+        let (session, _) = session(&mut example);
+        request(&session, "pause", json!({}));
+        assert_eq!(run(&session, &example, &[]), vec![stop(
+            "pause", "compute", 0
+        )]);
+        // A pause request only stops execution once
+        assert!(run(&session, &example, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_disconnect_releases_stopped_vm() {
+        let mut example = Example::compile(false);
+        let module_id = example.module_id();
+        let (session, output) = session(&mut example);
+        request(&session, "launch", json!({ "stopOnEntry": true }));
+
+        let vm = {
+            let session = session.clone();
+            let module_id = module_id.clone();
+            let function_index = example.function_index("compute");
+            thread::spawn(move || {
+                let position = Position {
+                    module_id: Some(&module_id),
+                    function_name: "compute",
+                    function_index,
+                    pc: 0,
+                    depth: 1,
+                };
+                session.on_position(&position, Vec::new);
+            })
+        };
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while output.events().is_empty() {
+            assert!(Instant::now() < deadline, "execution did not stop");
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(output.events()[0]["body"]["reason"], "entry");
+        assert!(!vm.is_finished());
+
+        request(&session, "disconnect", json!({}));
+        vm.join().unwrap();
+
+        // Execution no longer stops
+        request(&session, "pause", json!({}));
+        assert!(run(&session, &example, &[]).is_empty());
+        assert!(session.handle_request("stackTrace", &json!({})).is_err());
+    }
+
+    #[test]
+    fn test_stack_trace_and_locals() {
+        let mut example = Example::compile(true);
+        let module_id = example.module_id();
+        let (session, _) = session(&mut example);
+        request(
+            &session,
+            "setFunctionBreakpoints",
+            json!({ "breakpoints": [{ "name": "add" }] }),
+        );
+        let u64_bytes = |n: u64| MoveValue::U64(n).simple_serialize().unwrap();
+        let call = example.call_pcs("compute")[0];
+        let frames = vec![
+            debug_frame(&example, "add", 0, vec![
+                DebugLocal {
+                    ty: Some(TypeTag::U64),
+                    is_reference: false,
+                    value: Some(u64_bytes(3)),
+                },
+                DebugLocal {
+                    ty: Some(TypeTag::U64),
+                    is_reference: true,
+                    value: Some(u64_bytes(4)),
+                },
+            ]),
+            debug_frame(&example, "compute", call, vec![DebugLocal {
+                ty: Some(TypeTag::U64),
+                is_reference: false,
+                value: None,
+            }]),
+        ];
+        let reason = session.stop_at(
+            &mut session.state.lock().unwrap(),
+            &position(&example, &module_id, "add", 0, 2),
+            || frames,
+        );
+        assert_eq!(reason, Some("function breakpoint"));
+
+        let trace = request(&session, "stackTrace", json!({}));
+        assert_eq!(trace["totalFrames"], 2);
+        let stack_frames = &trace["stackFrames"];
+        assert_eq!(stack_frames[0]["name"], "0x42::example::add");
+        assert_eq!(stack_frames[0]["source"]["name"], "example.move");
+        assert_eq!(
+            stack_frames[0]["source"]["path"],
+            example.path.to_str().unwrap()
+        );
+        assert_eq!(stack_frames[0]["line"], line(&session, &example, "add", 0));
+        assert_eq!(stack_frames[1]["name"], "0x42::example::compute");
+        assert_eq!(stack_frames[1]["line"], 10);
+
+        let scopes = request(&session, "scopes", json!({ "frameId": 0 }));
+        assert_eq!(scopes["scopes"][0]["name"], "Locals");
+        assert_eq!(scopes["scopes"][1]["name"], "Global Reads");
+        let locals = request(
+            &session,
+            "variables",
+            json!({ "variablesReference": scopes["scopes"][0]["variablesReference"] }),
+        );
+        assert_eq!(
+            locals,
+            json!({ "variables": [
+                { "name": "a", "value": "3", "type": "u64", "variablesReference": 0 },
+                { "name": "b", "value": "4", "type": "&u64", "variablesReference": 0 },
+            ]})
+        );
+
+        let scopes = request(&session, "scopes", json!({ "frameId": 1 }));
+        let locals = request(
+            &session,
+            "variables",
+            json!({ "variablesReference": scopes["scopes"][0]["variablesReference"] }),
+        );
+        assert_eq!(
+            locals,
+            json!({ "variables": [
+                { "name": "x", "value": "<unavailable>", "type": "u64", "variablesReference": 0 },
+            ]})
+        );
+
+        assert!(session
+            .handle_request("scopes", &json!({ "frameId": 2 }))
+            .is_err());
+        assert!(session
+            .handle_request("variables", &json!({ "variablesReference": 100 }))
+            .is_err());
+    }
+
+    #[test]
+    fn test_global_reads() {
+        let mut example = Example::compile(false);
+        let module_id = example.module_id();
+        let (session, _) = session(&mut example);
+        request(
+            &session,
+            "setFunctionBreakpoints",
+            json!({ "breakpoints": [{ "name": "add" }] }),
+        );
+        let point = TypeTag::Struct(Box::new(StructTag {
+            address: *module_id.address(),
+            module: Identifier::new("example").unwrap(),
+            name: Identifier::new("Point").unwrap(),
+            type_args: vec![],
+        }));
+        let point_bytes = [1u64.to_le_bytes(), 2u64.to_le_bytes()].concat();
+        let addr = |hex: &str| AccountAddress::from_hex_literal(hex).unwrap();
+
+        // Reads before the outermost function is entered are not shown
+        session.on_resource_read(addr("0x3"), &point, Some(&point_bytes));
+        let entry = position(&example, &module_id, "compute", 0, 1);
+        assert_eq!(
+            session.stop_at(&mut session.state.lock().unwrap(), &entry, Vec::new),
+            None
+        );
+        session.on_resource_read(addr("0x1"), &point, Some(&point_bytes));
+        session.on_resource_read(addr("0x2"), &point, None);
+        let reason = session.stop_at(
+            &mut session.state.lock().unwrap(),
+            &position(&example, &module_id, "add", 0, 2),
+            || vec![debug_frame(&example, "add", 0, vec![])],
+        );
+        assert_eq!(reason, Some("function breakpoint"));
+
+        let scopes = request(&session, "scopes", json!({ "frameId": 0 }));
+        let reads = request(
+            &session,
+            "variables",
+            json!({ "variablesReference": scopes["scopes"][1]["variablesReference"] }),
+        );
+        let reads = reads["variables"].as_array().unwrap();
+        assert_eq!(reads.len(), 2);
+        assert_eq!(reads[0]["name"], "@0x1 0x42::example::Point");
+        assert_eq!(reads[0]["value"], "Point");
+        assert_eq!(reads[0]["type"], "0x42::example::Point");
+        assert_eq!(reads[1]["name"], "@0x2 0x42::example::Point");
+        assert_eq!(reads[1]["value"], "<does not exist>");
+        assert_eq!(reads[1]["variablesReference"], 0);
+
+        let fields = request(
+            &session,
+            "variables",
+            json!({ "variablesReference": reads[0]["variablesReference"] }),
+        );
+        assert_eq!(
+            fields,
+            json!({ "variables": [
+                { "name": "x", "value": "1", "type": "u64", "variablesReference": 0 },
+                { "name": "y", "value": "2", "type": "u64", "variablesReference": 0 },
+            ]})
+        );
+    }
+}
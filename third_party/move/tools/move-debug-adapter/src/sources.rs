@@ -0,0 +1,605 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Maps the code offsets of executed functions to locations in source files, using the source
+//! maps produced by the compiler. Functions for which there is no source map, such as those of
+//! modules fetched from chain, are shown as bytecode, one instruction per line.
+
+use move_binary_format::{file_format::FunctionDefinitionIndex, CompiledModule};
+use move_bytecode_source_map::source_map::SourceMap;
+use move_bytecode_utils::compiled_module_viewer::CompiledModuleView;
+use move_command_line_common::files::FileHash;
+use move_compiler::unit_test::TestPlan;
+use move_core_types::language_storage::ModuleId;
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+    sync::{Arc, Mutex, RwLock},
+};
+
+/// Loads modules which have not been added to [`DebugSources`] up front.
+pub type ModuleFetcher = Box<dyn Fn(&ModuleId) -> Option<Arc<CompiledModule>> + Send + Sync>;
+
+/// A source file, with the offsets at which its lines start.
+struct SourceFile {
+    path: PathBuf,
+    line_starts: Vec<usize>,
+}
+
+impl SourceFile {
+    fn new(path: PathBuf, contents: &str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(contents.match_indices('\n').map(|(idx, _)| idx + 1))
+            .collect();
+        Self { path, line_starts }
+    }
+
+    /// Returns the 1-based line and column of a byte offset.
+    fn line_and_column(&self, offset: usize) -> (usize, usize) {
+        let line = match self.line_starts.binary_search(&offset) {
+            Ok(line) => line,
+            Err(next_line) => next_line - 1,
+        };
+        (line + 1, offset - self.line_starts[line] + 1)
+    }
+}
+
+/// Identifies a source shown by the client: either a file on disk, or the bytecode of a
+/// function, which the client requests by reference.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SourceKey {
+    File(PathBuf),
+    Bytecode(i64),
+}
+
+/// A location in a source, with 1-based line and column.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SourceLocation {
+    pub source: SourceKey,
+    pub line: usize,
+    pub column: usize,
+}
+
+struct ModuleInfo {
+    module: Arc<CompiledModule>,
+    source_map: Option<SourceMap>,
+}
+
+/// The modules and source files known to the debugger.
+#[derive(Default)]
+pub struct DebugSources {
+    /// Modules by id. `None` marks a module which the fetcher could not find.
+    modules: RwLock<BTreeMap<ModuleId, Option<Arc<ModuleInfo>>>>,
+    files: HashMap<FileHash, SourceFile>,
+    fetcher: Option<ModuleFetcher>,
+    bytecode_sources: Mutex<BytecodeSources>,
+}
+
+/// The functions shown as bytecode. The reference of a function is its index plus one.
+#[derive(Default)]
+struct BytecodeSources {
+    functions: Vec<(ModuleId, FunctionDefinitionIndex)>,
+    references: BTreeMap<(ModuleId, FunctionDefinitionIndex), i64>,
+}
+
+impl DebugSources {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates the sources for running the tests of a test plan.
+    pub fn from_test_plan(test_plan: &TestPlan) -> Self {
+        let mut sources = Self::new();
+        for (hash, (name, contents)) in &test_plan.files {
+            sources.add_file(*hash, PathBuf::from(name.as_str()), contents);
+        }
+        for named_module in test_plan.module_info.values() {
+            sources.add_module(
+                named_module.module.clone(),
+                Some(named_module.source_map.clone()),
+            );
+        }
+        sources
+    }
+
+    /// Sets the function which loads modules which have not been added.
+    pub fn with_module_fetcher(mut self, fetcher: ModuleFetcher) -> Self {
+        self.fetcher = Some(fetcher);
+        self
+    }
+
+    pub fn add_file(&mut self, hash: FileHash, path: PathBuf, contents: &str) {
+        // Clients identify files by absolute paths.
+        let path = std::fs::canonicalize(&path).unwrap_or(path);
+        self.files.insert(hash, SourceFile::new(path, contents));
+    }
+
+    pub fn add_module(&mut self, module: CompiledModule, source_map: Option<SourceMap>) {
+        self.modules.get_mut().unwrap().insert(
+            module.self_id(),
+            Some(Arc::new(ModuleInfo {
+                module: Arc::new(module),
+                source_map,
+            })),
+        );
+    }
+
+    fn module(&self, id: &ModuleId) -> Option<Arc<ModuleInfo>> {
+        if let Some(info) = self.modules.read().unwrap().get(id) {
+            return info.clone();
+        }
+        let info = self.fetcher.as_ref().and_then(|fetcher| {
+            fetcher(id).map(|module| {
+                Arc::new(ModuleInfo {
+                    module,
+                    source_map: None,
+                })
+            })
+        });
+        self.modules
+            .write()
+            .unwrap()
+            .insert(id.clone(), info.clone());
+        info
+    }
+
+    /// Returns the source location of the instruction at `pc`, or `None` for scripts and
+    /// unknown modules.
+    pub fn location(
+        &self,
+        module_id: Option<&ModuleId>,
+        function_index: FunctionDefinitionIndex,
+        pc: u16,
+    ) -> Option<SourceLocation> {
+        let module_id = module_id?;
+        let info = self.module(module_id)?;
+        let file_location = info.source_map.as_ref().and_then(|source_map| {
+            let loc = source_map.get_code_location(function_index, pc).ok()?;
+            let file = self.files.get(&loc.file_hash())?;
+            let (line, column) = file.line_and_column(loc.start() as usize);
+            Some(SourceLocation {
+                source: SourceKey::File(file.path.clone()),
+                line,
+                column,
+            })
+        });
+        file_location.or_else(|| {
+            Some(SourceLocation {
+                source: SourceKey::Bytecode(self.bytecode_reference(module_id, function_index)),
+                line: pc as usize + 1,
+                column: 1,
+            })
+        })
+    }
+
+    fn bytecode_reference(
+        &self,
+        module_id: &ModuleId,
+        function_index: FunctionDefinitionIndex,
+    ) -> i64 {
+        let mut bytecode_sources = self.bytecode_sources.lock().unwrap();
+        let BytecodeSources {
+            functions,
+            references,
+        } = &mut *bytecode_sources;
+        *references
+            .entry((module_id.clone(), function_index))
+            .or_insert_with(|| {
+                functions.push((module_id.clone(), function_index));
+                functions.len() as i64
+            })
+    }
+
+    fn bytecode_function(&self, reference: i64) -> Option<(ModuleId, FunctionDefinitionIndex)> {
+        let bytecode_sources = self.bytecode_sources.lock().unwrap();
+        usize::try_from(reference - 1)
+            .ok()
+            .and_then(|idx| bytecode_sources.functions.get(idx).cloned())
+    }
+
+    /// Returns the name of a local as given in the source map, if there is one.
+    pub fn local_name(
+        &self,
+        module_id: Option<&ModuleId>,
+        function_index: FunctionDefinitionIndex,
+        local_index: usize,
+    ) -> Option<String> {
+        let info = self.module(module_id?)?;
+        let (name, _) = info
+            .source_map
+            .as_ref()?
+            .get_parameter_or_local_name(function_index, local_index as u64)
+            .ok()?;
+        Some(name)
+    }
+
+    /// Returns the name under which the client shows a source.
+    pub fn source_name(&self, source: &SourceKey) -> String {
+        match source {
+            SourceKey::File(path) => path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            SourceKey::Bytecode(reference) => match self.bytecode_function(*reference) {
+                Some((module_id, function_index)) => format!(
+                    "{} (bytecode)",
+                    self.function_name(&module_id, function_index)
+                ),
+                None => "<unknown>".to_string(),
+            },
+        }
+    }
+
+    /// Returns the fully qualified name of a function.
+    pub fn function_name(
+        &self,
+        module_id: &ModuleId,
+        function_index: FunctionDefinitionIndex,
+    ) -> String {
+        let name = self.module(module_id).and_then(|info| {
+            let module = &info.module;
+            let fdef = module.function_defs.get(function_index.0 as usize)?;
+            let handle = module.function_handle_at(fdef.function);
+            Some(module.identifier_at(handle.name).to_string())
+        });
+        format!(
+            "0x{}::{}::{}",
+            module_id.address().short_str_lossless(),
+            module_id.name(),
+            name.unwrap_or_else(|| format!("<function {}>", function_index.0))
+        )
+    }
+
+    /// Returns the text of a function shown as bytecode, with instruction `pc` on line `pc + 1`.
+    pub fn bytecode_source(&self, reference: i64) -> Option<String> {
+        let (module_id, function_index) = self.bytecode_function(reference)?;
+        let info = self.module(&module_id)?;
+        let code = info
+            .module
+            .function_defs
+            .get(function_index.0 as usize)?
+            .code
+            .as_ref()?;
+        Some(
+            code.code
+                .iter()
+                .enumerate()
+                .map(|(pc, instr)| format!("{}: {:?}\n", pc, instr))
+                .collect(),
+        )
+    }
+
+    /// Returns whether any instruction is located at the given line of a source, i.e. whether a
+    /// breakpoint at that line can be hit.
+    pub fn has_code_at(&self, source: &SourceKey, line: usize) -> bool {
+        match source {
+            SourceKey::File(path) => {
+                let Some((hash, file)) = self.files.iter().find(|(_, file)| file.path == *path)
+                else {
+                    return false;
+                };
+                self.modules.read().unwrap().values().flatten().any(|info| {
+                    let Some(source_map) = &info.source_map else {
+                        return false;
+                    };
+                    (0..info.module.function_defs.len())
+                        .filter_map(|idx| {
+                            source_map
+                                .get_function_source_map(FunctionDefinitionIndex(idx as u16))
+                                .ok()
+                        })
+                        .flat_map(|function_map| function_map.code_map.values())
+                        .any(|loc| {
+                            loc.file_hash() == *hash
+                                && file.line_and_column(loc.start() as usize).0 == line
+                        })
+                })
+            },
+            SourceKey::Bytecode(reference) => self
+                .bytecode_source(*reference)
+                .map_or(false, |text| line >= 1 && line <= text.lines().count()),
+        }
+    }
+}
+
+impl CompiledModuleView for &DebugSources {
+    type Item = Arc<CompiledModule>;
+
+    fn view_compiled_module(&self, id: &ModuleId) -> anyhow::Result<Option<Self::Item>> {
+        Ok(self.module(id).map(|info| info.module.clone()))
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use move_binary_format::file_format::Bytecode;
+    use move_compiler::{
+        compiled_unit::AnnotatedCompiledUnit,
+        shared::{known_attributes::KnownAttribute, Flags},
+        Compiler,
+    };
+    use move_core_types::{account_address::AccountAddress, identifier::Identifier};
+    use std::{
+        fs,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+    use tempfile::TempDir;
+
+    /// The module used in tests, which refer to its line numbers.
+    const EXAMPLE: &str = "\
+module 0x42::example {
+    struct Point has copy, drop { x: u64, y: u64 }
+
+    fun add(a: u64, b: u64): u64 {
+        let sum = a + b;
+        sum
+    }
+
+    public fun compute(x: u64): u64 {
+        let y = add(x, 1);
+        let z = add(y, 2);
+        z
+    }
+}
+";
+
+    /// The compiled example module, with the sources of the debugger for it.
+    pub(crate) struct Example {
+        /// Keeps the source file around.
+        _dir: TempDir,
+        pub(crate) path: PathBuf,
+        pub(crate) module: CompiledModule,
+        pub(crate) sources: DebugSources,
+    }
+
+    impl Example {
+        /// Compiles the example module. Unless `with_source_map` is set, the sources do not
+        /// contain its source map, so that its functions are shown as bytecode.
+        pub(crate) fn compile(with_source_map: bool) -> Self {
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("example.move");
+            fs::write(&path, EXAMPLE).unwrap();
+            let (files, units) = Compiler::from_files(
+                vec![path.to_string_lossy().to_string()],
+                vec![],
+                BTreeMap::<String, _>::new(),
+                Flags::empty(),
+                KnownAttribute::get_all_attribute_names(),
+            )
+            .build_and_report()
+            .unwrap();
+            let Some(AnnotatedCompiledUnit::Module(unit)) = units.into_iter().next() else {
+                panic!("expected a module");
+            };
+            let module = unit.named_module.module;
+            let mut sources = DebugSources::new();
+            if with_source_map {
+                for (hash, (name, contents)) in &files {
+                    sources.add_file(*hash, PathBuf::from(name.as_str()), contents);
+                }
+                sources.add_module(module.clone(), Some(unit.named_module.source_map));
+            } else {
+                sources.add_module(module.clone(), None);
+            }
+            Self {
+                path: fs::canonicalize(&path).unwrap(),
+                _dir: dir,
+                module,
+                sources,
+            }
+        }
+
+        pub(crate) fn module_id(&self) -> ModuleId {
+            self.module.self_id()
+        }
+
+        pub(crate) fn function_index(&self, name: &str) -> FunctionDefinitionIndex {
+            let idx = self
+                .module
+                .function_defs
+                .iter()
+                .position(|fdef| {
+                    let handle = self.module.function_handle_at(fdef.function);
+                    self.module.identifier_at(handle.name).as_str() == name
+                })
+                .unwrap();
+            FunctionDefinitionIndex(idx as u16)
+        }
+
+        pub(crate) fn code(&self, name: &str) -> &[Bytecode] {
+            let fdef = &self.module.function_defs[self.function_index(name).0 as usize];
+            &fdef.code.as_ref().unwrap().code
+        }
+
+        /// Returns the offsets of the calls in a function.
+        pub(crate) fn call_pcs(&self, name: &str) -> Vec<u16> {
+            (0..self.code(name).len() as u16)
+                .filter(|pc| matches!(self.code(name)[*pc as usize], Bytecode::Call(_)))
+                .collect()
+        }
+    }
+
+    fn location(example: &Example, function: &str, pc: u16) -> Option<SourceLocation> {
+        example.sources.location(
+            Some(&example.module_id()),
+            example.function_index(function),
+            pc,
+        )
+    }
+
+    #[test]
+    fn test_line_and_column() {
+        let file = SourceFile::new(PathBuf::from("file.move"), "ab\ncd\n\nx");
+        assert_eq!(file.line_and_column(0), (1, 1));
+        assert_eq!(file.line_and_column(1), (1, 2));
+        assert_eq!(file.line_and_column(3), (2, 1));
+        assert_eq!(file.line_and_column(6), (3, 1));
+        assert_eq!(file.line_and_column(7), (4, 1));
+    }
+
+    #[test]
+    fn test_file_locations() {
+        let example = Example::compile(true);
+        let entry = location(&example, "compute", 0).unwrap();
+        assert_eq!(entry.source, SourceKey::File(example.path.clone()));
+        assert_eq!(entry.line, 10);
+        // Both calls of `compute` are located on the lines of their `let`
+        let call_lines = example
+            .call_pcs("compute")
+            .into_iter()
+            .map(|pc| location(&example, "compute", pc).unwrap().line)
+            .collect::<Vec<_>>();
+        assert_eq!(call_lines, vec![10, 11]);
+        for pc in 0..example.code("add").len() as u16 {
+            let line = location(&example, "add", pc).unwrap().line;
+            assert!((4..=7).contains(&line));
+        }
+        // Scripts have no location
+        assert_eq!(
+            example
+                .sources
+                .location(None, example.function_index("add"), 0),
+            None
+        );
+    }
+
+    #[test]
+    fn test_bytecode_locations() {
+        let example = Example::compile(false);
+        // Functions get references in the order they are first shown, one line per instruction
+        assert_eq!(
+            location(&example, "compute", 3),
+            Some(SourceLocation {
+                source: SourceKey::Bytecode(1),
+                line: 4,
+                column: 1,
+            })
+        );
+        assert_eq!(
+            location(&example, "add", 0).unwrap().source,
+            SourceKey::Bytecode(2)
+        );
+        assert_eq!(
+            location(&example, "compute", 0).unwrap().source,
+            SourceKey::Bytecode(1)
+        );
+
+        let sources = &example.sources;
+        assert_eq!(
+            sources.source_name(&SourceKey::Bytecode(1)),
+            "0x42::example::compute (bytecode)"
+        );
+        assert_eq!(sources.source_name(&SourceKey::Bytecode(3)), "<unknown>");
+        let text = sources.bytecode_source(2).unwrap();
+        assert_eq!(text.lines().count(), example.code("add").len());
+        assert!(text.starts_with("0: "));
+        assert_eq!(sources.bytecode_source(3), None);
+        assert_eq!(sources.bytecode_source(0), None);
+    }
+
+    #[test]
+    fn test_has_code_at() {
+        let example = Example::compile(true);
+        let file = SourceKey::File(example.path.clone());
+        let sources = &example.sources;
+        assert!(sources.has_code_at(&file, 5));
+        assert!(sources.has_code_at(&file, 10));
+        assert!(sources.has_code_at(&file, 11));
+        // A struct declaration, an empty line, and a line past the end
+        assert!(!sources.has_code_at(&file, 2));
+        assert!(!sources.has_code_at(&file, 8));
+        assert!(!sources.has_code_at(&file, 100));
+        assert!(!sources.has_code_at(&SourceKey::File(PathBuf::from("other.move")), 5));
+
+        let example = Example::compile(false);
+        let sources = &example.sources;
+        let len = example.code("compute").len();
+        let SourceKey::Bytecode(reference) = location(&example, "compute", 0).unwrap().source
+        else {
+            panic!("expected a bytecode source");
+        };
+        let bytecode = SourceKey::Bytecode(reference);
+        assert!(sources.has_code_at(&bytecode, 1));
+        assert!(sources.has_code_at(&bytecode, len));
+        assert!(!sources.has_code_at(&bytecode, 0));
+        assert!(!sources.has_code_at(&bytecode, len + 1));
+        assert!(!sources.has_code_at(&SourceKey::Bytecode(reference + 1), 1));
+    }
+
+    #[test]
+    fn test_names() {
+        let example = Example::compile(true);
+        let module_id = example.module_id();
+        let add = example.function_index("add");
+        let sources = &example.sources;
+        assert_eq!(sources.function_name(&module_id, add), "0x42::example::add");
+        assert_eq!(
+            sources.function_name(&module_id, FunctionDefinitionIndex(100)),
+            "0x42::example::<function 100>"
+        );
+        let unknown = ModuleId::new(
+            AccountAddress::from_hex_literal("0x43").unwrap(),
+            Identifier::new("unknown").unwrap(),
+        );
+        assert_eq!(
+            sources.function_name(&unknown, add),
+            format!("0x43::unknown::<function {}>", add.0)
+        );
+        assert_eq!(
+            sources.local_name(Some(&module_id), add, 0).as_deref(),
+            Some("a")
+        );
+        assert_eq!(
+            sources.local_name(Some(&module_id), add, 1).as_deref(),
+            Some("b")
+        );
+        assert_eq!(sources.local_name(None, add, 0), None);
+        assert_eq!(
+            sources.source_name(&SourceKey::File(example.path.clone())),
+            "example.move"
+        );
+
+        // Without a source map, locals have no names
+        let example = Example::compile(false);
+        assert_eq!(
+            example
+                .sources
+                .local_name(Some(&module_id), example.function_index("add"), 0),
+            None
+        );
+    }
+
+    #[test]
+    fn test_module_fetcher() {
+        let example = Example::compile(false);
+        let module = Arc::new(example.module.clone());
+        let fetches = Arc::new(AtomicUsize::new(0));
+        let fetcher_fetches = fetches.clone();
+        let sources = DebugSources::new().with_module_fetcher(Box::new(move |id| {
+            fetcher_fetches.fetch_add(1, Ordering::SeqCst);
+            (*id == module.self_id()).then(|| module.clone())
+        }));
+
+        // Fetched modules are shown as bytecode, and are only fetched once
+        let add = example.function_index("add");
+        let loc = sources
+            .location(Some(&example.module_id()), add, 0)
+            .unwrap();
+        assert_eq!(loc.source, SourceKey::Bytecode(1));
+        assert_eq!(
+            sources.function_name(&example.module_id(), add),
+            "0x42::example::add"
+        );
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+
+        // Modules which cannot be fetched are only looked up once, too
+        let unknown = ModuleId::new(
+            AccountAddress::from_hex_literal("0x43").unwrap(),
+            Identifier::new("unknown").unwrap(),
+        );
+        assert_eq!(sources.location(Some(&unknown), add, 0), None);
+        assert_eq!(sources.location(Some(&unknown), add, 0), None);
+        assert_eq!(fetches.load(Ordering::SeqCst), 2);
+    }
+}
@@ -0,0 +1,223 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Decodes locals and resources into the tree of variables shown by the client.
+
+use crate::sources::DebugSources;
+use move_core_types::{account_address::AccountAddress, language_storage::TypeTag};
+use move_resource_viewer::{AnnotatedMoveValue, MoveValueAnnotator};
+
+/// A variable, with the fields or elements of its value as children.
+#[derive(Clone, Debug)]
+pub struct Variable {
+    pub name: String,
+    pub value: String,
+    pub ty: String,
+    pub children: Vec<Variable>,
+}
+
+impl Variable {
+    fn leaf(name: String, value: String, ty: String) -> Self {
+        Self {
+            name,
+            value,
+            ty,
+            children: vec![],
+        }
+    }
+}
+
+/// Decodes the BCS encoding of a value of type `ty`. A missing type or value is shown as
+/// unavailable, and values which cannot be decoded are shown as hex.
+pub fn decode(
+    sources: &DebugSources,
+    name: String,
+    ty: Option<&TypeTag>,
+    bytes: Option<&[u8]>,
+) -> Variable {
+    let Some(ty) = ty else {
+        return Variable::leaf(name, "<unavailable>".to_string(), "?".to_string());
+    };
+    let Some(bytes) = bytes else {
+        return Variable::leaf(name, "<unavailable>".to_string(), ty.to_string());
+    };
+    if let TypeTag::Signer = ty {
+        if let Ok(addr) = AccountAddress::from_bytes(bytes) {
+            return Variable::leaf(
+                name,
+                format!("signer(@{})", addr.short_str_lossless()),
+                ty.to_string(),
+            );
+        }
+    }
+    match MoveValueAnnotator::new(sources).view_value(ty, bytes) {
+        Ok(value) => from_annotated(name, &value),
+        Err(_) => Variable::leaf(name, format!("0x{}", hex::encode(bytes)), ty.to_string()),
+    }
+}
+
+fn from_annotated(name: String, value: &AnnotatedMoveValue) -> Variable {
+    use AnnotatedMoveValue as A;
+    let ty = value.ty_tag().to_string();
+    let value = match value {
+        A::U8(n) => n.to_string(),
+        A::U16(n) => n.to_string(),
+        A::U32(n) => n.to_string(),
+        A::U64(n) => n.to_string(),
+        A::U128(n) => n.to_string(),
+        A::U256(n) => n.to_string(),
+        A::Bool(b) => b.to_string(),
+        A::Address(addr) => format!("@{}", addr.short_str_lossless()),
+        A::Bytes(bytes) => format!("0x{}", hex::encode(bytes)),
+        A::Vector(_, elems) => {
+            return Variable {
+                name,
+                value: format!("vector (length {})", elems.len()),
+                ty,
+                children: elems
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, elem)| from_annotated(format!("[{}]", idx), elem))
+                    .collect(),
+            }
+        },
+        A::Struct(s) => {
+            let value = match &s.variant_info {
+                Some((_, variant)) => format!("{}::{}", s.ty_tag.name, variant),
+                None => s.ty_tag.name.to_string(),
+            };
+            return Variable {
+                name,
+                value,
+                ty,
+                children: s
+                    .value
+                    .iter()
+                    .map(|(field, value)| from_annotated(field.to_string(), value))
+                    .collect(),
+            };
+        },
+    };
+    Variable::leaf(name, value, ty)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sources::tests::Example;
+    use move_core_types::{identifier::Identifier, language_storage::StructTag, value::MoveValue};
+
+    fn decode_value(sources: &DebugSources, ty: TypeTag, value: MoveValue) -> Variable {
+        let bytes = value.simple_serialize().unwrap();
+        decode(sources, "v".to_string(), Some(&ty), Some(&bytes))
+    }
+
+    /// Returns the name, value and type of a variable and its children.
+    fn summary(var: &Variable) -> Vec<(String, String, String)> {
+        std::iter::once((var.name.clone(), var.value.clone(), var.ty.clone()))
+            .chain(var.children.iter().flat_map(summary))
+            .collect()
+    }
+
+    fn entry(name: &str, value: &str, ty: &str) -> (String, String, String) {
+        (name.to_string(), value.to_string(), ty.to_string())
+    }
+
+    #[test]
+    fn test_decode_primitives() {
+        let sources = DebugSources::new();
+        let addr = AccountAddress::from_hex_literal("0x1").unwrap();
+        let var = decode_value(&sources, TypeTag::U64, MoveValue::U64(42));
+        assert_eq!(summary(&var), vec![entry("v", "42", "u64")]);
+        assert!(var.children.is_empty());
+        let var = decode_value(&sources, TypeTag::U8, MoveValue::U8(7));
+        assert_eq!(summary(&var), vec![entry("v", "7", "u8")]);
+        let var = decode_value(&sources, TypeTag::Bool, MoveValue::Bool(true));
+        assert_eq!(summary(&var), vec![entry("v", "true", "bool")]);
+        let var = decode_value(&sources, TypeTag::Address, MoveValue::Address(addr));
+        assert_eq!(summary(&var), vec![entry("v", "@0x1", "address")]);
+        // Signers are serialized like their address
+        let var = decode_value(&sources, TypeTag::Signer, MoveValue::Address(addr));
+        assert_eq!(summary(&var), vec![entry("v", "signer(@0x1)", "signer")]);
+    }
+
+    #[test]
+    fn test_decode_vectors() {
+        let sources = DebugSources::new();
+        let bytes = MoveValue::Vector(vec![MoveValue::U8(1), MoveValue::U8(0xAB)]);
+        let var = decode_value(&sources, TypeTag::Vector(Box::new(TypeTag::U8)), bytes);
+        assert_eq!(summary(&var), vec![entry("v", "0x01ab", "vector<u8>")]);
+
+        let numbers = MoveValue::Vector(vec![MoveValue::U64(1), MoveValue::U64(2)]);
+        let var = decode_value(&sources, TypeTag::Vector(Box::new(TypeTag::U64)), numbers);
+        assert_eq!(summary(&var), vec![
+            entry("v", "vector (length 2)", "vector<u64>"),
+            entry("[0]", "1", "u64"),
+            entry("[1]", "2", "u64"),
+        ]);
+    }
+
+    #[test]
+    fn test_decode_structs() {
+        let example = Example::compile(false);
+        let point = TypeTag::Struct(Box::new(StructTag {
+            address: *example.module_id().address(),
+            module: Identifier::new("example").unwrap(),
+            name: Identifier::new("Point").unwrap(),
+            type_args: vec![],
+        }));
+        let points = TypeTag::Vector(Box::new(point.clone()));
+        let bytes = [1u64, 2, 3, 4]
+            .iter()
+            .flat_map(|n| n.to_le_bytes())
+            .collect::<Vec<_>>();
+        let mut points_bytes = vec![2];
+        points_bytes.extend(&bytes);
+
+        let var = decode(
+            &example.sources,
+            "points".to_string(),
+            Some(&points),
+            Some(&points_bytes),
+        );
+        assert_eq!(summary(&var), vec![
+            entry(
+                "points",
+                "vector (length 2)",
+                "vector<0x42::example::Point>"
+            ),
+            entry("[0]", "Point", "0x42::example::Point"),
+            entry("x", "1", "u64"),
+            entry("y", "2", "u64"),
+            entry("[1]", "Point", "0x42::example::Point"),
+            entry("x", "3", "u64"),
+            entry("y", "4", "u64"),
+        ]);
+    }
+
+    #[test]
+    fn test_decode_unavailable_values() {
+        let sources = DebugSources::new();
+        let var = decode(&sources, "v".to_string(), None, Some(&[1]));
+        assert_eq!(summary(&var), vec![entry("v", "<unavailable>", "?")]);
+        let var = decode(&sources, "v".to_string(), Some(&TypeTag::U64), None);
+        assert_eq!(summary(&var), vec![entry("v", "<unavailable>", "u64")]);
+        // Values which do not match their type are shown as hex
+        let var = decode(
+            &sources,
+            "v".to_string(),
+            Some(&TypeTag::U64),
+            Some(&[1, 2, 3]),
+        );
+        assert_eq!(summary(&var), vec![entry("v", "0x010203", "u64")]);
+        // So are values of types which cannot be resolved
+        let unknown = TypeTag::Struct(Box::new(StructTag {
+            address: AccountAddress::from_hex_literal("0x43").unwrap(),
+            module: Identifier::new("unknown").unwrap(),
+            name: Identifier::new("S").unwrap(),
+            type_args: vec![],
+        }));
+        let var = decode(&sources, "v".to_string(), Some(&unknown), Some(&[1]));
+        assert_eq!(summary(&var), vec![entry("v", "0x01", "0x43::unknown::S")]);
+    }
+}
@@ -4,10 +4,11 @@
 
 pub mod cargo_runner;
 pub mod extensions;
+mod random_args;
 pub mod test_reporter;
 pub mod test_runner;
 
-use crate::test_runner::TestRunner;
+use crate::{test_reporter::TestReportFormat, test_runner::TestRunner};
 use clap::*;
 use move_command_line_common::files::verify_and_create_named_address_mapping;
 use move_compiler::{
@@ -24,12 +25,16 @@ use std::{
     collections::BTreeMap,
     io::{Result, Write},
     marker::Send,
+    path::PathBuf,
     sync::Mutex,
 };
 
 /// The default value bounding the amount of gas consumed in a test.
 const DEFAULT_EXECUTION_BOUND: u64 = 1_000_000;
 
+/// The default number of runs of a test with random arguments.
+pub const DEFAULT_RANDOM_TEST_RUNS: u64 = 100;
+
 #[derive(Debug, Parser, Clone)]
 #[clap(author, version, about)]
 pub struct UnitTestingConfig {
@@ -107,6 +112,41 @@ pub struct UnitTestingConfig {
     #[clap(short = 'v', long = "verbose")]
     pub verbose: bool,
 
+    /// Write a machine-readable report of the test results in the given format
+    #[clap(
+        name = "report_format",
+        long = "report_format",
+        value_enum,
+        requires = "report_path"
+    )]
+    pub report_format: Option<TestReportFormat>,
+
+    /// The file to write the report given by `--report_format` to
+    #[clap(name = "report_path", long = "report_path", requires = "report_format")]
+    pub report_path: Option<PathBuf>,
+
+    /// Number of times each test annotated with `#[fuzz]` or `#[test(random_args)]` is run with
+    /// freshly generated arguments
+    #[clap(
+        name = "random_test_runs",
+        long = "random_test_runs",
+        default_value_t = DEFAULT_RANDOM_TEST_RUNS
+    )]
+    pub random_test_runs: u64,
+
+    /// Seed for generating the arguments of tests annotated with `#[fuzz]` or
+    /// `#[test(random_args)]`. A random seed is used if not given; it is printed when such a test
+    /// fails
+    #[clap(name = "seed", long = "seed")]
+    pub seed: Option<u64>,
+
+    /// Run the tests under a debugger: wait for a Debug Adapter Protocol client to connect on
+    /// the given port, then run the tests on a single thread, stopping at the breakpoints set
+    /// by the client
+    #[cfg(feature = "debugging")]
+    #[clap(name = "debug_port", long = "debug")]
+    pub debug_port: Option<u16>,
+
     /// Use the EVM-based execution backend.
     /// Does not work with --stackless.
     #[cfg(feature = "evm-backend")]
@@ -139,6 +179,13 @@ impl UnitTestingConfig {
             verbose: false,
             list: false,
             named_address_values: vec![],
+            report_format: None,
+            report_path: None,
+            random_test_runs: DEFAULT_RANDOM_TEST_RUNS,
+            seed: None,
+
+            #[cfg(feature = "debugging")]
+            debug_port: None,
 
             #[cfg(feature = "evm-backend")]
             evm: false,
@@ -235,10 +282,25 @@ impl UnitTestingConfig {
             return Ok((shared_writer.into_inner().unwrap(), true));
         }
 
+        // The debug hook is global, so tests run one at a time under the debugger.
+        #[cfg(feature = "debugging")]
+        let (num_threads, debug_session) = match self.debug_port {
+            Some(port) => (
+                1,
+                Some(move_debug_adapter::DebugSession::start(
+                    port,
+                    move_debug_adapter::DebugSources::from_test_plan(&test_plan),
+                )?),
+            ),
+            None => (self.num_threads, None),
+        };
+        #[cfg(not(feature = "debugging"))]
+        let num_threads = self.num_threads;
+
         writeln!(shared_writer.lock().unwrap(), "Running Move unit tests")?;
         let mut test_runner = TestRunner::new(
             self.gas_limit.unwrap_or(DEFAULT_EXECUTION_BOUND),
-            self.num_threads,
+            num_threads,
             self.report_storage_on_error,
             self.report_stacktrace_on_abort,
             test_plan,
@@ -246,6 +308,8 @@ impl UnitTestingConfig {
             genesis_state,
             cost_table,
             self.verbose,
+            self.random_test_runs,
+            self.seed.unwrap_or_else(rand::random),
             #[cfg(feature = "evm-backend")]
             self.evm,
         )
@@ -256,6 +320,10 @@ impl UnitTestingConfig {
         }
 
         let test_results = test_runner.run(&shared_writer).unwrap();
+        #[cfg(feature = "debugging")]
+        if let Some(debug_session) = debug_session {
+            debug_session.finish();
+        }
         if self.report_statistics {
             test_results.report_statistics(&shared_writer)?;
         }
@@ -264,6 +332,10 @@ impl UnitTestingConfig {
             test_results.report_goldens(&shared_writer)?;
         }
 
+        if let (Some(format), Some(path)) = (self.report_format, &self.report_path) {
+            test_results.write_report(format, path)?;
+        }
+
         let ok = test_results.summarize(&shared_writer)?;
 
         let writer = shared_writer.into_inner().unwrap();
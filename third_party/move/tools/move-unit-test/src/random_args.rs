@@ -0,0 +1,175 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Generation and shrinking of arguments for tests annotated with `#[fuzz]` or
+//! `#[test(random_args)]`.
+
+use move_compiler::unit_test::{RandomArgumentType, TestArgument};
+use move_core_types::{account_address::AccountAddress, u256::U256, value::MoveValue};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+/// The maximal length of a generated vector.
+const MAX_VECTOR_LEN: usize = 32;
+
+/// Creates the random number generator for a test. The qualified name of the test is mixed into
+/// the seed, so that different tests get different arguments, and the arguments of a test do not
+/// depend on which other tests are run.
+pub fn test_rng(seed: u64, qualified_test_name: &str) -> StdRng {
+    // FNV-1a, which unlike the std hasher is stable across Rust releases.
+    let name_hash = qualified_test_name
+        .bytes()
+        .fold(0xCBF2_9CE4_8422_2325_u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0100_0000_01B3)
+        });
+    StdRng::seed_from_u64(seed ^ name_hash)
+}
+
+/// Returns the arguments for one run of a test, generating values for the random parameters.
+pub fn generate_arguments(rng: &mut StdRng, params: &[(String, TestArgument)]) -> Vec<MoveValue> {
+    params
+        .iter()
+        .map(|(_, arg)| match arg {
+            TestArgument::Assigned(value) => value.clone(),
+            TestArgument::Random(ty) => generate_value(rng, ty),
+        })
+        .collect()
+}
+
+fn generate_value(rng: &mut StdRng, ty: &RandomArgumentType) -> MoveValue {
+    use RandomArgumentType as R;
+    match ty {
+        R::Bool => MoveValue::Bool(rng.gen()),
+        R::U8 => MoveValue::U8(generate_int(rng, u8::MAX as u128) as u8),
+        R::U16 => MoveValue::U16(generate_int(rng, u16::MAX as u128) as u16),
+        R::U32 => MoveValue::U32(generate_int(rng, u32::MAX as u128) as u32),
+        R::U64 => MoveValue::U64(generate_int(rng, u64::MAX as u128) as u64),
+        R::U128 => MoveValue::U128(generate_int(rng, u128::MAX)),
+        R::U256 => MoveValue::U256(generate_u256(rng)),
+        R::Address => MoveValue::Address(generate_address(rng)),
+        R::Signer => MoveValue::Signer(generate_address(rng)),
+        R::Vector(elem_ty) => {
+            let len = if rng.gen_bool(0.1) {
+                0
+            } else {
+                rng.gen_range(1, MAX_VECTOR_LEN + 1)
+            };
+            MoveValue::Vector((0..len).map(|_| generate_value(rng, elem_ty)).collect())
+        },
+    }
+}
+
+/// Generates an integer in `0..=max`, where `max` is of the form `2^n - 1`. Boundary values,
+/// around which arithmetic bugs tend to hide, are picked far more often than under a uniform
+/// distribution.
+fn generate_int(rng: &mut StdRng, max: u128) -> u128 {
+    match rng.gen_range(0, 8) {
+        0 => 0,
+        1 => 1,
+        2 => max,
+        3 => max - 1,
+        4 => (rng.gen::<u8>() as u128) & max,
+        _ => rng.gen::<u128>() & max,
+    }
+}
+
+fn generate_u256(rng: &mut StdRng) -> U256 {
+    match rng.gen_range(0, 8) {
+        0 => U256::zero(),
+        1 => U256::one(),
+        2 => U256::max_value(),
+        3 => U256::max_value() - U256::one(),
+        4 => U256::from(rng.gen::<u8>()),
+        5 => U256::from(rng.gen::<u128>()),
+        _ => U256::from_le_bytes(&rng.gen()),
+    }
+}
+
+/// Generates an address, which is a small one like `@0x1` half of the time.
+fn generate_address(rng: &mut StdRng) -> AccountAddress {
+    if rng.gen_bool(0.5) {
+        let mut bytes = [0u8; AccountAddress::LENGTH];
+        bytes[AccountAddress::LENGTH - 1] = rng.gen_range(0, 16);
+        AccountAddress::new(bytes)
+    } else {
+        AccountAddress::new(rng.gen())
+    }
+}
+
+/// Returns values which are simpler than `value`, simplest first. Returns no values if `value`
+/// cannot be simplified any further.
+pub fn shrink_value(value: &MoveValue) -> Vec<MoveValue> {
+    match value {
+        MoveValue::Bool(true) => vec![MoveValue::Bool(false)],
+        MoveValue::U8(n) => shrink_int(*n as u128)
+            .map(|n| MoveValue::U8(n as u8))
+            .collect(),
+        MoveValue::U16(n) => shrink_int(*n as u128)
+            .map(|n| MoveValue::U16(n as u16))
+            .collect(),
+        MoveValue::U32(n) => shrink_int(*n as u128)
+            .map(|n| MoveValue::U32(n as u32))
+            .collect(),
+        MoveValue::U64(n) => shrink_int(*n as u128)
+            .map(|n| MoveValue::U64(n as u64))
+            .collect(),
+        MoveValue::U128(n) => shrink_int(*n).map(MoveValue::U128).collect(),
+        MoveValue::U256(n) if *n != U256::zero() => {
+            let two = U256::from(2u8);
+            let mut candidates = vec![MoveValue::U256(U256::zero())];
+            let mut delta = *n / two;
+            while delta != U256::zero() {
+                candidates.push(MoveValue::U256(*n - delta));
+                delta = delta / two;
+            }
+            candidates
+        },
+        MoveValue::Address(addr) if *addr != AccountAddress::ZERO => {
+            vec![MoveValue::Address(AccountAddress::ZERO)]
+        },
+        MoveValue::Signer(addr) if *addr != AccountAddress::ZERO => {
+            vec![MoveValue::Signer(AccountAddress::ZERO)]
+        },
+        MoveValue::Vector(elems) => shrink_vector(elems),
+        _ => vec![],
+    }
+}
+
+/// Returns `0`, followed by values approaching `n` from below in halving steps, so that
+/// shrinking converges like a binary search on the smallest failing value.
+fn shrink_int(n: u128) -> impl Iterator<Item = u128> {
+    let mut candidates = vec![];
+    if n > 0 {
+        candidates.push(0);
+        let mut delta = n / 2;
+        while delta > 0 {
+            candidates.push(n - delta);
+            delta /= 2;
+        }
+    }
+    candidates.into_iter()
+}
+
+fn shrink_vector(elems: &[MoveValue]) -> Vec<MoveValue> {
+    let len = elems.len();
+    let mut candidates = vec![];
+    if len > 0 {
+        candidates.push(vec![]);
+    }
+    if len > 1 {
+        candidates.push(elems[..len / 2].to_vec());
+        candidates.push(elems[len / 2..].to_vec());
+    }
+    for idx in 0..len {
+        let mut shorter = elems.to_vec();
+        shorter.remove(idx);
+        candidates.push(shorter);
+    }
+    for (idx, elem) in elems.iter().enumerate() {
+        for simpler in shrink_value(elem) {
+            let mut candidate = elems.to_vec();
+            candidate[idx] = simpler;
+            candidates.push(candidate);
+        }
+    }
+    candidates.into_iter().map(MoveValue::Vector).collect()
+}
@@ -8,20 +8,29 @@ use colored::{control, Colorize};
 use move_binary_format::{
     access::ModuleAccess,
     errors::{ExecutionState, Location, VMError, VMResult},
+    file_format::ConstantPoolIndex,
 };
-use move_command_line_common::{env::read_bool_env_var, files::FileHash};
+use move_command_line_common::{env::read_bool_env_var, files::FileHash, xml};
 pub use move_compiler::unit_test::ExpectedMoveError as MoveError;
 use move_compiler::{
     diagnostics::{self, Diagnostic, Diagnostics},
     unit_test::{ModuleTestPlan, TestName, TestPlan},
 };
-use move_core_types::{effects::ChangeSet, language_storage::ModuleId, vm_status::StatusType};
+use move_core_types::{
+    effects::ChangeSet,
+    language_storage::ModuleId,
+    value::MoveValue,
+    vm_status::{StatusCode, StatusType},
+};
 use move_ir_types::location::Loc;
 use move_symbol_pool::Symbol;
 use once_cell::sync::Lazy;
+use serde::Serialize;
 use std::{
     collections::{BTreeMap, BTreeSet, HashMap},
-    io::{Result, Write},
+    fs::File,
+    io::{BufWriter, Result, Write},
+    path::Path,
     sync::Mutex,
     time::Duration,
 };
@@ -59,6 +68,8 @@ pub struct TestFailure {
     pub vm_error: Option<VMError>,
     pub failure_reason: FailureReason,
     pub storage_state: Option<String>,
+    // For tests with random arguments, the failing arguments and how to reproduce the failure
+    pub random_args: Option<String>,
 }
 
 #[derive(Debug, Clone, Ord, PartialOrd, PartialEq, Eq)]
@@ -81,6 +92,59 @@ pub struct TestResults {
     test_plan: TestPlan,
 }
 
+/// The format of a machine-readable test report, see `TestResults::write_report`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum TestReportFormat {
+    /// JUnit XML, as understood by most CI systems
+    Junit,
+    /// JSON, in the layout of `TestReport`
+    Json,
+}
+
+/// A machine-readable report of a test run.
+#[derive(Debug, Clone, Serialize)]
+pub struct TestReport {
+    pub passed: u64,
+    pub failed: u64,
+    pub tests: Vec<TestCaseReport>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TestStatus {
+    Passed,
+    Failed,
+    Timeout,
+}
+
+/// The result of a single test.
+#[derive(Debug, Clone, Serialize)]
+pub struct TestCaseReport {
+    /// The module of the test, as in `0x1::vector_tests`
+    pub module: String,
+    /// The name of the test function
+    pub name: String,
+    pub status: TestStatus,
+    pub duration_secs: f64,
+    pub gas_used: u64,
+    pub failure: Option<TestFailureReport>,
+}
+
+/// The details of a failed test.
+#[derive(Debug, Clone, Serialize)]
+pub struct TestFailureReport {
+    /// A one-line description of the failure
+    pub message: String,
+    /// The failure as it is printed by the test runner, without colors
+    pub details: String,
+    /// The source location at which the test failed, as in `sources/foo.move:12`
+    pub location: Option<String>,
+    /// The abort code, if the test aborted
+    pub abort_code: Option<u64>,
+    /// The name of the error constant matching the abort code, if one could be found
+    pub error_name: Option<String>,
+}
+
 impl TestRunInfo {
     pub fn new(function_ident: String, elapsed_time: Duration, instructions_executed: u64) -> Self {
         Self {
@@ -156,10 +220,20 @@ impl TestFailure {
             vm_error,
             failure_reason,
             storage_state,
+            random_args: None,
         }
     }
 
+    pub fn with_random_args(mut self, random_args: String) -> Self {
+        self.random_args = Some(random_args);
+        self
+    }
+
     pub fn render_error(&self, test_plan: &TestPlan) -> String {
+        self.render_error_with_color(test_plan, control::SHOULD_COLORIZE.should_colorize())
+    }
+
+    fn render_error_with_color(&self, test_plan: &TestPlan, colorize: bool) -> String {
         let error_string = match &self.failure_reason {
             FailureReason::NoError(message) => message.to_string(),
             FailureReason::Timeout(message) => message.to_string(),
@@ -169,7 +243,7 @@ impl TestFailure {
                     expected.verbiage(/* is_past_tense */ false),
                     actual.verbiage(/* is_past_tense */ true),
                 );
-                Self::report_error_with_location(test_plan, base_message, &self.vm_error)
+                Self::report_error_with_location(test_plan, base_message, &self.vm_error, colorize)
             },
             FailureReason::WrongAbortDEPRECATED(message, expected_code, actual) => {
                 let base_message = format!(
@@ -179,7 +253,7 @@ impl TestFailure {
                     expected_code,
                     actual.verbiage(/* is_past_tense */ true),
                 );
-                Self::report_error_with_location(test_plan, base_message, &self.vm_error)
+                Self::report_error_with_location(test_plan, base_message, &self.vm_error, colorize)
             },
             FailureReason::UnexpectedError(message, error) => {
                 let prefix = match error.0.status_type() {
@@ -203,7 +277,7 @@ impl TestFailure {
                     message,
                     error.verbiage(/* is_past_tense */ true)
                 );
-                Self::report_error_with_location(test_plan, base_message, &self.vm_error)
+                Self::report_error_with_location(test_plan, base_message, &self.vm_error, colorize)
             },
             FailureReason::Mismatch {
                 move_vm_return_values,
@@ -235,6 +309,11 @@ impl TestFailure {
             },
         };
 
+        let error_string = match &self.random_args {
+            None => error_string,
+            Some(random_args) => format!("{}\n{}", error_string, random_args),
+        };
+
         match &self.storage_state {
             None => error_string,
             Some(storage_state) => {
@@ -251,6 +330,125 @@ impl TestFailure {
         }
     }
 
+    /// Returns a one-line description of the failure.
+    fn summary(&self) -> String {
+        match &self.failure_reason {
+            FailureReason::NoError(message)
+            | FailureReason::Timeout(message)
+            | FailureReason::Property(message) => message.clone(),
+            FailureReason::WrongError(message, expected, actual) => format!(
+                "{message}. Expected test {} but instead it {}",
+                expected.verbiage(/* is_past_tense */ false),
+                actual.verbiage(/* is_past_tense */ true),
+            ),
+            FailureReason::WrongAbortDEPRECATED(message, expected_code, actual) => format!(
+                "{message}. Expected test to abort with code {expected_code}, but instead it {}",
+                actual.verbiage(/* is_past_tense */ true),
+            ),
+            FailureReason::UnexpectedError(message, error) => format!(
+                "{message}, but it {}",
+                error.verbiage(/* is_past_tense */ true)
+            ),
+            FailureReason::Mismatch { .. } => {
+                "Executions via Move VM and stackless VM yield different results".to_string()
+            },
+            #[cfg(feature = "evm-backend")]
+            FailureReason::MoveToEVMError(_) => {
+                "Failed to compile Move code into EVM bytecode".to_string()
+            },
+        }
+    }
+
+    /// Returns the error the test actually failed with, if it failed with one.
+    fn actual_error(&self) -> Option<&MoveError> {
+        match &self.failure_reason {
+            FailureReason::WrongError(_, _, actual)
+            | FailureReason::WrongAbortDEPRECATED(_, _, actual)
+            | FailureReason::UnexpectedError(_, actual) => Some(actual),
+            _ => None,
+        }
+    }
+
+    /// Returns the source location at which the test failed, as `file:line`.
+    fn failure_location(&self, test_plan: &TestPlan) -> Option<String> {
+        let vm_error = self.vm_error.as_ref()?;
+        let Location::Module(module_id) = vm_error.location() else {
+            return None;
+        };
+        let (fdef_idx, offset) = vm_error.offsets().first()?;
+        let loc = test_plan
+            .module_info
+            .get(module_id)?
+            .source_map
+            .get_function_source_map(*fdef_idx)
+            .ok()?
+            .get_code_location(*offset)?;
+        let (file_name, source) = test_plan.files.get(&loc.file_hash())?;
+        let mut files = SimpleFiles::new();
+        let id = files.add(*file_name, source.as_str());
+        let file_mapping = HashMap::from([(loc.file_hash(), id)]);
+        Some(format!(
+            "{}:{}",
+            file_name,
+            Self::get_line_number(&loc, &files, &file_mapping)
+        ))
+    }
+
+    /// Looks up the name of the error constant for an abort code raised in `module_id`. Both
+    /// plain codes and canonical codes, which carry the reason in their lower 16 bits, are
+    /// matched against the `E`-prefixed `u64` constants of the module. This depends on constant
+    /// names being recorded in the source map, so the result is best-effort.
+    fn decode_abort_code(test_plan: &TestPlan, module_id: &ModuleId, code: u64) -> Option<String> {
+        let module_info = test_plan.module_info.get(module_id)?;
+        let error_constants = module_info
+            .source_map
+            .constant_map
+            .iter()
+            .filter(|(name, _)| name.0.as_str().starts_with('E'))
+            .filter_map(|(name, idx)| {
+                match module_info
+                    .module
+                    .constant_at(ConstantPoolIndex(*idx))
+                    .deserialize_constant()?
+                {
+                    MoveValue::U64(value) => Some((name.0, value)),
+                    _ => None,
+                }
+            })
+            .collect::<Vec<_>>();
+        error_constants
+            .iter()
+            .find(|(_, value)| *value == code)
+            .or_else(|| {
+                error_constants
+                    .iter()
+                    .find(|(_, value)| *value == code & 0xFFFF)
+            })
+            .map(|(name, _)| name.to_string())
+    }
+
+    fn report(&self, test_plan: &TestPlan) -> TestFailureReport {
+        let (abort_code, error_name) = match self.actual_error() {
+            Some(MoveError(StatusCode::ABORTED, Some(code), location, _)) => {
+                let error_name = match location {
+                    Location::Module(module_id) => {
+                        Self::decode_abort_code(test_plan, module_id, *code)
+                    },
+                    Location::Script | Location::Undefined => None,
+                };
+                (Some(*code), error_name)
+            },
+            _ => (None, None),
+        };
+        TestFailureReport {
+            message: self.summary(),
+            details: self.render_error_with_color(test_plan, /* colorize */ false),
+            location: self.failure_location(test_plan),
+            abort_code,
+            error_name,
+        }
+    }
+
     fn get_line_number(
         loc: &Loc,
         files: &SimpleFiles<Symbol, &str>,
@@ -333,8 +531,9 @@ impl TestFailure {
         test_plan: &TestPlan,
         base_message: String,
         vm_error: &Option<VMError>,
+        colorize: bool,
     ) -> String {
-        let report_diagnostics = if control::SHOULD_COLORIZE.should_colorize() {
+        let report_diagnostics = if colorize {
             diagnostics::report_diagnostics_to_color_buffer
         } else {
             diagnostics::report_diagnostics_to_buffer
@@ -557,6 +756,64 @@ impl TestResults {
         writeln!(writer.lock().unwrap())
     }
 
+    /// Returns a machine-readable report of all tests, ordered by module and test name.
+    pub fn report(&self) -> TestReport {
+        let mut tests: BTreeMap<(&ModuleId, &str), TestCaseReport> = BTreeMap::new();
+        let case = |module_id: &ModuleId, info: &TestRunInfo| TestCaseReport {
+            module: format_module_id(module_id),
+            name: info.function_ident.clone(),
+            status: TestStatus::Passed,
+            duration_secs: info.elapsed_time.as_secs_f64(),
+            gas_used: info.instructions_executed,
+            failure: None,
+        };
+        for (module_id, test_results) in &self.final_statistics.passed {
+            for test_result in test_results {
+                tests.insert(
+                    (module_id, test_result.function_ident.as_str()),
+                    case(module_id, test_result),
+                );
+            }
+        }
+        for (module_id, test_failures) in &self.final_statistics.failed {
+            for test_failure in test_failures {
+                let info = &test_failure.test_run_info;
+                tests.insert((module_id, info.function_ident.as_str()), TestCaseReport {
+                    status: match test_failure.failure_reason {
+                        FailureReason::Timeout(_) => TestStatus::Timeout,
+                        _ => TestStatus::Failed,
+                    },
+                    failure: Some(test_failure.report(&self.test_plan)),
+                    ..case(module_id, info)
+                });
+            }
+        }
+        let tests = tests.into_values().collect::<Vec<_>>();
+        let passed = tests
+            .iter()
+            .filter(|test| test.status == TestStatus::Passed)
+            .count() as u64;
+        TestReport {
+            passed,
+            failed: tests.len() as u64 - passed,
+            tests,
+        }
+    }
+
+    /// Writes a report of all tests in the given format to `path`.
+    pub fn write_report(&self, format: TestReportFormat, path: &Path) -> Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        let report = self.report();
+        match format {
+            TestReportFormat::Json => {
+                serde_json::to_writer_pretty(&mut writer, &report)?;
+                writeln!(writer)?;
+            },
+            TestReportFormat::Junit => report.write_junit(&mut writer)?,
+        }
+        writer.flush()
+    }
+
     /// Returns `true` if all tests passed, `false` if there was a test failure/timeout
     pub fn summarize<W: Write>(self, writer: &Mutex<W>) -> Result<bool> {
         let num_failed_tests = self
@@ -610,3 +867,87 @@ impl TestResults {
         Ok(num_failed_tests == 0)
     }
 }
+
+impl TestReport {
+    /// Writes the report as JUnit XML, with one `testsuite` per module.
+    pub fn write_junit<W: Write>(&self, w: &mut W) -> Result<()> {
+        let total_time = |tests: &[&TestCaseReport]| -> f64 {
+            tests.iter().map(|test| test.duration_secs).sum()
+        };
+        let mut suites: BTreeMap<&str, Vec<&TestCaseReport>> = BTreeMap::new();
+        for test in &self.tests {
+            suites.entry(test.module.as_str()).or_default().push(test);
+        }
+        writeln!(w, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(
+            w,
+            r#"<testsuites name="move-unit-tests" tests="{}" failures="{}" time="{:.3}">"#,
+            self.tests.len(),
+            self.failed,
+            total_time(&self.tests.iter().collect::<Vec<_>>()),
+        )?;
+        for (module, tests) in &suites {
+            writeln!(
+                w,
+                r#"  <testsuite name="{}" tests="{}" failures="{}" time="{:.3}">"#,
+                xml::escape(module),
+                tests.len(),
+                tests
+                    .iter()
+                    .filter(|test| test.status != TestStatus::Passed)
+                    .count(),
+                total_time(tests),
+            )?;
+            for test in tests {
+                writeln!(
+                    w,
+                    r#"    <testcase name="{}" classname="{}" time="{:.3}">"#,
+                    xml::escape(&test.name),
+                    xml::escape(module),
+                    test.duration_secs,
+                )?;
+                writeln!(w, "      <properties>")?;
+                writeln!(
+                    w,
+                    r#"        <property name="gas_used" value="{}"/>"#,
+                    test.gas_used
+                )?;
+                if let Some(failure) = &test.failure {
+                    for (name, value) in [
+                        ("location", failure.location.clone()),
+                        (
+                            "abort_code",
+                            failure.abort_code.map(|code| code.to_string()),
+                        ),
+                        ("error_name", failure.error_name.clone()),
+                    ] {
+                        if let Some(value) = value {
+                            writeln!(
+                                w,
+                                r#"        <property name="{}" value="{}"/>"#,
+                                name,
+                                xml::escape(&value)
+                            )?;
+                        }
+                    }
+                }
+                writeln!(w, "      </properties>")?;
+                if let Some(failure) = &test.failure {
+                    writeln!(
+                        w,
+                        r#"      <failure type="{}" message="{}">{}</failure>"#,
+                        match test.status {
+                            TestStatus::Timeout => "timeout",
+                            _ => "failure",
+                        },
+                        xml::escape(&failure.message),
+                        xml::escape(&failure.details),
+                    )?;
+                }
+                writeln!(w, "    </testcase>")?;
+            }
+            writeln!(w, "  </testsuite>")?;
+        }
+        writeln!(w, "</testsuites>")
+    }
+}
@@ -3,7 +3,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    extensions, format_module_id,
+    extensions, format_module_id, random_args,
     test_reporter::{
         FailureReason, MoveError, TestFailure, TestResults, TestRunInfo, TestStatistics,
     },
@@ -12,12 +12,12 @@ use anyhow::Result;
 use colored::*;
 use move_binary_format::{errors::VMResult, file_format::CompiledModule};
 use move_bytecode_utils::Modules;
-use move_compiler::unit_test::{ExpectedFailure, ModuleTestPlan, TestCase, TestPlan};
+use move_compiler::unit_test::{ExpectedFailure, ModuleTestPlan, TestArgument, TestCase, TestPlan};
 use move_core_types::{
     account_address::AccountAddress,
     effects::{ChangeSet, Op},
     identifier::IdentStr,
-    value::serialize_values,
+    value::{serialize_values, MoveValue},
     vm_status::StatusCode,
 };
 use move_resource_viewer::MoveValueAnnotator;
@@ -32,17 +32,26 @@ use move_vm_test_utils::{
     InMemoryStorage,
 };
 use rayon::prelude::*;
-use std::{io::Write, marker::Send, sync::Mutex, time::Instant};
+use std::{
+    io::Write,
+    marker::Send,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
 #[cfg(feature = "evm-backend")]
 use {
     evm::{backend::MemoryVicinity, ExitReason},
     evm_exec_utils::exec::{ExecuteResult, Executor},
+    move_model::model::GlobalEnv,
     move_to_yul,
     primitive_types::{H160, U256},
     std::convert::TryInto,
-    std::time::Duration,
 };
 
+/// The maximal number of test executions spent on shrinking the arguments of a failing test with
+/// random arguments.
+const MAX_SHRINK_RUNS: usize = 1000;
+
 /// Test state common to all tests
 pub struct SharedTestingConfig {
     save_storage_state_on_failure: bool,
@@ -54,6 +63,8 @@ pub struct SharedTestingConfig {
     #[allow(dead_code)] // used by some features
     source_files: Vec<String>,
     record_writeset: bool,
+    random_test_runs: u64,
+    seed: u64,
 
     #[cfg(feature = "evm-backend")]
     evm: bool,
@@ -135,6 +146,8 @@ impl TestRunner {
         genesis_state: Option<ChangeSet>,
         cost_table: Option<CostTable>,
         record_writeset: bool,
+        random_test_runs: u64,
+        seed: u64,
         #[cfg(feature = "evm-backend")] evm: bool,
     ) -> Result<Self> {
         let source_files = tests
@@ -168,6 +181,8 @@ impl TestRunner {
                 cost_table: cost_table.unwrap_or_else(unit_cost_table),
                 source_files,
                 record_writeset,
+                random_test_runs,
+                seed,
                 #[cfg(feature = "evm-backend")]
                 evm,
             },
@@ -259,7 +274,7 @@ impl SharedTestingConfig {
         &self,
         test_plan: &ModuleTestPlan,
         function_name: &str,
-        test_info: &TestCase,
+        arguments: &[MoveValue],
     ) -> (
         VMResult<ChangeSet>,
         VMResult<NativeContextExtensions>,
@@ -279,7 +294,7 @@ impl SharedTestingConfig {
             &test_plan.module_id,
             IdentStr::new(function_name).unwrap(),
             vec![], // no ty args, at least for now
-            serialize_values(test_info.arguments.iter()),
+            serialize_values(arguments),
             &mut gas_meter,
             &mut TraversalContext::new(&storage),
         );
@@ -318,139 +333,244 @@ impl SharedTestingConfig {
         let mut stats = TestStatistics::new();
 
         for (function_name, test_info) in &test_plan.tests {
-            let (cs_result, ext_result, exec_result, test_run_info) =
-                self.execute_via_move_vm(test_plan, function_name, test_info);
-
-            if self.record_writeset {
-                stats.test_output(
-                    function_name.to_string(),
+            let result = match &test_info.random_args {
+                None => self.exec_test_case(
                     test_plan,
-                    format!("{:?}", cs_result),
-                );
+                    function_name,
+                    test_info,
+                    &test_info.arguments,
+                    &mut stats,
+                ),
+                Some(params) => self.exec_random_test_case(
+                    test_plan,
+                    function_name,
+                    params,
+                    &mut stats,
+                    |arguments, stats| {
+                        self.exec_test_case(test_plan, function_name, test_info, arguments, stats)
+                    },
+                ),
+            };
+            match result {
+                Ok(test_run_info) => {
+                    output.pass(function_name);
+                    stats.test_success(test_run_info, test_plan);
+                },
+                Err(test_failure) => {
+                    if let FailureReason::Timeout(_) = test_failure.failure_reason {
+                        // Ran out of ticks, report a test timeout and log a test failure
+                        output.timeout(function_name);
+                    } else {
+                        output.fail(function_name);
+                    }
+                    stats.test_failure(test_failure, test_plan);
+                },
             }
+        }
 
-            let save_session_state = || {
-                if self.save_storage_state_on_failure {
-                    cs_result.ok().and_then(|changeset| {
-                        ext_result.ok().and_then(|extensions| {
-                            print_resources_and_extensions(
-                                &changeset,
-                                extensions,
-                                &self.starting_storage_state,
-                            )
-                            .ok()
-                        })
+        stats
+    }
+
+    /// Runs a test with the given arguments, and checks the outcome against the expected failure
+    /// of the test.
+    fn exec_test_case(
+        &self,
+        test_plan: &ModuleTestPlan,
+        function_name: &str,
+        test_info: &TestCase,
+        arguments: &[MoveValue],
+        stats: &mut TestStatistics,
+    ) -> std::result::Result<TestRunInfo, TestFailure> {
+        let (cs_result, ext_result, exec_result, test_run_info) =
+            self.execute_via_move_vm(test_plan, function_name, arguments);
+
+        if self.record_writeset {
+            stats.test_output(
+                function_name.to_string(),
+                test_plan,
+                format!("{:?}", cs_result),
+            );
+        }
+
+        let save_session_state = || {
+            if self.save_storage_state_on_failure {
+                cs_result.ok().and_then(|changeset| {
+                    ext_result.ok().and_then(|extensions| {
+                        print_resources_and_extensions(
+                            &changeset,
+                            extensions,
+                            &self.starting_storage_state,
+                        )
+                        .ok()
                     })
+                })
+            } else {
+                None
+            }
+        };
+        match exec_result {
+            Err(err) => {
+                let actual_err = MoveError(
+                    err.major_status(),
+                    err.sub_status(),
+                    err.location().clone(),
+                    err.message().cloned(),
+                );
+                assert!(err.major_status() != StatusCode::EXECUTED);
+                match test_info.expected_failure.as_ref() {
+                    Some(ExpectedFailure::Expected) => Ok(test_run_info),
+                    Some(ExpectedFailure::ExpectedWithError(expected_err))
+                        if expected_err == &actual_err =>
+                    {
+                        Ok(test_run_info)
+                    },
+                    Some(ExpectedFailure::ExpectedWithCodeDEPRECATED(code))
+                        if actual_err.0 == StatusCode::ABORTED
+                            && actual_err.1.is_some()
+                            && actual_err.1.unwrap() == *code =>
+                    {
+                        Ok(test_run_info)
+                    },
+                    // incorrect cases
+                    Some(ExpectedFailure::ExpectedWithError(expected_err)) => {
+                        Err(TestFailure::new(
+                            FailureReason::wrong_error(expected_err.clone(), actual_err),
+                            test_run_info,
+                            Some(err),
+                            save_session_state(),
+                        ))
+                    },
+                    Some(ExpectedFailure::ExpectedWithCodeDEPRECATED(expected_code)) => {
+                        Err(TestFailure::new(
+                            FailureReason::wrong_abort_deprecated(*expected_code, actual_err),
+                            test_run_info,
+                            Some(err),
+                            save_session_state(),
+                        ))
+                    },
+                    None if err.major_status() == StatusCode::OUT_OF_GAS => Err(TestFailure::new(
+                        FailureReason::timeout(),
+                        test_run_info,
+                        Some(err),
+                        save_session_state(),
+                    )),
+                    None => Err(TestFailure::new(
+                        FailureReason::unexpected_error(actual_err),
+                        test_run_info,
+                        Some(err),
+                        save_session_state(),
+                    )),
+                }
+            },
+            Ok(_) => {
+                // Expected the test to fail, but it executed
+                if test_info.expected_failure.is_some() {
+                    Err(TestFailure::new(
+                        FailureReason::no_error(),
+                        test_run_info,
+                        None,
+                        save_session_state(),
+                    ))
                 } else {
-                    None
+                    // Expected the test to execute fully and it did
+                    Ok(test_run_info)
                 }
-            };
-            match exec_result {
-                Err(err) => {
-                    let actual_err = MoveError(
-                        err.major_status(),
-                        err.sub_status(),
-                        err.location().clone(),
-                        err.message().cloned(),
-                    );
-                    assert!(err.major_status() != StatusCode::EXECUTED);
-                    match test_info.expected_failure.as_ref() {
-                        Some(ExpectedFailure::Expected) => {
-                            output.pass(function_name);
-                            stats.test_success(test_run_info, test_plan);
-                        },
-                        Some(ExpectedFailure::ExpectedWithError(expected_err))
-                            if expected_err == &actual_err =>
-                        {
-                            output.pass(function_name);
-                            stats.test_success(test_run_info, test_plan);
-                        },
-                        Some(ExpectedFailure::ExpectedWithCodeDEPRECATED(code))
-                            if actual_err.0 == StatusCode::ABORTED
-                                && actual_err.1.is_some()
-                                && actual_err.1.unwrap() == *code =>
-                        {
-                            output.pass(function_name);
-                            stats.test_success(test_run_info, test_plan);
-                        },
-                        // incorrect cases
-                        Some(ExpectedFailure::ExpectedWithError(expected_err)) => {
-                            output.fail(function_name);
-                            stats.test_failure(
-                                TestFailure::new(
-                                    FailureReason::wrong_error(expected_err.clone(), actual_err),
-                                    test_run_info,
-                                    Some(err),
-                                    save_session_state(),
-                                ),
-                                test_plan,
-                            )
-                        },
-                        Some(ExpectedFailure::ExpectedWithCodeDEPRECATED(expected_code)) => {
-                            output.fail(function_name);
-                            stats.test_failure(
-                                TestFailure::new(
-                                    FailureReason::wrong_abort_deprecated(
-                                        *expected_code,
-                                        actual_err,
-                                    ),
-                                    test_run_info,
-                                    Some(err),
-                                    save_session_state(),
-                                ),
-                                test_plan,
-                            )
-                        },
-                        None if err.major_status() == StatusCode::OUT_OF_GAS => {
-                            // Ran out of ticks, report a test timeout and log a test failure
-                            output.timeout(function_name);
-                            stats.test_failure(
-                                TestFailure::new(
-                                    FailureReason::timeout(),
-                                    test_run_info,
-                                    Some(err),
-                                    save_session_state(),
-                                ),
-                                test_plan,
-                            )
-                        },
-                        None => {
-                            output.fail(function_name);
-                            stats.test_failure(
-                                TestFailure::new(
-                                    FailureReason::unexpected_error(actual_err),
-                                    test_run_info,
-                                    Some(err),
-                                    save_session_state(),
-                                ),
-                                test_plan,
-                            )
-                        },
-                    }
+            },
+        }
+    }
+
+    /// Runs a test with random arguments (`#[fuzz]` or `#[test(random_args)]`) with freshly
+    /// generated arguments for the configured number of runs, executing each run with `exec`. On
+    /// success, the returned time and gas are the totals over all runs. On failure, the arguments
+    /// are shrunk to a simpler failing set before reporting.
+    fn exec_random_test_case(
+        &self,
+        test_plan: &ModuleTestPlan,
+        function_name: &str,
+        params: &[(String, TestArgument)],
+        stats: &mut TestStatistics,
+        exec: impl Fn(
+            &[MoveValue],
+            &mut TestStatistics,
+        ) -> std::result::Result<TestRunInfo, TestFailure>,
+    ) -> std::result::Result<TestRunInfo, TestFailure> {
+        let mut rng = random_args::test_rng(
+            self.seed,
+            &format!(
+                "{}::{}",
+                format_module_id(&test_plan.module_id),
+                function_name
+            ),
+        );
+        let mut total_run_info = TestRunInfo::new(function_name.to_string(), Duration::ZERO, 0);
+        for run in 1..=self.random_test_runs {
+            let arguments = random_args::generate_arguments(&mut rng, params);
+            match exec(&arguments, stats) {
+                Ok(test_run_info) => {
+                    total_run_info.elapsed_time += test_run_info.elapsed_time;
+                    total_run_info.instructions_executed += test_run_info.instructions_executed;
                 },
-                Ok(_) => {
-                    // Expected the test to fail, but it executed
-                    if test_info.expected_failure.is_some() {
-                        output.fail(function_name);
-                        stats.test_failure(
-                            TestFailure::new(
-                                FailureReason::no_error(),
-                                test_run_info,
-                                None,
-                                save_session_state(),
-                            ),
-                            test_plan,
-                        )
-                    } else {
-                        // Expected the test to execute fully and it did
-                        output.pass(function_name);
-                        stats.test_success(test_run_info, test_plan);
-                    }
+                Err(test_failure) => {
+                    let (arguments, test_failure) =
+                        Self::shrink_random_arguments(params, arguments, test_failure, &exec);
+                    return Err(test_failure.with_random_args(format!(
+                        "Failed on run {} of {} with random arguments (seed {}):\n{}\n\
+                         Run the tests with `--seed {}` to reproduce",
+                        run,
+                        self.random_test_runs,
+                        self.seed,
+                        params
+                            .iter()
+                            .zip(&arguments)
+                            .map(|((name, _), value)| format!("    {} = {}", name, value))
+                            .collect::<Vec<_>>()
+                            .join("\n"),
+                        self.seed,
+                    )));
                 },
             }
         }
+        Ok(total_run_info)
+    }
 
-        stats
+    /// Greedily replaces random arguments of a failing test by simpler values, as long as the
+    /// test keeps failing for the same reason. Returns the simplest failing arguments found.
+    fn shrink_random_arguments(
+        params: &[(String, TestArgument)],
+        mut arguments: Vec<MoveValue>,
+        mut test_failure: TestFailure,
+        exec: impl Fn(
+            &[MoveValue],
+            &mut TestStatistics,
+        ) -> std::result::Result<TestRunInfo, TestFailure>,
+    ) -> (Vec<MoveValue>, TestFailure) {
+        // Outputs of shrinking runs are not recorded.
+        let mut scratch_stats = TestStatistics::new();
+        let mut shrink_runs = 0;
+        'shrink: loop {
+            for (idx, (_, param)) in params.iter().enumerate() {
+                if !matches!(param, TestArgument::Random(_)) {
+                    continue;
+                }
+                for candidate in random_args::shrink_value(&arguments[idx]) {
+                    if shrink_runs == MAX_SHRINK_RUNS {
+                        break 'shrink;
+                    }
+                    shrink_runs += 1;
+                    let mut candidate_arguments = arguments.clone();
+                    candidate_arguments[idx] = candidate;
+                    if let Err(candidate_failure) = exec(&candidate_arguments, &mut scratch_stats) {
+                        if candidate_failure.failure_reason == test_failure.failure_reason {
+                            arguments = candidate_arguments;
+                            test_failure = candidate_failure;
+                            continue 'shrink;
+                        }
+                    }
+                }
+            }
+            break;
+        }
+        (arguments, test_failure)
     }
 
     #[cfg(feature = "evm-backend")]
@@ -487,8 +607,6 @@ impl SharedTestingConfig {
         test_plan: &ModuleTestPlan,
         output: &TestOutput<impl Write>,
     ) -> TestStatistics {
-        use move_binary_format::errors::Location;
-
         let mut stats = TestStatistics::new();
 
         // TODO: Somehow, paths of some temporary Move interface files are being passed in after those files
@@ -519,153 +637,171 @@ impl SharedTestingConfig {
 
         let gen_options = move_to_yul::options::Options::default();
         for (function_name, test_info) in &test_plan.tests {
-            let yul_code = match move_to_yul::generator::Generator::run_for_unit_test(
-                &gen_options,
-                &model,
-                &test_plan.module_id,
-                IdentStr::new(function_name).unwrap(),
-                &test_info.arguments,
-            ) {
-                Ok(yul_code) => yul_code,
-                Err(diagnostics) => {
-                    // Failed to generate yul code due to some user errors.
-                    // Mark test as failed.
+            let exec = |arguments: &[MoveValue], _: &mut TestStatistics| {
+                self.exec_test_case_evm(
+                    &gen_options,
+                    &model,
+                    test_plan,
+                    function_name,
+                    test_info,
+                    arguments,
+                )
+            };
+            let result = match &test_info.random_args {
+                None => exec(&test_info.arguments, &mut stats),
+                Some(params) => {
+                    self.exec_random_test_case(test_plan, function_name, params, &mut stats, exec)
+                },
+            };
+            match result {
+                Ok(test_run_info) => {
+                    output.pass(function_name);
+                    stats.test_success(test_run_info, test_plan);
+                },
+                Err(test_failure) => {
                     output.fail(function_name);
-                    stats.test_failure(
-                        TestFailure::new(
-                            FailureReason::move_to_evm_error(diagnostics),
-                            TestRunInfo::new(function_name.to_string(), Duration::ZERO, 0),
-                            None,
-                            None,
-                        ),
-                        test_plan,
+                    let is_move_to_evm_error = matches!(
+                        test_failure.failure_reason,
+                        FailureReason::MoveToEVMError(_)
                     );
-                    return stats;
+                    stats.test_failure(test_failure, test_plan);
+                    if is_move_to_evm_error {
+                        return stats;
+                    }
                 },
-            };
-
-            let (res, duration) = self.execute_via_evm(&yul_code);
+            }
+        }
 
-            let abort_code = || -> u64 {
-                assert!(res.return_value.len() == 8);
+        stats
+    }
 
-                u64::from_be_bytes(res.return_value.as_slice().try_into().unwrap())
-            };
+    /// Runs a test on the EVM with the given arguments, and checks the outcome against the
+    /// expected failure of the test.
+    #[cfg(feature = "evm-backend")]
+    fn exec_test_case_evm(
+        &self,
+        gen_options: &move_to_yul::options::Options,
+        model: &GlobalEnv,
+        test_plan: &ModuleTestPlan,
+        function_name: &str,
+        test_info: &TestCase,
+        arguments: &[MoveValue],
+    ) -> std::result::Result<TestRunInfo, TestFailure> {
+        use move_binary_format::errors::Location;
 
-            let test_run_info =
-                || -> TestRunInfo { TestRunInfo::new(function_name.to_string(), duration, 0) };
-
-            // TODO: gas/timeout
-            // TODO: arguments
-            // TODO: locations
-
-            match (test_info.expected_failure.as_ref(), &res.exit_reason) {
-                // Test expected to succeed or abort with a specific abort code, but ran into an internal error.
-                (
-                    None
-                    | Some(
-                        ExpectedFailure::ExpectedWithCodeDEPRECATED(_)
-                        | ExpectedFailure::ExpectedWithError(_),
-                    ),
-                    ExitReason::Revert(_),
-                ) if abort_code() == u64::MAX => {
-                    output.fail(function_name);
-                    stats.test_failure(
-                        TestFailure::new(
-                            FailureReason::unexpected_error(MoveError(
-                                StatusCode::UNKNOWN_STATUS,
-                                None,
-                                Location::Undefined,
-                            )),
-                            test_run_info(),
-                            None,
-                            None,
-                        ),
-                        test_plan,
-                    );
-                },
+        let yul_code = match move_to_yul::generator::Generator::run_for_unit_test(
+            gen_options,
+            model,
+            &test_plan.module_id,
+            IdentStr::new(function_name).unwrap(),
+            arguments,
+        ) {
+            Ok(yul_code) => yul_code,
+            Err(diagnostics) => {
+                // Failed to generate yul code due to some user errors.
+                // Mark test as failed.
+                return Err(TestFailure::new(
+                    FailureReason::move_to_evm_error(diagnostics),
+                    TestRunInfo::new(function_name.to_string(), Duration::ZERO, 0),
+                    None,
+                    None,
+                ));
+            },
+        };
 
-                // Test expected to succeed, but aborted.
-                (None, ExitReason::Revert(_)) => {
-                    output.fail(function_name);
-                    stats.test_failure(
-                        TestFailure::new(
-                            FailureReason::unexpected_error(MoveError(
-                                StatusCode::ABORTED,
-                                Some(abort_code()),
-                                Location::Undefined,
-                            )),
-                            test_run_info(),
-                            None,
-                            None,
-                        ),
-                        test_plan,
-                    )
-                },
+        let (res, duration) = self.execute_via_evm(&yul_code);
 
-                // Expect the test to abort with a specific code.
-                (
-                    Some(
-                        ExpectedFailure::ExpectedWithError(MoveError(_, Some(exp_abort_code), _))
-                        | ExpectedFailure::ExpectedWithCodeDEPRECATED(exp_abort_code),
-                    ),
-                    ExitReason::Revert(_),
-                ) => {
-                    let abort_code = abort_code();
-                    if abort_code == *exp_abort_code {
-                        output.pass(function_name);
-                        stats.test_success(test_run_info(), test_plan);
-                    } else {
-                        output.fail(function_name);
-                        stats.test_failure(
-                            TestFailure::new(
-                                FailureReason::wrong_abort_deprecated(
-                                    *exp_abort_code,
-                                    MoveError(
-                                        StatusCode::ABORTED,
-                                        Some(abort_code),
-                                        Location::Undefined,
-                                    ),
-                                ),
-                                test_run_info(),
-                                None,
-                                None,
-                            ),
-                            test_plan,
-                        );
-                    }
-                },
+        let abort_code = || -> u64 {
+            assert!(res.return_value.len() == 8);
 
-                // Test expected to abort but succeeded.
-                (
-                    Some(
-                        ExpectedFailure::Expected
-                        | ExpectedFailure::ExpectedWithCodeDEPRECATED(_)
-                        | ExpectedFailure::ExpectedWithError(_),
-                    ),
-                    ExitReason::Succeed(_),
-                ) => {
-                    output.fail(function_name);
-                    stats.test_failure(
-                        TestFailure::new(FailureReason::no_error(), test_run_info(), None, None),
-                        test_plan,
-                    )
-                },
+            u64::from_be_bytes(res.return_value.as_slice().try_into().unwrap())
+        };
 
-                // Test succeeded or failed as expected.
-                (None, ExitReason::Succeed(_))
-                | (Some(ExpectedFailure::Expected), ExitReason::Revert(_)) => {
-                    output.pass(function_name);
-                    stats.test_success(test_run_info(), test_plan);
-                },
+        let test_run_info =
+            || -> TestRunInfo { TestRunInfo::new(function_name.to_string(), duration, 0) };
+
+        // TODO: gas/timeout
+        // TODO: locations
+
+        match (test_info.expected_failure.as_ref(), &res.exit_reason) {
+            // Test expected to succeed or abort with a specific abort code, but ran into an internal error.
+            (
+                None
+                | Some(
+                    ExpectedFailure::ExpectedWithCodeDEPRECATED(_)
+                    | ExpectedFailure::ExpectedWithError(_),
+                ),
+                ExitReason::Revert(_),
+            ) if abort_code() == u64::MAX => Err(TestFailure::new(
+                FailureReason::unexpected_error(MoveError(
+                    StatusCode::UNKNOWN_STATUS,
+                    None,
+                    Location::Undefined,
+                )),
+                test_run_info(),
+                None,
+                None,
+            )),
+
+            // Test expected to succeed, but aborted.
+            (None, ExitReason::Revert(_)) => Err(TestFailure::new(
+                FailureReason::unexpected_error(MoveError(
+                    StatusCode::ABORTED,
+                    Some(abort_code()),
+                    Location::Undefined,
+                )),
+                test_run_info(),
+                None,
+                None,
+            )),
+
+            // Expect the test to abort with a specific code.
+            (
+                Some(
+                    ExpectedFailure::ExpectedWithError(MoveError(_, Some(exp_abort_code), _))
+                    | ExpectedFailure::ExpectedWithCodeDEPRECATED(exp_abort_code),
+                ),
+                ExitReason::Revert(_),
+            ) => {
+                let abort_code = abort_code();
+                if abort_code == *exp_abort_code {
+                    Ok(test_run_info())
+                } else {
+                    Err(TestFailure::new(
+                        FailureReason::wrong_abort_deprecated(
+                            *exp_abort_code,
+                            MoveError(StatusCode::ABORTED, Some(abort_code), Location::Undefined),
+                        ),
+                        test_run_info(),
+                        None,
+                        None,
+                    ))
+                }
+            },
 
-                (exp, reason) => {
-                    unreachable!("Unexpected (exp, exit reason) pair: ({:?}, {:?}). This should not have happened.", exp, reason)
-                },
-            }
+            // Test expected to abort but succeeded.
+            (
+                Some(
+                    ExpectedFailure::Expected
+                    | ExpectedFailure::ExpectedWithCodeDEPRECATED(_)
+                    | ExpectedFailure::ExpectedWithError(_),
+                ),
+                ExitReason::Succeed(_),
+            ) => Err(TestFailure::new(
+                FailureReason::no_error(),
+                test_run_info(),
+                None,
+                None,
+            )),
+
+            // Test succeeded or failed as expected.
+            (None, ExitReason::Succeed(_))
+            | (Some(ExpectedFailure::Expected), ExitReason::Revert(_)) => Ok(test_run_info()),
+
+            (exp, reason) => {
+                unreachable!("Unexpected (exp, exit reason) pair: ({:?}, {:?}). This should not have happened.", exp, reason)
+            },
         }
-
-        stats
     }
 
     // TODO: comparison of results via different backends
@@ -97,6 +97,8 @@ fn run_test_impl(path: &Path) -> anyhow::Result<()> {
             .collect(),
         verbose: true,
         report_stacktrace_on_abort: true,
+        // make the arguments of tests with `#[test(random_args)]` deterministic
+        seed: Some(0),
 
         ..UnitTestingConfig::default_with_bound(None)
     };
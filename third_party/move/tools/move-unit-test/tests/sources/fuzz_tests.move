@@ -0,0 +1,19 @@
+module 0x1::fuzz_tests {
+    const ETOO_LARGE: u64 = 1;
+
+    #[fuzz]
+    fun passes_for_all_arguments(x: u64, v: vector<u8>) {
+        assert!(x == x && v == v, 0);
+    }
+
+    #[fuzz]
+    fun fails_above_threshold(x: u64) {
+        assert!(x < 1000, ETOO_LARGE);
+    }
+
+    #[test(s = @0x42, random_args)]
+    fun fails_above_threshold_with_assigned_argument(s: signer, x: u8) {
+        assert!(std::signer::address_of(&s) == @0x42, 0);
+        assert!(x < 100, ETOO_LARGE);
+    }
+}
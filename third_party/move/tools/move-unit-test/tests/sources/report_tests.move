@@ -0,0 +1,28 @@
+module 0x1::report_tests {
+    use std::error;
+
+    const EINVALID: u64 = 2;
+    const ENOT_FOUND: u64 = 3;
+
+    #[test]
+    fun passes() {}
+
+    #[test]
+    fun aborts_with_plain_code() {
+        abort EINVALID
+    }
+
+    #[test]
+    fun aborts_with_canonical_code() {
+        abort error::not_found(ENOT_FOUND)
+    }
+
+    #[test]
+    fun aborts_with_unknown_code() {
+        abort 42
+    }
+
+    #[test]
+    #[expected_failure]
+    fun fails_to_abort() {}
+}
@@ -0,0 +1,79 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use move_unit_test::{test_reporter::TestReportFormat, UnitTestingConfig};
+use serde_json::Value;
+use std::{fs, path::PathBuf};
+
+/// Runs the tests in `tests/sources/fuzz_tests.move` with the given seed, and returns the JSON
+/// report of the results.
+fn run_with_seed(seed: u64) -> Value {
+    let report_dir = tempfile::tempdir().unwrap();
+    let report_path = report_dir.path().join("report.json");
+    let source_path =
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/sources/fuzz_tests.move");
+    let testing_config = UnitTestingConfig {
+        num_threads: 1,
+        source_files: vec![source_path.to_string_lossy().to_string()],
+        dep_files: move_stdlib::move_stdlib_files(),
+        report_format: Some(TestReportFormat::Json),
+        report_path: Some(report_path.clone()),
+        seed: Some(seed),
+        ..UnitTestingConfig::default_with_bound(None)
+    }
+    .with_named_addresses(move_stdlib::move_stdlib_named_addresses());
+
+    let test_plan = testing_config.build_test_plan().unwrap();
+    let (_, ok) = testing_config
+        .run_and_report_unit_tests(test_plan, None, None, None, vec![])
+        .unwrap();
+    assert!(!ok);
+    serde_json::from_str(&fs::read_to_string(report_path).unwrap()).unwrap()
+}
+
+/// Returns the details of the failure of the given test in the report.
+fn failure_details<'a>(report: &'a Value, name: &str) -> &'a str {
+    let test = report["tests"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|test| test["name"] == name)
+        .unwrap();
+    assert_eq!(test["status"], "failed");
+    test["failure"]["details"].as_str().unwrap()
+}
+
+#[test]
+fn test_fuzz_passes() {
+    let report = run_with_seed(0);
+    assert_eq!(report["passed"], 1);
+    assert_eq!(report["failed"], 2);
+    let test = report["tests"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|test| test["name"] == "passes_for_all_arguments")
+        .unwrap();
+    assert_eq!(test["status"], "passed");
+}
+
+#[test]
+fn test_shrinks_to_threshold() {
+    // Whatever failing values are generated, shrinking ends at the smallest failing value.
+    for seed in [0, 1, 42] {
+        let report = run_with_seed(seed);
+
+        let details = failure_details(&report, "fails_above_threshold");
+        assert!(details.contains("with random arguments"));
+        assert!(details.contains("\n    x = 1000u64\n"));
+        assert!(details.contains(&format!(
+            "Run the tests with `--seed {}` to reproduce",
+            seed
+        )));
+
+        // Assigned arguments are kept while shrinking the random ones
+        let details = failure_details(&report, "fails_above_threshold_with_assigned_argument");
+        assert!(details.contains("\n    s = signer(0x42)\n"));
+        assert!(details.contains("\n    x = 100u8\n"));
+    }
+}
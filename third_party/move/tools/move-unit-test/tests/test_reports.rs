@@ -0,0 +1,111 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use move_unit_test::{test_reporter::TestReportFormat, UnitTestingConfig};
+use serde_json::{json, Value};
+use std::{fs, path::PathBuf};
+
+/// Runs the tests in `tests/sources/report_tests.move`, and returns the report written in the
+/// given format.
+fn run_with_report(format: TestReportFormat) -> String {
+    let report_dir = tempfile::tempdir().unwrap();
+    let report_path = report_dir.path().join("report");
+    let source_path =
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/sources/report_tests.move");
+    let testing_config = UnitTestingConfig {
+        num_threads: 1,
+        source_files: vec![source_path.to_string_lossy().to_string()],
+        dep_files: move_stdlib::move_stdlib_files(),
+        report_format: Some(format),
+        report_path: Some(report_path.clone()),
+        ..UnitTestingConfig::default_with_bound(None)
+    }
+    .with_named_addresses(move_stdlib::move_stdlib_named_addresses());
+
+    let test_plan = testing_config.build_test_plan().unwrap();
+    let (_, ok) = testing_config
+        .run_and_report_unit_tests(test_plan, None, None, None, vec![])
+        .unwrap();
+    assert!(!ok);
+    fs::read_to_string(report_path).unwrap()
+}
+
+#[test]
+fn test_json_report() {
+    let report: Value = serde_json::from_str(&run_with_report(TestReportFormat::Json)).unwrap();
+    assert_eq!(report["passed"], 1);
+    assert_eq!(report["failed"], 4);
+
+    let tests = report["tests"].as_array().unwrap();
+    let summary = tests
+        .iter()
+        .map(|test| {
+            let failure = &test["failure"];
+            json!([
+                test["module"],
+                test["name"],
+                test["status"],
+                failure["abort_code"],
+                failure["error_name"],
+            ])
+        })
+        .collect::<Vec<_>>();
+    // Tests are ordered by name. Error names are decoded from both plain and canonical codes.
+    assert_eq!(summary, vec![
+        json!([
+            "0x1::report_tests",
+            "aborts_with_canonical_code",
+            "failed",
+            0x60003,
+            "ENOT_FOUND"
+        ]),
+        json!([
+            "0x1::report_tests",
+            "aborts_with_plain_code",
+            "failed",
+            2,
+            "EINVALID"
+        ]),
+        json!([
+            "0x1::report_tests",
+            "aborts_with_unknown_code",
+            "failed",
+            42,
+            null
+        ]),
+        json!(["0x1::report_tests", "fails_to_abort", "failed", null, null]),
+        json!(["0x1::report_tests", "passes", "passed", null, null]),
+    ]);
+
+    for test in tests.iter().filter(|test| test["name"] != "passes") {
+        let failure = &test["failure"];
+        assert!(!failure["message"].as_str().unwrap().is_empty());
+        assert!(!failure["details"].as_str().unwrap().is_empty());
+    }
+    assert!(tests[1]["failure"]["location"]
+        .as_str()
+        .unwrap()
+        .contains("report_tests.move:"));
+}
+
+#[test]
+fn test_junit_report() {
+    let report = run_with_report(TestReportFormat::Junit);
+    assert!(report.starts_with(r#"<?xml version="1.0" encoding="UTF-8"?>"#));
+    assert!(report.contains(r#"<testsuites name="move-unit-tests" tests="5" failures="4""#));
+    assert!(report.contains(r#"<testsuite name="0x1::report_tests" tests="5" failures="4""#));
+    assert!(report.contains(r#"<testcase name="passes" classname="0x1::report_tests""#));
+    assert!(report.contains(r#"<property name="abort_code" value="393219"/>"#));
+    assert!(report.contains(r#"<property name="error_name" value="EINVALID"/>"#));
+    assert_eq!(
+        report
+            .matches(r#"<failure type="failure" message=""#)
+            .count(),
+        4
+    );
+    assert!(report.trim_end().ends_with("</testsuites>"));
+    // The report only contains characters allowed in XML
+    assert!(!report
+        .chars()
+        .any(|c| c.is_control() && !matches!(c, '\t' | '\n' | '\r')));
+}
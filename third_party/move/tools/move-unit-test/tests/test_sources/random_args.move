@@ -0,0 +1,20 @@
+module 0x1::random_args {
+    #[test(random_args)]
+    fun division_rounds_down(x: u64, y: u64) {
+        if (y > 0) assert!((x / y) * y <= x, 0);
+    }
+
+    #[test(_fixed = @0x42, random_args)]
+    fun mixed_arguments(_fixed: signer, _s: &signer, _a: address, _v: vector<vector<u8>>) { }
+
+    #[test(random_args)]
+    #[expected_failure(abort_code = 2, location = Self)]
+    fun always_aborts_as_expected(_x: u128) {
+        abort 2
+    }
+
+    #[test(random_args)]
+    fun shrinks_failing_arguments(_x: u64, _b: bool, _a: address, _v: vector<u8>) {
+        abort 7
+    }
+}